@@ -8,6 +8,7 @@ mod tag;
 
 pub use config::{AesGcmConfig, AesGcmConfigBuilder, AesGcmConfigBuilderError, Role};
 pub use error::AesGcmError;
+pub use tag::TAG_LEN;
 
 use async_trait::async_trait;
 use block_cipher::{Aes128, BlockCipher};
@@ -19,7 +20,7 @@ use tlsn_universal_hash::UniversalHash;
 use tracing::instrument;
 
 use crate::{
-    aes_gcm::tag::{compute_tag, verify_tag, TAG_LEN},
+    aes_gcm::tag::{compute_tag, verify_tag},
     Aead,
 };
 
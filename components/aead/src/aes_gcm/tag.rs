@@ -13,7 +13,8 @@ use tracing::instrument;
 
 use crate::aes_gcm::{AesGcmError, Role};
 
-pub(crate) const TAG_LEN: usize = 16;
+/// The length of the AES-GCM authentication tag, in bytes.
+pub const TAG_LEN: usize = 16;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TagShare([u8; TAG_LEN]);
@@ -1,6 +1,8 @@
 use mpz_circuits::{circuits::aes128_trace, once_cell::sync::Lazy, trace, Circuit, CircuitBuilder};
 use std::sync::Arc;
 
+use crate::StreamCipherError;
+
 /// AES encrypts a counter block.
 ///
 /// # Inputs
@@ -46,6 +48,46 @@ fn aes_128(key: [u8; 16], msg: [u8; 16]) -> [u8; 16] {
     ciphertext.into()
 }
 
+/// Builds a circuit computing `len` bytes of AES-128 keystream in counter mode, starting at the
+/// block with counter `start_ctr`.
+///
+/// # Inputs
+///
+///   0. KEY: 16-byte encryption key
+///   1. IV: 4-byte IV
+///   2. EXPLICIT_NONCE: 8-byte explicit nonce
+///
+/// # Outputs
+///
+///   0. KEYSTREAM: `len`-byte keystream
+///
+/// Returns an error if the counter of a block overflows.
+pub fn build_aes128_ctr_keystream(
+    len: usize,
+    start_ctr: u32,
+) -> Result<Arc<Circuit>, StreamCipherError> {
+    let builder = CircuitBuilder::new();
+    let key = builder.add_array_input::<u8, 16>();
+    let iv = builder.add_array_input::<u8, 4>();
+    let nonce = builder.add_array_input::<u8, 8>();
+
+    let block_count = (len + 15) / 16;
+    let mut keystream = Vec::with_capacity(block_count * 16);
+    for i in 0..block_count {
+        let ctr = u32::try_from(i)
+            .ok()
+            .and_then(|i| start_ctr.checked_add(i))
+            .ok_or_else(|| StreamCipherError::counter_overflow(start_ctr, block_count))?
+            .to_be_bytes()
+            .map(|b| builder.get_constant(b));
+        keystream.extend(aes_ctr_trace(builder.state(), key, iv, nonce, ctr));
+    }
+    keystream.truncate(len);
+    builder.add_output(keystream);
+
+    Ok(Arc::new(builder.build().expect("circuit is valid")))
+}
+
 /// Builds a circuit for computing the XOR of two arrays.
 pub(crate) fn build_array_xor(len: usize) -> Arc<Circuit> {
     let builder = CircuitBuilder::new();
@@ -55,3 +97,31 @@ pub(crate) fn build_array_xor(len: usize) -> Arc<Circuit> {
     builder.add_output(c);
     Arc::new(builder.build().expect("circuit is valid"))
 }
+
+#[cfg(test)]
+mod tests {
+    use mpz_circuits::evaluate;
+
+    use super::*;
+    use crate::{Aes128Ctr, CtrCircuit};
+
+    #[test]
+    fn test_aes128_ctr_keystream() {
+        let circ = build_aes128_ctr_keystream(100, 2).unwrap();
+
+        let key = [0u8; 16];
+        let iv = [1u8; 4];
+        let nonce = [2u8; 8];
+
+        let actual = evaluate!(circ, fn(key, iv, nonce) -> Vec<u8>).unwrap();
+        let expected = Aes128Ctr::apply_keystream(&key, &iv, 2, &nonce, &[0u8; 100]).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_aes128_ctr_keystream_counter_overflow() {
+        assert!(build_aes128_ctr_keystream(16, u32::MAX).is_ok());
+        assert!(build_aes128_ctr_keystream(17, u32::MAX).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use derive_builder::Builder;
 use std::fmt::Debug;
 
+use crate::CircuitCounter;
+
 /// Configuration for a stream cipher.
 #[derive(Debug, Clone, Builder)]
 pub struct StreamCipherConfig {
@@ -14,6 +16,16 @@ pub struct StreamCipherConfig {
     /// for the plaintext bytes during encryption and decryption.
     #[builder(setter(into), default = "\"transcript\".to_string()")]
     pub(crate) transcript_id: String,
+    /// The maximum number of bytes which are processed in a single circuit execution.
+    ///
+    /// Longer inputs are split across multiple executions, which bounds the number of labels
+    /// which are held in memory at once. By default the whole input is processed in a
+    /// single execution.
+    #[builder(setter(strip_option), default)]
+    pub(crate) max_execution_len: Option<usize>,
+    /// Counter of the circuits executed by the cipher, which may be shared with other ciphers.
+    #[builder(default)]
+    pub(crate) circuit_counter: CircuitCounter,
 }
 
 impl StreamCipherConfig {
@@ -0,0 +1,53 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use mpz_circuits::Circuit;
+
+/// Counts the circuits executed by one or more stream ciphers.
+///
+/// The counter is cheap to clone, and all clones share the same counts, so a single counter can
+/// be shared by the ciphers of both directions of a connection.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitCounter(Arc<Counts>);
+
+#[derive(Debug, Default)]
+struct Counts {
+    circuits: AtomicU64,
+    and_gates: AtomicU64,
+    xor_gates: AtomicU64,
+}
+
+impl CircuitCounter {
+    /// Creates a new counter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts an execution of a circuit.
+    pub fn add(&self, circuit: &Circuit) {
+        self.0.circuits.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .and_gates
+            .fetch_add(circuit.and_count() as u64, Ordering::Relaxed);
+        self.0
+            .xor_gates
+            .fetch_add(circuit.xor_count() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the number of circuits executed.
+    pub fn circuits(&self) -> u64 {
+        self.0.circuits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of AND gates of the circuits executed.
+    pub fn and_gates(&self) -> u64 {
+        self.0.and_gates.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of XOR gates of the circuits executed.
+    pub fn xor_gates(&self) -> u64 {
+        self.0.xor_gates.load(Ordering::Relaxed)
+    }
+}
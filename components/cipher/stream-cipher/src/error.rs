@@ -52,6 +52,15 @@ impl StreamCipherError {
         }
     }
 
+    pub(crate) fn counter_overflow(start_ctr: u32, block_count: usize) -> Self {
+        Self {
+            kind: ErrorKind::Counter,
+            source: Some(
+                format!("counter overflows: {block_count} blocks starting at {start_ctr}").into(),
+            ),
+        }
+    }
+
     pub(crate) fn key_not_set() -> Self {
         Self {
             kind: ErrorKind::Key,
@@ -66,6 +75,7 @@ pub(crate) enum ErrorKind {
     Key,
     Iv,
     ExplicitNonce,
+    Counter,
 }
 
 impl fmt::Display for StreamCipherError {
@@ -75,6 +85,7 @@ impl fmt::Display for StreamCipherError {
             ErrorKind::Key => write!(f, "key error")?,
             ErrorKind::Iv => write!(f, "iv error")?,
             ErrorKind::ExplicitNonce => write!(f, "explicit nonce error")?,
+            ErrorKind::Counter => write!(f, "counter error")?,
         }
 
         if let Some(ref source) = self.source {
@@ -4,7 +4,7 @@ use mpz_garble::{value::ValueRef, Execute, Load, Memory, Prove, Thread, Verify};
 use tracing::instrument;
 use utils::id::NestedId;
 
-use crate::{config::ExecutionMode, CtrCircuit, StreamCipherError};
+use crate::{config::ExecutionMode, CircuitCounter, CtrCircuit, StreamCipherError};
 
 pub(crate) struct KeyStream<C> {
     block_counter: NestedId,
@@ -131,6 +131,7 @@ impl<C: CtrCircuit> KeyStream<C> {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[instrument(level = "debug", skip_all, err)]
     pub(crate) async fn compute<T>(
         &mut self,
@@ -141,6 +142,8 @@ impl<C: CtrCircuit> KeyStream<C> {
         explicit_nonce: Vec<u8>,
         start_ctr: usize,
         len: usize,
+        max_execution_len: Option<usize>,
+        circuit_counter: &CircuitCounter,
     ) -> Result<ValueRef, StreamCipherError>
     where
         T: Thread + Memory + Execute + Prove + Verify + Send + 'static,
@@ -182,27 +185,40 @@ impl<C: CtrCircuit> KeyStream<C> {
             ));
         }
 
-        match mode {
-            ExecutionMode::Mpc => {
-                thread.commit(&inputs).await?;
-                for (circ, inputs, outputs) in calls {
-                    thread.execute(circ, &inputs, &outputs).await?;
+        // Commit to and execute the blocks in batches, so that only the labels of a single
+        // batch have to be held in memory at once.
+        let batch_blocks = max_execution_len
+            .map(|max_len| (max_len / C::BLOCK_LEN).max(1))
+            .unwrap_or(calls.len().max(1));
+
+        let mut calls = calls.into_iter();
+        for batch in inputs.chunks(batch_blocks * 4) {
+            let batch_calls = calls.by_ref().take(batch.len() / 4);
+            match mode {
+                ExecutionMode::Mpc => {
+                    thread.commit(batch).await?;
+                    for (circ, inputs, outputs) in batch_calls {
+                        circuit_counter.add(&circ);
+                        thread.execute(circ, &inputs, &outputs).await?;
+                    }
                 }
-            }
-            ExecutionMode::Prove => {
-                // Note that after the circuit execution, the value of `block` can be considered as
-                // implicitly authenticated since `key` and `iv` have already been authenticated earlier
-                // and `nonce_ref` and `ctr_ref` are public.
-                // [Prove::prove] will **not** be called on `block` at any later point.
-                thread.commit_prove(&inputs).await?;
-                for (circ, inputs, outputs) in calls {
-                    thread.execute_prove(circ, &inputs, &outputs).await?;
+                ExecutionMode::Prove => {
+                    // Note that after the circuit execution, the value of `block` can be considered as
+                    // implicitly authenticated since `key` and `iv` have already been authenticated earlier
+                    // and `nonce_ref` and `ctr_ref` are public.
+                    // [Prove::prove] will **not** be called on `block` at any later point.
+                    thread.commit_prove(batch).await?;
+                    for (circ, inputs, outputs) in batch_calls {
+                        circuit_counter.add(&circ);
+                        thread.execute_prove(circ, &inputs, &outputs).await?;
+                    }
                 }
-            }
-            ExecutionMode::Verify => {
-                thread.commit_verify(&inputs).await?;
-                for (circ, inputs, outputs) in calls {
-                    thread.execute_verify(circ, &inputs, &outputs).await?;
+                ExecutionMode::Verify => {
+                    thread.commit_verify(batch).await?;
+                    for (circ, inputs, outputs) in batch_calls {
+                        circuit_counter.add(&circ);
+                        thread.execute_verify(circ, &inputs, &outputs).await?;
+                    }
                 }
             }
         }
@@ -19,12 +19,15 @@
 mod cipher;
 mod circuit;
 mod config;
+mod counter;
 pub(crate) mod error;
 pub(crate) mod keystream;
 mod stream_cipher;
 
 pub use self::cipher::{Aes128Ctr, CtrCircuit};
+pub use circuit::build_aes128_ctr_keystream;
 pub use config::{StreamCipherConfig, StreamCipherConfigBuilder, StreamCipherConfigBuilderError};
+pub use counter::CircuitCounter;
 pub use error::StreamCipherError;
 pub use stream_cipher::MpcStreamCipher;
 
@@ -205,6 +208,19 @@ mod tests {
     ) -> (
         MpcStreamCipher<C, MockLeader>,
         MpcStreamCipher<C, MockFollower>,
+    ) {
+        create_test_pair_with_config(start_ctr, key, iv, None, CircuitCounter::new()).await
+    }
+
+    async fn create_test_pair_with_config<C: CtrCircuit>(
+        start_ctr: usize,
+        key: [u8; 16],
+        iv: [u8; 4],
+        max_execution_len: Option<usize>,
+        leader_circuit_counter: CircuitCounter,
+    ) -> (
+        MpcStreamCipher<C, MockLeader>,
+        MpcStreamCipher<C, MockFollower>,
     ) {
         let (leader_vm, follower_vm) = create_mock_deap_vm();
 
@@ -220,17 +236,22 @@ mod tests {
         follower_vm.assign(&follower_key, key).unwrap();
         follower_vm.assign(&follower_iv, iv).unwrap();
 
-        let leader_config = StreamCipherConfig::builder()
-            .id("test")
-            .start_ctr(start_ctr)
-            .build()
-            .unwrap();
-
-        let follower_config = StreamCipherConfig::builder()
+        let mut leader_config = StreamCipherConfig::builder();
+        leader_config
             .id("test")
             .start_ctr(start_ctr)
-            .build()
-            .unwrap();
+            .circuit_counter(leader_circuit_counter);
+        if let Some(max_execution_len) = max_execution_len {
+            leader_config.max_execution_len(max_execution_len);
+        }
+        let leader_config = leader_config.build().unwrap();
+
+        let mut follower_config = StreamCipherConfig::builder();
+        follower_config.id("test").start_ctr(start_ctr);
+        if let Some(max_execution_len) = max_execution_len {
+            follower_config.max_execution_len(max_execution_len);
+        }
+        let follower_config = follower_config.build().unwrap();
 
         let mut leader = MpcStreamCipher::<C, _>::new(leader_config, leader_vm);
         leader.set_key(leader_key, leader_iv);
@@ -459,4 +480,84 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[rstest]
+    #[case::one_byte(1)]
+    #[case::one_block(16)]
+    #[case::partial(17)]
+    #[timeout(Duration::from_millis(10000))]
+    #[tokio::test]
+    async fn test_stream_cipher_max_execution_len(#[case] max_execution_len: usize) {
+        let key = [0u8; 16];
+        let iv = [0u8; 4];
+        let explicit_nonce = [1u8; 8];
+
+        let msg = b"This is a test message which will be encrypted using AES-CTR.".to_vec();
+
+        let ciphertext = Aes128Ctr::apply_keystream(&key, &iv, 1, &explicit_nonce, &msg).unwrap();
+
+        let (mut leader, mut follower) = create_test_pair_with_config::<Aes128Ctr>(
+            1,
+            key,
+            iv,
+            Some(max_execution_len),
+            CircuitCounter::new(),
+        )
+        .await;
+
+        let (leader_decrypted_msg, _) = futures::try_join!(
+            leader.decrypt_private(explicit_nonce.to_vec(), ciphertext.clone()),
+            follower.decrypt_blind(explicit_nonce.to_vec(), ciphertext.clone())
+        )
+        .unwrap();
+
+        assert_eq!(leader_decrypted_msg, msg);
+
+        futures::try_join!(
+            leader.thread_mut().finalize(),
+            follower.thread_mut().finalize()
+        )
+        .unwrap();
+    }
+
+    #[rstest]
+    #[case::single_execution(None, 5)]
+    #[case::split_executions(Some(16), 8)]
+    #[timeout(Duration::from_millis(10000))]
+    #[tokio::test]
+    async fn test_stream_cipher_counts_circuits(
+        #[case] max_execution_len: Option<usize>,
+        #[case] expected_circuits: u64,
+    ) {
+        let key = [0u8; 16];
+        let iv = [0u8; 4];
+        let explicit_nonce = [0u8; 8];
+
+        // 4 keystream blocks.
+        let msg = b"This is a test message which will be encrypted using AES-CTR.".to_vec();
+
+        let counter = CircuitCounter::new();
+        let (mut leader, mut follower) = create_test_pair_with_config::<Aes128Ctr>(
+            1,
+            key,
+            iv,
+            max_execution_len,
+            counter.clone(),
+        )
+        .await;
+
+        futures::try_join!(
+            leader.encrypt_public(explicit_nonce.to_vec(), msg.clone()),
+            follower.encrypt_public(explicit_nonce.to_vec(), msg.clone())
+        )
+        .unwrap();
+
+        // The keystream blocks, and the executions XORing the message with the keystream.
+        assert_eq!(counter.circuits(), expected_circuits);
+        assert_eq!(
+            counter.and_gates(),
+            4 * Aes128Ctr::circuit().and_count() as u64
+        );
+        assert!(counter.xor_gates() > 4 * Aes128Ctr::circuit().xor_count() as u64);
+    }
 }
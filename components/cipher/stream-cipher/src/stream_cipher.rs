@@ -135,6 +135,8 @@ where
                 explicit_nonce,
                 start_ctr,
                 len,
+                self.config.max_execution_len,
+                &self.config.circuit_counter,
             )
             .await?;
 
@@ -156,47 +158,87 @@ where
         );
 
         let input_text = match input_text {
-            InputText::Public { ids, text } => {
-                let refs = text
-                    .into_iter()
-                    .zip(ids)
-                    .map(|(byte, id)| {
-                        let value_ref = self.thread.new_public_input::<u8>(&id)?;
-                        self.thread.assign(&value_ref, byte)?;
-
-                        Ok::<_, StreamCipherError>(value_ref)
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-                self.thread.array_from_values(&refs)?
-            }
-            InputText::Private { ids, text } => {
-                let refs = text
-                    .into_iter()
-                    .zip(ids)
-                    .map(|(byte, id)| {
-                        let value_ref = self.thread.new_private_input::<u8>(&id)?;
-                        self.thread.assign(&value_ref, byte)?;
-
-                        Ok::<_, StreamCipherError>(value_ref)
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-                self.thread.array_from_values(&refs)?
-            }
-            InputText::Blind { ids } => {
-                let refs = ids
-                    .into_iter()
-                    .map(|id| self.thread.new_blind_input::<u8>(&id))
-                    .collect::<Result<Vec<_>, _>>()?;
-                self.thread.array_from_values(&refs)?
+            InputText::Public { ids, text } => text
+                .into_iter()
+                .zip(ids)
+                .map(|(byte, id)| {
+                    let value_ref = self.thread.new_public_input::<u8>(&id)?;
+                    self.thread.assign(&value_ref, byte)?;
+
+                    Ok::<_, StreamCipherError>(value_ref)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            InputText::Private { ids, text } => text
+                .into_iter()
+                .zip(ids)
+                .map(|(byte, id)| {
+                    let value_ref = self.thread.new_private_input::<u8>(&id)?;
+                    self.thread.assign(&value_ref, byte)?;
+
+                    Ok::<_, StreamCipherError>(value_ref)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            InputText::Blind { ids } => ids
+                .into_iter()
+                .map(|id| self.thread.new_blind_input::<u8>(&id))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        let output_id = format!("{}/out/{}", self.config.id, self.state.counter);
+
+        let batch_len = match self.config.max_execution_len {
+            Some(max_len) if input_text.len() > max_len => max_len.max(1),
+            _ => {
+                let input_text = self.thread.array_from_values(&input_text)?;
+                return self
+                    .execute_xor(mode, input_text, keystream, &output_id)
+                    .await;
             }
         };
 
-        let output_text = self.thread.new_array_output::<u8>(
-            &format!("{}/out/{}", self.config.id, self.state.counter),
-            input_text.len(),
-        )?;
+        // Split the input into multiple executions to bound the number of labels held in
+        // memory at once.
+        let keystream = keystream
+            .iter()
+            .cloned()
+            .map(|id| ValueRef::Value { id })
+            .collect::<Vec<_>>();
+
+        let mut output_text = Vec::with_capacity(input_text.len());
+        for (i, (input_text, keystream)) in input_text
+            .chunks(batch_len)
+            .zip(keystream.chunks(batch_len))
+            .enumerate()
+        {
+            let input_text = self.thread.array_from_values(input_text)?;
+            let keystream = self.thread.array_from_values(keystream)?;
+
+            let output = self
+                .execute_xor(mode, input_text, keystream, &format!("{output_id}/{i}"))
+                .await?;
+
+            output_text.extend(output.iter().cloned().map(|id| ValueRef::Value { id }));
+        }
+
+        self.thread
+            .array_from_values(&output_text)
+            .map_err(StreamCipherError::from)
+    }
+
+    /// Executes a circuit which XORs the input text with the keystream.
+    async fn execute_xor(
+        &mut self,
+        mode: ExecutionMode,
+        input_text: ValueRef,
+        keystream: ValueRef,
+        output_id: &str,
+    ) -> Result<ValueRef, StreamCipherError> {
+        let output_text = self
+            .thread
+            .new_array_output::<u8>(output_id, input_text.len())?;
 
         let circ = build_array_xor(input_text.len());
+        self.config.circuit_counter.add(&circ);
 
         match mode {
             ExecutionMode::Mpc => {
@@ -656,6 +698,8 @@ where
                 explicit_nonce,
                 ctr,
                 C::BLOCK_LEN,
+                None,
+                &self.config.circuit_counter,
             )
             .await?;
 
@@ -14,6 +14,23 @@ pub enum Role {
 pub struct KeyExchangeConfig {
     /// Protocol role.
     role: Role,
+    /// Seed of the RNG which generates the private key share, only settable with the
+    /// `seeded-rng` feature.
+    ///
+    /// The private key share is generated from the OS RNG if not set.
+    #[builder(setter(custom), default)]
+    rng_seed: Option<[u8; 32]>,
+}
+
+impl KeyExchangeConfigBuilder {
+    /// Seeds the RNG which generates the private key share, so that runs are reproducible.
+    ///
+    /// This is insecure and must only be used in tests.
+    #[cfg(feature = "seeded-rng")]
+    pub fn rng_seed(&mut self, seed: [u8; 32]) -> &mut Self {
+        self.rng_seed = Some(Some(seed));
+        self
+    }
 }
 
 impl KeyExchangeConfig {
@@ -26,4 +43,9 @@ impl KeyExchangeConfig {
     pub fn role(&self) -> &Role {
         &self.role
     }
+
+    /// Get the seed of the RNG which generates the private key share, if any.
+    pub fn rng_seed(&self) -> Option<&[u8; 32]> {
+        self.rng_seed.as_ref()
+    }
 }
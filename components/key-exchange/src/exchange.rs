@@ -6,16 +6,19 @@ use mpz_garble::{value::ValueRef, Decode, Execute, Load, Memory};
 
 use mpz_fields::{p256::P256, Field};
 use mpz_share_conversion::{ShareConversionError, ShareConvert};
-use p256::{EncodedPoint, PublicKey, SecretKey};
+use p256::{elliptic_curve::sec1::ToEncodedPoint, PublicKey, SecretKey};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use serio::{stream::IoStreamExt, SinkExt};
 use std::fmt::Debug;
 use tracing::{debug, instrument};
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
-    circuit::build_pms_circuit,
+    circuit::{build_pms_circuit, P256_PRIME},
     config::{KeyExchangeConfig, Role},
     error::ErrorKind,
-    point_addition::derive_x_coord_share,
+    point_addition::{decompose_point, derive_x_coord_share},
     KeyExchange, KeyExchangeError, Pms,
 };
 
@@ -57,6 +60,11 @@ impl State {
 /// An MPC key exchange protocol.
 ///
 /// Can be either a leader or a follower depending on the `role` field in [`KeyExchangeConfig`].
+///
+/// The point addition is generic over the share conversion protocol: any implementation of
+/// [`ShareConvert`] over the P-256 field can be used for `C0` and `C1`, e.g. the OT-based
+/// converters of `mpz-share-conversion`, so deployments can choose their tradeoff between
+/// bandwidth and computation.
 #[derive(Debug)]
 pub struct MpcKeyExchange<Ctx, C0, C1, E> {
     ctx: Ctx,
@@ -149,29 +157,24 @@ where
             return Err(KeyExchangeError::state("not in preprocessed state"));
         };
 
-        let share_0_bytes: [u8; 32] = share_0
-            .to_be_bytes()
-            .try_into()
-            .expect("pms share is 32 bytes");
-        let share_1_bytes: [u8; 32] = share_1
-            .to_be_bytes()
-            .try_into()
-            .expect("pms share is 32 bytes");
+        // The shares are copied into the executor, the local copies are zeroized on drop.
+        let share_0_bytes = pms_share_bytes(&share_0);
+        let share_1_bytes = pms_share_bytes(&share_1);
 
         match self.config.role() {
             Role::Leader => {
-                self.executor.assign(&share_a0, share_0_bytes)?;
-                self.executor.assign(&share_a1, share_1_bytes)?;
+                self.executor.assign(&share_a0, *share_0_bytes)?;
+                self.executor.assign(&share_a1, *share_1_bytes)?;
             }
             Role::Follower => {
-                self.executor.assign(&share_b0, share_0_bytes)?;
-                self.executor.assign(&share_b1, share_1_bytes)?;
+                self.executor.assign(&share_b0, *share_0_bytes)?;
+                self.executor.assign(&share_b1, *share_1_bytes)?;
             }
         }
 
         self.executor
             .execute(
-                build_pms_circuit(),
+                build_pms_circuit(P256_PRIME),
                 &[share_a0, share_b0, share_a1, share_b1],
                 &[pms_0.clone(), pms_1, eq.clone()],
             )
@@ -308,7 +311,7 @@ where
             async {
                 self.executor
                     .load(
-                        build_pms_circuit(),
+                        build_pms_circuit(P256_PRIME),
                         &[
                             share_a0.clone(),
                             share_b0.clone(),
@@ -325,9 +328,10 @@ where
 
         // Follower can forward their key share immediately.
         if let Role::Follower = self.config.role() {
+            let rng_seed = self.config.rng_seed();
             let private_key = self
                 .private_key
-                .get_or_insert_with(|| SecretKey::random(&mut rand::rngs::OsRng));
+                .get_or_insert_with(|| random_private_key(rng_seed));
 
             self.ctx.io_mut().send(private_key.public_key()).await?;
 
@@ -350,9 +354,10 @@ where
     #[instrument(level = "debug", skip_all, err)]
     async fn client_key(&mut self) -> Result<PublicKey, KeyExchangeError> {
         if let Role::Leader = self.config.role() {
+            let rng_seed = self.config.rng_seed();
             let private_key = self
                 .private_key
-                .get_or_insert_with(|| SecretKey::random(&mut rand::rngs::OsRng));
+                .get_or_insert_with(|| random_private_key(rng_seed));
             let public_key = private_key.public_key();
 
             // Receive public key share from follower.
@@ -405,6 +410,21 @@ where
     }
 }
 
+/// Returns the big-endian bytes of a PMS share, which are zeroized on drop.
+fn pms_share_bytes(share: &P256) -> Zeroizing<[u8; 32]> {
+    let bytes = Zeroizing::new(share.to_be_bytes());
+
+    Zeroizing::new(bytes[..].try_into().expect("pms share is 32 bytes"))
+}
+
+/// Generates a private key share, from the seeded RNG if a seed is provided.
+fn random_private_key(rng_seed: Option<&[u8; 32]>) -> SecretKey {
+    match rng_seed {
+        Some(seed) => SecretKey::random(&mut ChaCha20Rng::from_seed(*seed)),
+        None => SecretKey::random(&mut rand::rngs::OsRng),
+    }
+}
+
 async fn compute_pms_shares<
     Ctx: Context,
     C0: ShareConvert<Ctx, P256> + Send,
@@ -422,22 +442,22 @@ async fn compute_pms_shares<
     // We need to mimic the [diffie-hellman](p256::ecdh::diffie_hellman) function without the
     // [SharedSecret](p256::ecdh::SharedSecret) wrapper, because this makes it harder to get
     // the result as an EC curve point.
-    let shared_secret = {
+    let mut shared_secret = {
         let public_projective = server_key.to_projective();
         (public_projective * private_key.to_nonzero_scalar().as_ref()).to_affine()
     };
+    let point = shared_secret.to_encoded_point(false);
+    shared_secret.zeroize();
 
-    let encoded_point = EncodedPoint::from(PublicKey::from_affine(shared_secret)?);
+    let coords = decompose_point(point)?;
 
     let (pms_share_0, pms_share_1) = ctx
         .try_join(
             |ctx| {
-                async { derive_x_coord_share(role, ctx, converter_0, encoded_point).await }
-                    .scope_boxed()
+                async { derive_x_coord_share(role, ctx, converter_0, coords).await }.scope_boxed()
             },
             |ctx| {
-                async { derive_x_coord_share(role, ctx, converter_1, encoded_point).await }
-                    .scope_boxed()
+                async { derive_x_coord_share(role, ctx, converter_1, coords).await }.scope_boxed()
             },
         )
         .await??;
@@ -470,6 +490,35 @@ mod tests {
             IdealShareConverter,
             MockFollower,
         >,
+    ) {
+        create_pair_with_configs(
+            KeyExchangeConfig::builder()
+                .role(Role::Leader)
+                .build()
+                .unwrap(),
+            KeyExchangeConfig::builder()
+                .role(Role::Follower)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn create_pair_with_configs(
+        leader_config: KeyExchangeConfig,
+        follower_config: KeyExchangeConfig,
+    ) -> (
+        MpcKeyExchange<
+            STExecutor<MemoryDuplex>,
+            IdealShareConverter,
+            IdealShareConverter,
+            MockLeader,
+        >,
+        MpcKeyExchange<
+            STExecutor<MemoryDuplex>,
+            IdealShareConverter,
+            IdealShareConverter,
+            MockFollower,
+        >,
     ) {
         let (leader_ctx, follower_ctx) = test_st_executor(8);
         let (leader_converter_0, follower_converter_0) = ideal_share_converter();
@@ -477,10 +526,7 @@ mod tests {
         let (leader_vm, follower_vm) = create_mock_deap_vm();
 
         let leader = MpcKeyExchange::new(
-            KeyExchangeConfig::builder()
-                .role(Role::Leader)
-                .build()
-                .unwrap(),
+            leader_config,
             leader_ctx,
             leader_converter_0,
             leader_converter_1,
@@ -488,10 +534,7 @@ mod tests {
         );
 
         let follower = MpcKeyExchange::new(
-            KeyExchangeConfig::builder()
-                .role(Role::Follower)
-                .build()
-                .unwrap(),
+            follower_config,
             follower_ctx,
             follower_converter_0,
             follower_converter_1,
@@ -530,6 +573,36 @@ mod tests {
         assert_eq!(client_public_key, expected_client_public_key);
     }
 
+    #[cfg(feature = "seeded-rng")]
+    #[tokio::test]
+    async fn test_seeded_key_exchange_is_reproducible() {
+        async fn client_key(leader_seed: [u8; 32], follower_seed: [u8; 32]) -> PublicKey {
+            let (mut leader, mut follower) = create_pair_with_configs(
+                KeyExchangeConfig::builder()
+                    .role(Role::Leader)
+                    .rng_seed(leader_seed)
+                    .build()
+                    .unwrap(),
+                KeyExchangeConfig::builder()
+                    .role(Role::Follower)
+                    .rng_seed(follower_seed)
+                    .build()
+                    .unwrap(),
+            );
+
+            tokio::try_join!(leader.setup(), follower.setup()).unwrap();
+            tokio::try_join!(leader.preprocess(), follower.preprocess()).unwrap();
+
+            leader.client_key().await.unwrap()
+        }
+
+        let client_public_key = client_key([1u8; 32], [2u8; 32]).await;
+
+        assert_eq!(client_key([1u8; 32], [2u8; 32]).await, client_public_key);
+        assert_ne!(client_key([3u8; 32], [2u8; 32]).await, client_public_key);
+        assert_ne!(client_key([1u8; 32], [3u8; 32]).await, client_public_key);
+    }
+
     #[tokio::test]
     async fn test_compute_pms() {
         let mut rng = ChaCha12Rng::from_seed([0_u8; 32]);
@@ -8,6 +8,10 @@
 //!
 //! A detailed description of this protocol can be found in our documentation
 //! <https://docs.tlsnotary.org/protocol/notarization/key_exchange.html>.
+//!
+//! The share conversion and the circuit combining the PMS shares are generic over the field of
+//! the curve. Only NIST P-256 is currently supported, as other curves such as P-384 require a
+//! field implementation for share conversion.
 
 #![deny(missing_docs, unreachable_pub, unused_must_use)]
 #![deny(clippy::all)]
@@ -3,27 +3,41 @@
 //! underlying the elliptic curve).
 //! This protocol has semi-honest security.
 //!
+//! The protocol only uses the field arithmetic of the curve, so it is generic over the field. Only
+//! decomposing a point into its coordinates is specific to a curve.
+//!
 //! The protocol is described in <https://docs.tlsnotary.org/protocol/notarization/key_exchange.html>
 
+use std::ops::Neg;
+
 use mpz_common::Context;
 use mpz_fields::{p256::P256, Field};
 use mpz_share_conversion::{AdditiveToMultiplicative, MultiplicativeToAdditive};
 use p256::EncodedPoint;
+use zeroize::Zeroize;
 
 use crate::{config::Role, error::ErrorKind, KeyExchangeError};
 
-/// Derives the x-coordinate share of an elliptic curve point.
-pub(crate) async fn derive_x_coord_share<Ctx, C>(
+/// Derives the x-coordinate share of the sum of the elliptic curve points of both parties.
+///
+/// # Arguments
+///
+/// * `role` - The role of this party.
+/// * `ctx` - The thread context.
+/// * `converter` - The share conversion protocol instance.
+/// * `share` - The x and y coordinates of the point of this party.
+pub(crate) async fn derive_x_coord_share<Ctx, C, F>(
     role: Role,
     ctx: &mut Ctx,
     converter: &mut C,
-    share: EncodedPoint,
-) -> Result<P256, KeyExchangeError>
+    share: [F; 2],
+) -> Result<F, KeyExchangeError>
 where
     Ctx: Context,
-    C: AdditiveToMultiplicative<Ctx, P256> + MultiplicativeToAdditive<Ctx, P256>,
+    F: Field + Copy + Neg<Output = F>,
+    C: AdditiveToMultiplicative<Ctx, F> + MultiplicativeToAdditive<Ctx, F>,
 {
-    let [x, y] = decompose_point(share)?;
+    let [x, y] = share;
 
     // Follower negates their share coordinates.
     let inputs = match role {
@@ -31,11 +45,10 @@ where
         Role::Follower => vec![-y, -x],
     };
 
-    let [a, b] = converter
-        .to_multiplicative(ctx, inputs)
-        .await?
-        .try_into()
-        .expect("output is same length as input");
+    let outputs = converter.to_multiplicative(ctx, inputs).await?;
+    let [a, b] = outputs[..] else {
+        unreachable!("output is same length as input");
+    };
 
     let c = a * b.inverse();
     let c = c * c;
@@ -47,8 +60,10 @@ where
     Ok(x_r)
 }
 
-/// Decomposes the x and y coordinates of a SEC1 encoded point.
-fn decompose_point(point: EncodedPoint) -> Result<[P256; 2], KeyExchangeError> {
+/// Decomposes the x and y coordinates of a SEC1 encoded P-256 point.
+///
+/// The point is a share of the shared secret, so it is zeroized along with the coordinate bytes.
+pub(crate) fn decompose_point(mut point: EncodedPoint) -> Result<[P256; 2], KeyExchangeError> {
     // Coordinates are stored as big-endian bytes.
     let mut x: [u8; 32] = (*point.x().ok_or(KeyExchangeError::new(
         ErrorKind::Key,
@@ -61,14 +76,17 @@ fn decompose_point(point: EncodedPoint) -> Result<[P256; 2], KeyExchangeError> {
     ))?)
     .into();
 
+    point.zeroize();
+
     // Reverse to little endian.
     x.reverse();
     y.reverse();
 
-    let x = P256::try_from(x).unwrap();
-    let y = P256::try_from(y).unwrap();
+    let coords = [P256::try_from(x).unwrap(), P256::try_from(y).unwrap()];
+    x.zeroize();
+    y.zeroize();
 
-    Ok([x, y])
+    Ok(coords)
 }
 
 #[cfg(test)]
@@ -101,8 +119,18 @@ mod tests {
         let (mut c_a, mut c_b) = ideal_share_converter();
 
         let (a, b) = tokio::try_join!(
-            derive_x_coord_share(Role::Leader, &mut ctx_a, &mut c_a, p1),
-            derive_x_coord_share(Role::Follower, &mut ctx_b, &mut c_b, p2)
+            derive_x_coord_share(
+                Role::Leader,
+                &mut ctx_a,
+                &mut c_a,
+                decompose_point(p1).unwrap()
+            ),
+            derive_x_coord_share(
+                Role::Follower,
+                &mut ctx_b,
+                &mut c_b,
+                decompose_point(p2).unwrap()
+            )
         )
         .unwrap();
 
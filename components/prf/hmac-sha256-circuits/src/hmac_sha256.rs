@@ -114,9 +114,38 @@ pub fn hmac_sha256_finalize(outer_state: [u32; 8], inner_state: [u32; 8], msg: &
     sha256(outer_state, 64, &sha256(inner_state, 64, msg))
 }
 
+/// Computes the HMAC-SHA256 digest of a message of arbitrary length.
+///
+/// # Arguments
+///
+/// * `builder_state`   - Reference to builder state.
+/// * `key`             - N-byte key (must be <= 64 bytes).
+/// * `msg`             - N-byte message.
+pub fn hmac_sha256_trace<'a>(
+    builder_state: &'a RefCell<BuilderState>,
+    key: &[Tracer<'a, U8>],
+    msg: &[Tracer<'a, U8>],
+) -> [Tracer<'a, U8>; 32] {
+    let (outer_state, inner_state) = hmac_sha256_partial_trace(builder_state, key);
+    hmac_sha256_finalize_trace(builder_state, outer_state, inner_state, msg)
+}
+
+/// Reference implementation of HMAC-SHA256.
+///
+/// Returns the HMAC-SHA256 digest of a message of arbitrary length.
+///
+/// # Arguments
+///
+/// * `key` - N-byte key (must be <= 64 bytes).
+/// * `msg` - N-byte message.
+pub fn hmac_sha256(key: &[u8], msg: &[u8]) -> [u8; 32] {
+    let (outer_state, inner_state) = hmac_sha256_partial(key);
+    hmac_sha256_finalize(outer_state, inner_state, msg)
+}
+
 #[cfg(test)]
 mod tests {
-    use mpz_circuits::{test_circ, CircuitBuilder};
+    use mpz_circuits::{evaluate, test_circ, CircuitBuilder};
 
     use super::*;
 
@@ -154,4 +183,18 @@ mod tests {
             fn(outer_state, inner_state, &msg) -> [u8; 32]
         );
     }
+
+    #[test]
+    fn test_hmac_sha256_multi_block() {
+        let circ = crate::build_hmac_sha256(32, 200);
+
+        let key = [69u8; 32];
+        let msg = [42u8; 200];
+
+        let actual = evaluate!(circ, fn(&key, &msg) -> [u8; 32]).unwrap();
+        let expected = ring::hmac::sign(&ring::hmac::Key::new(ring::hmac::HMAC_SHA256, &key), &msg);
+
+        assert_eq!(actual, hmac_sha256(&key, &msg));
+        assert_eq!(&actual, expected.as_ref());
+    }
 }
@@ -10,8 +10,8 @@ mod session_keys;
 mod verify_data;
 
 pub use hmac_sha256::{
-    hmac_sha256_finalize, hmac_sha256_finalize_trace, hmac_sha256_partial,
-    hmac_sha256_partial_trace,
+    hmac_sha256, hmac_sha256_finalize, hmac_sha256_finalize_trace, hmac_sha256_partial,
+    hmac_sha256_partial_trace, hmac_sha256_trace,
 };
 
 pub use prf::{prf, prf_trace};
@@ -39,6 +39,30 @@ pub fn build_session_keys() -> Arc<Circuit> {
     Arc::new(builder.build().expect("session keys should build"))
 }
 
+/// Builds an HMAC-SHA256 circuit for a key of `key_len` bytes and a message of `msg_len` bytes.
+///
+/// The message can span any number of SHA256 blocks.
+///
+/// # Inputs
+///
+///   0. KEY: `key_len`-byte key (must be <= 64 bytes)
+///   1. MSG: `msg_len`-byte message
+///
+/// # Outputs
+///
+///   0. DIGEST: 32-byte HMAC-SHA256 digest
+#[tracing::instrument(level = "trace")]
+pub fn build_hmac_sha256(key_len: usize, msg_len: usize) -> Arc<Circuit> {
+    assert!(key_len <= 64, "HMAC-SHA256 key must be at most 64 bytes");
+
+    let builder = CircuitBuilder::new();
+    let key = builder.add_vec_input::<u8>(key_len);
+    let msg = builder.add_vec_input::<u8>(msg_len);
+    let digest = hmac_sha256_trace(builder.state(), &key, &msg);
+    builder.add_output(digest);
+    Arc::new(builder.build().expect("hmac-sha256 should build"))
+}
+
 /// Builds a verify data circuit.
 #[tracing::instrument(level = "trace")]
 pub fn build_verify_data(label: &[u8]) -> Arc<Circuit> {
@@ -44,6 +44,12 @@ pub enum BackendError {
     EncryptionError(String),
     #[error("Decryption error: {0:?}")]
     DecryptionError(String),
+    #[error("Connection error: {0:?}")]
+    ConnectionError(String),
+    #[error("Peer misbehaved: {0:?}")]
+    PeerMisbehaved(String),
+    #[error("Quota exceeded: {0:?}")]
+    QuotaExceeded(String),
 }
 
 /// Encryption modes for Crypto implementor
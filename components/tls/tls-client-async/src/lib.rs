@@ -18,7 +18,9 @@ use futures::{
 };
 
 use std::{
+    ops::Range,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 
@@ -53,12 +55,85 @@ pub struct ClosedConnection {
     pub recv: Vec<u8>,
 }
 
+/// A view of the plaintext transcript of a TLS connection while it is open.
+///
+/// The view is updated as plaintext is sent to and received from the server.
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptView(Arc<Mutex<Transcripts>>);
+
+#[derive(Debug, Default)]
+struct Transcripts {
+    sent: Vec<u8>,
+    recv: Vec<u8>,
+}
+
+impl TranscriptView {
+    /// Calls `f` with the plaintext sent to the server so far.
+    ///
+    /// The transcript is locked while `f` runs, so `f` should not block.
+    pub fn with_sent<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.0.lock().unwrap().sent)
+    }
+
+    /// Calls `f` with the plaintext received from the server so far.
+    ///
+    /// The transcript is locked while `f` runs, so `f` should not block.
+    pub fn with_recv<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.0.lock().unwrap().recv)
+    }
+
+    /// Returns a copy of the plaintext sent to the server in the provided range, or `None` if it
+    /// has not been sent yet.
+    pub fn sent_range(&self, range: Range<usize>) -> Option<Vec<u8>> {
+        self.with_sent(|sent| sent.get(range).map(<[u8]>::to_vec))
+    }
+
+    /// Returns a copy of the plaintext received from the server in the provided range, or `None`
+    /// if it has not been received yet.
+    pub fn recv_range(&self, range: Range<usize>) -> Option<Vec<u8>> {
+        self.with_recv(|recv| recv.get(range).map(<[u8]>::to_vec))
+    }
+
+    /// Returns the number of plaintext bytes sent to the server so far.
+    pub fn sent_len(&self) -> usize {
+        self.0.lock().unwrap().sent.len()
+    }
+
+    /// Returns the number of plaintext bytes received from the server so far.
+    pub fn recv_len(&self) -> usize {
+        self.0.lock().unwrap().recv.len()
+    }
+
+    fn extend_sent(&self, data: &[u8]) {
+        self.0.lock().unwrap().sent.extend(data);
+    }
+
+    fn extend_recv(&self, data: &[u8]) {
+        self.0.lock().unwrap().recv.extend(data);
+    }
+
+    /// Returns a copy of the whole transcript, once the connection is closed.
+    fn snapshot(&self) -> (Vec<u8>, Vec<u8>) {
+        let transcripts = self.0.lock().unwrap();
+        (transcripts.sent.clone(), transcripts.recv.clone())
+    }
+}
+
 /// A future which runs the TLS connection to completion.
 ///
 /// This future must be polled in order for the connection to make progress.
 #[must_use = "futures do nothing unless polled"]
 pub struct ConnectionFuture {
     fut: Pin<Box<dyn Future<Output = Result<ClosedConnection, ConnectionError>> + Send>>,
+    transcript: TranscriptView,
+}
+
+impl ConnectionFuture {
+    /// Returns a view of the transcript of the connection, which can be inspected while the
+    /// connection is open.
+    pub fn transcript(&self) -> TranscriptView {
+        self.transcript.clone()
+    }
 }
 
 impl Future for ConnectionFuture {
@@ -84,8 +159,11 @@ pub fn bind_client<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
     let (mut rx_sender, rx_receiver) = mpsc::channel(1 << 14);
 
     let conn = TlsConnection::new(tx_sender, rx_receiver);
+    let transcript = TranscriptView::default();
 
+    let fut_transcript = transcript.clone();
     let fut = async move {
+        let transcript = fut_transcript;
         client.start().await?;
         let mut notify = client.get_notify().await?;
 
@@ -98,9 +176,6 @@ pub fn bind_client<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
         let mut client_closed = false;
         let mut server_closed = false;
 
-        let mut sent = Vec::with_capacity(1024);
-        let mut recv = Vec::with_capacity(1024);
-
         let mut rx_tls_fut = server_rx.read(&mut rx_tls_buf).fuse();
         // We don't start writing application data until the handshake is complete.
         let mut tx_recv_fut: Fuse<Next<'_, mpsc::Receiver<Bytes>>> = Fuse::terminated();
@@ -125,7 +200,7 @@ pub fn bind_client<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
             // Forward received plaintext to `TlsConnection`.
             while !client.plaintext_is_empty() {
                 let read = client.read_plaintext(&mut rx_buf)?;
-                recv.extend(&rx_buf[..read]);
+                transcript.extend_recv(&rx_buf[..read]);
                 // Ignore if the receiver has hung up.
                 _ = rx_sender
                     .send(Ok(Bytes::copy_from_slice(&rx_buf[..read])))
@@ -193,7 +268,7 @@ pub fn bind_client<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
                         #[cfg(feature = "tracing")]
                         trace!("writing {} plaintext bytes to client", data.len());
 
-                        sent.extend(&data);
+                        transcript.extend_sent(&data);
                         client
                             .write_all_plaintext(&data)
                             .await?;
@@ -233,17 +308,22 @@ pub fn bind_client<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
         trace!(
             "server close notify: {}, sent: {}, recv: {}",
             client.received_close_notify(),
-            sent.len(),
-            recv.len()
+            transcript.sent_len(),
+            transcript.recv_len()
         );
 
+        let (sent, recv) = transcript.snapshot();
+
         Ok(ClosedConnection { client, sent, recv })
     };
 
     #[cfg(feature = "tracing")]
     let fut = fut.instrument(debug_span!("tls_connection"));
 
-    let fut = ConnectionFuture { fut: Box::pin(fut) };
+    let fut = ConnectionFuture {
+        fut: Box::pin(fut),
+        transcript,
+    };
 
     (conn, fut)
 }
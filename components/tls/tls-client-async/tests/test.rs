@@ -418,6 +418,54 @@ async fn test_err_write_after_close(set_up_tls: impl Future<Output = TlsFixture>
     assert_eq!(res.err().unwrap().kind(), std::io::ErrorKind::BrokenPipe);
 }
 
+// Expect the transcript view to reflect the plaintext exchanged while the connection is open
+#[tokio::test]
+async fn test_ok_transcript_view() {
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+
+    let _server_task = tokio::spawn(bind_test_server(server_socket.compat()));
+
+    let mut root_store = tls_client::RootCertStore::empty();
+    root_store.add(&Certificate(CA_CERT_DER.to_vec())).unwrap();
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    let client = ClientConnection::new(
+        Arc::new(config),
+        Box::new(RustCryptoBackend::new()),
+        ServerName::try_from(SERVER_DOMAIN).unwrap(),
+    )
+    .unwrap();
+
+    let (mut client_tls_conn, tls_fut) = bind_client(client_socket.compat(), client);
+    let transcript = tls_fut.transcript();
+
+    let closed_tls_task = tokio::spawn(tls_fut);
+
+    let request = pad("expecting you to send back hello".to_string());
+    client_tls_conn.write_all(&request).await.unwrap();
+
+    let mut plaintext = vec![0u8; 320];
+    let n = client_tls_conn.read(&mut plaintext).await.unwrap();
+
+    assert_eq!(transcript.sent_len(), request.len());
+    assert_eq!(transcript.sent_range(0..request.len()).unwrap(), request);
+    transcript.with_recv(|recv| assert_eq!(recv, &plaintext[..n]));
+    assert!(transcript.recv_range(0..n + 1).is_none());
+
+    client_tls_conn
+        .write_all(&pad("send_close_notify".to_string()))
+        .await
+        .unwrap();
+    client_tls_conn.close().await.unwrap();
+
+    let closed_conn = closed_tls_task.await.unwrap().unwrap();
+
+    transcript.with_sent(|sent| assert_eq!(closed_conn.sent, sent));
+    transcript.with_recv(|recv| assert_eq!(closed_conn.recv, recv));
+}
+
 // Converts a string into a slice zero-padded to APP_RECORD_LENGTH
 fn pad(s: String) -> Vec<u8> {
     assert!(s.len() <= APP_RECORD_LENGTH);
@@ -7,6 +7,7 @@ use aes_gcm::{
 use async_trait::async_trait;
 use p256::{ecdh::EphemeralSecret, EncodedPoint, PublicKey as ECDHPublicKey};
 use rand::{rngs::OsRng, thread_rng, Rng};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use digest::Digest;
 use std::{any::Any, collections::VecDeque, convert::TryInto};
@@ -138,6 +139,8 @@ impl RustCryptoBackend {
                 write_key.copy_from_slice(&session_keys[0..16]);
                 write_iv.copy_from_slice(&session_keys[32..36]);
                 self.encrypter = Some(Encrypter::new(write_key, write_iv, cipher_suite.suite()));
+                write_key.zeroize();
+                write_iv.zeroize();
             }
             suite => return Err(BackendError::UnsupportedCiphersuite(suite)),
         }
@@ -164,6 +167,8 @@ impl RustCryptoBackend {
                 write_key.copy_from_slice(&session_keys[16..32]);
                 write_iv.copy_from_slice(&session_keys[36..40]);
                 self.decrypter = Some(Decrypter::new(write_key, write_iv, cipher_suite.suite()));
+                write_key.zeroize();
+                write_iv.zeroize();
             }
             suite => return Err(BackendError::UnsupportedCiphersuite(suite)),
         }
@@ -171,6 +176,24 @@ impl RustCryptoBackend {
     }
 }
 
+impl Zeroize for RustCryptoBackend {
+    fn zeroize(&mut self) {
+        self.master_secret.zeroize();
+        self.ems_seed.zeroize();
+        self.session_keys.zeroize();
+        self.encrypter.zeroize();
+        self.decrypter.zeroize();
+    }
+}
+
+impl Drop for RustCryptoBackend {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for RustCryptoBackend {}
+
 #[async_trait]
 impl Backend for RustCryptoBackend {
     async fn set_protocol_version(&mut self, version: ProtocolVersion) -> Result<(), BackendError> {
@@ -440,6 +463,21 @@ pub struct Encrypter {
     cipher_suite: CipherSuite,
 }
 
+impl Zeroize for Encrypter {
+    fn zeroize(&mut self) {
+        self.write_key.zeroize();
+        self.write_iv.zeroize();
+    }
+}
+
+impl Drop for Encrypter {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Encrypter {}
+
 impl Encrypter {
     pub fn new(write_key: [u8; 16], write_iv: [u8; 4], cipher_suite: CipherSuite) -> Self {
         Self {
@@ -496,6 +534,21 @@ pub struct Decrypter {
     cipher_suite: CipherSuite,
 }
 
+impl Zeroize for Decrypter {
+    fn zeroize(&mut self) {
+        self.write_key.zeroize();
+        self.write_iv.zeroize();
+    }
+}
+
+impl Drop for Decrypter {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Decrypter {}
+
 impl Decrypter {
     pub fn new(write_key: [u8; 16], write_iv: [u8; 4], cipher_suite: CipherSuite) -> Self {
         Self {
@@ -542,3 +595,42 @@ impl Decrypter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zeroize_encrypter_decrypter() {
+        let suite = CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256;
+
+        let mut encrypter = Encrypter::new([1u8; 16], [2u8; 4], suite);
+        encrypter.zeroize();
+        assert_eq!(encrypter.write_key, [0u8; 16]);
+        assert_eq!(encrypter.write_iv, [0u8; 4]);
+
+        let mut decrypter = Decrypter::new([1u8; 16], [2u8; 4], suite);
+        decrypter.zeroize();
+        assert_eq!(decrypter.write_key, [0u8; 16]);
+        assert_eq!(decrypter.write_iv, [0u8; 4]);
+    }
+
+    #[test]
+    fn test_zeroize_backend() {
+        let mut backend = RustCryptoBackend::new();
+        backend.master_secret = Some([1u8; 48]);
+        backend.ems_seed = Some(vec![1u8; 32]);
+        backend.session_keys = Some(vec![1u8; 40]);
+        backend.cipher_suite = Some(suites::tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256);
+        backend.set_encrypter().unwrap();
+        backend.set_decrypter().unwrap();
+
+        backend.zeroize();
+
+        assert!(backend.master_secret.is_none());
+        assert!(backend.ems_seed.is_none());
+        assert!(backend.session_keys.is_none());
+        assert!(backend.encrypter.is_none());
+        assert!(backend.decrypter.is_none());
+    }
+}
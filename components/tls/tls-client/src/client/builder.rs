@@ -173,6 +173,7 @@ impl ConfigBuilder<WantsClientCert> {
             enable_tickets: true,
             versions: self.state.versions,
             enable_sni: true,
+            enable_certificate_status: true,
             verifier: self.state.verifier,
             key_log: Arc::new(NoKeyLog {}),
             enable_early_data: false,
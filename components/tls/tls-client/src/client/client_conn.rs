@@ -135,6 +135,12 @@ pub struct ClientConfig {
     /// The default is true.
     pub enable_sni: bool,
 
+    /// Whether to ask the server to staple an OCSP response and to send
+    /// Signed Certificate Timestamps (SCTs) during the client handshake.
+    ///
+    /// The default is true.
+    pub enable_certificate_status: bool,
+
     /// How to verify the server certificate chain.
     pub(super) verifier: Arc<dyn verify::ServerCertVerifier>,
 
@@ -119,7 +119,7 @@ pub(super) async fn start_handshake(
     let random = cx.common.backend.get_client_random().await?;
     let hello_details = ClientHelloDetails::new();
     let sent_tls13_fake_ccs = false;
-    let may_send_sct_list = config.verifier.request_scts();
+    let may_send_sct_list = config.enable_certificate_status && config.verifier.request_scts();
     emit_client_hello_for_retry(
         config,
         cx,
@@ -216,9 +216,14 @@ async fn emit_client_hello_for_retry(
         ClientExtension::NamedGroups(config.kx_groups.iter().map(|skxg| skxg.name).collect()),
         ClientExtension::SignatureAlgorithms(config.verifier.supported_verify_schemes()),
         //ClientExtension::ExtendedMasterSecretRequest,
-        ClientExtension::CertificateStatusRequest(CertificateStatusRequest::build_ocsp()),
     ];
 
+    if config.enable_certificate_status {
+        exts.push(ClientExtension::CertificateStatusRequest(
+            CertificateStatusRequest::build_ocsp(),
+        ));
+    }
+
     if let (Some(sni_name), true) = (server_name.for_sni(), config.enable_sni) {
         exts.push(ClientExtension::make_sni(sni_name));
     }
@@ -82,7 +82,12 @@ pub static SECP384R1: SupportedKxGroup = SupportedKxGroup {
     agreement_algorithm: &ring::agreement::ECDH_P384,
 };
 
-/// A list of all the key exchange groups supported by rustls.
+/// A list of all the key exchange groups supported by MPC-TLS.
+///
+/// Only [`SECP256R1`] is supported. The 2PC key exchange converts shares of the coordinates of the
+/// shared point between additive and multiplicative form over the field of the curve, and `mpz`
+/// only implements the field of P-256. [`SECP384R1`] and [`X25519`] are not supported until it
+/// implements their fields.
 pub static ALL_KX_GROUPS: [&SupportedKxGroup; 1] = [
     // &X25519,
     &SECP256R1,
@@ -411,7 +411,7 @@ pub mod version {
 
 /// All defined key exchange groups appear in this module.
 ///
-/// ALL_KX_GROUPS is provided as an array of all of these values.
+/// ALL_KX_GROUPS is provided as an array of the values supported by MPC-TLS.
 pub mod kx_group {
     pub use crate::kx::{SECP256R1, SECP384R1, X25519};
 }
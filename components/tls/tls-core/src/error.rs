@@ -71,6 +71,15 @@ pub enum Error {
     /// The presented SCT(s) were invalid.
     InvalidSct(sct::Error),
 
+    /// The peer didn't give us any valid SCTs, but the policy requires them.
+    NoValidSct,
+
+    /// The stapled OCSP response was invalid or missing.
+    InvalidOcspResponse(String),
+
+    /// The stapled OCSP response shows the certificate was revoked.
+    CertificateRevoked,
+
     /// A catch-all error for unlikely errors.
     General(String),
 
@@ -151,6 +160,11 @@ impl fmt::Display for Error {
             Error::HandshakeNotComplete => write!(f, "handshake not complete"),
             Error::NoApplicationProtocol => write!(f, "peer doesn't support any known protocol"),
             Error::InvalidSct(ref err) => write!(f, "invalid certificate timestamp: {:?}", err),
+            Error::NoValidSct => write!(f, "no valid certificate timestamp"),
+            Error::InvalidOcspResponse(ref reason) => {
+                write!(f, "invalid ocsp response: {}", reason)
+            }
+            Error::CertificateRevoked => write!(f, "peer certificate was revoked"),
             Error::FailedToGetCurrentTime => write!(f, "failed to get current time"),
             Error::FailedToGetRandomBytes => write!(f, "failed to get random bytes"),
             Error::BadMaxFragmentSize => {
@@ -200,6 +214,9 @@ mod tests {
             Error::InvalidCertificateSignature,
             Error::InvalidCertificateData("Data".into()),
             Error::InvalidSct(sct::Error::MalformedSct),
+            Error::NoValidSct,
+            Error::InvalidOcspResponse("Data".into()),
+            Error::CertificateRevoked,
             Error::General("undocumented error".to_string()),
             Error::FailedToGetCurrentTime,
             Error::FailedToGetRandomBytes,
@@ -0,0 +1,476 @@
+//! Verification of OCSP responses stapled by the server, see
+//! [RFC 6960](https://datatracker.ietf.org/doc/html/rfc6960).
+//!
+//! A response must be signed either by the issuer of the end-entity certificate, or by a
+//! delegated responder whose certificate is included in the response. The certificate of a
+//! delegated responder must be issued by the issuer and be valid for OCSP signing, see section
+//! 4.2.2.2 of the RFC.
+
+use std::borrow::Cow;
+
+use der::{
+    asn1::{BitString, GeneralizedTime, Null, ObjectIdentifier, OctetString},
+    Choice, Decode, Encode, Enumerated, Sequence,
+};
+use ring::digest;
+use x509_cert::{
+    ext::{
+        pkix::{CrlReason, ExtendedKeyUsage},
+        Extensions,
+    },
+    name::Name,
+    serial_number::SerialNumber,
+    spki::AlgorithmIdentifierOwned,
+};
+
+use crate::{
+    error::Error,
+    key::Certificate,
+    verify::{
+        pki_error, verify_sig_using_any_alg, SignatureAlgorithms, ECDSA_SHA256, ECDSA_SHA384,
+        ED25519, RSA_SHA256, RSA_SHA384, RSA_SHA512,
+    },
+};
+
+/// id-pkix-ocsp-basic, RFC 6960
+const ID_PKIX_OCSP_BASIC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.48.1.1");
+/// id-kp-OCSPSigning, RFC 5280
+const ID_KP_OCSP_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.9");
+/// id-sha1, RFC 3279
+const ID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+/// id-sha256, RFC 5754
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+/// ecdsa-with-SHA256, RFC 5758
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+/// ecdsa-with-SHA384, RFC 5758
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+/// id-Ed25519, RFC 8410
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+/// sha256WithRSAEncryption, RFC 8017
+const SHA256_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+/// sha384WithRSAEncryption, RFC 8017
+const SHA384_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+/// sha512WithRSAEncryption, RFC 8017
+const SHA512_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+
+/// Policy for checking the OCSP response stapled by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OcspPolicy {
+    /// The OCSP response is not checked.
+    #[default]
+    Ignore,
+    /// The OCSP response is checked if the server stapled one.
+    VerifyIfPresent,
+    /// The server must staple a valid OCSP response.
+    Require,
+}
+
+/// Verifies that an OCSP response shows the end-entity certificate was not revoked at the
+/// provided time.
+///
+/// # Arguments
+///
+/// * `response` - The DER encoded `OCSPResponse`.
+/// * `end_entity` - The end-entity certificate.
+/// * `intermediates` - The intermediate certificates presented by the server, which must include
+///   the issuer of the end-entity certificate.
+/// * `now` - The time, in seconds since the UNIX epoch.
+pub fn verify_ocsp_response(
+    response: &[u8],
+    end_entity: &Certificate,
+    intermediates: &[Certificate],
+    now: u64,
+) -> Result<(), Error> {
+    let basic = decode_basic_response(response)?;
+    let end_entity = x509_cert::Certificate::from_der(&end_entity.0).map_err(malformed)?;
+    let (issuer_der, issuer) = intermediates
+        .iter()
+        .find_map(|cert| {
+            let parsed = x509_cert::Certificate::from_der(&cert.0).ok()?;
+            (parsed.tbs_certificate.subject == end_entity.tbs_certificate.issuer)
+                .then_some((cert, parsed))
+        })
+        .ok_or_else(|| invalid("issuer certificate was not presented"))?;
+
+    let responder = responder(&basic, issuer_der, &issuer, now)?;
+    let signer = webpki::EndEntityCert::try_from(responder.as_ref()).map_err(pki_error)?;
+    let tbs_response_data = basic.tbs_response_data.to_der().map_err(malformed)?;
+    verify_sig_using_any_alg(
+        &signer,
+        signature_algorithms(&basic.signature_algorithm.oid)?,
+        &tbs_response_data,
+        bit_string(&basic.signature)?,
+    )
+    .map_err(|_| invalid("response is not signed by the responder"))?;
+
+    let issuer_name = issuer.tbs_certificate.subject.to_der().map_err(malformed)?;
+    let issuer_key = bit_string(
+        &issuer
+            .tbs_certificate
+            .subject_public_key_info
+            .subject_public_key,
+    )?;
+
+    let mut found = false;
+    for single in &basic.tbs_response_data.responses {
+        let cert_id = &single.cert_id;
+        if cert_id.serial_number != end_entity.tbs_certificate.serial_number {
+            continue;
+        }
+
+        let alg = match cert_id.hash_algorithm.oid {
+            ID_SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            ID_SHA256 => &digest::SHA256,
+            _ => return Err(invalid("unsupported certificate id hash algorithm")),
+        };
+        if digest::digest(alg, &issuer_name).as_ref() != cert_id.issuer_name_hash.as_bytes()
+            || digest::digest(alg, issuer_key).as_ref() != cert_id.issuer_key_hash.as_bytes()
+        {
+            continue;
+        }
+
+        match single.cert_status {
+            CertStatus::Good(_) => {}
+            CertStatus::Revoked(_) => return Err(Error::CertificateRevoked),
+            CertStatus::Unknown(_) => return Err(invalid("certificate status is unknown")),
+        }
+
+        if now < unix_time(&single.this_update) {
+            return Err(invalid("response is not yet valid"));
+        }
+        if matches!(&single.next_update, Some(next_update) if now > unix_time(next_update)) {
+            return Err(invalid("response has expired"));
+        }
+
+        found = true;
+    }
+
+    if !found {
+        return Err(invalid("no response for the end-entity certificate"));
+    }
+
+    Ok(())
+}
+
+/// Returns the DER encoding of the certificate which signed the response, which is either the
+/// issuer or a delegated responder.
+fn responder<'a>(
+    basic: &BasicOcspResponse,
+    issuer_der: &'a Certificate,
+    issuer: &x509_cert::Certificate,
+    now: u64,
+) -> Result<Cow<'a, [u8]>, Error> {
+    let responder_id = &basic.tbs_response_data.responder_id;
+    if responder_id.matches(issuer)? {
+        return Ok(Cow::Borrowed(&issuer_der.0));
+    }
+
+    let mut responder = None;
+    for cert in basic.certs.iter().flatten() {
+        if responder_id.matches(cert)? {
+            responder = Some(cert);
+            break;
+        }
+    }
+    let responder = responder.ok_or_else(|| invalid("responder certificate was not included"))?;
+    let tbs = &responder.tbs_certificate;
+
+    if tbs.issuer != issuer.tbs_certificate.subject {
+        return Err(invalid("responder is not issued by the issuer"));
+    }
+
+    let signer = webpki::EndEntityCert::try_from(issuer_der.0.as_ref()).map_err(pki_error)?;
+    verify_sig_using_any_alg(
+        &signer,
+        signature_algorithms(&responder.signature_algorithm.oid)?,
+        &tbs.to_der().map_err(malformed)?,
+        bit_string(&responder.signature)?,
+    )
+    .map_err(|_| invalid("responder certificate is not signed by the issuer"))?;
+
+    let validity = &tbs.validity;
+    if now < validity.not_before.to_unix_duration().as_secs()
+        || now > validity.not_after.to_unix_duration().as_secs()
+    {
+        return Err(invalid("responder certificate is not valid"));
+    }
+
+    match tbs.get::<ExtendedKeyUsage>().map_err(malformed)? {
+        Some((_, usage)) if usage.0.contains(&ID_KP_OCSP_SIGNING) => {}
+        _ => return Err(invalid("responder is not authorized to sign responses")),
+    }
+
+    responder.to_der().map(Cow::Owned).map_err(malformed)
+}
+
+fn decode_basic_response(response: &[u8]) -> Result<BasicOcspResponse, Error> {
+    let response = OcspResponse::from_der(response).map_err(malformed)?;
+
+    if response.response_status != OcspResponseStatus::Successful {
+        return Err(invalid("response status is not successful"));
+    }
+
+    let response_bytes = response
+        .response_bytes
+        .ok_or_else(|| invalid("response is empty"))?;
+    if response_bytes.response_type != ID_PKIX_OCSP_BASIC {
+        return Err(invalid("response is not a basic response"));
+    }
+
+    BasicOcspResponse::from_der(response_bytes.response.as_bytes()).map_err(malformed)
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidOcspResponse(reason.to_string())
+}
+
+fn malformed(err: der::Error) -> Error {
+    Error::InvalidOcspResponse(format!("malformed encoding: {err}"))
+}
+
+fn signature_algorithms(oid: &ObjectIdentifier) -> Result<SignatureAlgorithms, Error> {
+    match *oid {
+        ECDSA_WITH_SHA256 => Ok(ECDSA_SHA256),
+        ECDSA_WITH_SHA384 => Ok(ECDSA_SHA384),
+        ID_ED25519 => Ok(ED25519),
+        SHA256_WITH_RSA_ENCRYPTION => Ok(RSA_SHA256),
+        SHA384_WITH_RSA_ENCRYPTION => Ok(RSA_SHA384),
+        SHA512_WITH_RSA_ENCRYPTION => Ok(RSA_SHA512),
+        _ => Err(invalid("unsupported signature algorithm")),
+    }
+}
+
+/// Returns the contents of a BIT STRING without unused bits.
+fn bit_string(value: &BitString) -> Result<&[u8], Error> {
+    value
+        .as_bytes()
+        .ok_or_else(|| invalid("unsupported bit string"))
+}
+
+fn unix_time(time: &GeneralizedTime) -> u64 {
+    time.to_unix_duration().as_secs()
+}
+
+/// OCSPResponse, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct OcspResponse {
+    response_status: OcspResponseStatus,
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    response_bytes: Option<ResponseBytes>,
+}
+
+/// OCSPResponseStatus, RFC 6960 section 4.2.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enumerated)]
+#[repr(u32)]
+enum OcspResponseStatus {
+    Successful = 0,
+    MalformedRequest = 1,
+    InternalError = 2,
+    TryLater = 3,
+    SigRequired = 5,
+    Unauthorized = 6,
+}
+
+/// ResponseBytes, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct ResponseBytes {
+    response_type: ObjectIdentifier,
+    response: OctetString,
+}
+
+/// BasicOCSPResponse, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct BasicOcspResponse {
+    tbs_response_data: ResponseData,
+    signature_algorithm: AlgorithmIdentifierOwned,
+    signature: BitString,
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    certs: Option<Vec<x509_cert::Certificate>>,
+}
+
+/// ResponseData, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct ResponseData {
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    version: Option<u8>,
+    responder_id: ResponderId,
+    produced_at: GeneralizedTime,
+    responses: Vec<SingleResponse>,
+    #[asn1(context_specific = "1", optional = "true", tag_mode = "EXPLICIT")]
+    response_extensions: Option<Extensions>,
+}
+
+/// ResponderID, RFC 6960 section 4.2.1.
+#[derive(Choice)]
+enum ResponderId {
+    #[asn1(context_specific = "1", tag_mode = "EXPLICIT", constructed = "true")]
+    ByName(Name),
+    #[asn1(context_specific = "2", tag_mode = "EXPLICIT", constructed = "true")]
+    ByKey(OctetString),
+}
+
+impl ResponderId {
+    /// Returns `true` if the responder is the subject of the certificate.
+    fn matches(&self, cert: &x509_cert::Certificate) -> Result<bool, Error> {
+        let tbs = &cert.tbs_certificate;
+        Ok(match self {
+            Self::ByName(name) => *name == tbs.subject,
+            // The SHA-1 hash of the public key, excluding the tag, length and unused bits.
+            Self::ByKey(hash) => {
+                let key = bit_string(&tbs.subject_public_key_info.subject_public_key)?;
+                digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, key).as_ref() == hash.as_bytes()
+            }
+        })
+    }
+}
+
+/// SingleResponse, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct SingleResponse {
+    cert_id: CertId,
+    cert_status: CertStatus,
+    this_update: GeneralizedTime,
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    next_update: Option<GeneralizedTime>,
+    #[asn1(context_specific = "1", optional = "true", tag_mode = "EXPLICIT")]
+    single_extensions: Option<Extensions>,
+}
+
+/// CertID, RFC 6960 section 4.1.1.
+#[derive(Sequence)]
+struct CertId {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    issuer_name_hash: OctetString,
+    issuer_key_hash: OctetString,
+    serial_number: SerialNumber,
+}
+
+/// CertStatus, RFC 6960 section 4.2.1.
+#[derive(Choice)]
+enum CertStatus {
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT")]
+    Good(Null),
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", constructed = "true")]
+    Revoked(RevokedInfo),
+    #[asn1(context_specific = "2", tag_mode = "IMPLICIT")]
+    Unknown(Null),
+}
+
+/// RevokedInfo, RFC 6960 section 4.2.1.
+#[derive(Sequence)]
+struct RevokedInfo {
+    revocation_time: GeneralizedTime,
+    #[asn1(context_specific = "0", optional = "true", tag_mode = "EXPLICIT")]
+    revocation_reason: Option<CrlReason>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EE: &[u8] = include_bytes!("../testdata/ocsp/ee.der");
+    const OTHER: &[u8] = include_bytes!("../testdata/ocsp/other.der");
+    const INTER: &[u8] = include_bytes!("../testdata/ocsp/inter.der");
+    const CA: &[u8] = include_bytes!("../testdata/ocsp/ca.der");
+    const GOOD: &[u8] = include_bytes!("../testdata/ocsp/good.der");
+    const REVOKED: &[u8] = include_bytes!("../testdata/ocsp/revoked.der");
+    /// Signed by a delegated responder of the intermediate.
+    const DELEGATED: &[u8] = include_bytes!("../testdata/ocsp/delegated.der");
+    /// Signed by a certificate of the intermediate which is not valid for OCSP signing.
+    const UNAUTHORIZED: &[u8] = include_bytes!("../testdata/ocsp/unauthorized.der");
+
+    /// The time at which the responses were produced.
+    const PRODUCED_AT: u64 = 1792137587;
+    const DAY: u64 = 86400;
+
+    fn certs(ders: &[&[u8]]) -> Vec<Certificate> {
+        ders.iter().map(|der| Certificate(der.to_vec())).collect()
+    }
+
+    fn verify(
+        response: &[u8],
+        end_entity: &[u8],
+        intermediates: &[&[u8]],
+        now: u64,
+    ) -> Result<(), Error> {
+        verify_ocsp_response(
+            response,
+            &Certificate(end_entity.to_vec()),
+            &certs(intermediates),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_good() {
+        verify(GOOD, EE, &[INTER, CA], PRODUCED_AT + DAY).unwrap();
+    }
+
+    #[test]
+    fn test_revoked() {
+        let err = verify(REVOKED, OTHER, &[INTER], PRODUCED_AT + DAY).unwrap_err();
+
+        assert_eq!(err, Error::CertificateRevoked);
+    }
+
+    #[test]
+    fn test_expired() {
+        let err = verify(GOOD, EE, &[INTER], PRODUCED_AT + 3651 * DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_not_yet_valid() {
+        let err = verify(GOOD, EE, &[INTER], PRODUCED_AT - DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_wrong_certificate() {
+        let err = verify(GOOD, OTHER, &[INTER], PRODUCED_AT + DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_missing_issuer() {
+        let err = verify(GOOD, EE, &[CA], PRODUCED_AT + DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_bad_signature() {
+        // The response ends with the signature, as it includes no certificates.
+        let mut response = GOOD.to_vec();
+        *response.last_mut().unwrap() ^= 1;
+
+        let err = verify(&response, EE, &[INTER], PRODUCED_AT + DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_delegated_responder() {
+        verify(DELEGATED, EE, &[INTER], PRODUCED_AT + DAY).unwrap();
+    }
+
+    #[test]
+    fn test_unauthorized_responder() {
+        let err = verify(UNAUTHORIZED, EE, &[INTER], PRODUCED_AT + DAY).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let err = verify(&GOOD[..GOOD.len() / 2], EE, &[INTER], 0).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidOcspResponse(_)));
+    }
+}
@@ -7,12 +7,13 @@ use crate::{
         enums::SignatureScheme,
         handshake::{DigitallySignedStruct, DistinguishedNames},
     },
+    ocsp::{verify_ocsp_response, OcspPolicy},
 };
 use ring::digest::Digest;
 use std::convert::TryFrom;
 use web_time::SystemTime;
 
-type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+pub(crate) type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
 
 /// Which signature verification mechanisms we support.  No particular
 /// order.
@@ -278,13 +279,15 @@ impl ServerCertVerifier for WebPkiVerifier {
     /// - Signed by a  trusted `RootCertStore` CA
     /// - Not Expired
     /// - Valid for DNS entry
+    /// - Logged in a trusted Certificate Transparency log, if a CT policy is set
+    /// - Not revoked according to the stapled OCSP response, see [`OcspPolicy`]
     fn verify_server_cert(
         &self,
         end_entity: &Certificate,
         intermediates: &[Certificate],
         server_name: &ServerName,
         scts: &mut (dyn Iterator<Item = &[u8]> + Send),
-        _ocsp_response: &[u8],
+        ocsp_response: &[u8],
         now: SystemTime,
     ) -> Result<ServerCertVerified, Error> {
         let (cert, chain, trustroots) = prepare(end_entity, intermediates, &self.roots)?;
@@ -313,6 +316,21 @@ impl ServerCertVerifier for WebPkiVerifier {
             policy.verify(end_entity, now, scts)?;
         }
 
+        match (self.ocsp_policy, ocsp_response.is_empty()) {
+            (OcspPolicy::Ignore, _) | (OcspPolicy::VerifyIfPresent, true) => {}
+            (OcspPolicy::Require, true) => {
+                return Err(Error::InvalidOcspResponse(
+                    "no ocsp response was stapled".to_string(),
+                ))
+            }
+            (_, false) => verify_ocsp_response(
+                ocsp_response,
+                end_entity,
+                intermediates,
+                seconds_since_unix_epoch,
+            )?,
+        }
+
         cert.verify_is_valid_for_dns_name(dns_name.0.as_ref())
             .map_err(pki_error)
             .map(|_| ServerCertVerified::assertion())
@@ -324,6 +342,7 @@ impl ServerCertVerifier for WebPkiVerifier {
 pub struct WebPkiVerifier {
     roots: RootCertStore,
     ct_policy: Option<CertificateTransparencyPolicy>,
+    ocsp_policy: OcspPolicy,
 }
 
 #[allow(unreachable_pub)]
@@ -336,7 +355,19 @@ impl WebPkiVerifier {
     /// Transparency. Currently CT log enforcement is opportunistic; see
     /// <https://github.com/rustls/rustls/issues/479>.
     pub fn new(roots: RootCertStore, ct_policy: Option<CertificateTransparencyPolicy>) -> Self {
-        Self { roots, ct_policy }
+        Self {
+            roots,
+            ct_policy,
+            ocsp_policy: OcspPolicy::default(),
+        }
+    }
+
+    /// Sets the policy for checking the OCSP response stapled by the server.
+    ///
+    /// By default the OCSP response is ignored.
+    pub fn with_ocsp_policy(mut self, policy: OcspPolicy) -> Self {
+        self.ocsp_policy = policy;
+        self
     }
 
     /// Returns the signature verification methods supported by
@@ -367,6 +398,7 @@ impl WebPkiVerifier {
 pub struct CertificateTransparencyPolicy {
     logs: &'static [&'static sct::Log<'static>],
     validation_deadline: SystemTime,
+    require_sct: bool,
 }
 
 impl CertificateTransparencyPolicy {
@@ -379,9 +411,19 @@ impl CertificateTransparencyPolicy {
         Self {
             logs,
             validation_deadline,
+            require_sct: false,
         }
     }
 
+    /// Requires the server to present at least one SCT which is valid for one of the logs.
+    ///
+    /// By default a server which presents no SCTs is accepted. Once required, SCTs can not be
+    /// verified without logs or after the validation deadline, so the certificate is rejected.
+    pub fn require_sct(mut self) -> Self {
+        self.require_sct = true;
+        self
+    }
+
     fn verify(
         &self,
         cert: &Certificate,
@@ -389,6 +431,9 @@ impl CertificateTransparencyPolicy {
         scts: &mut dyn Iterator<Item = &[u8]>,
     ) -> Result<(), Error> {
         if self.logs.is_empty() || self.validation_deadline.duration_since(now).is_err() {
+            if self.require_sct {
+                return Err(Error::NoValidSct);
+            }
             return Ok(());
         }
 
@@ -415,6 +460,10 @@ impl CertificateTransparencyPolicy {
             return Err(Error::InvalidSct(last_sct_error));
         }
 
+        if self.require_sct {
+            return Err(Error::NoValidSct);
+        }
+
         Ok(())
     }
 }
@@ -456,17 +505,17 @@ pub(crate) fn pki_error(error: webpki::Error) -> Error {
     }
 }
 
-static ECDSA_SHA256: SignatureAlgorithms =
+pub(crate) static ECDSA_SHA256: SignatureAlgorithms =
     &[&webpki::ECDSA_P256_SHA256, &webpki::ECDSA_P384_SHA256];
 
-static ECDSA_SHA384: SignatureAlgorithms =
+pub(crate) static ECDSA_SHA384: SignatureAlgorithms =
     &[&webpki::ECDSA_P256_SHA384, &webpki::ECDSA_P384_SHA384];
 
-static ED25519: SignatureAlgorithms = &[&webpki::ED25519];
+pub(crate) static ED25519: SignatureAlgorithms = &[&webpki::ED25519];
 
-static RSA_SHA256: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA256];
-static RSA_SHA384: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA384];
-static RSA_SHA512: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA512];
+pub(crate) static RSA_SHA256: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA256];
+pub(crate) static RSA_SHA384: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA384];
+pub(crate) static RSA_SHA512: SignatureAlgorithms = &[&webpki::RSA_PKCS1_2048_8192_SHA512];
 static RSA_PSS_SHA256: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY];
 static RSA_PSS_SHA384: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY];
 static RSA_PSS_SHA512: SignatureAlgorithms = &[&webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY];
@@ -494,7 +543,7 @@ fn convert_scheme(scheme: SignatureScheme) -> Result<SignatureAlgorithms, Error>
     }
 }
 
-fn verify_sig_using_any_alg(
+pub(crate) fn verify_sig_using_any_alg(
     cert: &webpki::EndEntityCert,
     algs: SignatureAlgorithms,
     message: &[u8],
@@ -609,4 +658,20 @@ mod tests {
             "ServerCertVerified(())"
         );
     }
+
+    #[test]
+    fn required_sct_fails_closed() {
+        let cert = Certificate(Vec::new());
+        let now = SystemTime::now();
+
+        let policy =
+            CertificateTransparencyPolicy::new(&[], now + std::time::Duration::from_secs(60));
+        assert!(policy.verify(&cert, now, &mut std::iter::empty()).is_ok());
+        assert!(matches!(
+            policy
+                .require_sct()
+                .verify(&cert, now, &mut std::iter::empty()),
+            Err(Error::NoValidSct)
+        ));
+    }
 }
@@ -11,15 +11,19 @@ use mpz_garble::{Decode, DecodePrivate, Execute, Load, Memory, Prove, Thread, Ve
 use mpz_ole::rot::{OLEReceiver, OLESender};
 use mpz_ot::{OTError, RandomOTReceiver, RandomOTSender};
 use mpz_share_conversion::{ShareConversionReceiver, ShareConversionSender};
-use tlsn_stream_cipher::{Aes128Ctr, MpcStreamCipher, StreamCipherConfig};
+use tlsn_stream_cipher::{Aes128Ctr, CircuitCounter, MpcStreamCipher, StreamCipherConfig};
 use tlsn_universal_hash::{
     ghash::{Ghash, GhashConfig},
     UniversalHash,
 };
 
+#[cfg(feature = "seeded-rng")]
+use crate::config::KEY_EXCHANGE_RNG_STREAM;
 use crate::{MpcTlsCommonConfig, TlsRole};
 
 /// Builds the components for MPC-TLS.
+///
+/// The circuits executed by the record layer are counted with `circuit_counter`.
 // TODO: Better dependency injection!!
 pub fn build_components<Ctx, T, OTS, OTR>(
     role: TlsRole,
@@ -38,6 +42,7 @@ pub fn build_components<Ctx, T, OTS, OTR>(
     thread_decrypter_stream_cipher: T,
     ot_send: OTS,
     ot_recv: OTR,
+    circuit_counter: CircuitCounter,
 ) -> (
     Box<dyn KeyExchange + Send>,
     Box<dyn Prf + Send>,
@@ -72,22 +77,22 @@ where
         + Sync
         + 'static,
 {
+    let mut ke_config = KeyExchangeConfig::builder();
+    #[cfg(feature = "seeded-rng")]
+    if let Some(seed) = config.derive_seed(KEY_EXCHANGE_RNG_STREAM) {
+        ke_config.rng_seed(seed);
+    }
+
     let ke: Box<dyn KeyExchange + Send> = match role {
         TlsRole::Leader => Box::new(MpcKeyExchange::new(
-            KeyExchangeConfig::builder()
-                .role(KeRole::Leader)
-                .build()
-                .unwrap(),
+            ke_config.role(KeRole::Leader).build().unwrap(),
             ctx_ke,
             ShareConversionSender::new(OLESender::new(ot_send.clone())),
             ShareConversionReceiver::new(OLEReceiver::new(ot_recv.clone())),
             thread_ke,
         )),
         TlsRole::Follower => Box::new(MpcKeyExchange::new(
-            KeyExchangeConfig::builder()
-                .role(KeRole::Follower)
-                .build()
-                .unwrap(),
+            ke_config.role(KeRole::Follower).build().unwrap(),
             ctx_ke,
             ShareConversionReceiver::new(OLEReceiver::new(ot_recv.clone())),
             ShareConversionSender::new(OLESender::new(ot_send.clone())),
@@ -120,6 +125,7 @@ where
         StreamCipherConfig::builder()
             .id("encrypter/stream_cipher")
             .transcript_id("tx")
+            .circuit_counter(circuit_counter.clone())
             .build()
             .unwrap(),
         thread_encrypter_stream_cipher,
@@ -168,6 +174,7 @@ where
         StreamCipherConfig::builder()
             .id("decrypter/stream_cipher")
             .transcript_id("rx")
+            .circuit_counter(circuit_counter)
             .build()
             .unwrap(),
         thread_decrypter_stream_cipher,
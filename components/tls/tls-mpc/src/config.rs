@@ -1,4 +1,6 @@
 use derive_builder::Builder;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 
 static DEFAULT_OPAQUE_TX_TRANSCRIPT_ID: &str = "opaque_tx";
 static DEFAULT_OPAQUE_RX_TRANSCRIPT_ID: &str = "opaque_rx";
@@ -82,6 +84,22 @@ pub struct MpcTlsCommonConfig {
     /// Whether the leader commits to the handshake data.
     #[builder(default = "true")]
     handshake_commit: bool,
+    /// Seed of the RNG which generates the private key share and the client random, only
+    /// settable with the `seeded-rng` feature. The OS RNG is used if not set.
+    #[builder(setter(custom), default)]
+    rng_seed: Option<[u8; 32]>,
+}
+
+impl MpcTlsCommonConfigBuilder {
+    /// Seeds the RNG which generates the private key share and the client random, so that runs
+    /// are reproducible.
+    ///
+    /// This is insecure and must only be used in tests.
+    #[cfg(feature = "seeded-rng")]
+    pub fn rng_seed(&mut self, seed: [u8; 32]) -> &mut Self {
+        self.rng_seed = Some(Some(seed));
+        self
+    }
 }
 
 impl MpcTlsCommonConfig {
@@ -114,8 +132,29 @@ impl MpcTlsCommonConfig {
     pub fn handshake_commit(&self) -> bool {
         self.handshake_commit
     }
+
+    /// Returns the seed of the RNG, if any.
+    pub fn rng_seed(&self) -> Option<&[u8; 32]> {
+        self.rng_seed.as_ref()
+    }
+
+    /// Derives the seed of a component from the seed of the RNG, if any. Each component uses a
+    /// distinct stream of the RNG.
+    pub(crate) fn derive_seed(&self, stream: u64) -> Option<[u8; 32]> {
+        self.rng_seed.map(|seed| {
+            let mut rng = ChaCha20Rng::from_seed(seed);
+            rng.set_stream(stream);
+            rng.gen()
+        })
+    }
 }
 
+/// Stream of the RNG used to seed the key exchange.
+#[cfg(feature = "seeded-rng")]
+pub(crate) const KEY_EXCHANGE_RNG_STREAM: u64 = 0;
+/// Stream of the RNG used to generate the client random.
+pub(crate) const CLIENT_RANDOM_RNG_STREAM: u64 = 1;
+
 /// Configuration for the leader
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Builder)]
@@ -140,6 +179,13 @@ impl MpcTlsLeaderConfig {
 #[derive(Debug, Clone, Builder)]
 pub struct MpcTlsFollowerConfig {
     common: MpcTlsCommonConfig,
+    /// Maximum number of application data records the leader can send and receive in total.
+    ///
+    /// Each record costs a fixed number of circuits to encrypt or decrypt on top of those
+    /// proportional to its length, so this bounds the work of the follower beyond the
+    /// transcript sizes. Unlimited if not set.
+    #[builder(default)]
+    max_records: Option<usize>,
 }
 
 impl MpcTlsFollowerConfig {
@@ -152,4 +198,10 @@ impl MpcTlsFollowerConfig {
     pub fn common(&self) -> &MpcTlsCommonConfig {
         &self.common
     }
+
+    /// Returns the maximum number of application data records the leader can send and receive,
+    /// if any.
+    pub fn max_records(&self) -> Option<usize> {
+        self.max_records
+    }
 }
@@ -55,12 +55,22 @@ impl MpcTlsError {
     pub fn msg(&self) -> &str {
         &self.msg
     }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns `true` if a resource quota of the session was exceeded.
+    pub fn is_quota_exceeded(&self) -> bool {
+        self.kind == Kind::QuotaExceeded
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 /// The kind of MPC-TLS error that occurred
-pub(crate) enum Kind {
+pub enum Kind {
     /// An unexpected state was encountered
     State,
     /// Context error.
@@ -81,6 +91,8 @@ pub(crate) enum Kind {
     Config,
     /// Peer misbehaved somehow, perhaps maliciously.
     PeerMisbehaved,
+    /// A resource quota of the session was exceeded.
+    QuotaExceeded,
     /// Other error
     Other,
 }
@@ -98,6 +110,7 @@ impl Display for Kind {
             Kind::Decrypt => write!(f, "Decryption"),
             Kind::Config => write!(f, "Config"),
             Kind::PeerMisbehaved => write!(f, "PeerMisbehaved"),
+            Kind::QuotaExceeded => write!(f, "QuotaExceeded"),
             Kind::Other => write!(f, "Other"),
         }
     }
@@ -165,6 +178,19 @@ impl From<hmac_sha256::PrfError> for MpcTlsError {
 
 impl From<MpcTlsError> for BackendError {
     fn from(err: MpcTlsError) -> Self {
-        BackendError::InternalError(err.to_string())
+        // The kind is kept so that the TLS client's errors can still be told apart.
+        let msg = err.to_string();
+        match err.kind {
+            Kind::Io | Kind::Ctx => BackendError::ConnectionError(msg),
+            Kind::PeerMisbehaved => BackendError::PeerMisbehaved(msg),
+            Kind::QuotaExceeded => BackendError::QuotaExceeded(msg),
+            Kind::Config => BackendError::InvalidConfig(msg),
+            Kind::State => BackendError::InvalidState(msg),
+            Kind::Encrypt => BackendError::EncryptionError(msg),
+            Kind::Decrypt => BackendError::DecryptionError(msg),
+            Kind::Mpc | Kind::KeyExchange | Kind::Prf | Kind::Other => {
+                BackendError::InternalError(msg)
+            }
+        }
     }
 }
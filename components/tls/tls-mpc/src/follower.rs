@@ -20,18 +20,23 @@ use tls_core::{
         alert::AlertMessagePayload,
         base::Payload,
         codec::Codec,
-        enums::{AlertDescription, ContentType, HandshakeType, NamedGroup, ProtocolVersion},
+        enums::{
+            AlertDescription, AlertLevel, CipherSuite, ContentType, HandshakeType, NamedGroup,
+            ProtocolVersion,
+        },
         handshake::{HandshakeMessagePayload, HandshakePayload},
         message::{OpaqueMessage, PlainMessage},
     },
 };
 use tracing::{debug, instrument, Instrument};
+use web_time::Instant;
 
 use crate::{
     error::Kind,
     msg::{CloseConnection, Commit, MpcTlsFollowerMsg, MpcTlsMessage},
-    record_layer::{Decrypter, Encrypter},
-    Direction, MpcTlsChannel, MpcTlsError, MpcTlsFollowerConfig,
+    record_layer::{record_overhead, Decrypter, Encrypter},
+    timing::unix_time_ms,
+    Direction, MpcTlsChannel, MpcTlsError, MpcTlsFollowerConfig, MpcTlsTimings,
 };
 
 /// Controller for MPC-TLS follower.
@@ -53,8 +58,24 @@ pub struct MpcTlsFollower {
 
     /// Whether the server has sent a CloseNotify alert.
     close_notify: bool,
+    /// Renegotiation requests sent by the server which the leader has not refused yet.
+    hello_requests: HelloRequests,
     /// Whether the leader has committed to the transcript.
     committed: bool,
+    /// Time spent in the 2PC so far.
+    timings: MpcTlsTimings,
+    /// Times at which the application data records were sent, in milliseconds since the UNIX
+    /// epoch.
+    sent_record_times: Vec<u64>,
+    /// Times at which the application data records were received, in milliseconds since the UNIX
+    /// epoch.
+    recv_record_times: Vec<u64>,
+    /// Plaintext lengths of the application data records sent.
+    sent_record_lens: Vec<usize>,
+    /// Plaintext lengths of the application data records received.
+    recv_record_lens: Vec<usize>,
+    /// Number of application data records sent before each record was received.
+    recv_record_order: Vec<usize>,
 }
 
 /// Data collected by the MPC-TLS follower.
@@ -68,6 +89,20 @@ pub struct MpcTlsFollowerData {
     pub bytes_sent: usize,
     /// The total number of bytes received
     pub bytes_recv: usize,
+    /// Time spent in the 2PC of the connection
+    pub timings: MpcTlsTimings,
+    /// Times at which the application data records were sent, in milliseconds since the UNIX
+    /// epoch
+    pub sent_record_times: Vec<u64>,
+    /// Times at which the application data records were received, in milliseconds since the UNIX
+    /// epoch
+    pub recv_record_times: Vec<u64>,
+    /// Plaintext lengths of the application data records sent
+    pub sent_record_lens: Vec<usize>,
+    /// Plaintext lengths of the application data records received
+    pub recv_record_lens: Vec<usize>,
+    /// Number of application data records sent before each record was received
+    pub recv_record_order: Vec<usize>,
 }
 
 impl ludi::Actor for MpcTlsFollower {
@@ -90,6 +125,12 @@ impl ludi::Actor for MpcTlsFollower {
             server_key,
             bytes_sent,
             bytes_recv,
+            timings: self.timings,
+            sent_record_times: mem::take(&mut self.sent_record_times),
+            recv_record_times: mem::take(&mut self.recv_record_times),
+            sent_record_lens: mem::take(&mut self.sent_record_lens),
+            recv_record_lens: mem::take(&mut self.recv_record_lens),
+            recv_record_order: mem::take(&mut self.recv_record_order),
         })
     }
 }
@@ -127,7 +168,14 @@ impl MpcTlsFollower {
             encrypter,
             decrypter,
             close_notify: false,
+            hello_requests: HelloRequests::default(),
             committed: false,
+            timings: MpcTlsTimings::default(),
+            sent_record_times: Vec::new(),
+            recv_record_times: Vec::new(),
+            sent_record_lens: Vec::new(),
+            recv_record_lens: Vec::new(),
+            recv_record_order: Vec::new(),
         }
     }
 
@@ -243,6 +291,27 @@ impl MpcTlsFollower {
         Ok(())
     }
 
+    /// Returns an error if the leader already sent and received the maximum number of records.
+    fn check_record_count(&self) -> Result<(), MpcTlsError> {
+        let Some(max_records) = self.config.max_records() else {
+            return Ok(());
+        };
+
+        let records = self.sent_record_lens.len() + self.recv_record_lens.len();
+        if records >= max_records {
+            return Err(MpcTlsError::new(
+                Kind::QuotaExceeded,
+                format!(
+                    "max number of records exceeded: {} > {}",
+                    records + 1,
+                    max_records
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Returns an error if the follower is not accepting new messages.
     ///
     /// This can happen if the follower has received a CloseNotify alert or if the leader has
@@ -270,6 +339,7 @@ impl MpcTlsFollower {
         &mut self,
         handshake_commitment: Option<Hash>,
         server_random: [u8; 32],
+        cipher_suite: CipherSuite,
     ) -> Result<(), MpcTlsError> {
         self.state.take().try_into_init()?;
 
@@ -280,6 +350,13 @@ impl MpcTlsFollower {
             ));
         }
 
+        let Some(record_overhead) = record_overhead(cipher_suite) else {
+            return Err(MpcTlsError::new(
+                Kind::PeerMisbehaved,
+                format!("unsupported cipher suite: {cipher_suite:?}"),
+            ));
+        };
+
         // Key exchange
         self.ke.compute_pms().await?;
 
@@ -299,6 +376,7 @@ impl MpcTlsFollower {
                 NamedGroup::secp256r1,
                 server_key.to_encoded_point(false).as_bytes(),
             ),
+            record_overhead,
         });
 
         Ok(())
@@ -309,6 +387,7 @@ impl MpcTlsFollower {
         let Ke {
             handshake_commitment,
             server_key,
+            record_overhead,
         } = self.state.take().try_into_ke()?;
 
         let client_finished = self.prf.compute_client_finished_vd(handshake_hash).await?;
@@ -317,6 +396,7 @@ impl MpcTlsFollower {
             handshake_commitment,
             server_key,
             client_finished,
+            record_overhead,
         });
 
         Ok(())
@@ -328,6 +408,7 @@ impl MpcTlsFollower {
             handshake_commitment,
             server_key,
             server_finished,
+            record_overhead,
         } = self.state.take().try_into_sf()?;
 
         let expected_server_finished = self.prf.compute_server_finished_vd(handshake_hash).await?;
@@ -346,6 +427,7 @@ impl MpcTlsFollower {
         self.state = State::Active(Active {
             handshake_commitment,
             server_key,
+            record_overhead,
             buffer: Default::default(),
         });
 
@@ -358,6 +440,7 @@ impl MpcTlsFollower {
             handshake_commitment,
             server_key,
             client_finished,
+            record_overhead,
         } = self.state.take().try_into_cf()?;
 
         let msg = HandshakeMessagePayload {
@@ -379,6 +462,7 @@ impl MpcTlsFollower {
             handshake_commitment,
             server_key,
             server_finished: None,
+            record_overhead,
         });
 
         Ok(())
@@ -388,12 +472,18 @@ impl MpcTlsFollower {
     async fn encrypt_alert(&mut self, msg: Vec<u8>) -> Result<(), MpcTlsError> {
         self.is_accepting_messages()?;
         if let Some(alert) = AlertMessagePayload::read_bytes(&msg) {
-            // We only allow the leader to send a CloseNotify alert
-            if alert.description != AlertDescription::CloseNotify {
-                return Err(MpcTlsError::new(
-                    Kind::PeerMisbehaved,
-                    "attempted to send an alert other than CloseNotify",
-                ));
+            // We only allow the leader to send a CloseNotify alert, or to refuse a renegotiation
+            // request of the server with a NoRenegotiation warning
+            match (alert.level, alert.description) {
+                (_, AlertDescription::CloseNotify) => {}
+                (AlertLevel::Warning, AlertDescription::NoRenegotiation)
+                    if self.hello_requests.refuse() => {}
+                _ => {
+                    return Err(MpcTlsError::new(
+                        Kind::PeerMisbehaved,
+                        "attempted to send an alert other than CloseNotify",
+                    ))
+                }
             }
         } else {
             return Err(MpcTlsError::new(
@@ -417,12 +507,16 @@ impl MpcTlsFollower {
     async fn encrypt_message(&mut self, len: usize) -> Result<(), MpcTlsError> {
         self.is_accepting_messages()?;
         self.check_transcript_length(Direction::Sent, len)?;
+        self.check_record_count()?;
         self.state.try_as_active()?;
 
         self.encrypter
             .encrypt_blind(ContentType::ApplicationData, ProtocolVersion::TLSv1_2, len)
             .await?;
 
+        self.sent_record_times.push(unix_time_ms());
+        self.sent_record_lens.push(len);
+
         Ok(())
     }
 
@@ -430,7 +524,14 @@ impl MpcTlsFollower {
     fn commit_message(&mut self, payload: Vec<u8>) -> Result<(), MpcTlsError> {
         self.is_accepting_messages()?;
         self.check_transcript_length(Direction::Recv, payload.len())?;
-        let Active { buffer, .. } = self.state.try_as_active_mut()?;
+        self.check_record_count()?;
+        let payload_len = payload.len();
+        let Active {
+            buffer,
+            record_overhead,
+            ..
+        } = self.state.try_as_active_mut()?;
+        let record_overhead = *record_overhead;
 
         buffer.push_back(OpaqueMessage {
             typ: ContentType::ApplicationData,
@@ -438,6 +539,11 @@ impl MpcTlsFollower {
             payload: Payload::new(payload),
         });
 
+        self.recv_record_times.push(unix_time_ms());
+        self.recv_record_lens
+            .push(payload_len.saturating_sub(record_overhead));
+        self.recv_record_order.push(self.sent_record_lens.len());
+
         Ok(())
     }
 
@@ -500,6 +606,25 @@ impl MpcTlsFollower {
         Ok(())
     }
 
+    #[instrument(level = "trace", skip_all, err)]
+    async fn decrypt_post_handshake(&mut self, msg: Vec<u8>) -> Result<(), MpcTlsError> {
+        self.is_accepting_messages()?;
+        self.state.try_as_active()?;
+
+        let msg = self
+            .decrypter
+            .decrypt_public(OpaqueMessage {
+                typ: ContentType::Handshake,
+                version: ProtocolVersion::TLSv1_2,
+                payload: Payload::new(msg),
+            })
+            .await?;
+
+        // The only handshake messages a TLS 1.2 server may send after the handshake are
+        // HelloRequests, which the leader refuses as renegotiation is not supported.
+        self.hello_requests.receive(&msg.payload.0)
+    }
+
     #[instrument(level = "trace", skip_all, err)]
     async fn decrypt_message(&mut self) -> Result<(), MpcTlsError> {
         let Active { buffer, .. } = self.state.try_as_active_mut()?;
@@ -570,40 +695,66 @@ impl MpcTlsFollower {
         &mut self,
         handshake_commitment: Option<Hash>,
         server_random: [u8; 32],
+        cipher_suite: CipherSuite,
     ) {
-        ctx.try_or_stop(|_| self.compute_key_exchange(handshake_commitment, server_random))
-            .await;
+        let start = Instant::now();
+        ctx.try_or_stop(|_| {
+            self.compute_key_exchange(handshake_commitment, server_random, cipher_suite)
+        })
+        .await;
+        self.timings.handshake += start.elapsed();
     }
 
     pub async fn client_finished_vd(&mut self, handshake_hash: [u8; 32]) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.client_finished_vd(handshake_hash))
             .await;
+        self.timings.handshake += start.elapsed();
     }
 
     pub async fn server_finished_vd(&mut self, handshake_hash: [u8; 32]) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.server_finished_vd(handshake_hash))
             .await;
+        self.timings.handshake += start.elapsed();
     }
 
     pub async fn encrypt_client_finished(&mut self) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.encrypt_client_finished()).await;
+        self.timings.handshake += start.elapsed();
     }
 
     pub async fn encrypt_alert(&mut self, msg: Vec<u8>) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.encrypt_alert(msg)).await;
+        self.timings.record_layer += start.elapsed();
     }
 
     pub async fn encrypt_message(&mut self, len: usize) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.encrypt_message(len)).await;
+        self.timings.record_layer += start.elapsed();
     }
 
     pub async fn decrypt_server_finished(&mut self, ciphertext: Vec<u8>) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.decrypt_server_finished(ciphertext))
             .await;
+        self.timings.handshake += start.elapsed();
     }
 
     pub async fn decrypt_alert(&mut self, ciphertext: Vec<u8>) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.decrypt_alert(ciphertext)).await;
+        self.timings.record_layer += start.elapsed();
+    }
+
+    pub async fn decrypt_post_handshake(&mut self, ciphertext: Vec<u8>) {
+        let start = Instant::now();
+        ctx.try_or_stop(|_| self.decrypt_post_handshake(ciphertext))
+            .await;
+        self.timings.record_layer += start.elapsed();
     }
 
     pub async fn commit_message(&mut self, msg: Vec<u8>) {
@@ -612,7 +763,9 @@ impl MpcTlsFollower {
     }
 
     pub async fn decrypt_message(&mut self) {
+        let start = Instant::now();
         ctx.try_or_stop(|_| self.decrypt_message()).await;
+        self.timings.record_layer += start.elapsed();
     }
 
     #[msg(skip, name = "CloseConnection")]
@@ -632,6 +785,54 @@ impl MpcTlsFollower {
     }
 }
 
+/// Renegotiation requests of the server, each of which the leader may refuse once.
+#[derive(Debug, Default)]
+struct HelloRequests {
+    pending: usize,
+}
+
+impl HelloRequests {
+    /// Records the requests in the plaintext of a handshake record received after the handshake.
+    fn receive(&mut self, payload: &[u8]) -> Result<(), MpcTlsError> {
+        let count = count_hello_requests(payload).ok_or_else(|| {
+            MpcTlsError::new(
+                Kind::PeerMisbehaved,
+                "server sent an unexpected post-handshake message",
+            )
+        })?;
+
+        self.pending += count;
+
+        Ok(())
+    }
+
+    /// Returns `true` if a refusal of the leader answers a pending request, which is then no
+    /// longer pending.
+    fn refuse(&mut self) -> bool {
+        if self.pending == 0 {
+            return false;
+        }
+
+        self.pending -= 1;
+
+        true
+    }
+}
+
+/// Returns the number of HelloRequest messages in the payload of a handshake record, or none if
+/// the payload contains any other message.
+fn count_hello_requests(payload: &[u8]) -> Option<usize> {
+    // A HelloRequest is a handshake message of type 0 with an empty body.
+    const HELLO_REQUEST: [u8; 4] = [0; 4];
+
+    let chunks = payload.chunks(4);
+    if payload.is_empty() || !chunks.clone().all(|chunk| chunk == HELLO_REQUEST) {
+        return None;
+    }
+
+    Some(chunks.len())
+}
+
 mod state {
     use super::*;
     use enum_try_as_inner::EnumTryAsInner;
@@ -664,6 +865,8 @@ mod state {
     pub(super) struct Ke {
         pub(super) handshake_commitment: Option<Hash>,
         pub(super) server_key: PublicKey,
+        /// Length of the explicit nonce and the tag of a record of the negotiated cipher suite.
+        pub(super) record_overhead: usize,
     }
 
     #[derive(Debug)]
@@ -671,6 +874,7 @@ mod state {
         pub(super) handshake_commitment: Option<Hash>,
         pub(super) server_key: PublicKey,
         pub(super) client_finished: [u8; 12],
+        pub(super) record_overhead: usize,
     }
 
     #[derive(Debug)]
@@ -678,12 +882,14 @@ mod state {
         pub(super) handshake_commitment: Option<Hash>,
         pub(super) server_key: PublicKey,
         pub(super) server_finished: Option<[u8; 12]>,
+        pub(super) record_overhead: usize,
     }
 
     #[derive(Debug)]
     pub(super) struct Active {
         pub(super) handshake_commitment: Option<Hash>,
         pub(super) server_key: PublicKey,
+        pub(super) record_overhead: usize,
         /// TLS messages purportedly received by the leader from the server.
         ///
         /// The follower must verify the authenticity of these messages with AEAD verification
@@ -699,3 +905,45 @@ mod state {
 }
 
 use state::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_hello_requests() {
+        assert_eq!(count_hello_requests(&[0; 4]), Some(1));
+        assert_eq!(count_hello_requests(&[0; 12]), Some(3));
+
+        assert_eq!(count_hello_requests(&[]), None);
+        // A truncated request.
+        assert_eq!(count_hello_requests(&[0; 3]), None);
+        assert_eq!(count_hello_requests(&[0; 6]), None);
+        // A request with a body.
+        assert_eq!(count_hello_requests(&[0, 0, 0, 1, 0]), None);
+        // A ClientHello following a request.
+        assert_eq!(count_hello_requests(&[0, 0, 0, 0, 1, 0, 0, 0]), None);
+        // A NewSessionTicket.
+        assert_eq!(count_hello_requests(&[4, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_hello_requests() {
+        let mut requests = HelloRequests::default();
+
+        // Nothing is refused before the server requests a renegotiation.
+        assert!(!requests.refuse());
+
+        requests.receive(&[0; 8]).unwrap();
+        requests.receive(&[0; 4]).unwrap();
+
+        let err = requests.receive(&[4, 0, 0, 0]).unwrap_err();
+        assert_eq!(err.kind(), Kind::PeerMisbehaved);
+
+        // Each request is refused once.
+        assert!(requests.refuse());
+        assert!(requests.refuse());
+        assert!(requests.refuse());
+        assert!(!requests.refuse());
+    }
+}
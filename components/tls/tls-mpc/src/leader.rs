@@ -21,24 +21,29 @@ use tls_core::{
     msgs::{
         alert::AlertMessagePayload,
         codec::Codec,
-        enums::{AlertDescription, CipherSuite, ContentType, NamedGroup, ProtocolVersion},
+        enums::{
+            AlertDescription, AlertLevel, CipherSuite, ContentType, NamedGroup, ProtocolVersion,
+        },
         handshake::Random,
         message::{OpaqueMessage, PlainMessage},
     },
     suites::SupportedCipherSuite,
 };
 use tracing::{debug, instrument, trace, Instrument};
+use web_time::Instant;
 
 use crate::{
+    config::CLIENT_RANDOM_RNG_STREAM,
     error::Kind,
     follower::{
         ClientFinishedVd, CommitMessage, ComputeKeyExchange, DecryptAlert, DecryptMessage,
-        DecryptServerFinished, EncryptAlert, EncryptClientFinished, EncryptMessage,
-        ServerFinishedVd,
+        DecryptPostHandshake, DecryptServerFinished, EncryptAlert, EncryptClientFinished,
+        EncryptMessage, ServerFinishedVd,
     },
     msg::{CloseConnection, Commit, MpcTlsLeaderMsg, MpcTlsMessage},
     record_layer::{Decrypter, Encrypter},
-    Direction, MpcTlsChannel, MpcTlsError, MpcTlsLeaderConfig,
+    timing::unix_time_ms,
+    Direction, MpcTlsChannel, MpcTlsError, MpcTlsLeaderConfig, MpcTlsTimings,
 };
 
 /// Controller for MPC-TLS leader.
@@ -66,6 +71,15 @@ pub struct MpcTlsLeader {
     buffer: VecDeque<OpaqueMessage>,
     /// Whether we have already committed to the transcript.
     committed: bool,
+    /// Time spent in the 2PC so far.
+    timings: MpcTlsTimings,
+    /// Application data records sent so far.
+    sent_records: Vec<RecordInfo>,
+    /// Application data records received so far.
+    recv_records: Vec<RecordInfo>,
+    /// Times at which the buffered application data records were received, with the number of
+    /// application data records sent before each of them.
+    recv_times: VecDeque<(u64, usize)>,
 }
 
 impl ludi::Actor for MpcTlsLeader {
@@ -75,7 +89,10 @@ impl ludi::Actor for MpcTlsLeader {
     async fn stopped(&mut self) -> Result<Self::Stop, Self::Error> {
         debug!("leader actor stopped");
 
-        let state::Closed { data } = self.state.take().try_into_closed()?;
+        let state::Closed { mut data } = self.state.take().try_into_closed()?;
+        data.timings = self.timings;
+        data.sent_records = std::mem::take(&mut self.sent_records);
+        data.recv_records = std::mem::take(&mut self.recv_records);
 
         Ok(data)
     }
@@ -105,7 +122,7 @@ impl MpcTlsLeader {
         Self {
             config,
             channel,
-            state: State::default(),
+            state: State::new(config.common().derive_seed(CLIENT_RANDOM_RNG_STREAM)),
             ke,
             prf,
             encrypter,
@@ -114,6 +131,10 @@ impl MpcTlsLeader {
             is_decrypting: true,
             buffer: VecDeque::new(),
             committed: false,
+            timings: MpcTlsTimings::default(),
+            sent_records: Vec::new(),
+            recv_records: Vec::new(),
+            recv_times: VecDeque::new(),
         }
     }
 
@@ -228,8 +249,13 @@ impl MpcTlsLeader {
     #[instrument(level = "debug", skip_all, err)]
     async fn encrypt_alert(&mut self, msg: PlainMessage) -> Result<OpaqueMessage, MpcTlsError> {
         if let Some(alert) = AlertMessagePayload::read_bytes(&msg.payload.0) {
-            // We only allow CloseNotify alerts.
-            if alert.description != AlertDescription::CloseNotify {
+            // We only allow CloseNotify alerts, and NoRenegotiation warnings to refuse
+            // renegotiation requests of the server.
+            if !matches!(
+                (alert.level, alert.description),
+                (_, AlertDescription::CloseNotify)
+                    | (AlertLevel::Warning, AlertDescription::NoRenegotiation)
+            ) {
                 return Err(MpcTlsError::other(
                     "attempted to send an alert other than CloseNotify",
                 ));
@@ -307,6 +333,24 @@ impl MpcTlsLeader {
         Ok(msg)
     }
 
+    #[instrument(level = "debug", skip_all, err)]
+    async fn decrypt_post_handshake(
+        &mut self,
+        msg: OpaqueMessage,
+    ) -> Result<PlainMessage, MpcTlsError> {
+        self.state.try_as_active()?;
+
+        self.channel
+            .send(MpcTlsMessage::DecryptPostHandshake(DecryptPostHandshake {
+                ciphertext: msg.payload.0.clone(),
+            }))
+            .await?;
+
+        let msg = self.decrypter.decrypt_public(msg).await?;
+
+        Ok(msg)
+    }
+
     #[instrument(level = "debug", skip_all, err)]
     async fn decrypt_application_data(
         &mut self,
@@ -441,7 +485,9 @@ impl Backend for MpcTlsLeader {
 
     #[instrument(level = "debug", skip_all, err)]
     async fn get_client_key_share(&mut self) -> Result<PublicKey, BackendError> {
+        let start = Instant::now();
         let pk = self.ke.client_key().await.map_err(MpcTlsError::from)?;
+        self.timings.handshake += start.elapsed();
 
         Ok(PublicKey::new(
             NamedGroup::secp256r1,
@@ -475,10 +521,12 @@ impl Backend for MpcTlsLeader {
 
             *server_public_key = Some(key);
 
+            let start = Instant::now();
             self.ke
                 .set_server_key(server_key)
                 .await
                 .map_err(MpcTlsError::from)?;
+            self.timings.handshake += start.elapsed();
 
             Ok(())
         }
@@ -525,6 +573,7 @@ impl Backend for MpcTlsLeader {
             .try_into()
             .map_err(|_| MpcTlsError::other("server finished handshake hash is not 32 bytes"))?;
 
+        let start = Instant::now();
         self.channel
             .send(MpcTlsMessage::ServerFinishedVd(ServerFinishedVd {
                 handshake_hash: hash,
@@ -537,6 +586,7 @@ impl Backend for MpcTlsLeader {
             .compute_server_finished_vd(hash)
             .await
             .map_err(MpcTlsError::from)?;
+        self.timings.handshake += start.elapsed();
 
         Ok(vd.to_vec())
     }
@@ -547,6 +597,7 @@ impl Backend for MpcTlsLeader {
             .try_into()
             .map_err(|_| MpcTlsError::other("client finished handshake hash is not 32 bytes"))?;
 
+        let start = Instant::now();
         self.channel
             .send(MpcTlsMessage::ClientFinishedVd(ClientFinishedVd {
                 handshake_hash: hash,
@@ -559,6 +610,7 @@ impl Backend for MpcTlsLeader {
             .compute_client_finished_vd(hash)
             .await
             .map_err(MpcTlsError::from)?;
+        self.timings.handshake += start.elapsed();
 
         Ok(vd.to_vec())
     }
@@ -575,6 +627,8 @@ impl Backend for MpcTlsLeader {
             server_kx_details,
         } = self.state.take().try_into_ke().map_err(MpcTlsError::from)?;
 
+        let start = Instant::now();
+
         let protocol_version =
             protocol_version.ok_or(MpcTlsError::other("protocol version not set"))?;
         let cipher_suite = cipher_suite.ok_or(MpcTlsError::other("cipher suite not set"))?;
@@ -606,6 +660,7 @@ impl Backend for MpcTlsLeader {
             .send(MpcTlsMessage::ComputeKeyExchange(ComputeKeyExchange {
                 handshake_commitment,
                 server_random: server_random.0,
+                cipher_suite,
             }))
             .await
             .map_err(|e| BackendError::InternalError(e.to_string()))?;
@@ -630,9 +685,14 @@ impl Backend for MpcTlsLeader {
                 server_kx_details,
                 handshake_data,
                 handshake_decommitment,
+                timings: MpcTlsTimings::default(),
+                sent_records: Vec::new(),
+                recv_records: Vec::new(),
             },
         });
 
+        self.timings.handshake += start.elapsed();
+
         Ok(())
     }
 
@@ -641,6 +701,14 @@ impl Backend for MpcTlsLeader {
         msg: PlainMessage,
         seq: u64,
     ) -> Result<OpaqueMessage, BackendError> {
+        let start = Instant::now();
+        let is_handshake = msg.typ == ContentType::Handshake;
+        let record = (msg.typ == ContentType::ApplicationData).then(|| RecordInfo {
+            seq,
+            len: msg.payload.0.len(),
+            time: unix_time_ms(),
+            sent_before: self.sent_records.len(),
+        });
         let msg = match msg.typ {
             ContentType::Handshake => self.encrypt_client_finished(msg).await,
             ContentType::ApplicationData => self.encrypt_application_data(msg).await,
@@ -653,6 +721,14 @@ impl Backend for MpcTlsLeader {
         }
         .map_err(BackendError::from)?;
 
+        self.sent_records.extend(record);
+
+        if is_handshake {
+            self.timings.handshake += start.elapsed();
+        } else {
+            self.timings.record_layer += start.elapsed();
+        }
+
         Ok(msg)
     }
 
@@ -661,8 +737,11 @@ impl Backend for MpcTlsLeader {
         msg: OpaqueMessage,
         seq: u64,
     ) -> Result<PlainMessage, BackendError> {
+        let start = Instant::now();
+        let is_handshake = msg.typ == ContentType::Handshake;
         let msg = match msg.typ {
-            ContentType::Handshake => self.decrypt_server_finished(msg).await,
+            ContentType::Handshake if self.state.is_sf() => self.decrypt_server_finished(msg).await,
+            ContentType::Handshake => self.decrypt_post_handshake(msg).await,
             ContentType::ApplicationData => self.decrypt_application_data(msg).await,
             ContentType::Alert => self.decrypt_alert(msg).await,
             _ => {
@@ -673,6 +752,25 @@ impl Backend for MpcTlsLeader {
         }
         .map_err(BackendError::from)?;
 
+        if msg.typ == ContentType::ApplicationData {
+            let (time, sent_before) = self
+                .recv_times
+                .pop_front()
+                .unwrap_or_else(|| (unix_time_ms(), self.sent_records.len()));
+            self.recv_records.push(RecordInfo {
+                seq,
+                len: msg.payload.0.len(),
+                time,
+                sent_before,
+            });
+        }
+
+        if is_handshake {
+            self.timings.handshake += start.elapsed();
+        } else {
+            self.timings.record_layer += start.elapsed();
+        }
+
         Ok(msg)
     }
 
@@ -690,6 +788,9 @@ impl Backend for MpcTlsLeader {
                 }))
                 .await
                 .map_err(|e| BackendError::InternalError(e.to_string()))?;
+
+            self.recv_times
+                .push_back((unix_time_ms(), self.sent_records.len()));
         }
 
         self.buffer.push_back(msg);
@@ -747,6 +848,26 @@ pub struct MpcTlsData {
     pub handshake_data: HandshakeData,
     /// Handshake data decommitment.
     pub handshake_decommitment: Option<Decommitment<HandshakeData>>,
+    /// Time spent in the 2PC of the connection.
+    pub timings: MpcTlsTimings,
+    /// Application data records sent, in order.
+    pub sent_records: Vec<RecordInfo>,
+    /// Application data records received, in order.
+    pub recv_records: Vec<RecordInfo>,
+}
+
+/// An application data record of the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordInfo {
+    /// Sequence number of the record.
+    pub seq: u64,
+    /// Length of the plaintext of the record.
+    pub len: usize,
+    /// Time at which the record was sent or received, in milliseconds since the UNIX epoch.
+    pub time: u64,
+    /// Number of application data records sent before this record was sent or received, which
+    /// orders the records of both directions.
+    pub sent_before: usize,
 }
 
 mod state {
@@ -768,14 +889,15 @@ mod state {
         pub(super) fn take(&mut self) -> Self {
             std::mem::replace(self, State::Error)
         }
-    }
 
-    impl Default for State {
-        fn default() -> Self {
+        /// Creates the initial state, with the given client random or a random one.
+        pub(super) fn new(client_random: Option<[u8; 32]>) -> Self {
             State::Ke(Ke {
                 protocol_version: None,
                 cipher_suite: None,
-                client_random: Random::new().expect("rng is available"),
+                client_random: client_random
+                    .map(Random)
+                    .unwrap_or_else(|| Random::new().expect("rng is available")),
                 server_random: None,
                 server_cert_details: None,
                 server_public_key: None,
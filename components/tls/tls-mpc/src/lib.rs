@@ -14,6 +14,7 @@ pub(crate) mod follower;
 pub(crate) mod leader;
 pub mod msg;
 pub(crate) mod record_layer;
+mod timing;
 
 pub use components::build_components;
 pub use config::{
@@ -22,9 +23,11 @@ pub use config::{
     MpcTlsLeaderConfig, MpcTlsLeaderConfigBuilder, MpcTlsLeaderConfigBuilderError,
     TranscriptConfig, TranscriptConfigBuilder, TranscriptConfigBuilderError,
 };
-pub use error::MpcTlsError;
+pub use error::{Kind as MpcTlsErrorKind, MpcTlsError};
 pub use follower::{FollowerCtrl, MpcTlsFollower, MpcTlsFollowerData};
-pub use leader::{LeaderCtrl, MpcTlsData, MpcTlsLeader};
+pub use leader::{LeaderCtrl, MpcTlsData, MpcTlsLeader, RecordInfo};
+pub use timing::MpcTlsTimings;
+pub use tlsn_stream_cipher::CircuitCounter;
 use utils_aio::duplex::Duplex;
 
 /// A channel for sending and receiving messages between leader and follower
@@ -6,8 +6,8 @@ use crate::{
     error::Kind,
     follower::{
         ClientFinishedVd, CommitMessage, ComputeKeyExchange, DecryptAlert, DecryptMessage,
-        DecryptServerFinished, EncryptAlert, EncryptClientFinished, EncryptMessage,
-        ServerFinishedVd,
+        DecryptPostHandshake, DecryptServerFinished, EncryptAlert, EncryptClientFinished,
+        EncryptMessage, ServerFinishedVd,
     },
     leader::{
         BackendMsgBufferIncoming, BackendMsgBufferLen, BackendMsgDecrypt, BackendMsgEncrypt,
@@ -33,6 +33,7 @@ pub enum MpcTlsMessage {
     ServerFinishedVd(ServerFinishedVd),
     DecryptServerFinished(DecryptServerFinished),
     DecryptAlert(DecryptAlert),
+    DecryptPostHandshake(DecryptPostHandshake),
     /// A leader commitment to a TLS message received from the server.
     CommitMessage(CommitMessage),
     EncryptMessage(EncryptMessage),
@@ -54,6 +55,7 @@ impl TryFrom<MpcTlsMessage> for MpcTlsFollowerMsg {
             MpcTlsMessage::ServerFinishedVd(msg) => Ok(Self::ServerFinishedVd(msg)),
             MpcTlsMessage::DecryptServerFinished(msg) => Ok(Self::DecryptServerFinished(msg)),
             MpcTlsMessage::DecryptAlert(msg) => Ok(Self::DecryptAlert(msg)),
+            MpcTlsMessage::DecryptPostHandshake(msg) => Ok(Self::DecryptPostHandshake(msg)),
             MpcTlsMessage::CommitMessage(msg) => Ok(Self::CommitMessage(msg)),
             MpcTlsMessage::EncryptMessage(msg) => Ok(Self::EncryptMessage(msg)),
             MpcTlsMessage::DecryptMessage(msg) => Ok(Self::DecryptMessage(msg)),
@@ -110,6 +112,7 @@ pub enum MpcTlsFollowerMsg {
     ServerFinishedVd(ServerFinishedVd),
     DecryptServerFinished(DecryptServerFinished),
     DecryptAlert(DecryptAlert),
+    DecryptPostHandshake(DecryptPostHandshake),
     CommitMessage(CommitMessage),
     EncryptMessage(EncryptMessage),
     DecryptMessage(DecryptMessage),
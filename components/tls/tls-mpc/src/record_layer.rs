@@ -1,17 +1,35 @@
-use aead::aes_gcm::AesGcmError;
+use aead::aes_gcm::{AesGcmError, TAG_LEN};
 use mpz_garble::value::ValueRef;
 
 use tls_core::{
     cipher::make_tls12_aad,
     msgs::{
         base::Payload,
-        enums::{ContentType, ProtocolVersion},
+        enums::{CipherSuite, ContentType, ProtocolVersion},
         message::{OpaqueMessage, PlainMessage},
     },
+    suites::{AEADAlgorithm, SupportedCipherSuite, ALL_CIPHER_SUITES},
 };
 
 use crate::{error::Kind, MpcTlsError};
 
+/// Returns the number of bytes which a record of the cipher suite holds along with the
+/// ciphertext, i.e. the explicit nonce and the authentication tag, or `None` if MPC-TLS does not
+/// support the cipher suite.
+pub(crate) fn record_overhead(suite: CipherSuite) -> Option<usize> {
+    ALL_CIPHER_SUITES
+        .iter()
+        .find_map(|supported| match supported {
+            SupportedCipherSuite::Tls12(tls12)
+                if tls12.common.suite == suite
+                    && *tls12.common.aead_algorithm == AEADAlgorithm::AES_128_GCM =>
+            {
+                Some(tls12.explicit_nonce_len + TAG_LEN)
+            }
+            _ => None,
+        })
+}
+
 pub(crate) struct Encrypter {
     aead: Box<dyn aead::Aead<Error = AesGcmError>>,
     seq: u64,
@@ -433,3 +451,29 @@ impl Decrypter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_overhead() {
+        assert_eq!(
+            record_overhead(CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256),
+            Some(8 + 16)
+        );
+        assert_eq!(
+            record_overhead(CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256),
+            Some(8 + 16)
+        );
+        assert_eq!(
+            record_overhead(CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384),
+            None
+        );
+        assert_eq!(
+            record_overhead(CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256),
+            None
+        );
+        assert_eq!(record_overhead(CipherSuite::TLS13_AES_128_GCM_SHA256), None);
+    }
+}
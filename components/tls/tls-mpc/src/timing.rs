@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Time spent by a party in the 2PC of an MPC-TLS connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MpcTlsTimings {
+    /// Time spent in the handshake: the key exchange, the derivation of the session keys and the
+    /// Finished messages.
+    pub handshake: Duration,
+    /// Time spent encrypting and decrypting application data and alert records.
+    pub record_layer: Duration,
+}
+
+/// Returns the current time in milliseconds since the UNIX epoch.
+pub(crate) fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is after the UNIX epoch")
+        .as_millis() as u64
+}
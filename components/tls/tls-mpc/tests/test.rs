@@ -15,8 +15,8 @@ use serio::StreamExt;
 use tls_client::Certificate;
 use tls_client_async::bind_client;
 use tls_mpc::{
-    build_components, MpcTlsCommonConfig, MpcTlsFollower, MpcTlsFollowerConfig, MpcTlsLeader,
-    MpcTlsLeaderConfig, TlsRole,
+    build_components, CircuitCounter, MpcTlsCommonConfig, MpcTlsFollower, MpcTlsFollowerConfig,
+    MpcTlsLeader, MpcTlsLeaderConfig, TlsRole,
 };
 use tls_server_fixture::{bind_test_server_hyper, CA_CERT_DER, SERVER_DOMAIN};
 use tokio_util::compat::TokioAsyncReadCompatExt;
@@ -110,6 +110,7 @@ async fn leader(config: MpcTlsCommonConfig, mux: TestFramedMux) {
         .unwrap(),
         ot_sender.clone(),
         ot_receiver.clone(),
+        CircuitCounter::new(),
     );
 
     let mut leader = MpcTlsLeader::new(
@@ -289,6 +290,7 @@ async fn follower(config: MpcTlsCommonConfig, mux: TestFramedMux) {
         .unwrap(),
         ot_sender.clone(),
         ot_receiver.clone(),
+        CircuitCounter::new(),
     );
 
     let mut follower = MpcTlsFollower::new(
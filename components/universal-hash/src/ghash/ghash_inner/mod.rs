@@ -12,6 +12,7 @@ use mpz_fields::gf2_128::Gf2_128;
 use mpz_share_conversion::{ShareConversionError, ShareConvert};
 use std::fmt::Debug;
 use tracing::instrument;
+use zeroize::Zeroizing;
 
 mod config;
 #[cfg(feature = "ideal")]
@@ -106,11 +107,13 @@ where
             ));
         }
 
-        let mut h_additive = [0u8; 16];
+        // The key is a share of the GHASH key, so the bytes are zeroized on drop.
+        let key = Zeroizing::new(key);
+        let mut h_additive = Zeroizing::new([0u8; 16]);
         h_additive.copy_from_slice(key.as_slice());
 
         // GHASH reflects the bits of the key.
-        let h_additive = Gf2_128::new(u128::from_be_bytes(h_additive).reverse_bits());
+        let h_additive = Gf2_128::new(u128::from_be_bytes(*h_additive).reverse_bits());
 
         let h_multiplicative = self
             .converter
@@ -2,17 +2,30 @@
 //!
 //! This module sets up connection to notary server via TCP or TLS for subsequent requests for notarization.
 
+use async_tungstenite::{
+    tokio::{client_async, TokioAdapter},
+    tungstenite::client::IntoClientRequest,
+};
+use futures::future::BoxFuture;
 use http_body_util::{BodyExt as _, Either, Empty, Full};
 use hyper::{body::Bytes, client::conn::http1::Parts, Request, StatusCode};
 use hyper_util::rt::TokioIo;
-use notary_server::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse};
+use notary_server::{
+    ClientType, NotarizationSessionRequest, NotarizationSessionResponse, ERROR_CATEGORY_HEADER,
+};
+use socket2::{SockRef, TcpKeepalive};
 use std::{
-    io::Error as IoError,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
+};
+use tlsn_common::{
+    config::{DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    error::{ClassifiedError, ErrorCategory},
+    reconnect::Reconnect,
 };
-use tlsn_common::config::{DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT};
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
     net::TcpStream,
@@ -22,10 +35,15 @@ use tokio_rustls::{
     rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore},
     TlsConnector,
 };
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 use tracing::{debug, error};
+use ws_stream_tungstenite::WsStream;
 
 use crate::error::{ClientError, ErrorKind};
 
+/// Default idle time after which TCP keepalive probes are sent to the notary server.
+pub const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(30);
+
 /// Parameters used to configure notarization.
 #[derive(Debug, Clone, derive_builder::Builder)]
 pub struct NotarizationRequest {
@@ -35,6 +53,12 @@ pub struct NotarizationRequest {
     /// Maximum number of bytes that can be received.
     #[builder(default = "DEFAULT_MAX_RECV_LIMIT")]
     max_recv_data: usize,
+    /// DNS name of the server to be notarized, required if the notary server enforces a domain policy.
+    #[builder(setter(into, strip_option), default)]
+    server_dns: Option<String>,
+    /// Id of the tenant to use, if the notary server hosts multiple tenants.
+    #[builder(setter(into, strip_option), default)]
+    tenant_id: Option<String>,
 }
 
 impl NotarizationRequest {
@@ -61,8 +85,17 @@ pub enum NotaryConnection {
     Tcp(TcpStream),
     /// TLS connection.
     Tls(TlsStream<TcpStream>),
+    /// WebSocket connection over unencrypted TCP.
+    Ws(WebSocket<TcpStream>),
+    /// WebSocket connection over TLS.
+    Wss(WebSocket<TlsStream<TcpStream>>),
 }
 
+/// A WebSocket connection which exchanges data in binary frames.
+///
+/// Pings sent by the notary server are answered automatically.
+pub type WebSocket<S> = WsStream<TokioAdapter<S>>;
+
 impl AsyncRead for NotaryConnection {
     #[inline]
     fn poll_read(
@@ -73,6 +106,8 @@ impl AsyncRead for NotaryConnection {
         match self.get_mut() {
             NotaryConnection::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
             NotaryConnection::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+            NotaryConnection::Ws(stream) => Pin::new(stream).poll_read(cx, buf),
+            NotaryConnection::Wss(stream) => Pin::new(stream).poll_read(cx, buf),
         }
     }
 }
@@ -87,6 +122,8 @@ impl AsyncWrite for NotaryConnection {
         match self.get_mut() {
             NotaryConnection::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
             NotaryConnection::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+            NotaryConnection::Ws(stream) => Pin::new(stream).poll_write(cx, buf),
+            NotaryConnection::Wss(stream) => Pin::new(stream).poll_write(cx, buf),
         }
     }
 
@@ -95,6 +132,8 @@ impl AsyncWrite for NotaryConnection {
         match self.get_mut() {
             NotaryConnection::Tcp(stream) => Pin::new(stream).poll_flush(cx),
             NotaryConnection::Tls(stream) => Pin::new(stream).poll_flush(cx),
+            NotaryConnection::Ws(stream) => Pin::new(stream).poll_flush(cx),
+            NotaryConnection::Wss(stream) => Pin::new(stream).poll_flush(cx),
         }
     }
 
@@ -103,6 +142,8 @@ impl AsyncWrite for NotaryConnection {
         match self.get_mut() {
             NotaryConnection::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
             NotaryConnection::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+            NotaryConnection::Ws(stream) => Pin::new(stream).poll_shutdown(cx),
+            NotaryConnection::Wss(stream) => Pin::new(stream).poll_shutdown(cx),
         }
     }
 }
@@ -125,6 +166,15 @@ pub struct NotaryClient {
     /// API key used to call notary server endpoints if whitelisting is enabled in notary server.
     #[builder(setter(into, strip_option), default)]
     api_key: Option<String>,
+    /// Flag to turn on/off connecting to the notary server via WebSocket, e.g. if only WebSocket
+    /// connections are allowed by the hosting environment.
+    #[builder(setter(name = "enable_websocket"), default)]
+    websocket: bool,
+    /// Idle time after which TCP keepalive probes are sent to the notary server, so that
+    /// intermediaries, e.g. load balancers, do not drop the connection while the prover waits for
+    /// the application server. `None` disables the probes.
+    #[builder(default = "Some(DEFAULT_KEEPALIVE)")]
+    keepalive: Option<Duration>,
 }
 
 impl NotaryClientBuilder {
@@ -159,9 +209,7 @@ impl NotaryClient {
                 .with_root_certificates(self.root_cert_store.clone())
                 .with_no_client_auth();
 
-            let notary_socket = tokio::net::TcpStream::connect((self.host.as_str(), self.port))
-                .await
-                .map_err(|err| ClientError::new(ErrorKind::Connection, Some(Box::new(err))))?;
+            let notary_socket = self.connect_tcp().await?;
 
             let notary_connector = TlsConnector::from(Arc::new(notary_client_config));
             let notary_tls_socket = notary_connector
@@ -175,28 +223,108 @@ impl NotaryClient {
                 .await
                 .map_err(|err| ClientError::new(ErrorKind::TlsSetup, Some(Box::new(err))))?;
 
-            self.send_request(notary_tls_socket, notarization_request)
-                .await
-                .map(|(connection, session_id)| Accepted {
-                    id: session_id,
-                    io: NotaryConnection::Tls(connection),
-                })
+            let tenant_id = notarization_request.tenant_id.clone();
+            let (connection, session_id) = self
+                .send_request(notary_tls_socket, notarization_request)
+                .await?;
+
+            let io = if self.websocket {
+                NotaryConnection::Wss(
+                    self.connect_websocket(connection, &session_id, tenant_id.as_deref())
+                        .await?,
+                )
+            } else {
+                NotaryConnection::Tls(connection)
+            };
+
+            Ok(Accepted { id: session_id, io })
         } else {
             debug!("Setting up tcp connection...");
 
-            let notary_socket = tokio::net::TcpStream::connect((self.host.as_str(), self.port))
-                .await
-                .map_err(|err| ClientError::new(ErrorKind::Connection, Some(Box::new(err))))?;
+            let notary_socket = self.connect_tcp().await?;
 
-            self.send_request(notary_socket, notarization_request)
-                .await
-                .map(|(connection, session_id)| Accepted {
-                    id: session_id,
-                    io: NotaryConnection::Tcp(connection),
-                })
+            let tenant_id = notarization_request.tenant_id.clone();
+            let (connection, session_id) = self
+                .send_request(notary_socket, notarization_request)
+                .await?;
+
+            let io = if self.websocket {
+                NotaryConnection::Ws(
+                    self.connect_websocket(connection, &session_id, tenant_id.as_deref())
+                        .await?,
+                )
+            } else {
+                NotaryConnection::Tcp(connection)
+            };
+
+            Ok(Accepted { id: session_id, io })
         }
     }
 
+    /// Returns a [`Reconnect`] which re-establishes the connection to the notary server, e.g. to
+    /// retry the setup of the prover with `Prover::setup_with_retry`.
+    ///
+    /// The notary server binds each connection to a single session, so each new connection is
+    /// requested as a new notarization with the same parameters, whose session id is reported by
+    /// [`Reconnect::session_id`].
+    pub fn reconnect(&self, notarization_request: NotarizationRequest) -> NotaryReconnect {
+        NotaryReconnect {
+            client: self.clone(),
+            request: notarization_request,
+            session_id: None,
+        }
+    }
+
+    /// Connects to the notary server over TCP, enabling keepalive probes if configured.
+    async fn connect_tcp(&self) -> Result<TcpStream, ClientError> {
+        let socket = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(|err| ClientError::new(ErrorKind::Connection, Some(Box::new(err))))?;
+
+        if let Some(keepalive) = self.keepalive {
+            SockRef::from(&socket)
+                .set_tcp_keepalive(&TcpKeepalive::new().with_time(keepalive))
+                .map_err(|err| ClientError::new(ErrorKind::Connection, Some(Box::new(err))))?;
+        }
+
+        Ok(socket)
+    }
+
+    /// Upgrades the connection to the notary server to a WebSocket connection for notarization.
+    async fn connect_websocket<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        &self,
+        notary_socket: S,
+        session_id: &str,
+        tenant_id: Option<&str>,
+    ) -> Result<WebSocket<S>, ClientError> {
+        let ws_scheme = if self.tls { "wss" } else { "ws" };
+
+        // Need to specify the session_id so that notary server knows the right configuration to use
+        // as the configuration is set in the previous HTTP call.
+        let request = format!(
+            "{ws_scheme}://{}:{}/notarize/ws?sessionId={session_id}{}",
+            self.host,
+            self.port,
+            tenant_id
+                .map(|tenant_id| format!("&tenantId={tenant_id}"))
+                .unwrap_or_default()
+        )
+        .into_client_request()
+        .map_err(|err| {
+            error!("Failed to build websocket request for notarization");
+            ClientError::new(ErrorKind::Internal, Some(Box::new(err)))
+        })?;
+
+        debug!("Sending websocket notarization request: {:?}", request);
+
+        let (notary_ws_stream, _) = client_async(request, notary_socket).await.map_err(|err| {
+            error!("Failed to upgrade to websocket connection");
+            ClientError::new(ErrorKind::Http, Some(Box::new(err)))
+        })?;
+
+        Ok(WsStream::new(notary_ws_stream))
+    }
+
     /// Sends notarization request to the notary server.
     async fn send_request<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         &self,
@@ -227,14 +355,22 @@ impl NotaryClient {
             Ok(notary_socket)
         };
 
+        // The tenant id is passed in the query of both requests, so that the notary server uses the tenant's settings and session storage.
+        let tenant_id = notarization_request.tenant_id.clone();
+
         // Create a future to send configuration and notarization requests to the notary server using the connection established above.
         let client_requests_fut = async {
             // Build the HTTP request to configure notarization.
             let configuration_request_payload =
                 serde_json::to_string(&NotarizationSessionRequest {
-                    client_type: ClientType::Tcp,
+                    client_type: if self.websocket {
+                        ClientType::Websocket
+                    } else {
+                        ClientType::Tcp
+                    },
                     max_sent_data: Some(notarization_request.max_sent_data),
                     max_recv_data: Some(notarization_request.max_recv_data),
+                    server_dns: notarization_request.server_dns.clone(),
                 })
                 .map_err(|err| {
                     error!("Failed to serialise http request for configuration");
@@ -243,8 +379,13 @@ impl NotaryClient {
 
             let mut configuration_request_builder = Request::builder()
                 .uri(format!(
-                    "{http_scheme}://{}:{}/session",
-                    self.host, self.port
+                    "{http_scheme}://{}:{}/session{}",
+                    self.host,
+                    self.port,
+                    tenant_id
+                        .as_ref()
+                        .map(|tenant_id| format!("?tenantId={tenant_id}"))
+                        .unwrap_or_default()
                 ))
                 .method("POST")
                 .header("Host", &self.host)
@@ -287,7 +428,8 @@ impl NotaryClient {
                         )
                         .into(),
                     ),
-                ));
+                )
+                .with_category(response_category(&configuration_response)));
             }
 
             let configuration_response_payload = configuration_response
@@ -314,13 +456,24 @@ impl NotaryClient {
                 configuration_response_payload_parsed
             );
 
+            // The WebSocket upgrade is requested after the connection is claimed back from the HTTP client.
+            if self.websocket {
+                return Ok(configuration_response_payload_parsed.session_id);
+            }
+
             // Send notarization request via HTTP, where the underlying TCP/TLS connection will be extracted later.
             let notarization_request = Request::builder()
                 // Need to specify the session_id so that notary server knows the right configuration to use
                 // as the configuration is set in the previous HTTP call.
                 .uri(format!(
-                    "{http_scheme}://{}:{}/notarize?sessionId={}",
-                    self.host, self.port, &configuration_response_payload_parsed.session_id
+                    "{http_scheme}://{}:{}/notarize?sessionId={}{}",
+                    self.host,
+                    self.port,
+                    &configuration_response_payload_parsed.session_id,
+                    tenant_id
+                        .as_ref()
+                        .map(|tenant_id| format!("&tenantId={tenant_id}"))
+                        .unwrap_or_default()
                 ))
                 .method("GET")
                 .header("Host", &self.host)
@@ -355,7 +508,8 @@ impl NotaryClient {
                         )
                         .into(),
                     ),
-                ));
+                )
+                .with_category(response_category(&notarization_response)));
             }
 
             Ok(configuration_response_payload_parsed.session_id)
@@ -369,6 +523,45 @@ impl NotaryClient {
     }
 }
 
+/// Re-establishes the connection to the notary server, see [`NotaryClient::reconnect`].
+#[derive(Debug)]
+pub struct NotaryReconnect {
+    client: NotaryClient,
+    request: NotarizationRequest,
+    session_id: Option<String>,
+}
+
+impl Reconnect for NotaryReconnect {
+    type Io = Compat<NotaryConnection>;
+
+    fn reconnect(&mut self) -> BoxFuture<'_, Result<Self::Io, IoError>> {
+        Box::pin(async move {
+            let Accepted { id, io } = self
+                .client
+                .request_notarization(self.request.clone())
+                .await
+                .map_err(|err| {
+                    // Only transient failures are retried by the prover.
+                    let kind = if err.is_retryable() {
+                        IoErrorKind::ConnectionRefused
+                    } else {
+                        IoErrorKind::Other
+                    };
+                    IoError::new(kind, err)
+                })?;
+
+            debug!(session_id = id, "Reconnected to notary server");
+            self.session_id = Some(id);
+
+            Ok(io.compat())
+        })
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+}
+
 /// Default root store using mozilla certs.
 fn default_root_store() -> RootCertStore {
     let mut root_store = RootCertStore::empty();
@@ -382,3 +575,14 @@ fn default_root_store() -> RootCertStore {
 
     root_store
 }
+
+/// Returns the category of the error reported by the notary server in an error response.
+fn response_category<B>(response: &hyper::Response<B>) -> Option<ErrorCategory> {
+    response
+        .headers()
+        .get(ERROR_CATEGORY_HEADER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
@@ -4,6 +4,7 @@
 
 use derive_builder::UninitializedFieldError;
 use std::{error::Error, fmt};
+use tlsn_common::error::{ClassifiedError, ErrorCategory};
 
 #[derive(Debug)]
 #[allow(missing_docs)]
@@ -22,11 +23,62 @@ pub struct ClientError {
     kind: ErrorKind,
     #[source]
     source: Option<Box<dyn Error + Send + Sync>>,
+    category: Option<ErrorCategory>,
 }
 
 impl ClientError {
     pub(crate) fn new(kind: ErrorKind, source: Option<Box<dyn Error + Send + Sync>>) -> Self {
-        Self { kind, source }
+        Self {
+            kind,
+            source,
+            category: None,
+        }
+    }
+
+    /// Sets the category of the error reported by the notary server, if any.
+    pub(crate) fn with_category(mut self, category: Option<ErrorCategory>) -> Self {
+        self.category = category;
+        self
+    }
+}
+
+impl ClassifiedError for ClientError {
+    fn category(&self) -> ErrorCategory {
+        if let Some(category) = self.category {
+            return category;
+        }
+        let source = || {
+            self.source
+                .as_deref()
+                .map(|source| source as &(dyn Error + 'static))
+        };
+        match self.kind {
+            ErrorKind::Internal => ErrorCategory::Internal,
+            ErrorKind::Builder => ErrorCategory::InvalidInput,
+            ErrorKind::Connection => ErrorCategory::NetworkTransient,
+            ErrorKind::TlsSetup => source()
+                .map(|source| {
+                    ErrorCategory::from_source_chain(source, ErrorCategory::ProtocolViolation)
+                })
+                .unwrap_or(ErrorCategory::ProtocolViolation),
+            ErrorKind::Http => source()
+                .map(|source| {
+                    ErrorCategory::from_source_chain(source, ErrorCategory::NetworkTransient)
+                })
+                .unwrap_or(ErrorCategory::NetworkTransient),
+            ErrorKind::Configuration => ErrorCategory::PolicyRejected,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::Internal => "client.internal",
+            ErrorKind::Builder => "client.builder",
+            ErrorKind::Connection => "client.connection",
+            ErrorKind::TlsSetup => "client.tls_setup",
+            ErrorKind::Http => "client.http",
+            ErrorKind::Configuration => "client.configuration",
+        }
     }
 }
 
@@ -1,7 +1,7 @@
 //! Notary client library.
 //!
-//! A notary client's purpose is to establish a connection to the notary server via TCP or TLS, and
-//! to configure and request notarization.
+//! A notary client's purpose is to establish a connection to the notary server via TCP or TLS, optionally
+//! over WebSocket, and to configure and request notarization.
 //! Note that the actual notarization is not performed by the notary client but by the prover of the
 //! TLSNotary protocol.
 #![deny(missing_docs, unreachable_pub, unused_must_use)]
@@ -11,5 +11,9 @@
 mod client;
 mod error;
 
-pub use client::{Accepted, NotarizationRequest, NotaryClient, NotaryConnection};
+pub use client::{
+    Accepted, NotarizationRequest, NotaryClient, NotaryConnection, NotaryReconnect, WebSocket,
+    DEFAULT_KEEPALIVE,
+};
 pub use error::ClientError;
+pub use tlsn_common::error::{ClassifiedError, ErrorCategory};
@@ -8,21 +8,22 @@ use hyper_util::{
     client::legacy::{connect::HttpConnector, Builder},
     rt::{TokioExecutor, TokioIo},
 };
-use notary_client::{NotarizationRequest, NotaryClient, NotaryConnection};
+use notary_client::{Accepted, NotarizationRequest, NotaryClient, NotaryConnection};
 use rstest::rstest;
 use rustls::{Certificate, RootCertStore};
 use std::{string::String, time::Duration};
 use tls_server_fixture::{bind_test_server_hyper, CA_CERT_DER, SERVER_DOMAIN};
-use tlsn_prover::tls::{Prover, ProverConfig};
-use tokio::io::{AsyncRead, AsyncWrite};
+use tlsn_prover::tls::{Prover, ProverConfig, Reconnect as _};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 use tracing::debug;
 use ws_stream_tungstenite::WsStream;
 
 use notary_server::{
-    read_pem_file, run_server, AuthorizationProperties, LoggingProperties, NotarizationProperties,
-    NotarizationSessionRequest, NotarizationSessionResponse, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    read_pem_file, run_server, AuthorizationProperties, LoggingProperties, MetricsProperties,
+    NotarizationProperties, NotarizationSessionRequest, NotarizationSessionResponse,
+    NotaryServerProperties, NotarySigningKeyProperties, PolicyProperties, ServerProperties,
+    SessionStoreProperties, TLSProperties, TelemetryProperties, TimestampProperties,
 };
 
 const MAX_SENT_DATA: usize = 1 << 13;
@@ -41,18 +42,33 @@ fn get_server_config(port: u16, tls_enabled: bool, auth_enabled: bool) -> Notary
             host: NOTARY_HOST.to_string(),
             port,
             html_info: "example html response".to_string(),
+            shutdown_timeout_secs: None,
         },
         notarization: NotarizationProperties {
             max_transcript_size: 1 << 14,
+            max_sent_data: None,
+            max_recv_data: None,
+            mpc_concurrency: None,
+            max_records: None,
+            max_ots: None,
+            max_session_duration_secs: None,
+            compression: false,
         },
         tls: TLSProperties {
             enabled: tls_enabled,
             private_key_pem_path: "../server/fixture/tls/notary.key".to_string(),
             certificate_pem_path: "../server/fixture/tls/notary.crt".to_string(),
+            acme: Default::default(),
         },
         notary_key: NotarySigningKeyProperties {
             private_key_pem_path: "../server/fixture/notary/notary.key".to_string(),
             public_key_pem_path: "../server/fixture/notary/notary.pub".to_string(),
+            key_id: None,
+            did: None,
+            bls_key_path: None,
+            retired_keys: vec![],
+            revoked_sessions: vec![],
+            revoked_key_ids: vec![],
         },
         logging: LoggingProperties {
             level: "DEBUG".to_string(),
@@ -61,7 +77,17 @@ fn get_server_config(port: u16, tls_enabled: bool, auth_enabled: bool) -> Notary
         authorization: AuthorizationProperties {
             enabled: auth_enabled,
             whitelist_csv_path: "../server/fixture/auth/whitelist.csv".to_string(),
+            max_concurrent_sessions_per_key: None,
+            max_daily_data_per_key: None,
         },
+        policy: PolicyProperties::default(),
+        tenants: vec![],
+        metrics: MetricsProperties::default(),
+        telemetry: TelemetryProperties::default(),
+        timestamp: TimestampProperties::default(),
+        session_store: SessionStoreProperties::default(),
+        websocket: Default::default(),
+        transparency_log: Default::default(),
     }
 }
 
@@ -116,6 +142,29 @@ async fn tcp_prover(notary_config: NotaryServerProperties) -> (NotaryConnection,
     (accepted_request.io, accepted_request.id)
 }
 
+async fn websocket_prover(notary_config: NotaryServerProperties) -> (NotaryConnection, String) {
+    let notary_client = NotaryClient::builder()
+        .host(&notary_config.server.host)
+        .port(notary_config.server.port)
+        .enable_tls(false)
+        .enable_websocket(true)
+        .build()
+        .unwrap();
+
+    let notarization_request = NotarizationRequest::builder()
+        .max_sent_data(MAX_SENT_DATA)
+        .max_recv_data(MAX_RECV_DATA)
+        .build()
+        .unwrap();
+
+    let accepted_request = notary_client
+        .request_notarization(notarization_request)
+        .await
+        .unwrap();
+
+    (accepted_request.io, accepted_request.id)
+}
+
 async fn tls_prover(notary_config: NotaryServerProperties) -> (NotaryConnection, String) {
     let mut certificate_file_reader = read_pem_file(NOTARY_CA_CERT_PATH).await.unwrap();
     let mut certificates: Vec<Certificate> = rustls_pemfile::certs(&mut certificate_file_reader)
@@ -161,6 +210,9 @@ async fn tls_prover(notary_config: NotaryServerProperties) -> (NotaryConnection,
 #[case::tcp_without_auth(
     tcp_prover(setup_config_and_server(100, 7049, false, false).await)
 )]
+#[case::websocket_without_auth(
+    websocket_prover(setup_config_and_server(100, 7051, false, false).await)
+)]
 #[awt]
 #[tokio::test]
 async fn test_tcp_prover<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
@@ -277,6 +329,7 @@ async fn test_websocket_prover() {
         client_type: notary_server::ClientType::Websocket,
         max_sent_data: Some(MAX_SENT_DATA),
         max_recv_data: Some(MAX_RECV_DATA),
+        server_dns: None,
     })
     .unwrap();
 
@@ -413,3 +466,118 @@ async fn test_websocket_prover() {
 
     debug!("Done notarization!");
 }
+
+#[tokio::test]
+async fn test_reconnect() {
+    let notary_config = setup_config_and_server(100, 7052, false, false).await;
+
+    let notarization_request = NotarizationRequest::builder()
+        .max_sent_data(MAX_SENT_DATA)
+        .max_recv_data(MAX_RECV_DATA)
+        .build()
+        .unwrap();
+
+    for websocket in [false, true] {
+        let notary_client = NotaryClient::builder()
+            .host(&notary_config.server.host)
+            .port(notary_config.server.port)
+            .enable_tls(false)
+            .enable_websocket(websocket)
+            .build()
+            .unwrap();
+
+        let mut reconnect = notary_client.reconnect(notarization_request.clone());
+        assert!(reconnect.session_id().is_none());
+
+        // Each connection is bound to a new session.
+        let _first = reconnect.reconnect().await.unwrap();
+        let first_id = reconnect.session_id().unwrap().to_string();
+        let _second = reconnect.reconnect().await.unwrap();
+
+        assert_ne!(reconnect.session_id().unwrap(), first_id);
+    }
+}
+
+#[tokio::test]
+async fn test_setup_retry_new_session() {
+    let notary_config = setup_config_and_server(100, 7053, false, false).await;
+
+    let notary_client = NotaryClient::builder()
+        .host(&notary_config.server.host)
+        .port(notary_config.server.port)
+        .enable_tls(false)
+        .build()
+        .unwrap();
+    let notarization_request = NotarizationRequest::builder()
+        .max_sent_data(MAX_SENT_DATA)
+        .max_recv_data(MAX_RECV_DATA)
+        .build()
+        .unwrap();
+
+    // The first connection fails before the setup, which consumes its session on the notary.
+    let Accepted { id, mut io } = notary_client
+        .request_notarization(notarization_request.clone())
+        .await
+        .unwrap();
+    io.shutdown().await.unwrap();
+
+    let mut root_cert_store = tls_core::anchors::RootCertStore::empty();
+    root_cert_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover_config = ProverConfig::builder()
+        .id(id)
+        .server_dns(SERVER_DOMAIN)
+        .max_sent_data(MAX_SENT_DATA)
+        .max_recv_data(MAX_RECV_DATA)
+        .root_cert_store(root_cert_store)
+        .build()
+        .unwrap();
+
+    // The setup is retried in a new session.
+    let prover = Prover::new(prover_config)
+        .setup_with_retry(
+            io.compat(),
+            notary_client.reconnect(notarization_request),
+            1,
+        )
+        .await
+        .unwrap();
+
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+    let server_task = tokio::spawn(bind_test_server_hyper(server_socket.compat()));
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(tls_connection.compat()))
+            .await
+            .unwrap();
+    tokio::spawn(connection);
+
+    let request = Request::builder()
+        .uri(format!("https://{}/echo", SERVER_DOMAIN))
+        .method("POST")
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .body(Full::<Bytes>::new("echo".into()))
+        .unwrap();
+    let response = request_sender.send_request(request).await.unwrap();
+    assert!(response.status() == StatusCode::OK);
+    response.into_body().collect().await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    _ = prover.finalize().await.unwrap();
+}
@@ -0,0 +1,582 @@
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use eyre::{eyre, Result};
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, LetsEncrypt,
+    NewAccount, NewOrder, OrderStatus,
+};
+use rcgen::{Certificate as CsrCertificate, CertificateParams, DistinguishedName};
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::{any_supported_type, CertifiedKey},
+    Certificate, PrivateKey,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Write,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+use tokio::net::TcpListener;
+use tracing::{debug, error, info, warn};
+use x509_cert::{der::Decode, ext::pkix::name::GeneralName, ext::pkix::SubjectAltName};
+
+use crate::config::AcmeProperties;
+
+/// Interval between checks of whether the certificate needs to be renewed
+const RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+/// Delay between polls of the state of an order
+const ORDER_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Maximum number of polls of the state of an order before giving up
+const ORDER_POLL_ATTEMPTS: usize = 30;
+
+/// Certificate resolver of the TLS endpoint, whose certificate can be replaced when it is renewed
+/// without restarting the server
+#[derive(Default)]
+pub struct AcmeCertResolver {
+    key: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl std::fmt::Debug for AcmeCertResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmeCertResolver")
+            .field("loaded", &self.key.read().unwrap().is_some())
+            .finish()
+    }
+}
+
+impl AcmeCertResolver {
+    /// Replace the certificate presented to provers
+    pub fn set(&self, certificates: Vec<Certificate>, private_key: &PrivateKey) -> Result<()> {
+        let signing_key = any_supported_type(private_key)
+            .map_err(|err| eyre!("Unsupported ACME certificate private key: {err}"))?;
+        *self.key.write().unwrap() = Some(Arc::new(CertifiedKey::new(certificates, signing_key)));
+        Ok(())
+    }
+}
+
+impl ResolvesServerCert for AcmeCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.key.read().unwrap().clone()
+    }
+}
+
+/// Credentials of the ACME account cached between restarts, along with the directory they were
+/// created with
+#[derive(Serialize, Deserialize)]
+struct CachedAccount {
+    directory_url: String,
+    credentials: AccountCredentials,
+}
+
+/// Key authorizations of the pending HTTP-01 challenges, keyed by token
+#[derive(Clone, Debug, Default)]
+struct ChallengeStore(Arc<Mutex<HashMap<String, String>>>);
+
+/// Obtains a publicly trusted certificate for the TLS endpoint of the notary with the ACME protocol,
+/// e.g. from Let's Encrypt, and renews it before it expires
+///
+/// The domains are validated with HTTP-01 challenges, served on a plain HTTP listener which must
+/// be reachable on port 80 of the domains. The certificate and the account are cached in a
+/// directory so that they are not requested again on every restart
+pub struct AcmeManager {
+    config: AcmeProperties,
+    resolver: Arc<AcmeCertResolver>,
+    challenges: ChallengeStore,
+    /// Expiry of the certificate being served
+    not_after: Mutex<Option<SystemTime>>,
+    /// Account the certificates are ordered with, created or loaded on the first order
+    account: tokio::sync::Mutex<Option<Account>>,
+}
+
+impl std::fmt::Debug for AcmeManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmeManager")
+            .field("config", &self.config)
+            .field("resolver", &self.resolver)
+            .field("not_after", &self.not_after)
+            .finish()
+    }
+}
+
+impl AcmeManager {
+    pub fn new(config: AcmeProperties) -> Result<Self> {
+        if config.domains.is_empty() {
+            return Err(eyre!("At least one domain must be configured for ACME"));
+        }
+
+        Ok(Self {
+            config,
+            resolver: Default::default(),
+            challenges: Default::default(),
+            not_after: Default::default(),
+            account: Default::default(),
+        })
+    }
+
+    /// Returns the certificate resolver to build the TLS config of the endpoint with
+    pub fn resolver(&self) -> Arc<AcmeCertResolver> {
+        self.resolver.clone()
+    }
+
+    /// Start serving the HTTP-01 challenges, load or obtain the certificate, and spawn a task
+    /// renewing it
+    pub async fn start(self: Arc<Self>) -> Result<()> {
+        self.serve_challenges().await?;
+
+        match self.load_cached_certificate()? {
+            Some(not_after) if !self.needs_renewal(not_after) => {
+                info!("Loaded cached ACME certificate");
+            }
+            _ => self.obtain_certificate().await?,
+        }
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(RENEWAL_CHECK_INTERVAL).await;
+                let not_after = *self.not_after.lock().unwrap();
+                if not_after.is_some_and(|not_after| !self.needs_renewal(not_after)) {
+                    continue;
+                }
+                info!("Renewing ACME certificate");
+                // The current certificate keeps being served if the renewal fails, and the
+                // renewal is retried on the next check
+                if let Err(err) = self.obtain_certificate().await {
+                    error!("Failed to renew ACME certificate: {err}");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Whether a certificate expiring at `not_after` is within the renewal window
+    fn needs_renewal(&self, not_after: SystemTime) -> bool {
+        let renew_before = Duration::from_secs(self.config.renew_before_days * 24 * 60 * 60);
+        not_after
+            .duration_since(SystemTime::now())
+            .map_or(true, |remaining| remaining <= renew_before)
+    }
+
+    fn certificate_path(&self) -> PathBuf {
+        Path::new(&self.config.cache_dir).join("certificate.pem")
+    }
+
+    fn private_key_path(&self) -> PathBuf {
+        Path::new(&self.config.cache_dir).join("private_key.pem")
+    }
+
+    fn account_path(&self) -> PathBuf {
+        Path::new(&self.config.cache_dir).join("account.json")
+    }
+
+    fn directory_url(&self) -> &str {
+        match self.config.directory_url.as_deref() {
+            Some(directory_url) => directory_url,
+            None => LetsEncrypt::Production.url(),
+        }
+    }
+
+    /// Load the cached certificate if there is one which has not expired and is for the
+    /// configured domains, returning its expiry
+    fn load_cached_certificate(&self) -> Result<Option<SystemTime>> {
+        if !self.certificate_path().exists() || !self.private_key_path().exists() {
+            return Ok(None);
+        }
+
+        let certificate_pem = std::fs::read(self.certificate_path())?;
+        let (not_after, domains) = certificate_validity(&certificate_pem)?;
+        if not_after <= SystemTime::now() {
+            warn!("Cached ACME certificate has expired");
+            return Ok(None);
+        }
+        let configured = self
+            .config
+            .domains
+            .iter()
+            .map(|domain| domain.to_ascii_lowercase())
+            .collect::<BTreeSet<_>>();
+        if domains != configured {
+            warn!(cached = ?domains, "Cached ACME certificate is for other domains");
+            return Ok(None);
+        }
+
+        let private_key_pem = std::fs::read(self.private_key_path())?;
+        self.set_certificate(&certificate_pem, &private_key_pem)?;
+
+        Ok(Some(not_after))
+    }
+
+    fn set_certificate(&self, certificate_pem: &[u8], private_key_pem: &[u8]) -> Result<()> {
+        let (not_after, _) = certificate_validity(certificate_pem)?;
+        let certificates = rustls_pemfile::certs(&mut &certificate_pem[..])?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+        let private_key = rustls_pemfile::pkcs8_private_keys(&mut &private_key_pem[..])?
+            .into_iter()
+            .next()
+            .map(PrivateKey)
+            .ok_or_else(|| eyre!("No private key found for the ACME certificate"))?;
+
+        self.resolver.set(certificates, &private_key)?;
+        *self.not_after.lock().unwrap() = Some(not_after);
+
+        Ok(())
+    }
+
+    /// Returns the ACME account, loading it from the cache or creating it on first use
+    async fn account(&self) -> Result<Account> {
+        let mut account = self.account.lock().await;
+        if let Some(account) = account.as_ref() {
+            return Ok(account.clone());
+        }
+
+        let directory_url = self.directory_url();
+        if let Ok(cached) = std::fs::read(self.account_path()) {
+            let cached: CachedAccount = serde_json::from_slice(&cached)
+                .map_err(|err| eyre!("Failed to parse cached ACME account: {err}"))?;
+            // An account is only valid with the certificate authority it was created with
+            if cached.directory_url == directory_url {
+                let loaded = Account::from_credentials(cached.credentials)
+                    .await
+                    .map_err(|err| eyre!("Failed to load cached ACME account: {err}"))?;
+                *account = Some(loaded.clone());
+                return Ok(loaded);
+            }
+        }
+
+        let contact = self
+            .config
+            .contact_email
+            .as_ref()
+            .map(|email| format!("mailto:{email}"));
+        let contact = contact.iter().map(String::as_str).collect::<Vec<_>>();
+        let (created, credentials) = Account::create(
+            &NewAccount {
+                contact: &contact,
+                terms_of_service_agreed: true,
+                only_return_existing: false,
+            },
+            directory_url,
+            None,
+        )
+        .await
+        .map_err(|err| eyre!("Failed to create ACME account: {err}"))?;
+
+        let cached = serde_json::to_vec(&CachedAccount {
+            directory_url: directory_url.to_string(),
+            credentials,
+        })?;
+        std::fs::create_dir_all(&self.config.cache_dir)?;
+        write_private(&self.account_path(), &cached)?;
+        info!("Created ACME account");
+
+        *account = Some(created.clone());
+        Ok(created)
+    }
+
+    /// Serve the key authorizations of the pending challenges at
+    /// `/.well-known/acme-challenge/{token}`
+    async fn serve_challenges(&self) -> Result<()> {
+        let router =
+            Router::new()
+                .route(
+                    "/.well-known/acme-challenge/:token",
+                    get(
+                        |State(challenges): State<ChallengeStore>,
+                         UrlPath(token): UrlPath<String>| async move {
+                            match challenges.0.lock().unwrap().get(&token) {
+                                Some(key_authorization) => {
+                                    (StatusCode::OK, key_authorization.clone()).into_response()
+                                }
+                                None => StatusCode::NOT_FOUND.into_response(),
+                            }
+                        },
+                    ),
+                )
+                .with_state(self.challenges.clone());
+
+        let address = SocketAddr::new(
+            IpAddr::V4(self.config.http_host.parse().map_err(|err| {
+                eyre!("Failed to parse ACME challenge host address from config: {err}")
+            })?),
+            self.config.http_port,
+        );
+        let listener = TcpListener::bind(address)
+            .await
+            .map_err(|err| eyre!("Failed to bind ACME challenge address: {err}"))?;
+        info!("Serving ACME challenges at {}", address);
+
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, router).await {
+                error!("ACME challenge server failed: {err}");
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Obtain a new certificate for the configured domains, then cache and serve it
+    async fn obtain_certificate(&self) -> Result<()> {
+        let account = self.account().await?;
+
+        let identifiers = self
+            .config
+            .domains
+            .iter()
+            .map(|domain| Identifier::Dns(domain.clone()))
+            .collect::<Vec<_>>();
+        let mut order = account
+            .new_order(&NewOrder {
+                identifiers: &identifiers,
+            })
+            .await
+            .map_err(|err| eyre!("Failed to create ACME order: {err}"))?;
+
+        let authorizations = order
+            .authorizations()
+            .await
+            .map_err(|err| eyre!("Failed to fetch ACME authorizations: {err}"))?;
+        let mut tokens = Vec::new();
+        for authorization in &authorizations {
+            if let AuthorizationStatus::Valid = authorization.status {
+                continue;
+            }
+            let challenge = authorization
+                .challenges
+                .iter()
+                .find(|challenge| challenge.r#type == ChallengeType::Http01)
+                .ok_or_else(|| eyre!("ACME server did not offer an HTTP-01 challenge"))?;
+
+            self.challenges.0.lock().unwrap().insert(
+                challenge.token.clone(),
+                order.key_authorization(challenge).as_str().to_string(),
+            );
+            tokens.push(challenge.token.clone());
+            order
+                .set_challenge_ready(&challenge.url)
+                .await
+                .map_err(|err| eyre!("Failed to validate ACME challenge: {err}"))?;
+        }
+
+        let result = self.finalize_order(&mut order).await;
+
+        let mut challenges = self.challenges.0.lock().unwrap();
+        for token in tokens {
+            challenges.remove(&token);
+        }
+        drop(challenges);
+
+        let (certificate_pem, private_key_pem) = result?;
+        self.set_certificate(certificate_pem.as_bytes(), private_key_pem.as_bytes())?;
+
+        std::fs::create_dir_all(&self.config.cache_dir)?;
+        write_private(&self.private_key_path(), private_key_pem.as_bytes())?;
+        std::fs::write(self.certificate_path(), &certificate_pem)?;
+        info!(domains = ?self.config.domains, "Obtained ACME certificate");
+
+        Ok(())
+    }
+
+    /// Wait for the challenges to be validated, then finalize the order with a new key, returning
+    /// the certificate chain and the private key in PEM format
+    async fn finalize_order(&self, order: &mut instant_acme::Order) -> Result<(String, String)> {
+        let mut attempts = 0;
+        loop {
+            let state = order
+                .refresh()
+                .await
+                .map_err(|err| eyre!("Failed to fetch ACME order: {err}"))?;
+            match state.status {
+                OrderStatus::Ready => break,
+                OrderStatus::Invalid => return Err(eyre!("ACME order is invalid")),
+                status => debug!(?status, "Waiting for ACME challenges to be validated"),
+            }
+            attempts += 1;
+            if attempts == ORDER_POLL_ATTEMPTS {
+                return Err(eyre!(
+                    "Timed out waiting for ACME challenges to be validated"
+                ));
+            }
+            tokio::time::sleep(ORDER_POLL_INTERVAL).await;
+        }
+
+        let mut params = CertificateParams::new(self.config.domains.clone());
+        params.distinguished_name = DistinguishedName::new();
+        let certificate = CsrCertificate::from_params(params)
+            .map_err(|err| eyre!("Failed to generate ACME certificate key: {err}"))?;
+        let csr = certificate
+            .serialize_request_der()
+            .map_err(|err| eyre!("Failed to generate ACME certificate request: {err}"))?;
+        order
+            .finalize(&csr)
+            .await
+            .map_err(|err| eyre!("Failed to finalize ACME order: {err}"))?;
+
+        for _ in 0..ORDER_POLL_ATTEMPTS {
+            if let Some(certificate_pem) = order
+                .certificate()
+                .await
+                .map_err(|err| eyre!("Failed to download ACME certificate: {err}"))?
+            {
+                return Ok((certificate_pem, certificate.serialize_private_key_pem()));
+            }
+            tokio::time::sleep(ORDER_POLL_INTERVAL).await;
+        }
+
+        Err(eyre!(
+            "Timed out waiting for the ACME certificate to be issued"
+        ))
+    }
+}
+
+/// Returns the expiry and the lowercase DNS names of the leaf certificate of a PEM chain
+fn certificate_validity(certificate_pem: &[u8]) -> Result<(SystemTime, BTreeSet<String>)> {
+    let leaf = rustls_pemfile::certs(&mut &certificate_pem[..])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("No certificate found in the ACME certificate chain"))?;
+    let certificate = x509_cert::Certificate::from_der(&leaf)
+        .map_err(|err| eyre!("Failed to parse ACME certificate: {err}"))?;
+
+    let not_after = certificate
+        .tbs_certificate
+        .validity
+        .not_after
+        .to_system_time();
+    let domains = certificate
+        .tbs_certificate
+        .get::<SubjectAltName>()
+        .map_err(|err| eyre!("Failed to parse ACME certificate domains: {err}"))?
+        .map(|(_, names)| names.0)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|name| match name {
+            GeneralName::DnsName(name) => Some(name.as_str().to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect();
+
+    Ok((not_after, domains))
+}
+
+/// Write a file only readable by the owner, as it holds a private key
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode is only applied when the file is created
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(cache_dir: &str, renew_before_days: u64) -> AcmeProperties {
+        AcmeProperties {
+            enabled: true,
+            domains: vec!["notary.example.com".to_string()],
+            cache_dir: cache_dir.to_string(),
+            renew_before_days,
+            ..Default::default()
+        }
+    }
+
+    fn write_certificate(manager: &AcmeManager, params: CertificateParams) {
+        let certificate = CsrCertificate::from_params(params).unwrap();
+        std::fs::create_dir_all(&manager.config.cache_dir).unwrap();
+        std::fs::write(
+            manager.certificate_path(),
+            certificate.serialize_pem().unwrap(),
+        )
+        .unwrap();
+        write_private(
+            &manager.private_key_path(),
+            certificate.serialize_private_key_pem().as_bytes(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_load_cached_certificate() {
+        let cache_dir = "./fixture/acme_cache_test";
+        let manager = AcmeManager::new(config(cache_dir, 30)).unwrap();
+        assert!(manager.load_cached_certificate().unwrap().is_none());
+
+        let mut params = CertificateParams::new(vec!["notary.example.com".to_string()]);
+        params.not_after = rcgen::date_time_ymd(4000, 1, 1);
+        write_certificate(&manager, params);
+
+        let not_after = manager.load_cached_certificate().unwrap().unwrap();
+        assert!(manager.resolver.key.read().unwrap().is_some());
+        assert_eq!(*manager.not_after.lock().unwrap(), Some(not_after));
+        assert!(!manager.needs_renewal(not_after));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(manager.private_key_path())
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_cached_certificate_is_not_served_if_stale() {
+        let cache_dir = "./fixture/acme_cache_stale_test";
+        let manager = AcmeManager::new(config(cache_dir, 30)).unwrap();
+
+        // An expired certificate
+        let mut params = CertificateParams::new(vec!["notary.example.com".to_string()]);
+        params.not_before = rcgen::date_time_ymd(2020, 1, 1);
+        params.not_after = rcgen::date_time_ymd(2021, 1, 1);
+        write_certificate(&manager, params);
+        assert!(manager.load_cached_certificate().unwrap().is_none());
+
+        // A certificate for other domains
+        let mut params = CertificateParams::new(vec!["other.example.com".to_string()]);
+        params.not_after = rcgen::date_time_ymd(4000, 1, 1);
+        write_certificate(&manager, params);
+        assert!(manager.load_cached_certificate().unwrap().is_none());
+        assert!(manager.resolver.key.read().unwrap().is_none());
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_needs_renewal() {
+        let manager = AcmeManager::new(config("./acme", 30)).unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        assert!(!manager.needs_renewal(SystemTime::now() + 31 * day));
+        assert!(manager.needs_renewal(SystemTime::now() + 29 * day));
+        assert!(manager.needs_renewal(SystemTime::now() - day));
+    }
+
+    #[test]
+    fn test_acme_requires_domains() {
+        assert!(AcmeManager::new(AcmeProperties::default()).is_err());
+    }
+}
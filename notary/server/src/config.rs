@@ -15,6 +15,222 @@ pub struct NotaryServerProperties {
     pub logging: LoggingProperties,
     /// Setting for authorization
     pub authorization: AuthorizationProperties,
+    /// Setting for restricting which server DNS names can be notarized
+    #[serde(default)]
+    pub policy: PolicyProperties,
+    /// Additional tenants hosted by this notary server, each with its own signing key, limits,
+    /// authorization and policy. Provers that don't specify a tenant id use the settings above
+    #[serde(default)]
+    pub tenants: Vec<TenantProperties>,
+    /// Setting for the prometheus metrics endpoint
+    #[serde(default)]
+    pub metrics: MetricsProperties,
+    /// Setting for exporting traces with OpenTelemetry
+    #[serde(default)]
+    pub telemetry: TelemetryProperties,
+    /// Setting for embedding a timestamp in the signed session header
+    #[serde(default)]
+    pub timestamp: TimestampProperties,
+    /// Setting for storing the configuration data of sessions between the /session and /notarize
+    /// requests, shared by all tenants
+    #[serde(default)]
+    pub session_store: SessionStoreProperties,
+    /// Setting for the /notarize/ws endpoint used by browser provers, shared by all tenants
+    #[serde(default)]
+    pub websocket: WebSocketProperties,
+    /// Setting for the transparency log of the session headers signed by the notary, each tenant
+    /// having its own log
+    #[serde(default)]
+    pub transparency_log: TransparencyLogProperties,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct WebSocketProperties {
+    /// Origins of the pages allowed to notarize over WebSocket, e.g. `https://example.com`, where
+    /// `*` allows any origin. If empty, no page is allowed
+    pub allowed_origins: Vec<String>,
+    /// Maximum size of a WebSocket message in bytes
+    pub max_message_size: usize,
+    /// Maximum size of a WebSocket frame in bytes
+    pub max_frame_size: usize,
+}
+
+impl WebSocketProperties {
+    /// Check if a page with the given origin is allowed to notarize. Requests without an origin
+    /// are not sent by pages, e.g. they come from native clients, so the origin does not protect
+    /// anything and they are allowed
+    pub fn is_origin_allowed(&self, origin: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        self.allowed_origins.iter().any(|allowed| {
+            allowed == "*" || allowed.trim_end_matches('/').eq_ignore_ascii_case(origin)
+        })
+    }
+}
+
+impl Default for WebSocketProperties {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            max_message_size: 64 << 20,
+            max_frame_size: 16 << 20,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionStoreKind {
+    /// Memory of the notary server, so both requests of a session must reach the same instance
+    #[default]
+    Memory,
+    /// Redis server, requires the redis feature
+    Redis,
+    /// Postgres database, requires the postgres feature
+    Postgres,
+}
+
+/// Seconds after which unused sessions expire, if not configured
+pub const DEFAULT_SESSION_TTL_SECS: u64 = 600;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct SessionStoreProperties {
+    /// Backend storing the sessions
+    pub backend: SessionStoreKind,
+    /// Connection url of the backend, used if the backend is redis or postgres
+    pub url: String,
+    /// Seconds after which unused sessions expire
+    pub session_ttl_secs: u64,
+}
+
+impl Default for SessionStoreProperties {
+    fn default() -> Self {
+        Self {
+            backend: SessionStoreKind::Memory,
+            url: String::new(),
+            session_ttl_secs: DEFAULT_SESSION_TTL_SECS,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampSourceKind {
+    /// System clock of the notary server
+    #[default]
+    System,
+    /// System clock of the notary server, checked against an NTP server
+    Ntp,
+    /// RFC 3161 time-stamp authority, whose signed token is embedded in the session header
+    Rfc3161,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct TimestampProperties {
+    /// Switch to turn on or off embedding a timestamp in the session header
+    pub enabled: bool,
+    /// Source of the timestamp
+    pub source: TimestampSourceKind,
+    /// Address of the NTP server, used if the source is ntp
+    pub ntp_server: String,
+    /// Maximum difference in seconds between the system clock and the NTP server, used if the
+    /// source is ntp
+    pub max_clock_offset_secs: u64,
+    /// Url of the time-stamp authority, used if the source is rfc3161. Only http urls are
+    /// supported, as time-stamp tokens are signed by the authority
+    pub tsa_url: String,
+    /// Path of the PEM certificate of the time-stamp authority, used to verify the signature of
+    /// its tokens if the source is rfc3161
+    pub tsa_certificate_path: Option<String>,
+}
+
+impl Default for TimestampProperties {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: TimestampSourceKind::System,
+            ntp_server: "pool.ntp.org:123".to_string(),
+            max_clock_offset_secs: 5,
+            tsa_url: "http://timestamp.digicert.com".to_string(),
+            tsa_certificate_path: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct TelemetryProperties {
+    /// Switch to turn on or off the export of traces to an OpenTelemetry collector
+    pub enabled: bool,
+    /// gRPC endpoint of the OTLP collector
+    pub otlp_endpoint: String,
+    /// Service name attached to the exported traces
+    pub service_name: String,
+}
+
+impl Default for TelemetryProperties {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            otlp_endpoint: "http://localhost:4317".to_string(),
+            service_name: "notary-server".to_string(),
+        }
+    }
+}
+
+/// Maximum number of entries of a transparency log, if not configured
+pub const DEFAULT_MAX_LOG_ENTRIES: usize = 1 << 20;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct TransparencyLogProperties {
+    /// Switch to turn on or off recording the session headers in an append-only log before they
+    /// are signed, which is served by the /transparency-log APIs
+    pub enabled: bool,
+    /// Directory of the files backing the logs. If not set, the logs are kept in memory and are
+    /// lost when the server restarts
+    pub dir: Option<String>,
+    /// Maximum number of entries of each log, which are all kept in memory. Once a log is full,
+    /// notarizations fail until the log is rotated
+    pub max_entries: usize,
+}
+
+impl Default for TransparencyLogProperties {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            max_entries: DEFAULT_MAX_LOG_ENTRIES,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct MetricsProperties {
+    /// Switch to turn on or off the /metrics endpoint
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct TenantProperties {
+    /// Unique id used by provers to select this tenant, may only contain alphanumeric characters, '-' and '_'
+    pub id: String,
+    /// File path of private key (in PEM format) used to sign the notarization of this tenant
+    pub notary_key: NotarySigningKeyProperties,
+    /// Setting for notarization of this tenant
+    pub notarization: NotarizationProperties,
+    /// Setting for authorization of this tenant
+    #[serde(default)]
+    pub authorization: AuthorizationProperties,
+    /// Setting for restricting which server DNS names can be notarized by this tenant
+    #[serde(default)]
+    pub policy: PolicyProperties,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -24,6 +240,38 @@ pub struct AuthorizationProperties {
     pub enabled: bool,
     /// File path of the whitelist API key csv
     pub whitelist_csv_path: String,
+    /// Maximum number of notarization sessions that can run concurrently per API key
+    pub max_concurrent_sessions_per_key: Option<usize>,
+    /// Maximum amount of data (sent + received, in bytes) that can be notarized per API key per UTC day
+    pub max_daily_data_per_key: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct PolicyProperties {
+    /// Switch to turn on or off the domain policy
+    pub enabled: bool,
+    /// Server DNS names that are allowed to be notarized, where `*.` prefix matches any subdomain.
+    /// If empty, any server DNS name that is not in the denylist is allowed
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    /// Server DNS names that are not allowed to be notarized, takes precedence over allowlist
+    #[serde(default)]
+    pub denylist: Vec<String>,
+    /// Size limits for specific server DNS names, the first matching entry is applied
+    #[serde(default)]
+    pub domain_limits: Vec<DomainLimitProperties>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct DomainLimitProperties {
+    /// Server DNS name pattern that this limit applies to
+    pub pattern: String,
+    /// Maximum number of bytes that can be sent to the server
+    pub max_sent_data: Option<usize>,
+    /// Maximum number of bytes that can be received from the server
+    pub max_recv_data: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -31,6 +279,31 @@ pub struct AuthorizationProperties {
 pub struct NotarizationProperties {
     /// Global limit for maximum transcript size in bytes
     pub max_transcript_size: usize,
+    /// Global limit for the number of bytes sent by the prover, enforced independently of the
+    /// bytes received. Sessions must request a limit for the sent data if it is set
+    #[serde(default)]
+    pub max_sent_data: Option<usize>,
+    /// Global limit for the number of bytes received by the prover, enforced independently of the
+    /// bytes sent. Sessions must request a limit for the received data if it is set
+    #[serde(default)]
+    pub max_recv_data: Option<usize>,
+    /// Maximum number of threads the MPC executor forks in a notarization. The lowest of this and
+    /// the concurrency of the prover is used. Defaults to 8
+    #[serde(default)]
+    pub mpc_concurrency: Option<usize>,
+    /// Maximum number of TLS records the prover can send and receive in a notarization
+    #[serde(default)]
+    pub max_records: Option<usize>,
+    /// Maximum number of oblivious transfers the notary performs in a notarization
+    #[serde(default)]
+    pub max_ots: Option<usize>,
+    /// Seconds after which a notarization is aborted, counted from the start of the MPC setup
+    #[serde(default)]
+    pub max_session_duration_secs: Option<u64>,
+    /// Whether to compress the connection to the prover if the prover supports it. Defaults to
+    /// false
+    #[serde(default)]
+    pub compression: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -43,6 +316,10 @@ pub struct ServerProperties {
     /// Static html response returned from API root endpoint "/". Default html response contains
     /// placeholder strings that will be replaced with actual values in server.rs, e.g. {version}, {public_key}
     pub html_info: String,
+    /// Seconds that in-flight notarizations are given to complete when the server shuts down,
+    /// after which they are aborted. Defaults to 30 seconds
+    #[serde(default)]
+    pub shutdown_timeout_secs: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -52,6 +329,47 @@ pub struct TLSProperties {
     pub enabled: bool,
     pub private_key_pem_path: String,
     pub certificate_pem_path: String,
+    /// Setting for obtaining the certificate with the ACME protocol instead of from the files above
+    #[serde(default)]
+    pub acme: AcmeProperties,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct AcmeProperties {
+    /// Switch to turn on or off obtaining and renewing the TLS certificate with ACME
+    pub enabled: bool,
+    /// Domains of the certificate, which must resolve to this notary server
+    pub domains: Vec<String>,
+    /// Email address the certificate authority can use to contact the operator
+    pub contact_email: Option<String>,
+    /// Directory url of the ACME certificate authority, Let's Encrypt production if not set
+    pub directory_url: Option<String>,
+    /// Directory where the certificate and its private key are cached between restarts
+    pub cache_dir: String,
+    /// Days before the expiry of the certificate at which it is renewed, Let's Encrypt
+    /// certificates are valid 90 days
+    pub renew_before_days: u64,
+    /// Host of the plain HTTP listener serving the HTTP-01 challenges
+    pub http_host: String,
+    /// Port of the plain HTTP listener serving the HTTP-01 challenges, which must be reachable on
+    /// port 80 of the domains
+    pub http_port: u16,
+}
+
+impl Default for AcmeProperties {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            domains: Vec::new(),
+            contact_email: None,
+            directory_url: None,
+            cache_dir: "./acme".to_string(),
+            renew_before_days: 30,
+            http_host: "0.0.0.0".to_string(),
+            http_port: 80,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -59,6 +377,38 @@ pub struct TLSProperties {
 pub struct NotarySigningKeyProperties {
     pub private_key_pem_path: String,
     pub public_key_pem_path: String,
+    /// Identifier of the signing key, which is embedded in the signed session header so that
+    /// verifiers can select the right key after the key is rotated
+    #[serde(default)]
+    pub key_id: Option<String>,
+    /// DID of the notary, e.g. a did:web DID URL whose fragment selects the signing key, which is
+    /// embedded in the signed session header so that verifiers can resolve the key from it
+    #[serde(default)]
+    pub did: Option<String>,
+    /// Path to a file of at least 32 bytes of secret keying material, from which the BLS12-381
+    /// key signing the session headers is derived instead of using the key above, so that the
+    /// headers can be aggregated. Only supported if the server is built with the `bls` feature
+    #[serde(default)]
+    pub bls_key_path: Option<String>,
+    /// Past keys that signed notarizations before the key was rotated, served by the /keys API
+    #[serde(default)]
+    pub retired_keys: Vec<RetiredNotaryKeyProperties>,
+    /// Hex encoded hashes of the session headers of revoked notarizations, published in the
+    /// revocation list signed with this key by the /revocations API
+    #[serde(default)]
+    pub revoked_sessions: Vec<String>,
+    /// Identifiers of revoked keys, whose notarizations are all revoked. Each must be the id of
+    /// the current or of a retired key, whose public key is revoked
+    #[serde(default)]
+    pub revoked_key_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct RetiredNotaryKeyProperties {
+    /// Identifier of the key, as embedded in the session headers it signed
+    pub key_id: String,
+    pub public_key_pem_path: String,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -71,3 +421,40 @@ pub struct LoggingProperties {
     /// This will override the default filtering logic above
     pub filter: Option<String>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_websocket_origin_allowed() {
+        let config = WebSocketProperties::default();
+        assert!(config.is_origin_allowed(None));
+        assert!(!config.is_origin_allowed(Some("https://example.com")));
+
+        let config = WebSocketProperties {
+            allowed_origins: vec!["*".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_origin_allowed(Some("https://example.com")));
+
+        let config = WebSocketProperties {
+            allowed_origins: vec!["https://example.com/".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_origin_allowed(Some("https://EXAMPLE.com")));
+        assert!(!config.is_origin_allowed(Some("https://evil.com")));
+        assert!(!config.is_origin_allowed(None));
+    }
+
+    #[test]
+    fn test_notarization_compression() {
+        let config: NotarizationProperties =
+            serde_yaml::from_str("max-transcript-size: 20480").unwrap();
+        assert!(!config.compression);
+
+        let config: NotarizationProperties =
+            serde_yaml::from_str("max-transcript-size: 20480\ncompression: true").unwrap();
+        assert!(config.compression);
+    }
+}
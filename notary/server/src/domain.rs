@@ -1,8 +1,11 @@
 pub mod auth;
 pub mod cli;
+pub mod gate;
 pub mod notary;
+pub mod policy;
 
 use serde::{Deserialize, Serialize};
+use tlsn_core::transparency::{ConsistencyProof, InclusionProof, SignedLogHead};
 
 /// Response object of the /info API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,3 +20,79 @@ pub struct InfoResponse {
     /// Current git commit timestamp of notary-server
     pub git_commit_timestamp: String,
 }
+
+/// Response object of the /keys API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeysResponse {
+    /// Public keys of the notary, starting with the current key followed by the retired keys
+    pub keys: Vec<NotaryKeyInfo>,
+}
+
+/// A public key of the notary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotaryKeyInfo {
+    /// Identifier of the key, as embedded in the session headers it signed
+    pub key_id: Option<String>,
+    /// Public key in PEM format
+    pub public_key: String,
+    /// Hex encoded compressed BLS12-381 public key which signs the session headers instead of the
+    /// public key above, if the notary signs them with BLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bls_public_key: Option<String>,
+    /// Whether the key is currently used to sign notarizations
+    pub current: bool,
+}
+
+/// Request query of the /transparency-log/entries API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntriesQuery {
+    /// Id of the tenant whose log is returned, the default tenant is used if not specified
+    pub tenant_id: Option<String>,
+    /// Index of the first entry to return, defaults to 0
+    pub start: Option<usize>,
+    /// Maximum number of entries to return, defaults to and is capped at 1000
+    pub limit: Option<usize>,
+}
+
+/// Request query of the /transparency-log/proof API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProofQuery {
+    /// Id of the tenant whose log is used, the default tenant is used if not specified
+    pub tenant_id: Option<String>,
+    /// Hex encoded hash of the session header
+    pub session: String,
+}
+
+/// Response object of the /transparency-log/proof API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProofResponse {
+    /// Proof that the session is included in the log
+    pub proof: InclusionProof,
+    /// Head of the log that the proof leads to, signed by the notary
+    pub head: SignedLogHead,
+}
+
+/// Request query of the /transparency-log/consistency API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyProofQuery {
+    /// Id of the tenant whose log is used, the default tenant is used if not specified
+    pub tenant_id: Option<String>,
+    /// Number of entries of the earlier head of the log
+    pub first: usize,
+}
+
+/// Response object of the /transparency-log/consistency API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyProofResponse {
+    /// Proof that the head extends the earlier head of the log
+    pub proof: ConsistencyProof,
+    /// Current head of the log, signed by the notary
+    pub head: SignedLogHead,
+}
@@ -1,5 +1,10 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 /// Structure of each whitelisted record of the API key whitelist for authorization purpose
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -20,3 +25,197 @@ pub fn authorization_whitelist_vec_into_hashmap(
     });
     hashmap
 }
+
+/// Hash an API key, so that it can be bound to a session without storing the key itself
+pub fn hash_api_key(api_key: &str) -> String {
+    hex::encode(Sha256::digest(api_key.as_bytes()))
+}
+
+/// Usage of a single API key, used to enforce per-key limits
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeyUsage {
+    /// Number of notarization sessions currently running with this API key
+    pub active_sessions: usize,
+    /// Amount of data (in bytes) declared by sessions started on `day`
+    pub data_used: usize,
+    /// The UTC day that `data_used` is tracked for
+    pub day: Option<NaiveDate>,
+}
+
+/// Limits enforced per API key, where `None` means no limit is enforced
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Maximum number of concurrent notarization sessions per API key
+    pub max_concurrent_sessions: Option<usize>,
+    /// Maximum amount of data (in bytes) that can be notarized per API key per UTC day
+    pub max_daily_data: Option<usize>,
+}
+
+/// Per API key rate limiter which enforces concurrency and daily data quotas
+///
+/// The limits are shared by the clones of the limiter, so that they can be hot reloaded
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeyRateLimiter {
+    limits: Arc<Mutex<RateLimits>>,
+    usage: Arc<Mutex<HashMap<String, ApiKeyUsage>>>,
+}
+
+impl ApiKeyRateLimiter {
+    /// Create a new rate limiter, where `None` means no limit is enforced
+    pub fn new(max_concurrent_sessions: Option<usize>, max_daily_data: Option<usize>) -> Self {
+        Self {
+            limits: Arc::new(Mutex::new(RateLimits {
+                max_concurrent_sessions,
+                max_daily_data,
+            })),
+            usage: Default::default(),
+        }
+    }
+
+    /// Returns the limits currently enforced
+    pub fn limits(&self) -> RateLimits {
+        *self.limits.lock().unwrap()
+    }
+
+    /// Replace the limits, which applies to the sessions started afterwards. The usage of the API
+    /// keys is kept
+    pub fn set_limits(&self, limits: RateLimits) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    /// Reserve a notarization session for an API key, returning a guard which releases the
+    /// session slot once dropped
+    pub fn acquire(
+        &self,
+        api_key: &str,
+        data_size: usize,
+        today: NaiveDate,
+    ) -> Result<ApiKeySessionGuard, RateLimitError> {
+        let limits = self.limits();
+        let mut usage = self.usage.lock().unwrap();
+        let key_usage = usage.entry(api_key.to_string()).or_default();
+
+        // Reset the data quota when a new day has started
+        if key_usage.day != Some(today) {
+            key_usage.day = Some(today);
+            key_usage.data_used = 0;
+        }
+
+        if let Some(max_concurrent_sessions) = limits.max_concurrent_sessions {
+            if key_usage.active_sessions >= max_concurrent_sessions {
+                return Err(RateLimitError::ConcurrencyLimitReached(
+                    max_concurrent_sessions,
+                ));
+            }
+        }
+
+        if let Some(max_daily_data) = limits.max_daily_data {
+            if key_usage.data_used.saturating_add(data_size) > max_daily_data {
+                return Err(RateLimitError::DailyDataQuotaExceeded {
+                    used: key_usage.data_used,
+                    requested: data_size,
+                    quota: max_daily_data,
+                });
+            }
+        }
+
+        key_usage.active_sessions += 1;
+        key_usage.data_used += data_size;
+
+        Ok(ApiKeySessionGuard {
+            api_key: api_key.to_string(),
+            usage: self.usage.clone(),
+        })
+    }
+
+    /// Returns the current usage of an API key
+    pub fn usage(&self, api_key: &str) -> Option<ApiKeyUsage> {
+        self.usage.lock().unwrap().get(api_key).cloned()
+    }
+}
+
+/// Errors returned when an API key exceeds its limits
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RateLimitError {
+    #[error("Maximum number of concurrent sessions ({0}) reached for API key")]
+    ConcurrencyLimitReached(usize),
+    #[error("Daily data quota exceeded for API key: used {used} + requested {requested} > quota {quota}")]
+    DailyDataQuotaExceeded {
+        used: usize,
+        requested: usize,
+        quota: usize,
+    },
+}
+
+/// Guard of a running notarization session, which releases the session slot of the API key when dropped
+#[derive(Debug)]
+pub struct ApiKeySessionGuard {
+    api_key: String,
+    usage: Arc<Mutex<HashMap<String, ApiKeyUsage>>>,
+}
+
+impl Drop for ApiKeySessionGuard {
+    fn drop(&mut self) {
+        if let Some(key_usage) = self.usage.lock().unwrap().get_mut(&self.api_key) {
+            key_usage.active_sessions = key_usage.active_sessions.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let limiter = ApiKeyRateLimiter::new(Some(1), None);
+
+        let guard = limiter.acquire("test-api-key-0", 10, today()).unwrap();
+        assert_eq!(
+            limiter.acquire("test-api-key-0", 10, today()).unwrap_err(),
+            RateLimitError::ConcurrencyLimitReached(1)
+        );
+        // Other keys are not affected
+        assert!(limiter.acquire("test-api-key-1", 10, today()).is_ok());
+
+        drop(guard);
+        assert!(limiter.acquire("test-api-key-0", 10, today()).is_ok());
+    }
+
+    #[test]
+    fn test_daily_data_quota() {
+        let limiter = ApiKeyRateLimiter::new(None, Some(100));
+
+        limiter.acquire("test-api-key-0", 60, today()).unwrap();
+        assert!(matches!(
+            limiter.acquire("test-api-key-0", 60, today()),
+            Err(RateLimitError::DailyDataQuotaExceeded { .. })
+        ));
+
+        // Quota is reset on the next day
+        let tomorrow = today().succ_opt().unwrap();
+        assert!(limiter.acquire("test-api-key-0", 60, tomorrow).is_ok());
+        assert_eq!(limiter.usage("test-api-key-0").unwrap().data_used, 60);
+    }
+
+    #[test]
+    fn test_set_limits() {
+        let limiter = ApiKeyRateLimiter::new(Some(1), None);
+        let _guard = limiter.acquire("test-api-key-0", 10, today()).unwrap();
+
+        // Clones share the limits, and the usage is kept when the limits are replaced
+        limiter.clone().set_limits(RateLimits {
+            max_concurrent_sessions: Some(2),
+            max_daily_data: None,
+        });
+        let _second_guard = limiter.acquire("test-api-key-0", 10, today()).unwrap();
+        assert_eq!(
+            limiter.acquire("test-api-key-0", 10, today()).unwrap_err(),
+            RateLimitError::ConcurrencyLimitReached(2)
+        );
+    }
+}
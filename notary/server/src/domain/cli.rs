@@ -4,7 +4,7 @@ use structopt::StructOpt;
 #[derive(Clone, Debug, StructOpt)]
 #[structopt(name = "Notary Server")]
 pub struct CliFields {
-    /// Configuration file location
+    /// Configuration file location, in yaml format or in toml format if its extension is `.toml`
     #[structopt(long, default_value = "./config/config.yaml")]
     pub config_file: String,
 }
@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use std::{fmt::Debug, sync::Arc};
+use tracing::warn;
+
+/// Errors returned by a [`SessionGate`] to reject a notarization session
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SessionGateError {
+    #[error("Payment required: {0}")]
+    PaymentRequired(String),
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Session rejected: {0}")]
+    Rejected(String),
+}
+
+/// A notarization session as seen by a [`SessionGate`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GatedSession {
+    /// Unique session id that is generated by notary and shared to prover
+    pub session_id: String,
+    /// Id of the tenant that the session was initialized with, none for the default tenant
+    pub tenant_id: Option<String>,
+    /// API key used to initialize the session, if authorization is enabled
+    pub api_key: Option<String>,
+    /// DNS name of the server declared by the prover, which is not authenticated by the notary
+    pub server_dns: Option<String>,
+    /// Maximum data that can be sent by the prover
+    pub max_sent_data: Option<usize>,
+    /// Maximum data that can be received by the prover
+    pub max_recv_data: Option<usize>,
+}
+
+/// Number of bytes of the transcript notarized in a session
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionUsage {
+    pub sent_len: usize,
+    pub recv_len: usize,
+}
+
+/// A hook invoked at the creation and finalization of notarization sessions, so that operators can
+/// plug in billing, quotas or payment checks without forking the server
+///
+/// The gate is shared by all tenants, which can be told apart with [`GatedSession::tenant_id`]
+#[async_trait]
+pub trait SessionGate: Debug + Send + Sync {
+    /// Called when a prover initializes a session with the /session API, before the session id is
+    /// returned. Returning an error rejects the session
+    async fn on_session_created(&self, session: &GatedSession) -> Result<(), SessionGateError>;
+
+    /// Called once for each session accepted by [`SessionGate::on_session_created`], when
+    /// the notarization of the session is finished, with the transcript byte counts if the
+    /// notarization succeeded, or none if it failed. Sessions which are rejected when the prover
+    /// calls /notarize, or which expire without being used, are reported as failed
+    async fn on_session_finalized(&self, session: &GatedSession, usage: Option<SessionUsage>);
+}
+
+/// Guard of a session accepted by a [`SessionGate`], which makes sure that the gate is told when
+/// the session ends, so that it can release what it holds for the session, e.g. a quota slot
+///
+/// The session is finalized with its usage by [`GatedSessionGuard::finalize`]. If the guard is
+/// dropped before, e.g. because the session was rejected when the connection was upgraded, or the
+/// notarization task was cancelled, the session is reported as failed
+#[derive(Debug)]
+pub struct GatedSessionGuard {
+    session_gate: Arc<dyn SessionGate>,
+    session: Option<GatedSession>,
+}
+
+impl GatedSessionGuard {
+    pub fn new(session_gate: Arc<dyn SessionGate>, session: GatedSession) -> Self {
+        Self {
+            session_gate,
+            session: Some(session),
+        }
+    }
+
+    /// Report the end of the session to the gate, with the transcript byte counts if the
+    /// notarization succeeded, or none if it failed
+    pub async fn finalize(mut self, usage: Option<SessionUsage>) {
+        if let Some(session) = self.session.take() {
+            self.session_gate
+                .on_session_finalized(&session, usage)
+                .await;
+        }
+    }
+
+    /// Drop the guard without reporting the end of the session, as it is reported by another
+    /// guard, e.g. the one of the notary instance which notarizes the session
+    pub fn disarm(mut self) {
+        self.session = None;
+    }
+}
+
+impl Drop for GatedSessionGuard {
+    fn drop(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        let session_gate = self.session_gate.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    session_gate.on_session_finalized(&session, None).await;
+                });
+            }
+            Err(_) => warn!(
+                session_id = session.session_id,
+                "Session could not be reported as failed to the session gate, as the runtime is not running"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Session gate recording the finalized sessions
+    #[derive(Debug, Default)]
+    pub(crate) struct RecordingGate {
+        pub(crate) finalized: Mutex<Vec<(String, Option<SessionUsage>)>>,
+    }
+
+    #[async_trait]
+    impl SessionGate for RecordingGate {
+        async fn on_session_created(
+            &self,
+            _session: &GatedSession,
+        ) -> Result<(), SessionGateError> {
+            Ok(())
+        }
+
+        async fn on_session_finalized(&self, session: &GatedSession, usage: Option<SessionUsage>) {
+            self.finalized
+                .lock()
+                .unwrap()
+                .push((session.session_id.clone(), usage));
+        }
+    }
+
+    pub(crate) fn gated_session(session_id: &str) -> GatedSession {
+        GatedSession {
+            session_id: session_id.to_string(),
+            tenant_id: None,
+            api_key: None,
+            server_dns: None,
+            max_sent_data: None,
+            max_recv_data: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gated_session_guard() {
+        let gate = Arc::new(RecordingGate::default());
+        let usage = SessionUsage {
+            sent_len: 1,
+            recv_len: 2,
+        };
+
+        // A finalized session is only reported once
+        GatedSessionGuard::new(gate.clone(), gated_session("finalized"))
+            .finalize(Some(usage))
+            .await;
+        // A disarmed session is reported by another guard
+        GatedSessionGuard::new(gate.clone(), gated_session("disarmed")).disarm();
+        // A dropped session is reported as failed
+        drop(GatedSessionGuard::new(
+            gate.clone(),
+            gated_session("dropped"),
+        ));
+        tokio::task::yield_now().await;
+
+        assert_eq!(
+            *gate.finalized.lock().unwrap(),
+            vec![
+                ("finalized".to_string(), Some(usage)),
+                ("dropped".to_string(), None),
+            ]
+        );
+    }
+}
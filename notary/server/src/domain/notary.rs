@@ -1,11 +1,30 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use chrono::{DateTime, Utc};
 use p256::ecdsa::SigningKey;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
+#[cfg(feature = "bls")]
+use tlsn_core::bls::BlsSigningKey;
+use tlsn_core::{
+    msg::{SignedAbortReceipt, SignedSessionOpenedReceipt},
+    revocation::{RevocationList, SignedRevocationList},
+};
+use tlsn_verifier::tls::time::TimeSource;
 
-use crate::{config::NotarizationProperties, domain::auth::AuthorizationWhitelistRecord};
+use crate::{
+    config::{NotarizationProperties, WebSocketProperties, DEFAULT_SESSION_TTL_SECS},
+    domain::{
+        auth::{ApiKeyRateLimiter, AuthorizationWhitelistRecord},
+        gate::SessionGate,
+        policy::DomainPolicy,
+    },
+    error::NotaryServerError,
+    metrics::NotaryMetrics,
+    shutdown::ShutdownController,
+    store::{MemorySessionStore, SessionStore},
+    transparency::TransparencyLog,
+};
 
 /// Response object of the /session API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +43,10 @@ pub struct NotarizationSessionRequest {
     pub max_sent_data: Option<usize>,
     /// Maximum data that can be received by the prover
     pub max_recv_data: Option<usize>,
+    /// DNS name of the server that the prover will notarize, required if domain policy is enabled.
+    /// Note that this is declared by the prover and is not authenticated by the notary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_dns: Option<String>,
 }
 
 /// Request query of the /notarize API
@@ -32,6 +55,16 @@ pub struct NotarizationSessionRequest {
 pub struct NotarizationRequestQuery {
     /// Session id that is returned from /session API
     pub session_id: String,
+    /// Id of the tenant that the session was initialized with
+    pub tenant_id: Option<String>,
+}
+
+/// Request query of the /session, /info and /keys APIs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantQuery {
+    /// Id of the tenant to use, the default tenant is used if not specified
+    pub tenant_id: Option<String>,
 }
 
 /// Types of client that the prover is using
@@ -44,22 +77,67 @@ pub enum ClientType {
 }
 
 /// Session configuration data to be stored in temporary storage
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SessionData {
     pub max_sent_data: Option<usize>,
     pub max_recv_data: Option<usize>,
     pub created_at: DateTime<Utc>,
+    /// Hex encoded SHA-256 hash of the API key used to initialize the session, if authorization is
+    /// enabled. The key itself is not stored, as the session store may be shared
+    pub api_key_hash: Option<String>,
+    /// DNS name of the server declared by the prover
+    pub server_dns: Option<String>,
+}
+
+/// Receipt signed by the notary for a session, kept as a record of the sessions it opened and
+/// aborted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionReceipt {
+    Opened(SignedSessionOpenedReceipt),
+    Aborted(SignedAbortReceipt),
 }
 
 /// Global data that needs to be shared with the axum handlers
 #[derive(Clone, Debug)]
 pub struct NotaryGlobals {
     pub notary_signing_key: SigningKey,
+    /// BLS12-381 key signing the session headers instead of the signing key, if it is configured
+    #[cfg(feature = "bls")]
+    pub bls_signing_key: Option<Arc<BlsSigningKey>>,
+    /// Identifier of the signing key, embedded in the signed session header
+    pub notary_key_id: Option<String>,
+    /// DID of the notary, embedded in the signed session header
+    pub notary_did: Option<String>,
     pub notarization_config: NotarizationProperties,
     /// A temporary storage to store configuration data, mainly used for WebSocket client
-    pub store: Arc<Mutex<HashMap<String, SessionData>>>,
+    pub store: Arc<dyn SessionStore>,
+    /// Time after which sessions that were not used to notarize expire
+    pub session_ttl: Duration,
     /// Whitelist of API keys for authorization purpose
     pub authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
+    /// Rate limiter enforcing per API key concurrency and daily data quotas
+    pub rate_limiter: ApiKeyRateLimiter,
+    /// Policy restricting which server DNS names can be notarized, which can be hot reloaded
+    pub domain_policy: Arc<Mutex<DomainPolicy>>,
+    /// Prometheus metrics, shared by all tenants
+    pub metrics: NotaryMetrics,
+    /// Source of the timestamp embedded in the session header, shared by all tenants
+    pub time_source: Option<Arc<dyn TimeSource>>,
+    /// Hook invoked at the creation and finalization of sessions, shared by all tenants
+    pub session_gate: Option<Arc<dyn SessionGate>>,
+    /// Controller of the graceful shutdown of the server, shared by all tenants
+    pub shutdown: ShutdownController,
+    /// Setting for the /notarize/ws endpoint, shared by all tenants
+    pub websocket_config: WebSocketProperties,
+    /// Revocation list of the notarizations, signed with the signing key, which is rebuilt when the
+    /// config is reloaded
+    pub revocation_list: Arc<Mutex<SignedRevocationList>>,
+    /// Log of the session headers signed with the signing key, if it is enabled
+    pub transparency_log: Option<Arc<TransparencyLog>>,
+    /// Globals of the additional tenants hosted by this notary, keyed by tenant id.
+    /// The globals above belong to the default tenant, and the tenants' own maps are empty
+    pub tenants: Arc<HashMap<String, NotaryGlobals>>,
 }
 
 impl NotaryGlobals {
@@ -67,12 +145,169 @@ impl NotaryGlobals {
         notary_signing_key: SigningKey,
         notarization_config: NotarizationProperties,
         authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
+        rate_limiter: ApiKeyRateLimiter,
+        domain_policy: DomainPolicy,
+        metrics: NotaryMetrics,
     ) -> Self {
+        let revocation_list = RevocationList::new(Utc::now().timestamp() as u64)
+            .sign::<p256::ecdsa::Signature>(&notary_signing_key);
         Self {
             notary_signing_key,
+            #[cfg(feature = "bls")]
+            bls_signing_key: None,
+            notary_key_id: None,
+            notary_did: None,
             notarization_config,
-            store: Default::default(),
+            store: Arc::new(MemorySessionStore::default()),
+            session_ttl: Duration::from_secs(DEFAULT_SESSION_TTL_SECS),
             authorization_whitelist,
+            rate_limiter,
+            domain_policy: Arc::new(Mutex::new(domain_policy)),
+            metrics,
+            time_source: None,
+            session_gate: None,
+            shutdown: ShutdownController::default(),
+            websocket_config: WebSocketProperties::default(),
+            revocation_list: Arc::new(Mutex::new(revocation_list)),
+            transparency_log: None,
+            tenants: Default::default(),
+        }
+    }
+
+    /// Set the identifier of the signing key embedded in the session header
+    pub fn with_key_id(mut self, key_id: Option<String>) -> Self {
+        self.notary_key_id = key_id;
+        self
+    }
+
+    /// Set the BLS12-381 key signing the session headers
+    #[cfg(feature = "bls")]
+    pub fn with_bls_signing_key(mut self, bls_signing_key: Option<BlsSigningKey>) -> Self {
+        self.bls_signing_key = bls_signing_key.map(Arc::new);
+        self
+    }
+
+    /// Set the DID of the notary embedded in the session header
+    pub fn with_did(mut self, did: Option<String>) -> Self {
+        self.notary_did = did;
+        self
+    }
+
+    /// Set the storage of the configuration data of sessions
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Set the time after which sessions that were not used to notarize expire
+    pub fn with_session_ttl(mut self, session_ttl: Duration) -> Self {
+        self.session_ttl = session_ttl;
+        self
+    }
+
+    /// Set the source of the timestamp embedded in the session header
+    pub fn with_time_source(mut self, time_source: Option<Arc<dyn TimeSource>>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Set the hook invoked at the creation and finalization of sessions
+    pub fn with_session_gate(mut self, session_gate: Option<Arc<dyn SessionGate>>) -> Self {
+        self.session_gate = session_gate;
+        self
+    }
+
+    /// Set the controller of the graceful shutdown of the server
+    pub fn with_shutdown(mut self, shutdown: ShutdownController) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Set the setting for the /notarize/ws endpoint
+    pub fn with_websocket_config(mut self, websocket_config: WebSocketProperties) -> Self {
+        self.websocket_config = websocket_config;
+        self
+    }
+
+    /// Set the revocation list of the notarizations
+    pub fn with_revocation_list(mut self, revocation_list: SignedRevocationList) -> Self {
+        self.revocation_list = Arc::new(Mutex::new(revocation_list));
+        self
+    }
+
+    /// Set the log of the session headers signed with the signing key
+    pub fn with_transparency_log(mut self, transparency_log: Option<Arc<TransparencyLog>>) -> Self {
+        self.transparency_log = transparency_log;
+        self
+    }
+
+    /// Set the additional tenants hosted by this notary
+    pub fn with_tenants(mut self, tenants: HashMap<String, NotaryGlobals>) -> Self {
+        self.tenants = Arc::new(tenants);
+        self
+    }
+
+    /// Get the globals of a tenant, or of the default tenant if no tenant id is specified
+    pub fn tenant(&self, tenant_id: Option<&str>) -> Result<NotaryGlobals, NotaryServerError> {
+        match tenant_id {
+            None => Ok(self.clone()),
+            Some(tenant_id) => self.tenants.get(tenant_id).cloned().ok_or_else(|| {
+                NotaryServerError::BadProverRequest(format!("Unknown tenant id {tenant_id}"))
+            }),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_notary_globals_fixture(key: u8) -> NotaryGlobals {
+        NotaryGlobals::new(
+            SigningKey::from_bytes(&[key; 32].into()).unwrap(),
+            NotarizationProperties {
+                max_transcript_size: 1 << 14,
+                max_sent_data: None,
+                max_recv_data: None,
+                mpc_concurrency: None,
+                max_records: None,
+                max_ots: None,
+                max_session_duration_secs: None,
+                compression: false,
+            },
+            None,
+            ApiKeyRateLimiter::new(None, None),
+            DomainPolicy::default(),
+            NotaryMetrics::new().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_tenant() {
+        let notary_globals = get_notary_globals_fixture(1).with_tenants(HashMap::from([(
+            "tenant-a".to_string(),
+            get_notary_globals_fixture(2),
+        )]));
+
+        let default_tenant = notary_globals.tenant(None).unwrap();
+        assert_eq!(
+            default_tenant.notary_signing_key,
+            notary_globals.notary_signing_key
+        );
+
+        let tenant = notary_globals.tenant(Some("tenant-a")).unwrap();
+        assert_eq!(
+            tenant.notary_signing_key,
+            get_notary_globals_fixture(2).notary_signing_key
+        );
+        assert!(tenant.tenants.is_empty());
+
+        // Sessions of each tenant are stored in separate namespaces
+        assert!(!Arc::ptr_eq(&tenant.store, &default_tenant.store));
+
+        assert!(matches!(
+            notary_globals.tenant(Some("tenant-b")),
+            Err(NotaryServerError::BadProverRequest(_))
+        ));
+    }
+}
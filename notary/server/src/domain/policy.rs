@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::{DomainLimitProperties, PolicyProperties};
+
+/// Errors returned when a notarization session violates the notary's domain policy
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PolicyError {
+    #[error("Server DNS name must be declared when domain policy is enabled")]
+    MissingServerDns,
+    #[error("Server DNS name {0} is denied by the notary")]
+    Denied(String),
+    #[error("Server DNS name {0} is not in the notary's allowlist")]
+    NotAllowed(String),
+    #[error("Maximum {direction} data must be declared for server DNS name {server_dns}")]
+    UndeclaredDataLimit {
+        server_dns: String,
+        direction: DataDirection,
+    },
+    #[error("Maximum {direction} data requested {requested} exceeds the limit {limit} for server DNS name {server_dns}")]
+    DataLimitExceeded {
+        server_dns: String,
+        direction: DataDirection,
+        requested: usize,
+        limit: usize,
+    },
+}
+
+/// Direction of the data limited by the domain policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataDirection {
+    Sent,
+    Received,
+}
+
+impl std::fmt::Display for DataDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataDirection::Sent => write!(f, "sent"),
+            DataDirection::Received => write!(f, "received"),
+        }
+    }
+}
+
+/// A server DNS name pattern, where a leading `*.` matches any subdomain, and `*` matches any name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainPattern(String);
+
+impl DomainPattern {
+    /// Create a new pattern, which is matched case-insensitively
+    pub fn new(pattern: &str) -> Self {
+        Self(normalize_domain(pattern))
+    }
+
+    /// Check if a normalized server DNS name matches this pattern
+    pub fn matches(&self, server_dns: &str) -> bool {
+        if self.0 == "*" {
+            return true;
+        }
+        match self.0.strip_prefix("*.") {
+            // Wildcard only matches subdomains, not the parent domain itself
+            Some(suffix) => server_dns
+                .strip_suffix(suffix)
+                .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+            None => self.0 == server_dns,
+        }
+    }
+}
+
+/// Domain policy evaluated when a prover initializes a notarization session
+#[derive(Clone, Debug, Default)]
+pub struct DomainPolicy {
+    enabled: bool,
+    allowlist: Vec<DomainPattern>,
+    denylist: Vec<DomainPattern>,
+    limits: Vec<(DomainPattern, DomainLimitProperties)>,
+}
+
+impl DomainPolicy {
+    /// Create a new domain policy from the notary server config
+    pub fn new(config: &PolicyProperties) -> Self {
+        Self {
+            enabled: config.enabled,
+            allowlist: config
+                .allowlist
+                .iter()
+                .map(|pattern| DomainPattern::new(pattern))
+                .collect(),
+            denylist: config
+                .denylist
+                .iter()
+                .map(|pattern| DomainPattern::new(pattern))
+                .collect(),
+            limits: config
+                .domain_limits
+                .iter()
+                .map(|limit| (DomainPattern::new(&limit.pattern), limit.clone()))
+                .collect(),
+        }
+    }
+
+    /// Evaluate the policy against the configuration submitted by the prover
+    ///
+    /// Denylist takes precedence over allowlist; an empty allowlist allows any name that is not denied.
+    /// Size limits are taken from the first entry in `domain_limits` whose pattern matches
+    pub fn evaluate(
+        &self,
+        server_dns: Option<&str>,
+        max_sent_data: Option<usize>,
+        max_recv_data: Option<usize>,
+    ) -> Result<(), PolicyError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let server_dns = normalize_domain(server_dns.ok_or(PolicyError::MissingServerDns)?);
+
+        if self
+            .denylist
+            .iter()
+            .any(|pattern| pattern.matches(&server_dns))
+        {
+            return Err(PolicyError::Denied(server_dns));
+        }
+
+        if !self.allowlist.is_empty()
+            && !self
+                .allowlist
+                .iter()
+                .any(|pattern| pattern.matches(&server_dns))
+        {
+            return Err(PolicyError::NotAllowed(server_dns));
+        }
+
+        if let Some((_, limit)) = self
+            .limits
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&server_dns))
+        {
+            check_limit(
+                &server_dns,
+                DataDirection::Sent,
+                max_sent_data,
+                limit.max_sent_data,
+            )?;
+            check_limit(
+                &server_dns,
+                DataDirection::Received,
+                max_recv_data,
+                limit.max_recv_data,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn check_limit(
+    server_dns: &str,
+    direction: DataDirection,
+    requested: Option<usize>,
+    limit: Option<usize>,
+) -> Result<(), PolicyError> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+    // The prover would otherwise fall back to its default limit which the notary can't check
+    let requested = requested.ok_or_else(|| PolicyError::UndeclaredDataLimit {
+        server_dns: server_dns.to_string(),
+        direction,
+    })?;
+    if requested > limit {
+        return Err(PolicyError::DataLimitExceeded {
+            server_dns: server_dns.to_string(),
+            direction,
+            requested,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// DNS names are case insensitive, and may be written with a trailing dot
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn get_policy_fixture() -> DomainPolicy {
+        DomainPolicy::new(&PolicyProperties {
+            enabled: true,
+            allowlist: vec!["*.example.com".to_string(), "tlsnotary.org".to_string()],
+            denylist: vec!["secret.example.com".to_string()],
+            domain_limits: vec![DomainLimitProperties {
+                pattern: "*.example.com".to_string(),
+                max_sent_data: Some(1024),
+                max_recv_data: None,
+            }],
+        })
+    }
+
+    #[test]
+    fn test_domain_pattern() {
+        let pattern = DomainPattern::new("*.Example.com.");
+        assert!(pattern.matches("api.example.com"));
+        assert!(pattern.matches("a.b.example.com"));
+        assert!(!pattern.matches("example.com"));
+        assert!(!pattern.matches("badexample.com"));
+
+        assert!(DomainPattern::new("*").matches("anything.org"));
+        assert!(DomainPattern::new("tlsnotary.org").matches("tlsnotary.org"));
+        assert!(!DomainPattern::new("tlsnotary.org").matches("docs.tlsnotary.org"));
+    }
+
+    #[test]
+    fn test_policy_allow_and_deny() {
+        let policy = get_policy_fixture();
+
+        assert!(policy
+            .evaluate(Some("TLSNotary.org"), Some(4096), Some(4096))
+            .is_ok());
+        assert_eq!(
+            policy.evaluate(Some("secret.example.com"), Some(1024), None),
+            Err(PolicyError::Denied("secret.example.com".to_string()))
+        );
+        assert_eq!(
+            policy.evaluate(Some("other.org"), None, None),
+            Err(PolicyError::NotAllowed("other.org".to_string()))
+        );
+        assert_eq!(
+            policy.evaluate(None, None, None),
+            Err(PolicyError::MissingServerDns)
+        );
+    }
+
+    #[test]
+    fn test_policy_domain_limits() {
+        let policy = get_policy_fixture();
+
+        assert!(policy
+            .evaluate(Some("api.example.com"), Some(1024), Some(1 << 20))
+            .is_ok());
+        assert!(matches!(
+            policy.evaluate(Some("api.example.com"), Some(1025), None),
+            Err(PolicyError::DataLimitExceeded {
+                direction: DataDirection::Sent,
+                ..
+            })
+        ));
+        assert!(matches!(
+            policy.evaluate(Some("api.example.com"), None, None),
+            Err(PolicyError::UndeclaredDataLimit {
+                direction: DataDirection::Sent,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_policy_disabled() {
+        let policy = DomainPolicy::default();
+        assert!(policy.evaluate(None, None, None).is_ok());
+    }
+}
@@ -1,9 +1,13 @@
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum_core::response::{IntoResponse as AxumCoreIntoResponse, Response};
 use eyre::Report;
 use std::error::Error;
 
-use tlsn_verifier::tls::{VerifierConfigBuilderError, VerifierError};
+use tlsn_verifier::tls::{
+    ClassifiedError, ErrorCategory, VerifierConfigBuilderError, VerifierError,
+};
+
+use crate::domain::{auth::RateLimitError, gate::SessionGateError, policy::PolicyError};
 
 #[derive(Debug, thiserror::Error)]
 pub enum NotaryServerError {
@@ -17,6 +21,24 @@ pub enum NotaryServerError {
     BadProverRequest(String),
     #[error("Unauthorized request from prover: {0}")]
     UnauthorizedProverRequest(String),
+    #[error("Too many requests from prover: {0}")]
+    RateLimitedProverRequest(String),
+    #[error("Request from prover violates notary policy: {0}")]
+    PolicyViolation(#[from] PolicyError),
+    #[error("Request from prover rejected by session gate: {0}")]
+    SessionGate(#[from] SessionGateError),
+    #[error("Notary server is shutting down")]
+    ShuttingDown,
+    #[error("Origin {0} is not allowed to notarize")]
+    ForbiddenOrigin(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+}
+
+impl From<RateLimitError> for NotaryServerError {
+    fn from(error: RateLimitError) -> Self {
+        Self::RateLimitedProverRequest(error.to_string())
+    }
 }
 
 impl From<VerifierError> for NotaryServerError {
@@ -31,10 +53,82 @@ impl From<VerifierConfigBuilderError> for NotaryServerError {
     }
 }
 
+/// Header of the error responses carrying the stable code of the error
+pub const ERROR_CODE_HEADER: &str = "x-tlsn-error-code";
+/// Header of the error responses carrying the category of the error
+pub const ERROR_CATEGORY_HEADER: &str = "x-tlsn-error-category";
+
+impl NotaryServerError {
+    fn verifier_error(&self) -> Option<&VerifierError> {
+        match self {
+            NotaryServerError::Notarization(error) => error.downcast_ref::<VerifierError>(),
+            _ => None,
+        }
+    }
+}
+
+impl ClassifiedError for NotaryServerError {
+    fn category(&self) -> ErrorCategory {
+        if let Some(error) = self.verifier_error() {
+            return error.category();
+        }
+        match self {
+            NotaryServerError::Unexpected(_) | NotaryServerError::Notarization(_) => {
+                ErrorCategory::Internal
+            }
+            NotaryServerError::Connection(_) | NotaryServerError::ShuttingDown => {
+                ErrorCategory::NetworkTransient
+            }
+            NotaryServerError::BadProverRequest(_) | NotaryServerError::NotFound(_) => {
+                ErrorCategory::InvalidInput
+            }
+            NotaryServerError::RateLimitedProverRequest(_)
+            | NotaryServerError::SessionGate(SessionGateError::QuotaExceeded(_)) => {
+                ErrorCategory::ResourceLimit
+            }
+            NotaryServerError::UnauthorizedProverRequest(_)
+            | NotaryServerError::PolicyViolation(_)
+            | NotaryServerError::SessionGate(_)
+            | NotaryServerError::ForbiddenOrigin(_) => ErrorCategory::PolicyRejected,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        if let Some(error) = self.verifier_error() {
+            return error.code();
+        }
+        match self {
+            NotaryServerError::Unexpected(_) => "notary.unexpected",
+            NotaryServerError::Connection(_) => "notary.connection",
+            NotaryServerError::Notarization(_) => "notary.notarization",
+            NotaryServerError::BadProverRequest(_) => "notary.bad_request",
+            NotaryServerError::UnauthorizedProverRequest(_) => "notary.unauthorized",
+            NotaryServerError::RateLimitedProverRequest(_) => "notary.rate_limited",
+            NotaryServerError::PolicyViolation(_) => "notary.policy_violation",
+            NotaryServerError::SessionGate(SessionGateError::PaymentRequired(_)) => {
+                "notary.payment_required"
+            }
+            NotaryServerError::SessionGate(SessionGateError::QuotaExceeded(_)) => {
+                "notary.quota_exceeded"
+            }
+            NotaryServerError::SessionGate(SessionGateError::Rejected(_)) => {
+                "notary.session_rejected"
+            }
+            NotaryServerError::ShuttingDown => "notary.shutting_down",
+            NotaryServerError::ForbiddenOrigin(_) => "notary.forbidden_origin",
+            NotaryServerError::NotFound(_) => "notary.not_found",
+        }
+    }
+}
+
 /// Trait implementation to convert this error into an axum http response
 impl AxumCoreIntoResponse for NotaryServerError {
     fn into_response(self) -> Response {
-        match self {
+        let headers = [
+            (ERROR_CODE_HEADER, self.code()),
+            (ERROR_CATEGORY_HEADER, self.category().as_str()),
+        ];
+        let mut response = match self {
             bad_request_error @ NotaryServerError::BadProverRequest(_) => {
                 (StatusCode::BAD_REQUEST, bad_request_error.to_string()).into_response()
             }
@@ -43,11 +137,65 @@ impl AxumCoreIntoResponse for NotaryServerError {
                 unauthorized_request_error.to_string(),
             )
                 .into_response(),
+            rate_limited_request_error @ NotaryServerError::RateLimitedProverRequest(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                rate_limited_request_error.to_string(),
+            )
+                .into_response(),
+            forbidden_origin_error @ NotaryServerError::ForbiddenOrigin(_) => {
+                (StatusCode::FORBIDDEN, forbidden_origin_error.to_string()).into_response()
+            }
+            not_found_error @ NotaryServerError::NotFound(_) => {
+                (StatusCode::NOT_FOUND, not_found_error.to_string()).into_response()
+            }
+            policy_violation_error @ NotaryServerError::PolicyViolation(_) => {
+                (StatusCode::FORBIDDEN, policy_violation_error.to_string()).into_response()
+            }
+            NotaryServerError::SessionGate(error) => {
+                let status = match &error {
+                    SessionGateError::PaymentRequired(_) => StatusCode::PAYMENT_REQUIRED,
+                    SessionGateError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+                    SessionGateError::Rejected(_) => StatusCode::FORBIDDEN,
+                };
+                (status, NotaryServerError::SessionGate(error).to_string()).into_response()
+            }
+            shutting_down_error @ NotaryServerError::ShuttingDown => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                shutting_down_error.to_string(),
+            )
+                .into_response(),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Something wrong happened.",
             )
                 .into_response(),
+        };
+        for (name, value) in headers {
+            response
+                .headers_mut()
+                .insert(name, HeaderValue::from_static(value));
         }
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_classification() {
+        let error = NotaryServerError::from(VerifierError::InvalidRange);
+        assert_eq!(error.code(), "verifier.invalid_range");
+        assert_eq!(error.category(), ErrorCategory::ProtocolViolation);
+
+        let error = NotaryServerError::ShuttingDown;
+        assert!(error.is_retryable());
+
+        let response =
+            NotaryServerError::RateLimitedProverRequest("limit".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[ERROR_CODE_HEADER], "notary.rate_limited");
+        assert_eq!(response.headers()[ERROR_CATEGORY_HEADER], "resource_limit");
     }
 }
@@ -1,21 +1,38 @@
+mod acme;
 mod config;
 mod domain;
 mod error;
+mod metrics;
 mod middleware;
 mod server;
 mod server_tracing;
 mod service;
+mod shutdown;
+mod store;
+mod time;
+mod transparency;
 mod util;
 
 pub use config::{
-    AuthorizationProperties, LoggingProperties, NotarizationProperties, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    AcmeProperties, AuthorizationProperties, DomainLimitProperties, LoggingProperties,
+    MetricsProperties, NotarizationProperties, NotaryServerProperties, NotarySigningKeyProperties,
+    PolicyProperties, ServerProperties, SessionStoreKind, SessionStoreProperties, TLSProperties,
+    TelemetryProperties, TenantProperties, TimestampProperties, TimestampSourceKind,
+    TransparencyLogProperties, WebSocketProperties,
 };
 pub use domain::{
     cli::CliFields,
-    notary::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse},
+    gate::{GatedSession, SessionGate, SessionGateError, SessionUsage},
+    notary::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse, SessionData},
+    policy::{DataDirection, PolicyError},
 };
-pub use error::NotaryServerError;
-pub use server::{read_pem_file, run_server};
-pub use server_tracing::init_tracing;
+pub use error::{NotaryServerError, ERROR_CATEGORY_HEADER, ERROR_CODE_HEADER};
+pub use server::{
+    read_pem_file, run_server, run_server_with_config_reload, run_server_with_session_gate,
+    run_server_with_shutdown,
+};
+pub use server_tracing::{init_tracing, shutdown_tracing};
+pub use shutdown::{ShutdownController, DEFAULT_SHUTDOWN_TIMEOUT_SECS};
+pub use store::{MemorySessionStore, SessionStore};
+pub use transparency::TransparencyLog;
 pub use util::parse_config_file;
@@ -3,8 +3,8 @@ use structopt::StructOpt;
 use tracing::debug;
 
 use notary_server::{
-    init_tracing, parse_config_file, run_server, CliFields, NotaryServerError,
-    NotaryServerProperties,
+    init_tracing, parse_config_file, run_server_with_config_reload, shutdown_tracing, CliFields,
+    NotaryServerError, NotaryServerProperties,
 };
 
 #[tokio::main]
@@ -18,8 +18,11 @@ async fn main() -> Result<(), NotaryServerError> {
 
     debug!(?config, "Server config loaded");
 
-    // Run the server
-    run_server(&config).await?;
+    // Run the server, applying changes of the policies and rate limits in the config file live
+    let result = run_server_with_config_reload(&config, &cli_fields.config_file).await;
 
-    Ok(())
+    // Flush the traces which have not been exported yet
+    shutdown_tracing();
+
+    result
 }
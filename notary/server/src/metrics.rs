@@ -0,0 +1,322 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use eyre::{eyre, Result};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use tlsn_verifier::tls::VerifierError;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Buckets of the session duration histogram in seconds, notarizations usually take tens of seconds
+const SESSION_DURATION_BUCKETS: &[f64] = &[1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0];
+
+/// Prometheus metrics of the notary server, cheap to clone as all metrics are reference counted
+#[derive(Clone)]
+pub struct NotaryMetrics {
+    registry: Registry,
+    /// Number of notarizations currently running
+    active_sessions: IntGauge,
+    /// Number of finished notarizations by outcome
+    sessions: IntCounterVec,
+    /// Number of failed notarizations by reason
+    session_failures: IntCounterVec,
+    /// Duration of notarizations, including failed ones
+    session_duration: Histogram,
+    /// Bytes exchanged with provers during the MPC protocol, i.e. garbled circuits, OT and commitments
+    mpc_bytes: IntCounterVec,
+    /// Bytes of transcripts notarized successfully
+    transcript_bytes: IntCounterVec,
+}
+
+impl NotaryMetrics {
+    /// Create and register all metrics in a new registry
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new();
+
+        let active_sessions = IntGauge::new(
+            "notary_active_sessions",
+            "Number of notarizations currently running",
+        )?;
+        let sessions = IntCounterVec::new(
+            Opts::new(
+                "notary_sessions_total",
+                "Number of finished notarizations by outcome",
+            ),
+            &["outcome"],
+        )?;
+        let session_failures = IntCounterVec::new(
+            Opts::new(
+                "notary_session_failures_total",
+                "Number of failed notarizations by reason",
+            ),
+            &["reason"],
+        )?;
+        let session_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "notary_session_duration_seconds",
+                "Duration of notarizations in seconds",
+            )
+            .buckets(SESSION_DURATION_BUCKETS.to_vec()),
+        )?;
+        let mpc_bytes = IntCounterVec::new(
+            Opts::new(
+                "notary_mpc_bytes_total",
+                "Bytes exchanged with provers during notarization",
+            ),
+            &["direction"],
+        )?;
+        let transcript_bytes = IntCounterVec::new(
+            Opts::new(
+                "notary_transcript_bytes_total",
+                "Bytes of TLS transcripts notarized successfully",
+            ),
+            &["direction"],
+        )?;
+
+        registry.register(Box::new(active_sessions.clone()))?;
+        registry.register(Box::new(sessions.clone()))?;
+        registry.register(Box::new(session_failures.clone()))?;
+        registry.register(Box::new(session_duration.clone()))?;
+        registry.register(Box::new(mpc_bytes.clone()))?;
+        registry.register(Box::new(transcript_bytes.clone()))?;
+
+        Ok(Self {
+            registry,
+            active_sessions,
+            sessions,
+            session_failures,
+            session_duration,
+            mpc_bytes,
+            transcript_bytes,
+        })
+    }
+
+    /// Record the start of a notarization, which is recorded as finished when the returned guard is dropped
+    pub fn start_session(&self) -> SessionMetricsGuard {
+        self.active_sessions.inc();
+        SessionMetricsGuard {
+            metrics: self.clone(),
+            timer: Some(self.session_duration.start_timer()),
+        }
+    }
+
+    /// Record a successful notarization of a transcript with the given lengths
+    pub fn record_success(&self, sent_len: usize, recv_len: usize) {
+        self.sessions.with_label_values(&["success"]).inc();
+        self.transcript_bytes
+            .with_label_values(&["sent"])
+            .inc_by(sent_len as u64);
+        self.transcript_bytes
+            .with_label_values(&["received"])
+            .inc_by(recv_len as u64);
+    }
+
+    /// Record a failed notarization
+    pub fn record_failure(&self, reason: FailureReason) {
+        self.sessions.with_label_values(&["failure"]).inc();
+        self.session_failures
+            .with_label_values(&[reason.as_str()])
+            .inc();
+    }
+
+    /// Wrap a prover connection to count the bytes exchanged over it
+    pub fn meter<T>(&self, inner: T) -> MeteredStream<T> {
+        MeteredStream {
+            inner,
+            sent: self.mpc_bytes.with_label_values(&["sent"]),
+            received: self.mpc_bytes.with_label_values(&["received"]),
+        }
+    }
+
+    /// Encode all metrics in the prometheus text format
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|err| eyre!("Failed to encode metrics: {err}"))?;
+        String::from_utf8(buffer).map_err(|err| eyre!("Failed to encode metrics: {err}"))
+    }
+}
+
+impl std::fmt::Debug for NotaryMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotaryMetrics").finish_non_exhaustive()
+    }
+}
+
+/// Reasons for a notarization to fail
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureReason {
+    /// Configuration submitted by the prover is invalid
+    Config,
+    /// Connection with the prover failed
+    Io,
+    /// Multiplexing of the connection with the prover failed
+    Muxer,
+    /// MPC protocol with the prover failed
+    Mpc,
+    /// Prover requested an invalid range of the transcript
+    InvalidRange,
+    /// Prover sent invalid plaintext hash commitments
+    InvalidPlaintextHashes,
+    /// Prover sent an invalid session binding
+    InvalidSessionBinding,
+    /// Prover sent record timings inconsistent with the notary's observation
+    InvalidRecordTimings,
+    /// Prover sent a record order inconsistent with the notary's observation
+    InvalidRecordOrder,
+    /// Timestamp of the session header could not be obtained
+    Timestamp,
+    /// Session header could not be recorded in the transparency log
+    SessionLog,
+    /// Session exceeded one of its resource quotas
+    QuotaExceeded,
+    /// Session was aborted by either party
+    Aborted,
+    /// Session was aborted because the notary server shut down before it completed
+    Shutdown,
+}
+
+impl FailureReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::Config => "config",
+            FailureReason::Io => "io",
+            FailureReason::Muxer => "muxer",
+            FailureReason::Mpc => "mpc",
+            FailureReason::InvalidRange => "invalid_range",
+            FailureReason::InvalidPlaintextHashes => "invalid_plaintext_hashes",
+            FailureReason::InvalidSessionBinding => "invalid_session_binding",
+            FailureReason::InvalidRecordTimings => "invalid_record_timings",
+            FailureReason::InvalidRecordOrder => "invalid_record_order",
+            FailureReason::Timestamp => "timestamp",
+            FailureReason::SessionLog => "session_log",
+            FailureReason::QuotaExceeded => "quota_exceeded",
+            FailureReason::Aborted => "aborted",
+            FailureReason::Shutdown => "shutdown",
+        }
+    }
+}
+
+impl From<&VerifierError> for FailureReason {
+    fn from(error: &VerifierError) -> Self {
+        match error {
+            VerifierError::IOError(_) => FailureReason::Io,
+            VerifierError::MuxerError(_) => FailureReason::Muxer,
+            VerifierError::MpcError(_) => FailureReason::Mpc,
+            VerifierError::InvalidRange => FailureReason::InvalidRange,
+            VerifierError::InvalidPlaintextHashes(_) => FailureReason::InvalidPlaintextHashes,
+            VerifierError::InvalidSessionBinding(_) => FailureReason::InvalidSessionBinding,
+            VerifierError::InvalidRecordTimings(_) => FailureReason::InvalidRecordTimings,
+            VerifierError::InvalidRecordOrder(_) => FailureReason::InvalidRecordOrder,
+            VerifierError::TimeSourceError(_) => FailureReason::Timestamp,
+            VerifierError::SessionLogError(_) => FailureReason::SessionLog,
+            VerifierError::QuotaExceeded(_) => FailureReason::QuotaExceeded,
+            VerifierError::Aborted(_) => FailureReason::Aborted,
+        }
+    }
+}
+
+/// Guard that records a notarization as finished when dropped
+pub struct SessionMetricsGuard {
+    metrics: NotaryMetrics,
+    timer: Option<prometheus::HistogramTimer>,
+}
+
+impl Drop for SessionMetricsGuard {
+    fn drop(&mut self) {
+        self.metrics.active_sessions.dec();
+        if let Some(timer) = self.timer.take() {
+            timer.observe_duration();
+        }
+    }
+}
+
+/// Connection wrapper counting the bytes sent to and received from the prover
+pub struct MeteredStream<T> {
+    inner: T,
+    sent: prometheus::IntCounter,
+    received: prometheus::IntCounter,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for MeteredStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.received.inc_by((buf.filled().len() - filled) as u64);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for MeteredStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.sent.inc_by(n as u64);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metered_stream() {
+        let metrics = NotaryMetrics::new().unwrap();
+        let (client, server) = tokio::io::duplex(64);
+        let mut server = metrics.meter(server);
+        let mut client = client;
+
+        server.write_all(b"hello").await.unwrap();
+        client.write_all(b"hi").await.unwrap();
+        let mut buf = [0u8; 2];
+        server.read_exact(&mut buf).await.unwrap();
+
+        assert_eq!(metrics.mpc_bytes.with_label_values(&["sent"]).get(), 5);
+        assert_eq!(metrics.mpc_bytes.with_label_values(&["received"]).get(), 2);
+    }
+
+    #[test]
+    fn test_session_metrics() {
+        let metrics = NotaryMetrics::new().unwrap();
+
+        let guard = metrics.start_session();
+        assert_eq!(metrics.active_sessions.get(), 1);
+        metrics.record_failure(FailureReason::Mpc);
+        drop(guard);
+        assert_eq!(metrics.active_sessions.get(), 0);
+
+        metrics.record_success(10, 20);
+
+        let encoded = metrics.encode().unwrap();
+        assert!(encoded.contains("notary_session_failures_total{reason=\"mpc\"} 1"));
+        assert!(encoded.contains("notary_sessions_total{outcome=\"success\"} 1"));
+        assert!(encoded.contains("notary_transcript_bytes_total{direction=\"received\"} 20"));
+        assert!(encoded.contains("notary_session_duration_seconds_count 1"));
+    }
+}
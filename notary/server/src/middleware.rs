@@ -1,11 +1,17 @@
 use async_trait::async_trait;
-use axum::http::{header, request::Parts};
+use axum::{
+    extract::Query,
+    http::{header, request::Parts},
+};
 use axum_core::extract::{FromRef, FromRequestParts};
 use std::collections::HashMap;
 use tracing::{error, trace};
 
 use crate::{
-    domain::{auth::AuthorizationWhitelistRecord, notary::NotaryGlobals},
+    domain::{
+        auth::AuthorizationWhitelistRecord,
+        notary::{NotaryGlobals, TenantQuery},
+    },
     NotaryServerError,
 };
 
@@ -21,7 +27,12 @@ where
     type Rejection = NotaryServerError;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let notary_globals = NotaryGlobals::from_ref(state);
+        // The API key is checked against the whitelist of the tenant selected by the request
+        let Query(tenant_query) = Query::<TenantQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| NotaryServerError::BadProverRequest(err.to_string()))?;
+        let notary_globals =
+            NotaryGlobals::from_ref(state).tenant(tenant_query.tenant_id.as_deref())?;
         let Some(whitelist) = notary_globals.authorization_whitelist else {
             trace!("Skipping authorization as whitelist is not set.");
             return Ok(Self);
@@ -1,19 +1,24 @@
 use axum::{
-    extract::Request,
-    http::StatusCode,
+    extract::{Query, Request, State},
+    http::{header, StatusCode},
     middleware::from_extractor_with_state,
     response::{Html, IntoResponse},
     routing::{get, post},
     Json, Router,
 };
+use chrono::Utc;
 use eyre::{ensure, eyre, Result};
 use futures_util::future::poll_fn;
+use hex::FromHex;
 use hyper::{body::Incoming, server::conn::http1};
 use hyper_util::rt::TokioIo;
 use notify::{
     event::ModifyKind, Error, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
 };
-use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+use p256::{
+    ecdsa::{Signature, SigningKey},
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+};
 use rustls::{Certificate, PrivateKey, ServerConfig};
 use std::{
     collections::HashMap,
@@ -23,47 +28,136 @@ use std::{
     path::Path,
     pin::Pin,
     sync::{Arc, Mutex},
+    time::Duration,
 };
+#[cfg(feature = "bls")]
+use tlsn_core::bls::BlsSigningKey;
+use tlsn_core::revocation::{RevocationList, SignedRevocationList};
+use tlsn_verifier::tls::AesBackend;
 use tokio::{fs::File, net::TcpListener};
 use tokio_rustls::TlsAcceptor;
 use tower_http::cors::CorsLayer;
 use tower_service::Service;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    config::{NotaryServerProperties, NotarySigningKeyProperties},
+    acme::AcmeManager,
+    config::{
+        AuthorizationProperties, NotarizationProperties, NotaryServerProperties,
+        NotarySigningKeyProperties, PolicyProperties,
+    },
     domain::{
-        auth::{authorization_whitelist_vec_into_hashmap, AuthorizationWhitelistRecord},
-        notary::NotaryGlobals,
-        InfoResponse,
+        auth::{
+            authorization_whitelist_vec_into_hashmap, ApiKeyRateLimiter,
+            AuthorizationWhitelistRecord, RateLimits,
+        },
+        gate::SessionGate,
+        notary::{NotaryGlobals, TenantQuery},
+        policy::DomainPolicy,
+        InfoResponse, KeysResponse, NotaryKeyInfo,
     },
     error::NotaryServerError,
+    metrics::NotaryMetrics,
     middleware::AuthorizationMiddleware,
-    service::{initialize, upgrade_protocol},
-    util::parse_csv_file,
+    service::{
+        initialize, transparency_log_consistency, transparency_log_entries, transparency_log_head,
+        transparency_log_proof, upgrade_protocol, upgrade_websocket,
+    },
+    shutdown::{shutdown_on_signal, ShutdownController, DEFAULT_SHUTDOWN_TIMEOUT_SECS},
+    store::build_session_store,
+    time::build_time_source,
+    transparency::build_transparency_log,
+    util::{parse_config_file, parse_csv_file},
 };
 
 /// Start a TCP server (with or without TLS) to accept notarization request for both TCP and WebSocket clients
-#[tracing::instrument(skip(config))]
+///
+/// The server shuts down gracefully on Ctrl-C or SIGTERM
 pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotaryServerError> {
-    // Load the private key for notarized transcript signing
-    let notary_signing_key = load_notary_signing_key(&config.notary_key).await?;
+    serve(config, None, None, shutdown_controller_on_signal()).await
+}
+
+/// Start the server like [`run_server`] with the config loaded from a file, which is watched so
+/// that the settings that are safe to change are applied to new sessions without a restart
+///
+/// The domain policies and the API key rate limits of the existing tenants are reloaded, other
+/// changes require a restart
+pub async fn run_server_with_config_reload(
+    config: &NotaryServerProperties,
+    config_file: &str,
+) -> Result<(), NotaryServerError> {
+    serve(
+        config,
+        Some(config_file),
+        None,
+        shutdown_controller_on_signal(),
+    )
+    .await
+}
+
+/// Start the server like [`run_server`], invoking the provided hook at the creation and
+/// finalization of every notarization session, e.g. to charge for or meter sessions
+pub async fn run_server_with_session_gate(
+    config: &NotaryServerProperties,
+    session_gate: Arc<dyn SessionGate>,
+) -> Result<(), NotaryServerError> {
+    serve(
+        config,
+        None,
+        Some(session_gate),
+        shutdown_controller_on_signal(),
+    )
+    .await
+}
+
+/// Start the server like [`run_server`], shutting down gracefully when a shutdown is requested
+/// with the provided controller instead of on termination signals
+pub async fn run_server_with_shutdown(
+    config: &NotaryServerProperties,
+    shutdown: ShutdownController,
+) -> Result<(), NotaryServerError> {
+    serve(config, None, None, shutdown).await
+}
+
+/// Build a shutdown controller which requests a shutdown on termination signals
+fn shutdown_controller_on_signal() -> ShutdownController {
+    let shutdown = ShutdownController::new();
+    tokio::spawn(shutdown_on_signal(shutdown.clone()));
+    shutdown
+}
+
+#[tracing::instrument(skip(config, session_gate, shutdown))]
+async fn serve(
+    config: &NotaryServerProperties,
+    config_file: Option<&str>,
+    session_gate: Option<Arc<dyn SessionGate>>,
+    shutdown: ShutdownController,
+) -> Result<(), NotaryServerError> {
     // Build TLS acceptor if it is turned on
     let tls_acceptor = if !config.tls.enabled {
         debug!("Skipping TLS setup as it is turned off.");
         None
     } else {
-        let (tls_private_key, tls_certificates) = load_tls_key_and_cert(
-            &config.tls.private_key_pem_path,
-            &config.tls.certificate_pem_path,
-        )
-        .await?;
-
-        let mut server_config = ServerConfig::builder()
+        let server_config = ServerConfig::builder()
             .with_safe_defaults()
-            .with_no_client_auth()
-            .with_single_cert(tls_certificates, tls_private_key)
-            .map_err(|err| eyre!("Failed to instantiate notary server tls config: {err}"))?;
+            .with_no_client_auth();
+        let mut server_config = if config.tls.acme.enabled {
+            // Present a publicly trusted certificate obtained and renewed with ACME
+            let acme = Arc::new(AcmeManager::new(config.tls.acme.clone())?);
+            let resolver = acme.resolver();
+            acme.start().await?;
+            server_config.with_cert_resolver(resolver)
+        } else {
+            let (tls_private_key, tls_certificates) = load_tls_key_and_cert(
+                &config.tls.private_key_pem_path,
+                &config.tls.certificate_pem_path,
+            )
+            .await?;
+
+            server_config
+                .with_single_cert(tls_certificates, tls_private_key)
+                .map_err(|err| eyre!("Failed to instantiate notary server tls config: {err}"))?
+        };
 
         // Set the http protocols we support
         server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
@@ -71,14 +165,89 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
         Some(TlsAcceptor::from(tls_config))
     };
 
-    // Load the authorization whitelist csv if it is turned on
-    let authorization_whitelist =
-        load_authorization_whitelist(config)?.map(|whitelist| Arc::new(Mutex::new(whitelist)));
-    // Enable hot reload if authorization whitelist is available
-    let watcher =
-        watch_and_reload_authorization_whitelist(config.clone(), authorization_whitelist.clone())?;
-    if watcher.is_some() {
-        debug!("Successfully setup watcher for hot reload of authorization whitelist!");
+    // Load the globals of the default tenant and of each additional tenant
+    // Need to keep the watchers alive, else they will be dropped and stop listening
+    let mut watchers = Vec::new();
+    let metrics = NotaryMetrics::new()?;
+    let time_source = build_time_source(&config.timestamp)?;
+    if let Some(time_source) = &time_source {
+        debug!(?time_source, "Timestamping session headers");
+    }
+    let (notary_globals, watcher) = load_notary_globals(
+        &config.notary_key,
+        &config.notarization,
+        &config.authorization,
+        &config.policy,
+        metrics.clone(),
+    )
+    .await?;
+    watchers.extend(watcher);
+
+    let session_ttl = Duration::from_secs(config.session_store.session_ttl_secs);
+    let mut tenants = HashMap::new();
+    let mut tenant_public_keys = HashMap::new();
+    let mut tenant_keys = HashMap::new();
+    for tenant in &config.tenants {
+        ensure!(
+            !tenant.id.is_empty()
+                && tenant
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Tenant id {:?} must be non-empty and only contain alphanumeric characters, '-' and '_'",
+            tenant.id
+        );
+        ensure!(
+            !tenants.contains_key(&tenant.id),
+            "Tenant id {} is configured more than once",
+            tenant.id
+        );
+        let (tenant_globals, watcher) = load_notary_globals(
+            &tenant.notary_key,
+            &tenant.notarization,
+            &tenant.authorization,
+            &tenant.policy,
+            metrics.clone(),
+        )
+        .await?;
+        watchers.extend(watcher);
+        tenants.insert(
+            tenant.id.clone(),
+            tenant_globals
+                .with_time_source(time_source.clone())
+                .with_session_gate(session_gate.clone())
+                .with_shutdown(shutdown.clone())
+                .with_websocket_config(config.websocket.clone())
+                .with_session_store(build_session_store(&config.session_store, &tenant.id).await?)
+                .with_session_ttl(session_ttl)
+                .with_transparency_log(build_transparency_log(
+                    &config.transparency_log,
+                    &tenant.id,
+                )?),
+        );
+        tenant_public_keys.insert(
+            tenant.id.clone(),
+            load_notary_public_key(&tenant.notary_key)?,
+        );
+        tenant_keys.insert(tenant.id.clone(), load_notary_keys(&tenant.notary_key)?);
+        debug!(tenant_id = tenant.id, "Successfully loaded tenant!");
+    }
+    // Tenant ids are non-empty, so the empty namespace is reserved for the default tenant
+    let notary_globals = notary_globals
+        .with_time_source(time_source)
+        .with_session_gate(session_gate)
+        .with_shutdown(shutdown.clone())
+        .with_websocket_config(config.websocket.clone())
+        .with_session_store(build_session_store(&config.session_store, "").await?)
+        .with_session_ttl(session_ttl)
+        .with_transparency_log(build_transparency_log(&config.transparency_log, "")?)
+        .with_tenants(tenants);
+    if let Some(config_file) = config_file {
+        watchers.push(watch_and_reload_config(
+            config_file,
+            notary_globals.clone(),
+        )?);
+        debug!("Successfully setup watcher for hot reload of the config!");
     }
 
     let notary_address = SocketAddr::new(
@@ -93,16 +262,20 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
 
     info!("Listening for TCP traffic at {}", notary_address);
 
+    let aes_backend = AesBackend::detect();
+    if aes_backend.is_hardware() {
+        info!("Using hardware AES for garbling");
+    } else {
+        warn!("Hardware AES is not available, garbling uses the slower software implementation");
+    }
+
     let protocol = Arc::new(http1::Builder::new());
-    let notary_globals = NotaryGlobals::new(
-        notary_signing_key,
-        config.notarization.clone(),
-        authorization_whitelist,
-    );
 
     // Parameters needed for the info endpoint
-    let public_key = std::fs::read_to_string(&config.notary_key.public_key_pem_path)
-        .map_err(|err| eyre!("Failed to load notary public signing key for notarization: {err}"))?;
+    let public_key = load_notary_public_key(&config.notary_key)?;
+    // Parameters needed for the keys endpoint
+    let keys = load_notary_keys(&config.notary_key)?;
+
     let version = env!("CARGO_PKG_VERSION").to_string();
     let git_commit_hash = env!("GIT_COMMIT_HASH").to_string();
     let git_commit_timestamp = env!("GIT_COMMIT_TIMESTAMP").to_string();
@@ -117,7 +290,28 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
             .replace("{public_key}", &public_key),
     );
 
-    let router = Router::new()
+    let mut router = Router::new();
+    // Expose the prometheus metrics for scraping if it is turned on
+    if config.metrics.enabled {
+        router = router.route(
+            "/metrics",
+            get(|| async move {
+                match metrics.encode() {
+                    Ok(encoded) => (
+                        StatusCode::OK,
+                        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                        encoded,
+                    )
+                        .into_response(),
+                    Err(err) => {
+                        error!("{err}");
+                        NotaryServerError::from(err).into_response()
+                    }
+                }
+            }),
+        );
+    }
+    let router = router
         .route(
             "/",
             get(|| async move { (StatusCode::OK, html_info).into_response() }),
@@ -128,7 +322,20 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
         )
         .route(
             "/info",
-            get(|| async move {
+            get(|Query(tenant_query): Query<TenantQuery>| async move {
+                // Return the public key of the tenant if one is specified
+                let public_key = match tenant_query.tenant_id {
+                    Some(tenant_id) => match tenant_public_keys.get(&tenant_id) {
+                        Some(public_key) => public_key.clone(),
+                        None => {
+                            return NotaryServerError::BadProverRequest(format!(
+                                "Unknown tenant id {tenant_id}"
+                            ))
+                            .into_response()
+                        }
+                    },
+                    None => public_key,
+                };
                 (
                     StatusCode::OK,
                     Json(InfoResponse {
@@ -141,23 +348,73 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
                     .into_response()
             }),
         )
+        .route(
+            "/keys",
+            get(|Query(tenant_query): Query<TenantQuery>| async move {
+                // Return the keys of the tenant if one is specified
+                let keys = match tenant_query.tenant_id {
+                    Some(tenant_id) => match tenant_keys.get(&tenant_id) {
+                        Some(keys) => keys.clone(),
+                        None => {
+                            return NotaryServerError::BadProverRequest(format!(
+                                "Unknown tenant id {tenant_id}"
+                            ))
+                            .into_response()
+                        }
+                    },
+                    None => keys,
+                };
+                (StatusCode::OK, Json(KeysResponse { keys })).into_response()
+            }),
+        )
+        .route(
+            "/revocations",
+            get(
+                |State(notary_globals): State<NotaryGlobals>,
+                 Query(tenant_query): Query<TenantQuery>| async move {
+                    // Return the revocation list of the tenant if one is specified
+                    let notary_globals =
+                        match notary_globals.tenant(tenant_query.tenant_id.as_deref()) {
+                            Ok(notary_globals) => notary_globals,
+                            Err(err) => return err.into_response(),
+                        };
+                    let revocation_list = notary_globals.revocation_list.lock().unwrap().clone();
+                    (StatusCode::OK, Json(revocation_list)).into_response()
+                },
+            ),
+        )
         .route("/session", post(initialize))
-        // Not applying auth middleware to /notarize endpoint for now as we can rely on our
-        // short-lived session id generated from /session endpoint, as it is not possible
-        // to use header for API key for websocket /notarize endpoint due to browser restriction
+        // Not applying auth middleware to /notarize endpoint as it is not possible to use header
+        // for API key for websocket /notarize endpoint due to browser restriction
         // ref: https://stackoverflow.com/a/4361358; And putting it in url query param
         // seems to be more insecured: https://stackoverflow.com/questions/5517281/place-api-key-in-headers-or-url
+        // Instead, the API key used to call /session is bound to the short-lived session id, and is
+        // validated again (together with its rate limits) when /notarize is called
         .route_layer(from_extractor_with_state::<
             AuthorizationMiddleware,
             NotaryGlobals,
         >(notary_globals.clone()))
         .route("/notarize", get(upgrade_protocol))
+        .route("/notarize/ws", get(upgrade_websocket))
+        // The transparency log is public so that anyone can audit the notarizations
+        .route("/transparency-log", get(transparency_log_head))
+        .route("/transparency-log/entries", get(transparency_log_entries))
+        .route("/transparency-log/proof", get(transparency_log_proof))
+        .route(
+            "/transparency-log/consistency",
+            get(transparency_log_consistency),
+        )
         .layer(CorsLayer::permissive())
         .with_state(notary_globals);
 
     loop {
         // Poll and await for any incoming connection, ensure that all operations inside are infallible to prevent bringing down the server
-        let stream = match poll_fn(|cx| Pin::new(&mut listener).poll_accept(cx)).await {
+        // Stop accepting connections once a shutdown is requested
+        let accepted = tokio::select! {
+            accepted = poll_fn(|cx| Pin::new(&mut listener).poll_accept(cx)) => accepted,
+            _ = shutdown.requested() => break,
+        };
+        let stream = match accepted {
             Ok((stream, _)) => stream,
             Err(err) => {
                 error!("{}", NotaryServerError::Connection(err.to_string()));
@@ -214,6 +471,136 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
             }
         });
     }
+
+    // Close the listener so that new connections are refused while draining
+    drop(listener);
+    let timeout = Duration::from_secs(
+        config
+            .server
+            .shutdown_timeout_secs
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+    );
+    let aborted = shutdown.drain(timeout).await;
+    info!(aborted, "Notary server shut down");
+
+    Ok(())
+}
+
+/// Load the globals of a tenant, including the watcher to hot reload its authorization whitelist if it is enabled
+async fn load_notary_globals(
+    notary_key: &NotarySigningKeyProperties,
+    notarization: &NotarizationProperties,
+    authorization: &AuthorizationProperties,
+    policy: &PolicyProperties,
+    metrics: NotaryMetrics,
+) -> Result<(NotaryGlobals, Option<RecommendedWatcher>)> {
+    // Load the private key for notarized transcript signing
+    let notary_signing_key = load_notary_signing_key(notary_key).await?;
+
+    // Load the authorization whitelist csv if it is turned on
+    let authorization_whitelist = load_authorization_whitelist(authorization)?
+        .map(|whitelist| Arc::new(Mutex::new(whitelist)));
+    // Enable hot reload if authorization whitelist is available
+    let watcher = watch_and_reload_authorization_whitelist(
+        authorization.clone(),
+        authorization_whitelist.clone(),
+    )?;
+    if watcher.is_some() {
+        debug!("Successfully setup watcher for hot reload of authorization whitelist!");
+    }
+
+    let notary_globals = NotaryGlobals::new(
+        notary_signing_key,
+        notarization.clone(),
+        authorization_whitelist,
+        ApiKeyRateLimiter::new(
+            authorization.max_concurrent_sessions_per_key,
+            authorization.max_daily_data_per_key,
+        ),
+        DomainPolicy::new(policy),
+        metrics,
+    )
+    .with_key_id(notary_key.key_id.clone())
+    .with_did(notary_key.did.clone());
+    let revocation_list = build_revocation_list(notary_key, &notary_globals.notary_signing_key)?;
+    let notary_globals = notary_globals.with_revocation_list(revocation_list);
+    #[cfg(feature = "bls")]
+    let notary_globals = notary_globals.with_bls_signing_key(load_bls_signing_key(notary_key)?);
+    #[cfg(not(feature = "bls"))]
+    ensure!(
+        notary_key.bls_key_path.is_none(),
+        "A BLS key is configured, but the server is built without the bls feature"
+    );
+
+    Ok((notary_globals, watcher))
+}
+
+/// Load the current and retired notary public keys (in PEM format) from static files, starting
+/// with the current key
+fn load_notary_keys(config: &NotarySigningKeyProperties) -> Result<Vec<NotaryKeyInfo>> {
+    #[cfg(feature = "bls")]
+    let bls_public_key = load_bls_signing_key(config)?
+        .map(|bls_signing_key| hex::encode(bls_signing_key.public_key().to_bytes()));
+    #[cfg(not(feature = "bls"))]
+    let bls_public_key = None;
+
+    let mut keys = vec![NotaryKeyInfo {
+        key_id: config.key_id.clone(),
+        public_key: load_notary_public_key(config)?,
+        bls_public_key,
+        current: true,
+    }];
+    for retired_key in &config.retired_keys {
+        ensure!(
+            keys.iter()
+                .all(|key| key.key_id.as_ref() != Some(&retired_key.key_id)),
+            "Notary key id {} is configured more than once",
+            retired_key.key_id
+        );
+        let public_key = std::fs::read_to_string(&retired_key.public_key_pem_path)
+            .map_err(|err| eyre!("Failed to load retired notary public key: {err}"))?;
+        keys.push(NotaryKeyInfo {
+            key_id: Some(retired_key.key_id.clone()),
+            public_key,
+            bls_public_key: None,
+            current: false,
+        });
+    }
+
+    Ok(keys)
+}
+
+/// Build the revocation list of the notarizations signed with a key, signed with the same key.
+/// Revoked key ids are resolved to the configured current and retired keys, which are revoked by
+/// their fingerprint
+fn build_revocation_list(
+    config: &NotarySigningKeyProperties,
+    signing_key: &SigningKey,
+) -> Result<SignedRevocationList> {
+    let mut revocation_list = RevocationList::new(Utc::now().timestamp() as u64);
+    for session in &config.revoked_sessions {
+        let hash = <[u8; 32]>::from_hex(session)
+            .map_err(|err| eyre!("Invalid revoked session hash {session:?}: {err}"))?;
+        revocation_list.revoke_session(hash.into());
+    }
+    let keys = load_notary_keys(config)?;
+    for key_id in &config.revoked_key_ids {
+        let key = keys
+            .iter()
+            .find(|key| key.key_id.as_ref() == Some(key_id))
+            .ok_or_else(|| eyre!("Revoked notary key id {key_id} is not configured"))?;
+        let public_key = p256::PublicKey::from_public_key_pem(&key.public_key)
+            .map_err(|err| eyre!("Failed to parse notary public key {key_id}: {err}"))?;
+        revocation_list.revoke_key(public_key);
+    }
+
+    Ok(revocation_list.sign::<Signature>(signing_key))
+}
+
+/// Load notary public key (in PEM format) from static file
+fn load_notary_public_key(config: &NotarySigningKeyProperties) -> Result<String> {
+    std::fs::read_to_string(&config.public_key_pem_path)
+        .map_err(|err| eyre!("Failed to load notary public signing key for notarization: {err}"))
 }
 
 /// Load notary signing key from static file
@@ -227,6 +614,21 @@ async fn load_notary_signing_key(config: &NotarySigningKeyProperties) -> Result<
     Ok(notary_signing_key)
 }
 
+/// Load the BLS12-381 key signing the session headers from static file, if it is configured
+#[cfg(feature = "bls")]
+fn load_bls_signing_key(config: &NotarySigningKeyProperties) -> Result<Option<BlsSigningKey>> {
+    let Some(path) = &config.bls_key_path else {
+        return Ok(None);
+    };
+
+    let ikm =
+        std::fs::read(path).map_err(|err| eyre!("Failed to load notary BLS signing key: {err}"))?;
+    let bls_signing_key = BlsSigningKey::from_ikm(&ikm)
+        .map_err(|err| eyre!("Failed to load notary BLS signing key: {err}"))?;
+
+    Ok(Some(bls_signing_key))
+}
+
 /// Read a PEM-formatted file and return its buffer reader
 pub async fn read_pem_file(file_path: &str) -> Result<BufReader<StdFile>> {
     let key_file = File::open(file_path).await?.into_std().await;
@@ -260,17 +662,16 @@ async fn load_tls_key_and_cert(
 
 /// Load authorization whitelist if it is enabled
 fn load_authorization_whitelist(
-    config: &NotaryServerProperties,
+    config: &AuthorizationProperties,
 ) -> Result<Option<HashMap<String, AuthorizationWhitelistRecord>>> {
-    let authorization_whitelist = if !config.authorization.enabled {
+    let authorization_whitelist = if !config.enabled {
         debug!("Skipping authorization as it is turned off.");
         None
     } else {
         // Load the csv
-        let whitelist_csv = parse_csv_file::<AuthorizationWhitelistRecord>(
-            &config.authorization.whitelist_csv_path,
-        )
-        .map_err(|err| eyre!("Failed to parse authorization whitelist csv: {:?}", err))?;
+        let whitelist_csv =
+            parse_csv_file::<AuthorizationWhitelistRecord>(&config.whitelist_csv_path)
+                .map_err(|err| eyre!("Failed to parse authorization whitelist csv: {:?}", err))?;
         // Convert the whitelist record into hashmap for faster lookup
         let whitelist_hashmap = authorization_whitelist_vec_into_hashmap(whitelist_csv);
         Some(whitelist_hashmap)
@@ -282,7 +683,7 @@ fn load_authorization_whitelist(
 // When the list file is modified, the watcher thread will reload the whitelist
 // The watcher is setup in a separate thread by the notify library which is synchronous
 fn watch_and_reload_authorization_whitelist(
-    config: NotaryServerProperties,
+    config: AuthorizationProperties,
     authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
 ) -> Result<Option<RecommendedWatcher>> {
     // Only setup the watcher if auth whitelist is loaded
@@ -321,7 +722,7 @@ fn watch_and_reload_authorization_whitelist(
         // Start watcher to listen to any changes on the whitelist file
         watcher
             .watch(
-                Path::new(&config.authorization.whitelist_csv_path),
+                Path::new(&config.whitelist_csv_path),
                 RecursiveMode::Recursive,
             )
             .map_err(|err| eyre!("Error occured when starting up watcher for hot reload: {err}"))?;
@@ -335,15 +736,98 @@ fn watch_and_reload_authorization_whitelist(
     Ok(watcher)
 }
 
+/// Apply the settings of a reloaded config that are safe to change while sessions are running,
+/// i.e. the domain policies and the API key rate limits of the default and existing tenants, which
+/// take effect for the sessions started afterwards
+fn apply_reloaded_config(config: &NotaryServerProperties, notary_globals: &NotaryGlobals) {
+    let apply = |globals: &NotaryGlobals,
+                 notary_key: &NotarySigningKeyProperties,
+                 policy: &PolicyProperties,
+                 authorization: &AuthorizationProperties| {
+        *globals.domain_policy.lock().unwrap() = DomainPolicy::new(policy);
+        globals.rate_limiter.set_limits(RateLimits {
+            max_concurrent_sessions: authorization.max_concurrent_sessions_per_key,
+            max_daily_data: authorization.max_daily_data_per_key,
+        });
+        // The signing key itself is only loaded at startup, so the list keeps being signed by it
+        match build_revocation_list(notary_key, &globals.notary_signing_key) {
+            Ok(revocation_list) => *globals.revocation_list.lock().unwrap() = revocation_list,
+            Err(err) => {
+                error!("Failed to rebuild the revocation list, keeping the previous one: {err}")
+            }
+        }
+    };
+
+    apply(
+        notary_globals,
+        &config.notary_key,
+        &config.policy,
+        &config.authorization,
+    );
+    for tenant in &config.tenants {
+        match notary_globals.tenants.get(&tenant.id) {
+            Some(tenant_globals) => apply(
+                tenant_globals,
+                &tenant.notary_key,
+                &tenant.policy,
+                &tenant.authorization,
+            ),
+            None => warn!(
+                tenant_id = tenant.id,
+                "Tenant added to the config is ignored until the server is restarted"
+            ),
+        }
+    }
+}
+
+// Setup a watcher to detect any changes to the config file
+// When the file is modified, the watcher thread will apply the settings that are safe to change
+fn watch_and_reload_config(
+    config_file: &str,
+    notary_globals: NotaryGlobals,
+) -> Result<RecommendedWatcher> {
+    let cloned_config_file = config_file.to_string();
+    let mut watcher = RecommendedWatcher::new(
+        move |event: Result<Event, Error>| match event {
+            Ok(event) => {
+                // Only reload the config if it's an event that modified the file data
+                if let EventKind::Modify(ModifyKind::Data(_)) = event.kind {
+                    debug!("Config file is modified");
+                    match parse_config_file::<NotaryServerProperties>(&cloned_config_file) {
+                        Ok(config) => {
+                            apply_reloaded_config(&config, &notary_globals);
+                            info!("Successfully reloaded domain policies, rate limits and revocation lists!");
+                        }
+                        // Ensure that an invalid config doesn't bring the server down, the
+                        // previous settings are kept
+                        Err(err) => error!("Failed to reload config: {err}"),
+                    }
+                }
+            }
+            Err(err) => {
+                error!("Error occured when watcher detected an event: {err}")
+            }
+        },
+        notify::Config::default(),
+    )
+    .map_err(|err| eyre!("Error occured when setting up watcher for hot reload: {err}"))?;
+
+    watcher
+        .watch(Path::new(config_file), RecursiveMode::NonRecursive)
+        .map_err(|err| eyre!("Error occured when starting up watcher for hot reload: {err}"))?;
+
+    Ok(watcher)
+}
+
 #[cfg(test)]
 mod test {
     use std::{fs::OpenOptions, time::Duration};
 
     use csv::WriterBuilder;
 
-    use crate::AuthorizationProperties;
-
     use super::*;
+    use crate::config::{RetiredNotaryKeyProperties, TenantProperties};
+    use tlsn_core::signature::NotaryPublicKey;
 
     #[tokio::test]
     async fn test_load_notary_key_and_cert() {
@@ -359,11 +843,169 @@ mod test {
         let config = NotarySigningKeyProperties {
             private_key_pem_path: "./fixture/notary/notary.key".to_string(),
             public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            key_id: None,
+            did: None,
+            bls_key_path: None,
+            retired_keys: vec![],
+            revoked_sessions: vec![],
+            revoked_key_ids: vec![],
         };
         let result: Result<SigningKey> = load_notary_signing_key(&config).await;
         assert!(result.is_ok(), "Could not load notary private key");
     }
 
+    #[test]
+    fn test_load_notary_keys() {
+        let config = NotarySigningKeyProperties {
+            private_key_pem_path: "./fixture/notary/notary.key".to_string(),
+            public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            key_id: Some("notary-2".to_string()),
+            did: None,
+            bls_key_path: None,
+            retired_keys: vec![RetiredNotaryKeyProperties {
+                key_id: "notary-1".to_string(),
+                public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            }],
+            revoked_sessions: vec![],
+            revoked_key_ids: vec![],
+        };
+        let keys = load_notary_keys(&config).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].current);
+        assert_eq!(keys[1].key_id.as_deref(), Some("notary-1"));
+        assert!(!keys[1].current);
+
+        // Key ids must be unique
+        let config = NotarySigningKeyProperties {
+            key_id: Some("notary-1".to_string()),
+            ..config
+        };
+        assert!(load_notary_keys(&config).is_err());
+    }
+
+    #[cfg(feature = "bls")]
+    #[test]
+    fn test_load_bls_signing_key() {
+        let mut config = NotarySigningKeyProperties {
+            private_key_pem_path: "./fixture/notary/notary.key".to_string(),
+            public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            bls_key_path: Some("./fixture/notary/notary.bls".to_string()),
+            ..Default::default()
+        };
+
+        let bls_signing_key = load_bls_signing_key(&config).unwrap().unwrap();
+        let keys = load_notary_keys(&config).unwrap();
+        assert_eq!(
+            keys[0].bls_public_key,
+            Some(hex::encode(bls_signing_key.public_key().to_bytes()))
+        );
+
+        config.bls_key_path = None;
+        assert!(load_bls_signing_key(&config).unwrap().is_none());
+        assert!(load_notary_keys(&config).unwrap()[0]
+            .bls_public_key
+            .is_none());
+
+        config.bls_key_path = Some("./fixture/notary/missing.bls".to_string());
+        assert!(load_bls_signing_key(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_build_revocation_list() {
+        let mut config = NotarySigningKeyProperties {
+            private_key_pem_path: "./fixture/notary/notary.key".to_string(),
+            public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            key_id: None,
+            did: None,
+            bls_key_path: None,
+            retired_keys: vec![RetiredNotaryKeyProperties {
+                key_id: "notary-1".to_string(),
+                public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
+            }],
+            revoked_sessions: vec![hex::encode([1u8; 32])],
+            revoked_key_ids: vec!["notary-1".to_string()],
+        };
+        let signing_key = load_notary_signing_key(&config).await.unwrap();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        // The key id is resolved to the fingerprint of the configured key
+        let revocation_list = build_revocation_list(&config, &signing_key).unwrap();
+        let list = revocation_list.verify(public_key).unwrap();
+        assert_eq!(list.sessions().count(), 1);
+        assert_eq!(
+            list.keys().collect::<Vec<_>>(),
+            vec![NotaryPublicKey::from(public_key).fingerprint()]
+        );
+
+        // Revoked key ids must be configured
+        config.revoked_key_ids = vec!["notary-2".to_string()];
+        assert!(build_revocation_list(&config, &signing_key).is_err());
+
+        // Session hashes must be valid hex encoded 32 byte hashes
+        config.revoked_key_ids = vec![];
+        config.revoked_sessions = vec!["0102".to_string()];
+        assert!(build_revocation_list(&config, &signing_key).is_err());
+    }
+
+    #[test]
+    fn test_apply_reloaded_config() {
+        let globals = |key: u8| {
+            NotaryGlobals::new(
+                SigningKey::from_bytes(&[key; 32].into()).unwrap(),
+                NotarizationProperties {
+                    max_transcript_size: 1 << 14,
+                    max_sent_data: None,
+                    max_recv_data: None,
+                    mpc_concurrency: None,
+                    max_records: None,
+                    max_ots: None,
+                    max_session_duration_secs: None,
+                    compression: false,
+                },
+                None,
+                ApiKeyRateLimiter::new(None, None),
+                DomainPolicy::default(),
+                NotaryMetrics::new().unwrap(),
+            )
+        };
+        let notary_globals =
+            globals(1).with_tenants(HashMap::from([("tenant-a".to_string(), globals(2))]));
+
+        let mut config: NotaryServerProperties = parse_config_file("./config/config.yaml").unwrap();
+        config.policy = PolicyProperties {
+            enabled: true,
+            allowlist: vec!["example.com".to_string()],
+            ..Default::default()
+        };
+        config.notary_key.revoked_sessions = vec![hex::encode([1u8; 32])];
+        config.tenants = vec![TenantProperties {
+            id: "tenant-a".to_string(),
+            authorization: AuthorizationProperties {
+                max_concurrent_sessions_per_key: Some(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        }];
+        apply_reloaded_config(&config, &notary_globals);
+
+        let policy = notary_globals.domain_policy.lock().unwrap().clone();
+        assert!(policy.evaluate(Some("example.com"), None, None).is_ok());
+        assert!(policy.evaluate(Some("other.com"), None, None).is_err());
+        assert_eq!(
+            notary_globals.tenants["tenant-a"]
+                .rate_limiter
+                .limits()
+                .max_concurrent_sessions,
+            Some(3)
+        );
+
+        // The revocation list is rebuilt and still signed with the key of the tenant
+        let public_key = p256::PublicKey::from(notary_globals.notary_signing_key.verifying_key());
+        let revocation_list = notary_globals.revocation_list.lock().unwrap().clone();
+        let list = revocation_list.verify(public_key).unwrap();
+        assert_eq!(list.sessions().collect::<Vec<_>>(), vec![[1u8; 32].into()]);
+    }
+
     #[tokio::test]
     async fn test_watch_and_reload_authorization_whitelist() {
         // Clone fixture auth whitelist for testing
@@ -372,11 +1014,9 @@ mod test {
         std::fs::copy(original_whitelist_csv_path, &whitelist_csv_path).unwrap();
 
         // Setup watcher
-        let config = NotaryServerProperties {
-            authorization: AuthorizationProperties {
-                enabled: true,
-                whitelist_csv_path,
-            },
+        let config = AuthorizationProperties {
+            enabled: true,
+            whitelist_csv_path,
             ..Default::default()
         };
         let authorization_whitelist = load_authorization_whitelist(&config)
@@ -401,7 +1041,7 @@ mod test {
         };
         let file = OpenOptions::new()
             .append(true)
-            .open(&config.authorization.whitelist_csv_path)
+            .open(&config.whitelist_csv_path)
             .unwrap();
         let mut wtr = WriterBuilder::new()
             .has_headers(false) // Set to false to avoid writing header again
@@ -419,6 +1059,6 @@ mod test {
             .contains_key("unit-test-api-key"));
 
         // Delete the cloned whitelist
-        std::fs::remove_file(&config.authorization.whitelist_csv_path).unwrap();
+        std::fs::remove_file(&config.whitelist_csv_path).unwrap();
     }
 }
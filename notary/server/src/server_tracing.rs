@@ -1,9 +1,12 @@
 use eyre::Result;
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace::Config, Resource};
 use std::str::FromStr;
 use tracing::Level;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
 
-use crate::config::NotaryServerProperties;
+use crate::config::{NotaryServerProperties, TelemetryProperties};
 
 pub fn init_tracing(config: &NotaryServerProperties) -> Result<()> {
     // Retrieve log filtering logic from config
@@ -25,10 +28,46 @@ pub fn init_tracing(config: &NotaryServerProperties) -> Result<()> {
         .with_thread_ids(true)
         .with_thread_names(true);
 
+    // Export traces to an OpenTelemetry collector if enabled
+    let telemetry_layer = if config.telemetry.enabled {
+        let tracer = init_tracer(&config.telemetry)?;
+        Some(tracing_opentelemetry::layer().with_tracer(tracer))
+    } else {
+        None
+    };
+
     Registry::default()
         .with(filter_layer)
         .with(format_layer)
+        .with(telemetry_layer)
         .try_init()?;
 
     Ok(())
 }
+
+/// Flush and shut down the export of traces, if enabled
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+fn init_tracer(config: &TelemetryProperties) -> Result<opentelemetry_sdk::trace::Tracer> {
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.otlp_endpoint),
+        )
+        .with_trace_config(
+            Config::default().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                config.service_name.clone(),
+            )])),
+        )
+        .install_batch(runtime::Tokio)?;
+
+    let tracer = provider.tracer("notary-server");
+    opentelemetry::global::set_tracer_provider(provider);
+
+    Ok(tracer)
+}
@@ -5,29 +5,44 @@ pub mod websocket;
 use async_trait::async_trait;
 use axum::{
     extract::{rejection::JsonRejection, FromRequestParts, Query, State},
-    http::{header, request::Parts, StatusCode},
+    http::{header, request::Parts, HeaderMap, StatusCode},
     response::{IntoResponse, Json, Response},
 };
 use axum_macros::debug_handler;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use p256::ecdsa::{Signature, SigningKey};
-use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use std::{sync::Arc, time::Duration};
+use tlsn_common::config::{DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT};
+#[cfg(feature = "bls")]
+use tlsn_core::bls::BlsSignature;
+use tlsn_verifier::tls::{Aborted, Verifier, VerifierConfig, VerifierError};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::compat::TokioAsyncReadCompatExt;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, instrument, trace};
 use uuid::Uuid;
 
 use crate::{
-    domain::notary::{
-        NotarizationRequestQuery, NotarizationSessionRequest, NotarizationSessionResponse,
-        NotaryGlobals, SessionData,
+    config::WebSocketProperties,
+    domain::{
+        auth::{hash_api_key, ApiKeySessionGuard},
+        gate::{GatedSession, GatedSessionGuard, SessionUsage},
+        notary::{
+            NotarizationRequestQuery, NotarizationSessionRequest, NotarizationSessionResponse,
+            NotaryGlobals, SessionData, SessionReceipt, TenantQuery,
+        },
+        ConsistencyProofQuery, ConsistencyProofResponse, InclusionProofQuery,
+        InclusionProofResponse, LogEntriesQuery,
     },
     error::NotaryServerError,
+    metrics::{FailureReason, NotaryMetrics},
     service::{
         axum_websocket::{header_eq, WebSocketUpgrade},
         tcp::{tcp_notarize, TcpUpgrade},
         websocket::websocket_notarize,
     },
+    shutdown::InFlightSessionGuard,
+    store::SessionStore,
+    transparency::{TransparencyLog, MAX_LOG_ENTRIES_PER_REQUEST},
 };
 
 /// A wrapper enum to facilitate extracting TCP connection for either WebSocket or TCP clients,
@@ -71,55 +86,307 @@ where
 pub async fn upgrade_protocol(
     protocol_upgrade: ProtocolUpgrade,
     State(notary_globals): State<NotaryGlobals>,
+    headers: HeaderMap,
     Query(params): Query<NotarizationRequestQuery>,
 ) -> Response {
     info!("Received upgrade protocol request");
-    let session_id = params.session_id;
-    // Fetch the configuration data from the store using the session_id
-    // This also removes the configuration data from the store as each session_id can only be used once
-    let (max_sent_data, max_recv_data) =
-        match notary_globals.store.lock().unwrap().remove(&session_id) {
-            Some(data) => (data.max_sent_data, data.max_recv_data),
-            None => {
-                let err_msg = format!("Session id {} does not exist", session_id);
-                error!(err_msg);
-                return NotaryServerError::BadProverRequest(err_msg).into_response();
+    // WebSocket clients may be browser pages, which are subject to the same checks as on
+    // /notarize/ws
+    let protocol_upgrade = match protocol_upgrade {
+        ProtocolUpgrade::Ws(ws) => {
+            match configure_websocket(ws, &notary_globals.websocket_config, &headers) {
+                Ok(ws) => ProtocolUpgrade::Ws(ws),
+                Err(err) => return err.into_response(),
             }
-        };
+        }
+        tcp => tcp,
+    };
+    let session = match PendingNotarization::start(notary_globals, params).await {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
     // This completes the HTTP Upgrade request and returns a successful response to the client, meanwhile initiating the websocket or tcp connection
     match protocol_upgrade {
-        ProtocolUpgrade::Ws(ws) => ws.on_upgrade(move |socket| {
-            websocket_notarize(
-                socket,
+        ProtocolUpgrade::Ws(ws) => session.run_websocket(ws),
+        ProtocolUpgrade::Tcp(tcp) => tcp.on_upgrade(move |stream| async move {
+            let PendingNotarization {
                 notary_globals,
                 session_id,
-                max_sent_data,
-                max_recv_data,
-            )
+                gated_session,
+                gate_guard,
+                session_guard,
+                in_flight_guard,
+            } = session;
+            // Hold the guards until the notarization is finished to release the session slot
+            let _session_guard = session_guard;
+            let _in_flight_guard = in_flight_guard;
+            let shutdown = notary_globals.shutdown.clone();
+            let metrics = notary_globals.metrics.clone();
+            let usage = tokio::select! {
+                usage = tcp_notarize(
+                    stream,
+                    notary_globals,
+                    &gated_session,
+                ) => usage,
+                _ = shutdown.aborted() => {
+                    error!(?session_id, "Notarization aborted as the server is shutting down");
+                    metrics.record_failure(FailureReason::Shutdown);
+                    None
+                }
+            };
+            if let Some(gate_guard) = gate_guard {
+                gate_guard.finalize(usage).await;
+            }
         }),
-        ProtocolUpgrade::Tcp(tcp) => tcp.on_upgrade(move |stream| {
-            tcp_notarize(
-                stream,
+    }
+}
+
+/// Handler of the /notarize/ws endpoint for browser provers, which runs the notarization over
+/// the binary frames of a WebSocket like the WASM prover transport
+///
+/// The origin of the page running the prover is checked against the configured allowed origins,
+/// and the size of the WebSocket messages is limited
+pub async fn upgrade_websocket(
+    ws: WebSocketUpgrade,
+    State(notary_globals): State<NotaryGlobals>,
+    headers: HeaderMap,
+    Query(params): Query<NotarizationRequestQuery>,
+) -> Response {
+    info!("Received websocket upgrade request");
+    let ws = match configure_websocket(ws, &notary_globals.websocket_config, &headers) {
+        Ok(ws) => ws,
+        Err(err) => return err.into_response(),
+    };
+
+    let session = match PendingNotarization::start(notary_globals, params).await {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+    session.run_websocket(ws)
+}
+
+/// Reject WebSocket upgrades from pages whose origin is not allowed, and limit the size of the
+/// messages of the others
+fn configure_websocket(
+    ws: WebSocketUpgrade,
+    config: &WebSocketProperties,
+    headers: &HeaderMap,
+) -> Result<WebSocketUpgrade, NotaryServerError> {
+    let origin = headers
+        .get(header::ORIGIN)
+        .map(|value| value.to_str().unwrap_or("invalid"));
+    if !config.is_origin_allowed(origin) {
+        let err = NotaryServerError::ForbiddenOrigin(origin.unwrap_or("none").to_string());
+        error!("{err}");
+        return Err(err);
+    }
+
+    Ok(ws
+        .max_message_size(config.max_message_size)
+        .max_frame_size(config.max_frame_size))
+}
+
+/// A notarization whose session was fetched from the store and authorized, waiting for the
+/// connection with the prover to be upgraded
+struct PendingNotarization {
+    /// Globals of the tenant that initialized the session
+    notary_globals: NotaryGlobals,
+    session_id: String,
+    gated_session: GatedSession,
+    /// Reports the end of the session to the session gate, also if the notarization does not start
+    gate_guard: Option<GatedSessionGuard>,
+    /// Session slot of the API key bound to the session
+    session_guard: Option<ApiKeySessionGuard>,
+    /// Tracks the notarization so that a graceful shutdown waits for it
+    in_flight_guard: InFlightSessionGuard,
+}
+
+impl PendingNotarization {
+    /// Fetch the configuration data that was submitted to /session, and authorize the notarization
+    async fn start(
+        notary_globals: NotaryGlobals,
+        params: NotarizationRequestQuery,
+    ) -> Result<Self, NotaryServerError> {
+        // Sessions are stored in the namespace of the tenant that initialized them
+        let notary_globals = notary_globals
+            .tenant(params.tenant_id.as_deref())
+            .inspect_err(|err| error!("{err}"))?;
+        let session_id = params.session_id;
+        // Track the notarization so that a graceful shutdown waits for it. This is done before the
+        // session is removed from the store so that it can be notarized by another instance
+        let Some(in_flight_guard) = notary_globals.shutdown.begin_session() else {
+            error!(
+                ?session_id,
+                "Notarization rejected as the server is shutting down"
+            );
+            return Err(NotaryServerError::ShuttingDown);
+        };
+        // Fetch the configuration data from the store using the session_id
+        // This also removes the configuration data from the store as each session_id can only be used once
+        let session_data = match notary_globals.store.remove(&session_id).await {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                let err_msg = format!("Session id {} does not exist", session_id);
+                error!(err_msg);
+                return Err(NotaryServerError::BadProverRequest(err_msg));
+            }
+            Err(err) => {
+                error!(?session_id, "Failed to fetch session from the store: {err}");
+                return Err(NotaryServerError::from(err));
+            }
+        };
+        // Only the hash of the API key bound to this session is stored, the key itself is found in
+        // the whitelist, unless it was removed since the session was initialized
+        let api_key = session_data
+            .api_key_hash
+            .as_deref()
+            .map(|api_key_hash| whitelisted_api_key(&notary_globals, api_key_hash));
+        let gated_session = GatedSession {
+            session_id: session_id.clone(),
+            tenant_id: params.tenant_id,
+            api_key: api_key.clone().flatten(),
+            server_dns: session_data.server_dns,
+            max_sent_data: session_data.max_sent_data,
+            max_recv_data: session_data.max_recv_data,
+        };
+        // From now on the session is finalized, whether it is rejected or notarized
+        let gate_guard = notary_globals
+            .session_gate
+            .clone()
+            .map(|session_gate| GatedSessionGuard::new(session_gate, gated_session.clone()));
+        // The session may not have been removed from the store yet after it expired
+        if is_expired(session_data.created_at, notary_globals.session_ttl) {
+            let err_msg = format!("Session id {} has expired", session_id);
+            error!(err_msg);
+            return Err(NotaryServerError::BadProverRequest(err_msg));
+        }
+        // Validate the API key bound to this session and reserve a session slot for it
+        let session_guard = match api_key {
+            Some(Some(api_key)) => Some(
+                authorize_notarization(
+                    &notary_globals,
+                    &api_key,
+                    gated_session.max_sent_data,
+                    gated_session.max_recv_data,
+                )
+                .inspect_err(|err| error!(?session_id, "{err}"))?,
+            ),
+            Some(None) => {
+                let err =
+                    NotaryServerError::UnauthorizedProverRequest("Invalid API key.".to_string());
+                error!(?session_id, "{err}");
+                return Err(err);
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            notary_globals,
+            session_id,
+            gated_session,
+            gate_guard,
+            session_guard,
+            in_flight_guard,
+        })
+    }
+
+    /// Complete the WebSocket upgrade and run the notarization over the WebSocket
+    fn run_websocket(self, ws: WebSocketUpgrade) -> Response {
+        ws.on_upgrade(move |socket| async move {
+            let Self {
                 notary_globals,
                 session_id,
-                max_sent_data,
-                max_recv_data,
-            )
-        }),
+                gated_session,
+                gate_guard,
+                session_guard,
+                in_flight_guard,
+            } = self;
+            // Hold the guards until the notarization is finished to release the session slot
+            let _session_guard = session_guard;
+            let _in_flight_guard = in_flight_guard;
+            let shutdown = notary_globals.shutdown.clone();
+            let metrics = notary_globals.metrics.clone();
+            let usage = tokio::select! {
+                usage = websocket_notarize(
+                    socket,
+                    notary_globals,
+                    &gated_session,
+                ) => usage,
+                _ = shutdown.aborted() => {
+                    error!(?session_id, "Notarization aborted as the server is shutting down");
+                    metrics.record_failure(FailureReason::Shutdown);
+                    None
+                }
+            };
+            if let Some(gate_guard) = gate_guard {
+                gate_guard.finalize(usage).await;
+            }
+        })
     }
 }
 
+/// Find the whitelisted API key with the given hash. The whitelist could have been hot reloaded
+/// since the session was initialized
+fn whitelisted_api_key(notary_globals: &NotaryGlobals, api_key_hash: &str) -> Option<String> {
+    notary_globals
+        .authorization_whitelist
+        .as_ref()?
+        .lock()
+        .unwrap()
+        .keys()
+        .find(|api_key| hash_api_key(api_key) == api_key_hash)
+        .cloned()
+}
+
+/// Check that starting the notarization does not exceed the rate limits of the whitelisted API key
+/// bound to a session
+fn authorize_notarization(
+    notary_globals: &NotaryGlobals,
+    api_key: &str,
+    max_sent_data: Option<usize>,
+    max_recv_data: Option<usize>,
+) -> Result<ApiKeySessionGuard, NotaryServerError> {
+    // Account for the limits the verifier is configured with, which are the defaults of the
+    // directions whose limit the prover did not declare
+    let data_size = max_sent_data.unwrap_or(DEFAULT_MAX_SENT_LIMIT)
+        + max_recv_data.unwrap_or(DEFAULT_MAX_RECV_LIMIT);
+
+    let guard = notary_globals
+        .rate_limiter
+        .acquire(api_key, data_size, Utc::now().date_naive())?;
+
+    Ok(guard)
+}
+
 /// Handler to initialize and configure notarization for both TCP and WebSocket clients
 #[debug_handler(state = NotaryGlobals)]
 pub async fn initialize(
     State(notary_globals): State<NotaryGlobals>,
+    Query(tenant_query): Query<TenantQuery>,
+    headers: HeaderMap,
     payload: Result<Json<NotarizationSessionRequest>, JsonRejection>,
 ) -> impl IntoResponse {
     info!(
+        ?tenant_query,
         ?payload,
         "Received request for initializing a notarization session"
     );
 
+    // Use the signing key, limits, authorization, policy and storage of the selected tenant
+    let notary_globals = match notary_globals.tenant(tenant_query.tenant_id.as_deref()) {
+        Ok(notary_globals) => notary_globals,
+        Err(err) => {
+            error!("{err}");
+            return err.into_response();
+        }
+    };
+
+    // Don't start sessions that could not complete before the server shuts down
+    if notary_globals.shutdown.is_draining() {
+        error!("Notarization session rejected as the server is shutting down");
+        return NotaryServerError::ShuttingDown.into_response();
+    }
+
     // Parse the body payload
     let payload = match payload {
         Ok(payload) => payload,
@@ -145,20 +412,121 @@ pub async fn initialize(
         }
     }
 
+    // Ensure that the data sent and received are each within the global limit of their direction.
+    // The prover must declare the limit of a limited direction, as it would otherwise use a
+    // default limit which the notary does not know of
+    for (direction, requested, limit) in [
+        (
+            "sent",
+            payload.max_sent_data,
+            notary_globals.notarization_config.max_sent_data,
+        ),
+        (
+            "received",
+            payload.max_recv_data,
+            notary_globals.notarization_config.max_recv_data,
+        ),
+    ] {
+        let Some(limit) = limit else {
+            continue;
+        };
+        match requested {
+            None => {
+                error!("Max {direction} data was not requested but is limited to {limit:?}");
+                return NotaryServerError::BadProverRequest(format!(
+                    "Max {direction} data must be requested as it is limited by the notary"
+                ))
+                .into_response();
+            }
+            Some(requested) if requested > limit => {
+                error!(
+                    "Max {direction} data requested {:?} exceeds the maximum threshold {:?}",
+                    requested, limit
+                );
+                return NotaryServerError::BadProverRequest(format!(
+                    "Max {direction} data requested exceeds the maximum threshold"
+                ))
+                .into_response();
+            }
+            Some(_) => {}
+        }
+    }
+
+    // Bind the API key to the session so that its limits can be enforced when notarization starts.
+    // The key has already been validated by the auth middleware at this point
+    let api_key = if notary_globals.authorization_whitelist.is_some() {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| std::str::from_utf8(value.as_bytes()).ok())
+            .map(|value| value.to_string())
+    } else {
+        None
+    };
+
+    // Ensure that the server the prover is going to notarize is allowed by the notary's policy
+    // The policy can be hot reloaded, so it is only locked to evaluate it
+    let policy_result = notary_globals.domain_policy.lock().unwrap().evaluate(
+        payload.server_dns.as_deref(),
+        payload.max_sent_data,
+        payload.max_recv_data,
+    );
+    if let Err(err) = policy_result {
+        error!("Notarization session rejected by domain policy: {err}");
+        return NotaryServerError::from(err).into_response();
+    }
+
     let prover_session_id = Uuid::new_v4().to_string();
 
+    // Let the operator's hook bill, meter or reject the session before it is stored. Once the
+    // session is accepted, it is reported as failed if it can't be stored or expires unused
+    let gate_guard = match &notary_globals.session_gate {
+        Some(session_gate) => {
+            let gated_session = GatedSession {
+                session_id: prover_session_id.clone(),
+                tenant_id: tenant_query.tenant_id.clone(),
+                api_key: api_key.clone(),
+                server_dns: payload.server_dns.clone(),
+                max_sent_data: payload.max_sent_data,
+                max_recv_data: payload.max_recv_data,
+            };
+            if let Err(err) = session_gate.on_session_created(&gated_session).await {
+                error!("Notarization session rejected by session gate: {err}");
+                return NotaryServerError::from(err).into_response();
+            }
+            Some(GatedSessionGuard::new(session_gate.clone(), gated_session))
+        }
+        None => None,
+    };
+
     // Store the configuration data in a temporary store
-    notary_globals.store.lock().unwrap().insert(
-        prover_session_id.clone(),
-        SessionData {
-            max_sent_data: payload.max_sent_data,
-            max_recv_data: payload.max_recv_data,
-            created_at: Utc::now(),
-        },
-    );
+    if let Err(err) = notary_globals
+        .store
+        .insert(
+            &prover_session_id,
+            SessionData {
+                max_sent_data: payload.max_sent_data,
+                max_recv_data: payload.max_recv_data,
+                created_at: Utc::now(),
+                // The store may be shared, so the API key itself is not stored
+                api_key_hash: api_key.as_deref().map(hash_api_key),
+                server_dns: payload.server_dns.clone(),
+            },
+        )
+        .await
+    {
+        error!("Failed to store notarization session: {err}");
+        return NotaryServerError::from(err).into_response();
+    }
 
     trace!("Latest store state: {:?}", notary_globals.store);
 
+    tokio::spawn(expire_session(
+        notary_globals.store.clone(),
+        prover_session_id.clone(),
+        notary_globals.session_ttl,
+        gate_guard,
+    ));
+
     // Return the session id in the response to the client
     (
         StatusCode::OK,
@@ -169,33 +537,613 @@ pub async fn initialize(
         .into_response()
 }
 
-/// Run the notarization
+/// Whether a session created at the given time has expired
+fn is_expired(created_at: DateTime<Utc>, session_ttl: Duration) -> bool {
+    Utc::now()
+        .signed_duration_since(created_at)
+        .to_std()
+        .is_ok_and(|age| age > session_ttl)
+}
+
+/// Remove a session from the store once it expires, and report it as failed to the session gate if
+/// it was not used to notarize, so that the gate releases what it holds for the session
+async fn expire_session(
+    store: Arc<dyn SessionStore>,
+    session_id: String,
+    session_ttl: Duration,
+    gate_guard: Option<GatedSessionGuard>,
+) {
+    tokio::time::sleep(session_ttl).await;
+    match store.remove(&session_id).await {
+        Ok(Some(_)) => debug!(
+            ?session_id,
+            "Session expired without being used to notarize"
+        ),
+        // The session was used to notarize, possibly by another instance, which finalizes it
+        Ok(None) => {
+            if let Some(gate_guard) = gate_guard {
+                gate_guard.disarm();
+            }
+            return;
+        }
+        // The session is released rather than held forever
+        Err(err) => error!(
+            ?session_id,
+            "Failed to remove expired session from the store: {err}"
+        ),
+    }
+    if let Some(gate_guard) = gate_guard {
+        gate_guard.finalize(None).await;
+    }
+}
+
+/// Run the notarization of a session with the configuration of the notary, recording its outcome
+/// in the metrics, and return the number of bytes notarized
+///
+/// The span carries the session id, which the prover also uses as the id of its session, so traces
+/// of both parties can be correlated
+#[instrument(level = "info", name = "notarization", skip_all, fields(session_id = %session.session_id))]
 pub async fn notary_service<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
-    signing_key: &SigningKey,
-    session_id: &str,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
-) -> Result<(), NotaryServerError> {
+    notary_globals: &NotaryGlobals,
+    session: &GatedSession,
+) -> Result<SessionUsage, NotaryServerError> {
+    let session_id = &session.session_id;
+    let notarization_config = &notary_globals.notarization_config;
+    let metrics = &notary_globals.metrics;
+    let signing_key = &notary_globals.notary_signing_key;
     debug!(?session_id, "Starting notarization...");
 
+    // Record the session as active and time it until this function returns
+    let _session_metrics_guard = metrics.start_session();
+
     let mut config_builder = VerifierConfig::builder();
 
     config_builder = config_builder.id(session_id);
 
-    if let Some(max_sent_data) = max_sent_data {
+    if let Some(max_sent_data) = session.max_sent_data {
         config_builder = config_builder.max_sent_data(max_sent_data);
     }
 
-    if let Some(max_recv_data) = max_recv_data {
+    if let Some(max_recv_data) = session.max_recv_data {
         config_builder = config_builder.max_recv_data(max_recv_data);
     }
 
-    let config = config_builder.build()?;
+    if let Some(mpc_concurrency) = notarization_config.mpc_concurrency {
+        config_builder = config_builder.mpc_concurrency(mpc_concurrency);
+    }
+
+    if let Some(max_records) = notarization_config.max_records {
+        config_builder = config_builder.max_records(max_records);
+    }
+
+    if let Some(max_ots) = notarization_config.max_ots {
+        config_builder = config_builder.max_ots(max_ots);
+    }
+
+    if let Some(max_session_duration_secs) = notarization_config.max_session_duration_secs {
+        config_builder =
+            config_builder.max_session_duration(Duration::from_secs(max_session_duration_secs));
+    }
+
+    config_builder = config_builder.compression(notarization_config.compression);
+
+    if let Some(key_id) = notary_globals.notary_key_id.clone() {
+        config_builder = config_builder.key_id(key_id);
+    }
+
+    if let Some(notary_did) = notary_globals.notary_did.clone() {
+        config_builder = config_builder.notary_did(notary_did);
+    }
+
+    // Bind the attestation to the server declared to the domain policy, which is otherwise only
+    // the prover's word
+    if let Some(server_dns) = session.server_dns.clone() {
+        config_builder = config_builder.server_name(server_dns);
+    }
 
-    Verifier::new(config)
-        .notarize::<_, Signature>(socket.compat(), signing_key)
-        .await?;
+    if let Some(time_source) = notary_globals.time_source.clone() {
+        config_builder = config_builder.time_source(time_source);
+    }
 
-    Ok(())
+    // The header is recorded before it is signed, so that every attestation is in the log
+    if let Some(transparency_log) = notary_globals.transparency_log.clone() {
+        config_builder = config_builder.session_log(transparency_log);
+    }
+
+    let config = match config_builder.build() {
+        Ok(config) => config,
+        Err(err) => {
+            metrics.record_failure(FailureReason::Config);
+            return Err(err.into());
+        }
+    };
+
+    let verifier = Verifier::new(config)
+        .with_abort_receipts::<_, Signature>(signing_key.clone())
+        .with_session_receipts::<_, Signature>(signing_key.clone());
+    let timings = verifier.timings_handle();
+
+    let socket = metrics.meter(socket).compat();
+    let result = async {
+        let verifier = verifier.setup(socket).await?;
+        if let Some(receipt) = verifier.opened_receipt() {
+            store_receipt(
+                notary_globals,
+                session_id,
+                SessionReceipt::Opened(receipt.clone()),
+            )
+            .await;
+        }
+        let verifier = verifier.run().await?.start_notarize();
+
+        // The receipts are still signed with the signing key, only the session header is signed
+        // with BLS
+        #[cfg(feature = "bls")]
+        if let Some(bls_signing_key) = notary_globals.bls_signing_key.as_deref() {
+            return verifier.finalize::<BlsSignature>(bls_signing_key).await;
+        }
+
+        verifier.finalize::<Signature>(signing_key).await
+    }
+    .await;
+
+    if let Err(VerifierError::Aborted(Aborted {
+        receipt: Some(receipt),
+        ..
+    })) = &result
+    {
+        store_receipt(
+            notary_globals,
+            session_id,
+            SessionReceipt::Aborted(receipt.clone()),
+        )
+        .await;
+    }
+
+    match result {
+        Ok(header) => {
+            info!(timings = ?timings.get(), "Notarization completed");
+            metrics.record_success(header.sent_len(), header.recv_len());
+            Ok(SessionUsage {
+                sent_len: header.sent_len(),
+                recv_len: header.recv_len(),
+            })
+        }
+        Err(err) => {
+            metrics.record_failure(FailureReason::from(&err));
+            Err(err.into())
+        }
+    }
+}
+
+/// Keep a receipt signed for a session in the session store, as the operator's record of what the
+/// notary signed
+async fn store_receipt(notary_globals: &NotaryGlobals, session_id: &str, receipt: SessionReceipt) {
+    info!(?receipt, "Signed session receipt");
+    if let Err(err) = notary_globals
+        .store
+        .insert_receipt(session_id, receipt)
+        .await
+    {
+        error!(?session_id, "Failed to store session receipt: {err}");
+    }
+}
+
+/// Get the transparency log of the tenant selected by a request
+fn tenant_transparency_log(
+    notary_globals: &NotaryGlobals,
+    tenant_id: Option<&str>,
+) -> Result<(NotaryGlobals, Arc<TransparencyLog>), NotaryServerError> {
+    let notary_globals = notary_globals.tenant(tenant_id)?;
+    let transparency_log = notary_globals.transparency_log.clone().ok_or_else(|| {
+        NotaryServerError::NotFound("Transparency log is not enabled".to_string())
+    })?;
+
+    Ok((notary_globals, transparency_log))
+}
+
+/// Handler to return the current head of the transparency log, signed by the notary
+pub async fn transparency_log_head(
+    State(notary_globals): State<NotaryGlobals>,
+    Query(tenant_query): Query<TenantQuery>,
+) -> Response {
+    let (notary_globals, transparency_log) =
+        match tenant_transparency_log(&notary_globals, tenant_query.tenant_id.as_deref()) {
+            Ok(log) => log,
+            Err(err) => return err.into_response(),
+        };
+
+    let head = transparency_log
+        .head(Utc::now().timestamp() as u64)
+        .sign::<Signature>(&notary_globals.notary_signing_key);
+
+    (StatusCode::OK, Json(head)).into_response()
+}
+
+/// Handler to return the entries of the transparency log, so that relying parties can audit
+/// which attestations the notary issued
+pub async fn transparency_log_entries(
+    State(notary_globals): State<NotaryGlobals>,
+    Query(query): Query<LogEntriesQuery>,
+) -> Response {
+    let (_, transparency_log) =
+        match tenant_transparency_log(&notary_globals, query.tenant_id.as_deref()) {
+            Ok(log) => log,
+            Err(err) => return err.into_response(),
+        };
+
+    let limit = query
+        .limit
+        .unwrap_or(MAX_LOG_ENTRIES_PER_REQUEST)
+        .min(MAX_LOG_ENTRIES_PER_REQUEST);
+    let entries = transparency_log.entries(query.start.unwrap_or_default(), limit);
+
+    (StatusCode::OK, Json(entries)).into_response()
+}
+
+/// Handler to return the proof that a session is included in the transparency log, together with
+/// the signed head of the log the proof leads to
+pub async fn transparency_log_proof(
+    State(notary_globals): State<NotaryGlobals>,
+    Query(query): Query<InclusionProofQuery>,
+) -> Response {
+    let (notary_globals, transparency_log) =
+        match tenant_transparency_log(&notary_globals, query.tenant_id.as_deref()) {
+            Ok(log) => log,
+            Err(err) => return err.into_response(),
+        };
+
+    let header_hash: [u8; 32] = match hex::decode(&query.session)
+        .ok()
+        .and_then(|hash| hash.try_into().ok())
+    {
+        Some(header_hash) => header_hash,
+        None => {
+            return NotaryServerError::BadProverRequest(
+                "Session must be a hex encoded 32 byte hash".to_string(),
+            )
+            .into_response()
+        }
+    };
+
+    match transparency_log.prove(&header_hash, Utc::now().timestamp() as u64) {
+        Some((proof, head)) => (
+            StatusCode::OK,
+            Json(InclusionProofResponse {
+                proof,
+                head: head.sign::<Signature>(&notary_globals.notary_signing_key),
+            }),
+        )
+            .into_response(),
+        None => NotaryServerError::NotFound(format!(
+            "Session {} is not in the transparency log",
+            query.session
+        ))
+        .into_response(),
+    }
+}
+
+/// Handler to return the proof that the current head of the transparency log extends the head of
+/// the log when it had a given number of entries, together with the signed current head
+pub async fn transparency_log_consistency(
+    State(notary_globals): State<NotaryGlobals>,
+    Query(query): Query<ConsistencyProofQuery>,
+) -> Response {
+    let (notary_globals, transparency_log) =
+        match tenant_transparency_log(&notary_globals, query.tenant_id.as_deref()) {
+            Ok(log) => log,
+            Err(err) => return err.into_response(),
+        };
+
+    match transparency_log.prove_consistency(query.first, Utc::now().timestamp() as u64) {
+        Some((proof, head)) => (
+            StatusCode::OK,
+            Json(ConsistencyProofResponse {
+                proof,
+                head: head.sign::<Signature>(&notary_globals.notary_signing_key),
+            }),
+        )
+            .into_response(),
+        None => NotaryServerError::NotFound(format!(
+            "Transparency log has fewer than {} entries",
+            query.first
+        ))
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+    use crate::{
+        config::NotarizationProperties,
+        domain::{
+            auth::{ApiKeyRateLimiter, AuthorizationWhitelistRecord},
+            gate::{
+                test::{gated_session, RecordingGate},
+                SessionGate,
+            },
+            notary::ClientType,
+            policy::DomainPolicy,
+        },
+    };
+
+    fn notary_globals(session_gate: Arc<RecordingGate>) -> NotaryGlobals {
+        NotaryGlobals::new(
+            SigningKey::from_bytes(&[1u8; 32].into()).unwrap(),
+            NotarizationProperties {
+                max_transcript_size: 1 << 14,
+                max_sent_data: None,
+                max_recv_data: None,
+                mpc_concurrency: None,
+                max_records: None,
+                max_ots: None,
+                max_session_duration_secs: None,
+                compression: false,
+            },
+            Some(Arc::new(Mutex::new(HashMap::new()))),
+            ApiKeyRateLimiter::new(None, None),
+            DomainPolicy::default(),
+            NotaryMetrics::new().unwrap(),
+        )
+        .with_session_gate(Some(session_gate as Arc<dyn SessionGate>))
+    }
+
+    fn session_data(created_at: DateTime<Utc>, api_key: &str) -> SessionData {
+        SessionData {
+            max_sent_data: None,
+            max_recv_data: None,
+            created_at,
+            api_key_hash: Some(hash_api_key(api_key)),
+            server_dns: None,
+        }
+    }
+
+    fn query(session_id: &str) -> NotarizationRequestQuery {
+        NotarizationRequestQuery {
+            session_id: session_id.to_string(),
+            tenant_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejected_session_is_finalized() {
+        let gate = Arc::new(RecordingGate::default());
+        let notary_globals = notary_globals(gate.clone());
+
+        // The API key was removed from the whitelist since the session was initialized
+        notary_globals
+            .store
+            .insert("unauthorized", session_data(Utc::now(), "removed-key"))
+            .await
+            .unwrap();
+        assert!(
+            PendingNotarization::start(notary_globals.clone(), query("unauthorized"))
+                .await
+                .is_err()
+        );
+
+        // The session expired but was not removed from the store yet
+        let created_at = Utc::now() - chrono::Duration::seconds(2);
+        notary_globals
+            .store
+            .insert("expired", session_data(created_at, "removed-key"))
+            .await
+            .unwrap();
+        assert!(PendingNotarization::start(
+            notary_globals.with_session_ttl(Duration::from_secs(1)),
+            query("expired")
+        )
+        .await
+        .is_err());
+
+        tokio::task::yield_now().await;
+        assert_eq!(
+            *gate.finalized.lock().unwrap(),
+            vec![
+                ("unauthorized".to_string(), None),
+                ("expired".to_string(), None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limited_direction_must_be_requested() {
+        let mut notary_globals = notary_globals(Arc::new(RecordingGate::default()));
+        notary_globals.notarization_config.max_sent_data = Some(1 << 10);
+
+        let request = |max_sent_data, max_recv_data| {
+            initialize(
+                State(notary_globals.clone()),
+                Query(TenantQuery { tenant_id: None }),
+                HeaderMap::new(),
+                Ok(Json(NotarizationSessionRequest {
+                    client_type: ClientType::Tcp,
+                    max_sent_data,
+                    max_recv_data,
+                    server_dns: None,
+                })),
+            )
+        };
+
+        // The limit of the received data is not configured, so it may be left to the default
+        for (max_sent_data, max_recv_data) in
+            [(None, None), (None, Some(1 << 10)), (Some(1 << 11), None)]
+        {
+            let response = initialize(max_sent_data, max_recv_data)
+                .await
+                .into_response();
+            assert_eq!(
+                response.status(),
+                StatusCode::BAD_REQUEST,
+                "{max_sent_data:?} sent and {max_recv_data:?} received"
+            );
+        }
+
+        let response = request(Some(1 << 10), None).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The session is stored with the requested limits only
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let session: NotarizationSessionResponse = serde_json::from_slice(&body).unwrap();
+        let stored = notary_globals
+            .store
+            .remove(&session.session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.max_sent_data, Some(1 << 10));
+        assert_eq!(stored.max_recv_data, None);
+    }
+
+    /// Extracts the upgrade of a WebSocket request to /notarize from a page with the given origin
+    async fn websocket_upgrade(origin: Option<&str>) -> (ProtocolUpgrade, HeaderMap) {
+        let mut request = axum::http::Request::builder()
+            .uri("/notarize?sessionId=unknown")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(origin) = origin {
+            request = request.header(header::ORIGIN, origin);
+        }
+        let mut request = request.body(()).unwrap();
+        let on_upgrade = hyper::upgrade::on(&mut request);
+        request.extensions_mut().insert(on_upgrade);
+
+        let (mut parts, _) = request.into_parts();
+        let upgrade = ProtocolUpgrade::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_or_else(|_| panic!("request is a websocket upgrade"));
+        (upgrade, parts.headers)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrade_checks_origin() {
+        let notary_globals = notary_globals(Arc::new(RecordingGate::default()))
+            .with_websocket_config(WebSocketProperties {
+                allowed_origins: vec!["https://example.com".to_string()],
+                ..Default::default()
+            });
+
+        // The unknown session is only rejected once the origin is allowed
+        for (origin, status) in [
+            (Some("https://evil.com"), StatusCode::FORBIDDEN),
+            (Some("https://example.com"), StatusCode::BAD_REQUEST),
+            (None, StatusCode::BAD_REQUEST),
+        ] {
+            let (upgrade, headers) = websocket_upgrade(origin).await;
+            let response = upgrade_protocol(
+                upgrade,
+                State(notary_globals.clone()),
+                headers,
+                Query(query("unknown")),
+            )
+            .await;
+            assert_eq!(response.status(), status, "/notarize from {origin:?}");
+
+            let (ProtocolUpgrade::Ws(ws), headers) = websocket_upgrade(origin).await else {
+                panic!("request is a websocket upgrade");
+            };
+            let response = upgrade_websocket(
+                ws,
+                State(notary_globals.clone()),
+                headers,
+                Query(query("unknown")),
+            )
+            .await;
+            assert_eq!(response.status(), status, "/notarize/ws from {origin:?}");
+        }
+
+        // Pages are rejected unless their origin is allowed
+        let (upgrade, headers) = websocket_upgrade(Some("https://example.com")).await;
+        let response = upgrade_protocol(
+            upgrade,
+            State(notary_globals.with_websocket_config(WebSocketProperties::default())),
+            headers,
+            Query(query("unknown")),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_session_api_key_is_found_by_hash() {
+        let notary_globals = notary_globals(Arc::new(RecordingGate::default()));
+        notary_globals
+            .authorization_whitelist
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .insert(
+                "valid-key".to_string(),
+                AuthorizationWhitelistRecord {
+                    name: "test".to_string(),
+                    api_key: "valid-key".to_string(),
+                    created_at: "2024-01-01".to_string(),
+                },
+            );
+
+        let data = session_data(Utc::now(), "valid-key");
+        assert!(!serde_json::to_string(&data).unwrap().contains("valid-key"));
+        notary_globals.store.insert("session", data).await.unwrap();
+
+        let session = PendingNotarization::start(notary_globals, query("session"))
+            .await
+            .unwrap();
+        assert_eq!(session.gated_session.api_key.as_deref(), Some("valid-key"));
+        assert!(session.session_guard.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_finalized() {
+        let gate = Arc::new(RecordingGate::default());
+        let store = notary_globals(gate.clone()).store;
+        let guard = |session_id: &str| {
+            Some(GatedSessionGuard::new(
+                gate.clone(),
+                gated_session(session_id),
+            ))
+        };
+
+        // An unused session is removed and finalized once it expires
+        store
+            .insert("unused", session_data(Utc::now(), "key"))
+            .await
+            .unwrap();
+        expire_session(
+            store.clone(),
+            "unused".to_string(),
+            Duration::ZERO,
+            guard("unused"),
+        )
+        .await;
+        assert!(store.remove("unused").await.unwrap().is_none());
+
+        // A session used to notarize is finalized by the notarization instead
+        store
+            .insert("used", session_data(Utc::now(), "key"))
+            .await
+            .unwrap();
+        store.remove("used").await.unwrap();
+        expire_session(
+            store.clone(),
+            "used".to_string(),
+            Duration::ZERO,
+            guard("used"),
+        )
+        .await;
+
+        tokio::task::yield_now().await;
+        assert_eq!(
+            *gate.finalized.lock().unwrap(),
+            vec![("unused".to_string(), None)]
+        );
+    }
 }
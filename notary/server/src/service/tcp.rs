@@ -10,7 +10,14 @@ use hyper_util::rt::TokioIo;
 use std::future::Future;
 use tracing::{debug, error, info};
 
-use crate::{domain::notary::NotaryGlobals, service::notary_service, NotaryServerError};
+use crate::{
+    domain::{
+        gate::{GatedSession, SessionUsage},
+        notary::NotaryGlobals,
+    },
+    service::notary_service,
+    NotaryServerError,
+};
 
 /// Custom extractor used to extract underlying TCP connection for TCP client — using the same upgrade primitives used by
 /// the WebSocket implementation where the underlying TCP connection (wrapped in an Upgraded object) only gets polled as an OnUpgrade future
@@ -78,29 +85,23 @@ impl TcpUpgrade {
     }
 }
 
-/// Perform notarization using the extracted tcp connection
+/// Perform notarization using the extracted tcp connection, returning the number of bytes notarized if it
+/// succeeded
 pub async fn tcp_notarize(
     stream: TokioIo<Upgraded>,
     notary_globals: NotaryGlobals,
-    session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
-) {
+    session: &GatedSession,
+) -> Option<SessionUsage> {
+    let session_id = &session.session_id;
     debug!(?session_id, "Upgraded to tcp connection");
-    match notary_service(
-        stream,
-        &notary_globals.notary_signing_key,
-        &session_id,
-        max_sent_data,
-        max_recv_data,
-    )
-    .await
-    {
-        Ok(_) => {
+    match notary_service(stream, &notary_globals, session).await {
+        Ok(usage) => {
             info!(?session_id, "Successful notarization using tcp!");
+            Some(usage)
         }
         Err(err) => {
             error!(?session_id, "Failed notarization using tcp: {err}");
+            None
         }
     }
 }
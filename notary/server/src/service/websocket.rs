@@ -2,35 +2,32 @@ use tracing::{debug, error, info};
 use ws_stream_tungstenite::WsStream;
 
 use crate::{
-    domain::notary::NotaryGlobals,
+    domain::{
+        gate::{GatedSession, SessionUsage},
+        notary::NotaryGlobals,
+    },
     service::{axum_websocket::WebSocket, notary_service},
 };
 
-/// Perform notarization using the established websocket connection
+/// Perform notarization using the established websocket connection, returning the number of bytes notarized if it
+/// succeeded
 pub async fn websocket_notarize(
     socket: WebSocket,
     notary_globals: NotaryGlobals,
-    session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
-) {
+    session: &GatedSession,
+) -> Option<SessionUsage> {
+    let session_id = &session.session_id;
     debug!(?session_id, "Upgraded to websocket connection");
     // Wrap the websocket in WsStream so that we have AsyncRead and AsyncWrite implemented
     let stream = WsStream::new(socket.into_inner());
-    match notary_service(
-        stream,
-        &notary_globals.notary_signing_key,
-        &session_id,
-        max_sent_data,
-        max_recv_data,
-    )
-    .await
-    {
-        Ok(_) => {
+    match notary_service(stream, &notary_globals, session).await {
+        Ok(usage) => {
             info!(?session_id, "Successful notarization using websocket!");
+            Some(usage)
         }
         Err(err) => {
             error!(?session_id, "Failed notarization using websocket: {err}");
+            None
         }
     }
 }
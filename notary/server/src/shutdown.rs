@@ -0,0 +1,202 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Seconds that in-flight notarizations are given to complete after a shutdown is requested, if
+/// not configured
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+/// Controller of the graceful shutdown of the notary server
+///
+/// Once a shutdown is requested, the server stops accepting connections and new sessions, and lets
+/// the in-flight notarizations complete until the deadline passed to [`ShutdownController::drain`].
+/// The notarizations still running at the deadline are aborted, which releases their rate limit
+/// slots and reports them as failed to the session gate. Sessions initialized with /session but
+/// not yet notarized are left in the session store, so with a shared store they can be notarized
+/// by another instance
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownController {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    requested: CancellationToken,
+    aborted: CancellationToken,
+    in_flight: AtomicUsize,
+    drained: Notify,
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the server to shut down, so that it stops accepting new sessions
+    pub fn request_shutdown(&self) {
+        self.inner.requested.cancel();
+    }
+
+    /// Whether a shutdown was requested, in which case new sessions are rejected
+    pub fn is_draining(&self) -> bool {
+        self.inner.requested.is_cancelled()
+    }
+
+    /// Wait until a shutdown is requested
+    pub async fn requested(&self) {
+        self.inner.requested.cancelled().await
+    }
+
+    /// Wait until the in-flight notarizations are aborted because the shutdown deadline passed
+    pub async fn aborted(&self) {
+        self.inner.aborted.cancelled().await
+    }
+
+    /// Register an in-flight notarization, which is tracked until the returned guard is dropped.
+    /// Returns none if a shutdown was requested
+    pub fn begin_session(&self) -> Option<InFlightSessionGuard> {
+        if self.is_draining() {
+            return None;
+        }
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        Some(InFlightSessionGuard {
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Number of notarizations in flight
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Request a shutdown and wait for the in-flight notarizations to complete, aborting those
+    /// still running after the timeout. Returns the number of aborted notarizations
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.request_shutdown();
+        info!(
+            in_flight = self.in_flight(),
+            ?timeout,
+            "Draining in-flight notarizations"
+        );
+
+        if tokio::time::timeout(timeout, self.wait_drained())
+            .await
+            .is_ok()
+        {
+            info!("All in-flight notarizations completed");
+            return 0;
+        }
+
+        let aborted = self.in_flight();
+        warn!(
+            aborted,
+            "Shutdown deadline passed, aborting in-flight notarizations"
+        );
+        self.inner.aborted.cancel();
+        // Let the aborted notarizations release their resources
+        self.wait_drained().await;
+
+        aborted
+    }
+
+    async fn wait_drained(&self) {
+        loop {
+            // Created before checking the count so that a notification in between is not missed
+            let drained = self.inner.drained.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            drained.await;
+        }
+    }
+}
+
+/// Guard of an in-flight notarization registered with [`ShutdownController::begin_session`]
+#[derive(Debug)]
+pub struct InFlightSessionGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for InFlightSessionGuard {
+    fn drop(&mut self) {
+        if self.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.drained.notify_waiters();
+        }
+    }
+}
+
+/// Wait for a termination signal (Ctrl-C, or SIGTERM on unix) and request a shutdown
+pub async fn shutdown_on_signal(shutdown: ShutdownController) {
+    let ctrl_c = tokio::signal::ctrl_c();
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = ctrl_c => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(err) => {
+                warn!("Failed to listen for SIGTERM: {err}");
+                let _ = ctrl_c.await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = ctrl_c.await;
+
+    info!("Received termination signal, shutting down");
+    shutdown.request_shutdown();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_completed_sessions() {
+        let shutdown = ShutdownController::new();
+        let guard = shutdown.begin_session().unwrap();
+
+        let drain = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(10)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // New sessions are rejected while draining
+        assert!(shutdown.is_draining());
+        assert!(shutdown.begin_session().is_none());
+
+        drop(guard);
+        assert_eq!(drain.await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_aborts_sessions_after_timeout() {
+        let shutdown = ShutdownController::new();
+        let guard = shutdown.begin_session().unwrap();
+
+        let session = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                // A notarization that only ends when it is aborted
+                shutdown.aborted().await;
+                drop(guard);
+            }
+        });
+
+        assert_eq!(shutdown.drain(Duration::from_millis(50)).await, 1);
+        session.await.unwrap();
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+}
@@ -0,0 +1,438 @@
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    config::{SessionStoreKind, SessionStoreProperties},
+    domain::notary::{SessionData, SessionReceipt},
+};
+
+/// Seconds that shared backends keep sessions after they expire, so that the notary instance which
+/// initialized an unused session can still remove it to report it as failed to the session gate
+const EXPIRED_SESSION_RETENTION_SECS: u64 = 60;
+
+/// Storage of the configuration data of sessions, from the /session request until the /notarize
+/// request of the same session, and of the receipts the notary signed for the sessions
+///
+/// Shared backends let the two requests of a session be served by different notary instances,
+/// e.g. behind a load balancer
+#[async_trait]
+pub trait SessionStore: Debug + Send + Sync {
+    /// Store the configuration data of a session
+    async fn insert(&self, session_id: &str, data: SessionData) -> Result<()>;
+
+    /// Remove and return the configuration data of a session, so that each session id can only be
+    /// used once. Returns none if the session does not exist or was removed by the backend after it
+    /// expired, expired sessions which are still stored being returned
+    async fn remove(&self, session_id: &str) -> Result<Option<SessionData>>;
+
+    /// Store a receipt signed for a session. Receipts don't expire with the session, they are the
+    /// record of what the notary signed
+    async fn insert_receipt(&self, session_id: &str, receipt: SessionReceipt) -> Result<()>;
+
+    /// Return the receipts signed for a session, in the order they were stored
+    async fn receipts(&self, session_id: &str) -> Result<Vec<SessionReceipt>>;
+}
+
+/// Build the session store of a tenant, whose sessions are stored separately from the sessions of
+/// other tenants
+///
+/// `namespace` identifies the tenant in shared backends
+pub async fn build_session_store(
+    config: &SessionStoreProperties,
+    namespace: &str,
+) -> Result<Arc<dyn SessionStore>> {
+    let store: Arc<dyn SessionStore> = match config.backend {
+        SessionStoreKind::Memory => Arc::new(MemorySessionStore::default()),
+        #[cfg(feature = "redis")]
+        SessionStoreKind::Redis => Arc::new(
+            redis_store::RedisSessionStore::connect(
+                &config.url,
+                namespace,
+                config.session_ttl_secs,
+            )
+            .await?,
+        ),
+        #[cfg(feature = "postgres")]
+        SessionStoreKind::Postgres => Arc::new(
+            postgres_store::PostgresSessionStore::connect(
+                &config.url,
+                namespace,
+                config.session_ttl_secs,
+            )
+            .await?,
+        ),
+        #[allow(unreachable_patterns)]
+        ref backend => {
+            let _ = namespace;
+            return Err(eyre!(
+                "Session store backend {backend:?} is not enabled, rebuild the notary server with the corresponding feature"
+            ));
+        }
+    };
+
+    Ok(store)
+}
+
+/// Session store keeping sessions in the memory of a single notary instance
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, SessionData>>,
+    receipts: Mutex<HashMap<String, Vec<SessionReceipt>>>,
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn insert(&self, session_id: &str, data: SessionData) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), data);
+        Ok(())
+    }
+
+    async fn remove(&self, session_id: &str) -> Result<Option<SessionData>> {
+        Ok(self.sessions.lock().unwrap().remove(session_id))
+    }
+
+    async fn insert_receipt(&self, session_id: &str, receipt: SessionReceipt) -> Result<()> {
+        self.receipts
+            .lock()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .push(receipt);
+        Ok(())
+    }
+
+    async fn receipts(&self, session_id: &str) -> Result<Vec<SessionReceipt>> {
+        Ok(self
+            .receipts
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "redis")]
+mod redis_store {
+    use super::*;
+    use redis::aio::ConnectionManager;
+
+    /// Session store keeping sessions in Redis, which removes them after they expired
+    pub struct RedisSessionStore {
+        connection: ConnectionManager,
+        namespace: String,
+        session_ttl_secs: u64,
+    }
+
+    impl Debug for RedisSessionStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RedisSessionStore")
+                .field("namespace", &self.namespace)
+                .field("session_ttl_secs", &self.session_ttl_secs)
+                .finish()
+        }
+    }
+
+    impl RedisSessionStore {
+        pub async fn connect(url: &str, namespace: &str, session_ttl_secs: u64) -> Result<Self> {
+            let client = redis::Client::open(url)?;
+            let connection = ConnectionManager::new(client).await?;
+            Ok(Self {
+                connection,
+                namespace: namespace.to_string(),
+                session_ttl_secs,
+            })
+        }
+
+        fn key(&self, session_id: &str) -> String {
+            format!("notary-session:{}:{session_id}", self.namespace)
+        }
+
+        fn receipts_key(&self, session_id: &str) -> String {
+            format!("notary-receipts:{}:{session_id}", self.namespace)
+        }
+    }
+
+    #[async_trait]
+    impl SessionStore for RedisSessionStore {
+        async fn insert(&self, session_id: &str, data: SessionData) -> Result<()> {
+            let value = serde_json::to_vec(&data)?;
+            redis::cmd("SET")
+                .arg(self.key(session_id))
+                .arg(value)
+                .arg("EX")
+                .arg(self.session_ttl_secs + EXPIRED_SESSION_RETENTION_SECS)
+                .query_async::<_, ()>(&mut self.connection.clone())
+                .await?;
+            Ok(())
+        }
+
+        async fn remove(&self, session_id: &str) -> Result<Option<SessionData>> {
+            // GETDEL is atomic, so that a session can't be used by two concurrent requests
+            let value: Option<Vec<u8>> = redis::cmd("GETDEL")
+                .arg(self.key(session_id))
+                .query_async(&mut self.connection.clone())
+                .await?;
+            Ok(value
+                .map(|value| serde_json::from_slice(&value))
+                .transpose()?)
+        }
+
+        async fn insert_receipt(&self, session_id: &str, receipt: SessionReceipt) -> Result<()> {
+            let value = serde_json::to_vec(&receipt)?;
+            redis::cmd("RPUSH")
+                .arg(self.receipts_key(session_id))
+                .arg(value)
+                .query_async::<_, ()>(&mut self.connection.clone())
+                .await?;
+            Ok(())
+        }
+
+        async fn receipts(&self, session_id: &str) -> Result<Vec<SessionReceipt>> {
+            let values: Vec<Vec<u8>> = redis::cmd("LRANGE")
+                .arg(self.receipts_key(session_id))
+                .arg(0)
+                .arg(-1)
+                .query_async(&mut self.connection.clone())
+                .await?;
+            Ok(values
+                .iter()
+                .map(|value| serde_json::from_slice(value))
+                .collect::<Result<_, _>>()?)
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres_store {
+    use super::*;
+    use chrono::Utc;
+    use postgres::{Client, NoTls};
+    use std::{sync::Weak, time::Duration};
+    use tracing::error;
+
+    /// Interval at which the sessions that expired without being used are deleted
+    const EXPIRED_SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Session store keeping sessions in a Postgres table, from which they are removed after they
+    /// expired
+    #[derive(Debug)]
+    pub struct PostgresSessionStore {
+        client: Arc<Client>,
+        namespace: String,
+        session_ttl_secs: u64,
+    }
+
+    impl PostgresSessionStore {
+        pub async fn connect(url: &str, namespace: &str, session_ttl_secs: u64) -> Result<Self> {
+            let (client, connection) = postgres::connect(url, NoTls).await?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    error!("Session store connection to postgres failed: {err}");
+                }
+            });
+
+            client
+                .batch_execute(
+                    "CREATE TABLE IF NOT EXISTS notary_sessions (
+                        namespace TEXT NOT NULL,
+                        session_id TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        expires_at BIGINT NOT NULL,
+                        PRIMARY KEY (namespace, session_id)
+                    );
+                    CREATE INDEX IF NOT EXISTS notary_sessions_expires_at
+                        ON notary_sessions (namespace, expires_at);
+                    CREATE TABLE IF NOT EXISTS notary_session_receipts (
+                        id BIGSERIAL PRIMARY KEY,
+                        namespace TEXT NOT NULL,
+                        session_id TEXT NOT NULL,
+                        receipt TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS notary_session_receipts_session_id
+                        ON notary_session_receipts (namespace, session_id)",
+                )
+                .await?;
+
+            let client = Arc::new(client);
+            tokio::spawn(sweep_expired_sessions(
+                Arc::downgrade(&client),
+                namespace.to_string(),
+            ));
+
+            Ok(Self {
+                client,
+                namespace: namespace.to_string(),
+                session_ttl_secs,
+            })
+        }
+    }
+
+    /// Periodically delete the sessions of a namespace that expired without being used to
+    /// notarize, until the store is dropped
+    async fn sweep_expired_sessions(client: Weak<Client>, namespace: String) {
+        let mut interval = tokio::time::interval(EXPIRED_SESSION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(client) = client.upgrade() else {
+                return;
+            };
+            if let Err(err) = client
+                .execute(
+                    "DELETE FROM notary_sessions WHERE namespace = $1 AND expires_at < $2",
+                    &[&namespace, &Utc::now().timestamp()],
+                )
+                .await
+            {
+                error!("Failed to delete expired sessions from postgres: {err}");
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SessionStore for PostgresSessionStore {
+        async fn insert(&self, session_id: &str, data: SessionData) -> Result<()> {
+            let expires_at = Utc::now().timestamp()
+                + (self.session_ttl_secs + EXPIRED_SESSION_RETENTION_SECS) as i64;
+            self.client
+                .execute(
+                    "INSERT INTO notary_sessions (namespace, session_id, data, expires_at) VALUES ($1, $2, $3, $4)",
+                    &[
+                        &self.namespace,
+                        &session_id,
+                        &serde_json::to_string(&data)?,
+                        &expires_at,
+                    ],
+                )
+                .await?;
+            Ok(())
+        }
+
+        async fn remove(&self, session_id: &str) -> Result<Option<SessionData>> {
+            // DELETE .. RETURNING is atomic, so that a session can't be used by two concurrent
+            // requests
+            let row = self
+                .client
+                .query_opt(
+                    "DELETE FROM notary_sessions WHERE namespace = $1 AND session_id = $2 RETURNING data, expires_at",
+                    &[&self.namespace, &session_id],
+                )
+                .await?;
+            let Some(row) = row else {
+                return Ok(None);
+            };
+            let expires_at: i64 = row.get(1);
+            if expires_at < Utc::now().timestamp() {
+                return Ok(None);
+            }
+            let data: String = row.get(0);
+            Ok(Some(serde_json::from_str(&data)?))
+        }
+
+        async fn insert_receipt(&self, session_id: &str, receipt: SessionReceipt) -> Result<()> {
+            self.client
+                .execute(
+                    "INSERT INTO notary_session_receipts (namespace, session_id, receipt) VALUES ($1, $2, $3)",
+                    &[
+                        &self.namespace,
+                        &session_id,
+                        &serde_json::to_string(&receipt)?,
+                    ],
+                )
+                .await?;
+            Ok(())
+        }
+
+        async fn receipts(&self, session_id: &str) -> Result<Vec<SessionReceipt>> {
+            let rows = self
+                .client
+                .query(
+                    "SELECT receipt FROM notary_session_receipts WHERE namespace = $1 AND session_id = $2 ORDER BY id",
+                    &[&self.namespace, &session_id],
+                )
+                .await?;
+            rows.iter()
+                .map(|row| Ok(serde_json::from_str(row.get::<_, &str>(0))?))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use p256::ecdsa::signature::Signer;
+    use tlsn_core::msg::{SessionLimits, SessionOpenedReceipt, SignedSessionOpenedReceipt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_session_store() {
+        let store = build_session_store(&SessionStoreProperties::default(), "default")
+            .await
+            .unwrap();
+        let data = SessionData {
+            max_sent_data: Some(1024),
+            max_recv_data: None,
+            created_at: Utc::now(),
+            api_key_hash: None,
+            server_dns: Some("example.com".to_string()),
+        };
+
+        store.insert("session", data.clone()).await.unwrap();
+        assert!(store.remove("other").await.unwrap().is_none());
+
+        let removed = store.remove("session").await.unwrap().unwrap();
+        assert_eq!(removed.max_sent_data, data.max_sent_data);
+        assert_eq!(removed.server_dns, data.server_dns);
+
+        // Each session id can only be used once
+        assert!(store.remove("session").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_session_store_receipts() {
+        let store = build_session_store(&SessionStoreProperties::default(), "default")
+            .await
+            .unwrap();
+        let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+        let receipt = SessionOpenedReceipt {
+            id: "session".to_string(),
+            audience: None,
+            limits: SessionLimits {
+                max_sent_data: 1024,
+                max_recv_data: 1024,
+                max_records: None,
+                max_session_duration_secs: None,
+            },
+            time: 0,
+        };
+        let signature: p256::ecdsa::Signature = signing_key.sign(&receipt.signed_bytes());
+        let receipt = SignedSessionOpenedReceipt {
+            receipt,
+            signature: signature.into(),
+        };
+
+        store
+            .insert_receipt("session", SessionReceipt::Opened(receipt.clone()))
+            .await
+            .unwrap();
+        assert!(store.receipts("other").await.unwrap().is_empty());
+
+        // Receipts are kept after the session is used
+        assert!(store.remove("session").await.unwrap().is_none());
+        let receipts = store.receipts("session").await.unwrap();
+        assert!(matches!(
+            receipts.as_slice(),
+            [SessionReceipt::Opened(stored)] if stored.receipt == receipt.receipt
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+use chrono::Utc;
+use cms::content_info::ContentInfo;
+use der::{
+    asn1::{BitString, Int, ObjectIdentifier, OctetString, Utf8StringRef},
+    Decode, Encode, Sequence,
+};
+use eyre::{ensure, eyre, Result};
+use futures::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, header, Request};
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use std::{fs::File, io::BufReader, sync::Arc, time::Duration};
+use tlsn_core::session::TimestampToken;
+use tlsn_verifier::tls::time::{
+    Hash, SystemTimeSource, TimeSource, TimeSourceError, Timestamp, TimestampSource,
+};
+use tokio::{net::UdpSocket, time::timeout};
+use x509_cert::{ext::Extensions, spki::AlgorithmIdentifierOwned, Certificate};
+
+use crate::config::{TimestampProperties, TimestampSourceKind};
+
+/// Timeout of requests to the NTP server or the time-stamp authority
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Seconds between the NTP epoch (1900) and the UNIX epoch (1970)
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Object identifier of SHA-256
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+
+/// Build the source of the timestamp embedded in session headers, if timestamping is turned on
+pub fn build_time_source(config: &TimestampProperties) -> Result<Option<Arc<dyn TimeSource>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let time_source: Arc<dyn TimeSource> = match config.source {
+        TimestampSourceKind::System => Arc::new(SystemTimeSource),
+        TimestampSourceKind::Ntp => Arc::new(NtpTimeSource {
+            server: config.ntp_server.clone(),
+            max_clock_offset: config.max_clock_offset_secs,
+        }),
+        TimestampSourceKind::Rfc3161 => {
+            ensure!(
+                config.tsa_url.starts_with("http://"),
+                "Only http time-stamp authority urls are supported: {}",
+                config.tsa_url
+            );
+            let tsa_certificate = config
+                .tsa_certificate_path
+                .as_deref()
+                .map(load_tsa_certificate)
+                .transpose()?;
+            Arc::new(Rfc3161TimeSource {
+                url: config.tsa_url.clone(),
+                tsa_certificate,
+            })
+        }
+    };
+
+    Ok(Some(time_source))
+}
+
+/// Load the PEM encoded certificate of the time-stamp authority
+fn load_tsa_certificate(path: &str) -> Result<Certificate> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let certificate = rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("No certificate found in {path}"))?;
+
+    Ok(Certificate::from_der(&certificate)?)
+}
+
+/// A time source using the system clock, which fails if the clock deviates from an NTP server
+#[derive(Debug)]
+pub struct NtpTimeSource {
+    /// Address of the NTP server, e.g. pool.ntp.org:123
+    server: String,
+    /// Maximum difference between the system clock and the NTP server in seconds
+    max_clock_offset: u64,
+}
+
+impl NtpTimeSource {
+    /// Query the time of the NTP server with SNTP, in seconds since the UNIX epoch
+    async fn query(&self) -> Result<u64> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(&self.server).await?;
+
+        // LI = 0, VN = 4, Mode = 3 (client)
+        let mut packet = [0u8; 48];
+        packet[0] = 0b00_100_011;
+        socket.send(&packet).await?;
+
+        let len = timeout(REQUEST_TIMEOUT, socket.recv(&mut packet))
+            .await
+            .map_err(|_| eyre!("NTP server {} did not respond", self.server))??;
+        ensure!(len == 48, "Invalid NTP response length {len}");
+
+        // Seconds of the transmit timestamp
+        let seconds = u32::from_be_bytes(packet[40..44].try_into().unwrap()) as u64;
+        seconds
+            .checked_sub(NTP_UNIX_OFFSET)
+            .ok_or_else(|| eyre!("Invalid NTP transmit timestamp {seconds}"))
+    }
+}
+
+impl TimeSource for NtpTimeSource {
+    fn timestamp(&self, _message: Hash) -> BoxFuture<'_, Result<Timestamp, TimeSourceError>> {
+        Box::pin(async move {
+            let ntp_time = self.query().await.map_err(TimeSourceError::new)?;
+            let system_time = Utc::now().timestamp() as u64;
+
+            if ntp_time.abs_diff(system_time) > self.max_clock_offset {
+                return Err(TimeSourceError::new(format!(
+                    "System clock deviates from NTP server {} by {} seconds",
+                    self.server,
+                    ntp_time.abs_diff(system_time)
+                )));
+            }
+
+            Ok(Timestamp::new(
+                TimestampSource::Ntp {
+                    server: self.server.clone(),
+                },
+                system_time,
+                None,
+            ))
+        })
+    }
+}
+
+/// A time source using an RFC 3161 time-stamp authority
+#[derive(Debug)]
+pub struct Rfc3161TimeSource {
+    /// Url of the time-stamp authority
+    url: String,
+    /// Certificate of the time-stamp authority, used to verify the signature of its tokens
+    tsa_certificate: Option<Certificate>,
+}
+
+impl Rfc3161TimeSource {
+    /// Request a time-stamp token of the message, returning the token and its time in seconds
+    /// since the UNIX epoch
+    async fn request(&self, message: &[u8; 32]) -> Result<(Vec<u8>, u64)> {
+        let mut nonce: [u8; 8] = uuid::Uuid::new_v4().as_bytes()[..8].try_into().unwrap();
+        // Keep the nonce positive and minimally encoded
+        nonce[0] = (nonce[0] & 0x7f) | 0x40;
+
+        let request = Request::post(&self.url)
+            .header(header::CONTENT_TYPE, "application/timestamp-query")
+            .body(Full::new(Bytes::from(encode_request(message, &nonce)?)))?;
+
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        let response = timeout(REQUEST_TIMEOUT, client.request(request))
+            .await
+            .map_err(|_| eyre!("Time-stamp authority {} did not respond", self.url))??;
+        ensure!(
+            response.status().is_success(),
+            "Time-stamp authority responded with status {}",
+            response.status()
+        );
+
+        let body = timeout(REQUEST_TIMEOUT, response.into_body().collect())
+            .await
+            .map_err(|_| eyre!("Time-stamp authority {} did not respond", self.url))??
+            .to_bytes();
+
+        parse_response(&body, message, &nonce, self.tsa_certificate.as_ref())
+    }
+}
+
+impl TimeSource for Rfc3161TimeSource {
+    fn timestamp(&self, message: Hash) -> BoxFuture<'_, Result<Timestamp, TimeSourceError>> {
+        Box::pin(async move {
+            let (token, time) = self
+                .request(message.as_bytes())
+                .await
+                .map_err(TimeSourceError::new)?;
+
+            Ok(Timestamp::new(
+                TimestampSource::Rfc3161 {
+                    url: self.url.clone(),
+                },
+                time,
+                Some(token),
+            ))
+        })
+    }
+}
+
+/// Encode a TimeStampReq of the SHA-256 hash of the message, requesting the certificate of the TSA
+fn encode_request(message: &[u8; 32], nonce: &[u8; 8]) -> Result<Vec<u8>> {
+    let request = TimeStampReq {
+        version: 1,
+        message_imprint: MessageImprint {
+            hash_algorithm: AlgorithmIdentifierOwned {
+                oid: ID_SHA256,
+                parameters: None,
+            },
+            hashed_message: OctetString::new(message.as_slice())?,
+        },
+        req_policy: None,
+        nonce: Some(Int::new(nonce)?),
+        cert_req: true,
+        extensions: None,
+    };
+
+    Ok(request.to_der()?)
+}
+
+/// Parse a TimeStampResp, checking that the token timestamps the message with the nonce of the
+/// request, and returning the DER encoded TimeStampToken and its time in seconds since the UNIX
+/// epoch
+///
+/// The signature of the token is checked if the certificate of the time-stamp authority is
+/// provided, otherwise that is up to the verifiers of the session header
+fn parse_response(
+    response: &[u8],
+    message: &[u8; 32],
+    nonce: &[u8; 8],
+    tsa_certificate: Option<&Certificate>,
+) -> Result<(Vec<u8>, u64)> {
+    let response = TimeStampResp::from_der(response)?;
+
+    // Status 0 is granted and 1 is granted with modifications
+    let status = response.status.status;
+    ensure!(
+        status == 0 || status == 1,
+        "Time-stamp authority rejected the request with status {status}"
+    );
+
+    let token = response
+        .time_stamp_token
+        .ok_or_else(|| eyre!("Time-stamp authority did not return a token"))?
+        .to_der()?;
+    let info = TimestampToken::parse(&token)?;
+
+    ensure!(
+        info.message.as_bytes() == message,
+        "Time-stamp token does not timestamp the session header"
+    );
+    ensure!(
+        info.nonce.as_deref() == Some(Int::new(nonce)?.as_bytes()),
+        "Time-stamp token does not contain the nonce"
+    );
+    if let Some(tsa_certificate) = tsa_certificate {
+        info.verify_signature(tsa_certificate)?;
+    }
+
+    Ok((token, info.time))
+}
+
+/// `MessageImprint`, RFC 3161
+#[derive(Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+/// `TimeStampReq`, RFC 3161
+#[derive(Sequence)]
+struct TimeStampReq {
+    version: u8,
+    message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    nonce: Option<Int>,
+    #[asn1(default = "Default::default")]
+    cert_req: bool,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+/// `PKIStatusInfo`, RFC 3161
+#[derive(Sequence)]
+struct PkiStatusInfo<'a> {
+    status: u8,
+    #[asn1(optional = "true")]
+    status_string: Option<Vec<Utf8StringRef<'a>>>,
+    #[asn1(optional = "true")]
+    fail_info: Option<BitString>,
+}
+
+/// `TimeStampResp`, RFC 3161
+#[derive(Sequence)]
+struct TimeStampResp<'a> {
+    status: PkiStatusInfo<'a>,
+    #[asn1(optional = "true")]
+    time_stamp_token: Option<ContentInfo>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MESSAGE: [u8; 32] = [7u8; 32];
+    const NONCE: [u8; 8] = [0x41, 1, 2, 3, 4, 5, 6, 7];
+
+    /// Response of an OpenSSL time-stamp authority to the request of MESSAGE with NONCE
+    const RESPONSE: &[u8] = include_bytes!("../fixture/tsa/response.tsr");
+    /// Response of an OpenSSL time-stamp authority rejecting a request
+    const REJECTED: &[u8] = include_bytes!("../fixture/tsa/rejected.tsr");
+    const TSA_CERTIFICATE: &str = "./fixture/tsa/tsa.crt";
+
+    fn tsa_certificate() -> Certificate {
+        load_tsa_certificate(TSA_CERTIFICATE).unwrap()
+    }
+
+    #[test]
+    fn test_encode_request() {
+        let request = encode_request(&MESSAGE, &NONCE).unwrap();
+        let request = TimeStampReq::from_der(&request).unwrap();
+
+        assert_eq!(request.version, 1);
+        assert_eq!(request.message_imprint.hash_algorithm.oid, ID_SHA256);
+        assert_eq!(request.message_imprint.hashed_message.as_bytes(), MESSAGE);
+        assert_eq!(request.nonce.unwrap().as_bytes(), NONCE);
+        assert!(request.cert_req);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let (token, time) =
+            parse_response(RESPONSE, &MESSAGE, &NONCE, Some(&tsa_certificate())).unwrap();
+
+        assert_eq!(time, 1792132009);
+        assert!(RESPONSE.ends_with(&token));
+    }
+
+    #[test]
+    fn test_parse_response_fails() {
+        let tsa_certificate = tsa_certificate();
+        // Rejected
+        assert!(parse_response(REJECTED, &MESSAGE, &NONCE, None).is_err());
+        // Wrong message
+        assert!(parse_response(RESPONSE, &[8u8; 32], &NONCE, None).is_err());
+        // Wrong nonce
+        assert!(parse_response(RESPONSE, &MESSAGE, &[0x42; 8], None).is_err());
+        // Truncated
+        assert!(parse_response(&RESPONSE[..RESPONSE.len() - 1], &MESSAGE, &NONCE, None).is_err());
+        // Invalid signature, which is at the end of the response
+        let mut response = RESPONSE.to_vec();
+        *response.last_mut().unwrap() ^= 1;
+        assert!(parse_response(&response, &MESSAGE, &NONCE, None).is_ok());
+        assert!(parse_response(&response, &MESSAGE, &NONCE, Some(&tsa_certificate)).is_err());
+    }
+}
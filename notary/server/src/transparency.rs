@@ -0,0 +1,291 @@
+use chrono::Utc;
+use eyre::{eyre, Result};
+use futures::future::{self, BoxFuture};
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tlsn_core::{
+    transparency::{ConsistencyProof, InclusionProof, LogEntry, LogHead, LogTree},
+    SessionHeader,
+};
+use tlsn_verifier::tls::log::{SessionLog, SessionLogError};
+use tracing::debug;
+
+use crate::config::TransparencyLogProperties;
+
+/// Maximum number of entries returned by a single /transparency-log/entries request
+pub const MAX_LOG_ENTRIES_PER_REQUEST: usize = 1000;
+
+/// Append-only log of the hashes of the session headers signed by a notary, which are the leaves
+/// of a Merkle tree so that relying parties can audit which attestations the notary issued
+///
+/// The header of a session is appended before it is signed, and the session fails if it cannot
+/// be appended. If the log is backed by a file, each entry is appended to it as a JSON line
+/// before the entry is added to the log, and the log is restored from the file when the server
+/// restarts
+#[derive(Debug)]
+pub struct TransparencyLog {
+    state: Mutex<LogState>,
+    max_entries: usize,
+}
+
+#[derive(Debug, Default)]
+struct LogState {
+    entries: Vec<LogEntry>,
+    tree: LogTree,
+    /// Index of the entry of each session header hash
+    indices: HashMap<[u8; 32], u64>,
+    file: Option<File>,
+}
+
+impl LogState {
+    fn push(&mut self, entry: LogEntry) {
+        self.indices
+            .insert(*entry.session().as_bytes(), entry.index());
+        self.tree.push(entry.hash());
+        self.entries.push(entry);
+    }
+
+    fn head(&self, issued_at: u64) -> LogHead {
+        LogHead::new(self.entries.len() as u64, self.tree.root(), issued_at)
+    }
+}
+
+/// Build the transparency log of a tenant if it is enabled, where `namespace` identifies the
+/// tenant in the name of the file backing the log
+pub fn build_transparency_log(
+    config: &TransparencyLogProperties,
+    namespace: &str,
+) -> Result<Option<Arc<TransparencyLog>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let log = match &config.dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| eyre!("Failed to create transparency log directory {dir}: {err}"))?;
+            let file_name = if namespace.is_empty() {
+                "transparency-log.jsonl".to_string()
+            } else {
+                format!("transparency-log-{namespace}.jsonl")
+            };
+            TransparencyLog::open(Path::new(dir).join(file_name), config.max_entries)?
+        }
+        None => TransparencyLog::new(config.max_entries),
+    };
+
+    Ok(Some(Arc::new(log)))
+}
+
+impl TransparencyLog {
+    /// Create an empty log kept in memory, which holds at most `max_entries` entries
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            state: Mutex::new(LogState::default()),
+            max_entries,
+        }
+    }
+
+    /// Open the log backed by the file at the given path, restoring the entries it contains, which
+    /// holds at most `max_entries` entries
+    pub fn open(path: impl AsRef<Path>, max_entries: usize) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| eyre!("Failed to open transparency log {path:?}: {err}"))?;
+
+        let mut state = LogState::default();
+        for (index, line) in BufReader::new(&file).lines().enumerate() {
+            if index >= max_entries {
+                return Err(eyre!(
+                    "Transparency log {path:?} has more than {max_entries} entries"
+                ));
+            }
+            let line =
+                line.map_err(|err| eyre!("Failed to read transparency log {path:?}: {err}"))?;
+            let entry: LogEntry = serde_json::from_str(&line).map_err(|err| {
+                eyre!("Failed to parse entry {index} of transparency log {path:?}: {err}")
+            })?;
+            if entry.index() != index as u64 {
+                return Err(eyre!(
+                    "Entry {index} of transparency log {path:?} has index {}",
+                    entry.index()
+                ));
+            }
+            state.push(entry);
+        }
+        state.file = Some(file);
+
+        Ok(Self {
+            state: Mutex::new(state),
+            max_entries,
+        })
+    }
+
+    /// Append the session with the given header hash to the log
+    ///
+    /// # Arguments
+    ///
+    /// * `header_hash` - The hash of the session header, which is signed once it is appended
+    /// * `logged_at` - The time the session is logged, in seconds since the UNIX epoch
+    pub fn append(&self, header_hash: [u8; 32], logged_at: u64) -> Result<LogEntry> {
+        let mut state = self.state.lock().unwrap();
+        if state.entries.len() >= self.max_entries {
+            return Err(eyre!(
+                "Transparency log is full with {} entries",
+                self.max_entries
+            ));
+        }
+        if state.indices.contains_key(&header_hash) {
+            return Err(eyre!("Session is already in the transparency log"));
+        }
+        let entry = LogEntry::new(state.entries.len() as u64, header_hash.into(), logged_at);
+
+        if let Some(file) = &mut state.file {
+            let mut line = serde_json::to_vec(&entry)?;
+            line.push(b'\n');
+            file.write_all(&line)
+                .and_then(|_| file.sync_data())
+                .map_err(|err| eyre!("Failed to write to transparency log: {err}"))?;
+        }
+        state.push(entry);
+
+        Ok(entry)
+    }
+
+    /// Number of entries of the log
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether the log has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Current head of the log, issued at the given time in seconds since the UNIX epoch
+    pub fn head(&self, issued_at: u64) -> LogHead {
+        self.state.lock().unwrap().head(issued_at)
+    }
+
+    /// Up to `limit` entries of the log, starting with the entry at index `start`
+    pub fn entries(&self, start: usize, limit: usize) -> Vec<LogEntry> {
+        let state = self.state.lock().unwrap();
+        state
+            .entries
+            .iter()
+            .skip(start)
+            .take(limit)
+            .copied()
+            .collect()
+    }
+
+    /// Proof that the session with the given header hash is included in the log, together with the
+    /// current head of the log the proof leads to, or none if the session is not in the log
+    pub fn prove(
+        &self,
+        header_hash: &[u8; 32],
+        issued_at: u64,
+    ) -> Option<(InclusionProof, LogHead)> {
+        let state = self.state.lock().unwrap();
+        let index = *state.indices.get(header_hash)? as usize;
+
+        let path = state.tree.inclusion_path(index, state.entries.len())?;
+        let proof = InclusionProof::new(state.entries[index], path);
+
+        Some((proof, state.head(issued_at)))
+    }
+
+    /// Proof that the current head of the log extends the head of the log when it had `first`
+    /// entries, together with the current head, or none if the log has fewer entries
+    pub fn prove_consistency(
+        &self,
+        first: usize,
+        issued_at: u64,
+    ) -> Option<(ConsistencyProof, LogHead)> {
+        let state = self.state.lock().unwrap();
+        let path = state.tree.consistency_path(first, state.entries.len())?;
+
+        Some((ConsistencyProof::new(path), state.head(issued_at)))
+    }
+}
+
+impl SessionLog for TransparencyLog {
+    fn record(&self, header: &SessionHeader) -> BoxFuture<'_, Result<(), SessionLogError>> {
+        let result = self
+            .append(*header.hash().as_bytes(), Utc::now().timestamp() as u64)
+            .map(|entry| {
+                debug!(
+                    index = entry.index(),
+                    "Session recorded in the transparency log"
+                )
+            })
+            .map_err(|err| SessionLogError::new(err.to_string()));
+
+        Box::pin(future::ready(result))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tlsn_core::fixtures::session_header;
+
+    #[test]
+    fn test_transparency_log() {
+        let path = std::env::temp_dir().join(format!(
+            "notary-transparency-log-{}.jsonl",
+            uuid::Uuid::new_v4()
+        ));
+        let headers = (0..5u8)
+            .map(|seed| session_header([seed; 32].into(), 100, 100))
+            .collect::<Vec<_>>();
+
+        let log = TransparencyLog::open(&path, 5).unwrap();
+        for header in &headers[..3] {
+            log.append(*header.hash().as_bytes(), 0).unwrap();
+        }
+        assert!(log.append(*headers[0].hash().as_bytes(), 0).is_err());
+        let first = log.head(0);
+        drop(log);
+
+        // The log is restored from its file
+        let log = TransparencyLog::open(&path, 5).unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.head(0), first);
+
+        for header in &headers[3..] {
+            log.append(*header.hash().as_bytes(), 0).unwrap();
+        }
+        assert!(log
+            .append(
+                *session_header([9; 32].into(), 100, 100).hash().as_bytes(),
+                0
+            )
+            .is_err());
+
+        let (proof, head) = log.prove(headers[1].hash().as_bytes(), 0).unwrap();
+        assert_eq!(head, log.head(0));
+        proof.verify(&headers[1], &head).unwrap();
+        assert!(log.prove(&[9; 32], 0).is_none());
+
+        let (proof, second) = log.prove_consistency(3, 0).unwrap();
+        assert_eq!(second, head);
+        proof.verify(&first, &second).unwrap();
+        assert!(log.prove_consistency(6, 0).is_none());
+        drop(log);
+
+        // The log does not fit in a smaller limit
+        assert!(TransparencyLog::open(&path, 4).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
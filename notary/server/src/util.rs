@@ -1,8 +1,18 @@
 use eyre::Result;
 use serde::de::DeserializeOwned;
+use std::path::Path;
 
-/// Parse a yaml configuration file into a struct
+/// Parse a configuration file into a struct, which is parsed as toml if its extension is `.toml`
+/// and as yaml otherwise
 pub fn parse_config_file<T: DeserializeOwned>(location: &str) -> Result<T> {
+    if Path::new(location)
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        let config: T = toml::from_str(&std::fs::read_to_string(location)?)?;
+        return Ok(config);
+    }
+
     let file = std::fs::File::open(location)?;
     let config: T = serde_yaml::from_reader(file)?;
     Ok(config)
@@ -40,6 +50,19 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_parse_toml_config_file() {
+        let toml_config: NotaryServerProperties =
+            parse_config_file("./config/config.toml").unwrap();
+        let yaml_config: NotaryServerProperties =
+            parse_config_file("./config/config.yaml").unwrap();
+        assert_eq!(toml_config.server.port, yaml_config.server.port);
+        assert_eq!(
+            toml_config.session_store.backend,
+            yaml_config.session_store.backend
+        );
+    }
+
     #[test]
     fn test_parse_csv_file() {
         let location = "./fixture/auth/whitelist.csv";
@@ -6,7 +6,7 @@ use http_body_util::{BodyExt, Empty};
 use hyper::{body::Bytes, Request, StatusCode};
 use hyper_util::rt::TokioIo;
 use notary_client::{Accepted, NotarizationRequest, NotaryClient};
-use std::{env, ops::Range, str};
+use std::{env, str};
 use tlsn_core::proof::TlsProof;
 use tlsn_prover::tls::{Prover, ProverConfig};
 use tokio::io::AsyncWriteExt as _;
@@ -137,23 +137,18 @@ async fn main() {
     // Prepare for notarization
     let mut prover = prover.start_notarize();
 
-    // Identify the ranges in the transcript that contain secrets
-    let (public_ranges, private_ranges) =
-        find_ranges(prover.sent_transcript().data(), &[auth_token.as_bytes()]);
+    // Commit to the outbound transcript, excluding the auth token from the public commitments
+    let sent_commitments = prover
+        .commit_sent_excluding(&[auth_token.as_bytes()])
+        .unwrap();
 
     let recv_len = prover.recv_transcript().data().len();
 
-    let builder = prover.commitment_builder();
-
-    // Collect commitment ids for the outbound transcript
-    let mut commitment_ids = public_ranges
-        .iter()
-        .chain(private_ranges.iter())
-        .map(|range| builder.commit_sent(range).unwrap())
-        .collect::<Vec<_>>();
-
     // Commit to the full received transcript in one shot, as we don't need to redact anything
-    commitment_ids.push(builder.commit_recv(&(0..recv_len)).unwrap());
+    let recv_commitment = prover
+        .commitment_builder()
+        .commit_recv(&(0..recv_len))
+        .unwrap();
 
     // Finalize, returning the notarized session
     let notarized_session = prover.finalize().await.unwrap();
@@ -176,10 +171,11 @@ async fn main() {
 
     let mut proof_builder = notarized_session.data().build_substrings_proof();
 
-    // Reveal everything but the auth token (which was assigned commitment id 2)
-    proof_builder.reveal_by_id(commitment_ids[0]).unwrap();
-    proof_builder.reveal_by_id(commitment_ids[1]).unwrap();
-    proof_builder.reveal_by_id(commitment_ids[3]).unwrap();
+    // Reveal everything but the auth token
+    for commitment_id in sent_commitments.public {
+        proof_builder.reveal_by_id(commitment_id).unwrap();
+    }
+    proof_builder.reveal_by_id(recv_commitment).unwrap();
 
     let substrings_proof = proof_builder.build().unwrap();
 
@@ -196,35 +192,3 @@ async fn main() {
         .await
         .unwrap();
 }
-
-/// Find the ranges of the public and private parts of a sequence.
-///
-/// Returns a tuple of `(public, private)` ranges.
-fn find_ranges(seq: &[u8], sub_seq: &[&[u8]]) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
-    let mut private_ranges = Vec::new();
-    for s in sub_seq {
-        for (idx, w) in seq.windows(s.len()).enumerate() {
-            if w == *s {
-                private_ranges.push(idx..(idx + w.len()));
-            }
-        }
-    }
-
-    let mut sorted_ranges = private_ranges.clone();
-    sorted_ranges.sort_by_key(|r| r.start);
-
-    let mut public_ranges = Vec::new();
-    let mut last_end = 0;
-    for r in sorted_ranges {
-        if r.start > last_end {
-            public_ranges.push(last_end..r.start);
-        }
-        last_end = r.end;
-    }
-
-    if last_end < seq.len() {
-        public_ranges.push(last_end..seq.len());
-    }
-
-    (public_ranges, private_ranges)
-}
@@ -0,0 +1,167 @@
+// This example runs the full TLSNotary protocol locally, without any network access: the Prover
+// fetches private messages from the `tlsn-server-fixture` messages API and proves them to an
+// in-process Verifier, redacting its bearer token.
+use http_body_util::Empty;
+use hyper::{body::Bytes, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use tls_core::{anchors::RootCertStore, key::Certificate, verify::WebPkiVerifier};
+use tlsn_core::{proof::SessionInfo, Direction, RedactedTranscript};
+use tlsn_prover::tls::{state::Prove, Prover, ProverConfig};
+use tlsn_server_fixture::{API_TOKEN, CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+use tracing::instrument;
+
+const ROUTE: &str = "/api/messages";
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let id = "local dm demo";
+
+    // Connect prover and verifier.
+    let (prover_socket, verifier_socket) = tokio::io::duplex(1 << 23);
+    let prover = prover(prover_socket, id);
+    let verifier = verifier(verifier_socket, id);
+    let (_, (sent, received, _session_info)) = tokio::join!(prover, verifier);
+
+    println!("Successfully verified https://{SERVER_DOMAIN}{ROUTE}");
+    println!(
+        "Verified sent data:\n{}",
+        bytes_to_redacted_string(sent.data())
+    );
+    println!(
+        "Verified received data:\n{}",
+        bytes_to_redacted_string(received.data())
+    );
+}
+
+#[instrument(skip(verifier_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(verifier_socket: T, id: &str) {
+    // Create prover and connect to verifier.
+    //
+    // The prover trusts the CA of the server fixture.
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id(id)
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store())
+            .build()
+            .unwrap(),
+    )
+    .setup(verifier_socket.compat())
+    .await
+    .unwrap();
+
+    // Serve the fixture over an in-memory connection instead of connecting to a real server.
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+    tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    // Pass server connection into the prover.
+    let (mpc_tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+
+    // Wrap the connection in a TokioIo compatibility layer to use it with hyper.
+    let mpc_tls_connection = TokioIo::new(mpc_tls_connection.compat());
+
+    // Spawn the Prover to run in the background.
+    let prover_task = tokio::spawn(prover_fut);
+
+    // MPC-TLS Handshake.
+    let (mut request_sender, connection) =
+        hyper::client::conn::http1::handshake(mpc_tls_connection)
+            .await
+            .unwrap();
+
+    // Spawn the connection to run in the background.
+    tokio::spawn(connection);
+
+    // MPC-TLS: Send Request and wait for Response.
+    let request = Request::builder()
+        .uri(ROUTE)
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .header("Authorization", format!("Bearer {API_TOKEN}"))
+        .method("GET")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    let response = request_sender.send_request(request).await.unwrap();
+
+    assert!(response.status() == StatusCode::OK);
+
+    // Create proof for the Verifier.
+    let mut prover = prover_task.await.unwrap().unwrap().start_prove();
+    redact_and_reveal_sent_data(&mut prover);
+    let recv_len = prover.recv_transcript().data().len();
+    _ = prover.reveal(0..recv_len, Direction::Received);
+    prover.prove().await.unwrap();
+
+    // Finalize.
+    prover.finalize().await.unwrap()
+}
+
+#[instrument(skip(socket))]
+async fn verifier<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(
+    socket: T,
+    id: &str,
+) -> (RedactedTranscript, RedactedTranscript, SessionInfo) {
+    // Setup Verifier, which also trusts the CA of the server fixture.
+    let verifier_config = VerifierConfig::builder()
+        .id(id)
+        .cert_verifier(WebPkiVerifier::new(root_store(), None))
+        .build()
+        .unwrap();
+    let verifier = Verifier::new(verifier_config);
+
+    // Verify MPC-TLS and wait for (redacted) data.
+    let (sent, received, session_info) = verifier.verify(socket.compat()).await.unwrap();
+
+    // Check sent data: check route and that the token is redacted.
+    let sent_data = String::from_utf8(sent.data().to_vec()).expect("Verifier expected sent data");
+    sent_data
+        .find(ROUTE)
+        .unwrap_or_else(|| panic!("Verification failed: Expected route {}", ROUTE));
+    assert!(!sent_data.contains(API_TOKEN));
+
+    // Check received data: check the conversation is present.
+    let response =
+        String::from_utf8(received.data().to_vec()).expect("Verifier expected received data");
+    response
+        .find("conversation_id")
+        .expect("Expected conversation in JSON response");
+
+    // Check Session info: server name.
+    assert_eq!(session_info.server_name.as_str(), SERVER_DOMAIN);
+
+    (sent, received, session_info)
+}
+
+/// Redacts the bearer token and reveals the rest of the sent data to the verifier.
+fn redact_and_reveal_sent_data(prover: &mut Prover<Prove>) {
+    let sent_transcript_len = prover.sent_transcript().data().len();
+
+    let sent_string = String::from_utf8(prover.sent_transcript().data().to_vec()).unwrap();
+    let token_start = sent_string.find(API_TOKEN).unwrap();
+
+    // Reveal everything except for the token.
+    _ = prover.reveal(0..token_start, Direction::Sent);
+    _ = prover.reveal(
+        token_start + API_TOKEN.len()..sent_transcript_len,
+        Direction::Sent,
+    );
+}
+
+/// Returns a root store trusting the CA of the server fixture.
+fn root_store() -> RootCertStore {
+    let mut root_store = RootCertStore::empty();
+    root_store.add(&Certificate(CA_CERT_DER.to_vec())).unwrap();
+    root_store
+}
+
+/// Render redacted bytes as `🙈`.
+fn bytes_to_redacted_string(bytes: &[u8]) -> String {
+    String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .replace('\0', "🙈")
+}
@@ -4,8 +4,7 @@
 use http_body_util::Empty;
 use hyper::{body::Bytes, Request, StatusCode};
 use hyper_util::rt::TokioIo;
-use std::ops::Range;
-use tlsn_core::proof::TlsProof;
+use tlsn_core::{proof::TlsProof, range};
 use tokio::io::AsyncWriteExt as _;
 use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 
@@ -110,38 +109,6 @@ async fn main() {
     println!("The proof has been written to `simple_proof.json`");
 }
 
-/// Find the ranges of the public and private parts of a sequence.
-///
-/// Returns a tuple of `(public, private)` ranges.
-fn find_ranges(seq: &[u8], private_seq: &[&[u8]]) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
-    let mut private_ranges = Vec::new();
-    for s in private_seq {
-        for (idx, w) in seq.windows(s.len()).enumerate() {
-            if w == *s {
-                private_ranges.push(idx..(idx + w.len()));
-            }
-        }
-    }
-
-    let mut sorted_ranges = private_ranges.clone();
-    sorted_ranges.sort_by_key(|r| r.start);
-
-    let mut public_ranges = Vec::new();
-    let mut last_end = 0;
-    for r in sorted_ranges {
-        if r.start > last_end {
-            public_ranges.push(last_end..r.start);
-        }
-        last_end = r.end;
-    }
-
-    if last_end < seq.len() {
-        public_ranges.push(last_end..seq.len());
-    }
-
-    (public_ranges, private_ranges)
-}
-
 async fn build_proof_without_redactions(mut prover: Prover<Notarize>) -> TlsProof {
     let sent_len = prover.sent_transcript().data().len();
     let recv_len = prover.recv_transcript().data().len();
@@ -170,7 +137,7 @@ async fn build_proof_without_redactions(mut prover: Prover<Notarize>) -> TlsProo
 
 async fn build_proof_with_redactions(mut prover: Prover<Notarize>) -> TlsProof {
     // Identify the ranges in the outbound data which contain data which we want to disclose
-    let (sent_public_ranges, _) = find_ranges(
+    let (sent_public_ranges, _) = range::split(
         prover.sent_transcript().data(),
         &[
             // Redact the value of the "User-Agent" header. It will NOT be disclosed.
@@ -179,7 +146,7 @@ async fn build_proof_with_redactions(mut prover: Prover<Notarize>) -> TlsProof {
     );
 
     // Identify the ranges in the inbound data which contain data which we want to disclose
-    let (recv_public_ranges, _) = find_ranges(
+    let (recv_public_ranges, _) = range::split(
         prover.recv_transcript().data(),
         &[
             // Redact the value of the title. It will NOT be disclosed.
@@ -190,15 +157,9 @@ async fn build_proof_with_redactions(mut prover: Prover<Notarize>) -> TlsProof {
     let builder = prover.commitment_builder();
 
     // Commit to each range of the public outbound data which we want to disclose
-    let sent_commitments: Vec<_> = sent_public_ranges
-        .iter()
-        .map(|range| builder.commit_sent(range).unwrap())
-        .collect();
+    let sent_commitments = builder.commit_sent_batch(&sent_public_ranges).unwrap();
     // Commit to each range of the public inbound data which we want to disclose
-    let recv_commitments: Vec<_> = recv_public_ranges
-        .iter()
-        .map(|range| builder.commit_recv(range).unwrap())
-        .collect();
+    let recv_commitments = builder.commit_recv_batch(&recv_public_ranges).unwrap();
 
     // Finalize, returning the notarized session
     let notarized_session = prover.finalize().await.unwrap();
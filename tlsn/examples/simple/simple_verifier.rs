@@ -2,7 +2,8 @@ use std::{str, time::Duration};
 
 use elliptic_curve::pkcs8::DecodePublicKey;
 
-use tlsn_core::proof::{SessionProof, TlsProof};
+use tlsn_core::proof::{default_cert_verifier, TlsProof};
+use tlsn_verifier::trust::{TrustStore, TrustedNotary, VerifiedProof};
 
 /// A simple verifier which reads a proof generated by `simple_prover.rs` from "proof.json", verifies
 /// it and prints the verified data to the console.
@@ -11,39 +12,27 @@ fn main() {
     let proof = std::fs::read_to_string("simple_proof.json").unwrap();
     let proof: TlsProof = serde_json::from_str(proof.as_str()).unwrap();
 
-    let TlsProof {
-        // The session proof establishes the identity of the server and the commitments
-        // to the TLS transcript.
-        session,
-        // The substrings proof proves select portions of the transcript, while redacting
-        // anything the Prover chose not to disclose.
-        substrings,
-    } = proof;
+    // The Notaries trusted by this Verifier. The store can also be loaded from a file with
+    // `TrustStore::load`, so keys can be rotated without changing the code.
+    let mut trust_store = TrustStore::new();
+    trust_store
+        .add(TrustedNotary::new("notary", notary_pubkey()))
+        .unwrap();
 
-    // Verify the session proof against the Notary's public key
+    // Verify the proof against the trusted Notaries.
     //
     // This verifies the identity of the server using a default certificate verifier which trusts
-    // the root certificates from the `webpki-roots` crate.
-    session
-        .verify_with_default_cert_verifier(notary_pubkey())
-        .unwrap();
-
-    let SessionProof {
-        // The session header that was signed by the Notary is a succinct commitment to the TLS transcript.
-        header,
-        // This is the session_info, which contains the server_name, that is checked against the
-        // certificate chain shared in the TLS handshake.
-        session_info,
+    // the root certificates from the `webpki-roots` crate, and returns the redacted transcripts.
+    let VerifiedProof {
+        server_name,
+        time,
+        mut sent,
+        mut recv,
         ..
-    } = session;
+    } = trust_store.verify(proof, &default_cert_verifier()).unwrap();
 
     // The time at which the session was recorded
-    let time = chrono::DateTime::UNIX_EPOCH + Duration::from_secs(header.time());
-
-    // Verify the substrings proof against the session header.
-    //
-    // This returns the redacted transcripts
-    let (mut sent, mut recv) = substrings.verify(&header).unwrap();
+    let time = chrono::DateTime::UNIX_EPOCH + Duration::from_secs(time);
 
     // Replace the bytes which the Prover chose not to disclose with 'X'
     sent.set_redacted(b'X');
@@ -52,7 +41,7 @@ fn main() {
     println!("-------------------------------------------------------------------");
     println!(
         "Successfully verified that the bytes below came from a session with {:?} at {}.",
-        session_info.server_name, time
+        server_name, time
     );
     println!("Note that the bytes which the Prover chose not to disclose are shown as X.");
     println!();
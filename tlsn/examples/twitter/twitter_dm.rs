@@ -4,13 +4,12 @@
 
 use http_body_util::{BodyExt, Empty};
 use hyper::{body::Bytes, Request, StatusCode};
-use hyper_util::rt::TokioIo;
 use notary_client::{Accepted, NotarizationRequest, NotaryClient};
 use std::{env, str};
 use tlsn_core::{commitment::CommitmentKind, proof::TlsProof};
-use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_prover::tls::{http1, Prover, ProverConfig};
 use tokio::io::AsyncWriteExt as _;
-use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+use tokio_util::compat::TokioAsyncReadCompatExt;
 use tracing::debug;
 
 // Setting of the application server
@@ -75,7 +74,6 @@ async fn main() {
 
     // Bind the Prover to server connection
     let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
-    let tls_connection = TokioIo::new(tls_connection.compat());
 
     // Grab a control handle to the Prover
     let prover_ctrl = prover_fut.control();
@@ -84,9 +82,7 @@ async fn main() {
     let prover_task = tokio::spawn(prover_fut);
 
     // Attach the hyper HTTP client to the TLS connection
-    let (mut request_sender, connection) = hyper::client::conn::http1::handshake(tls_connection)
-        .await
-        .unwrap();
+    let (mut request_sender, connection) = http1::handshake(tls_connection).await.unwrap();
 
     // Spawn the HTTP task to be run concurrently
     tokio::spawn(connection);
@@ -143,19 +139,19 @@ async fn main() {
     prover.commit().unwrap();
 
     // Finalize, returning the notarized HTTP session
-    let notarized_session = prover.finalize().await.unwrap();
+    let mut notarized_session = prover.finalize().await.unwrap();
+
+    // Label the commitments, so the fields of the session can be addressed by name, e.g.
+    // "response/0/body", once it is loaded with `NotarizedSession::from_json`.
+    notarized_session.index_commitments().unwrap();
 
     debug!("Notarization complete!");
 
     // Dump the notarized session to a file
     let mut file = tokio::fs::File::create("twitter_dm.json").await.unwrap();
-    file.write_all(
-        serde_json::to_string_pretty(notarized_session.session())
-            .unwrap()
-            .as_bytes(),
-    )
-    .await
-    .unwrap();
+    file.write_all(notarized_session.session().to_json().unwrap().as_bytes())
+        .await
+        .unwrap();
 
     let session_proof = notarized_session.session_proof();
 
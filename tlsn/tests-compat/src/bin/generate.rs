@@ -0,0 +1,140 @@
+//! Generates an archive of a notarized session with the current version.
+//!
+//! The archive is written to `fixtures/<version>/http_get/`, and should be committed when cutting
+//! a release so that later versions are checked against it.
+
+use http_body_util::{BodyExt as _, Empty};
+use hyper::{body::Bytes, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use tests_compat::{fixtures_dir, Archive};
+use tlsn_core::proof::TlsProof;
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+const ARCHIVE_NAME: &str = "http_get";
+const USER_AGENT: &str = "tests-compat";
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+    let notary_public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+    let (socket_0, socket_1) = tokio::io::duplex(1 << 23);
+
+    let (proof, _) = tokio::join!(prover(socket_0), notary(socket_1, signing_key));
+
+    // Verify a copy of the proof to obtain the transcripts the archive is expected to recover.
+    let copy: TlsProof = bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+    let (sent, recv) = copy.substrings.verify(&copy.session.header).unwrap();
+
+    let version = env!("CARGO_PKG_VERSION");
+    let archive = Archive::new(version, &notary_public_key, proof, &sent, &recv).unwrap();
+
+    let dir = fixtures_dir().join(version).join(ARCHIVE_NAME);
+    archive.save(&dir).unwrap();
+
+    println!("Archive written to {}", dir.display());
+}
+
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socket: T) -> TlsProof {
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("compat")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_socket.compat())
+    .await
+    .unwrap();
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, connection) =
+        hyper::client::conn::http1::handshake(TokioIo::new(tls_connection.compat()))
+            .await
+            .unwrap();
+
+    tokio::spawn(connection);
+
+    let request = Request::builder()
+        .uri(format!("https://{}/bytes?size=256", SERVER_DOMAIN))
+        .header("Host", SERVER_DOMAIN)
+        .header("User-Agent", USER_AGENT)
+        .header("Connection", "close")
+        .method("GET")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+
+    let response = request_sender.send_request(request).await.unwrap();
+
+    assert!(response.status() == StatusCode::OK);
+
+    response.into_body().collect().await.unwrap();
+
+    let _ = server_task.await.unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    // Redact the user agent so the archive also covers redacted transcripts.
+    let user_agent_start = prover
+        .sent_transcript()
+        .data()
+        .windows(USER_AGENT.len())
+        .position(|window| window == USER_AGENT.as_bytes())
+        .unwrap();
+    let user_agent_end = user_agent_start + USER_AGENT.len();
+
+    let builder = prover.commitment_builder();
+
+    let sent_commitments = [
+        builder.commit_sent(&(0..user_agent_start)).unwrap(),
+        builder.commit_sent(&(user_agent_end..sent_len)).unwrap(),
+    ];
+    let recv_commitment = builder.commit_recv(&(0..recv_len)).unwrap();
+
+    let notarized_session = prover.finalize().await.unwrap();
+
+    let mut proof_builder = notarized_session.data().build_substrings_proof();
+
+    for commitment in sent_commitments {
+        proof_builder.reveal_by_id(commitment).unwrap();
+    }
+    proof_builder.reveal_by_id(recv_commitment).unwrap();
+
+    TlsProof {
+        session: notarized_session.session_proof(),
+        substrings: proof_builder.build().unwrap(),
+    }
+}
+
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(
+    socket: T,
+    signing_key: p256::ecdsa::SigningKey,
+) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("compat").build().unwrap());
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
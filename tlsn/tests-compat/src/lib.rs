@@ -0,0 +1,299 @@
+//! Compatibility harness for notarized sessions archived by previous releases.
+//!
+//! An archive contains a [`TlsProof`] produced by a release, together with the data needed to check
+//! that the current code still verifies it and recovers the same transcripts. A header archive
+//! only contains a [`SessionHeader`] signed by the notary of a release, to check that the current
+//! code decodes it and still verifies its signature.
+
+#![deny(missing_docs, unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use p256::pkcs8::{DecodePublicKey, EncodePublicKey, LineEnding};
+use serde::{Deserialize, Serialize};
+use tls_core::{anchors::RootCertStore, key::Certificate, verify::WebPkiVerifier};
+use tlsn_core::{
+    proof::{SessionProofError, SubstringsProofError, TlsProof},
+    session::HeaderVersion,
+    RedactedTranscript, SessionHeader, SignatureVerifyError,
+};
+use tlsn_server_fixture::CA_CERT_DER;
+
+/// Name of the file containing the serialized proof.
+pub const PROOF_FILE: &str = "proof.bin";
+/// Name of the file containing the archive metadata.
+pub const META_FILE: &str = "meta.json";
+/// Name of the file containing the serialized header of a header archive.
+pub const HEADER_FILE: &str = "header.bin";
+
+/// An error that can occur while loading or verifying an archive.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum ArchiveError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("failed to (de)serialize proof: {0}")]
+    Proof(#[from] bincode::Error),
+    #[error("failed to (de)serialize metadata: {0}")]
+    Meta(#[from] serde_json::Error),
+    #[error("invalid transcript encoding: {0}")]
+    Transcript(#[from] hex::FromHexError),
+    #[error("invalid notary public key: {0}")]
+    NotaryPublicKey(String),
+    #[error("invalid signature encoding")]
+    SignatureEncoding,
+    #[error(transparent)]
+    Signature(#[from] SignatureVerifyError),
+    #[error(transparent)]
+    SessionProof(#[from] SessionProofError),
+    #[error(transparent)]
+    SubstringsProof(#[from] SubstringsProofError),
+    #[error("verified data does not match the archive: {0}")]
+    Mismatch(&'static str),
+}
+
+/// Metadata of an archived session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveMeta {
+    /// Version of the release which produced the archive.
+    pub version: String,
+    /// Public key of the notary which signed the session, in PEM format.
+    pub notary_public_key: String,
+    /// Name of the server.
+    pub server_name: String,
+    /// Expected sent transcript (hex encoded), with redacted bytes set to 0.
+    pub sent: String,
+    /// Expected received transcript (hex encoded), with redacted bytes set to 0.
+    pub recv: String,
+    /// Expected authenticated ranges of the sent transcript.
+    pub sent_authed: Vec<Range<usize>>,
+    /// Expected authenticated ranges of the received transcript.
+    pub recv_authed: Vec<Range<usize>>,
+    /// Version of the layout of the session header. Archives of releases which did not version
+    /// their headers don't have one, and are decoded in the layout of [`HeaderVersion::V0`].
+    #[serde(default)]
+    pub header_version: Option<HeaderVersion>,
+}
+
+/// An archived notarized session.
+#[derive(Debug)]
+pub struct Archive {
+    /// Metadata of the archive.
+    pub meta: ArchiveMeta,
+    /// The archived proof.
+    pub proof: TlsProof,
+}
+
+impl Archive {
+    /// Creates a new archive from a proof and the transcripts it is expected to verify to.
+    pub fn new(
+        version: impl Into<String>,
+        notary_public_key: &p256::PublicKey,
+        proof: TlsProof,
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Result<Self, ArchiveError> {
+        let notary_public_key = notary_public_key
+            .to_public_key_pem(LineEnding::LF)
+            .map_err(|e| ArchiveError::NotaryPublicKey(e.to_string()))?;
+
+        Ok(Self {
+            meta: ArchiveMeta {
+                version: version.into(),
+                notary_public_key,
+                server_name: proof.session.session_info.server_name.as_str().to_string(),
+                sent: hex::encode(sent.data()),
+                recv: hex::encode(recv.data()),
+                sent_authed: sent.authed().iter_ranges().collect(),
+                recv_authed: recv.authed().iter_ranges().collect(),
+                header_version: Some(proof.session.header.version()),
+            },
+            proof,
+        })
+    }
+
+    /// Loads an archive from a directory.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let dir = dir.as_ref();
+        let meta: ArchiveMeta = serde_json::from_slice(&std::fs::read(dir.join(META_FILE))?)?;
+        let bytes = std::fs::read(dir.join(PROOF_FILE))?;
+        let proof = match meta.header_version {
+            None => TlsProof::from_v0_bytes(&bytes)?,
+            Some(_) => bincode::deserialize(&bytes)?,
+        };
+
+        Ok(Self { meta, proof })
+    }
+
+    /// Saves the archive to a directory, creating it if needed.
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<(), ArchiveError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(PROOF_FILE), bincode::serialize(&self.proof)?)?;
+        std::fs::write(dir.join(META_FILE), serde_json::to_vec_pretty(&self.meta)?)?;
+
+        Ok(())
+    }
+
+    /// Verifies the archived proof with the current code, checking that it recovers the
+    /// expected transcripts.
+    pub fn verify(self) -> Result<(), ArchiveError> {
+        let Self { meta, proof } = self;
+        let TlsProof {
+            session,
+            substrings,
+        } = proof;
+
+        let notary_public_key = p256::PublicKey::from_public_key_pem(&meta.notary_public_key)
+            .map_err(|e| ArchiveError::NotaryPublicKey(e.to_string()))?;
+
+        session.verify(notary_public_key, &cert_verifier())?;
+
+        if session.session_info.server_name.as_str() != meta.server_name {
+            return Err(ArchiveError::Mismatch("server name"));
+        }
+
+        let (sent, recv) = substrings.verify(&session.header)?;
+
+        if sent.data() != hex::decode(&meta.sent)? {
+            return Err(ArchiveError::Mismatch("sent transcript"));
+        }
+        if recv.data() != hex::decode(&meta.recv)? {
+            return Err(ArchiveError::Mismatch("received transcript"));
+        }
+        if sent.authed().iter_ranges().collect::<Vec<_>>() != meta.sent_authed {
+            return Err(ArchiveError::Mismatch(
+                "authenticated ranges of sent transcript",
+            ));
+        }
+        if recv.authed().iter_ranges().collect::<Vec<_>>() != meta.recv_authed {
+            return Err(ArchiveError::Mismatch(
+                "authenticated ranges of received transcript",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Metadata of an archived session header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderArchiveMeta {
+    /// Version of the release whose layout the header is serialized in.
+    pub version: String,
+    /// Public key of the notary which signed the header, in PEM format.
+    pub notary_public_key: String,
+    /// Hex encoded P-256 signature of the header, as the concatenation of `r` and `s`.
+    pub signature: String,
+    /// Expected number of bytes sent to the server.
+    pub sent_len: usize,
+    /// Expected number of bytes received from the server.
+    pub recv_len: usize,
+    /// Expected time of the session, in seconds since the UNIX epoch.
+    pub time: u64,
+    /// Version of the layout of the header, see [`ArchiveMeta::header_version`].
+    #[serde(default)]
+    pub header_version: Option<HeaderVersion>,
+}
+
+/// An archived session header signed by a notary.
+#[derive(Debug)]
+pub struct HeaderArchive {
+    /// Metadata of the archive.
+    pub meta: HeaderArchiveMeta,
+    /// The archived header.
+    pub header: SessionHeader,
+}
+
+impl HeaderArchive {
+    /// Loads a header archive from a directory.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let dir = dir.as_ref();
+        let meta: HeaderArchiveMeta = serde_json::from_slice(&std::fs::read(dir.join(META_FILE))?)?;
+        let bytes = std::fs::read(dir.join(HEADER_FILE))?;
+        let header = match meta.header_version {
+            None => SessionHeader::from_v0_bytes(&bytes)?,
+            Some(_) => bincode::deserialize(&bytes)?,
+        };
+
+        Ok(Self { meta, header })
+    }
+
+    /// Verifies the signature of the archived header with the current code, checking that the
+    /// header decodes to the expected values.
+    pub fn verify(self) -> Result<(), ArchiveError> {
+        let Self { meta, header } = self;
+
+        let notary_public_key = p256::PublicKey::from_public_key_pem(&meta.notary_public_key)
+            .map_err(|e| ArchiveError::NotaryPublicKey(e.to_string()))?;
+        let signature = p256::ecdsa::Signature::from_slice(&hex::decode(&meta.signature)?)
+            .map_err(|_| ArchiveError::SignatureEncoding)?;
+
+        tlsn_core::Signature::from(signature).verify(&header.to_bytes(), notary_public_key)?;
+
+        if header.sent_len() != meta.sent_len {
+            return Err(ArchiveError::Mismatch("sent length"));
+        }
+        if header.recv_len() != meta.recv_len {
+            return Err(ArchiveError::Mismatch("received length"));
+        }
+        if header.time() != meta.time {
+            return Err(ArchiveError::Mismatch("time"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the directory containing the archives.
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// Returns the directories of all proof archives, laid out as `<version>/<name>/`, sorted by path.
+pub fn archive_dirs(fixtures_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, ArchiveError> {
+    dirs_containing(fixtures_dir, PROOF_FILE)
+}
+
+/// Returns the directories of all header archives, laid out as `<version>/<name>/`, sorted by
+/// path.
+pub fn header_archive_dirs(fixtures_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, ArchiveError> {
+    dirs_containing(fixtures_dir, HEADER_FILE)
+}
+
+fn dirs_containing(
+    fixtures_dir: impl AsRef<Path>,
+    file: &str,
+) -> Result<Vec<PathBuf>, ArchiveError> {
+    let mut dirs = Vec::new();
+    for version in std::fs::read_dir(fixtures_dir)? {
+        let version = version?.path();
+        if !version.is_dir() {
+            continue;
+        }
+        for archive in std::fs::read_dir(version)? {
+            let archive = archive?.path();
+            if archive.join(file).is_file() {
+                dirs.push(archive);
+            }
+        }
+    }
+    dirs.sort();
+
+    Ok(dirs)
+}
+
+/// Returns a certificate verifier which trusts the CA of the server fixture.
+pub fn cert_verifier() -> WebPkiVerifier {
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&Certificate(CA_CERT_DER.to_vec()))
+        .expect("server fixture CA is a valid certificate");
+
+    WebPkiVerifier::new(root_store, None)
+}
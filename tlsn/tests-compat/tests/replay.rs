@@ -0,0 +1,36 @@
+use tests_compat::{archive_dirs, fixtures_dir, header_archive_dirs, Archive, HeaderArchive};
+
+#[test]
+fn replay_archives() {
+    let proof_dirs = archive_dirs(fixtures_dir()).unwrap();
+    let header_dirs = header_archive_dirs(fixtures_dir()).unwrap();
+
+    assert!(
+        !proof_dirs.is_empty() || !header_dirs.is_empty(),
+        "no archived sessions found in {}",
+        fixtures_dir().display()
+    );
+
+    let mut failures = Vec::new();
+    let results = proof_dirs
+        .into_iter()
+        .map(|dir| (Archive::load(&dir).and_then(Archive::verify), dir))
+        .chain(header_dirs.into_iter().map(|dir| {
+            (
+                HeaderArchive::load(&dir).and_then(HeaderArchive::verify),
+                dir,
+            )
+        }));
+    for (result, dir) in results {
+        match result {
+            Ok(()) => println!("verified {}", dir.display()),
+            Err(e) => failures.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "failed to replay archived sessions:\n{}",
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,59 @@
+use p256::ecdsa::SigningKey;
+use tlsn_core::msg::{AbortReason, SessionParty, SessionPhase};
+use tlsn_prover::tls::{AbortOrigin, Prover, ProverConfig, ProverError};
+use tlsn_verifier::{
+    mock::MOCK_NOTARY_KEY,
+    tls::{Verifier, VerifierConfig, VerifierError},
+};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+#[ignore]
+async fn prover_abort() {
+    let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+
+    let signing_key = SigningKey::from_bytes(&MOCK_NOTARY_KEY.into()).unwrap();
+    let verifying_key = *signing_key.verifying_key();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns("test-server.io")
+            .build()
+            .unwrap(),
+    );
+    assert!(prover
+        .abort_handle()
+        .abort(AbortReason::Cancelled, Some("user cancelled".to_string())));
+
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap())
+        .with_abort_receipts::<_, p256::ecdsa::Signature>(signing_key);
+
+    let (prover_result, verifier_result) = tokio::join!(
+        prover.setup(prover_socket.compat()),
+        verifier.setup(notary_socket.compat())
+    );
+
+    let Err(ProverError::Aborted(prover_aborted)) = prover_result else {
+        panic!("prover should be aborted");
+    };
+    let Err(VerifierError::Aborted(verifier_aborted)) = verifier_result else {
+        panic!("verifier should be aborted");
+    };
+
+    // Both parties converge to the same abort.
+    assert_eq!(prover_aborted.origin, AbortOrigin::Local);
+    assert_eq!(verifier_aborted.origin, AbortOrigin::Remote);
+    assert_eq!(prover_aborted.abort, verifier_aborted.abort);
+    assert_eq!(prover_aborted.abort.reason, AbortReason::Cancelled);
+    assert_eq!(prover_aborted.abort.stats.phase, SessionPhase::Setup);
+
+    // The prover receives the notary's receipt.
+    let receipt = prover_aborted.receipt.expect("notary issues a receipt");
+    receipt
+        .verify(p256::PublicKey::from(verifying_key))
+        .unwrap();
+    assert_eq!(receipt.receipt.id, "test");
+    assert_eq!(receipt.receipt.initiator, SessionParty::Prover);
+    assert_eq!(receipt.receipt.abort, prover_aborted.abort);
+}
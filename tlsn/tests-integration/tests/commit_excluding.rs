@@ -0,0 +1,143 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilderError},
+    Direction,
+};
+use tlsn_prover::tls::{Prover, ProverConfig, ProverError};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+const TOKEN: &[u8] = b"secret-token";
+const SECRET: &[u8] = b"Bearer secret-token";
+
+#[tokio::test]
+#[ignore]
+async fn test_commit_sent_excluding() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    tokio::join!(prover(socket_0), notary(socket_1));
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socket: T) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_socket.compat())
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = [
+        b"GET / HTTP/1.1\r\nHost: test-server.io\r\nAuthorization: ".as_slice(),
+        SECRET,
+        b"\r\nX-Echo: ",
+        SECRET,
+        b"\r\n\r\n",
+    ]
+    .concat();
+    tls_connection.write_all(&request).await.unwrap();
+    tls_connection.close().await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+
+    let _ = server_task.await.unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+
+    let secret_start = request
+        .windows(SECRET.len())
+        .position(|window| window == SECRET)
+        .unwrap();
+    let secret_range = secret_start..secret_start + SECRET.len();
+
+    // The commitment to one of the secrets already exists, so nothing is committed to.
+    let secret_id = prover
+        .commitment_builder()
+        .commit_sent(&secret_range)
+        .unwrap();
+    let err = prover.commit_sent_excluding(&[SECRET]).unwrap_err();
+    assert!(matches!(
+        err,
+        ProverError::CommitmentBuilder(TranscriptCommitmentBuilderError::Duplicate(id))
+            if id == secret_id
+    ));
+    assert_eq!(prover.commitment_builder().len(), 1);
+
+    let commitments = prover.commit_recv_excluding(&[SECRET]).unwrap();
+    assert!(commitments.private.is_empty());
+
+    // Excluding only the token does not conflict with the existing commitment.
+    let commitments = prover.commit_sent_excluding(&[TOKEN]).unwrap();
+
+    // Both occurrences of the token are excluded from the public commitments.
+    let token_start = secret_start + SECRET.len() - TOKEN.len();
+    assert_eq!(commitments.private.len(), 2);
+    assert_eq!(commitments.public.len(), 3);
+    assert!(commitments
+        .private_ranges
+        .iter_ranges()
+        .all(|range| &request[range] == TOKEN));
+    assert_eq!(
+        commitments.private_ranges.iter_ranges().next(),
+        Some(token_start..token_start + TOKEN.len())
+    );
+
+    let builder = prover.commitment_builder();
+    for (range, id) in commitments
+        .public_ranges
+        .iter_ranges()
+        .zip(&commitments.public)
+        .chain(
+            commitments
+                .private_ranges
+                .iter_ranges()
+                .zip(&commitments.private),
+        )
+    {
+        assert_eq!(
+            builder.get_id(CommitmentKind::Blake3, range, Direction::Sent),
+            Some(*id)
+        );
+    }
+
+    // Committing again fails without adding any commitment.
+    let len = prover.commitment_builder().len();
+    assert!(prover.commit_sent_excluding(&[TOKEN]).is_err());
+    assert_eq!(prover.commitment_builder().len(), len);
+
+    let _notarized_session = prover.finalize().await.unwrap();
+}
+
+#[instrument(skip(socket))]
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(socket: T) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
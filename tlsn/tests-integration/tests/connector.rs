@@ -0,0 +1,128 @@
+use http_body_util::{BodyExt as _, Empty};
+use hyper::{body::Bytes, Request, StatusCode};
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use tlsn_prover::tls::{
+    http1,
+    state::{Closed, Setup},
+    Prover, ProverConfig, ProverConnector,
+};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::mock::MockNotary;
+use tokio::{io::DuplexStream, task::JoinHandle};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+#[ignore]
+async fn connector() {
+    let (prover, client_socket, server_task, notary_task) = setup().await;
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    // Use the connection with an off-the-shelf hyper client.
+    let client = Client::builder(TokioExecutor::new())
+        .build::<_, Empty<Bytes>>(ProverConnector::new(tls_connection, SERVER_DOMAIN));
+
+    let request = Request::builder()
+        .uri(format!("https://{SERVER_DOMAIN}/formats/json?size=1"))
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+
+    let response = client.request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.into_body().collect().await.unwrap();
+
+    // The connector only hands out the connection once.
+    let request = Request::builder()
+        .uri(format!("https://{SERVER_DOMAIN}/"))
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    assert!(client.request(request).await.is_err());
+    drop(client);
+
+    server_task.await.unwrap();
+
+    finalize(prover_task.await.unwrap().unwrap()).await;
+    notary_task.await.unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+async fn http1_handshake() {
+    let (prover, client_socket, server_task, notary_task) = setup().await;
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    // Use the connection with hyper directly, without compatibility shims.
+    let (mut request_sender, connection) = http1::handshake(tls_connection).await.unwrap();
+    let connection_task = tokio::spawn(connection);
+
+    let request = Request::builder()
+        .uri(format!("https://{SERVER_DOMAIN}/formats/json?size=1"))
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+
+    let response = request_sender.send_request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.into_body().collect().await.unwrap();
+
+    connection_task.await.unwrap().unwrap();
+    server_task.await.unwrap();
+
+    finalize(prover_task.await.unwrap().unwrap()).await;
+    notary_task.await.unwrap();
+}
+
+/// Sets up a prover with a mock notary, returning the prover, its socket to the server fixture,
+/// and the tasks running the server and the notary.
+async fn setup() -> (Prover<Setup>, DuplexStream, JoinHandle<()>, JoinHandle<()>) {
+    let notary = MockNotary::new();
+    let (notary_io, notary_fut) = notary.bind();
+    let notary_task = tokio::spawn(async move {
+        notary_fut.await.unwrap();
+    });
+
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+    let server_task = tokio::spawn(async move {
+        tlsn_server_fixture::bind(server_socket.compat())
+            .await
+            .unwrap();
+    });
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_io)
+    .await
+    .unwrap();
+
+    (prover, client_socket, server_task, notary_task)
+}
+
+/// Notarizes the whole transcript.
+async fn finalize(prover: Prover<Closed>) {
+    let mut prover = prover.start_notarize();
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    prover.finalize().await.unwrap();
+}
@@ -0,0 +1,109 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tlsn_core::{commitment::CommitmentKind, Direction};
+use tlsn_prover::tls::{Prover, ProverConfig, ProverError};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::instrument;
+
+#[tokio::test]
+#[ignore]
+async fn test_early_commitment() {
+    tracing_subscriber::fmt::init();
+
+    let (socket_0, socket_1) = tokio::io::duplex(2 << 23);
+
+    tokio::join!(prover(socket_0), notary(socket_1));
+}
+
+#[instrument(skip(notary_socket))]
+async fn prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(notary_socket: T) {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store)
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_socket.compat())
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_ctrl = prover_fut.control();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let request = b"GET / HTTP/1.1\r\nHost: test-server.io\r\n\r\n";
+    tls_connection.write_all(request).await.unwrap();
+
+    // Once the server responded, the request has been transmitted.
+    let mut response = vec![0u8; 1024];
+    let read = tls_connection.read(&mut response).await.unwrap();
+    assert!(read > 0);
+
+    let transcript = prover_ctrl.transcript();
+    assert_eq!(
+        transcript.sent_range(0..request.len()).unwrap(),
+        request.to_vec()
+    );
+    transcript.with_recv(|recv| assert_eq!(recv, &response[..recv.len()]));
+
+    // Commit to the transmitted data while the connection is still open.
+    let recv_len = transcript.recv_len();
+    prover_ctrl
+        .commit(0..request.len(), Direction::Sent)
+        .unwrap();
+    prover_ctrl
+        .commit(0..recv_len, Direction::Received)
+        .unwrap();
+
+    // Data which has not been transmitted can not be committed to.
+    assert!(matches!(
+        prover_ctrl.commit(0..request.len() + 1, Direction::Sent),
+        Err(ProverError::InvalidRange)
+    ));
+
+    tls_connection.close().await.unwrap();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+
+    let _ = server_task.await.unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+
+    assert!(matches!(
+        prover_ctrl.commit(0..1, Direction::Sent),
+        Err(ProverError::ConnectionClosed)
+    ));
+
+    let builder = prover.commitment_builder();
+    assert!(builder
+        .get_id(CommitmentKind::Blake3, 0..request.len(), Direction::Sent)
+        .is_some());
+    assert!(builder
+        .get_id(CommitmentKind::Blake3, 0..recv_len, Direction::Received)
+        .is_some());
+
+    let _notarized_session = prover.finalize().await.unwrap();
+}
+
+#[instrument(skip(socket))]
+async fn notary<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(socket: T) {
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+    let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+    _ = verifier
+        .notarize::<_, p256::ecdsa::Signature>(socket.compat(), &signing_key)
+        .await
+        .unwrap();
+}
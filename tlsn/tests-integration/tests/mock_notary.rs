@@ -0,0 +1,73 @@
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tls_core::verify::WebPkiVerifier;
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::mock::MockNotary;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+#[ignore]
+async fn mock_notary() {
+    let notary = MockNotary::new();
+    let notary_key = notary.verifying_key();
+    let (notary_io, notary_fut) = notary.bind();
+    let notary_task = tokio::spawn(notary_fut);
+
+    let (client_socket, server_socket) = tokio::io::duplex(1 << 16);
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let mut root_store = tls_core::anchors::RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store.clone())
+            .build()
+            .unwrap(),
+    )
+    .setup(notary_io)
+    .await
+    .unwrap();
+
+    let (mut tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+    let prover_task = tokio::spawn(prover_fut);
+
+    tls_connection
+        .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    tls_connection.close().await.unwrap();
+
+    let mut response = Vec::new();
+    tls_connection.read_to_end(&mut response).await.unwrap();
+
+    server_task.await.unwrap().unwrap();
+
+    let mut prover = prover_task.await.unwrap().unwrap().start_notarize();
+    let sent_len = prover.sent_transcript().data().len();
+    let recv_len = prover.recv_transcript().data().len();
+
+    let builder = prover.commitment_builder();
+    builder.commit_sent(&(0..sent_len)).unwrap();
+    builder.commit_recv(&(0..recv_len)).unwrap();
+
+    let notarized_session = prover.finalize().await.unwrap();
+    let header = notary_task.await.unwrap().unwrap();
+
+    assert_eq!(
+        notarized_session.header().merkle_root(),
+        header.merkle_root()
+    );
+
+    notarized_session
+        .session_proof()
+        .verify(
+            p256::PublicKey::from(notary_key),
+            &WebPkiVerifier::new(root_store, None),
+        )
+        .unwrap();
+}
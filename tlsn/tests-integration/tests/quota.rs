@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use tlsn_core::msg::{AbortReason, SessionPhase};
+use tlsn_prover::tls::{AbortOrigin, Prover, ProverConfig, ProverError};
+use tlsn_verifier::tls::{Verifier, VerifierConfig, VerifierError};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+fn prover() -> Prover<tlsn_prover::tls::state::Initialized> {
+    Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns("test-server.io")
+            .build()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+#[ignore]
+async fn ot_quota_exceeded() {
+    let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .max_ots(1usize)
+            .build()
+            .unwrap(),
+    );
+
+    let (_, verifier_result) = tokio::join!(
+        prover().setup(prover_socket.compat()),
+        verifier.setup(notary_socket.compat())
+    );
+
+    let Err(VerifierError::QuotaExceeded(_)) = verifier_result else {
+        panic!("verifier should exceed its oblivious transfer quota");
+    };
+}
+
+#[tokio::test]
+#[ignore]
+async fn session_duration_exceeded() {
+    let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .max_session_duration(Duration::ZERO)
+            .build()
+            .unwrap(),
+    );
+
+    let (prover_result, verifier_result) = tokio::join!(
+        prover().setup(prover_socket.compat()),
+        verifier.setup(notary_socket.compat())
+    );
+
+    let Err(ProverError::Aborted(prover_aborted)) = prover_result else {
+        panic!("prover should be aborted");
+    };
+    let Err(VerifierError::Aborted(verifier_aborted)) = verifier_result else {
+        panic!("verifier should be aborted");
+    };
+
+    assert_eq!(verifier_aborted.origin, AbortOrigin::Local);
+    assert_eq!(prover_aborted.origin, AbortOrigin::Remote);
+    assert_eq!(verifier_aborted.abort.reason, AbortReason::LimitExceeded);
+    assert_eq!(verifier_aborted.abort.stats.phase, SessionPhase::Setup);
+}
@@ -0,0 +1,46 @@
+use p256::ecdsa::SigningKey;
+use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_verifier::{
+    mock::MOCK_NOTARY_KEY,
+    tls::{Verifier, VerifierConfig},
+};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+#[ignore]
+async fn session_opened_receipt() {
+    let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+
+    let signing_key = SigningKey::from_bytes(&MOCK_NOTARY_KEY.into()).unwrap();
+    let verifying_key = *signing_key.verifying_key();
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns("test-server.io")
+            .build()
+            .unwrap(),
+    );
+
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap())
+        .with_session_receipts::<_, p256::ecdsa::Signature>(signing_key);
+
+    let (prover, verifier) = tokio::join!(
+        prover.setup(prover_socket.compat()),
+        verifier.setup(notary_socket.compat())
+    );
+    let prover = prover.unwrap();
+    let verifier = verifier.unwrap();
+
+    // The prover receives the receipt before the TLS session starts.
+    let receipt = prover.opened_receipt().expect("notary issues a receipt");
+    receipt
+        .verify(p256::PublicKey::from(verifying_key))
+        .unwrap();
+    assert_eq!(receipt.receipt.id, "test");
+    assert_eq!(receipt.receipt.audience, None);
+    assert_eq!(
+        verifier.opened_receipt().map(|receipt| &receipt.receipt),
+        Some(&receipt.receipt)
+    );
+}
@@ -0,0 +1,71 @@
+use std::io;
+
+use futures::future::BoxFuture;
+use tlsn_prover::tls::{Prover, ProverConfig, ProverError, Reconnect};
+use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tokio::io::DuplexStream;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+/// Hands out a single new connection to the verifier.
+struct NewConnection(Option<Compat<DuplexStream>>);
+
+impl Reconnect for NewConnection {
+    type Io = Compat<DuplexStream>;
+
+    fn reconnect(&mut self) -> BoxFuture<'_, io::Result<Self::Io>> {
+        let socket = self
+            .0
+            .take()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionRefused));
+        Box::pin(async move { socket })
+    }
+}
+
+fn prover() -> Prover<tlsn_prover::tls::state::Initialized> {
+    Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns("test-server.io")
+            .build()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+#[ignore]
+async fn setup_retry() {
+    // The first connection to the verifier is dropped before the setup starts.
+    let (dropped_socket, _) = tokio::io::duplex(2 << 23);
+    let (prover_socket, notary_socket) = tokio::io::duplex(2 << 23);
+
+    let verifier = Verifier::new(VerifierConfig::builder().id("test").build().unwrap());
+
+    let (prover_result, verifier_result) = tokio::join!(
+        prover().setup_with_retry(
+            dropped_socket.compat(),
+            NewConnection(Some(prover_socket.compat())),
+            1,
+        ),
+        verifier.setup(notary_socket.compat())
+    );
+
+    prover_result.unwrap();
+    verifier_result.unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+async fn setup_retries_exhausted() {
+    let (dropped_socket, _) = tokio::io::duplex(2 << 23);
+
+    let err = prover()
+        .setup_with_retry(dropped_socket.compat(), NewConnection(None), 3)
+        .await
+        .unwrap_err();
+
+    // The last error is returned once no new connection can be established.
+    let ProverError::IOError(err) = err else {
+        panic!("setup should fail with an IO error");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+}
@@ -0,0 +1,122 @@
+use std::{net::IpAddr, path::PathBuf};
+
+use eyre::Result;
+use structopt::StructOpt;
+use tlsn_notarize::{
+    request::{load_headers_file, parse_header, parse_header_env},
+    NotarizeOptions, NotaryOptions,
+};
+
+/// Notarizes an HTTPS request with a notary server and writes the proof to disk.
+#[derive(Clone, Debug, StructOpt)]
+#[structopt(name = "tlsn")]
+pub struct Cli {
+    /// URL of the request, e.g. https://example.com/api?id=1
+    pub url: String,
+
+    /// HTTP method of the request
+    #[structopt(short = "X", long, default_value = "GET")]
+    pub method: String,
+
+    /// Header of the request formatted as "Name: value", can be repeated
+    #[structopt(short = "H", long = "header")]
+    pub headers: Vec<String>,
+
+    /// File containing headers of the request, one "Name: value" per line
+    #[structopt(long)]
+    pub headers_file: Option<PathBuf>,
+
+    /// Header of the request whose value is read from an environment variable, formatted as
+    /// "Name=VARIABLE", can be repeated
+    #[structopt(long = "header-env")]
+    pub header_envs: Vec<String>,
+
+    /// Body of the request
+    #[structopt(short, long)]
+    pub data: Option<String>,
+
+    /// Regex matching data of the request to redact from the proof, can be repeated. If the regex
+    /// has capture groups, only the groups are redacted.
+    #[structopt(long)]
+    pub redact_sent: Vec<String>,
+
+    /// Regex matching data of the response to redact from the proof, can be repeated. If the regex
+    /// has capture groups, only the groups are redacted.
+    #[structopt(long)]
+    pub redact_recv: Vec<String>,
+
+    /// Host of the notary server
+    #[structopt(long, default_value = "127.0.0.1")]
+    pub notary_host: String,
+
+    /// Port of the notary server
+    #[structopt(long, default_value = "7047")]
+    pub notary_port: u16,
+
+    /// Connects to the notary server without TLS, only use with a notary running locally
+    #[structopt(long)]
+    pub notary_no_tls: bool,
+
+    /// API key of the notary server, if whitelisting is enabled
+    #[structopt(long)]
+    pub notary_api_key: Option<String>,
+
+    /// Maximum number of bytes that can be sent
+    #[structopt(long)]
+    pub max_sent_data: Option<usize>,
+
+    /// Maximum number of bytes that can be received
+    #[structopt(long)]
+    pub max_recv_data: Option<usize>,
+
+    /// IP address of the server to connect to instead of resolving the host of the URL, e.g. to
+    /// test a staging server. The certificate is still validated against the host
+    #[structopt(long)]
+    pub connect_to: Option<IpAddr>,
+
+    /// Prints the transcripts of the session to stderr, with the data which is not revealed by the
+    /// proof shaded and the commitments labelled
+    #[structopt(long)]
+    pub inspect: bool,
+
+    /// File to write the proof to
+    #[structopt(short, long, default_value = "proof.json")]
+    pub output: PathBuf,
+}
+
+impl Cli {
+    /// Returns the options of the notarization.
+    ///
+    /// Headers are loaded from the headers file, the command line and the environment, in that
+    /// order.
+    pub fn into_options(self) -> Result<NotarizeOptions> {
+        let mut headers = match &self.headers_file {
+            Some(path) => load_headers_file(path)?,
+            None => Vec::new(),
+        };
+        for header in &self.headers {
+            headers.push(parse_header(header)?);
+        }
+        for header_env in &self.header_envs {
+            headers.push(parse_header_env(header_env)?);
+        }
+
+        Ok(NotarizeOptions {
+            url: self.url,
+            method: self.method,
+            headers,
+            body: self.data,
+            redact_sent: self.redact_sent,
+            redact_recv: self.redact_recv,
+            notary: NotaryOptions {
+                host: self.notary_host,
+                port: self.notary_port,
+                tls: !self.notary_no_tls,
+                api_key: self.notary_api_key,
+            },
+            max_sent_data: self.max_sent_data,
+            max_recv_data: self.max_recv_data,
+            server_addr: self.connect_to,
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//! Command-line tool to notarize an HTTPS request with a notary server.
+//!
+//! The tool sends a single request to the server, notarizes the session with the notary, and
+//! writes a proof revealing the request and the response, except for the parts matched by the
+//! redaction regexes.
+
+mod cli;
+
+use std::io::IsTerminal;
+
+use eyre::{Result, WrapErr};
+use structopt::StructOpt;
+use tracing_subscriber::EnvFilter;
+
+use tlsn_core::inspect::{PrintStyle, SessionPrinter};
+use tlsn_notarize::{build_proof, notarize_session};
+
+use crate::cli::Cli;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()))
+        .init();
+
+    let cli = Cli::from_args();
+    let output = cli.output.clone();
+    let inspect = cli.inspect;
+    let options = cli.into_options()?;
+
+    let session = notarize_session(options.clone()).await?;
+    let proof = build_proof(&session, &options)?;
+
+    if inspect {
+        let style = if std::io::stderr().is_terminal() {
+            PrintStyle::Ansi
+        } else {
+            PrintStyle::Plain
+        };
+        eprint!(
+            "{}",
+            SessionPrinter::new(session.data())
+                .reveal_proof(&proof.substrings)
+                .style(style)
+        );
+    }
+
+    tokio::fs::write(&output, serde_json::to_vec_pretty(&proof)?)
+        .await
+        .wrap_err_with(|| format!("Failed to write the proof to {}", output.display()))?;
+
+    eprintln!("Proof written to {}", output.display());
+
+    Ok(())
+}
@@ -0,0 +1,398 @@
+//! Abort subprotocol.
+//!
+//! Either party can abort a session at any point using an [`AbortHandle`]. The aborting party
+//! sends a [`SessionAbort`] over a dedicated stream of the multiplexer, which the peer
+//! acknowledges. If the verifier is a notary, it attaches a [`SignedAbortReceipt`] to its
+//! message.
+//!
+//! Once the exchange completes, both parties terminate the protocol with an [`Aborted`] error
+//! describing the same abort, and close the connection. A party only processes an abort from its
+//! peer while it is running a phase of the protocol, so the acknowledgement may be delayed, e.g.
+//! while the prover builds its commitments.
+
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures::{
+    future::{pending, poll_fn, FutureExt},
+    pin_mut, select,
+};
+use futures_timer::Delay;
+use serio::{stream::IoStreamExt, SinkExt as _};
+use tlsn_core::{
+    msg::{
+        AbortMessage, AbortReason, AbortReceipt, AbortStats, SessionAbort, SessionParty,
+        SessionPhase, SignedAbortReceipt,
+    },
+    Signature,
+};
+use tracing::{debug, warn};
+
+use crate::{Io, Role};
+
+/// Id of the multiplexer stream used by the abort subprotocol.
+pub const ABORT_STREAM_ID: &[u8] = b"tlsnotary/abort";
+
+/// Signs the bytes of an [`AbortReceipt`].
+pub type AbortReceiptSigner = Arc<dyn Fn(&[u8]) -> Signature + Send + Sync>;
+
+/// The party which initiated an abort, from the point of view of the local party.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortOrigin {
+    /// The local party aborted the session.
+    Local,
+    /// The peer aborted the session.
+    Remote,
+}
+
+/// The terminal state of an aborted session.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("session aborted ({origin:?}): {:?}", .abort.reason)]
+pub struct Aborted {
+    /// The party which aborted the session.
+    pub origin: AbortOrigin,
+    /// The abort.
+    pub abort: SessionAbort,
+    /// The receipt issued by the notary, if any.
+    pub receipt: Option<SignedAbortReceipt>,
+}
+
+/// A handle to abort a session.
+#[derive(Debug, Clone, Default)]
+pub struct AbortHandle(Arc<Mutex<AbortRequest>>);
+
+#[derive(Debug, Default)]
+struct AbortRequest {
+    requested: bool,
+    request: Option<(AbortReason, Option<String>)>,
+    waker: Option<Waker>,
+}
+
+impl AbortHandle {
+    /// Creates a new handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the session to be aborted.
+    ///
+    /// Returns `false` if an abort was already requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason for aborting.
+    /// * `details` - Human readable details sent to the peer.
+    pub fn abort(&self, reason: AbortReason, details: Option<String>) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        if inner.requested {
+            return false;
+        }
+
+        inner.requested = true;
+        inner.request = Some((reason, details));
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+
+        true
+    }
+
+    /// Returns `true` if an abort was requested.
+    pub fn is_aborted(&self) -> bool {
+        self.0.lock().unwrap().requested
+    }
+
+    fn poll_request(&self, cx: &mut Context<'_>) -> Poll<(AbortReason, Option<String>)> {
+        let mut inner = self.0.lock().unwrap();
+        match inner.request.take() {
+            Some(request) => Poll::Ready(request),
+            None => {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Channel of the abort subprotocol.
+pub struct AbortChannel {
+    io: Io,
+    id: String,
+    party: SessionParty,
+    handle: AbortHandle,
+    signer: Option<AbortReceiptSigner>,
+    start: web_time::Instant,
+    deadline: Option<web_time::Instant>,
+    closed: bool,
+}
+
+impl AbortChannel {
+    /// Creates a new abort channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `io` - The stream opened with [`ABORT_STREAM_ID`].
+    /// * `role` - The role of the local party.
+    /// * `id` - The id of the session.
+    /// * `handle` - The handle used by the local party to abort.
+    pub fn new(io: Io, role: Role, id: impl Into<String>, handle: AbortHandle) -> Self {
+        Self {
+            io,
+            id: id.into(),
+            party: party(role),
+            handle,
+            signer: None,
+            start: web_time::Instant::now(),
+            deadline: None,
+            closed: false,
+        }
+    }
+
+    /// Sets the signer used to issue abort receipts, which should only be set by a notary.
+    pub fn with_receipt_signer(mut self, signer: Option<AbortReceiptSigner>) -> Self {
+        self.signer = signer;
+        self
+    }
+
+    /// Sets the time after which the session is aborted for exceeding its maximum duration.
+    ///
+    /// A guarded phase is aborted once the deadline passes, even if neither party makes progress.
+    pub fn with_deadline(mut self, deadline: Option<web_time::Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Returns the handle to abort the session.
+    pub fn handle(&self) -> AbortHandle {
+        self.handle.clone()
+    }
+
+    /// Runs a phase of the protocol until completion, unless it is aborted by either party.
+    ///
+    /// # Arguments
+    ///
+    /// * `phase` - The phase of the protocol.
+    /// * `fut` - The future running the phase.
+    pub async fn guard<F: Future>(
+        &mut self,
+        phase: SessionPhase,
+        fut: F,
+    ) -> Result<F::Output, Aborted> {
+        let fut = fut.fuse();
+        let deadline = self.deadline;
+        let expired = async move {
+            match deadline {
+                Some(deadline) => {
+                    Delay::new(deadline.saturating_duration_since(web_time::Instant::now())).await
+                }
+                None => pending().await,
+            }
+        }
+        .fuse();
+        pin_mut!(fut, expired);
+
+        loop {
+            let event = {
+                let handle = &self.handle;
+                let local = poll_fn(|cx| handle.poll_request(cx)).fuse();
+
+                let io = &mut self.io;
+                let closed = self.closed;
+                let remote = async move {
+                    if closed {
+                        pending::<()>().await;
+                    }
+                    io.expect_next::<AbortMessage>().await
+                }
+                .fuse();
+
+                pin_mut!(local, remote);
+                select! {
+                    () = expired => Event::Local(
+                        AbortReason::LimitExceeded,
+                        Some("session exceeded its maximum duration".to_string()),
+                    ),
+                    output = fut => return Ok(output),
+                    (reason, details) = local => Event::Local(reason, details),
+                    msg = remote => Event::Remote(msg),
+                }
+            };
+
+            match event {
+                Event::Local(reason, details) => {
+                    return Err(self.abort_local(phase, reason, details).await);
+                }
+                Event::Remote(Ok(AbortMessage::Abort { abort, receipt })) => {
+                    return Err(self.abort_remote(abort, receipt).await);
+                }
+                Event::Remote(Ok(AbortMessage::Ack { .. })) => {
+                    warn!("received unexpected abort acknowledgement");
+                }
+                Event::Remote(Err(e)) => {
+                    // The peer closed the connection, the protocol surfaces the error.
+                    debug!("abort stream closed: {e}");
+                    self.closed = true;
+                }
+            }
+        }
+    }
+
+    async fn abort_local(
+        &mut self,
+        phase: SessionPhase,
+        reason: AbortReason,
+        details: Option<String>,
+    ) -> Aborted {
+        let abort = SessionAbort {
+            reason,
+            details,
+            stats: AbortStats {
+                phase,
+                elapsed_ms: self.start.elapsed().as_millis() as u64,
+            },
+        };
+        let receipt = self.issue_receipt(self.party, &abort);
+
+        debug!("aborting session: {:?}", abort.reason);
+
+        let peer_receipt = if self.closed {
+            None
+        } else if let Err(e) = self
+            .io
+            .send(AbortMessage::Abort {
+                abort: abort.clone(),
+                receipt: receipt.clone(),
+            })
+            .await
+        {
+            debug!("failed to send abort: {e}");
+            None
+        } else {
+            // Both parties may abort concurrently, in which case the peer's abort acknowledges ours.
+            match self.io.expect_next::<AbortMessage>().await {
+                Ok(AbortMessage::Ack { receipt }) | Ok(AbortMessage::Abort { receipt, .. }) => {
+                    receipt
+                }
+                Err(e) => {
+                    debug!("abort was not acknowledged: {e}");
+                    None
+                }
+            }
+        };
+
+        Aborted {
+            origin: AbortOrigin::Local,
+            abort,
+            receipt: receipt.or(peer_receipt),
+        }
+    }
+
+    async fn abort_remote(
+        &mut self,
+        abort: SessionAbort,
+        peer_receipt: Option<SignedAbortReceipt>,
+    ) -> Aborted {
+        debug!("peer aborted session: {:?}", abort.reason);
+
+        let receipt = self.issue_receipt(peer(self.party), &abort);
+        if let Err(e) = self
+            .io
+            .send(AbortMessage::Ack {
+                receipt: receipt.clone(),
+            })
+            .await
+        {
+            debug!("failed to acknowledge abort: {e}");
+        }
+
+        Aborted {
+            origin: AbortOrigin::Remote,
+            abort,
+            receipt: receipt.or(peer_receipt),
+        }
+    }
+
+    fn issue_receipt(
+        &self,
+        initiator: SessionParty,
+        abort: &SessionAbort,
+    ) -> Option<SignedAbortReceipt> {
+        let signer = self.signer.as_ref()?;
+        let receipt = AbortReceipt {
+            id: self.id.clone(),
+            initiator,
+            abort: abort.clone(),
+            time: web_time::UNIX_EPOCH.elapsed().unwrap().as_secs(),
+        };
+        let signature = signer(&receipt.signed_bytes());
+
+        Some(SignedAbortReceipt { receipt, signature })
+    }
+}
+
+enum Event {
+    Local(AbortReason, Option<String>),
+    Remote(Result<AbortMessage, std::io::Error>),
+}
+
+fn party(role: Role) -> SessionParty {
+    match role {
+        Role::Prover => SessionParty::Prover,
+        Role::Verifier => SessionParty::Verifier,
+    }
+}
+
+fn peer(party: SessionParty) -> SessionParty {
+    match party {
+        SessionParty::Prover => SessionParty::Verifier,
+        SessionParty::Verifier => SessionParty::Prover,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::{attach_mux, MuxConfig};
+    use std::time::Duration;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+    use uid_mux::FramedUidMux;
+
+    #[tokio::test]
+    async fn test_deadline_aborts_stalled_phase() {
+        let (prover_socket, verifier_socket) = tokio::io::duplex(1 << 16);
+        let config = MuxConfig::default();
+        let (mut prover_mux, prover_ctrl) =
+            attach_mux(prover_socket.compat(), Role::Prover, &config);
+        let (mut verifier_mux, verifier_ctrl) =
+            attach_mux(verifier_socket.compat(), Role::Verifier, &config);
+
+        let prover = async {
+            let io = prover_ctrl.open_framed(ABORT_STREAM_ID).await.unwrap();
+            let mut abort = AbortChannel::new(io, Role::Prover, "test", AbortHandle::new());
+            abort.guard(SessionPhase::Setup, pending::<()>()).await
+        };
+        let verifier = async {
+            let io = verifier_ctrl.open_framed(ABORT_STREAM_ID).await.unwrap();
+            let mut abort = AbortChannel::new(io, Role::Verifier, "test", AbortHandle::new())
+                .with_deadline(Some(web_time::Instant::now() + Duration::from_millis(50)));
+            abort.guard(SessionPhase::Setup, pending::<()>()).await
+        };
+
+        // Neither phase makes progress, only the deadline can end them.
+        let (prover_result, verifier_result) = futures::join!(
+            prover_mux.poll_with(prover),
+            verifier_mux.poll_with(verifier)
+        );
+
+        let prover_aborted = prover_result.unwrap_err();
+        let verifier_aborted = verifier_result.unwrap_err();
+
+        assert_eq!(verifier_aborted.origin, AbortOrigin::Local);
+        assert_eq!(prover_aborted.origin, AbortOrigin::Remote);
+        assert_eq!(verifier_aborted.abort.reason, AbortReason::LimitExceeded);
+        assert!(verifier_aborted.abort.stats.elapsed_ms >= 50);
+    }
+}
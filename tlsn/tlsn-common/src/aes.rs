@@ -0,0 +1,63 @@
+//! Detection of the AES implementation used by the fixed-key cipher of the garbling scheme.
+//!
+//! The `aes` crate picks its implementation at runtime: AES-NI on x86 and the ARMv8 cryptography
+//! extensions on aarch64 when the CPU supports them, and otherwise a constant-time bitsliced
+//! software implementation, which is also the one used on wasm32. The software implementation is
+//! several times slower, so garbling, which is dominated by AES, is much slower without hardware
+//! support. [`AesBackend::detect`] performs the same detection so that the choice can be reported.
+
+use std::fmt;
+
+/// The AES implementation used by the fixed-key cipher.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AesBackend {
+    /// Hardware AES instructions, i.e. AES-NI or the ARMv8 cryptography extensions.
+    Hardware,
+    /// The portable constant-time software implementation.
+    #[default]
+    Software,
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+cpufeatures::new!(aes_intrinsics, "aes", "sse2");
+#[cfg(target_arch = "aarch64")]
+cpufeatures::new!(aes_intrinsics, "aes");
+
+impl AesBackend {
+    /// Detects the AES implementation supported by the CPU at runtime.
+    pub fn detect() -> Self {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+        if aes_intrinsics::get() {
+            return Self::Hardware;
+        }
+
+        Self::Software
+    }
+
+    /// Returns `true` if hardware AES instructions are used.
+    pub fn is_hardware(&self) -> bool {
+        matches!(self, Self::Hardware)
+    }
+}
+
+impl fmt::Display for AesBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hardware => f.write_str("hardware"),
+            Self::Software => f.write_str("software"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_is_stable() {
+        assert_eq!(AesBackend::detect(), AesBackend::detect());
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        assert_eq!(AesBackend::detect(), AesBackend::Software);
+    }
+}
@@ -0,0 +1,122 @@
+//! Typed channels over arbitrary transports.
+//!
+//! A [`Channel`] frames the messages of a single type over any `AsyncRead + AsyncWrite`
+//! transport using a serde [`Codec`], e.g. to run a protocol over a unix socket, an in-memory
+//! duplex or a WebRTC data channel.
+
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+use serde::{de::DeserializeOwned, Serialize};
+use serio::codec::Codec;
+
+/// A channel which sends and receives messages of type `T`.
+///
+/// Implements [`Sink<T>`](futures::Sink) and [`Stream`](futures::Stream) of `Result<T, _>`.
+#[derive(Debug)]
+pub struct Channel<T, F> {
+    framed: F,
+    _pd: PhantomData<fn(T) -> T>,
+}
+
+impl<T, F> Channel<T, F> {
+    /// Returns the framed transport.
+    pub fn into_inner(self) -> F {
+        self.framed
+    }
+}
+
+/// Creates a new channel over a transport, framing the messages with the provided codec.
+///
+/// # Arguments
+///
+/// * `io` - The transport.
+/// * `codec` - The codec used to serialize the messages, e.g. [`Bincode`](serio::codec::Bincode).
+pub fn channel<T, Io, C>(io: Io, codec: C) -> Channel<T, C::Framed>
+where
+    Io: AsyncRead + AsyncWrite + Send + Unpin,
+    C: Codec<Io>,
+{
+    Channel {
+        framed: codec.new_framed(io),
+        _pd: PhantomData,
+    }
+}
+
+impl<T, F> futures::Sink<T> for Channel<T, F>
+where
+    T: Serialize,
+    F: serio::Sink + Unpin,
+{
+    type Error = F::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().framed).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().framed).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().framed).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().framed).poll_close(cx)
+    }
+}
+
+impl<T, F> futures::Stream for Channel<T, F>
+where
+    T: DeserializeOwned,
+    F: serio::Stream + Unpin,
+{
+    type Item = Result<T, F::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().framed).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use serde::Deserialize;
+    use serio::codec::Bincode;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message {
+        id: u32,
+        payload: Vec<u8>,
+    }
+
+    #[tokio::test]
+    async fn test_channel() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+
+        let mut a = channel::<Message, _, _>(a.compat(), Bincode);
+        let mut b = channel::<Message, _, _>(b.compat(), Bincode);
+
+        let msg = Message {
+            id: 42,
+            payload: vec![1, 2, 3],
+        };
+
+        a.send(msg).await.unwrap();
+
+        assert_eq!(
+            b.next().await.unwrap().unwrap(),
+            Message {
+                id: 42,
+                payload: vec![1, 2, 3],
+            }
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Optional compression of the connection between the prover and the verifier.
+//!
+//! Compression is negotiated in the setup message, see [`crate::setup`]: each party announces
+//! whether it supports compression, and the connection is only compressed if both parties do.
+//! Otherwise, the connection falls back to being uncompressed.
+//!
+//! Note that garbled tables and OT messages are pseudorandom and do not compress, so the benefit
+//! depends on the remaining traffic. Compression is most useful on low bandwidth links.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_compression::futures::{bufread::ZstdDecoder, write::ZstdEncoder};
+use futures::{
+    io::{BufReader, ReadHalf, WriteHalf},
+    AsyncRead, AsyncReadExt, AsyncWrite,
+};
+
+/// A connection which may be compressed.
+pub enum MaybeCompressed<T> {
+    /// An uncompressed connection.
+    Plain(T),
+    /// A connection compressed with zstd.
+    Compressed(Compressed<T>),
+}
+
+impl<T: AsyncRead + AsyncWrite> MaybeCompressed<T> {
+    /// Wraps a connection, compressing it if `compress` is `true`.
+    pub(crate) fn new(io: T, compress: bool) -> Self {
+        if compress {
+            Self::Compressed(Compressed::new(io))
+        } else {
+            Self::Plain(io)
+        }
+    }
+}
+
+impl<T> MaybeCompressed<T> {
+    /// Returns `true` if the connection is compressed.
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, Self::Compressed(_))
+    }
+}
+
+/// A connection compressed with zstd.
+pub struct Compressed<T> {
+    reader: ZstdDecoder<BufReader<ReadHalf<T>>>,
+    writer: ZstdEncoder<WriteHalf<T>>,
+}
+
+impl<T: AsyncRead + AsyncWrite> Compressed<T> {
+    fn new(io: T) -> Self {
+        let (reader, writer) = io.split();
+        Self {
+            reader: ZstdDecoder::new(BufReader::new(reader)),
+            writer: ZstdEncoder::new(writer),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for MaybeCompressed<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(io) => Pin::new(io).poll_read(cx, buf),
+            Self::Compressed(io) => Pin::new(&mut io.reader).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncWrite for MaybeCompressed<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(io) => Pin::new(io).poll_write(cx, buf),
+            Self::Compressed(io) => Pin::new(&mut io.writer).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(io) => Pin::new(io).poll_flush(cx),
+            Self::Compressed(io) => Pin::new(&mut io.writer).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(io) => Pin::new(io).poll_close(cx),
+            Self::Compressed(io) => Pin::new(&mut io.writer).poll_close(cx),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! The MPC concurrency of a session.
+//!
+//! The concurrency is the maximum number of threads the MPC executor forks to run circuits and
+//! OT batches simultaneously. Both parties must use the same concurrency, so each party announces
+//! the concurrency it allows in the [setup message](crate::setup), and both use the lowest one. A
+//! memory-constrained prover can thus lower the peak memory of a session, at the cost of speed,
+//! while a verifier serving many sessions can cap it.
+
+/// Maximum concurrency which can be negotiated.
+pub const MAX_MPC_CONCURRENCY: usize = u8::MAX as usize;
@@ -1,34 +1,28 @@
 //! TLSNotary protocol config and config utilities.
 
-use crate::Role;
+use crate::{ot_pool::OtPoolSize, Role};
 
 /// Default for the maximum number of bytes that can be sent (4Kb).
 pub const DEFAULT_MAX_SENT_LIMIT: usize = 1 << 12;
 /// Default for the maximum number of bytes that can be received (16Kb).
 pub const DEFAULT_MAX_RECV_LIMIT: usize = 1 << 14;
-
-// Extra cushion room, eg. for sharing J0 blocks.
-const EXTRA_OTS: usize = 16384;
-const OTS_PER_BYTE_SENT: usize = 8;
-// Without deferred decryption we use 16, with it we use 8.
-const OTS_PER_BYTE_RECV: usize = 16;
+/// Default for the maximum number of threads the MPC executor forks.
+pub const DEFAULT_MPC_CONCURRENCY: usize = 8;
 
 /// Returns an estimate of the number of OTs that will be sent.
+///
+/// The estimate is sized for [`DEFAULT_RECORD_BUDGET`](crate::ot_pool::DEFAULT_RECORD_BUDGET)
+/// records, see [`OtPoolSize`] for the size of the pool of a session with a negotiated number of
+/// records. It saturates at `usize::MAX` if the pool overflows.
 pub fn ot_send_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
-    match role {
-        Role::Prover => EXTRA_OTS,
-        Role::Verifier => {
-            EXTRA_OTS + (max_sent_data * OTS_PER_BYTE_SENT) + (max_recv_data * OTS_PER_BYTE_RECV)
-        }
-    }
+    OtPoolSize::new(role, max_sent_data, max_recv_data, None).map_or(usize::MAX, |pool| pool.send)
 }
 
 /// Returns an estimate of the number of OTs that will be received.
+///
+/// The estimate is sized for [`DEFAULT_RECORD_BUDGET`](crate::ot_pool::DEFAULT_RECORD_BUDGET)
+/// records, see [`OtPoolSize`] for the size of the pool of a session with a negotiated number of
+/// records. It saturates at `usize::MAX` if the pool overflows.
 pub fn ot_recv_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
-    match role {
-        Role::Prover => {
-            EXTRA_OTS + (max_sent_data * OTS_PER_BYTE_SENT) + (max_recv_data * OTS_PER_BYTE_RECV)
-        }
-        Role::Verifier => EXTRA_OTS,
-    }
+    OtPoolSize::new(role, max_sent_data, max_recv_data, None).map_or(usize::MAX, |pool| pool.recv)
 }
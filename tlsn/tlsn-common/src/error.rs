@@ -0,0 +1,303 @@
+//! Classification of errors.
+//!
+//! The errors of the prover, the verifier and the notary are classified into an
+//! [`ErrorCategory`], which tells whether the operation can be retried, and identified by a
+//! stable [`code`](ClassifiedError::code), so that they can be handled programmatically without
+//! matching on error messages.
+
+use std::{error::Error, fmt, io, str::FromStr};
+
+use tls_client::BackendError;
+use tls_mpc::{MpcTlsError, MpcTlsErrorKind};
+use tlsn_core::msg::AbortReason;
+
+use crate::abort::Aborted;
+
+/// The category of an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// A transient network failure, e.g. a connection reset or a timeout.
+    NetworkTransient,
+    /// The peer or the server deviated from the protocol.
+    ProtocolViolation,
+    /// The session was rejected by the policy of a party, e.g. the server is not allowed.
+    PolicyRejected,
+    /// A limit was exceeded, e.g. the transcript size or a rate limit.
+    ResourceLimit,
+    /// The session was cancelled.
+    Cancelled,
+    /// The inputs provided by the caller are invalid.
+    InvalidInput,
+    /// The operation is not possible in the current state of the session, e.g. the connection is
+    /// already closed.
+    InvalidState,
+    /// An internal error occurred.
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Returns whether an operation failing with an error of this category can be retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::NetworkTransient)
+    }
+
+    /// Returns the stable name of the category.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NetworkTransient => "network_transient",
+            Self::ProtocolViolation => "protocol_violation",
+            Self::PolicyRejected => "policy_rejected",
+            Self::ResourceLimit => "resource_limit",
+            Self::Cancelled => "cancelled",
+            Self::InvalidInput => "invalid_input",
+            Self::InvalidState => "invalid_state",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// Returns the category of an IO error.
+    pub fn from_io(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::WouldBlock => Self::NetworkTransient,
+            io::ErrorKind::InvalidData => Self::ProtocolViolation,
+            io::ErrorKind::InvalidInput => Self::InvalidInput,
+            _ => Self::Internal,
+        }
+    }
+
+    /// Returns the category of an error whose source chain may contain an IO error, or `fallback`
+    /// if it does not.
+    pub fn from_source_chain(err: &(dyn Error + 'static), fallback: Self) -> Self {
+        let mut source = Some(err);
+        while let Some(err) = source {
+            if let Some(err) = err.downcast_ref::<io::Error>() {
+                return Self::from_io(err);
+            }
+            source = err.source();
+        }
+
+        fallback
+    }
+}
+
+impl FromStr for ErrorCategory {
+    type Err = UnknownErrorCategory;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            Self::NetworkTransient,
+            Self::ProtocolViolation,
+            Self::PolicyRejected,
+            Self::ResourceLimit,
+            Self::Cancelled,
+            Self::InvalidInput,
+            Self::InvalidState,
+            Self::Internal,
+        ]
+        .into_iter()
+        .find(|category| category.as_str() == s)
+        .ok_or_else(|| UnknownErrorCategory(s.to_string()))
+    }
+}
+
+/// An error returned when parsing an unknown [`ErrorCategory`].
+#[derive(Debug, thiserror::Error)]
+#[error("unknown error category: {0}")]
+pub struct UnknownErrorCategory(String);
+
+impl From<AbortReason> for ErrorCategory {
+    fn from(reason: AbortReason) -> Self {
+        match reason {
+            AbortReason::Cancelled => Self::Cancelled,
+            AbortReason::Timeout => Self::NetworkTransient,
+            AbortReason::LimitExceeded => Self::ResourceLimit,
+            AbortReason::PolicyViolation => Self::PolicyRejected,
+            AbortReason::ProtocolError => Self::ProtocolViolation,
+            AbortReason::Internal => Self::Internal,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error classified into an [`ErrorCategory`], with a stable code.
+pub trait ClassifiedError: Error {
+    /// Returns the category of the error.
+    fn category(&self) -> ErrorCategory;
+
+    /// Returns the stable code of the error, e.g. `prover.server_no_close_notify`.
+    ///
+    /// Codes are never reused for a different error, so they can be relied upon across versions.
+    fn code(&self) -> &'static str;
+
+    /// Returns whether the operation which failed with this error can be retried.
+    fn is_retryable(&self) -> bool {
+        self.category().is_retryable()
+    }
+}
+
+impl ClassifiedError for Aborted {
+    fn category(&self) -> ErrorCategory {
+        self.abort.reason.into()
+    }
+
+    fn code(&self) -> &'static str {
+        match self.abort.reason {
+            AbortReason::Cancelled => "abort.cancelled",
+            AbortReason::Timeout => "abort.timeout",
+            AbortReason::LimitExceeded => "abort.limit_exceeded",
+            AbortReason::PolicyViolation => "abort.policy_violation",
+            AbortReason::ProtocolError => "abort.protocol_error",
+            AbortReason::Internal => "abort.internal",
+        }
+    }
+}
+
+impl ClassifiedError for MpcTlsError {
+    fn category(&self) -> ErrorCategory {
+        let fallback = match self.kind() {
+            MpcTlsErrorKind::Io | MpcTlsErrorKind::Ctx => ErrorCategory::NetworkTransient,
+            MpcTlsErrorKind::PeerMisbehaved => return ErrorCategory::ProtocolViolation,
+            MpcTlsErrorKind::QuotaExceeded => return ErrorCategory::ResourceLimit,
+            MpcTlsErrorKind::Config => return ErrorCategory::InvalidInput,
+            // The 2PC protocols only fail on their own if the peer deviates from them.
+            MpcTlsErrorKind::Mpc
+            | MpcTlsErrorKind::KeyExchange
+            | MpcTlsErrorKind::Prf
+            | MpcTlsErrorKind::Encrypt
+            | MpcTlsErrorKind::Decrypt => ErrorCategory::ProtocolViolation,
+            _ => ErrorCategory::Internal,
+        };
+
+        // A failure of the connection to the peer surfaces in any of the protocols.
+        ErrorCategory::from_source_chain(self, fallback)
+    }
+
+    fn code(&self) -> &'static str {
+        match self.kind() {
+            MpcTlsErrorKind::Io => "mpc_tls.io",
+            MpcTlsErrorKind::Ctx => "mpc_tls.context",
+            MpcTlsErrorKind::PeerMisbehaved => "mpc_tls.peer_misbehaved",
+            MpcTlsErrorKind::QuotaExceeded => "mpc_tls.quota_exceeded",
+            MpcTlsErrorKind::Config => "mpc_tls.config",
+            MpcTlsErrorKind::Mpc => "mpc_tls.mpc",
+            MpcTlsErrorKind::KeyExchange => "mpc_tls.key_exchange",
+            MpcTlsErrorKind::Prf => "mpc_tls.prf",
+            MpcTlsErrorKind::Encrypt => "mpc_tls.encrypt",
+            MpcTlsErrorKind::Decrypt => "mpc_tls.decrypt",
+            MpcTlsErrorKind::State => "mpc_tls.state",
+            _ => "mpc_tls.other",
+        }
+    }
+}
+
+impl ClassifiedError for BackendError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::ConnectionError(_) => ErrorCategory::NetworkTransient,
+            Self::PeerMisbehaved(_) | Self::InvalidServerKey | Self::DecryptionError(_) => {
+                ErrorCategory::ProtocolViolation
+            }
+            Self::UnsupportedProtocolVersion(_)
+            | Self::UnsupportedCiphersuite(_)
+            | Self::UnsupportedCurveGroup(_) => ErrorCategory::PolicyRejected,
+            Self::QuotaExceeded(_) => ErrorCategory::ResourceLimit,
+            Self::InvalidConfig(_) => ErrorCategory::InvalidInput,
+            Self::InvalidState(_) | Self::InternalError(_) | Self::EncryptionError(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidState(_) => "tls_backend.invalid_state",
+            Self::UnsupportedProtocolVersion(_) => "tls_backend.unsupported_protocol_version",
+            Self::UnsupportedCiphersuite(_) => "tls_backend.unsupported_ciphersuite",
+            Self::UnsupportedCurveGroup(_) => "tls_backend.unsupported_curve_group",
+            Self::InvalidConfig(_) => "tls_backend.invalid_config",
+            Self::InvalidServerKey => "tls_backend.invalid_server_key",
+            Self::InternalError(_) => "tls_backend.internal",
+            Self::EncryptionError(_) => "tls_backend.encryption",
+            Self::DecryptionError(_) => "tls_backend.decryption",
+            Self::ConnectionError(_) => "tls_backend.connection",
+            Self::PeerMisbehaved(_) => "tls_backend.peer_misbehaved",
+            Self::QuotaExceeded(_) => "tls_backend.quota_exceeded",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("wrapped")]
+    struct Wrapped(#[source] io::Error);
+
+    #[test]
+    fn test_error_category() {
+        let reset = io::Error::from(io::ErrorKind::ConnectionReset);
+        assert_eq!(
+            ErrorCategory::from_io(&reset),
+            ErrorCategory::NetworkTransient
+        );
+        assert!(ErrorCategory::from_io(&reset).is_retryable());
+
+        let wrapped = Wrapped(io::Error::from(io::ErrorKind::InvalidData));
+        assert_eq!(
+            ErrorCategory::from_source_chain(&wrapped, ErrorCategory::Internal),
+            ErrorCategory::ProtocolViolation
+        );
+
+        assert_eq!(
+            ErrorCategory::from_source_chain(&fmt::Error, ErrorCategory::Internal),
+            ErrorCategory::Internal
+        );
+
+        assert_eq!(
+            ErrorCategory::from(AbortReason::LimitExceeded),
+            ErrorCategory::ResourceLimit
+        );
+        assert!(!ErrorCategory::PolicyRejected.is_retryable());
+
+        assert_eq!(
+            "resource_limit".parse::<ErrorCategory>().unwrap(),
+            ErrorCategory::ResourceLimit
+        );
+        assert!("unknown".parse::<ErrorCategory>().is_err());
+    }
+
+    #[test]
+    fn test_mpc_tls_error_category() {
+        let err = MpcTlsError::from(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert_eq!(err.category(), ErrorCategory::NetworkTransient);
+        assert_eq!(err.code(), "mpc_tls.io");
+
+        // The kind of the error is kept when the TLS client reports it.
+        let err = BackendError::from(err);
+        assert_eq!(err.category(), ErrorCategory::NetworkTransient);
+        assert_eq!(err.code(), "tls_backend.connection");
+
+        assert_eq!(
+            BackendError::InternalError("internal".to_string()).category(),
+            ErrorCategory::Internal
+        );
+        assert_eq!(
+            BackendError::InvalidServerKey.category(),
+            ErrorCategory::ProtocolViolation
+        );
+    }
+}
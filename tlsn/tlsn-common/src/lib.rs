@@ -4,8 +4,22 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod abort;
+pub mod aes;
+pub mod channel;
+pub mod compress;
+pub mod concurrency;
 pub mod config;
+pub mod error;
 pub mod mux;
+pub mod ot_pool;
+pub mod plaintext_hash;
+pub mod reconnect;
+pub mod rng;
+pub mod schedule;
+pub mod setup;
+pub mod stats;
+pub mod timing;
 
 use serio::codec::Codec;
 
@@ -31,6 +45,7 @@ pub type DEAPThread = mpz_garble::protocol::deap::DEAPThread<Context, OTSender,
 /// The party's role in the TLSN protocol.
 ///
 /// A Notary is classified as a Verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Role {
     /// The prover.
     Prover,
@@ -10,16 +10,84 @@ use serio::codec::Bincode;
 use tracing::error;
 use uid_mux::{yamux, FramedMux};
 
-use crate::Role;
+use crate::{
+    schedule::{Scheduled, SchedulerConfig, StreamPriorities},
+    Role,
+};
 
 /// Multiplexer supporting unique deterministic stream IDs.
 pub type Mux<Io> = yamux::Yamux<Io>;
 /// Multiplexer controller providing streams with a codec attached.
 pub type MuxControl = FramedMux<yamux::YamuxCtrl, Bincode>;
 
+/// Default for the maximum number of concurrent streams.
+pub const DEFAULT_MAX_NUM_STREAMS: usize = 64;
+
+/// Configuration of the multiplexer.
+///
+/// The defaults are tuned for low latency links. On links with a high bandwidth-delay product,
+/// increasing the receive window improves the throughput of large transfers such as garbled
+/// circuits.
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct MuxConfig {
+    /// Maximum number of concurrent streams.
+    ///
+    /// Must be the same for the prover and the verifier.
+    #[builder(default = "DEFAULT_MAX_NUM_STREAMS")]
+    max_num_streams: usize,
+    /// Maximum receive window of the connection, in bytes, which is shared by all streams.
+    ///
+    /// If `None`, the receive window is unbounded.
+    #[builder(default = "Some(1024 * 1024 * 1024)")]
+    max_connection_receive_window: Option<usize>,
+    /// Maximum size of a single frame sent on a stream, in bytes.
+    #[builder(default = "16 * 1024")]
+    split_send_size: usize,
+    /// Configuration of the scheduler sending the frames of high priority streams before the
+    /// frames of bulk streams, which is useful when the uplink is constrained.
+    ///
+    /// If `None`, frames are sent in the order they are produced.
+    #[builder(setter(strip_option), default)]
+    scheduler: Option<SchedulerConfig>,
+}
+
+impl MuxConfig {
+    /// Creates a new builder for the multiplexer configuration.
+    pub fn builder() -> MuxConfigBuilder {
+        MuxConfigBuilder::default()
+    }
+
+    /// Returns the maximum number of concurrent streams.
+    pub fn max_num_streams(&self) -> usize {
+        self.max_num_streams
+    }
+
+    /// Returns the maximum receive window of the connection, in bytes.
+    pub fn max_connection_receive_window(&self) -> Option<usize> {
+        self.max_connection_receive_window
+    }
+
+    /// Returns the maximum size of a single frame sent on a stream, in bytes.
+    pub fn split_send_size(&self) -> usize {
+        self.split_send_size
+    }
+
+    /// Returns the configuration of the frame scheduler, if frames are scheduled.
+    pub fn scheduler(&self) -> Option<&SchedulerConfig> {
+        self.scheduler.as_ref()
+    }
+}
+
+impl Default for MuxConfig {
+    fn default() -> Self {
+        Self::builder().build().unwrap()
+    }
+}
+
 /// Multiplexer future which must be polled for the muxer to make progress.
 pub struct MuxFuture(
     Box<dyn FusedFuture<Output = Result<(), yamux::ConnectionError>> + Send + Unpin>,
+    Option<StreamPriorities>,
 );
 
 impl MuxFuture {
@@ -28,6 +96,13 @@ impl MuxFuture {
         self.0.is_terminated()
     }
 
+    /// Returns the priorities of the streams, if frames are scheduled.
+    ///
+    /// The streams opened with a configured priority must be registered with it.
+    pub fn priorities(&self) -> Option<StreamPriorities> {
+        self.1.clone()
+    }
+
     /// Awaits a future, polling the muxer future concurrently.
     pub async fn poll_with<F, R>(&mut self, fut: F) -> R
     where
@@ -67,24 +142,44 @@ impl Future for MuxFuture {
 ///
 /// * `socket` - The socket to attach the multiplexer to.
 /// * `role` - The role of the party using the multiplexer.
+/// * `config` - The configuration of the multiplexer.
 pub fn attach_mux<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
     role: Role,
+    config: &MuxConfig,
 ) -> (MuxFuture, MuxControl) {
     let mut mux_config = yamux::Config::default();
-    mux_config.set_max_num_streams(64);
+    mux_config.set_max_num_streams(config.max_num_streams);
+    mux_config.set_max_connection_receive_window(config.max_connection_receive_window);
+    mux_config.set_split_send_size(config.split_send_size);
 
     let mux_role = match role {
         Role::Prover => yamux::Mode::Client,
         Role::Verifier => yamux::Mode::Server,
     };
 
-    let mux = Mux::new(socket, mux_config, mux_role);
-    let ctrl = FramedMux::new(mux.control(), Bincode);
+    let (fut, ctrl) = match &config.scheduler {
+        Some(scheduler) => {
+            let socket = Scheduled::new(socket, scheduler.clone());
+            let priorities = socket.priorities();
+            let mux = Mux::new(socket, mux_config, mux_role);
+            let ctrl = mux.control();
+            (
+                MuxFuture(Box::new(mux.into_future().fuse()), Some(priorities)),
+                ctrl,
+            )
+        }
+        None => {
+            let mux = Mux::new(socket, mux_config, mux_role);
+            let ctrl = mux.control();
+            (MuxFuture(Box::new(mux.into_future().fuse()), None), ctrl)
+        }
+    };
+    let ctrl = FramedMux::new(ctrl, Bincode);
 
     if let Role::Prover = role {
-        ctrl.mux().alloc(64);
+        ctrl.mux().alloc(config.max_num_streams);
     }
 
-    (MuxFuture(Box::new(mux.into_future().fuse())), ctrl)
+    (fut, ctrl)
 }
@@ -0,0 +1,189 @@
+//! Sizing of the pool of oblivious transfers preprocessed during setup.
+//!
+//! The number of OTs a session requires is predictable from the maximum amount of data sent and
+//! received, and from the number of TLS records, as every cipher suite supported by MPC-TLS uses
+//! AES-128-GCM. Both parties preprocess exactly this number of OTs during setup, so the session
+//! neither over-provisions the pool nor stalls to extend it while the connection to the server is
+//! open.
+//!
+//! Both pools must have the same size, so they are sized from the limits negotiated when the
+//! connection is set up, see [`SessionParams`].
+
+use crate::{setup::SessionParams, Role};
+
+/// Number of OTs preprocessed on top of the ones for the data, for the handshake and the first
+/// [`DEFAULT_RECORD_BUDGET`] records, e.g. for sharing their J0 blocks.
+const EXTRA_OTS: usize = 16384;
+/// Number of OTs required by each TLS record beyond [`DEFAULT_RECORD_BUDGET`]. The J0 block of a
+/// record is shared by masking it with a 128-bit mask of each party.
+const OTS_PER_RECORD: usize = 2 * 128;
+/// Number of OTs required by each byte sent.
+const OTS_PER_BYTE_SENT: usize = 8;
+/// Number of OTs required by each byte received. Without deferred decryption we use 16, with it
+/// we use 8.
+const OTS_PER_BYTE_RECV: usize = 16;
+
+/// Number of TLS records covered by the extra OTs, which the pool is sized for if neither party
+/// limits the number of records.
+pub const DEFAULT_RECORD_BUDGET: usize = 32;
+
+/// The number of OTs a party preprocesses during setup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OtPoolSize {
+    /// Number of OTs preprocessed as the sender.
+    pub send: usize,
+    /// Number of OTs preprocessed as the receiver.
+    pub recv: usize,
+}
+
+impl OtPoolSize {
+    /// Returns the size of the pool of a party.
+    ///
+    /// Returns an error if the size of the pool overflows, which can only be the case for limits
+    /// which no session could reach.
+    ///
+    /// # Arguments
+    ///
+    /// * `role` - The role of the party.
+    /// * `max_sent_data` - The maximum number of bytes sent to the server.
+    /// * `max_recv_data` - The maximum number of bytes received from the server.
+    /// * `max_records` - The negotiated maximum number of TLS records, or `None` to size the pool
+    ///   for [`DEFAULT_RECORD_BUDGET`] records.
+    pub fn new(
+        role: Role,
+        max_sent_data: usize,
+        max_recv_data: usize,
+        max_records: Option<usize>,
+    ) -> Result<Self, OtPoolOverflow> {
+        // A lower limit does not shrink the pool, the extra OTs are only a rough estimate of
+        // what the handshake needs.
+        let records = max_records.unwrap_or(DEFAULT_RECORD_BUDGET);
+        let fixed = records
+            .saturating_sub(DEFAULT_RECORD_BUDGET)
+            .checked_mul(OTS_PER_RECORD)
+            .and_then(|ots| ots.checked_add(EXTRA_OTS))
+            .ok_or(OtPoolOverflow)?;
+        let data = max_sent_data
+            .checked_mul(OTS_PER_BYTE_SENT)
+            .zip(max_recv_data.checked_mul(OTS_PER_BYTE_RECV))
+            .and_then(|(sent, recv)| sent.checked_add(recv))
+            .ok_or(OtPoolOverflow)?;
+        let with_data = fixed.checked_add(data).ok_or(OtPoolOverflow)?;
+        // Both pools must fit, so their total can be compared against a quota.
+        with_data.checked_add(fixed).ok_or(OtPoolOverflow)?;
+
+        // The prover evaluates the circuits, so it receives the OTs for the data.
+        Ok(match role {
+            Role::Prover => Self {
+                send: fixed,
+                recv: with_data,
+            },
+            Role::Verifier => Self {
+                send: with_data,
+                recv: fixed,
+            },
+        })
+    }
+
+    /// Returns the size of the pool of a party for the negotiated parameters of a session.
+    ///
+    /// # Arguments
+    ///
+    /// * `role` - The role of the party.
+    /// * `params` - The negotiated parameters of the session.
+    pub fn for_session(role: Role, params: &SessionParams) -> Result<Self, OtPoolOverflow> {
+        Self::new(
+            role,
+            params.max_sent_data,
+            params.max_recv_data,
+            params.max_records,
+        )
+    }
+
+    /// Returns the total number of OTs of the pool.
+    pub fn total(&self) -> usize {
+        self.send + self.recv
+    }
+
+    /// Checks that the pool does not exceed the maximum number of OTs of a party.
+    ///
+    /// The OT extension is only run once, during setup, to preprocess the pool. It refuses to
+    /// perform any OT beyond the preprocessed ones, so a party whose pool is within its quota
+    /// never performs more OTs than the quota, whatever its peer requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_ots` - The maximum number of OTs of the party.
+    pub fn check_quota(&self, max_ots: usize) -> Result<(), OtQuotaExceeded> {
+        if self.total() > max_ots {
+            return Err(OtQuotaExceeded {
+                ots: self.total(),
+                max_ots,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned if the size of the pool of OTs overflows.
+#[derive(Debug, thiserror::Error)]
+#[error("the size of the pool of oblivious transfers overflows")]
+pub struct OtPoolOverflow;
+
+/// Error returned if the pool of OTs exceeds the quota of a party.
+#[derive(Debug, thiserror::Error)]
+#[error("max number of oblivious transfers exceeded: {ots} > {max_ots}")]
+pub struct OtQuotaExceeded {
+    ots: usize,
+    max_ots: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pools_match() {
+        let prover = OtPoolSize::new(Role::Prover, 4096, 16384, Some(10)).unwrap();
+        let verifier = OtPoolSize::new(Role::Verifier, 4096, 16384, Some(10)).unwrap();
+
+        assert_eq!(prover.send, verifier.recv);
+        assert_eq!(prover.recv, verifier.send);
+
+        // Each record beyond the budget adds a fixed number of OTs to both pools.
+        let budget = DEFAULT_RECORD_BUDGET;
+        let more = OtPoolSize::new(Role::Prover, 4096, 16384, Some(budget + 1)).unwrap();
+        assert_eq!(
+            more.total()
+                - OtPoolSize::new(Role::Prover, 4096, 16384, None)
+                    .unwrap()
+                    .total(),
+            2 * OTS_PER_RECORD
+        );
+    }
+
+    #[test]
+    fn test_low_record_limit_keeps_extra_ots() {
+        let unlimited = OtPoolSize::new(Role::Prover, 4096, 16384, None).unwrap();
+        let limited = OtPoolSize::new(Role::Prover, 4096, 16384, Some(1)).unwrap();
+
+        assert_eq!(limited, unlimited);
+        assert_eq!(limited.send, EXTRA_OTS);
+    }
+
+    #[test]
+    fn test_check_quota() {
+        let pool = OtPoolSize::new(Role::Verifier, 4096, 16384, Some(10)).unwrap();
+
+        assert!(pool.check_quota(pool.total()).is_ok());
+        assert!(pool.check_quota(pool.total() - 1).is_err());
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert!(OtPoolSize::new(Role::Verifier, 4096, 16384, Some(usize::MAX)).is_err());
+        assert!(OtPoolSize::new(Role::Verifier, usize::MAX, 16384, None).is_err());
+        assert!(OtPoolSize::new(Role::Prover, 4096, usize::MAX / OTS_PER_BYTE_RECV, None).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Plaintext hash commitments, which the prover proves to the notary during notarization.
+//!
+//! The prover proves in zero-knowledge that the salted hash of each commitment is computed from the
+//! transcript in the MPC-TLS session, so the notary can sign the commitments without learning the
+//! transcript.
+
+use std::sync::Arc;
+
+use mpz_circuits::{circuits::sha256_trace, Circuit, CircuitBuilder, Tracer};
+use mpz_garble::{value::ValueRef, Memory, MemoryError};
+use tlsn_core::{
+    commitment::{
+        hash::{SALT_LEN, SHA256_INITIAL_STATE},
+        CommitmentInfo,
+    },
+    transcript::get_value_ids,
+};
+
+/// Builds a circuit which computes the salted SHA-256 hash of `len` bytes.
+///
+/// The inputs of the circuit are the salt and the data, and the output is the hash.
+pub fn build_plaintext_hash_circuit(len: usize) -> Arc<Circuit> {
+    let builder = CircuitBuilder::new();
+    let salt = builder.add_array_input::<u8, SALT_LEN>();
+    let data = builder.add_vec_input::<u8>(len);
+
+    let state = SHA256_INITIAL_STATE.map(|v| {
+        Tracer::new(
+            builder.state(),
+            builder.state().borrow_mut().get_constant(v),
+        )
+    });
+    let msg = salt.into_iter().chain(data).collect::<Vec<_>>();
+    let hash = sha256_trace(builder.state(), state, 0, &msg);
+    builder.add_output(hash);
+
+    Arc::new(builder.build().expect("circuit is valid"))
+}
+
+/// Returns the id of the salt of the plaintext hash with the provided index.
+pub fn salt_id(index: usize) -> String {
+    format!("plaintext_hash/{index}/salt")
+}
+
+/// Returns the references to the transcript data of a plaintext hash commitment and to the
+/// output of its hash circuit.
+///
+/// # Panics
+///
+/// Panics if the ranges of the commitment exceed the transcript.
+pub fn plaintext_hash_refs<T: Memory>(
+    vm: &mut T,
+    index: usize,
+    info: &CommitmentInfo,
+) -> Result<(ValueRef, ValueRef), MemoryError> {
+    let data = get_value_ids(info.ranges(), *info.direction())
+        .map(|id| {
+            vm.get_value(id.as_str())
+                .expect("Byte should be in VM memory")
+        })
+        .collect::<Vec<_>>();
+
+    let data = vm.array_from_values(data.as_slice())?;
+    let hash = vm.new_output::<[u8; 32]>(&format!("plaintext_hash/{index}"))?;
+
+    Ok((data, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use mpz_circuits::evaluate;
+    use tlsn_core::commitment::hash::hash_plaintext;
+
+    use super::*;
+
+    #[test]
+    fn test_plaintext_hash_circuit() {
+        let circ = build_plaintext_hash_circuit(70);
+
+        let salt = [42u8; SALT_LEN];
+        let data = [69u8; 70];
+
+        let hash = evaluate!(circ, fn(salt, data) -> [u8; 32]).unwrap();
+
+        assert_eq!(&hash, hash_plaintext(&salt, &data).as_bytes());
+    }
+}
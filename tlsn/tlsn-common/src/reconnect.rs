@@ -0,0 +1,33 @@
+//! Re-establishment of the connection between the prover and the verifier.
+//!
+//! A failed connection is not resumed: the MPC protocol keeps state on both sides which cannot be
+//! recovered once messages are lost. Instead, a connection which fails during the setup is
+//! replaced by a new one over which the setup is run again from the start, see
+//! `Prover::setup_with_retry`. A connection which fails after the setup fails the session.
+//!
+//! A verifier may bind each connection to a single session, as the notary server does, in which
+//! case the new connection belongs to a new session whose id is reported by
+//! [`Reconnect::session_id`].
+
+use std::io;
+
+use futures::{future::BoxFuture, AsyncRead, AsyncWrite};
+
+/// Establishes a new connection to the peer.
+pub trait Reconnect: Send {
+    /// The connection.
+    type Io: AsyncRead + AsyncWrite + Send + Unpin;
+
+    /// Establishes a new connection to the peer.
+    ///
+    /// Implementations may wait before reconnecting, e.g. to back off.
+    fn reconnect(&mut self) -> BoxFuture<'_, io::Result<Self::Io>>;
+
+    /// Returns the id of the session of the last connection established, if each connection
+    /// belongs to a new session.
+    ///
+    /// Returns `None` if the new connections continue the session they replace.
+    fn session_id(&self) -> Option<&str> {
+        None
+    }
+}
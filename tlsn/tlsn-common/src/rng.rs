@@ -0,0 +1,55 @@
+//! Seeding of the randomness of a session.
+//!
+//! The prover and the verifier can be configured with a seed, behind their `seeded-rng` feature,
+//! so that protocol runs are reproducible in tests. Each component of the session derives its
+//! seed from a distinct stream of the seeded RNG. Without a seed, the seeds are drawn from the OS
+//! RNG.
+
+use rand::{rngs::OsRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// Stream of the RNG used to seed the garbled circuit VM and its encoder.
+pub const VM_RNG_STREAM: u64 = 0;
+/// Stream of the RNG used to seed MPC-TLS.
+pub const MPC_TLS_RNG_STREAM: u64 = 1;
+
+/// Derives the seed of a component of the session from the stream of the seeded RNG, or from the
+/// OS RNG if no seed is provided.
+///
+/// # Arguments
+///
+/// * `seed` - The seed of the session, if any.
+/// * `stream` - The stream of the RNG used by the component.
+pub fn derive_seed(seed: Option<&[u8; 32]>, stream: u64) -> [u8; 32] {
+    match seed {
+        Some(seed) => {
+            let mut rng = ChaCha20Rng::from_seed(*seed);
+            rng.set_stream(stream);
+            rng.gen()
+        }
+        None => OsRng.gen(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_seed() {
+        let seed = [1u8; 32];
+
+        assert_eq!(
+            derive_seed(Some(&seed), VM_RNG_STREAM),
+            derive_seed(Some(&seed), VM_RNG_STREAM)
+        );
+        assert_ne!(
+            derive_seed(Some(&seed), VM_RNG_STREAM),
+            derive_seed(Some(&seed), MPC_TLS_RNG_STREAM)
+        );
+        assert_ne!(
+            derive_seed(None, VM_RNG_STREAM),
+            derive_seed(None, VM_RNG_STREAM)
+        );
+    }
+}
@@ -0,0 +1,495 @@
+//! Scheduling of the frames the multiplexer sends to the peer.
+//!
+//! The multiplexer writes the frames of all streams to the connection in the order they are
+//! produced. On a constrained uplink, e.g. a prover on a mobile connection, the large frames of
+//! garbled tables and OT extensions then delay the small, latency-critical messages of the TLS
+//! handshake which are exchanged at the same time.
+//!
+//! The [`Scheduled`] connection sits between the multiplexer and the socket, and sends the frames
+//! of high priority streams before the frames of bulk streams. The priority of a stream is
+//! configured by its id in [`SchedulerConfig`], and applies once the stream is
+//! [registered](StreamPriorities::register) after it is opened. Window updates are sent first
+//! whatever the priority of their stream, so a bulk transfer never holds back the credit the peer
+//! needs to send. The frames of a single stream are otherwise always sent in order.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::{abort::ABORT_STREAM_ID, Io};
+
+/// Length of the header of a yamux frame.
+const HEADER_LEN: usize = 12;
+
+/// Type of yamux data frames, which are the only frames with a body.
+const TYPE_DATA: u8 = 0;
+/// Type of yamux window update frames.
+const TYPE_WINDOW_UPDATE: u8 = 1;
+/// Type of yamux go away frames, which terminate the session.
+const TYPE_GO_AWAY: u8 = 3;
+
+/// Flags of yamux frames which close or reset their stream.
+const FLAGS_FIN_RST: u16 = 0x4 | 0x8;
+
+/// Default for the maximum number of bytes buffered by the scheduler.
+pub const DEFAULT_MAX_BUFFERED: usize = 1024 * 1024;
+
+/// The priority of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamPriority {
+    /// The stream carries latency-critical messages, which are sent first.
+    High,
+    /// The stream carries a bulk transfer, which is sent when no latency-critical message is
+    /// waiting.
+    Bulk,
+}
+
+/// Configuration of the frame scheduler.
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct SchedulerConfig {
+    /// Priorities of the streams, by stream id.
+    ///
+    /// By default the MPC-TLS and abort streams, which carry the latency-critical messages of the
+    /// TLS connection and of the session, have a high priority.
+    #[builder(setter(custom), default = "default_priorities()")]
+    priorities: HashMap<Vec<u8>, StreamPriority>,
+    /// Priority of the streams without a configured priority, e.g. the streams of the MPC
+    /// executor.
+    #[builder(default = "StreamPriority::Bulk")]
+    default_priority: StreamPriority,
+    /// Maximum number of bytes buffered by the scheduler, after which the multiplexer waits for
+    /// the connection.
+    #[builder(default = "DEFAULT_MAX_BUFFERED")]
+    max_buffered: usize,
+}
+
+impl SchedulerConfigBuilder {
+    /// Sets the priority of the stream with the provided id.
+    pub fn priority(&mut self, id: impl Into<Vec<u8>>, priority: StreamPriority) -> &mut Self {
+        self.priorities
+            .get_or_insert_with(default_priorities)
+            .insert(id.into(), priority);
+        self
+    }
+}
+
+impl SchedulerConfig {
+    /// Creates a new builder for the scheduler configuration.
+    pub fn builder() -> SchedulerConfigBuilder {
+        SchedulerConfigBuilder::default()
+    }
+
+    /// Returns the priority of the stream with the provided id.
+    pub fn priority(&self, id: &[u8]) -> StreamPriority {
+        self.priorities
+            .get(id)
+            .copied()
+            .unwrap_or(self.default_priority)
+    }
+
+    /// Returns the maximum number of bytes buffered by the scheduler.
+    pub fn max_buffered(&self) -> usize {
+        self.max_buffered
+    }
+}
+
+fn default_priorities() -> HashMap<Vec<u8>, StreamPriority> {
+    HashMap::from([
+        (b"mpc_tls".to_vec(), StreamPriority::High),
+        (ABORT_STREAM_ID.to_vec(), StreamPriority::High),
+    ])
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self::builder().build().unwrap()
+    }
+}
+
+/// The priorities of the streams of a [`Scheduled`] connection.
+///
+/// The multiplexer assigns its own ids to the streams, so each stream is registered with the
+/// connection once it is opened for its configured priority to apply. Until then, its frames have
+/// the default priority.
+#[derive(Debug, Clone)]
+pub struct StreamPriorities {
+    config: SchedulerConfig,
+    streams: Arc<Mutex<HashMap<u32, StreamPriority>>>,
+}
+
+impl StreamPriorities {
+    fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config,
+            streams: Default::default(),
+        }
+    }
+
+    /// Registers a stream, so that its frames are scheduled with the priority configured for its
+    /// id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id the stream was opened with.
+    /// * `io` - The stream.
+    pub fn register(&self, id: &[u8], io: &Io) {
+        self.set(io.get_ref().id().val(), self.config.priority(id));
+    }
+
+    fn set(&self, stream_id: u32, priority: StreamPriority) {
+        self.streams.lock().unwrap().insert(stream_id, priority);
+    }
+
+    fn get(&self, stream_id: u32) -> StreamPriority {
+        self.streams
+            .lock()
+            .unwrap()
+            .get(&stream_id)
+            .copied()
+            .unwrap_or(self.config.default_priority)
+    }
+}
+
+/// A frame waiting to be sent.
+struct Frame {
+    stream_id: u32,
+    bytes: Vec<u8>,
+}
+
+/// A connection which schedules the frames written by the multiplexer by the priority of their
+/// stream.
+pub struct Scheduled<T> {
+    io: T,
+    priorities: StreamPriorities,
+    /// The bytes of the frame being written by the multiplexer.
+    partial: Vec<u8>,
+    high: VecDeque<Frame>,
+    bulk: VecDeque<Frame>,
+    /// The number of frames of each stream waiting in the bulk queue.
+    bulk_streams: HashMap<u32, usize>,
+    /// The frame being sent and the number of its bytes already sent.
+    sending: Option<(Frame, usize)>,
+    buffered: usize,
+}
+
+impl<T> Scheduled<T> {
+    /// Creates a new scheduled connection.
+    pub fn new(io: T, config: SchedulerConfig) -> Self {
+        Self {
+            io,
+            priorities: StreamPriorities::new(config),
+            partial: Vec::new(),
+            high: VecDeque::new(),
+            bulk: VecDeque::new(),
+            bulk_streams: HashMap::new(),
+            sending: None,
+            buffered: 0,
+        }
+    }
+
+    /// Returns the priorities of the streams of the connection.
+    pub fn priorities(&self) -> StreamPriorities {
+        self.priorities.clone()
+    }
+
+    /// Returns the priority of a stream.
+    pub fn priority(&self, stream_id: u32) -> StreamPriority {
+        self.priorities.get(stream_id)
+    }
+
+    /// Returns the number of bytes waiting to be sent.
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /// Splits the bytes written by the multiplexer into frames and queues them.
+    fn push(&mut self, mut buf: &[u8]) {
+        while !buf.is_empty() {
+            let take = match frame_len(&self.partial) {
+                Some(len) => len - self.partial.len(),
+                None => HEADER_LEN - self.partial.len(),
+            }
+            .min(buf.len());
+
+            self.partial.extend_from_slice(&buf[..take]);
+            buf = &buf[take..];
+
+            if frame_len(&self.partial) == Some(self.partial.len()) {
+                let bytes = std::mem::take(&mut self.partial);
+                self.enqueue(bytes);
+            }
+        }
+    }
+
+    fn enqueue(&mut self, bytes: Vec<u8>) {
+        let ty = bytes[1];
+        let flags = u16::from_be_bytes([bytes[2], bytes[3]]);
+        let stream_id = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+
+        // A window update only grants credit, so it can overtake the frames of its stream unless
+        // it also closes the stream.
+        let is_credit = ty == TYPE_WINDOW_UPDATE && flags & FLAGS_FIN_RST == 0;
+        // The frames of a stream with bulk frames waiting must follow them to preserve the order
+        // of the stream, and terminating the session must not overtake any frame.
+        let is_bulk = !is_credit
+            && (self.priorities.get(stream_id) == StreamPriority::Bulk
+                || ty == TYPE_GO_AWAY
+                || self.bulk_streams.contains_key(&stream_id));
+
+        self.buffered += bytes.len();
+        let frame = Frame { stream_id, bytes };
+        if is_bulk {
+            *self.bulk_streams.entry(stream_id).or_default() += 1;
+            self.bulk.push_back(frame);
+        } else {
+            self.high.push_back(frame);
+        }
+    }
+
+    fn next_frame(&mut self) -> Option<Frame> {
+        if let Some(frame) = self.high.pop_front() {
+            return Some(frame);
+        }
+
+        let frame = self.bulk.pop_front()?;
+        if let Some(count) = self.bulk_streams.get_mut(&frame.stream_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.bulk_streams.remove(&frame.stream_id);
+            }
+        }
+
+        Some(frame)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Scheduled<T> {
+    /// Sends the queued frames until the connection is not ready or no frame is left.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if self.sending.is_none() {
+                match self.next_frame() {
+                    Some(frame) => self.sending = Some((frame, 0)),
+                    None => return Poll::Ready(Ok(())),
+                }
+            }
+
+            let (frame, sent) = self.sending.as_mut().expect("frame is being sent");
+            let n = futures::ready!(Pin::new(&mut self.io).poll_write(cx, &frame.bytes[*sent..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+
+            *sent += n;
+            self.buffered -= n;
+            if *sent == frame.bytes.len() {
+                self.sending = None;
+            }
+        }
+    }
+}
+
+/// Returns the length of a frame, if its header is complete.
+fn frame_len(partial: &[u8]) -> Option<usize> {
+    if partial.len() < HEADER_LEN {
+        return None;
+    }
+
+    if partial[1] == TYPE_DATA {
+        let body_len = u32::from_be_bytes(partial[8..12].try_into().unwrap()) as usize;
+        Some(HEADER_LEN + body_len)
+    } else {
+        Some(HEADER_LEN)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Scheduled<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Scheduled<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.buffered >= this.priorities.config.max_buffered {
+            futures::ready!(this.poll_send(cx))?;
+        }
+
+        this.push(buf);
+
+        // Start sending the frames, the rest is sent on the next write or flush.
+        if let Poll::Ready(Err(e)) = this.poll_send(cx) {
+            return Poll::Ready(Err(e));
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures::ready!(this.poll_send(cx))?;
+        Pin::new(&mut this.io).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures::ready!(this.poll_send(cx))?;
+        Pin::new(&mut this.io).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{io::Cursor, AsyncWriteExt};
+
+    use super::*;
+
+    fn frame(ty: u8, flags: u16, stream_id: u32, body: &[u8]) -> Vec<u8> {
+        let len = if ty == TYPE_DATA {
+            body.len() as u32
+        } else {
+            0
+        };
+        let mut bytes = vec![0, ty];
+        bytes.extend_from_slice(&flags.to_be_bytes());
+        bytes.extend_from_slice(&stream_id.to_be_bytes());
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// A connection where stream 3 has a high priority.
+    fn scheduled() -> Scheduled<Cursor<Vec<u8>>> {
+        let io = Scheduled::new(Cursor::new(Vec::new()), SchedulerConfig::default());
+        io.priorities.set(3, StreamPriority::High);
+        io
+    }
+
+    #[test]
+    fn test_configured_priorities() {
+        let config = SchedulerConfig::builder()
+            .priority(b"notarize".to_vec(), StreamPriority::High)
+            .default_priority(StreamPriority::High)
+            .priority(b"mpc_tls".to_vec(), StreamPriority::Bulk)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.priority(b"notarize"), StreamPriority::High);
+        assert_eq!(config.priority(b"mpc_tls"), StreamPriority::Bulk);
+        assert_eq!(config.priority(ABORT_STREAM_ID), StreamPriority::High);
+        assert_eq!(config.priority(b"other"), StreamPriority::High);
+
+        let config = SchedulerConfig::default();
+        assert_eq!(config.priority(b"mpc_tls"), StreamPriority::High);
+        assert_eq!(config.priority(b"other"), StreamPriority::Bulk);
+    }
+
+    #[test]
+    fn test_high_priority_first() {
+        // Small frames of a bulk stream and large frames of a high priority stream.
+        let bulk = frame(TYPE_DATA, 0, 1, &[1; 8]);
+        let high = frame(TYPE_DATA, 0, 3, &[3; 64]);
+        let bulk_tail = frame(TYPE_DATA, 0, 1, &[1; 8]);
+
+        let mut io = scheduled();
+        io.push(&[bulk.clone(), high.clone(), bulk_tail.clone()].concat());
+
+        assert_eq!(io.priority(1), StreamPriority::Bulk);
+        assert_eq!(io.priority(3), StreamPriority::High);
+
+        futures::executor::block_on(io.flush()).unwrap();
+
+        assert_eq!(io.buffered(), 0);
+        assert_eq!(io.io.into_inner(), [high, bulk, bulk_tail].concat());
+    }
+
+    #[test]
+    fn test_window_updates_first() {
+        let bulk = frame(TYPE_DATA, 0, 1, &[1; 64]);
+        let window_update = frame(TYPE_WINDOW_UPDATE, 0, 1, &[]);
+        let fin = frame(TYPE_WINDOW_UPDATE, 0x4, 1, &[]);
+        let high = frame(TYPE_DATA, 0, 3, &[3; 8]);
+
+        let mut io = scheduled();
+        io.push(
+            &[
+                bulk.clone(),
+                window_update.clone(),
+                fin.clone(),
+                high.clone(),
+            ]
+            .concat(),
+        );
+        futures::executor::block_on(io.flush()).unwrap();
+
+        // The window update of the bulk stream does not wait for its data, unlike closing it.
+        assert_eq!(
+            io.io.into_inner(),
+            [window_update, high, bulk, fin].concat()
+        );
+    }
+
+    #[test]
+    fn test_registered_stream_follows_queued_frames() {
+        let queued = frame(TYPE_DATA, 0, 5, &[5; 8]);
+        let next = frame(TYPE_DATA, 0, 5, &[5; 8]);
+
+        let mut io = scheduled();
+        io.push(&queued);
+        io.priorities.set(5, StreamPriority::High);
+        io.push(&next);
+        futures::executor::block_on(io.flush()).unwrap();
+
+        assert_eq!(io.io.into_inner(), [queued, next].concat());
+    }
+
+    #[test]
+    fn test_partial_writes() {
+        let frames = [
+            frame(TYPE_DATA, 0, 1, &[1; 64]),
+            frame(2, 0, 0, &[]),
+            frame(TYPE_DATA, 0, 3, &[3; 4]),
+        ];
+        let bytes = frames.concat();
+
+        let mut io = scheduled();
+        for chunk in bytes.chunks(5) {
+            io.push(chunk);
+        }
+        futures::executor::block_on(io.flush()).unwrap();
+
+        assert_eq!(
+            io.io.into_inner(),
+            [frames[2].clone(), frames[0].clone(), frames[1].clone()].concat()
+        );
+    }
+
+    #[test]
+    fn test_go_away_last() {
+        let bulk = frame(TYPE_DATA, 0, 1, &[1; 64]);
+        let go_away = frame(TYPE_GO_AWAY, 0, 0, &[]);
+
+        let mut io = scheduled();
+        io.priorities.set(0, StreamPriority::High);
+        io.push(&[bulk.clone(), go_away.clone()].concat());
+        futures::executor::block_on(io.flush()).unwrap();
+
+        assert_eq!(io.io.into_inner(), [bulk, go_away].concat());
+    }
+}
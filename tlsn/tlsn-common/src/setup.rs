@@ -0,0 +1,445 @@
+//! Negotiation of the parameters of a session between the prover and the verifier.
+//!
+//! When the connection is set up, each party sends a single setup message which announces the
+//! version of the setup protocol it speaks and the parameters it allows. Both parties then derive
+//! the same parameters of the session from the two messages, see [`negotiate`].
+//!
+//! The message starts with [`SETUP_MAGIC`] and [`SETUP_VERSION`], so a peer speaking another
+//! version of the protocol is rejected with an explicit error instead of misinterpreting the
+//! message.
+
+use std::io;
+
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compress::MaybeCompressed, concurrency::MAX_MPC_CONCURRENCY, ot_pool::DEFAULT_RECORD_BUDGET,
+};
+
+/// Prefix of the setup message.
+pub const SETUP_MAGIC: [u8; 4] = *b"TLSN";
+/// Version of the setup protocol.
+pub const SETUP_VERSION: u8 = 1;
+
+/// Maximum length of the parameters of a setup message, in bytes.
+const MAX_PARAMS_LEN: usize = 1024;
+
+/// The parameters a party allows for a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetupParams {
+    /// Whether the party supports compression of the connection.
+    pub compression: bool,
+    /// The MPC concurrency the party allows, between 1 and [`MAX_MPC_CONCURRENCY`].
+    pub concurrency: usize,
+    /// Whether the party exchanges a session opened receipt after the MPC setup, i.e. whether the
+    /// prover accepts one or the verifier issues one.
+    pub session_receipts: bool,
+    /// The maximum number of bytes the party allows to be sent to the server.
+    pub max_sent_data: usize,
+    /// The maximum number of bytes the party allows to be received from the server.
+    pub max_recv_data: usize,
+    /// The maximum number of TLS records the party allows, if limited. A party which does not
+    /// limit the number of records allows [`DEFAULT_RECORD_BUDGET`] records if its peer does.
+    pub max_records: Option<usize>,
+}
+
+/// The parameters of a session, agreed upon by both parties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionParams {
+    /// Whether the connection is compressed, which is only the case if both parties support it.
+    pub compression: bool,
+    /// The MPC concurrency, which is the lowest one allowed by the parties.
+    pub concurrency: usize,
+    /// Whether a session opened receipt is exchanged, which is only the case if both parties
+    /// support it.
+    pub session_receipts: bool,
+    /// The maximum number of bytes sent to the server, which is the lowest one allowed by the
+    /// parties.
+    pub max_sent_data: usize,
+    /// The maximum number of bytes received from the server, which is the lowest one allowed by
+    /// the parties.
+    pub max_recv_data: usize,
+    /// The maximum number of TLS records, which is the lowest one allowed by the parties, or
+    /// `None` if neither party limits the number of records.
+    ///
+    /// The pools of oblivious transfers are sized from this limit, so a party which does not
+    /// limit the number of records allows [`DEFAULT_RECORD_BUDGET`] records. Otherwise its peer
+    /// could make it preprocess an arbitrary number of OTs by announcing a high limit.
+    pub max_records: Option<usize>,
+}
+
+impl SessionParams {
+    fn agree(local: &SetupParams, peer: &SetupParams) -> io::Result<Self> {
+        if peer.concurrency == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "peer announced a concurrency of 0",
+            ));
+        }
+
+        if peer.max_records == Some(0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "peer announced a maximum of 0 records",
+            ));
+        }
+
+        Ok(Self {
+            compression: local.compression && peer.compression,
+            concurrency: local.concurrency.min(peer.concurrency),
+            session_receipts: local.session_receipts && peer.session_receipts,
+            max_sent_data: local.max_sent_data.min(peer.max_sent_data),
+            max_recv_data: local.max_recv_data.min(peer.max_recv_data),
+            max_records: match (local.max_records, peer.max_records) {
+                (None, None) => None,
+                (a, b) => Some(
+                    a.unwrap_or(DEFAULT_RECORD_BUDGET)
+                        .min(b.unwrap_or(DEFAULT_RECORD_BUDGET)),
+                ),
+            },
+        })
+    }
+}
+
+/// Negotiates the parameters of a session with the peer.
+///
+/// Returns the agreed parameters, and the connection, compressed if both parties support it.
+///
+/// # Arguments
+///
+/// * `io` - The connection to the peer.
+/// * `params` - The parameters allowed by this party.
+pub async fn negotiate<T: AsyncRead + AsyncWrite + Unpin>(
+    mut io: T,
+    params: SetupParams,
+) -> io::Result<(MaybeCompressed<T>, SessionParams)> {
+    if !(1..=MAX_MPC_CONCURRENCY).contains(&params.concurrency) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "concurrency must be between 1 and {MAX_MPC_CONCURRENCY}, got {}",
+                params.concurrency
+            ),
+        ));
+    }
+
+    if params.max_records == Some(0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "max records must be at least 1",
+        ));
+    }
+
+    let encoded = bincode::serialize(&params)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+    let mut msg = Vec::with_capacity(SETUP_MAGIC.len() + 3 + encoded.len());
+    msg.extend_from_slice(&SETUP_MAGIC);
+    msg.push(SETUP_VERSION);
+    msg.extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+    msg.extend_from_slice(&encoded);
+
+    io.write_all(&msg).await?;
+    io.flush().await?;
+
+    let peer = read_params(&mut io).await?;
+    let session = SessionParams::agree(&params, &peer)?;
+
+    Ok((MaybeCompressed::new(io, session.compression), session))
+}
+
+/// Reads the setup message of the peer.
+async fn read_params<T: AsyncRead + Unpin>(io: &mut T) -> io::Result<SetupParams> {
+    let mut prefix = [0u8; SETUP_MAGIC.len() + 3];
+    io.read_exact(&mut prefix).await?;
+
+    if prefix[..SETUP_MAGIC.len()] != SETUP_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "peer did not send a setup message, it may use an incompatible version",
+        ));
+    }
+
+    let version = prefix[SETUP_MAGIC.len()];
+    if version != SETUP_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("peer uses version {version} of the setup protocol, expected {SETUP_VERSION}"),
+        ));
+    }
+
+    let len =
+        u16::from_be_bytes([prefix[SETUP_MAGIC.len() + 1], prefix[SETUP_MAGIC.len() + 2]]) as usize;
+    if len > MAX_PARAMS_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("setup message of the peer is too long: {len} bytes"),
+        ));
+    }
+
+    let mut encoded = vec![0u8; len];
+    io.read_exact(&mut encoded).await?;
+
+    bincode::deserialize(&encoded).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ot_pool::OtPoolSize, Role};
+    use rstest::*;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    fn params(compression: bool, concurrency: usize) -> SetupParams {
+        SetupParams {
+            compression,
+            concurrency,
+            session_receipts: false,
+            max_sent_data: 4096,
+            max_recv_data: 16384,
+            max_records: None,
+        }
+    }
+
+    #[rstest]
+    #[case::both(true, true, true)]
+    #[case::local_only(true, false, false)]
+    #[case::peer_only(false, true, false)]
+    #[case::neither(false, false, false)]
+    #[tokio::test]
+    async fn test_negotiate_compression(
+        #[case] a_enabled: bool,
+        #[case] b_enabled: bool,
+        #[case] expect_compressed: bool,
+    ) {
+        let (a, b) = tokio::io::duplex(1 << 16);
+
+        let ((mut a, a_params), (mut b, b_params)) = futures::try_join!(
+            negotiate(a.compat(), params(a_enabled, 1)),
+            negotiate(b.compat(), params(b_enabled, 1))
+        )
+        .unwrap();
+
+        assert_eq!(a_params, b_params);
+        assert_eq!(a_params.compression, expect_compressed);
+        assert_eq!(a.is_compressed(), expect_compressed);
+        assert_eq!(b.is_compressed(), expect_compressed);
+
+        let msg = vec![42u8; 4096];
+
+        let write = async {
+            a.write_all(&msg).await.unwrap();
+            a.flush().await.unwrap();
+        };
+        let read = async {
+            let mut buf = vec![0u8; msg.len()];
+            b.read_exact(&mut buf).await.unwrap();
+            buf
+        };
+
+        let (_, received) = futures::join!(write, read);
+
+        assert_eq!(received, msg);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_concurrency() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+
+        let ((_, a_params), (_, b_params)) = futures::try_join!(
+            negotiate(a.compat(), params(false, 8)),
+            negotiate(b.compat(), params(false, 2))
+        )
+        .unwrap();
+
+        assert_eq!(a_params.concurrency, 2);
+        assert_eq!(b_params.concurrency, 2);
+
+        let io = tokio::io::duplex(64).0.compat();
+        let err = negotiate(io, params(false, 0)).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[rstest]
+    #[case::both(true, true, true)]
+    #[case::prover_only(true, false, false)]
+    #[case::verifier_only(false, true, false)]
+    #[tokio::test]
+    async fn test_negotiate_session_receipts(
+        #[case] a_enabled: bool,
+        #[case] b_enabled: bool,
+        #[case] expected: bool,
+    ) {
+        let (a, b) = tokio::io::duplex(1 << 16);
+
+        let ((_, a_params), (_, b_params)) = futures::try_join!(
+            negotiate(
+                a.compat(),
+                SetupParams {
+                    session_receipts: a_enabled,
+                    ..params(false, 1)
+                }
+            ),
+            negotiate(
+                b.compat(),
+                SetupParams {
+                    session_receipts: b_enabled,
+                    ..params(false, 1)
+                }
+            )
+        )
+        .unwrap();
+
+        assert_eq!(a_params.session_receipts, expected);
+        assert_eq!(b_params.session_receipts, expected);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_limits() {
+        for (a, b, expected) in [
+            (Some(100), Some(10), Some(10)),
+            (Some(10), None, Some(10)),
+            (Some(100), None, Some(DEFAULT_RECORD_BUDGET)),
+            (None, None, None),
+        ] {
+            let (io_a, io_b) = tokio::io::duplex(1 << 16);
+
+            let ((_, a_params), (_, b_params)) = futures::try_join!(
+                negotiate(
+                    io_a.compat(),
+                    SetupParams {
+                        max_sent_data: 1024,
+                        max_records: a,
+                        ..params(false, 1)
+                    }
+                ),
+                negotiate(
+                    io_b.compat(),
+                    SetupParams {
+                        max_recv_data: 2048,
+                        max_records: b,
+                        ..params(false, 1)
+                    }
+                )
+            )
+            .unwrap();
+
+            assert_eq!(a_params, b_params);
+            assert_eq!(a_params.max_sent_data, 1024);
+            assert_eq!(a_params.max_recv_data, 2048);
+            assert_eq!(a_params.max_records, expected);
+        }
+
+        let io = tokio::io::duplex(64).0.compat();
+        let err = negotiate(
+            io,
+            SetupParams {
+                max_records: Some(0),
+                ..params(false, 1)
+            },
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_hostile_max_records() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let (a, mut b) = (a.compat(), b.compat());
+
+        // A peer which announces a limit of records far beyond any session.
+        let peer = async {
+            let encoded = bincode::serialize(&SetupParams {
+                max_records: Some(usize::MAX),
+                ..params(false, 1)
+            })
+            .unwrap();
+            let mut msg = SETUP_MAGIC.to_vec();
+            msg.push(SETUP_VERSION);
+            msg.extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+            msg.extend_from_slice(&encoded);
+            b.write_all(&msg).await.unwrap();
+        };
+
+        let (result, _) = futures::join!(negotiate(a, params(false, 1)), peer);
+        let (_, session) = result.unwrap();
+
+        assert_eq!(session.max_records, Some(DEFAULT_RECORD_BUDGET));
+        assert_eq!(
+            OtPoolSize::for_session(Role::Verifier, &session).unwrap(),
+            OtPoolSize::new(Role::Verifier, 4096, 16384, None).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejects_zero_concurrency_peer() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let (a, mut b) = (a.compat(), b.compat());
+
+        let peer = async {
+            let encoded = bincode::serialize(&params(false, 0)).unwrap();
+            let mut msg = SETUP_MAGIC.to_vec();
+            msg.push(SETUP_VERSION);
+            msg.extend_from_slice(&(encoded.len() as u16).to_be_bytes());
+            msg.extend_from_slice(&encoded);
+            b.write_all(&msg).await.unwrap();
+        };
+
+        let (result, _) = futures::join!(negotiate(a, params(false, 1)), peer);
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejects_other_version() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let (a, mut b) = (a.compat(), b.compat());
+
+        let peer = async {
+            let mut msg = SETUP_MAGIC.to_vec();
+            msg.extend_from_slice(&[SETUP_VERSION + 1, 0, 0]);
+            b.write_all(&msg).await.unwrap();
+        };
+
+        let (result, _) = futures::join!(negotiate(a, params(false, 1)), peer);
+
+        let err = result.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("version"));
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejects_unversioned_peer() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let (a, mut b) = (a.compat(), b.compat());
+
+        // A peer which only announces a raw flag, without the setup message.
+        let peer = async {
+            b.write_all(&[1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+        };
+
+        let (result, _) = futures::join!(negotiate(a, params(false, 1)), peer);
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejects_oversized_params() {
+        let (a, b) = tokio::io::duplex(1 << 16);
+        let (a, mut b) = (a.compat(), b.compat());
+
+        let peer = async {
+            let mut msg = SETUP_MAGIC.to_vec();
+            msg.push(SETUP_VERSION);
+            msg.extend_from_slice(&u16::MAX.to_be_bytes());
+            b.write_all(&msg).await.unwrap();
+        };
+
+        let (result, _) = futures::join!(negotiate(a, params(false, 1)), peer);
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,149 @@
+//! Counters of the cost of the MPC protocol.
+//!
+//! Both the prover and the verifier count the oblivious transfers they run, the circuits they
+//! garble or evaluate and the bytes they exchange in each phase of a session. The counters are
+//! updated as the protocol runs, never derived from the configuration or the transcript. A
+//! [`ProtocolStats`] snapshot lets benchmarking tools and capacity planners measure the cost of a
+//! session as a function of the size of its transcript.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use mpz_circuits::Circuit;
+use tls_mpc::CircuitCounter;
+use tlsn_core::msg::SessionPhase;
+
+/// Bytes exchanged with the peer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Traffic {
+    /// Number of bytes sent to the peer.
+    pub sent: u64,
+    /// Number of bytes received from the peer.
+    pub recv: u64,
+}
+
+/// A snapshot of the cost of the MPC protocol of a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtocolStats {
+    /// Number of oblivious transfers extended as the sender.
+    ///
+    /// The OT extension only runs during setup, see [`OtPoolSize`](crate::ot_pool::OtPoolSize),
+    /// so this includes the OTs which the session did not use.
+    pub ots_sent: u64,
+    /// Number of oblivious transfers extended as the receiver.
+    pub ots_received: u64,
+    /// Number of circuits garbled or evaluated by the record layer and to prove the plaintext
+    /// hash commitments.
+    ///
+    /// The circuits of the handshake are not counted, their number is the same for every session.
+    pub circuits: u64,
+    /// Number of AND gates of the circuits, which dominate the cost of garbling.
+    pub and_gates: u64,
+    /// Number of XOR gates of the circuits, which are free to garble.
+    pub xor_gates: u64,
+    /// Bytes exchanged with the peer in each phase which has completed, in the order they
+    /// completed.
+    pub traffic: Vec<(SessionPhase, Traffic)>,
+}
+
+impl ProtocolStats {
+    /// Returns the bytes exchanged in a phase, if it has completed.
+    pub fn phase_traffic(&self, phase: SessionPhase) -> Option<Traffic> {
+        self.traffic
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|(_, traffic)| *traffic)
+    }
+
+    /// Returns the bytes exchanged in all the phases which have completed.
+    pub fn total_traffic(&self) -> Traffic {
+        self.traffic
+            .iter()
+            .fold(Traffic::default(), |total, (_, traffic)| Traffic {
+                sent: total.sent + traffic.sent,
+                recv: total.recv + traffic.recv,
+            })
+    }
+}
+
+/// A handle to the [`ProtocolStats`] of a session.
+///
+/// The handle remains valid after the prover or verifier is consumed, e.g. to inspect the
+/// counters once the session is finalized.
+#[derive(Debug, Clone, Default)]
+pub struct ProtocolStatsHandle(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    ots_sent: AtomicU64,
+    ots_received: AtomicU64,
+    circuits: CircuitCounter,
+    traffic: Mutex<Vec<(SessionPhase, Traffic)>>,
+}
+
+impl ProtocolStatsHandle {
+    /// Returns a snapshot of the counters.
+    pub fn get(&self) -> ProtocolStats {
+        ProtocolStats {
+            ots_sent: self.0.ots_sent.load(Ordering::Relaxed),
+            ots_received: self.0.ots_received.load(Ordering::Relaxed),
+            circuits: self.0.circuits.circuits(),
+            and_gates: self.0.circuits.and_gates(),
+            xor_gates: self.0.circuits.xor_gates(),
+            traffic: self.0.traffic.lock().unwrap().clone(),
+        }
+    }
+
+    /// Adds oblivious transfers which have been extended.
+    pub fn add_ots(&self, sent: usize, received: usize) {
+        self.0.ots_sent.fetch_add(sent as u64, Ordering::Relaxed);
+        self.0
+            .ots_received
+            .fetch_add(received as u64, Ordering::Relaxed);
+    }
+
+    /// Adds an execution of a circuit.
+    pub fn add_circuit(&self, circuit: &Circuit) {
+        self.0.circuits.add(circuit);
+    }
+
+    /// Returns the counter of the circuits, which is passed to the record layer to count the
+    /// circuits it executes.
+    pub fn circuit_counter(&self) -> CircuitCounter {
+        self.0.circuits.clone()
+    }
+
+    /// Adds the bytes exchanged in a phase which has completed.
+    pub fn add_traffic(&self, phase: SessionPhase, traffic: Traffic) {
+        self.0.traffic.lock().unwrap().push((phase, traffic));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpz_circuits::circuits::AES128;
+
+    #[test]
+    fn test_protocol_stats() {
+        let handle = ProtocolStatsHandle::default();
+        handle.add_ots(10, 20);
+        handle.add_circuit(&AES128);
+        handle.circuit_counter().add(&AES128);
+        handle.add_traffic(SessionPhase::Setup, Traffic { sent: 1, recv: 2 });
+        handle.add_traffic(SessionPhase::TlsSession, Traffic { sent: 3, recv: 4 });
+
+        let stats = handle.get();
+        assert_eq!((stats.ots_sent, stats.ots_received), (10, 20));
+        assert_eq!(stats.circuits, 2);
+        assert_eq!(stats.and_gates, 2 * AES128.and_count() as u64);
+        assert_eq!(
+            stats.phase_traffic(SessionPhase::TlsSession),
+            Some(Traffic { sent: 3, recv: 4 })
+        );
+        assert_eq!(stats.phase_traffic(SessionPhase::Finalize), None);
+        assert_eq!(stats.total_traffic(), Traffic { sent: 4, recv: 6 });
+    }
+}
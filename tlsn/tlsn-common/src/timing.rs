@@ -0,0 +1,49 @@
+//! Timings of the phases of a session.
+//!
+//! Both the prover and the verifier record the duration of each phase as it completes, so
+//! performance regressions can be pinpointed by comparing the [`SetupTimings`] of sessions.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Durations of the phases of a session.
+///
+/// A phase is `None` if it has not completed yet, or is not measured separately.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SetupTimings {
+    /// Base oblivious transfers, part of [`mpc_setup`](Self::mpc_setup).
+    pub base_ot: Option<Duration>,
+    /// Extension of the pool of oblivious transfers, part of [`mpc_setup`](Self::mpc_setup).
+    pub ot_extension: Option<Duration>,
+    /// MPC setup prior to connecting to the server, including the oblivious transfers and the
+    /// preprocessing of the circuits.
+    pub mpc_setup: Option<Duration>,
+    /// 2PC of the TLS handshake: the key exchange, the derivation of the session keys and the
+    /// Finished messages.
+    pub handshake_2pc: Option<Duration>,
+    /// 2PC encryption and decryption of the records of the TLS connection.
+    pub record_encryption: Option<Duration>,
+    /// Finalization of the notarization or verification, after the connection is closed.
+    pub finalization: Option<Duration>,
+}
+
+/// A handle to the [`SetupTimings`] of a session.
+///
+/// The handle remains valid after the prover or verifier is consumed, e.g. to inspect the
+/// timings once the session is finalized.
+#[derive(Debug, Clone, Default)]
+pub struct TimingsHandle(Arc<Mutex<SetupTimings>>);
+
+impl TimingsHandle {
+    /// Returns a snapshot of the timings.
+    pub fn get(&self) -> SetupTimings {
+        *self.0.lock().unwrap()
+    }
+
+    /// Updates the timings.
+    pub fn update(&self, f: impl FnOnce(&mut SetupTimings)) {
+        f(&mut self.0.lock().unwrap())
+    }
+}
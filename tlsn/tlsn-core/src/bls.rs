@@ -0,0 +1,261 @@
+//! BLS12-381 signatures of session headers.
+//!
+//! BLS signatures of many session headers can be aggregated into a single signature, which is
+//! verified against all of the headers at once, e.g. to check a batch of attestations on-chain.
+//!
+//! Signatures are in G2 and public keys in G1 (the "minimal public key" variant), using the
+//! ciphersuite `BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_`. Aggregate signatures are only
+//! secure over distinct messages, so [`BlsSignature::verify_aggregate`] rejects a batch in which a
+//! session header appears more than once.
+//!
+//! The notary signs with BLS by passing a [`BlsSigningKey`] as the signer when finalizing the
+//! notarization, which the notary server does when it is built with the `bls` feature and
+//! configured with a BLS key.
+
+use std::collections::HashSet;
+
+use blst::{min_pk, BLST_ERROR};
+use p256::ecdsa::signature;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{SessionHeader, SignatureVerifyError};
+
+/// The domain separation tag of the ciphersuite.
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// A BLS12-381 signing key of a notary.
+pub struct BlsSigningKey(min_pk::SecretKey);
+
+opaque_debug::implement!(BlsSigningKey);
+
+impl BlsSigningKey {
+    /// Derives a signing key from at least 32 bytes of secret keying material.
+    pub fn from_ikm(ikm: &[u8]) -> Result<Self, SignatureVerifyError> {
+        min_pk::SecretKey::key_gen(ikm, &[])
+            .map(Self)
+            .map_err(|e| SignatureVerifyError(format!("invalid keying material: {e:?}")))
+    }
+
+    /// Returns the public key of this signing key.
+    pub fn public_key(&self) -> BlsPublicKey {
+        BlsPublicKey(self.0.sk_to_pk())
+    }
+}
+
+impl signature::Signer<BlsSignature> for BlsSigningKey {
+    fn try_sign(&self, msg: &[u8]) -> Result<BlsSignature, signature::Error> {
+        Ok(BlsSignature(self.0.sign(msg, DST, &[])))
+    }
+}
+
+/// A BLS12-381 public key of a notary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlsPublicKey(min_pk::PublicKey);
+
+impl BlsPublicKey {
+    /// Returns the compressed encoding of the public key.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.compress()
+    }
+
+    /// Decodes a public key from its compressed encoding, checking that it is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignatureVerifyError> {
+        min_pk::PublicKey::key_validate(bytes)
+            .map(Self)
+            .map_err(|e| SignatureVerifyError(format!("invalid BLS public key: {e:?}")))
+    }
+}
+
+/// A BLS12-381 signature, or an aggregate of signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlsSignature(min_pk::Signature);
+
+impl BlsSignature {
+    /// Returns the compressed encoding of the signature.
+    pub fn to_bytes(&self) -> [u8; 96] {
+        self.0.compress()
+    }
+
+    /// Decodes a signature from its compressed encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignatureVerifyError> {
+        min_pk::Signature::uncompress(bytes)
+            .map(Self)
+            .map_err(|e| SignatureVerifyError(format!("invalid BLS signature: {e:?}")))
+    }
+
+    /// Aggregates the provided signatures into a single signature.
+    pub fn aggregate(signatures: &[BlsSignature]) -> Result<Self, SignatureVerifyError> {
+        let signatures = signatures.iter().map(|sig| &sig.0).collect::<Vec<_>>();
+
+        min_pk::AggregateSignature::aggregate(&signatures, true)
+            .map(|aggregate| Self(aggregate.to_signature()))
+            .map_err(|e| SignatureVerifyError(format!("failed to aggregate signatures: {e:?}")))
+    }
+
+    /// Verifies the signature of a message.
+    pub(crate) fn verify(
+        &self,
+        msg: &[u8],
+        public_key: &BlsPublicKey,
+    ) -> Result<(), SignatureVerifyError> {
+        check(self.0.verify(true, msg, DST, &[], &public_key.0, true))
+    }
+
+    /// Verifies an aggregate signature over a batch of session headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - The session headers, which must be distinct.
+    /// * `public_keys` - The public key of the notary which signed each header.
+    pub fn verify_aggregate(
+        &self,
+        headers: &[SessionHeader],
+        public_keys: &[BlsPublicKey],
+    ) -> Result<(), SignatureVerifyError> {
+        if headers.is_empty() || headers.len() != public_keys.len() {
+            return Err(SignatureVerifyError(format!(
+                "expected a public key for each of the {} headers, got {}",
+                headers.len(),
+                public_keys.len()
+            )));
+        }
+
+        let msgs = headers
+            .iter()
+            .map(|header| header.to_bytes())
+            .collect::<Vec<_>>();
+
+        // The aggregate of the signatures of the same message is forgeable, e.g. it can cancel
+        // out signatures with rogue keys.
+        let mut distinct = HashSet::with_capacity(msgs.len());
+        if !msgs.iter().all(|msg| distinct.insert(msg.as_slice())) {
+            return Err(SignatureVerifyError(
+                "the batch contains the same session header more than once".to_string(),
+            ));
+        }
+
+        let msgs = msgs.iter().map(|msg| msg.as_slice()).collect::<Vec<_>>();
+        let public_keys = public_keys.iter().map(|key| &key.0).collect::<Vec<_>>();
+
+        check(
+            self.0
+                .aggregate_verify(true, &msgs, DST, &public_keys, true),
+        )
+    }
+}
+
+fn check(result: BLST_ERROR) -> Result<(), SignatureVerifyError> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => Err(SignatureVerifyError(format!(
+            "BLS signature verification failed: {e:?}"
+        ))),
+    }
+}
+
+impl Serialize for BlsPublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsPublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for BlsSignature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsSignature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, Signature};
+    use signature::Signer;
+
+    fn session_header(seed: u8) -> SessionHeader {
+        fixtures::session_header([seed; 32].into(), 100, 100)
+    }
+
+    fn key(seed: u8) -> BlsSigningKey {
+        BlsSigningKey::from_ikm(&[seed; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_bls_aggregate() {
+        let keys = [key(1), key(2), key(3)];
+        let headers = (0..3u8).map(session_header).collect::<Vec<_>>();
+
+        let signatures = keys
+            .iter()
+            .zip(&headers)
+            .map(|(key, header)| key.sign(&header.to_bytes()))
+            .collect::<Vec<_>>();
+        let public_keys = keys
+            .iter()
+            .map(BlsSigningKey::public_key)
+            .collect::<Vec<_>>();
+
+        for ((signature, header), public_key) in signatures.iter().zip(&headers).zip(&public_keys) {
+            signature.verify(&header.to_bytes(), public_key).unwrap();
+        }
+
+        let aggregate = BlsSignature::aggregate(&signatures).unwrap();
+        aggregate.verify_aggregate(&headers, &public_keys).unwrap();
+
+        // The aggregate does not verify if a header is swapped.
+        let mut other_headers = headers.clone();
+        other_headers[2] = session_header(42);
+        assert!(aggregate
+            .verify_aggregate(&other_headers, &public_keys)
+            .is_err());
+    }
+
+    #[test]
+    fn test_bls_notary_signature() {
+        let key = key(1);
+        let header = session_header(0);
+
+        let signature = Signature::from(key.sign(&header.to_bytes()));
+        signature
+            .verify(&header.to_bytes(), key.public_key())
+            .unwrap();
+
+        // The signature does not verify against a key of another scheme.
+        let p256_key = p256::PublicKey::from(fixtures::notary_signing_key().verifying_key());
+        assert!(signature.verify(&header.to_bytes(), p256_key).is_err());
+    }
+
+    #[test]
+    fn test_bls_aggregate_rejects_duplicate_headers() {
+        let keys = [key(1), key(2)];
+        let header = session_header(0);
+
+        let signatures = keys
+            .iter()
+            .map(|key| key.sign(&header.to_bytes()))
+            .collect::<Vec<_>>();
+        let public_keys = keys
+            .iter()
+            .map(BlsSigningKey::public_key)
+            .collect::<Vec<_>>();
+
+        let aggregate = BlsSignature::aggregate(&signatures).unwrap();
+        let err = aggregate
+            .verify_aggregate(&[header.clone(), header], &public_keys)
+            .unwrap_err();
+        assert!(err.to_string().contains("more than once"));
+    }
+}
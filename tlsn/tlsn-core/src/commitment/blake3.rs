@@ -1,4 +1,7 @@
-use crate::commitment::{Commitment, CommitmentOpening};
+use crate::{
+    commitment::{Commitment, CommitmentInfo, CommitmentKind, CommitmentOpening, CommitmentScheme},
+    SessionHeader,
+};
 use mpz_core::{
     commit::{Decommitment, HashCommit, Nonce},
     hash::Hash,
@@ -103,3 +106,31 @@ impl From<Blake3Opening> for CommitmentOpening {
         Self::Blake3(value)
     }
 }
+
+/// The scheme of BLAKE3 commitments to the encodings of the transcript.
+#[derive(Debug, Clone, Copy)]
+pub struct Blake3Scheme;
+
+impl CommitmentScheme for Blake3Scheme {
+    const KIND: CommitmentKind = CommitmentKind::Blake3;
+
+    type Commitment = Blake3Commitment;
+    type Opening = Blake3Opening;
+
+    fn recover_expected(
+        header: &SessionHeader,
+        info: &CommitmentInfo,
+        opening: &Blake3Opening,
+    ) -> Option<Blake3Commitment> {
+        // Make sure the opening length matches the ranges length.
+        if opening.data().len() != info.ranges().len() {
+            return None;
+        }
+
+        // Generate the expected encodings for the purported data in the opening, with the
+        // encoding scheme the session was notarized with.
+        let encodings = header.encodings(*info.direction(), info.ranges());
+
+        Some(opening.recover(&encodings))
+    }
+}
@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, ops::Range};
 
 use bimap::BiMap;
 use mpz_core::hash::Hash;
-use utils::range::{RangeSet, ToRangeSet};
+use mpz_garble_core::{encoding_state, EncodedValue};
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use utils::range::{RangeSet, RangeUnion, ToRangeSet};
 
 use crate::{
     commitment::{
-        blake3::Blake3Commitment, Commitment, CommitmentId, CommitmentInfo, CommitmentKind,
+        blake3::Blake3Commitment, hash::PlaintextHashCommitment, Commitment, CommitmentConstraints,
+        CommitmentFilter, CommitmentId, CommitmentInfo, CommitmentKind, CommitmentOpening,
         TranscriptCommitments,
     },
     merkle::MerkleTree,
@@ -29,24 +34,55 @@ pub enum TranscriptCommitmentBuilderError {
         upper_transcript: usize,
     },
     /// Failed to retrieve encodings for the provided transcript ranges.
+    ///
+    /// The encodings of Blake3 commitments are retrieved when the builder is
+    /// [built](TranscriptCommitmentBuilder::build), so this is returned by `build`.
     #[error("failed to retrieve encodings for the provided transcript ranges")]
     MissingEncodings,
+    /// The plaintext of the transcript was not provided to the builder.
+    #[error("the plaintext of the transcript is required to commit to its hash")]
+    MissingPlaintext,
     /// Duplicate commitment
     #[error("attempted to create a duplicate commitment, overwriting: {0:?}")]
     Duplicate(CommitmentId),
     /// No commitments were added
     #[error("no commitments were added")]
     NoCommitments,
+    /// The chunk size of a chunked commitment is zero
+    #[error("the chunk size of a chunked commitment must not be zero")]
+    ZeroChunkSize,
+    /// No commitment exists with the provided id
+    #[error("no commitment exists with id: {0:?}")]
+    UnknownCommitment(CommitmentId),
+    /// The commitment would publicly commit to data rejected by the commitment filter
+    #[error("commitment to {direction:?} data overlaps filtered secrets at {ranges:?}")]
+    Filtered {
+        /// The direction of the commitment
+        direction: Direction,
+        /// The ranges of the secrets overlapped by the commitment
+        ranges: RangeSet<usize>,
+    },
 }
 
 /// A builder for [`TranscriptCommitments`].
+///
+/// The encodings committed to by Blake3 commitments are only retrieved and hashed when the
+/// commitments are [built](Self::build), so that the encodings of overlapping ranges are
+/// retrieved once and the commitments are hashed in parallel if the `rayon` feature is enabled.
 pub struct TranscriptCommitmentBuilder {
     commitments: HashMap<CommitmentId, Commitment>,
-    /// Information about the above `commitments`.
+    /// Information about the above `commitments`, and about the `pending` commitments.
     commitment_info: BiMap<CommitmentId, CommitmentInfo>,
-    merkle_leaves: Vec<Hash>,
+    /// The Blake3 commitments which are hashed when the builder is built.
+    pending: Vec<CommitmentId>,
+    /// The leaves of the Merkle tree, unless the commitment is pending.
+    merkle_leaves: Vec<Option<Hash>>,
     /// A function that returns the encodings for the provided transcript byte ids.
     encoding_provider: EncodingProvider,
+    /// The plaintext of the sent and received transcripts, required for plaintext hash commitments.
+    plaintext: Option<(Vec<u8>, Vec<u8>)>,
+    /// The filter of secrets which must not be committed to publicly.
+    filter: Option<CommitmentFilter>,
     sent_len: usize,
     recv_len: usize,
 }
@@ -64,19 +100,51 @@ impl TranscriptCommitmentBuilder {
         Self {
             commitments: HashMap::default(),
             commitment_info: BiMap::default(),
+            pending: Vec::default(),
             merkle_leaves: Vec::default(),
             encoding_provider,
+            plaintext: None,
+            filter: None,
             sent_len,
             recv_len,
         }
     }
 
+    /// Sets the plaintext of the sent and received transcripts, which is required to commit to
+    /// plaintext hashes.
+    #[doc(hidden)]
+    pub fn with_plaintext(mut self, sent: Vec<u8>, recv: Vec<u8>) -> Self {
+        self.plaintext = Some((sent, recv));
+        self
+    }
+
+    /// Sets the filter of secrets which must not be committed to publicly, see
+    /// [`CommitmentFilter`].
+    ///
+    /// Once set, committing to ranges which overlap an occurrence of a secret in the transcript
+    /// fails with [`TranscriptCommitmentBuilderError::Filtered`], and no commitment is added.
+    /// Only literal occurrences of the secrets are matched, so a secret which is encoded in the
+    /// transcript, e.g. escaped in a JSON string, is not blocked.
+    ///
+    /// Requires the plaintext of the transcripts, see [`with_plaintext`](Self::with_plaintext).
+    /// Without it, the filtered commitments fail with
+    /// [`TranscriptCommitmentBuilderError::MissingPlaintext`].
+    pub fn with_filter(mut self, filter: CommitmentFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
     /// Commits to the provided ranges of the `sent` transcript.
     pub fn commit_sent(
         &mut self,
         ranges: &dyn ToRangeSet<usize>,
     ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
-        self.add_substrings_commitment(&ranges.to_range_set(), Direction::Sent)
+        self.add_substrings_commitment(
+            &ranges.to_range_set(),
+            Direction::Sent,
+            CommitmentKind::Blake3,
+            CommitmentConstraints::default(),
+        )
     }
 
     /// Commits to the provided ranges of the `received` transcript.
@@ -84,10 +152,19 @@ impl TranscriptCommitmentBuilder {
         &mut self,
         ranges: &dyn ToRangeSet<usize>,
     ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
-        self.add_substrings_commitment(&ranges.to_range_set(), Direction::Received)
+        self.add_substrings_commitment(
+            &ranges.to_range_set(),
+            Direction::Received,
+            CommitmentKind::Blake3,
+            CommitmentConstraints::default(),
+        )
     }
 
     /// Commits to the provided ranges of the transcript.
+    ///
+    /// The encodings of the ranges are only retrieved when the builder is
+    /// [built](Self::build), so if they are not available, `build` fails with
+    /// [`TranscriptCommitmentBuilderError::MissingEncodings`] rather than this method.
     pub fn commit(
         &mut self,
         ranges: &dyn ToRangeSet<usize>,
@@ -99,7 +176,203 @@ impl TranscriptCommitmentBuilder {
         }
     }
 
-    /// Gets the commitment id for the provided commitment info.
+    /// Commits to each range of the provided range set of the `sent` transcript separately.
+    ///
+    /// See [`commit_batch`](Self::commit_batch).
+    pub fn commit_sent_batch(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        self.commit_batch(ranges, Direction::Sent)
+    }
+
+    /// Commits to each range of the provided range set of the `received` transcript separately.
+    ///
+    /// See [`commit_batch`](Self::commit_batch).
+    pub fn commit_recv_batch(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        self.commit_batch(ranges, Direction::Received)
+    }
+
+    /// Commits to each range of the provided range set of the transcript separately.
+    ///
+    /// This is equivalent to calling [`commit`](Self::commit) for every range of the set, except
+    /// that the ranges are validated once. Overlapping and adjacent ranges are merged by the range
+    /// set beforehand. If any of the commitments already exists, no commitment is added.
+    /// Missing encodings are only reported by [`build`](Self::build), as for `commit`.
+    ///
+    /// Returns the ids of the commitments, ordered by their ranges.
+    pub fn commit_batch(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+        direction: Direction,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        let ranges = ranges.to_range_set();
+        let chunks = ranges.iter_ranges().collect();
+
+        self.commit_chunks(&ranges, chunks, direction)
+    }
+
+    /// Commits to the provided ranges of the transcript in chunks of at most `chunk_size` bytes,
+    /// each chunk being a separate commitment.
+    ///
+    /// This is intended for very large ranges, e.g. multi-megabyte bodies. Each of the chunks can
+    /// be opened on its own. If any of the commitments already exists, no commitment is added.
+    ///
+    /// Returns the ids of the commitments, ordered by their ranges.
+    pub fn commit_chunked(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+        direction: Direction,
+        chunk_size: usize,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        if chunk_size == 0 {
+            return Err(TranscriptCommitmentBuilderError::ZeroChunkSize);
+        }
+
+        let ranges = ranges.to_range_set();
+        let chunks = ranges
+            .iter_ranges()
+            .flat_map(|range| {
+                range
+                    .clone()
+                    .step_by(chunk_size)
+                    .map(move |start| start..(start + chunk_size).min(range.end))
+            })
+            .collect();
+
+        self.commit_chunks(&ranges, chunks, direction)
+    }
+
+    /// Commits to each of the provided ranges of the transcript separately.
+    ///
+    /// Unlike [`commit_batch`](Self::commit_batch), adjacent ranges are not merged, so each of
+    /// them can be opened on its own. All ranges are validated before any commitment is added, so
+    /// if any of them is empty, out of bounds, filtered or already committed to, no commitment is
+    /// added.
+    ///
+    /// Returns the ids of the commitments, in the order of the provided ranges.
+    pub fn commit_ranges(
+        &mut self,
+        ranges: &[Range<usize>],
+        direction: Direction,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        let mut positions = HashMap::with_capacity(ranges.len());
+        for (position, range) in ranges.iter().enumerate() {
+            if range.is_empty() {
+                return Err(TranscriptCommitmentBuilderError::EmptyRange);
+            }
+
+            // A range provided twice would be rejected once the first one is inserted.
+            if let Some(first) = positions.insert(range, position) {
+                return Err(TranscriptCommitmentBuilderError::Duplicate(
+                    CommitmentId::new((self.merkle_leaves.len() + first) as u32),
+                ));
+            }
+        }
+
+        let union = RangeSet::from(ranges.to_vec());
+
+        self.commit_chunks(&union, ranges.to_vec(), direction)
+    }
+
+    /// Commits to each of the chunks, which must be ordered and partition the ranges.
+    fn commit_chunks(
+        &mut self,
+        ranges: &RangeSet<usize>,
+        chunks: Vec<Range<usize>>,
+        direction: Direction,
+    ) -> Result<Vec<CommitmentId>, TranscriptCommitmentBuilderError> {
+        self.check_bounds(ranges, direction)?;
+        self.check_filter(ranges, direction)?;
+
+        let infos = chunks
+            .into_iter()
+            .map(|range| CommitmentInfo::new(CommitmentKind::Blake3, range.into(), direction))
+            .collect::<Vec<_>>();
+
+        if let Some(id) = infos
+            .iter()
+            .find_map(|info| self.commitment_info.get_by_right(info))
+        {
+            return Err(TranscriptCommitmentBuilderError::Duplicate(*id));
+        }
+
+        infos
+            .into_iter()
+            .map(|info| self.insert_pending(info))
+            .collect()
+    }
+
+    /// Commits to the provided ranges of the transcript, restricting the use of the commitment with
+    /// the provided constraints.
+    ///
+    /// The constraints are bound to the commitment in the Merkle tree, so they can not be removed
+    /// from proofs opening the commitment.
+    pub fn commit_with_constraints(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+        direction: Direction,
+        constraints: CommitmentConstraints,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        self.add_substrings_commitment(
+            &ranges.to_range_set(),
+            direction,
+            CommitmentKind::Blake3,
+            constraints,
+        )
+    }
+
+    /// Adds a Blake3 commitment to the provided ranges of the transcript which was already hashed,
+    /// e.g. while the connection to the server was still open.
+    ///
+    /// The commitment must have been created from the encodings of the ranges.
+    #[doc(hidden)]
+    pub fn commit_precomputed(
+        &mut self,
+        ranges: &RangeSet<usize>,
+        direction: Direction,
+        commitment: Blake3Commitment,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        self.check_bounds(ranges, direction)?;
+        self.check_filter(ranges, direction)?;
+
+        let info = CommitmentInfo::new(CommitmentKind::Blake3, ranges.clone(), direction);
+
+        self.insert_commitment(info, commitment.into())
+    }
+
+    /// Commits to the salted hash of the provided ranges of the transcript.
+    ///
+    /// Opening the commitment reveals the hash instead of the data, so a verifier can match the
+    /// data against a known value without learning it otherwise. The hash is checked by the
+    /// Notary during notarization.
+    pub fn commit_hash(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+        direction: Direction,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        self.commit_with_kind(ranges, direction, CommitmentKind::PlaintextHash)
+    }
+
+    /// Commits to the provided ranges of the transcript with a commitment of the provided kind.
+    pub fn commit_with_kind(
+        &mut self,
+        ranges: &dyn ToRangeSet<usize>,
+        direction: Direction,
+        kind: CommitmentKind,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        self.add_substrings_commitment(
+            &ranges.to_range_set(),
+            direction,
+            kind,
+            CommitmentConstraints::default(),
+        )
+    }
+
+    /// Gets the commitment id for the provided unconstrained commitment info.
     pub fn get_id(
         &self,
         kind: CommitmentKind,
@@ -107,20 +380,140 @@ impl TranscriptCommitmentBuilder {
         direction: Direction,
     ) -> Option<CommitmentId> {
         self.commitment_info
-            .get_by_right(&CommitmentInfo {
-                kind,
-                ranges: ranges.into(),
-                direction,
-            })
+            .get_by_right(&CommitmentInfo::new(kind, ranges.into(), direction))
             .copied()
     }
 
+    /// Returns the info of the commitment with the provided id.
+    pub fn get_info(&self, id: &CommitmentId) -> Option<&CommitmentInfo> {
+        self.commitment_info.get_by_left(id)
+    }
+
+    /// Returns the number of commitments added to the builder.
+    pub fn len(&self) -> usize {
+        self.merkle_leaves.len()
+    }
+
+    /// Returns `true` if no commitments were added to the builder.
+    pub fn is_empty(&self) -> bool {
+        self.merkle_leaves.is_empty()
+    }
+
+    /// Returns the number of plaintext hash commitments added to the builder.
+    pub fn plaintext_hash_count(&self) -> usize {
+        self.commitments
+            .values()
+            .filter(|commitment| matches!(commitment, Commitment::PlaintextHash(_)))
+            .count()
+    }
+
+    /// Returns the serialized size, in bytes, of a [`SubstringsProof`](crate::proof::SubstringsProof)
+    /// opening the provided commitments.
+    ///
+    /// The size is computed from the commitments added so far, so it can be used to adjust which
+    /// commitments are made or revealed before the session is notarized. Adding commitments
+    /// afterwards may change the size of the inclusion proof.
+    pub fn substrings_proof_size(
+        &self,
+        ids: &[CommitmentId],
+    ) -> Result<usize, TranscriptCommitmentBuilderError> {
+        // The size of the inclusion proof only depends on the number of leaves, not on their
+        // value, so the leaves of pending commitments are left empty.
+        let merkle_leaves = self
+            .merkle_leaves
+            .iter()
+            .map(|leaf| leaf.unwrap_or_else(|| Hash::from([0u8; 32])))
+            .collect::<Vec<_>>();
+        let merkle_tree = MerkleTree::from_leaves(&merkle_leaves)
+            .map_err(|_| TranscriptCommitmentBuilderError::NoCommitments)?;
+
+        let mut openings = HashMap::with_capacity(ids.len());
+        for id in ids {
+            let info = self
+                .commitment_info
+                .get_by_left(id)
+                .ok_or(TranscriptCommitmentBuilderError::UnknownCommitment(*id))?;
+
+            // The size of an opening only depends on the length of the data, not on its value.
+            let opening: CommitmentOpening = match self.commitments.get(id) {
+                Some(Commitment::Blake3(commitment)) => {
+                    commitment.open(vec![0; info.ranges.len()]).into()
+                }
+                Some(Commitment::PlaintextHash(commitment)) => commitment.open().into(),
+                None => Blake3Commitment::new(&[])
+                    .open(vec![0; info.ranges.len()])
+                    .into(),
+            };
+
+            openings.insert(*id, (info.clone(), opening));
+        }
+
+        let mut indices = ids
+            .iter()
+            .map(|id| id.to_inner() as usize)
+            .collect::<Vec<_>>();
+        indices.sort();
+        indices.dedup();
+
+        let inclusion_proof = merkle_tree.proof(&indices);
+
+        // A struct is serialized as the tuple of its fields.
+        let size = bincode::serialized_size(&(&openings, &inclusion_proof))
+            .expect("substrings proof is serializable");
+
+        Ok(size as usize)
+    }
+
     /// Add a commitment to substrings of the transcript
     fn add_substrings_commitment(
         &mut self,
         ranges: &RangeSet<usize>,
         direction: Direction,
+        kind: CommitmentKind,
+        constraints: CommitmentConstraints,
     ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        self.check_bounds(ranges, direction)?;
+
+        let info = CommitmentInfo {
+            kind,
+            ranges: ranges.clone(),
+            direction,
+            constraints,
+        };
+
+        let commitment: Commitment = match kind {
+            CommitmentKind::Blake3 => {
+                self.check_filter(ranges, direction)?;
+                return self.insert_pending(info);
+            }
+            CommitmentKind::PlaintextHash => {
+                let (sent, recv) = self
+                    .plaintext
+                    .as_ref()
+                    .ok_or(TranscriptCommitmentBuilderError::MissingPlaintext)?;
+                let transcript = match direction {
+                    Direction::Sent => sent,
+                    Direction::Received => recv,
+                };
+
+                let data = ranges
+                    .iter_ranges()
+                    .flat_map(|range| transcript[range].iter().copied())
+                    .collect::<Vec<_>>();
+
+                PlaintextHashCommitment::new(&data, rand::thread_rng().gen()).into()
+            }
+        };
+
+        self.insert_commitment(info, commitment)
+    }
+
+    /// Checks that the ranges are not empty and within the bounds of the transcript.
+    fn check_bounds(
+        &self,
+        ranges: &RangeSet<usize>,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
         let max = ranges
             .max()
             .ok_or(TranscriptCommitmentBuilderError::EmptyRange)?;
@@ -136,42 +529,145 @@ impl TranscriptCommitmentBuilder {
             });
         }
 
-        let ids: Vec<_> = get_value_ids(ranges, direction).collect();
-
-        let id_refs = ids.iter().map(|id| id.as_ref()).collect::<Vec<_>>();
+        Ok(())
+    }
 
-        let encodings = (self.encoding_provider)(&id_refs)
-            .ok_or(TranscriptCommitmentBuilderError::MissingEncodings)?;
+    /// Checks that a commitment revealing the data of the ranges does not overlap the secrets of
+    /// the filter, if any.
+    fn check_filter(
+        &self,
+        ranges: &RangeSet<usize>,
+        direction: Direction,
+    ) -> Result<(), TranscriptCommitmentBuilderError> {
+        let Some(filter) = &self.filter else {
+            return Ok(());
+        };
 
-        // We only support BLAKE3 for now
-        let commitment = Blake3Commitment::new(&encodings);
-        let hash = *commitment.hash();
+        let (sent, recv) = self
+            .plaintext
+            .as_ref()
+            .ok_or(TranscriptCommitmentBuilderError::MissingPlaintext)?;
+        let data = match direction {
+            Direction::Sent => sent,
+            Direction::Received => recv,
+        };
 
-        let id = CommitmentId::new(self.merkle_leaves.len() as u32);
+        let blocked = filter.blocked(data, ranges);
+        if !blocked.is_empty() {
+            return Err(TranscriptCommitmentBuilderError::Filtered {
+                direction,
+                ranges: blocked,
+            });
+        }
 
-        let commitment: Commitment = commitment.into();
+        Ok(())
+    }
 
-        // Store commitment with its id
-        self.commitment_info
-            .insert_no_overwrite(
-                id,
-                CommitmentInfo::new(commitment.kind(), ranges.clone(), direction),
-            )
-            .map_err(|(id, _)| TranscriptCommitmentBuilderError::Duplicate(id))?;
+    /// Inserts a commitment, adding its leaf to the Merkle tree.
+    fn insert_commitment(
+        &mut self,
+        info: CommitmentInfo,
+        commitment: Commitment,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        let leaf = info.leaf(commitment.hash());
+        let id = self.insert_info(info)?;
 
         if self.commitments.insert(id, commitment).is_some() {
             // This shouldn't be possible, as we check for duplicates above.
             panic!("commitment id already exists");
         }
 
-        // Insert commitment hash into the merkle tree
-        self.merkle_leaves.push(hash);
+        self.merkle_leaves[id.to_inner() as usize] = Some(leaf);
+
+        Ok(id)
+    }
+
+    /// Inserts a Blake3 commitment which is hashed when the builder is built.
+    fn insert_pending(
+        &mut self,
+        info: CommitmentInfo,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        let id = self.insert_info(info)?;
+        self.pending.push(id);
 
         Ok(id)
     }
 
+    /// Inserts the info of a commitment, reserving its leaf in the Merkle tree.
+    fn insert_info(
+        &mut self,
+        info: CommitmentInfo,
+    ) -> Result<CommitmentId, TranscriptCommitmentBuilderError> {
+        let id = CommitmentId::new(self.merkle_leaves.len() as u32);
+
+        // Store commitment info with its id
+        self.commitment_info
+            .insert_no_overwrite(id, info)
+            .map_err(|(id, _)| TranscriptCommitmentBuilderError::Duplicate(id))?;
+
+        self.merkle_leaves.push(None);
+
+        Ok(id)
+    }
+
+    /// Hashes the pending commitments.
+    ///
+    /// The encodings of the union of the ranges of each direction are retrieved at once, so the
+    /// encodings of overlapping ranges are only retrieved once.
+    fn hash_pending(&mut self) -> Result<(), TranscriptCommitmentBuilderError> {
+        let pending = std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|id| {
+                let info = self
+                    .commitment_info
+                    .get_by_left(&id)
+                    .expect("pending commitment has info");
+                (id, info)
+            })
+            .collect::<Vec<_>>();
+
+        let encodings = [Direction::Sent, Direction::Received].map(|direction| {
+            let ranges = pending
+                .iter()
+                .filter(|(_, info)| info.direction == direction)
+                .fold(RangeSet::default(), |ranges, (_, info)| {
+                    ranges.union(&info.ranges)
+                });
+
+            EncodingCache::new(&self.encoding_provider, ranges, direction)
+        });
+        let [sent, recv] = encodings;
+        let (sent, recv) = (sent?, recv?);
+
+        let hash = |(id, info): &(CommitmentId, &CommitmentInfo)| {
+            let encodings = match info.direction {
+                Direction::Sent => &sent,
+                Direction::Received => &recv,
+            };
+            let commitment = Blake3Commitment::new(&encodings.get(&info.ranges));
+
+            (*id, info.leaf(*commitment.hash()), commitment)
+        };
+
+        #[cfg(feature = "rayon")]
+        let commitments: Vec<_> = pending.par_iter().map(hash).collect();
+        #[cfg(not(feature = "rayon"))]
+        let commitments: Vec<_> = pending.iter().map(hash).collect();
+
+        for (id, leaf, commitment) in commitments {
+            self.commitments.insert(id, commitment.into());
+            self.merkle_leaves[id.to_inner() as usize] = Some(leaf);
+        }
+
+        Ok(())
+    }
+
     /// Builds the [`TranscriptCommitments`]
-    pub fn build(self) -> Result<TranscriptCommitments, TranscriptCommitmentBuilderError> {
+    ///
+    /// Returns an error if the encodings of the committed ranges can not be retrieved.
+    pub fn build(mut self) -> Result<TranscriptCommitments, TranscriptCommitmentBuilderError> {
+        self.hash_pending()?;
+
         let Self {
             commitments,
             commitment_info,
@@ -179,6 +675,11 @@ impl TranscriptCommitmentBuilder {
             ..
         } = self;
 
+        let merkle_leaves = merkle_leaves
+            .into_iter()
+            .map(|leaf| leaf.expect("all commitments are hashed"))
+            .collect::<Vec<_>>();
+
         let merkle_tree = MerkleTree::from_leaves(&merkle_leaves)
             .map_err(|_| TranscriptCommitmentBuilderError::NoCommitments)?;
 
@@ -189,3 +690,309 @@ impl TranscriptCommitmentBuilder {
         })
     }
 }
+
+/// The encodings of the union of the ranges committed to in a direction.
+struct EncodingCache {
+    /// The ranges of the union, with the offset of their first encoding.
+    ranges: Vec<(Range<usize>, usize)>,
+    encodings: Vec<EncodedValue<encoding_state::Active>>,
+}
+
+impl EncodingCache {
+    /// Retrieves the encodings of the ranges.
+    fn new(
+        encoding_provider: &EncodingProvider,
+        ranges: RangeSet<usize>,
+        direction: Direction,
+    ) -> Result<Self, TranscriptCommitmentBuilderError> {
+        if ranges.is_empty() {
+            return Ok(Self {
+                ranges: Vec::new(),
+                encodings: Vec::new(),
+            });
+        }
+
+        let ids: Vec<_> = get_value_ids(&ranges, direction).collect();
+        let id_refs = ids.iter().map(|id| id.as_ref()).collect::<Vec<_>>();
+
+        let encodings = encoding_provider(&id_refs)
+            .filter(|encodings| encodings.len() == ids.len())
+            .ok_or(TranscriptCommitmentBuilderError::MissingEncodings)?;
+
+        let mut offset = 0;
+        let ranges = ranges
+            .iter_ranges()
+            .map(|range| {
+                let start = offset;
+                offset += range.len();
+                (range, start)
+            })
+            .collect();
+
+        Ok(Self { ranges, encodings })
+    }
+
+    /// Returns the encodings of the provided ranges, which must be included in the cached ranges.
+    fn get(&self, ranges: &RangeSet<usize>) -> Cow<'_, [EncodedValue<encoding_state::Active>]> {
+        let slice = |range: Range<usize>| {
+            let idx = self
+                .ranges
+                .partition_point(|(cached, _)| cached.end <= range.start);
+            let (cached, offset) = &self.ranges[idx];
+            let start = offset + range.start - cached.start;
+
+            &self.encodings[start..start + range.len()]
+        };
+
+        // The encodings of a single range are contiguous in the cache.
+        let mut iter = ranges.iter_ranges();
+        match (iter.next(), iter.next()) {
+            (Some(range), None) => Cow::Borrowed(slice(range)),
+            _ => Cow::Owned(
+                ranges
+                    .iter_ranges()
+                    .flat_map(|range| slice(range).iter().cloned())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures::encoding_provider, proof::SubstringsProofBuilder, Transcript};
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use mpz_core::commit::Decommitment;
+
+    const SENT: &[u8] = b"sent data";
+    const RECV: &[u8] = b"received data";
+
+    fn builder() -> TranscriptCommitmentBuilder {
+        TranscriptCommitmentBuilder::new(encoding_provider(SENT, RECV), SENT.len(), RECV.len())
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut builder = builder()
+            .with_plaintext(SENT.to_vec(), RECV.to_vec())
+            .with_filter(CommitmentFilter::new(["data"]));
+
+        builder.commit_sent(&(0..4)).unwrap();
+        builder.commit_hash(&(5..9), Direction::Sent).unwrap();
+
+        let err = builder.commit_recv(&(0..10)).unwrap_err();
+        assert!(matches!(
+            err,
+            TranscriptCommitmentBuilderError::Filtered { direction: Direction::Received, ranges }
+                if ranges == RangeSet::from(9..13)
+        ));
+        assert!(matches!(
+            builder.commit_chunked(&(0..9), Direction::Sent, 4),
+            Err(TranscriptCommitmentBuilderError::Filtered { .. })
+        ));
+    }
+
+    #[test]
+    fn test_commit_batch() {
+        let mut batch = builder();
+        let ids = batch
+            .commit_sent_batch(&RangeSet::from([0..2, 2..4, 6..8]))
+            .unwrap();
+
+        // Adjacent ranges are merged.
+        assert_eq!(ids.len(), 2);
+
+        let mut single = builder();
+        single.commit_sent(&(0..4)).unwrap();
+        single.commit_sent(&(6..8)).unwrap();
+
+        assert_eq!(
+            batch.build().unwrap().merkle_root(),
+            single.build().unwrap().merkle_root()
+        );
+    }
+
+    #[test]
+    fn test_commit_batch_out_of_bounds() {
+        let mut builder = builder();
+        let err = builder
+            .commit_recv_batch(&RangeSet::from([0..2, 10..14]))
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            TranscriptCommitmentBuilderError::RangeOutOfBounds { .. }
+        ));
+        assert!(builder.is_empty());
+    }
+
+    #[test]
+    fn test_commit_batch_duplicate() {
+        let mut builder = builder();
+        let id = builder.commit_sent(&(6..8)).unwrap();
+
+        let err = builder
+            .commit_sent_batch(&RangeSet::from([0..2, 6..8]))
+            .unwrap_err();
+
+        assert!(matches!(err, TranscriptCommitmentBuilderError::Duplicate(dup) if dup == id));
+        assert_eq!(builder.len(), 1);
+    }
+
+    #[test]
+    fn test_commit_ranges() {
+        let mut builder = builder()
+            .with_plaintext(SENT.to_vec(), RECV.to_vec())
+            .with_filter(CommitmentFilter::new(["data"]));
+
+        // Nothing is added if any of the ranges is rejected.
+        assert!(matches!(
+            builder.commit_ranges(&[0..4, 4..5, 5..9], Direction::Sent),
+            Err(TranscriptCommitmentBuilderError::Filtered { .. })
+        ));
+        assert!(matches!(
+            builder.commit_ranges(&[0..4, 4..5, 5..10], Direction::Sent),
+            Err(TranscriptCommitmentBuilderError::RangeOutOfBounds { .. })
+        ));
+        assert!(matches!(
+            builder.commit_ranges(&[0..4, 4..4], Direction::Sent),
+            Err(TranscriptCommitmentBuilderError::EmptyRange)
+        ));
+        assert!(matches!(
+            builder.commit_ranges(&[0..4, 4..5, 0..4], Direction::Sent),
+            Err(TranscriptCommitmentBuilderError::Duplicate(_))
+        ));
+        assert!(builder.is_empty());
+
+        // Adjacent ranges are not merged.
+        let ids = builder
+            .commit_ranges(&[4..5, 0..4], Direction::Sent)
+            .unwrap();
+
+        assert_eq!(
+            ids,
+            [4..5, 0..4]
+                .into_iter()
+                .map(|range| builder
+                    .get_id(CommitmentKind::Blake3, range, Direction::Sent)
+                    .unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_commit_chunked() {
+        let mut builder = builder();
+        let ids = builder
+            .commit_chunked(&RangeSet::from([0..5, 7..13]), Direction::Received, 4)
+            .unwrap();
+
+        assert_eq!(ids.len(), 4);
+        for range in [0..4, 4..5, 7..11, 11..13] {
+            assert!(builder
+                .get_id(CommitmentKind::Blake3, range, Direction::Received)
+                .is_some());
+        }
+
+        assert!(matches!(
+            builder.commit_chunked(&(0..4), Direction::Sent, 0),
+            Err(TranscriptCommitmentBuilderError::ZeroChunkSize)
+        ));
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        let requested = Arc::new(AtomicUsize::new(0));
+        let counter = requested.clone();
+        let provider = encoding_provider(SENT, RECV);
+        let mut builder = TranscriptCommitmentBuilder::new(
+            Box::new(move |ids: &[&str]| {
+                counter.fetch_add(ids.len(), Ordering::Relaxed);
+                provider(ids)
+            }),
+            SENT.len(),
+            RECV.len(),
+        );
+
+        let ids = [
+            builder.commit_recv(&(0..8)).unwrap(),
+            builder.commit_recv(&(4..13)).unwrap(),
+            builder.commit_recv(&RangeSet::from([0..2, 6..10])).unwrap(),
+            builder.commit_sent(&(2..6)).unwrap(),
+        ];
+        let commitments = builder.build().unwrap();
+
+        // The encodings of the overlapping ranges are only retrieved once.
+        assert_eq!(requested.load(Ordering::Relaxed), RECV.len() + 4);
+
+        let provider = encoding_provider(SENT, RECV);
+        for id in ids {
+            let info = commitments.get_info(&id).unwrap();
+            let Some(Commitment::Blake3(commitment)) = commitments.get(&id) else {
+                panic!("commitment is a Blake3 commitment");
+            };
+
+            let ids: Vec<_> = get_value_ids(&info.ranges, info.direction).collect();
+            let id_refs = ids.iter().map(|id| id.as_ref()).collect::<Vec<_>>();
+            let encodings = provider(&id_refs).unwrap();
+
+            assert_eq!(
+                Decommitment::new_with_nonce(encodings, *commitment.nonce()).commit(),
+                *commitment.hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_encodings() {
+        let provider = encoding_provider(SENT, RECV);
+        let mut builder = TranscriptCommitmentBuilder::new(
+            Box::new(move |ids: &[&str]| {
+                let mut encodings = provider(ids)?;
+                encodings.pop();
+                Some(encodings)
+            }),
+            SENT.len(),
+            RECV.len(),
+        );
+
+        // The encodings are only retrieved when the builder is built.
+        builder.commit_recv(&(0..8)).unwrap();
+
+        assert!(matches!(
+            builder.build(),
+            Err(TranscriptCommitmentBuilderError::MissingEncodings)
+        ));
+    }
+
+    #[test]
+    fn test_substrings_proof_size() {
+        let mut builder = builder().with_plaintext(SENT.to_vec(), RECV.to_vec());
+        let sent = builder.commit_sent(&(0..4)).unwrap();
+        let recv = builder.commit_recv(&(2..9)).unwrap();
+        let hash = builder.commit_hash(&(0..8), Direction::Received).unwrap();
+        builder.commit_recv(&(9..13)).unwrap();
+
+        assert_eq!(builder.len(), 4);
+        assert!(builder.get_info(&hash).is_some());
+
+        let ids = [sent, recv, hash];
+        let size = builder.substrings_proof_size(&ids).unwrap();
+
+        let commitments = builder.build().unwrap();
+        let (tx, rx) = (Transcript::new(SENT), Transcript::new(RECV));
+        let mut proof_builder = SubstringsProofBuilder::new(&commitments, &tx, &rx);
+        for id in ids {
+            proof_builder.reveal_by_id(id).unwrap();
+        }
+        let proof = proof_builder.build().unwrap();
+
+        assert_eq!(size, bincode::serialize(&proof).unwrap().len());
+    }
+}
@@ -0,0 +1,200 @@
+use mpz_core::{hash::Hash, utils::blake3};
+use serde::{Deserialize, Serialize};
+
+/// Domain separator of the Merkle leaves of constrained commitments.
+const CONSTRAINED_LEAF_DOMAIN: &[u8] = b"tlsn/commitment-constraints";
+
+/// Optional constraints on how a commitment may be used, bound to the commitment in the Merkle
+/// tree signed by the Notary.
+///
+/// Constraints are chosen by the Prover, so they do not restrict the Prover itself. Instead, they
+/// restrict a relying party from replaying a proof to another relying party, or after it has expired.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CommitmentConstraints {
+    expires_at: Option<u64>,
+    audience: Option<String>,
+}
+
+impl CommitmentConstraints {
+    /// Creates new constraints without any restrictions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the commitment to proofs verified before the provided time, in seconds since the
+    /// UNIX epoch.
+    pub fn expires_at(mut self, time: u64) -> Self {
+        self.expires_at = Some(time);
+        self
+    }
+
+    /// Restricts the commitment to proofs verified by the provided audience, e.g. a relying party's
+    /// domain name.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Returns the expiration time, in seconds since the UNIX epoch.
+    pub fn expiration(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Returns the intended audience.
+    pub fn intended_audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    /// Returns `true` if there are no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.expires_at.is_none() && self.audience.is_none()
+    }
+
+    /// Returns the Merkle leaf of a commitment with these constraints.
+    ///
+    /// The leaf of an unconstrained commitment is the hash of the commitment itself.
+    pub(crate) fn leaf(&self, commitment_hash: Hash) -> Hash {
+        if self.is_empty() {
+            return commitment_hash;
+        }
+
+        let mut bytes = CONSTRAINED_LEAF_DOMAIN.to_vec();
+        bytes.extend_from_slice(commitment_hash.as_bytes());
+        match self.expires_at {
+            Some(time) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&time.to_be_bytes());
+            }
+            None => bytes.push(0),
+        }
+        match &self.audience {
+            Some(audience) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(audience.len() as u64).to_be_bytes());
+                bytes.extend_from_slice(audience.as_bytes());
+            }
+            None => bytes.push(0),
+        }
+
+        Hash::from(blake3(&bytes))
+    }
+
+    /// Checks the constraints against the provided context.
+    pub fn check(&self, context: &ConstraintContext) -> Result<(), ConstraintError> {
+        if let Some(expires_at) = self.expires_at {
+            let time = context.time.ok_or(ConstraintError::MissingTime)?;
+            if time >= expires_at {
+                return Err(ConstraintError::Expired { expires_at, time });
+            }
+        }
+
+        if let Some(audience) = &self.audience {
+            if context.audience.as_ref() != Some(audience) {
+                return Err(ConstraintError::AudienceMismatch {
+                    expected: audience.clone(),
+                    actual: context.audience.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The context in which a proof is verified, checked against the [`CommitmentConstraints`] of the
+/// opened commitments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstraintContext {
+    time: Option<u64>,
+    audience: Option<String>,
+}
+
+impl ConstraintContext {
+    /// Creates a new empty context, which only accepts unconstrained commitments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the current time, in seconds since the UNIX epoch.
+    pub fn time(mut self, time: u64) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the audience verifying the proof.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+}
+
+/// An error for violated [`CommitmentConstraints`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConstraintError {
+    /// The commitment has expired.
+    #[error("commitment expired at {expires_at}, current time is {time}")]
+    Expired {
+        /// The expiration time.
+        expires_at: u64,
+        /// The current time.
+        time: u64,
+    },
+    /// The commitment has an expiration time, but the current time was not provided.
+    #[error("commitment has an expiration time but the current time was not provided")]
+    MissingTime,
+    /// The commitment is intended for another audience.
+    #[error("commitment is intended for audience {expected:?}, not {actual:?}")]
+    AudienceMismatch {
+        /// The intended audience.
+        expected: String,
+        /// The audience verifying the proof.
+        actual: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unconstrained_leaf() {
+        let hash = Hash::from([1u8; 32]);
+        assert_eq!(CommitmentConstraints::new().leaf(hash), hash);
+        assert_ne!(CommitmentConstraints::new().expires_at(0).leaf(hash), hash);
+        assert_ne!(
+            CommitmentConstraints::new().audience("a").leaf(hash),
+            CommitmentConstraints::new().audience("b").leaf(hash)
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let constraints = CommitmentConstraints::new()
+            .expires_at(100)
+            .audience("relying-party");
+
+        assert!(constraints
+            .check(&ConstraintContext::new().time(99).audience("relying-party"))
+            .is_ok());
+        assert_eq!(
+            constraints.check(&ConstraintContext::new().time(100).audience("relying-party")),
+            Err(ConstraintError::Expired {
+                expires_at: 100,
+                time: 100
+            })
+        );
+        assert_eq!(
+            constraints.check(&ConstraintContext::new().audience("relying-party")),
+            Err(ConstraintError::MissingTime)
+        );
+        assert!(matches!(
+            constraints.check(&ConstraintContext::new().time(0).audience("other")),
+            Err(ConstraintError::AudienceMismatch { .. })
+        ));
+
+        assert!(CommitmentConstraints::new()
+            .check(&ConstraintContext::new())
+            .is_ok());
+    }
+}
@@ -0,0 +1,103 @@
+use mpz_circuits::types::ValueType;
+use mpz_garble_core::{encoding_state::Full, ChaChaEncoder, EncodedValue, Encoder};
+use serde::{Deserialize, Serialize};
+use utils::range::RangeSet;
+
+use crate::{transcript::get_value_ids, Direction, EncodingId};
+
+/// The scheme of the encodings of the transcript.
+///
+/// The encodings are the labels the Prover obtains for the transcript during the MPC, which the
+/// BLAKE3 commitments are computed over. The Notary reveals the seed of the scheme after the
+/// Prover committed, so a verifier can recover the full encodings and check the commitments
+/// opened by a proof.
+///
+/// The scheme is carried in the [`SessionHeader`](crate::SessionHeader), so verifiers recover the
+/// encodings of a session with the scheme it was notarized with. A new encoding is added as a new
+/// variant, which leaves sessions notarized with the other schemes verifiable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum EncodingSchemeKind {
+    /// Encodings generated by the [`ChaChaEncoder`], a ChaCha12 PRG seeded with the encoder seed.
+    #[default]
+    ChaCha,
+}
+
+impl EncodingSchemeKind {
+    /// All kinds of encoding schemes supported by this version.
+    pub const ALL: &'static [EncodingSchemeKind] = &[EncodingSchemeKind::ChaCha];
+
+    /// Returns the stable identifier of the algorithm of this kind.
+    pub fn id(&self) -> &'static str {
+        match self {
+            EncodingSchemeKind::ChaCha => "chacha12",
+        }
+    }
+
+    /// Returns the kind with the provided algorithm identifier, if it is supported.
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().find(|kind| kind.id() == id).copied()
+    }
+
+    /// Returns the full encodings of the bytes of the transcript in the provided ranges, using the
+    /// scheme of this kind instantiated with `seed`.
+    pub fn encode(
+        &self,
+        seed: [u8; 32],
+        direction: Direction,
+        ranges: &RangeSet<usize>,
+    ) -> Vec<EncodedValue<Full>> {
+        match self {
+            EncodingSchemeKind::ChaCha => {
+                let encoder = ChaChaEncoder::new(seed);
+                get_value_ids(ranges, direction)
+                    .map(|id| {
+                        encoder.encode_by_type(EncodingId::new(&id).to_inner(), &ValueType::U8)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commitment::blake3::Blake3Commitment, fixtures};
+
+    #[test]
+    fn test_kind_id_roundtrip() {
+        for kind in EncodingSchemeKind::ALL {
+            assert_eq!(EncodingSchemeKind::from_id(kind.id()), Some(*kind));
+        }
+        assert_eq!(EncodingSchemeKind::from_id("kzg"), None);
+    }
+
+    #[test]
+    fn test_chacha_matches_encoder() {
+        let ranges = RangeSet::from(2..6);
+        let encodings = EncodingSchemeKind::ChaCha.encode(
+            fixtures::encoder_seed(),
+            Direction::Received,
+            &ranges,
+        );
+
+        // A commitment to the active encodings of the Prover opens against the recovered encodings.
+        let data = b"data".to_vec();
+        let active = get_value_ids(&ranges, Direction::Received)
+            .zip(&data)
+            .map(|(id, byte)| {
+                fixtures::encoder()
+                    .encode_by_type(EncodingId::new(&id).to_inner(), &ValueType::U8)
+                    .select(*byte)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let commitment = Blake3Commitment::new(&active);
+
+        assert_eq!(
+            commitment.open(data).recover(&encodings).hash(),
+            commitment.hash()
+        );
+    }
+}
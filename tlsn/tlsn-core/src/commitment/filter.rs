@@ -0,0 +1,110 @@
+use utils::range::{RangeDisjoint, RangeSet};
+
+use crate::range::find_all;
+
+/// A filter of secrets, e.g. tokens or passwords, which must never be committed to publicly.
+///
+/// A commitment which reveals the data it commits to when opened, e.g. a BLAKE3 commitment, is
+/// rejected if its ranges overlap any occurrence of a secret in the transcript. This is a safety
+/// net against redaction bugs in application code, which would otherwise commit to a secret and
+/// allow it to be revealed by mistake.
+///
+/// Commitments which do not reveal the data, e.g. plaintext hash commitments, are not filtered.
+///
+/// The secrets are only matched as literal bytes. A secret which appears in the transcript in
+/// another form, e.g. escaped in a JSON string, percent-encoded in a URL, base64-encoded in a
+/// header or compressed in a body, does not match, so its commitments are not rejected. The
+/// filter must be given each form in which a secret is sent or received.
+#[derive(Clone, Default)]
+pub struct CommitmentFilter {
+    patterns: Vec<Vec<u8>>,
+}
+
+impl std::fmt::Debug for CommitmentFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The patterns are secrets, so they are never printed.
+        f.debug_struct("CommitmentFilter")
+            .field("patterns", &self.patterns.len())
+            .finish()
+    }
+}
+
+impl CommitmentFilter {
+    /// Creates a new filter of the provided secrets.
+    ///
+    /// Empty secrets are ignored.
+    pub fn new<P: Into<Vec<u8>>>(patterns: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(Into::into)
+                .filter(|pattern: &Vec<u8>| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Adds a secret to the filter.
+    pub fn with_pattern(mut self, pattern: impl Into<Vec<u8>>) -> Self {
+        let pattern = pattern.into();
+        if !pattern.is_empty() {
+            self.patterns.push(pattern);
+        }
+        self
+    }
+
+    /// Returns `true` if the filter has no secrets.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the ranges of the occurrences of the secrets in the data which overlap the
+    /// provided ranges.
+    ///
+    /// A commitment to the ranges is allowed if the returned set is empty.
+    pub fn blocked(&self, data: &[u8], ranges: &RangeSet<usize>) -> RangeSet<usize> {
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| pattern.as_slice())
+            .collect::<Vec<_>>();
+
+        let blocked = find_all(data, &patterns)
+            .iter_ranges()
+            .filter(|occurrence| !ranges.is_disjoint(occurrence))
+            .collect::<Vec<_>>();
+
+        RangeSet::from(blocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocked() {
+        let data = b"GET / HTTP/1.1\r\nAuthorization: secret\r\nCookie: token\r\n\r\n";
+        let filter = CommitmentFilter::new([b"secret".as_slice(), b"token", b""]);
+
+        assert!(filter.blocked(data, &RangeSet::from(0..31)).is_empty());
+        assert!(filter
+            .blocked(data, &RangeSet::from([0..31, 37..47]))
+            .is_empty());
+
+        // A commitment revealing part of a secret is blocked.
+        assert_eq!(
+            filter.blocked(data, &RangeSet::from(0..33)),
+            RangeSet::from(31..37)
+        );
+        assert_eq!(
+            filter.blocked(data, &RangeSet::from(0..data.len())),
+            RangeSet::from([31..37, 47..52])
+        );
+    }
+
+    #[test]
+    fn test_debug_hides_patterns() {
+        let filter = CommitmentFilter::default().with_pattern("secret");
+        assert!(!format!("{filter:?}").contains("secret"));
+    }
+}
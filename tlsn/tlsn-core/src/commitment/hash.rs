@@ -0,0 +1,211 @@
+use mpz_core::{hash::Hash, utils::blake3};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utils::range::RangeSet;
+
+use crate::{
+    commitment::{Commitment, CommitmentInfo, CommitmentKind, CommitmentOpening, CommitmentScheme},
+    Direction, SessionHeader,
+};
+
+/// Length of the salt of a plaintext hash, in bytes.
+pub const SALT_LEN: usize = 16;
+
+/// Initial state of SHA-256, used by the circuit which proves a plaintext hash.
+pub const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Domain separator of the Merkle leaves of plaintext hash commitments.
+const PLAINTEXT_HASH_LEAF_DOMAIN: &[u8] = b"tlsn/plaintext-hash";
+
+/// Returns the salted SHA-256 hash of the plaintext, `SHA-256(salt || data)`.
+pub fn hash_plaintext(salt: &[u8; SALT_LEN], data: &[u8]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(data);
+
+    Hash::from(<[u8; 32]>::from(hasher.finalize()))
+}
+
+/// Returns the Merkle leaf of a plaintext hash of the provided ranges of a transcript.
+///
+/// Unlike the encodings of the transcript, the hash does not depend on the position of the data in
+/// the transcript, so the ranges are bound to the leaf explicitly.
+pub(crate) fn leaf(direction: Direction, ranges: &RangeSet<usize>, hash: &Hash) -> Hash {
+    let mut bytes = PLAINTEXT_HASH_LEAF_DOMAIN.to_vec();
+    bytes.push(match direction {
+        Direction::Sent => 0,
+        Direction::Received => 1,
+    });
+    let ranges = ranges.iter_ranges().collect::<Vec<_>>();
+    bytes.extend_from_slice(&(ranges.len() as u64).to_be_bytes());
+    for range in ranges {
+        bytes.extend_from_slice(&(range.start as u64).to_be_bytes());
+        bytes.extend_from_slice(&(range.end as u64).to_be_bytes());
+    }
+    bytes.extend_from_slice(hash.as_bytes());
+
+    Hash::from(blake3(&bytes))
+}
+
+/// A commitment to the salted SHA-256 hash of the substrings of a [`Transcript`](crate::Transcript).
+///
+/// The Notary checks the hash against the transcript during notarization, so a proof can reveal the
+/// hash instead of the plaintext. This allows a Verifier to match the plaintext against a known
+/// value, without learning it otherwise.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PlaintextHashCommitment {
+    hash: Hash,
+    salt: [u8; SALT_LEN],
+}
+
+opaque_debug::implement!(PlaintextHashCommitment);
+
+impl PlaintextHashCommitment {
+    /// Creates a new plaintext hash commitment
+    pub fn new(data: &[u8], salt: [u8; SALT_LEN]) -> Self {
+        Self {
+            hash: hash_plaintext(&salt, data),
+            salt,
+        }
+    }
+
+    /// Returns the salted hash of the plaintext
+    pub fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// Returns the salt of the hash
+    pub fn salt(&self) -> &[u8; SALT_LEN] {
+        &self.salt
+    }
+
+    /// Opens this commitment
+    pub fn open(&self) -> PlaintextHashOpening {
+        PlaintextHashOpening {
+            hash: self.hash,
+            salt: self.salt,
+        }
+    }
+}
+
+impl From<PlaintextHashCommitment> for Commitment {
+    fn from(value: PlaintextHashCommitment) -> Self {
+        Self::PlaintextHash(value)
+    }
+}
+
+/// An opening revealing the salted hash of the substrings, instead of the substrings.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlaintextHashOpening {
+    hash: Hash,
+    salt: [u8; SALT_LEN],
+}
+
+impl PlaintextHashOpening {
+    /// Recovers the expected commitment from this opening.
+    pub fn recover(&self) -> PlaintextHashCommitment {
+        PlaintextHashCommitment {
+            hash: self.hash,
+            salt: self.salt,
+        }
+    }
+
+    /// Returns the salted hash of the plaintext
+    pub fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// Returns the salt of the hash
+    pub fn salt(&self) -> &[u8; SALT_LEN] {
+        &self.salt
+    }
+
+    /// Returns `true` if the provided plaintext matches the hash.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        hash_plaintext(&self.salt, data) == self.hash
+    }
+}
+
+impl From<PlaintextHashOpening> for CommitmentOpening {
+    fn from(value: PlaintextHashOpening) -> Self {
+        Self::PlaintextHash(value)
+    }
+}
+
+/// The scheme of commitments to the salted SHA-256 hash of the transcript.
+#[derive(Debug, Clone, Copy)]
+pub struct PlaintextHashScheme;
+
+impl CommitmentScheme for PlaintextHashScheme {
+    const KIND: CommitmentKind = CommitmentKind::PlaintextHash;
+
+    type Commitment = PlaintextHashCommitment;
+    type Opening = PlaintextHashOpening;
+
+    fn recover_expected(
+        _header: &SessionHeader,
+        _info: &CommitmentInfo,
+        opening: &PlaintextHashOpening,
+    ) -> Option<PlaintextHashCommitment> {
+        // The opening reveals the hash rather than the data, so there is nothing to check it
+        // against here.
+        Some(opening.recover())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_plaintext() {
+        let commitment = PlaintextHashCommitment::new(b"secret", [1u8; SALT_LEN]);
+        let opening = commitment.open();
+
+        assert!(opening.matches(b"secret"));
+        assert!(!opening.matches(b"secreT"));
+        assert_ne!(
+            PlaintextHashCommitment::new(b"secret", [2u8; SALT_LEN]).hash(),
+            commitment.hash()
+        );
+    }
+
+    #[test]
+    fn test_recover_expected_dispatches_on_kind() {
+        let header = crate::fixtures::session_header([0u8; 32].into(), 6, 6);
+        let opening: CommitmentOpening = PlaintextHashCommitment::new(b"secret", [1u8; SALT_LEN])
+            .open()
+            .into();
+
+        let info = CommitmentInfo::new(
+            CommitmentKind::PlaintextHash,
+            RangeSet::from(0..6),
+            Direction::Sent,
+        );
+        assert!(opening.recover_expected(&header, &info).is_some());
+
+        // The opening is not of the kind of the commitment.
+        let info = CommitmentInfo::new(
+            CommitmentKind::Blake3,
+            RangeSet::from(0..6),
+            Direction::Sent,
+        );
+        assert!(opening.recover_expected(&header, &info).is_none());
+    }
+
+    #[test]
+    fn test_leaf_binds_ranges() {
+        let hash = hash_plaintext(&[0u8; SALT_LEN], b"secret");
+
+        assert_ne!(
+            leaf(Direction::Sent, &RangeSet::from(0..6), &hash),
+            leaf(Direction::Sent, &RangeSet::from(1..7), &hash)
+        );
+        assert_ne!(
+            leaf(Direction::Sent, &RangeSet::from(0..6), &hash),
+            leaf(Direction::Received, &RangeSet::from(0..6), &hash)
+        );
+    }
+}
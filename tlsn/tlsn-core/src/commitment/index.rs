@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+
+use mpz_core::hash::Hash;
+use serde::{Deserialize, Serialize};
+use utils::range::RangeSet;
+
+use crate::{
+    commitment::{CommitmentId, CommitmentKind, TranscriptCommitments},
+    merkle::MerkleRoot,
+    Direction,
+};
+
+/// An error for [`CommitmentIndex`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CommitmentIndexError {
+    /// The label is already used by another commitment.
+    #[error("label {0:?} is already used")]
+    DuplicateLabel(String),
+    /// The commitment of a label does not exist.
+    #[error("commitment of label {0:?} does not exist")]
+    UnknownCommitment(String),
+    /// The entry of a label does not match its commitment.
+    #[error("entry of label {0:?} does not match its commitment")]
+    Mismatch(String),
+    /// The commitment of a label is not included in the signed Merkle root.
+    #[error("commitment of label {0:?} is not included in the signed merkle root")]
+    NotSigned(String),
+}
+
+/// An entry of a [`CommitmentIndex`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// The id of the commitment.
+    pub id: CommitmentId,
+    /// The kind of the commitment.
+    pub kind: CommitmentKind,
+    /// The direction of the transcript which is committed to.
+    pub direction: Direction,
+    /// The ranges of the transcript which are committed to.
+    pub ranges: RangeSet<usize>,
+    /// The hash of the commitment.
+    pub hash: Hash,
+}
+
+/// An index of transcript commitments by label, e.g. `"response/0/body"`.
+///
+/// The index is stored alongside the commitments of a
+/// [`NotarizedSession`](crate::NotarizedSession), so applications can address the fields of a
+/// session by name rather than by commitment id. The index itself is not signed, so it must be
+/// validated against the signed Merkle root before it is relied upon, see
+/// [`validate`](Self::validate).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentIndex {
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+impl CommitmentIndex {
+    /// Creates a new, empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Labels a commitment.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label of the commitment.
+    /// * `id` - The id of the commitment.
+    /// * `commitments` - The commitments of the session.
+    pub fn insert(
+        &mut self,
+        label: impl Into<String>,
+        id: CommitmentId,
+        commitments: &TranscriptCommitments,
+    ) -> Result<(), CommitmentIndexError> {
+        let label = label.into();
+        if self.entries.contains_key(&label) {
+            return Err(CommitmentIndexError::DuplicateLabel(label));
+        }
+
+        let (Some(commitment), Some(info)) = (commitments.get(&id), commitments.get_info(&id))
+        else {
+            return Err(CommitmentIndexError::UnknownCommitment(label));
+        };
+
+        self.entries.insert(
+            label,
+            IndexEntry {
+                id,
+                kind: info.kind(),
+                direction: *info.direction(),
+                ranges: info.ranges().clone(),
+                hash: commitment.hash(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Returns the entry of a label.
+    pub fn get(&self, label: &str) -> Option<&IndexEntry> {
+        self.entries.get(label)
+    }
+
+    /// Returns `true` if the label is in the index.
+    pub fn contains(&self, label: &str) -> bool {
+        self.entries.contains_key(label)
+    }
+
+    /// Returns an iterator over the labels and their entries, sorted by label.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &IndexEntry)> {
+        self.entries
+            .iter()
+            .map(|(label, entry)| (label.as_str(), entry))
+    }
+
+    /// Returns the number of labels in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the index has no labels.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Validates the index against the commitments of a session and the Merkle root signed by
+    /// the Notary.
+    ///
+    /// Every entry must match its commitment, and the commitment must be a leaf of the tree with
+    /// the signed root.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitments` - The commitments of the session.
+    /// * `merkle_root` - The Merkle root of the signed session header.
+    pub fn validate(
+        &self,
+        commitments: &TranscriptCommitments,
+        merkle_root: &MerkleRoot,
+    ) -> Result<(), CommitmentIndexError> {
+        for (label, entry) in &self.entries {
+            let (Some(commitment), Some(info)) =
+                (commitments.get(&entry.id), commitments.get_info(&entry.id))
+            else {
+                return Err(CommitmentIndexError::UnknownCommitment(label.clone()));
+            };
+
+            if info.kind() != entry.kind
+                || *info.direction() != entry.direction
+                || *info.ranges() != entry.ranges
+                || commitment.hash() != entry.hash
+            {
+                return Err(CommitmentIndexError::Mismatch(label.clone()));
+            }
+
+            let index = entry.id.to_inner() as usize;
+            commitments
+                .merkle_tree()
+                .proof(&[index])
+                .verify(merkle_root, &[index], &[info.leaf(entry.hash)])
+                .map_err(|_| CommitmentIndexError::NotSigned(label.clone()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commitment::TranscriptCommitmentBuilder, fixtures::encoding_provider, Transcript};
+
+    fn commitments() -> (TranscriptCommitments, CommitmentId, CommitmentId) {
+        let sent = Transcript::new(b"GET / HTTP/1.1\r\n\r\n".to_vec());
+        let recv = Transcript::new(b"HTTP/1.1 200 OK\r\n\r\nhello".to_vec());
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            encoding_provider(sent.data(), recv.data()),
+            sent.data().len(),
+            recv.data().len(),
+        );
+        let target = builder.commit_sent(&(4..5)).unwrap();
+        let body = builder.commit_recv(&(19..24)).unwrap();
+
+        (builder.build().unwrap(), target, body)
+    }
+
+    #[test]
+    fn test_index() {
+        let (commitments, target, body) = commitments();
+
+        let mut index = CommitmentIndex::new();
+        index
+            .insert("request/0/target", target, &commitments)
+            .unwrap();
+        index.insert("response/0/body", body, &commitments).unwrap();
+
+        assert_eq!(
+            index.insert("response/0/body", target, &commitments),
+            Err(CommitmentIndexError::DuplicateLabel(
+                "response/0/body".to_string()
+            ))
+        );
+
+        let entry = index.get("response/0/body").unwrap();
+        assert_eq!(entry.id, body);
+        assert_eq!(entry.direction, Direction::Received);
+        assert_eq!(entry.ranges, RangeSet::from(19..24));
+
+        index
+            .validate(&commitments, &commitments.merkle_root())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_validate_rejects_tampered_index() {
+        let (commitments, target, body) = commitments();
+
+        let mut index = CommitmentIndex::new();
+        index.insert("response/0/body", body, &commitments).unwrap();
+
+        // The label points to a different commitment than the one it was created for.
+        let mut tampered = index.clone();
+        tampered.entries.get_mut("response/0/body").unwrap().id = target;
+        assert_eq!(
+            tampered.validate(&commitments, &commitments.merkle_root()),
+            Err(CommitmentIndexError::Mismatch(
+                "response/0/body".to_string()
+            ))
+        );
+
+        // The commitments are not the ones signed by the Notary.
+        assert_eq!(
+            index.validate(&commitments, &MerkleRoot::from([0; 32])),
+            Err(CommitmentIndexError::NotSigned(
+                "response/0/body".to_string()
+            ))
+        );
+    }
+}
@@ -3,6 +3,13 @@
 /// BLAKE3 commitments.
 pub mod blake3;
 mod builder;
+mod constraints;
+/// Encodings of the transcript which BLAKE3 commitments are computed over.
+pub mod encoding;
+mod filter;
+/// Salted plaintext hash commitments.
+pub mod hash;
+mod index;
 
 use std::collections::HashMap;
 
@@ -14,10 +21,13 @@ use utils::range::RangeSet;
 
 use crate::{
     merkle::{MerkleRoot, MerkleTree},
-    Direction,
+    Direction, SessionHeader,
 };
 
 pub use builder::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError};
+pub use constraints::{CommitmentConstraints, ConstraintContext, ConstraintError};
+pub use filter::CommitmentFilter;
+pub use index::{CommitmentIndex, CommitmentIndexError, IndexEntry};
 
 /// A commitment id.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -41,15 +51,17 @@ pub struct CommitmentInfo {
     pub(crate) kind: CommitmentKind,
     pub(crate) ranges: RangeSet<usize>,
     pub(crate) direction: Direction,
+    pub(crate) constraints: CommitmentConstraints,
 }
 
 impl CommitmentInfo {
-    /// Creates new commitment info.
+    /// Creates new commitment info without constraints.
     pub(crate) fn new(kind: CommitmentKind, ranges: RangeSet<usize>, direction: Direction) -> Self {
         Self {
             kind,
             ranges,
             direction,
+            constraints: CommitmentConstraints::default(),
         }
     }
 
@@ -67,6 +79,23 @@ impl CommitmentInfo {
     pub fn direction(&self) -> &Direction {
         &self.direction
     }
+
+    /// Returns the constraints of this commitment
+    pub fn constraints(&self) -> &CommitmentConstraints {
+        &self.constraints
+    }
+
+    /// Returns the Merkle leaf of a commitment with this info, given the hash of the commitment.
+    pub(crate) fn leaf(&self, commitment_hash: Hash) -> Hash {
+        let hash = match self.kind {
+            CommitmentKind::Blake3 => commitment_hash,
+            CommitmentKind::PlaintextHash => {
+                hash::leaf(self.direction, &self.ranges, &commitment_hash)
+            }
+        };
+
+        self.constraints.leaf(hash)
+    }
 }
 
 /// A commitment to some bytes in a transcript
@@ -75,6 +104,8 @@ impl CommitmentInfo {
 pub enum Commitment {
     /// A BLAKE3 commitment to encodings of the transcript.
     Blake3(blake3::Blake3Commitment),
+    /// A commitment to the salted SHA-256 hash of the transcript.
+    PlaintextHash(hash::PlaintextHashCommitment),
 }
 
 impl Commitment {
@@ -82,6 +113,7 @@ impl Commitment {
     pub fn hash(&self) -> Hash {
         match self {
             Commitment::Blake3(commitment) => *commitment.hash(),
+            Commitment::PlaintextHash(commitment) => *commitment.hash(),
         }
     }
 
@@ -89,6 +121,7 @@ impl Commitment {
     pub fn kind(&self) -> CommitmentKind {
         match self {
             Commitment::Blake3(_) => CommitmentKind::Blake3,
+            Commitment::PlaintextHash(_) => CommitmentKind::PlaintextHash,
         }
     }
 }
@@ -99,6 +132,36 @@ impl Commitment {
 pub enum CommitmentKind {
     /// A BLAKE3 commitment to encodings of the transcript.
     Blake3,
+    /// A commitment to the salted SHA-256 hash of the transcript, which is opened without revealing
+    /// the transcript.
+    PlaintextHash,
+}
+
+/// A scheme of transcript commitments.
+///
+/// Each scheme is identified by a [`CommitmentKind`], which is carried in the [`CommitmentInfo`]
+/// of every commitment of a proof. Verifiers dispatch the recovery of the commitment from its
+/// opening to the scheme of this kind, see [`CommitmentOpening::recover_expected`]. A new hash
+/// function is added as a new scheme with its own kind, which leaves the format of proofs using
+/// the other schemes unchanged.
+pub trait CommitmentScheme {
+    /// The kind identifying this scheme.
+    const KIND: CommitmentKind;
+
+    /// The commitment of this scheme.
+    type Commitment: Into<Commitment>;
+    /// The opening of this scheme.
+    type Opening: Into<CommitmentOpening>;
+
+    /// Recovers the commitment a verifier expects from an opening of the commitment with the
+    /// provided info, in a session with the provided header.
+    ///
+    /// Returns `None` if the opening does not match the info of the commitment.
+    fn recover_expected(
+        header: &SessionHeader,
+        info: &CommitmentInfo,
+        opening: &Self::Opening,
+    ) -> Option<Self::Commitment>;
 }
 
 /// An opening to a commitment to the transcript.
@@ -107,6 +170,8 @@ pub enum CommitmentKind {
 pub enum CommitmentOpening {
     /// An opening to a BLAKE3 commitment
     Blake3(blake3::Blake3Opening),
+    /// An opening to a plaintext hash commitment, revealing the hash instead of the data
+    PlaintextHash(hash::PlaintextHashOpening),
 }
 
 impl CommitmentOpening {
@@ -114,11 +179,14 @@ impl CommitmentOpening {
     pub fn kind(&self) -> CommitmentKind {
         match self {
             CommitmentOpening::Blake3(_) => CommitmentKind::Blake3,
+            CommitmentOpening::PlaintextHash(_) => CommitmentKind::PlaintextHash,
         }
     }
 
     /// Recovers the expected commitment from this opening.
     ///
+    /// The encodings are ignored by openings which do not reveal the transcript data.
+    ///
     /// # Panics
     ///
     /// Implementations may panic if the following conditions are not met:
@@ -128,20 +196,48 @@ impl CommitmentOpening {
     pub fn recover(&self, encodings: &[EncodedValue<Full>]) -> Commitment {
         match self {
             CommitmentOpening::Blake3(opening) => opening.recover(encodings).into(),
+            CommitmentOpening::PlaintextHash(opening) => opening.recover().into(),
+        }
+    }
+
+    /// Recovers the commitment a verifier expects from this opening of the commitment with the
+    /// provided info, with the scheme of the kind of the commitment.
+    ///
+    /// Returns `None` if the opening is not of the kind of the commitment, or does not match its
+    /// info.
+    pub fn recover_expected(
+        &self,
+        header: &SessionHeader,
+        info: &CommitmentInfo,
+    ) -> Option<Commitment> {
+        match (info.kind, self) {
+            (blake3::Blake3Scheme::KIND, CommitmentOpening::Blake3(opening)) => {
+                blake3::Blake3Scheme::recover_expected(header, info, opening).map(Into::into)
+            }
+            (hash::PlaintextHashScheme::KIND, CommitmentOpening::PlaintextHash(opening)) => {
+                hash::PlaintextHashScheme::recover_expected(header, info, opening).map(Into::into)
+            }
+            _ => None,
         }
     }
 
     /// Returns the transcript data corresponding to this opening
+    ///
+    /// Openings which do not reveal the transcript data return an empty slice.
     pub fn data(&self) -> &[u8] {
         match self {
             CommitmentOpening::Blake3(opening) => opening.data(),
+            CommitmentOpening::PlaintextHash(_) => &[],
         }
     }
 
     /// Returns the transcript data corresponding to this opening
+    ///
+    /// Openings which do not reveal the transcript data return an empty vector.
     pub fn into_data(self) -> Vec<u8> {
         match self {
             CommitmentOpening::Blake3(opening) => opening.into_data(),
+            CommitmentOpening::PlaintextHash(_) => Vec::new(),
         }
     }
 }
@@ -174,7 +270,7 @@ impl TranscriptCommitments {
         self.commitments.get(id)
     }
 
-    /// Returns the commitment id for a commitment with the given info, if it exists.
+    /// Returns the commitment id for an unconstrained commitment with the given info, if it exists.
     pub fn get_id_by_info(
         &self,
         kind: CommitmentKind,
@@ -190,4 +286,34 @@ impl TranscriptCommitments {
     pub fn get_info(&self, id: &CommitmentId) -> Option<&CommitmentInfo> {
         self.commitment_info.get_by_left(id)
     }
+
+    /// Returns an iterator over the ids of the commitments with their info, in no particular order.
+    pub fn iter_info(&self) -> impl Iterator<Item = (&CommitmentId, &CommitmentInfo)> {
+        self.commitment_info.iter()
+    }
+
+    /// Returns the plaintext hash commitments with their info, sorted by id.
+    pub fn plaintext_hashes(
+        &self,
+    ) -> Vec<(
+        CommitmentId,
+        &CommitmentInfo,
+        &hash::PlaintextHashCommitment,
+    )> {
+        let mut hashes = self
+            .commitments
+            .iter()
+            .filter_map(|(id, commitment)| match commitment {
+                Commitment::PlaintextHash(commitment) => Some((
+                    *id,
+                    self.get_info(id).expect("info exists if commitment exists"),
+                    commitment,
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        hashes.sort_by_key(|(id, _, _)| *id);
+
+        hashes
+    }
 }
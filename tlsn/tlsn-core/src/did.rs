@@ -0,0 +1,424 @@
+//! Decentralized identifiers (DIDs) of Notaries.
+//!
+//! A Notary can reference itself in the session header by a DID (see
+//! [`SessionHeader::notary_did`]), so that verifiers discover its public key by resolving the DID
+//! with a [`DidResolver`] instead of configuring the key beforehand. The DID may be a DID URL whose
+//! fragment selects one of the verification methods of the DID document, e.g. after the Notary
+//! rotated its key.
+//!
+//! Two DID methods are supported:
+//!
+//! - `did:key`, which encodes a P-256 public key in the DID itself, see [`KeyDidResolver`].
+//! - `did:web`, whose DID document is hosted on the web server of the Notary, see
+//!   [`WebDidResolver`].
+//!
+//! Resolving a DID only establishes which key the DID refers to: anyone can create a `did:key` DID
+//! of their own key and sign a header referencing it. Verifiers must therefore decide which
+//! Notaries they trust, and only resolve the DIDs of those, see [`DidResolver::resolve_header`].
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use serde::Deserialize;
+
+use crate::{NotaryPublicKey, SessionHeader};
+
+/// The multicodec prefix of compressed P-256 public keys, `p256-pub` (0x1200) as a varint.
+const P256_PUB_MULTICODEC: [u8; 2] = [0x80, 0x24];
+
+/// An error for [`DidResolver`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DidError {
+    /// The session header does not reference the Notary by a DID.
+    #[error("the session header does not reference the notary by a DID")]
+    MissingDid,
+    /// The DID is malformed.
+    #[error("invalid DID {0}")]
+    InvalidDid(String),
+    /// The DID method is not supported by the resolver.
+    #[error("unsupported DID method: {0}")]
+    UnsupportedMethod(String),
+    /// The DID document could not be fetched.
+    #[error("failed to fetch DID document: {0}")]
+    Fetch(String),
+    /// The DID document is malformed, or does not belong to the DID.
+    #[error("invalid DID document: {0}")]
+    InvalidDocument(String),
+    /// The DID document has no P-256 verification method for the DID URL.
+    #[error("the DID document has no P-256 verification method for {0}")]
+    MissingKey(String),
+    /// The DID does not identify a trusted Notary.
+    #[error("the DID {0} does not identify a trusted notary")]
+    Untrusted(String),
+}
+
+/// A resolver of the public keys of Notaries from their DIDs.
+pub trait DidResolver {
+    /// Resolves the public key the DID refers to.
+    fn resolve(&self, did: &str) -> Result<NotaryPublicKey, DidError>;
+
+    /// Resolves the public key of the Notary referenced by the session header, if it is one of
+    /// the trusted Notaries.
+    ///
+    /// The DID is taken from the header, which is not authenticated before its signature is
+    /// verified with the resolved key. It is only resolved if the DID, without the fragment
+    /// selecting one of its keys, is one of the trusted DIDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `trusted_dids` - The DIDs of the trusted Notaries.
+    fn resolve_header(
+        &self,
+        header: &SessionHeader,
+        trusted_dids: &[&str],
+    ) -> Result<NotaryPublicKey, DidError> {
+        let did_url = header.notary_did().ok_or(DidError::MissingDid)?;
+
+        let (did, _) = split_fragment(did_url);
+        if !trusted_dids.contains(&did) {
+            return Err(DidError::Untrusted(did.to_string()));
+        }
+
+        self.resolve(did_url)
+    }
+}
+
+/// A resolver of `did:key` DIDs of P-256 keys.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KeyDidResolver;
+
+impl DidResolver for KeyDidResolver {
+    fn resolve(&self, did: &str) -> Result<NotaryPublicKey, DidError> {
+        let (did, _) = split_fragment(did);
+        let key = did
+            .strip_prefix("did:key:")
+            .ok_or_else(|| DidError::UnsupportedMethod(did.to_string()))?;
+
+        decode_multibase_key(key)
+            .map(NotaryPublicKey::P256)
+            .ok_or_else(|| DidError::InvalidDid(did.to_string()))
+    }
+}
+
+/// A resolver of `did:web` DIDs, which fetches DID documents with the provided fetcher.
+///
+/// The fetcher is given the HTTPS URL of the DID document and returns its contents. It is
+/// responsible for the authenticity of the document, i.e. for validating the certificate of the
+/// web server.
+pub struct WebDidResolver<F> {
+    fetch: F,
+}
+
+impl<F> WebDidResolver<F>
+where
+    F: Fn(&str) -> Result<Vec<u8>, String>,
+{
+    /// Creates a new resolver with the provided fetcher of DID documents.
+    pub fn new(fetch: F) -> Self {
+        Self { fetch }
+    }
+}
+
+impl<F> DidResolver for WebDidResolver<F>
+where
+    F: Fn(&str) -> Result<Vec<u8>, String>,
+{
+    fn resolve(&self, did_url: &str) -> Result<NotaryPublicKey, DidError> {
+        let (did, fragment) = split_fragment(did_url);
+        let url = did_web_document_url(did)?;
+
+        let document = (self.fetch)(&url).map_err(DidError::Fetch)?;
+        let document: DidDocument = serde_json::from_slice(&document)
+            .map_err(|err| DidError::InvalidDocument(err.to_string()))?;
+
+        document.key(did, fragment).map(NotaryPublicKey::P256)
+    }
+}
+
+/// A resolver which dispatches DIDs to the resolver of their method.
+pub struct AnyDidResolver<F> {
+    web: WebDidResolver<F>,
+}
+
+impl<F> AnyDidResolver<F>
+where
+    F: Fn(&str) -> Result<Vec<u8>, String>,
+{
+    /// Creates a new resolver of `did:key` and `did:web` DIDs, which fetches `did:web` documents
+    /// with the provided fetcher.
+    pub fn new(fetch: F) -> Self {
+        Self {
+            web: WebDidResolver::new(fetch),
+        }
+    }
+}
+
+impl<F> DidResolver for AnyDidResolver<F>
+where
+    F: Fn(&str) -> Result<Vec<u8>, String>,
+{
+    fn resolve(&self, did: &str) -> Result<NotaryPublicKey, DidError> {
+        if did.starts_with("did:key:") {
+            KeyDidResolver.resolve(did)
+        } else {
+            self.web.resolve(did)
+        }
+    }
+}
+
+/// Returns the `did:key` DID of a P-256 public key.
+pub fn did_key(public_key: &p256::PublicKey) -> String {
+    format!("did:key:{}", encode_multibase_key(public_key))
+}
+
+/// Returns the HTTPS URL of the DID document of a `did:web` DID.
+pub fn did_web_document_url(did: &str) -> Result<String, DidError> {
+    let invalid = || DidError::InvalidDid(did.to_string());
+
+    let id = did
+        .strip_prefix("did:web:")
+        .ok_or_else(|| DidError::UnsupportedMethod(did.to_string()))?;
+
+    let mut segments = id.split(':');
+    let host = segments
+        .next()
+        .filter(|host| !host.is_empty())
+        .ok_or_else(invalid)?;
+    // A port is percent-encoded, as colons separate the path segments.
+    let host = host.replace("%3A", ":").replace("%3a", ":");
+    if host.contains(['/', '%', '?', '#', '@']) {
+        return Err(invalid());
+    }
+
+    let path = segments.collect::<Vec<_>>();
+    if path.iter().any(|segment| {
+        segment.is_empty() || *segment == "." || *segment == ".." || segment.contains('/')
+    }) {
+        return Err(invalid());
+    }
+
+    Ok(if path.is_empty() {
+        format!("https://{host}/.well-known/did.json")
+    } else {
+        format!("https://{host}/{}/did.json", path.join("/"))
+    })
+}
+
+/// Splits a DID URL into its DID and fragment.
+fn split_fragment(did_url: &str) -> (&str, Option<&str>) {
+    match did_url.split_once('#') {
+        Some((did, fragment)) => (did, Some(fragment)),
+        None => (did_url, None),
+    }
+}
+
+fn did_url(did: &str, fragment: Option<&str>) -> String {
+    match fragment {
+        Some(fragment) => format!("{did}#{fragment}"),
+        None => did.to_string(),
+    }
+}
+
+/// A DID document, restricted to the properties needed to resolve a P-256 key.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidDocument {
+    id: String,
+    #[serde(default)]
+    verification_method: Vec<VerificationMethod>,
+    #[serde(default)]
+    assertion_method: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMethod {
+    id: String,
+    public_key_jwk: Option<Jwk>,
+    public_key_multibase: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+    y: String,
+}
+
+impl DidDocument {
+    /// Returns the key of the verification method selected by the fragment, or of the first
+    /// assertion method if there is no fragment.
+    fn key(&self, did: &str, fragment: Option<&str>) -> Result<p256::PublicKey, DidError> {
+        if self.id != did {
+            return Err(DidError::InvalidDocument(format!(
+                "the document belongs to {}",
+                self.id
+            )));
+        }
+
+        // Verification methods can be referenced by their absolute or relative id.
+        let matches = |method: &VerificationMethod, fragment: &str| {
+            method.id == format!("{did}#{fragment}") || method.id == format!("#{fragment}")
+        };
+
+        let method = match fragment {
+            Some(fragment) => self
+                .verification_method
+                .iter()
+                .find(|method| matches(method, fragment)),
+            None => self
+                .assertion_method
+                .iter()
+                .filter_map(|method| method.as_str())
+                .filter_map(|reference| reference.rsplit_once('#'))
+                .find_map(|(_, fragment)| {
+                    self.verification_method
+                        .iter()
+                        .find(|method| matches(method, fragment))
+                }),
+        }
+        .ok_or_else(|| DidError::MissingKey(did_url(did, fragment)))?;
+
+        method
+            .key()
+            .ok_or_else(|| DidError::MissingKey(did_url(did, fragment)))
+    }
+}
+
+impl VerificationMethod {
+    fn key(&self) -> Option<p256::PublicKey> {
+        if let Some(jwk) = &self.public_key_jwk {
+            if jwk.kty != "EC" || jwk.crv != "P-256" {
+                return None;
+            }
+
+            let mut point = vec![0x04];
+            point.extend(BASE64URL.decode(&jwk.x).ok().filter(|x| x.len() == 32)?);
+            point.extend(BASE64URL.decode(&jwk.y).ok().filter(|y| y.len() == 32)?);
+
+            return p256::PublicKey::from_sec1_bytes(&point).ok();
+        }
+
+        decode_multibase_key(self.public_key_multibase.as_deref()?)
+    }
+}
+
+/// Encodes a P-256 key as a base58btc multibase string of its multicodec encoding.
+fn encode_multibase_key(public_key: &p256::PublicKey) -> String {
+    let mut bytes = P256_PUB_MULTICODEC.to_vec();
+    bytes.extend_from_slice(public_key.to_encoded_point(true).as_bytes());
+
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+/// Decodes a P-256 key from a base58btc multibase string of its multicodec encoding.
+fn decode_multibase_key(key: &str) -> Option<p256::PublicKey> {
+    let bytes = bs58::decode(key.strip_prefix('z')?).into_vec().ok()?;
+    let point = bytes.strip_prefix(P256_PUB_MULTICODEC.as_slice())?;
+
+    p256::PublicKey::from_sec1_bytes(point).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{notary_signing_key, session_header};
+
+    #[test]
+    fn test_did_key() {
+        let public_key = p256::PublicKey::from(notary_signing_key().verifying_key());
+        let did = did_key(&public_key);
+        assert!(did.starts_with("did:key:zDn"));
+
+        let resolved = KeyDidResolver.resolve(&did).unwrap();
+        assert!(matches!(resolved, NotaryPublicKey::P256(key) if key == public_key));
+        assert!(KeyDidResolver.resolve("did:key:zQ3s").is_err());
+        assert!(KeyDidResolver.resolve("did:key:z0OIl").is_err());
+    }
+
+    #[test]
+    fn test_resolve_header_only_trusted() {
+        let public_key = p256::PublicKey::from(notary_signing_key().verifying_key());
+        let did = did_key(&public_key);
+        let header = session_header([0; 32].into(), 100, 100).with_notary_did(format!("{did}#0"));
+
+        let resolved = KeyDidResolver
+            .resolve_header(&header, &[did.as_str()])
+            .unwrap();
+        assert!(matches!(resolved, NotaryPublicKey::P256(key) if key == public_key));
+
+        // Anyone can sign a header with the key of its own DID.
+        let other = did_key(&p256::PublicKey::from(
+            p256::ecdsa::SigningKey::from_slice(&[2; 32])
+                .unwrap()
+                .verifying_key(),
+        ));
+        assert!(matches!(
+            KeyDidResolver.resolve_header(&header, &[other.as_str()]),
+            Err(DidError::Untrusted(untrusted)) if untrusted == did
+        ));
+        assert!(matches!(
+            KeyDidResolver
+                .resolve_header(&session_header([0; 32].into(), 100, 100), &[did.as_str()]),
+            Err(DidError::MissingDid)
+        ));
+    }
+
+    #[test]
+    fn test_did_web() {
+        let public_key = p256::PublicKey::from(notary_signing_key().verifying_key());
+        let point = public_key.to_encoded_point(false);
+        let document = serde_json::json!({
+            "id": "did:web:notary.example%3A8443:tlsn",
+            "verificationMethod": [
+                {
+                    "id": "did:web:notary.example%3A8443:tlsn#notary-1",
+                    "type": "JsonWebKey2020",
+                    "publicKeyJwk": {
+                        "kty": "EC",
+                        "crv": "P-256",
+                        "x": BASE64URL.encode(point.x().unwrap()),
+                        "y": BASE64URL.encode(point.y().unwrap()),
+                    },
+                },
+                {
+                    "id": "#notary-2",
+                    "type": "Multikey",
+                    "publicKeyMultibase": encode_multibase_key(&public_key),
+                },
+            ],
+            "assertionMethod": ["did:web:notary.example%3A8443:tlsn#notary-2"],
+        })
+        .to_string();
+
+        let resolver = WebDidResolver::new(|_: &str| Ok(document.as_bytes().to_vec()));
+
+        for did in [
+            "did:web:notary.example%3A8443:tlsn",
+            "did:web:notary.example%3A8443:tlsn#notary-1",
+        ] {
+            let resolved = resolver.resolve(did).unwrap();
+            assert!(matches!(resolved, NotaryPublicKey::P256(key) if key == public_key));
+        }
+
+        assert!(matches!(
+            resolver.resolve("did:web:notary.example%3A8443:tlsn#notary-3"),
+            Err(DidError::MissingKey(_))
+        ));
+        assert!(matches!(
+            resolver.resolve("did:web:notary.example%3A8443:other"),
+            Err(DidError::InvalidDocument(_))
+        ));
+        assert_eq!(
+            did_web_document_url("did:web:notary.example%3A8443:tlsn").unwrap(),
+            "https://notary.example:8443/tlsn/did.json"
+        );
+        assert_eq!(
+            did_web_document_url("did:web:notary.example").unwrap(),
+            "https://notary.example/.well-known/did.json"
+        );
+        assert!(did_web_document_url("did:web:notary.example:..").is_err());
+    }
+}
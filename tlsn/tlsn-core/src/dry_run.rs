@@ -0,0 +1,198 @@
+//! Dry runs of a notarization.
+//!
+//! A [`DryRun`] plays both the Prover and the Notary in one process against a fake transcript,
+//! without any networking or MPC. It produces a [`NotarizedSession`] with the same structure as a
+//! notarized one, so applications can build and test their commitment and redaction logic and
+//! their verifier pipelines.
+//!
+//! A dry run proves nothing: the handshake is the one of the [`fixtures`](crate::fixtures) for
+//! `tlsnotary.org`, the encodings are derived from a fixed seed and the header is signed with the
+//! publicly known [`DRY_RUN_NOTARY_KEY`]. The header is marked as a dry run, see
+//! [`SessionHeader::is_dry_run`], and only verifies against [`dry_run_notary_public_key`].
+
+use mpz_core::commit::HashCommit;
+use p256::ecdsa::{signature::Signer, Signature as P256Signature, SigningKey};
+use tls_core::{
+    cert::ServerCertDetails, handshake::HandshakeData, ke::ServerKxDetails,
+    msgs::handshake::DigitallySignedStruct,
+};
+
+use crate::{
+    commitment::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    fixtures,
+    msg::PlaintextHashes,
+    HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader, Transcript,
+};
+
+/// Secret key of the notary of dry runs, which is publicly known.
+pub const DRY_RUN_NOTARY_KEY: [u8; 32] = [0xd7; 32];
+
+/// Returns the signing key of the notary of dry runs.
+pub fn dry_run_notary_key() -> SigningKey {
+    SigningKey::from_bytes(&DRY_RUN_NOTARY_KEY.into()).expect("dry run notary key is valid")
+}
+
+/// Returns the public key of the notary of dry runs, which dry run sessions verify against.
+pub fn dry_run_notary_public_key() -> p256::PublicKey {
+    p256::PublicKey::from(*dry_run_notary_key().verifying_key())
+}
+
+/// An error that can occur during a dry run.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DryRunError {
+    /// The commitments of the transcript could not be built.
+    #[error(transparent)]
+    Commitment(#[from] TranscriptCommitmentBuilderError),
+}
+
+/// A dry run of a notarization against a fake transcript.
+pub struct DryRun {
+    sent: Transcript,
+    recv: Transcript,
+    builder: TranscriptCommitmentBuilder,
+}
+
+opaque_debug::implement!(DryRun);
+
+impl DryRun {
+    /// Creates a new dry run of a session with the provided transcripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `sent` - The data sent to the fake server.
+    /// * `recv` - The data received from the fake server.
+    pub fn new(sent: impl Into<Vec<u8>>, recv: impl Into<Vec<u8>>) -> Self {
+        let (sent, recv) = (sent.into(), recv.into());
+
+        let builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&sent, &recv),
+            sent.len(),
+            recv.len(),
+        )
+        .with_plaintext(sent.clone(), recv.clone());
+
+        Self {
+            sent: Transcript::new(sent),
+            recv: Transcript::new(recv),
+            builder,
+        }
+    }
+
+    /// Returns the transcript of the data sent to the fake server.
+    pub fn sent_transcript(&self) -> &Transcript {
+        &self.sent
+    }
+
+    /// Returns the transcript of the data received from the fake server.
+    pub fn recv_transcript(&self) -> &Transcript {
+        &self.recv
+    }
+
+    /// Returns the transcript commitment builder.
+    pub fn commitment_builder(&mut self) -> &mut TranscriptCommitmentBuilder {
+        &mut self.builder
+    }
+
+    /// Finalizes the dry run, returning a [`NotarizedSession`] signed by the dry run notary.
+    pub fn finalize(self) -> Result<NotarizedSession, DryRunError> {
+        let Self {
+            sent,
+            recv,
+            builder,
+        } = self;
+
+        let commitments = builder.build()?;
+
+        let testdata = fixtures::cert::tlsnotary();
+        let handshake_data = HandshakeData::new(
+            ServerCertDetails::new(
+                vec![
+                    testdata.ee.clone(),
+                    testdata.inter.clone(),
+                    testdata.ca.clone(),
+                ],
+                vec![],
+                None,
+            ),
+            ServerKxDetails::new(
+                testdata.kx_params(),
+                DigitallySignedStruct::new(testdata.sig_scheme, testdata.sig.clone()),
+            ),
+            testdata.cr,
+            testdata.sr,
+        );
+        let (handshake_decommitment, handshake_commitment) = handshake_data.hash_commit();
+
+        let header = SessionHeader::new(
+            fixtures::encoder_seed(),
+            commitments.merkle_root(),
+            sent.data().len(),
+            recv.data().len(),
+            HandshakeSummary::new(testdata.time, testdata.pubkey, handshake_commitment),
+        )
+        .with_plaintext_hashes(PlaintextHashes::new(&commitments).leaves())
+        .with_dry_run();
+
+        let signature: P256Signature = dry_run_notary_key().sign(&header.to_bytes());
+
+        let data = SessionData::new(
+            ServerName::Dns(testdata.dns_name),
+            handshake_decommitment,
+            sent,
+            recv,
+            commitments,
+        );
+
+        Ok(NotarizedSession::new(header, Some(signature.into()), data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures::notary_signing_key, Direction};
+
+    #[test]
+    fn test_dry_run() {
+        let mut dry_run = DryRun::new(b"GET / HTTP/1.1".to_vec(), b"HTTP/1.1 200 OK".to_vec());
+        let sent_id = dry_run.commitment_builder().commit_sent(&(0..3)).unwrap();
+        let recv_id = dry_run.commitment_builder().commit_recv(&(9..12)).unwrap();
+        let hash_id = dry_run
+            .commitment_builder()
+            .commit_hash(&(0..8), Direction::Received)
+            .unwrap();
+
+        let session = dry_run.finalize().unwrap();
+        assert!(session.header().is_dry_run());
+
+        let session_proof = session.session_proof();
+        session_proof
+            .verify_with_default_cert_verifier(dry_run_notary_public_key())
+            .unwrap();
+
+        // A dry run never verifies against another notary.
+        let other_key = p256::PublicKey::from(*notary_signing_key().verifying_key());
+        assert!(session_proof
+            .verify_with_default_cert_verifier(other_key)
+            .is_err());
+
+        let mut builder = session.data().build_substrings_proof();
+        builder
+            .reveal_by_id(sent_id)
+            .unwrap()
+            .reveal_by_id(recv_id)
+            .unwrap()
+            .reveal_by_id(hash_id)
+            .unwrap();
+        let (sent, recv, hashes) = builder
+            .build()
+            .unwrap()
+            .verify_with_hashes(session.header(), &Default::default())
+            .unwrap();
+
+        assert_eq!(&sent.data()[0..3], b"GET");
+        assert_eq!(&recv.data()[9..12], b"200");
+        assert!(hashes[0].matches(b"HTTP/1.1"));
+    }
+}
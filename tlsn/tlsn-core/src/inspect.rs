@@ -0,0 +1,312 @@
+//! Rendering of notarized sessions as annotated text, for inspection and debugging.
+//!
+//! A [`SessionPrinter`] renders both transcripts of a session line by line, prefixed with the
+//! direction and the offset of the line, shading the ranges which are not revealed and labelling
+//! the ranges of the commitments inline. This makes mistakes in the selection of ranges, e.g. an
+//! off-by-one which reveals the first byte of a secret, visible at a glance.
+//!
+//! ```text
+//! > sent (35 bytes, 16 revealed)
+//! > 000000 | [#0GET / HTTP/1.1\r\n#0]
+//! > 000016 | ░░░░░░░░░░░░░░░\r\n
+//! > 000033 | \r\n
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+};
+
+use utils::range::{RangeSet, RangeUnion};
+
+use crate::{
+    commitment::{Commitment, CommitmentId},
+    proof::SubstringsProof,
+    Direction, SessionData,
+};
+
+/// The character which replaces the bytes which are not revealed in [`PrintStyle::Plain`].
+pub const REDACTED_CHAR: char = '░';
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_REDACTED: &str = "\x1b[2;7m";
+const ANSI_LABEL: &str = "\x1b[36m";
+
+/// The style of the text rendered by a [`SessionPrinter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrintStyle {
+    /// Plain text, where the bytes which are not revealed are replaced by [`REDACTED_CHAR`].
+    #[default]
+    Plain,
+    /// Text with ANSI escape codes for terminals, where the bytes which are not revealed are
+    /// shaded, so their content remains visible.
+    Ansi,
+}
+
+/// Renders a notarized session as annotated text.
+///
+/// All the data is considered redacted until it is revealed with [`reveal`](Self::reveal),
+/// [`reveal_commitment`](Self::reveal_commitment) or [`reveal_proof`](Self::reveal_proof).
+#[derive(Debug, Clone)]
+pub struct SessionPrinter<'a> {
+    data: &'a SessionData,
+    sent_revealed: RangeSet<usize>,
+    recv_revealed: RangeSet<usize>,
+    style: PrintStyle,
+    show_commitments: bool,
+}
+
+impl<'a> SessionPrinter<'a> {
+    /// Creates a new printer for the provided session.
+    pub fn new(data: &'a SessionData) -> Self {
+        Self {
+            data,
+            sent_revealed: RangeSet::default(),
+            recv_revealed: RangeSet::default(),
+            style: PrintStyle::default(),
+            show_commitments: true,
+        }
+    }
+
+    /// Sets the style of the text.
+    pub fn style(mut self, style: PrintStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets whether the ranges of the commitments are labelled, which is the default.
+    pub fn show_commitments(mut self, show_commitments: bool) -> Self {
+        self.show_commitments = show_commitments;
+        self
+    }
+
+    /// Marks the provided ranges of the transcript as revealed.
+    pub fn reveal(mut self, direction: Direction, ranges: &RangeSet<usize>) -> Self {
+        let revealed = match direction {
+            Direction::Sent => &mut self.sent_revealed,
+            Direction::Received => &mut self.recv_revealed,
+        };
+        *revealed = revealed.union(ranges);
+        self
+    }
+
+    /// Marks the ranges of the provided commitment as revealed.
+    ///
+    /// Commitments which do not reveal the data when opened, e.g. plaintext hashes, and unknown
+    /// commitments are ignored.
+    pub fn reveal_commitment(self, id: CommitmentId) -> Self {
+        let commitments = self.data.commitments();
+        match (commitments.get(&id), commitments.get_info(&id)) {
+            (Some(Commitment::Blake3(_)), Some(info)) => {
+                let (direction, ranges) = (*info.direction(), info.ranges().clone());
+                self.reveal(direction, &ranges)
+            }
+            _ => self,
+        }
+    }
+
+    /// Marks the ranges opened by the provided proof as revealed.
+    pub fn reveal_proof(self, proof: &SubstringsProof) -> Self {
+        let ids = proof.opened().map(|(id, _)| *id).collect::<Vec<_>>();
+        ids.into_iter()
+            .fold(self, |printer, id| printer.reveal_commitment(id))
+    }
+
+    /// Renders the session.
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+
+    fn render_direction(&self, f: &mut fmt::Formatter<'_>, direction: Direction) -> fmt::Result {
+        let (data, revealed, marker, name) = match direction {
+            Direction::Sent => (
+                self.data.sent_transcript().data(),
+                &self.sent_revealed,
+                '>',
+                "sent",
+            ),
+            Direction::Received => (
+                self.data.recv_transcript().data(),
+                &self.recv_revealed,
+                '<',
+                "received",
+            ),
+        };
+
+        let mut is_revealed = vec![false; data.len()];
+        for range in revealed.iter_ranges() {
+            let end = range.end.min(data.len());
+            if range.start < end {
+                is_revealed[range.start..end].fill(true);
+            }
+        }
+
+        // The labels opening and closing at each offset.
+        let mut opens = BTreeMap::<usize, Vec<u32>>::new();
+        let mut closes = BTreeMap::<usize, Vec<u32>>::new();
+        if self.show_commitments {
+            let mut commitments = self
+                .data
+                .commitments()
+                .iter_info()
+                .filter(|(_, info)| *info.direction() == direction)
+                .collect::<Vec<_>>();
+            commitments.sort_by_key(|(id, _)| **id);
+
+            for (id, info) in commitments {
+                for range in info.ranges().iter_ranges() {
+                    opens.entry(range.start).or_default().push(id.to_inner());
+                    closes
+                        .entry(range.end)
+                        .or_default()
+                        .insert(0, id.to_inner());
+                }
+            }
+        }
+
+        writeln!(
+            f,
+            "{marker} {name} ({} bytes, {} revealed)",
+            data.len(),
+            is_revealed.iter().filter(|revealed| **revealed).count()
+        )?;
+
+        let mut line_start = true;
+        for (offset, byte) in data.iter().enumerate() {
+            if line_start {
+                write!(f, "{marker} {offset:06} | ")?;
+                line_start = false;
+            }
+
+            for id in opens.get(&offset).into_iter().flatten() {
+                self.write_label(f, &format!("[#{id}"))?;
+            }
+
+            self.write_byte(f, *byte, is_revealed[offset])?;
+
+            for id in closes.get(&(offset + 1)).into_iter().flatten() {
+                self.write_label(f, &format!("#{id}]"))?;
+            }
+
+            if *byte == b'\n' {
+                f.write_char('\n')?;
+                line_start = true;
+            }
+        }
+
+        if !line_start {
+            f.write_char('\n')?;
+        }
+
+        Ok(())
+    }
+
+    fn write_label(&self, f: &mut fmt::Formatter<'_>, label: &str) -> fmt::Result {
+        match self.style {
+            PrintStyle::Plain => f.write_str(label),
+            PrintStyle::Ansi => write!(f, "{ANSI_LABEL}{label}{ANSI_RESET}"),
+        }
+    }
+
+    fn write_byte(&self, f: &mut fmt::Formatter<'_>, byte: u8, revealed: bool) -> fmt::Result {
+        let escaped = escape(byte);
+        match (self.style, revealed) {
+            (_, true) => f.write_str(&escaped),
+            (PrintStyle::Plain, false) => {
+                // Line endings are kept, so the layout of the transcript is preserved.
+                if matches!(byte, b'\r' | b'\n') {
+                    f.write_str(&escaped)
+                } else {
+                    (0..escaped.len()).try_for_each(|_| f.write_char(REDACTED_CHAR))
+                }
+            }
+            (PrintStyle::Ansi, false) => write!(f, "{ANSI_REDACTED}{escaped}{ANSI_RESET}"),
+        }
+    }
+}
+
+impl fmt::Display for SessionPrinter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render_direction(f, Direction::Sent)?;
+        self.render_direction(f, Direction::Received)
+    }
+}
+
+/// Escapes a byte which is not printable ASCII.
+fn escape(byte: u8) -> String {
+    match byte {
+        b'\r' => "\\r".to_string(),
+        b'\n' => "\\n".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\\' => "\\\\".to_string(),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("\\x{byte:02x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures, ServerName, Transcript,
+    };
+    use mpz_core::commit::HashCommit;
+
+    const SENT: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    const RECV: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n\x00";
+
+    fn session_data() -> SessionData {
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(SENT, RECV),
+            SENT.len(),
+            RECV.len(),
+        );
+        builder.commit_sent(&(0..16)).unwrap();
+        builder.commit_recv(&(9..12)).unwrap();
+        let (decommitment, _) = fixtures::handshake_data().hash_commit();
+
+        SessionData::new(
+            ServerName::Dns("localhost".to_string()),
+            decommitment,
+            Transcript::new(SENT),
+            Transcript::new(RECV),
+            builder.build().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_session_printer() {
+        let data = session_data();
+        let id = data
+            .commitments()
+            .get_id_by_info(CommitmentKind::Blake3, &(0..16).into(), Direction::Sent)
+            .unwrap();
+
+        let rendered = SessionPrinter::new(&data)
+            .reveal_commitment(id)
+            .reveal(Direction::Received, &(0..8).into())
+            .render();
+
+        let expected = "\
+> sent (35 bytes, 16 revealed)
+> 000000 | [#0GET / HTTP/1.1\\r\\n#0]
+> 000016 | ░░░░░░░░░░░░░░░\\r\\n
+> 000033 | \\r\\n
+< received (20 bytes, 8 revealed)
+< 000000 | HTTP/1.1░[#1░░░#1]░░░\\r\\n
+< 000017 | \\r\\n
+< 000019 | ░░░░
+";
+        assert_eq!(rendered, expected);
+
+        let plain = SessionPrinter::new(&data)
+            .show_commitments(false)
+            .reveal(Direction::Sent, &(0..35).into())
+            .render();
+        assert!(plain.contains("> 000016 | Host: localhost\\r\\n\n"));
+
+        let ansi = SessionPrinter::new(&data).style(PrintStyle::Ansi).render();
+        assert!(ansi.contains(&format!("{ANSI_REDACTED}G{ANSI_RESET}")));
+    }
+}
@@ -0,0 +1,986 @@
+//! Canonical JSON encoding of sessions and proofs.
+//!
+//! The canonical encoding gives every value a single JSON representation, so proofs can be
+//! exchanged with and verified by other language ecosystems, e.g. browser verifiers, and hashed or
+//! compared byte-for-byte:
+//!
+//! - Objects have their keys sorted by their UTF-8 bytes and contain no duplicates.
+//! - No insignificant whitespace is emitted.
+//! - Integers are formatted in decimal, without sign for non-negative values, leading zeros or
+//!   exponent. Floating point numbers are not supported.
+//! - Byte strings, including sequences of `u8`, are encoded as standard base64 strings with
+//!   padding. Empty sequences are encoded as `[]`.
+//! - Strings escape `"`, `\` and control characters only, using the short escapes `\b`, `\t`,
+//!   `\n`, `\f` and `\r` where possible and `\u00xx` otherwise.
+//! - Map keys which are integers are encoded as strings.
+//! - Enums use the externally tagged representation, i.e. `"Variant"` for unit variants and
+//!   `{"Variant":value}` otherwise.
+//!
+//! The parser is strict: it rejects any input which is not the canonical encoding of the value it
+//! decodes to.
+
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{
+    de::{
+        self, value::SeqDeserializer, DeserializeOwned, DeserializeSeed, EnumAccess,
+        IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+    },
+    ser::{self, Impossible},
+    Serialize,
+};
+
+/// An error for the canonical JSON encoding.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CanonicalJsonError {
+    /// The input is not valid JSON.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The input is valid JSON, but not in canonical form.
+    #[error("JSON is not in canonical form")]
+    NotCanonical,
+    /// Floating point numbers are not supported.
+    #[error("floating point numbers are not supported")]
+    Float,
+    /// A map key is neither a string nor an integer.
+    #[error("map keys must be strings or integers")]
+    InvalidKey,
+    /// A byte string is not valid base64.
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    /// A custom error from the (de)serialized type.
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl ser::Error for CanonicalJsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl de::Error for CanonicalJsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// Encodes a value as canonical JSON.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String, CanonicalJsonError> {
+    let value = value.serialize(ValueSerializer)?;
+    let mut out = String::new();
+    write_value(&value, &mut out);
+
+    Ok(out)
+}
+
+/// Decodes a value from canonical JSON, rejecting any input which is not in canonical form.
+pub fn from_canonical_json<T>(json: &str) -> Result<T, CanonicalJsonError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let decoded = T::deserialize(ValueDeserializer(value))?;
+
+    // The encoding of each value is unique, so the input is canonical if and only if it is the
+    // encoding of the decoded value.
+    if to_canonical_json(&decoded)? != json {
+        return Err(CanonicalJsonError::NotCanonical);
+    }
+
+    Ok(decoded)
+}
+
+/// A JSON value prior to encoding.
+enum Value {
+    Null,
+    Bool(bool),
+    /// A `u8`, which is encoded as part of a byte string if all the elements of a sequence are.
+    Byte(u8),
+    Unsigned(u64),
+    Signed(i64),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Byte(n) => out.push_str(&n.to_string()),
+        Value::Unsigned(n) => out.push_str(&n.to_string()),
+        Value::Signed(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(s, out),
+        Value::Bytes(bytes) => write_string(&BASE64.encode(bytes), out),
+        Value::Array(values) => {
+            if !values.is_empty() && values.iter().all(|v| matches!(v, Value::Byte(_))) {
+                let bytes: Vec<u8> = values
+                    .iter()
+                    .map(|v| match v {
+                        Value::Byte(b) => *b,
+                        _ => unreachable!("all values are bytes"),
+                    })
+                    .collect();
+                write_string(&BASE64.encode(bytes), out);
+                return;
+            }
+
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(value, out);
+            }
+            out.push(']');
+        }
+        Value::Object(entries) => {
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeVariant<SerializeObject>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Self::Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Self::Error> {
+        Ok(if v < 0 {
+            Value::Signed(v)
+        } else {
+            Value::Unsigned(v as u64)
+        })
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Self::Error> {
+        Ok(Value::Byte(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Self::Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Self::Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Self::Error> {
+        Ok(Value::Unsigned(v))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Value, Self::Error> {
+        Err(CanonicalJsonError::Float)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Value, Self::Error> {
+        Err(CanonicalJsonError::Float)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Self::Error> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Self::Error> {
+        Ok(if v.is_empty() {
+            Value::Array(Vec::new())
+        } else {
+            Value::Bytes(v.to_vec())
+        })
+    }
+
+    fn serialize_none(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Self::Error> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Self::Error> {
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeArray(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: SerializeArray(Vec::with_capacity(len)),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeObject::default())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SerializeObject::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: SerializeObject::default(),
+        })
+    }
+}
+
+fn tagged(variant: &str, value: Value) -> Value {
+    Value::Object(BTreeMap::from([(variant.to_string(), value)]))
+}
+
+struct SerializeArray(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+#[derive(Default)]
+struct SerializeObject {
+    entries: BTreeMap<String, Value>,
+    next_key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self
+            .next_key
+            .take()
+            .expect("serialize_value is called after serialize_key");
+        self.entries.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::Object(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.entries
+            .insert(key.to_string(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(Value::Object(self.entries))
+    }
+}
+
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Self::Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(tagged(self.variant, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeObject> {
+    type Ok = Value;
+    type Error = CanonicalJsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value, Self::Error> {
+        Ok(tagged(self.variant, ser::SerializeStruct::end(self.inner)?))
+    }
+}
+
+/// Serializer of map keys, which must be strings or integers.
+struct KeySerializer;
+
+macro_rules! serialize_integer_key {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String, Self::Error> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+macro_rules! reject_key {
+    ($($method:ident($($arg:ty),*) -> $ret:ty),*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<$ret, Self::Error> {
+                Err(CanonicalJsonError::InvalidKey)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = CanonicalJsonError;
+
+    type SerializeSeq = Impossible<String, CanonicalJsonError>;
+    type SerializeTuple = Impossible<String, CanonicalJsonError>;
+    type SerializeTupleStruct = Impossible<String, CanonicalJsonError>;
+    type SerializeTupleVariant = Impossible<String, CanonicalJsonError>;
+    type SerializeMap = Impossible<String, CanonicalJsonError>;
+    type SerializeStruct = Impossible<String, CanonicalJsonError>;
+    type SerializeStructVariant = Impossible<String, CanonicalJsonError>;
+
+    serialize_integer_key!(
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64
+    );
+
+    reject_key!(
+        serialize_bool(bool) -> String,
+        serialize_f32(f32) -> String,
+        serialize_f64(f64) -> String,
+        serialize_bytes(&[u8]) -> String,
+        serialize_none() -> String,
+        serialize_unit() -> String,
+        serialize_unit_struct(&'static str) -> String,
+        serialize_seq(Option<usize>) -> Self::SerializeSeq,
+        serialize_tuple(usize) -> Self::SerializeTuple,
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_map(Option<usize>) -> Self::SerializeMap,
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant
+    );
+
+    fn serialize_char(self, v: char) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Self::Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, Self::Error> {
+        Err(CanonicalJsonError::InvalidKey)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Self::Error> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Self::Error> {
+        Err(CanonicalJsonError::InvalidKey)
+    }
+}
+
+/// Deserializer of a parsed JSON value, decoding base64 strings where bytes are expected.
+struct ValueDeserializer(serde_json::Value);
+
+impl ValueDeserializer {
+    fn visit_base64<'de, V: Visitor<'de>>(
+        encoded: &str,
+        visitor: V,
+    ) -> Result<V::Value, CanonicalJsonError> {
+        let bytes = BASE64.decode(encoded)?;
+        visitor.visit_seq(SeqDeserializer::new(bytes.into_iter()))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = CanonicalJsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        use serde_json::Value as Json;
+
+        match self.0 {
+            Json::Null => visitor.visit_unit(),
+            Json::Bool(b) => visitor.visit_bool(b),
+            Json::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    visitor.visit_u64(n)
+                } else if let Some(n) = n.as_i64() {
+                    visitor.visit_i64(n)
+                } else {
+                    Err(CanonicalJsonError::Float)
+                }
+            }
+            Json::String(s) => visitor.visit_string(s),
+            Json::Array(values) => visitor.visit_seq(ArrayDeserializer(values.into_iter())),
+            Json::Object(entries) => visitor.visit_map(ObjectDeserializer {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            serde_json::Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(ValueDeserializer(value)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.0 {
+            serde_json::Value::String(encoded) => Self::visit_base64(encoded, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.0 {
+            serde_json::Value::String(encoded) => visitor.visit_byte_buf(BASE64.decode(encoded)?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            serde_json::Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            serde_json::Value::Object(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().expect("object has an entry");
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            _ => Err(de::Error::custom(
+                "expected a string or an object with a single entry for an enum",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string unit unit_struct map
+        struct identifier ignored_any
+    }
+}
+
+struct ArrayDeserializer(std::vec::IntoIter<serde_json::Value>);
+
+impl<'de> SeqAccess<'de> for ArrayDeserializer {
+    type Error = CanonicalJsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.0
+            .next()
+            .map(|value| seed.deserialize(ValueDeserializer(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct ObjectDeserializer {
+    entries: serde_json::map::IntoIter,
+    value: Option<serde_json::Value>,
+}
+
+impl<'de> MapAccess<'de> for ObjectDeserializer {
+    type Error = CanonicalJsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed is called after next_key_seed");
+        seed.deserialize(ValueDeserializer(value))
+    }
+}
+
+/// Deserializer of map keys, parsing integers from strings.
+struct KeyDeserializer(String);
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident: $ty:ty),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let n: $ty = self
+                    .0
+                    .parse()
+                    .map_err(|_| CanonicalJsonError::InvalidKey)?;
+                visitor.$visit(n)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = CanonicalJsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_integer_key!(
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64
+    );
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(EnumDeserializer {
+            variant: self.0,
+            value: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Option<serde_json::Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = CanonicalJsonError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(
+            IntoDeserializer::<CanonicalJsonError>::into_deserializer(self.variant),
+        )?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+struct VariantDeserializer(Option<serde_json::Value>);
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = CanonicalJsonError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.0 {
+            None => Ok(()),
+            Some(_) => Err(de::Error::custom("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        let value = self
+            .0
+            .ok_or_else(|| de::Error::custom("expected a newtype variant"))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self
+            .0
+            .ok_or_else(|| de::Error::custom("expected a tuple variant"))?;
+        de::Deserializer::deserialize_seq(ValueDeserializer(value), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = self
+            .0
+            .ok_or_else(|| de::Error::custom("expected a struct variant"))?;
+        de::Deserializer::deserialize_any(ValueDeserializer(value), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Unit,
+        Newtype(u32),
+        Tuple(u8, u8),
+        Struct { a: i64 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Id(u32);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Example {
+        name: String,
+        hash: [u8; 4],
+        data: Vec<u8>,
+        empty: Vec<u8>,
+        counts: Vec<u32>,
+        kinds: Vec<Kind>,
+        by_id: HashMap<Id, bool>,
+        optional: Option<u64>,
+    }
+
+    fn example() -> Example {
+        Example {
+            name: "a\"b\n\u{1}é".to_string(),
+            hash: [1, 2, 3, 4],
+            data: b"hello".to_vec(),
+            empty: Vec::new(),
+            counts: vec![1, 2],
+            kinds: vec![
+                Kind::Unit,
+                Kind::Newtype(1),
+                Kind::Tuple(1, 2),
+                Kind::Struct { a: -1 },
+            ],
+            by_id: HashMap::from([(Id(10), true), (Id(2), false)]),
+            optional: None,
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            to_canonical_json(&example()).unwrap(),
+            concat!(
+                r#"{"by_id":{"10":true,"2":false},"counts":[1,2],"data":"aGVsbG8=","empty":[],"#,
+                r#""hash":"AQIDBA==","kinds":["Unit",{"Newtype":1},{"Tuple":"AQI="},"#,
+                r#"{"Struct":{"a":-1}}],"name":"a\"b\n\u0001é","optional":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let json = to_canonical_json(&example()).unwrap();
+        assert_eq!(from_canonical_json::<Example>(&json).unwrap(), example());
+    }
+
+    #[test]
+    fn test_reject_non_canonical() {
+        let json = to_canonical_json(&example()).unwrap();
+
+        // Whitespace
+        let spaced = json.replacen(':', ": ", 1);
+        assert!(matches!(
+            from_canonical_json::<Example>(&spaced),
+            Err(CanonicalJsonError::NotCanonical)
+        ));
+
+        // Bytes as an array
+        let array = json.replace(r#""aGVsbG8=""#, "[104,101,108,108,111]");
+        assert!(matches!(
+            from_canonical_json::<Example>(&array),
+            Err(CanonicalJsonError::NotCanonical)
+        ));
+
+        // Unsorted keys
+        let unsorted = json.replacen(
+            r#""by_id":{"10":true,"2":false}"#,
+            r#""by_id":{"2":false,"10":true}"#,
+            1,
+        );
+        assert!(from_canonical_json::<Example>(&unsorted).is_err());
+
+        // Escaped character which does not need to be
+        let escaped = json.replace('é', "\\u00e9");
+        assert!(from_canonical_json::<Example>(&escaped).is_err());
+    }
+
+    #[test]
+    fn test_reject_float() {
+        assert!(matches!(
+            to_canonical_json(&1.0f64),
+            Err(CanonicalJsonError::Float)
+        ));
+    }
+}
@@ -6,19 +6,33 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "bls")]
+pub mod bls;
 pub mod commitment;
+pub mod did;
+#[cfg(any(test, feature = "dry-run"))]
+pub mod dry_run;
 #[cfg(any(test, feature = "fixtures"))]
 pub mod fixtures;
+pub mod inspect;
+pub mod json;
 pub mod merkle;
 pub mod msg;
 pub mod proof;
+pub mod range;
+pub mod revocation;
 pub mod session;
 mod signature;
 pub mod transcript;
+pub mod transparency;
+pub mod vc;
+pub mod zk;
 
 pub use session::{HandshakeSummary, NotarizedSession, SessionData, SessionHeader};
-pub use signature::{NotaryPublicKey, Signature};
-pub use transcript::{Direction, RedactedTranscript, Transcript, TranscriptSlice};
+pub use signature::{NotaryPublicKey, Signature, SignatureVerifyError};
+pub use transcript::{
+    Direction, RedactedTranscript, Transcript, TranscriptError, TranscriptRecord, TranscriptSlice,
+};
 
 use mpz_garble_core::{encoding_state, EncodedValue};
 use serde::{Deserialize, Serialize};
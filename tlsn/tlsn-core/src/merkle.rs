@@ -12,10 +12,13 @@
 
 use mpz_core::hash::Hash;
 use rs_merkle::{
-    algorithms::Sha256, proof_serializers, MerkleProof as MerkleProof_rs_merkle,
-    MerkleTree as MerkleTree_rs_merkle,
+    algorithms::Sha256,
+    proof_serializers,
+    utils::indices::{proof_indices_by_layers, tree_depth},
+    MerkleProof as MerkleProof_rs_merkle, MerkleTree as MerkleTree_rs_merkle, PartialTree,
 };
 use serde::{ser::Serializer, Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 use utils::iter::DuplicateCheck;
 
 /// A Merkle root.
@@ -99,6 +102,102 @@ impl MerkleProof {
         }
         Ok(())
     }
+
+    /// Derives a proof for the subset `retained` of the leaves proven by this proof.
+    ///
+    /// The nodes of the tree required by the narrower proof are recomputed from the leaves
+    /// proven by this proof, so the leaf hashes must be provided.
+    ///
+    /// # Panics
+    ///
+    /// - If the length of `leaf_indices` and `leaf_hashes` does not match.
+    /// - If `leaf_indices` contains duplicates.
+    pub fn narrow(
+        &self,
+        leaf_indices: &[usize],
+        leaf_hashes: &[Hash],
+        retained: &[usize],
+    ) -> Result<MerkleProof, MerkleError> {
+        assert_eq!(
+            leaf_indices.len(),
+            leaf_hashes.len(),
+            "leaf indices length must match leaf hashes length"
+        );
+        assert!(
+            !leaf_indices.iter().contains_dups(),
+            "duplicate indices provided {:?}",
+            leaf_indices
+        );
+
+        if retained.is_empty() {
+            return Err(MerkleError::MerkleNoLeavesProvided);
+        }
+
+        // zip indices and hashes
+        let mut leaves: Vec<(usize, [u8; 32])> = leaf_indices
+            .iter()
+            .cloned()
+            .zip(leaf_hashes.iter().cloned().map(|h| *h.as_bytes()))
+            .collect();
+        leaves.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let indices: Vec<usize> = leaves.iter().map(|(index, _)| *index).collect();
+
+        // Rebuild the nodes of the tree which are known from the leaves and the proof hashes,
+        // the same way the root is computed when verifying the proof.
+        let mut proof_hashes = self.proof.proof_hashes().iter().cloned();
+        let mut layers: Vec<Vec<(usize, [u8; 32])>> =
+            proof_indices_by_layers(&indices, self.total_leaves)
+                .into_iter()
+                .map(|layer| {
+                    layer
+                        .into_iter()
+                        .map(|index| {
+                            proof_hashes
+                                .next()
+                                .map(|hash| (index, hash))
+                                .ok_or(MerkleError::MerkleProofVerificationFailed)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<_, _>>()?;
+
+        match layers.first_mut() {
+            Some(first) => first.extend(leaves),
+            None => layers.push(leaves),
+        }
+
+        let tree = PartialTree::<Sha256>::build(layers, tree_depth(self.total_leaves))
+            .map_err(|_| MerkleError::MerkleProofVerificationFailed)?;
+        let known: Vec<HashMap<usize, [u8; 32]>> = tree
+            .layers()
+            .iter()
+            .map(|layer| layer.iter().cloned().collect())
+            .collect();
+
+        // Collect the nodes required to prove the retained leaves.
+        let mut retained = retained.to_vec();
+        retained.sort();
+        retained.dedup();
+
+        let mut hashes = Vec::new();
+        for (depth, layer) in proof_indices_by_layers(&retained, self.total_leaves)
+            .into_iter()
+            .enumerate()
+        {
+            for index in layer {
+                let hash = known
+                    .get(depth)
+                    .and_then(|layer| layer.get(&index))
+                    .ok_or(MerkleError::MerkleProofVerificationFailed)?;
+                hashes.push(*hash);
+            }
+        }
+
+        Ok(MerkleProof {
+            proof: MerkleProof_rs_merkle::new(hashes),
+            total_leaves: self.total_leaves,
+        })
+    }
 }
 
 impl Clone for MerkleProof {
@@ -237,6 +336,42 @@ mod test {
             .is_ok(),);
     }
 
+    #[test]
+    fn test_narrow_success() {
+        let leaves: Vec<Hash> = (0..7u8).map(|i| Hash::from([i; 32])).collect();
+        let tree = MerkleTree::from_leaves(&leaves).unwrap();
+        let proof = tree.proof(&[0, 2, 3, 6]);
+        let hashes = [leaves[0], leaves[2], leaves[3], leaves[6]];
+
+        for retained in [vec![2], vec![0, 6], vec![3, 2], vec![0, 2, 3, 6]] {
+            let narrowed = proof.narrow(&[0, 2, 3, 6], &hashes, &retained).unwrap();
+            let retained_hashes: Vec<Hash> = retained.iter().map(|i| leaves[*i]).collect();
+
+            assert!(narrowed
+                .verify(&tree.root(), &retained, &retained_hashes)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_narrow_fail_wrong_leaf() {
+        let leaves: Vec<Hash> = (0..5u8).map(|i| Hash::from([i; 32])).collect();
+        let tree = MerkleTree::from_leaves(&leaves).unwrap();
+        let proof = tree.proof(&[1, 4]);
+
+        // the narrowed proof is derived from a wrong leaf, so it does not verify
+        let narrowed = proof
+            .narrow(&[1, 4], &[leaves[0], leaves[4]], &[4])
+            .unwrap();
+        assert_eq!(
+            narrowed
+                .verify(&tree.root(), &[4], &[leaves[4]])
+                .err()
+                .unwrap(),
+            MerkleError::MerkleProofVerificationFailed
+        );
+    }
+
     #[test]
     fn test_verify_fail_wrong_leaf() {
         let leaf0 = Hash::from([0u8; 32]);
@@ -1,15 +1,37 @@
 //! Protocol message types.
 
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize};
 use serde::{Deserialize, Serialize};
 use utils::range::RangeSet;
 
-use crate::{merkle::MerkleRoot, proof::SessionInfo, signature::Signature, SessionHeader};
+use crate::{
+    commitment::{CommitmentId, CommitmentInfo, CommitmentKind, TranscriptCommitments},
+    merkle::{MerkleError, MerkleProof, MerkleRoot},
+    proof::SessionInfo,
+    session::{RecordOrder, RecordTimings, SessionBinding},
+    signature::{domain_separated, NotaryPublicKey, Signature, SignatureVerifyError},
+    SessionHeader,
+};
+
+/// Domain separator of signed abort receipts.
+const ABORT_RECEIPT_DOMAIN: &[u8] = b"tlsn/abort-receipt/v1";
+/// Domain separator of signed session opened receipts.
+const SESSION_OPENED_RECEIPT_DOMAIN: &[u8] = b"tlsn/session-opened-receipt/v1";
 
 /// Top-level enum for all messages
 #[derive(Debug, Serialize, Deserialize)]
 pub enum TlsnMessage {
     /// A Merkle root for the tree of commitments to the transcript.
     TranscriptCommitmentRoot(MerkleRoot),
+    /// The binding of the session to a relying party, if the prover requested one.
+    SessionBinding(Option<SessionBinding>),
+    /// The timings of the records of the session as observed by the sender, if the prover
+    /// commits to them.
+    RecordTimings(Option<RecordTimings>),
+    /// The order of the records of the session, if the prover commits to it.
+    RecordOrder(Option<RecordOrder>),
+    /// The plaintext hashes in the tree of commitments to the transcript.
+    PlaintextHashes(PlaintextHashes),
     /// A session header signed by a notary.
     SignedSessionHeader(SignedSessionHeader),
     /// A session header.
@@ -18,6 +40,13 @@ pub enum TlsnMessage {
     SessionInfo(SessionInfo),
     /// Information about the values the prover wants to prove
     ProvingInfo(ProvingInfo),
+    /// A message of the abort subprotocol
+    Abort(AbortMessage),
+    /// The audience of the session, which the prover supplies when the session is opened.
+    SessionAudience(Option<String>),
+    /// A receipt acknowledging that the session was opened, if the verifier is a notary which
+    /// issues them.
+    SessionOpened(Option<SignedSessionOpenedReceipt>),
 }
 
 /// A signed session header.
@@ -29,6 +58,80 @@ pub struct SignedSessionHeader {
     pub signature: Signature,
 }
 
+/// The plaintext hash commitments in the tree of commitments to the transcript, which the prover
+/// proves to the notary.
+///
+/// The notary learns the ranges of the transcript and the salted hashes, but not the data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlaintextHashes {
+    /// The ids and info of the commitments, with the salted hashes.
+    pub commitments: Vec<(CommitmentId, CommitmentInfo, Hash)>,
+    /// A proof of the inclusion of the commitments in the Merkle tree, if there are any.
+    pub inclusion_proof: Option<MerkleProof>,
+}
+
+impl PlaintextHashes {
+    /// Creates the message for the plaintext hash commitments in the provided commitments.
+    pub fn new(commitments: &TranscriptCommitments) -> Self {
+        let hashes = commitments.plaintext_hashes();
+
+        let inclusion_proof = (!hashes.is_empty()).then(|| {
+            let indices = hashes
+                .iter()
+                .map(|(id, _, _)| id.to_inner() as usize)
+                .collect::<Vec<_>>();
+            commitments.merkle_tree().proof(&indices)
+        });
+
+        Self {
+            commitments: hashes
+                .into_iter()
+                .map(|(id, info, commitment)| (id, info.clone(), *commitment.hash()))
+                .collect(),
+            inclusion_proof,
+        }
+    }
+
+    /// Returns the Merkle leaves of the commitments, which the Notary binds to the session header
+    /// once it proved the hashes, see [`SessionHeader::plaintext_hashes`].
+    pub fn leaves(&self) -> Vec<Hash> {
+        self.commitments
+            .iter()
+            .map(|(_, info, hash)| info.leaf(*hash))
+            .collect()
+    }
+
+    /// Verifies that the commitments are included in the tree with the provided root.
+    ///
+    /// The hashes must be checked against the transcript separately.
+    pub fn verify(&self, root: &MerkleRoot) -> Result<(), MerkleError> {
+        let Some(inclusion_proof) = &self.inclusion_proof else {
+            return if self.commitments.is_empty() {
+                Ok(())
+            } else {
+                Err(MerkleError::MerkleProofVerificationFailed)
+            };
+        };
+
+        let mut indices = Vec::with_capacity(self.commitments.len());
+        let mut leaves = Vec::with_capacity(self.commitments.len());
+        for (id, info, hash) in &self.commitments {
+            if info.kind() != CommitmentKind::PlaintextHash || indices.contains(&id.to_inner()) {
+                return Err(MerkleError::MerkleProofVerificationFailed);
+            }
+
+            indices.push(id.to_inner());
+            leaves.push(info.leaf(*hash));
+        }
+
+        let indices = indices
+            .into_iter()
+            .map(|index| index as usize)
+            .collect::<Vec<_>>();
+        inclusion_proof.verify(root, &indices, &leaves)
+    }
+}
+
 /// Information about the values the prover wants to prove
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct ProvingInfo {
@@ -39,3 +142,239 @@ pub struct ProvingInfo {
     /// Purported cleartext values
     pub cleartext: Vec<u8>,
 }
+
+/// A party of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionParty {
+    /// The prover.
+    Prover,
+    /// The verifier, e.g. a notary.
+    Verifier,
+}
+
+/// A phase of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionPhase {
+    /// MPC setup prior to connecting to the server.
+    Setup,
+    /// The TLS session with the server.
+    TlsSession,
+    /// Proving the transcript to the verifier.
+    Prove,
+    /// Finalization of the notarization or proof.
+    Finalize,
+}
+
+/// The reason for aborting a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbortReason {
+    /// The session was cancelled, e.g. by the user.
+    Cancelled,
+    /// The session took too long.
+    Timeout,
+    /// The session exceeded a configured limit, e.g. the transcript size.
+    LimitExceeded,
+    /// The session violates the policy of the party, e.g. the server is not allowed.
+    PolicyViolation,
+    /// The peer deviated from the protocol.
+    ProtocolError,
+    /// An internal error occurred.
+    Internal,
+}
+
+/// Statistics of a session at the time it was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbortStats {
+    /// The phase the aborting party was in.
+    pub phase: SessionPhase,
+    /// Time elapsed since the start of the session, in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+/// A request to abort a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionAbort {
+    /// The reason for aborting.
+    pub reason: AbortReason,
+    /// Human readable details about the abort.
+    pub details: Option<String>,
+    /// Statistics of the session at the time it was aborted.
+    pub stats: AbortStats,
+}
+
+/// A receipt acknowledging that a session was aborted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbortReceipt {
+    /// Id of the session.
+    pub id: String,
+    /// The party which aborted the session.
+    pub initiator: SessionParty,
+    /// The abort.
+    pub abort: SessionAbort,
+    /// Time the receipt was issued, in seconds since the UNIX epoch.
+    pub time: u64,
+}
+
+impl AbortReceipt {
+    /// Returns the bytes of the receipt which are signed.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        domain_separated(ABORT_RECEIPT_DOMAIN, &self.to_bytes())
+    }
+}
+
+/// An abort receipt signed by a notary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAbortReceipt {
+    /// The receipt.
+    pub receipt: AbortReceipt,
+    /// The notary's signature
+    pub signature: Signature,
+}
+
+impl SignedAbortReceipt {
+    /// Verifies the signature of the receipt.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    pub fn verify(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), SignatureVerifyError> {
+        self.signature
+            .verify(&self.receipt.signed_bytes(), notary_public_key)
+    }
+}
+
+/// The limits of a session enforced by the notary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLimits {
+    /// Maximum number of bytes that can be sent.
+    pub max_sent_data: usize,
+    /// Maximum number of bytes that can be received.
+    pub max_recv_data: usize,
+    /// Maximum number of TLS records, if limited.
+    pub max_records: Option<usize>,
+    /// Maximum duration of the session, in seconds, if limited.
+    pub max_session_duration_secs: Option<u64>,
+}
+
+/// A receipt acknowledging that a session was opened, issued by a notary once the MPC setup has
+/// completed.
+///
+/// The receipt lets a prover prove that it attempted a notarization even if the session fails
+/// later, and gives the operator of the notary a non-repudiable record of the sessions it opened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionOpenedReceipt {
+    /// Id of the session.
+    pub id: String,
+    /// The audience supplied by the prover, e.g. the relying party which requested the session.
+    pub audience: Option<String>,
+    /// The limits of the session.
+    pub limits: SessionLimits,
+    /// Time the receipt was issued, in seconds since the UNIX epoch.
+    pub time: u64,
+}
+
+impl SessionOpenedReceipt {
+    /// Returns the bytes of the receipt which are signed.
+    ///
+    /// The bytes are prefixed with a domain separator, so that the prover-chosen audience can't
+    /// be used to obtain a signature over another message signed with the notary key.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        domain_separated(SESSION_OPENED_RECEIPT_DOMAIN, &self.to_bytes())
+    }
+}
+
+/// A session opened receipt signed by a notary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedSessionOpenedReceipt {
+    /// The receipt.
+    pub receipt: SessionOpenedReceipt,
+    /// The notary's signature
+    pub signature: Signature,
+}
+
+impl SignedSessionOpenedReceipt {
+    /// Verifies the signature of the receipt.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    pub fn verify(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), SignatureVerifyError> {
+        self.signature
+            .verify(&self.receipt.signed_bytes(), notary_public_key)
+    }
+}
+
+/// A message of the abort subprotocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AbortMessage {
+    /// Aborts the session.
+    Abort {
+        /// The abort.
+        abort: SessionAbort,
+        /// A receipt issued by the aborting party, if it is a notary.
+        receipt: Option<SignedAbortReceipt>,
+    },
+    /// Acknowledges an abort.
+    Ack {
+        /// A receipt issued by the acknowledging party, if it is a notary.
+        receipt: Option<SignedAbortReceipt>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::notary_signing_key;
+    use p256::ecdsa::signature::Signer;
+
+    fn opened_receipt() -> SessionOpenedReceipt {
+        SessionOpenedReceipt {
+            id: "test".to_string(),
+            audience: Some("relying-party".to_string()),
+            limits: SessionLimits {
+                max_sent_data: 1024,
+                max_recv_data: 1024,
+                max_records: None,
+                max_session_duration_secs: None,
+            },
+            time: 0,
+        }
+    }
+
+    #[test]
+    fn test_session_opened_receipt() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        let receipt = opened_receipt();
+        let signature: p256::ecdsa::Signature = signing_key.sign(&receipt.signed_bytes());
+        let signed = SignedSessionOpenedReceipt {
+            receipt,
+            signature: signature.into(),
+        };
+
+        assert!(signed.verify(public_key).is_ok());
+    }
+
+    #[test]
+    fn test_session_opened_receipt_domain_separated() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        // A signature of the serialized receipt without the domain is not accepted.
+        let receipt = opened_receipt();
+        let signature: p256::ecdsa::Signature = signing_key.sign(&receipt.to_bytes());
+        let signed = SignedSessionOpenedReceipt {
+            receipt,
+            signature: signature.into(),
+        };
+
+        assert!(signed.verify(public_key).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tls_core::verify::ServerCertVerifier;
+
+use crate::{
+    json::{from_canonical_json, to_canonical_json, CanonicalJsonError},
+    proof::{PolicyError, TlsProof, VerifiedProof, VerifierPolicy},
+    NotaryPublicKey,
+};
+
+/// Metadata shared by all the proofs of a [`ProofBundle`].
+///
+/// The metadata is chosen by the Prover and is not signed by the Notary, so it must only be used
+/// to organize the proofs, e.g. to display them, never to trust them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleMetadata {
+    /// A human readable description of the bundle, e.g. "Transactions of March".
+    pub description: Option<String>,
+    /// Arbitrary attributes of the bundle.
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// A proof of a [`ProofBundle`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleItem {
+    /// The label of the proof within the bundle, e.g. "page 2".
+    pub label: String,
+    /// The proof.
+    pub proof: TlsProof,
+}
+
+/// A bundle of proofs of sessions which belong together, e.g. the pages of a paginated API
+/// response, each notarized in its own session.
+///
+/// The proofs are verified independently of each other, see [`ProofBundle::evaluate`], so an
+/// invalid proof does not prevent the others from being used.
+///
+/// Nothing binds the proofs of a bundle together: whoever holds the bundle can drop, reorder or
+/// swap its proofs, or relabel them, without invalidating it. A verifier which relies on a proof
+/// being part of a bundle, or on its position, must check this by other means, e.g. by requiring
+/// each session to be [bound](VerifierPolicy::require_binding) to a nonce it chose.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProofBundle {
+    /// The metadata shared by the proofs.
+    pub metadata: BundleMetadata,
+    /// The proofs, in order.
+    pub items: Vec<BundleItem>,
+}
+
+impl ProofBundle {
+    /// Creates a new, empty bundle with the provided metadata.
+    pub fn new(metadata: BundleMetadata) -> Self {
+        Self {
+            metadata,
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds a proof to the bundle.
+    pub fn push(&mut self, label: impl Into<String>, proof: TlsProof) {
+        self.items.push(BundleItem {
+            label: label.into(),
+            proof,
+        });
+    }
+
+    /// Returns the number of proofs in the bundle.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the bundle has no proofs.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Verifies every proof of the bundle against the provided notary key.
+    ///
+    /// The creation time of the key is not known, so use [`ProofBundle::evaluate`] with a policy
+    /// to limit the age of the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify(
+        self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> BundleReport {
+        let policy = VerifierPolicy::new().accept_notary_key(notary_public_key, None);
+
+        self.evaluate(&policy, cert_verifier)
+    }
+
+    /// Evaluates every proof of the bundle against the provided policy, see
+    /// [`VerifierPolicy::evaluate`].
+    ///
+    /// Returns a report with the result of each proof, in the order of the bundle.
+    pub fn evaluate(
+        self,
+        policy: &VerifierPolicy,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> BundleReport {
+        let items = self
+            .items
+            .into_iter()
+            .map(|BundleItem { label, proof }| {
+                let server_name = proof.session.session_info.server_name.as_ref().to_string();
+                let time = proof.session.header.time();
+
+                let result =
+                    policy
+                        .evaluate(proof, cert_verifier)
+                        .map(|(sent, recv)| VerifiedProof {
+                            notary: None,
+                            server_name,
+                            time,
+                            sent,
+                            recv,
+                        });
+
+                BundleItemReport { label, result }
+            })
+            .collect();
+
+        BundleReport {
+            metadata: self.metadata,
+            items,
+        }
+    }
+
+    /// Encodes the bundle as canonical JSON, see [`json`](crate::json).
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_canonical_json(self)
+    }
+
+    /// Decodes a bundle from canonical JSON, rejecting any input which is not in canonical form.
+    pub fn from_canonical_json(json: &str) -> Result<Self, CanonicalJsonError> {
+        from_canonical_json(json)
+    }
+}
+
+/// The result of the verification of a proof of a bundle.
+#[derive(Debug)]
+pub struct BundleItemReport {
+    /// The label of the proof.
+    pub label: String,
+    /// The contents of the proof, or the reason it was rejected.
+    pub result: Result<VerifiedProof, PolicyError>,
+}
+
+/// The results of the verification of a [`ProofBundle`].
+#[derive(Debug)]
+pub struct BundleReport {
+    /// The metadata of the bundle, which is not verified.
+    pub metadata: BundleMetadata,
+    /// The result of each proof, in the order of the bundle.
+    pub items: Vec<BundleItemReport>,
+}
+
+impl BundleReport {
+    /// Returns `true` if every proof of the bundle is valid.
+    pub fn is_valid(&self) -> bool {
+        self.items.iter().all(|item| item.result.is_ok())
+    }
+
+    /// Returns an iterator over the verified proofs, with their labels.
+    pub fn verified(&self) -> impl Iterator<Item = (&str, &VerifiedProof)> {
+        self.items.iter().filter_map(|item| {
+            item.result
+                .as_ref()
+                .ok()
+                .map(|verified| (item.label.as_str(), verified))
+        })
+    }
+
+    /// Returns an iterator over the rejected proofs, with their labels.
+    pub fn rejected(&self) -> impl Iterator<Item = (&str, &PolicyError)> {
+        self.items.iter().filter_map(|item| {
+            item.result
+                .as_ref()
+                .err()
+                .map(|err| (item.label.as_str(), err))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dry_run::{dry_run_notary_public_key, DryRun},
+        proof::default_cert_verifier,
+    };
+
+    fn proof(recv: &[u8]) -> TlsProof {
+        let mut dry_run = DryRun::new(b"GET /items HTTP/1.1\r\n\r\n".to_vec(), recv.to_vec());
+        let id = dry_run
+            .commitment_builder()
+            .commit_recv(&(0..recv.len()))
+            .unwrap();
+
+        let session = dry_run.finalize().unwrap();
+        let mut builder = session.data().build_substrings_proof();
+        builder.reveal_by_id(id).unwrap();
+
+        TlsProof {
+            session: session.session_proof(),
+            substrings: builder.build().unwrap(),
+        }
+    }
+
+    fn bundle() -> ProofBundle {
+        let mut bundle = ProofBundle::new(BundleMetadata {
+            description: Some("items".to_string()),
+            ..Default::default()
+        });
+        bundle.push("page 1", proof(b"HTTP/1.1 200 OK\r\n\r\n[1, 2]"));
+        bundle.push("page 2", proof(b"HTTP/1.1 200 OK\r\n\r\n[3]"));
+        bundle
+    }
+
+    #[test]
+    fn test_verify_bundle() {
+        let report = bundle().verify(dry_run_notary_public_key(), &default_cert_verifier());
+
+        assert!(report.is_valid());
+        assert_eq!(report.metadata.description.as_deref(), Some("items"));
+
+        let verified = report.verified().collect::<Vec<_>>();
+        assert_eq!(verified.len(), 2);
+        assert_eq!(verified[1].0, "page 2");
+        assert_eq!(verified[1].1.server_name, "tlsnotary.org");
+        assert!(verified[1].1.recv.data().ends_with(b"[3]"));
+    }
+
+    #[test]
+    fn test_reports_each_item() {
+        let mut bundle = bundle();
+
+        // The substrings of the first page do not match the session of the second page.
+        let first = bundle.items.remove(0);
+        bundle.items[0].proof.substrings = first.proof.substrings;
+        bundle.push("page 1", proof(b"HTTP/1.1 200 OK\r\n\r\n[1, 2]"));
+
+        let report = bundle.verify(dry_run_notary_public_key(), &default_cert_verifier());
+
+        assert!(!report.is_valid());
+        assert_eq!(
+            report
+                .rejected()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>(),
+            ["page 2"]
+        );
+        assert_eq!(
+            report
+                .verified()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>(),
+            ["page 1"]
+        );
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let bundle = bundle();
+        let json = bundle.to_canonical_json().unwrap();
+
+        let decoded = ProofBundle::from_canonical_json(&json).unwrap();
+        assert_eq!(decoded.metadata, bundle.metadata);
+        assert_eq!(decoded.len(), 2);
+    }
+}
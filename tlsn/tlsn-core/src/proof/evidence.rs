@@ -0,0 +1,105 @@
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize, utils::blake3};
+use serde::{Deserialize, Serialize};
+use tls_core::{
+    anchors::RootCertStore,
+    key::Certificate,
+    msgs::{
+        enums::{NamedGroup, SignatureScheme},
+        handshake::ServerECDHParams,
+    },
+    verify::WebPkiVerifier,
+};
+
+use crate::{proof::SessionProofError, HandshakeSummary, ServerName};
+
+/// Parameters with which the handshake of a session is verified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationParams {
+    /// The time at which the certificate chain is verified, in seconds since the UNIX epoch.
+    pub time: u64,
+    /// The signature scheme of the server's signature over the key exchange parameters.
+    pub signature_scheme: SignatureScheme,
+    /// The named group of the server's ephemeral key.
+    pub named_group: NamedGroup,
+}
+
+/// Evidence of the TLS handshake of a session, which shows that the prover connected to the
+/// server it claims.
+///
+/// The evidence is extracted from a [`SessionInfo`](crate::proof::SessionInfo) which was verified
+/// against a [`SessionProof`](crate::proof::SessionProof), so it can be stored or forwarded for
+/// audit. Verifiers should re-validate the session proof against their own root store, see
+/// [`cert_verifier_from_roots`].
+///
+/// The stapled OCSP response and the SCTs are only checked if the certificate verifier is
+/// configured to do so, see [`WebPkiVerifier::with_ocsp_policy`] and
+/// [`CertificateTransparencyPolicy::require_sct`](tls_core::verify::CertificateTransparencyPolicy::require_sct).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakeEvidence {
+    /// The name of the server.
+    pub server_name: ServerName,
+    /// The DER encoded certificate chain presented by the server, starting with the end-entity
+    /// certificate.
+    pub certificate_chain: Vec<Vec<u8>>,
+    /// The OCSP response stapled by the server, if any.
+    pub ocsp_response: Option<Vec<u8>>,
+    /// The Signed Certificate Timestamps sent by the server.
+    pub scts: Vec<Vec<u8>>,
+    /// The BLAKE3 hash of the handshake data the prover committed to in the session header.
+    pub handshake_hash: Hash,
+    /// The parameters with which the handshake is verified.
+    pub params: VerificationParams,
+}
+
+impl HandshakeEvidence {
+    /// Extracts the evidence of the handshake from the session info.
+    ///
+    /// This does not verify the session info, see [`SessionInfo::verify`](crate::proof::SessionInfo::verify).
+    pub(crate) fn new(
+        server_name: &ServerName,
+        handshake_summary: &HandshakeSummary,
+        handshake: &tls_core::handshake::HandshakeData,
+    ) -> Result<Self, SessionProofError> {
+        let ecdh_params = tls_core::suites::tls12::decode_ecdh_params::<ServerECDHParams>(
+            handshake.server_kx_details().kx_params(),
+        )
+        .ok_or_else(|| {
+            SessionProofError::InvalidHandshake("invalid key exchange parameters".to_string())
+        })?;
+
+        let cert_details = handshake.server_cert_details();
+
+        Ok(Self {
+            server_name: server_name.clone(),
+            certificate_chain: cert_details
+                .cert_chain()
+                .iter()
+                .map(|cert| cert.0.clone())
+                .collect(),
+            ocsp_response: (!cert_details.ocsp_response().is_empty())
+                .then(|| cert_details.ocsp_response().to_vec()),
+            scts: cert_details
+                .scts()
+                .map(|scts| scts.iter().map(|sct| sct.0.clone()).collect())
+                .unwrap_or_default(),
+            handshake_hash: Hash::from(blake3(&handshake.to_bytes())),
+            params: VerificationParams {
+                time: handshake_summary.time(),
+                signature_scheme: handshake.server_kx_details().kx_sig().scheme,
+                named_group: ecdh_params.curve_params.named_group,
+            },
+        })
+    }
+}
+
+/// Creates a new [`WebPkiVerifier`] which trusts the provided DER encoded root certificates.
+pub fn cert_verifier_from_roots(roots: &[Vec<u8>]) -> Result<WebPkiVerifier, SessionProofError> {
+    let mut root_store = RootCertStore::empty();
+    for root in roots {
+        root_store
+            .add(&Certificate(root.clone()))
+            .map_err(|e| SessionProofError::InvalidRootStore(e.to_string()))?;
+    }
+
+    Ok(WebPkiVerifier::new(root_store, None))
+}
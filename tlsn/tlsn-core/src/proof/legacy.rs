@@ -0,0 +1,73 @@
+//! Decoding of the proofs of releases which did not version their layout.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use utils::range::RangeSet;
+
+use crate::{
+    commitment::{CommitmentId, CommitmentInfo, CommitmentKind, CommitmentOpening},
+    merkle::MerkleProof,
+    proof::{SessionInfo, SessionProof, SubstringsProof, TlsProof},
+    session::BaseFields,
+    Direction, Signature,
+};
+
+/// The layout of a [`TlsProof`] of [`HeaderVersion::V0`](crate::session::HeaderVersion::V0).
+#[derive(Deserialize)]
+struct TlsProofV0 {
+    session: SessionProofV0,
+    substrings: SubstringsProofV0,
+}
+
+#[derive(Deserialize)]
+struct SessionProofV0 {
+    header: BaseFields,
+    signature: Option<Signature>,
+    session_info: SessionInfo,
+}
+
+#[derive(Deserialize)]
+struct SubstringsProofV0 {
+    openings: HashMap<CommitmentId, (CommitmentInfoV0, CommitmentOpening)>,
+    inclusion_proof: MerkleProof,
+}
+
+/// The layout of a [`CommitmentInfo`] before commitments could be constrained.
+#[derive(Deserialize)]
+struct CommitmentInfoV0 {
+    kind: CommitmentKind,
+    ranges: RangeSet<usize>,
+    direction: Direction,
+}
+
+/// Decodes a proof in the layout of [`HeaderVersion::V0`](crate::session::HeaderVersion::V0).
+pub(crate) fn deserialize_v0(bytes: &[u8]) -> Result<TlsProof, bincode::Error> {
+    let TlsProofV0 {
+        session,
+        substrings,
+    } = bincode::deserialize(bytes)?;
+
+    let openings = substrings
+        .openings
+        .into_iter()
+        .map(|(id, (info, opening))| {
+            (
+                id,
+                (
+                    CommitmentInfo::new(info.kind, info.ranges, info.direction),
+                    opening,
+                ),
+            )
+        })
+        .collect();
+
+    Ok(TlsProof {
+        session: SessionProof {
+            header: session.header.into(),
+            signature: session.signature,
+            session_info: session.session_info,
+        },
+        substrings: SubstringsProof::from_parts(openings, substrings.inclusion_proof),
+    })
+}
@@ -1,16 +1,29 @@
 //! Different types of proofs used in the TLSNotary protocol.
 
+mod bundle;
+mod evidence;
+mod legacy;
+mod policy;
 mod session;
 mod substrings;
 
+pub use bundle::{BundleItem, BundleItemReport, BundleMetadata, BundleReport, ProofBundle};
+pub use evidence::{cert_verifier_from_roots, HandshakeEvidence, VerificationParams};
+pub use policy::{PolicyError, PolicyViolation, VerifierPolicy};
 pub use session::{default_cert_verifier, SessionInfo, SessionProof, SessionProofError};
 pub use substrings::{
-    SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError, SubstringsProofError,
+    PlaintextHash, SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError,
+    SubstringsProofError,
 };
 
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+use crate::{
+    json::{from_canonical_json, to_canonical_json, CanonicalJsonError},
+    RedactedTranscript,
+};
+
 /// Proof that a transcript of communications took place between a Prover and Server.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TlsProof {
@@ -19,3 +32,71 @@ pub struct TlsProof {
     /// Proof regarding the contents of the transcript.
     pub substrings: SubstringsProof,
 }
+
+impl TlsProof {
+    /// Encodes the proof as canonical JSON, see [`json`](crate::json).
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_canonical_json(self)
+    }
+
+    /// Decodes a proof from canonical JSON, rejecting any input which is not in canonical form.
+    pub fn from_canonical_json(json: &str) -> Result<Self, CanonicalJsonError> {
+        from_canonical_json(json)
+    }
+
+    /// Decodes a proof serialized with bincode by a release up to `0.1.0-alpha.6`, whose layout
+    /// is not versioned, see [`HeaderVersion::V0`](crate::session::HeaderVersion::V0).
+    pub fn from_v0_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        legacy::deserialize_v0(bytes)
+    }
+}
+
+/// The contents of a verified [`TlsProof`].
+#[derive(Debug)]
+pub struct VerifiedProof {
+    /// The id of the notary which signed the proof, if the proof was verified against a set of
+    /// named notaries, e.g. a trust store.
+    pub notary: Option<String>,
+    /// The name of the server.
+    pub server_name: String,
+    /// The time of the session, in seconds since the UNIX epoch.
+    pub time: u64,
+    /// The redacted transcript of the data sent to the server.
+    pub sent: RedactedTranscript,
+    /// The redacted transcript of the data received from the server.
+    pub recv: RedactedTranscript,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dry_run::{dry_run_notary_public_key, DryRun};
+
+    #[test]
+    fn test_canonical_json_round_trip() {
+        let mut dry_run = DryRun::new(b"GET / HTTP/1.1".to_vec(), b"HTTP/1.1 200 OK".to_vec());
+        let id = dry_run.commitment_builder().commit_sent(&(0..3)).unwrap();
+        let session = dry_run.finalize().unwrap();
+
+        let mut builder = session.data().build_substrings_proof();
+        builder.reveal_by_id(id).unwrap();
+        let proof = TlsProof {
+            session: session.session_proof(),
+            substrings: builder.build().unwrap(),
+        };
+
+        let json = proof.to_canonical_json().unwrap();
+        let decoded = TlsProof::from_canonical_json(&json).unwrap();
+        assert_eq!(decoded.to_canonical_json().unwrap(), json);
+
+        decoded
+            .session
+            .verify_with_default_cert_verifier(dry_run_notary_public_key())
+            .unwrap();
+        let (sent, _) = decoded.substrings.verify(&decoded.session.header).unwrap();
+        assert_eq!(&sent.data()[0..3], b"GET");
+
+        // Input which is not in canonical form is rejected.
+        assert!(TlsProof::from_canonical_json(&format!("{json} ")).is_err());
+    }
+}
@@ -0,0 +1,579 @@
+use std::collections::HashSet;
+
+use tls_core::verify::ServerCertVerifier;
+use utils::range::RangeDifference;
+
+use crate::{
+    proof::{SessionProofError, SubstringsProofError, TlsProof},
+    revocation::{RevocationError, SignedRevocationList},
+    session::SessionBinding,
+    Direction, NotaryPublicKey, RedactedTranscript, SignatureVerifyError,
+};
+
+/// An error for [`VerifierPolicy::evaluate`].
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    /// The proof is not signed by any of the notary keys accepted by the policy.
+    #[error("the proof is not signed by an accepted notary key")]
+    UntrustedNotary,
+    /// The session proof is invalid.
+    #[error(transparent)]
+    SessionProof(#[from] SessionProofError),
+    /// The substrings proof is invalid.
+    #[error(transparent)]
+    SubstringsProof(#[from] SubstringsProofError),
+    /// The session is revoked, or the revocation list is invalid.
+    #[error(transparent)]
+    Revoked(#[from] RevocationError),
+    /// The proof is valid, but violates the policy.
+    #[error("the proof violates the policy: {0:?}")]
+    Violations(Vec<PolicyViolation>),
+}
+
+/// A violation of a [`VerifierPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    /// The server name is not accepted.
+    #[error("server name is not accepted: {0}")]
+    ServerName(String),
+    /// The session was notarized before the start of the accepted time window.
+    #[error("session was notarized at {time}, before {not_before}")]
+    TooEarly {
+        /// The time of the session, in seconds since the UNIX epoch.
+        time: u64,
+        /// The start of the accepted time window, in seconds since the UNIX epoch.
+        not_before: u64,
+    },
+    /// The session was notarized after the end of the accepted time window.
+    #[error("session was notarized at {time}, after {not_after}")]
+    TooLate {
+        /// The time of the session, in seconds since the UNIX epoch.
+        time: u64,
+        /// The end of the accepted time window, in seconds since the UNIX epoch.
+        not_after: u64,
+    },
+    /// The notary key was older than the maximum age when the session was notarized.
+    #[error("notary key was {age}s old, exceeding the maximum of {max_age}s")]
+    NotaryKeyTooOld {
+        /// The age of the key, in seconds.
+        age: u64,
+        /// The maximum age of the key, in seconds.
+        max_age: u64,
+    },
+    /// A maximum age of the notary key is set, but the creation time of the key is unknown.
+    #[error("the creation time of the notary key is unknown, so its age can not be checked")]
+    NotaryKeyAgeUnknown,
+    /// A required header is not revealed with the expected value.
+    #[error("header {name:?} is not revealed with the expected value in the {direction:?} data")]
+    MissingHeader {
+        /// The direction of the transcript.
+        direction: Direction,
+        /// The name of the header.
+        name: String,
+    },
+    /// The session is not bound to the expected relying party and nonce.
+    #[error("session is not bound to the expected relying party and nonce")]
+    Binding,
+    /// Required data is not revealed.
+    #[error("required data is not revealed in the {direction:?} data")]
+    MissingData {
+        /// The direction of the transcript.
+        direction: Direction,
+        /// The required data.
+        data: Vec<u8>,
+    },
+}
+
+/// A notary key accepted by a [`VerifierPolicy`].
+#[derive(Debug, Clone)]
+struct AcceptedKey {
+    key: NotaryPublicKey,
+    /// The time the key was created, in seconds since the UNIX epoch, if known.
+    created_at: Option<u64>,
+}
+
+/// A declarative policy for accepting proofs.
+///
+/// The policy checks the authenticity of a [`TlsProof`] and additionally requires its contents to
+/// satisfy the configured rules, see [`VerifierPolicy::evaluate`].
+///
+/// # Example
+///
+/// ```ignore
+/// let policy = VerifierPolicy::new()
+///     .accept_notary_key(notary_key, key_created_at)
+///     .max_notary_key_age(90 * 24 * 60 * 60)
+///     .accept_server_name("example.com")
+///     .time_window(not_before, not_after)
+///     .require_header(Direction::Received, "Content-Type", "application/json");
+///
+/// let (sent, recv) = policy.evaluate(proof, &default_cert_verifier())?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VerifierPolicy {
+    notary_keys: Vec<AcceptedKey>,
+    max_notary_key_age: Option<u64>,
+    server_names: Option<HashSet<String>>,
+    not_before: Option<u64>,
+    not_after: Option<u64>,
+    headers: Vec<(Direction, String, String)>,
+    data: Vec<(Direction, Vec<u8>)>,
+    revocation_list: Option<SignedRevocationList>,
+    binding: Option<SessionBinding>,
+}
+
+impl VerifierPolicy {
+    /// Creates a new policy which accepts no notary keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts proofs signed by the provided notary key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The public key of the notary.
+    /// * `created_at` - The time the key was created, in seconds since the UNIX epoch, or `None`
+    ///   if it is unknown, in which case proofs signed by the key are rejected if a
+    ///   [maximum key age](Self::max_notary_key_age) is set.
+    pub fn accept_notary_key(
+        mut self,
+        key: impl Into<NotaryPublicKey>,
+        created_at: impl Into<Option<u64>>,
+    ) -> Self {
+        self.notary_keys.push(AcceptedKey {
+            key: key.into(),
+            created_at: created_at.into(),
+        });
+        self
+    }
+
+    /// Sets the maximum age of the notary key at the time of notarization, in seconds.
+    pub fn max_notary_key_age(mut self, max_age: u64) -> Self {
+        self.max_notary_key_age = Some(max_age);
+        self
+    }
+
+    /// Accepts proofs for the provided server name.
+    ///
+    /// If no server name is configured, proofs for any server are accepted.
+    pub fn accept_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_names
+            .get_or_insert_with(HashSet::default)
+            .insert(server_name.into());
+        self
+    }
+
+    /// Only accepts sessions notarized within the provided time window, in seconds since the UNIX
+    /// epoch, inclusive.
+    pub fn time_window(mut self, not_before: u64, not_after: u64) -> Self {
+        self.not_before = Some(not_before);
+        self.not_after = Some(not_after);
+        self
+    }
+
+    /// Requires an HTTP header to be revealed with the expected value.
+    ///
+    /// The header name is matched case-insensitively, and the entire header line must be
+    /// revealed.
+    pub fn require_header(
+        mut self,
+        direction: Direction,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.headers.push((direction, name.into(), value.into()));
+        self
+    }
+
+    /// Requires the provided data to be revealed.
+    pub fn require_data(mut self, direction: Direction, data: impl Into<Vec<u8>>) -> Self {
+        self.data.push((direction, data.into()));
+        self
+    }
+
+    /// Rejects sessions revoked by the provided revocation list, which must be signed by one of
+    /// the accepted notary keys.
+    pub fn revocation_list(mut self, revocation_list: SignedRevocationList) -> Self {
+        self.revocation_list = Some(revocation_list);
+        self
+    }
+
+    /// Requires the session to be bound to the provided audience and nonce, so a proof
+    /// generated for another relying party, or replayed with an old nonce, is rejected.
+    pub fn require_binding(mut self, binding: SessionBinding) -> Self {
+        self.binding = Some(binding);
+        self
+    }
+
+    /// Verifies the proof and evaluates the policy against it, returning the redacted sent and
+    /// received transcripts if the proof is accepted.
+    ///
+    /// All violations of the policy are reported at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof to evaluate.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    pub fn evaluate(
+        &self,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), PolicyError> {
+        let TlsProof {
+            session,
+            substrings,
+        } = proof;
+
+        let signature = session
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+        let msg = session.header.to_bytes();
+        let key = self
+            .notary_keys
+            .iter()
+            .find(|accepted| signature.verify(&msg, accepted.key.clone()).is_ok())
+            .ok_or(PolicyError::UntrustedNotary)?;
+
+        session.verify(key.key.clone(), cert_verifier)?;
+
+        if let Some(revocation_list) = &self.revocation_list {
+            // The list may be signed with another accepted key, e.g. after a key rotation.
+            let list = self
+                .notary_keys
+                .iter()
+                .find_map(|accepted| revocation_list.verify(accepted.key.clone()).ok())
+                .ok_or_else(|| {
+                    RevocationError::InvalidSignature(SignatureVerifyError(
+                        "the revocation list is not signed by an accepted notary key".to_string(),
+                    ))
+                })?;
+            list.check(&session.header, key.key.clone())?;
+        }
+        let (sent, recv) = substrings.verify(&session.header)?;
+
+        let violations = self.check(
+            session.session_info.server_name.as_ref(),
+            session.header.time(),
+            key.created_at,
+            session.header.binding(),
+            &sent,
+            &recv,
+        );
+
+        if violations.is_empty() {
+            Ok((sent, recv))
+        } else {
+            Err(PolicyError::Violations(violations))
+        }
+    }
+
+    /// Checks the contents of a verified proof against the policy.
+    fn check(
+        &self,
+        server_name: &str,
+        time: u64,
+        key_created_at: Option<u64>,
+        binding: Option<&SessionBinding>,
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+
+        if let Some(server_names) = &self.server_names {
+            if !server_names.contains(server_name) {
+                violations.push(PolicyViolation::ServerName(server_name.to_string()));
+            }
+        }
+
+        if let Some(not_before) = self.not_before {
+            if time < not_before {
+                violations.push(PolicyViolation::TooEarly { time, not_before });
+            }
+        }
+
+        if let Some(not_after) = self.not_after {
+            if time > not_after {
+                violations.push(PolicyViolation::TooLate { time, not_after });
+            }
+        }
+
+        if let Some(max_age) = self.max_notary_key_age {
+            match key_created_at {
+                Some(created_at) => {
+                    let age = time.saturating_sub(created_at);
+                    if age > max_age {
+                        violations.push(PolicyViolation::NotaryKeyTooOld { age, max_age });
+                    }
+                }
+                None => violations.push(PolicyViolation::NotaryKeyAgeUnknown),
+            }
+        }
+
+        if let Some(expected) = &self.binding {
+            if binding != Some(expected) {
+                violations.push(PolicyViolation::Binding);
+            }
+        }
+
+        let transcript = |direction| match direction {
+            Direction::Sent => sent,
+            Direction::Received => recv,
+        };
+
+        for (direction, name, value) in &self.headers {
+            if !has_header(transcript(*direction), name, value) {
+                violations.push(PolicyViolation::MissingHeader {
+                    direction: *direction,
+                    name: name.clone(),
+                });
+            }
+        }
+
+        for (direction, data) in &self.data {
+            if !has_data(transcript(*direction), data) {
+                violations.push(PolicyViolation::MissingData {
+                    direction: *direction,
+                    data: data.clone(),
+                });
+            }
+        }
+
+        violations
+    }
+}
+
+/// Returns `true` if the range of the transcript is fully revealed.
+fn is_revealed(transcript: &RedactedTranscript, start: usize, end: usize) -> bool {
+    (start..end).difference(transcript.authed()).is_empty()
+}
+
+/// Returns `true` if the data is revealed anywhere in the transcript.
+fn has_data(transcript: &RedactedTranscript, data: &[u8]) -> bool {
+    if data.is_empty() {
+        return true;
+    }
+
+    transcript
+        .data()
+        .windows(data.len())
+        .enumerate()
+        .any(|(idx, window)| window == data && is_revealed(transcript, idx, idx + data.len()))
+}
+
+/// Returns `true` if a revealed line of the header section of the transcript is the header with
+/// the provided value.
+///
+/// Only the lines between the start line and the first revealed empty line are considered, so a
+/// line of the body can not be passed off as a header. The line must be revealed along with the
+/// line breaks delimiting it.
+fn has_header(transcript: &RedactedTranscript, name: &str, value: &str) -> bool {
+    let data = transcript.data();
+
+    let Some(headers_end) = data
+        .windows(4)
+        .enumerate()
+        .position(|(idx, window)| window == b"\r\n\r\n" && is_revealed(transcript, idx, idx + 4))
+    else {
+        return false;
+    };
+    // Includes the line break of the last header.
+    let data = &data[..headers_end + 2];
+
+    let Some(mut start) = data
+        .windows(2)
+        .position(|window| window == b"\r\n")
+        .map(|pos| pos + 2)
+    else {
+        return false;
+    };
+
+    while start < data.len() {
+        let end = data[start..]
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .map(|pos| start + pos)
+            .unwrap_or(data.len());
+
+        let line = &data[start..end];
+        if let Some(colon) = line.iter().position(|byte| *byte == b':') {
+            let (line_name, line_value) = (&line[..colon], &line[colon + 1..]);
+            if line_name.eq_ignore_ascii_case(name.as_bytes())
+                && trim(line_value) == value.as_bytes()
+                && is_revealed(transcript, start - 2, end + 2)
+            {
+                return true;
+            }
+        }
+
+        start = end + 2;
+    }
+
+    false
+}
+
+/// Trims leading and trailing optional whitespace from a header value.
+fn trim(mut value: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = value {
+        value = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = value {
+        value = rest;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TranscriptSlice;
+
+    const RESPONSE: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: secret\r\n\r\n{}";
+
+    fn redacted(data: &[u8], ranges: &[std::ops::Range<usize>]) -> RedactedTranscript {
+        RedactedTranscript::new(
+            data.len(),
+            ranges
+                .iter()
+                .map(|range| TranscriptSlice::new(range.clone(), data[range.clone()].to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_policy_accepts() {
+        let policy = VerifierPolicy::new()
+            .accept_server_name("example.com")
+            .time_window(100, 200)
+            .max_notary_key_age(50)
+            .require_header(Direction::Received, "content-type", "application/json")
+            .require_data(Direction::Received, b"200 OK".to_vec());
+
+        let sent = redacted(b"GET / HTTP/1.1\r\n\r\n", &[0..18]);
+        // Everything but the cookie is revealed.
+        let recv = redacted(RESPONSE, &[0..61, 67..RESPONSE.len()]);
+
+        assert!(policy
+            .check("example.com", 150, Some(120), None, &sent, &recv)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_policy_reports_all_violations() {
+        let policy = VerifierPolicy::new()
+            .accept_server_name("example.com")
+            .time_window(100, 200)
+            .max_notary_key_age(50)
+            .require_header(Direction::Received, "Set-Cookie", "secret")
+            .require_data(Direction::Sent, b"Authorization".to_vec());
+
+        let sent = redacted(b"GET / HTTP/1.1\r\n\r\n", &[0..18]);
+        let recv = redacted(RESPONSE, &[0..61, 67..RESPONSE.len()]);
+
+        assert_eq!(
+            policy.check("example.org", 250, Some(120), None, &sent, &recv),
+            vec![
+                PolicyViolation::ServerName("example.org".to_string()),
+                PolicyViolation::TooLate {
+                    time: 250,
+                    not_after: 200
+                },
+                PolicyViolation::NotaryKeyTooOld {
+                    age: 130,
+                    max_age: 50
+                },
+                PolicyViolation::MissingHeader {
+                    direction: Direction::Received,
+                    name: "Set-Cookie".to_string(),
+                },
+                PolicyViolation::MissingData {
+                    direction: Direction::Sent,
+                    data: b"Authorization".to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy_unknown_key_age() {
+        let sent = redacted(b"GET / HTTP/1.1\r\n\r\n", &[0..18]);
+        let recv = redacted(RESPONSE, &[0..RESPONSE.len()]);
+
+        assert!(VerifierPolicy::new()
+            .check("example.com", 150, None, None, &sent, &recv)
+            .is_empty());
+        assert_eq!(
+            VerifierPolicy::new().max_notary_key_age(50).check(
+                "example.com",
+                150,
+                None,
+                None,
+                &sent,
+                &recv
+            ),
+            vec![PolicyViolation::NotaryKeyAgeUnknown]
+        );
+    }
+
+    #[test]
+    fn test_policy_header_in_body() {
+        let policy = VerifierPolicy::new().require_header(
+            Direction::Received,
+            "Content-Type",
+            "application/json",
+        );
+
+        // The body contains a line which looks like the header.
+        let response = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n\
+            Content-Type: application/json\r\n";
+        let sent = redacted(b"GET / HTTP/1.1\r\n\r\n", &[0..18]);
+        let violation = vec![PolicyViolation::MissingHeader {
+            direction: Direction::Received,
+            name: "Content-Type".to_string(),
+        }];
+
+        // Everything is revealed.
+        let recv = redacted(response, &[0..response.len()]);
+        assert_eq!(
+            policy.check("example.com", 150, Some(120), None, &sent, &recv),
+            violation
+        );
+
+        // The end of the headers is hidden.
+        let recv = redacted(response, &[0..41, 45..response.len()]);
+        assert_eq!(
+            policy.check("example.com", 150, Some(120), None, &sent, &recv),
+            violation
+        );
+
+        // The line breaks delimiting the header are hidden.
+        let recv = redacted(RESPONSE, &[0..47, 49..RESPONSE.len()]);
+        assert_eq!(
+            policy.check("example.com", 150, Some(120), None, &sent, &recv),
+            violation
+        );
+    }
+
+    #[test]
+    fn test_policy_binding() {
+        let binding = SessionBinding::new("https://example.com", b"nonce".to_vec()).unwrap();
+        let policy = VerifierPolicy::new().require_binding(binding.clone());
+
+        let sent = redacted(b"GET / HTTP/1.1\r\n\r\n", &[0..18]);
+        let recv = redacted(RESPONSE, &[0..RESPONSE.len()]);
+
+        assert!(policy
+            .check("example.com", 150, Some(120), Some(&binding), &sent, &recv)
+            .is_empty());
+
+        let replayed = SessionBinding::new("https://example.org", b"nonce".to_vec()).unwrap();
+        for binding in [None, Some(&replayed)] {
+            assert_eq!(
+                policy.check("example.com", 150, Some(120), binding, &sent, &recv),
+                vec![PolicyViolation::Binding]
+            );
+        }
+    }
+}
@@ -2,7 +2,7 @@ use web_time::{Duration, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
-use mpz_core::{commit::Decommitment, serialize::CanonicalSerialize};
+use mpz_core::commit::Decommitment;
 use tls_core::{
     anchors::{OwnedTrustAnchor, RootCertStore},
     dns::ServerName as TlsServerName,
@@ -11,6 +11,8 @@ use tls_core::{
 };
 
 use crate::{
+    did::{DidError, DidResolver},
+    proof::HandshakeEvidence,
     session::SessionHeader,
     signature::{Signature, SignatureVerifyError},
     HandshakeSummary, NotaryPublicKey, ServerName,
@@ -29,12 +31,26 @@ pub enum SessionProofError {
     /// Invalid server name.
     #[error("invalid server name: {0}")]
     InvalidServerName(String),
+    /// The server name is not the one the Prover declared to the Notary.
+    #[error("server name {proven} is not the server name {declared} declared to the notary")]
+    ServerNameMismatch {
+        /// The server name declared to the Notary.
+        declared: String,
+        /// The server name of the proof.
+        proven: String,
+    },
     /// Invalid handshake
     #[error("handshake verification failed: {0}")]
     InvalidHandshake(String),
     /// Invalid server certificate
     #[error("server certificate verification failed: {0}")]
     InvalidServerCertificate(String),
+    /// Invalid root certificate store
+    #[error("invalid root certificate store: {0}")]
+    InvalidRootStore(String),
+    /// The public key of the notary could not be resolved from its DID
+    #[error(transparent)]
+    DidResolution(#[from] DidError),
 }
 
 /// A session proof which is created from a [crate::session::NotarizedSession]
@@ -53,6 +69,9 @@ pub struct SessionProof {
 impl SessionProof {
     /// Verify the session proof.
     ///
+    /// If the Prover declared the DNS name of the server to the Notary, the server name of the
+    /// proof must be the declared one.
+    ///
     /// # Arguments
     ///
     /// * `notary_public_key` - The public key of the notary.
@@ -69,12 +88,48 @@ impl SessionProof {
             .ok_or(SessionProofError::MissingNotarySignature)?;
 
         signature.verify(&self.header.to_bytes(), notary_public_key)?;
+
+        if let Some(declared) = self.header.server_name() {
+            let proven = self.session_info.server_name.as_str();
+            if !declared
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(proven.trim_end_matches('.'))
+            {
+                return Err(SessionProofError::ServerNameMismatch {
+                    declared: declared.to_string(),
+                    proven: proven.to_string(),
+                });
+            }
+        }
+
         self.session_info
             .verify(self.header.handshake_summary(), cert_verifier)?;
 
         Ok(())
     }
 
+    /// Verify the session proof with the public key of the notary resolved from the DID in the
+    /// session header.
+    ///
+    /// The proof is rejected if the DID is not one of the trusted DIDs, as anyone can sign a
+    /// header with the key of a DID of their own, see [`DidResolver::resolve_header`].
+    ///
+    /// # Arguments
+    ///
+    /// * `trusted_dids` - The DIDs of the trusted notaries.
+    /// * `resolver` - The resolver of the DID of the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    pub fn verify_with_resolver(
+        &self,
+        trusted_dids: &[&str],
+        resolver: &impl DidResolver,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(), SessionProofError> {
+        let notary_public_key = resolver.resolve_header(&self.header, trusted_dids)?;
+
+        self.verify(notary_public_key, cert_verifier)
+    }
+
     /// Verify the session proof using trust anchors from the `webpki-roots` crate.
     ///
     /// # Arguments
@@ -86,6 +141,27 @@ impl SessionProof {
     ) -> Result<(), SessionProofError> {
         self.verify(notary_public_key, &default_cert_verifier())
     }
+
+    /// Returns the evidence of the TLS handshake, which should only be used after the proof is
+    /// verified.
+    pub fn evidence(&self) -> Result<HandshakeEvidence, SessionProofError> {
+        self.session_info.evidence(self.header.handshake_summary())
+    }
+
+    /// Returns the serialized size, in bytes, of a session proof with the provided header and
+    /// session info, signed by the Notary with a P-256 key.
+    ///
+    /// This is used to estimate the size of a proof before the Notary signs the header.
+    pub fn estimate_size(header: &SessionHeader, session_info: &SessionInfo) -> usize {
+        // The size of a signature does not depend on its value.
+        let signature = Signature::P256(
+            p256::ecdsa::Signature::from_slice(&[1; 64]).expect("signature is valid"),
+        );
+
+        // A struct is serialized as the tuple of its fields.
+        bincode::serialized_size(&(header, Some(&signature), session_info))
+            .expect("session proof is serializable") as usize
+    }
 }
 
 /// Contains information about the session
@@ -110,9 +186,9 @@ impl SessionInfo {
         let server_name = TlsServerName::try_from(self.server_name.as_ref())
             .map_err(|e| SessionProofError::InvalidServerName(e.to_string()))?;
 
-        // Verify handshake
-        self.handshake_decommitment
-            .verify(handshake_summary.handshake_commitment())
+        // Verify handshake, including that the server ephemeral key is the one the Notary signed
+        handshake_summary
+            .verify(&self.handshake_decommitment)
             .map_err(|e| SessionProofError::InvalidHandshake(e.to_string()))?;
 
         // Verify server certificate
@@ -139,6 +215,23 @@ impl SessionInfo {
     ) -> Result<(), SessionProofError> {
         self.verify(handshake_summary, &default_cert_verifier())
     }
+
+    /// Returns the evidence of the TLS handshake, which should only be used after the session
+    /// info is verified.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake_summary` - The summary of the handshake.
+    pub fn evidence(
+        &self,
+        handshake_summary: &HandshakeSummary,
+    ) -> Result<HandshakeEvidence, SessionProofError> {
+        HandshakeEvidence::new(
+            &self.server_name,
+            handshake_summary,
+            self.handshake_decommitment.data(),
+        )
+    }
 }
 
 /// Create a new [`WebPkiVerifier`] with the default trust anchors from the `webpki-roots` crate.
@@ -160,8 +253,17 @@ mod tests {
     use super::*;
     use rstest::*;
 
-    use crate::fixtures::cert::{appliedzkp, tlsnotary, TestData};
-    use tls_core::{dns::ServerName, key::Certificate};
+    use crate::{
+        fixtures::cert::{appliedzkp, tlsnotary, TestData},
+        proof::cert_verifier_from_roots,
+    };
+    use mpz_core::commit::HashCommit;
+    use tls_core::{
+        dns::ServerName,
+        key::Certificate,
+        msgs::enums::{NamedGroup, SignatureScheme},
+        ocsp::OcspPolicy,
+    };
     use web_time::SystemTime;
 
     /// Expect chain verification to succeed
@@ -336,4 +438,163 @@ mod tests {
             )
             .is_err());
     }
+
+    fn session_info() -> SessionInfo {
+        let (handshake_decommitment, _) = crate::fixtures::handshake_data().hash_commit();
+
+        SessionInfo {
+            server_name: crate::ServerName::Dns("tlsnotary.org".to_string()),
+            handshake_decommitment,
+        }
+    }
+
+    fn handshake_summary(info: &SessionInfo) -> HandshakeSummary {
+        HandshakeSummary::new(
+            tlsnotary().time,
+            crate::fixtures::server_ephemeral_key(),
+            info.handshake_decommitment.commit(),
+        )
+    }
+
+    /// Expect the session info to verify against a root store with the CA cert
+    #[test]
+    fn test_verify_session_info_with_roots() {
+        let info = session_info();
+        let summary = handshake_summary(&info);
+
+        let cert_verifier = cert_verifier_from_roots(&[tlsnotary().ca.0]).unwrap();
+        info.verify(&summary, &cert_verifier).unwrap();
+
+        // The end-entity cert is not a root of any chain
+        let cert_verifier = cert_verifier_from_roots(&[tlsnotary().inter.0]).unwrap();
+        assert!(matches!(
+            info.verify(&summary, &cert_verifier),
+            Err(SessionProofError::InvalidServerCertificate(_))
+        ));
+    }
+
+    /// Expect to fail because the server ephemeral key is not the one the Notary signed
+    #[test]
+    fn test_verify_session_info_fail_bad_ephemeral_key() {
+        let info = session_info();
+        let summary = HandshakeSummary::new(
+            tlsnotary().time,
+            appliedzkp().pubkey,
+            info.handshake_decommitment.commit(),
+        );
+
+        assert!(matches!(
+            info.verify_with_default_cert_verifier(&summary),
+            Err(SessionProofError::InvalidHandshake(_))
+        ));
+    }
+
+    #[test]
+    fn test_handshake_evidence() {
+        let info = session_info();
+        let summary = handshake_summary(&info);
+
+        let evidence = info.evidence(&summary).unwrap();
+
+        assert_eq!(evidence.server_name, info.server_name);
+        assert_eq!(
+            evidence.certificate_chain,
+            vec![tlsnotary().ee.0, tlsnotary().inter.0, tlsnotary().ca.0]
+        );
+        assert_eq!(evidence.ocsp_response, None);
+        assert!(evidence.scts.is_empty());
+        assert_eq!(
+            evidence.params,
+            crate::proof::VerificationParams {
+                time: tlsnotary().time,
+                signature_scheme: SignatureScheme::RSA_PKCS1_SHA256,
+                named_group: NamedGroup::secp256r1,
+            }
+        );
+    }
+
+    /// Expect to fail when an OCSP response is required but the server did not staple one
+    #[test]
+    fn test_verify_session_info_ocsp_policy() {
+        let info = session_info();
+        let summary = handshake_summary(&info);
+
+        let cert_verifier = cert_verifier_from_roots(&[tlsnotary().ca.0])
+            .unwrap()
+            .with_ocsp_policy(OcspPolicy::VerifyIfPresent);
+        info.verify(&summary, &cert_verifier).unwrap();
+
+        let cert_verifier = cert_verifier_from_roots(&[tlsnotary().ca.0])
+            .unwrap()
+            .with_ocsp_policy(OcspPolicy::Require);
+        assert!(matches!(
+            info.verify(&summary, &cert_verifier),
+            Err(SessionProofError::InvalidServerCertificate(_))
+        ));
+    }
+
+    /// Expect the proof to only verify for the server name declared to the Notary
+    #[test]
+    fn test_verify_declared_server_name() {
+        use p256::ecdsa::signature::Signer;
+
+        let signed_proof = |server_name: &str| {
+            let session_info = session_info();
+            let header = SessionHeader::new(
+                crate::fixtures::encoder_seed(),
+                [0; 32].into(),
+                0,
+                0,
+                handshake_summary(&session_info),
+            )
+            .with_server_name(server_name);
+            let signature: p256::ecdsa::Signature =
+                crate::fixtures::notary_signing_key().sign(&header.to_bytes());
+
+            SessionProof {
+                header,
+                signature: Some(signature.into()),
+                session_info,
+            }
+        };
+        let notary_public_key =
+            p256::PublicKey::from(crate::fixtures::notary_signing_key().verifying_key());
+        let cert_verifier = cert_verifier_from_roots(&[tlsnotary().ca.0]).unwrap();
+
+        signed_proof("TLSNotary.org.")
+            .verify(notary_public_key, &cert_verifier)
+            .unwrap();
+
+        assert!(matches!(
+            signed_proof("appliedzkp.org").verify(notary_public_key, &cert_verifier),
+            Err(SessionProofError::ServerNameMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_root_store() {
+        assert!(matches!(
+            cert_verifier_from_roots(&[vec![0u8; 8]]),
+            Err(SessionProofError::InvalidRootStore(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimate_size() {
+        use p256::ecdsa::signature::Signer;
+
+        let header = crate::fixtures::session_header([0; 32].into(), 16, 32);
+        let session_info = session_info();
+        let estimate = SessionProof::estimate_size(&header, &session_info);
+
+        let signature: p256::ecdsa::Signature =
+            crate::fixtures::notary_signing_key().sign(&header.to_bytes());
+        let proof = SessionProof {
+            header,
+            signature: Some(signature.into()),
+            session_info,
+        };
+
+        assert_eq!(estimate, bincode::serialize(&proof).unwrap().len());
+    }
 }
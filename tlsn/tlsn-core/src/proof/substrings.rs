@@ -2,16 +2,15 @@
 
 use crate::{
     commitment::{
+        hash::{hash_plaintext, SALT_LEN},
         Commitment, CommitmentId, CommitmentInfo, CommitmentKind, CommitmentOpening,
-        TranscriptCommitments,
+        ConstraintContext, ConstraintError, TranscriptCommitments,
     },
     merkle::MerkleProof,
-    transcript::get_value_ids,
-    Direction, EncodingId, RedactedTranscript, SessionHeader, Transcript, TranscriptSlice,
+    Direction, RedactedTranscript, SessionHeader, Transcript, TranscriptSlice,
     MAX_TOTAL_COMMITTED_DATA,
 };
-use mpz_circuits::types::ValueType;
-use mpz_garble_core::Encoder;
+use mpz_core::hash::Hash;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use utils::range::{RangeDisjoint, RangeSet, RangeUnion, ToRangeSet};
@@ -112,25 +111,23 @@ impl<'a> SubstringsProofBuilder<'a> {
             .get_info(&id)
             .expect("info exists if commitment exists");
 
-        #[allow(irrefutable_let_patterns)]
-        let Commitment::Blake3(commitment) = commitment
-        else {
-            return Err(SubstringsProofBuilderError::InvalidCommitmentType(id));
-        };
+        let opening = match commitment {
+            Commitment::Blake3(commitment) => {
+                let transcript = match info.direction() {
+                    Direction::Sent => self.transcript_tx,
+                    Direction::Received => self.transcript_rx,
+                };
 
-        let transcript = match info.direction() {
-            Direction::Sent => self.transcript_tx,
-            Direction::Received => self.transcript_rx,
-        };
+                let data = transcript.get_bytes_in_ranges(info.ranges());
 
-        let data = transcript.get_bytes_in_ranges(info.ranges());
+                commitment.open(data).into()
+            }
+            // Only the hash is revealed.
+            Commitment::PlaintextHash(commitment) => commitment.open().into(),
+        };
 
         // add commitment to openings and return an error if it is already present
-        if self
-            .openings
-            .insert(id, (info.clone(), commitment.open(data).into()))
-            .is_some()
-        {
+        if self.openings.insert(id, (info.clone(), opening)).is_some() {
             return Err(SubstringsProofBuilderError::DuplicateCommitmentId(id));
         }
 
@@ -182,6 +179,51 @@ pub enum SubstringsProofError {
     /// The proof contains an invalid inclusion proof.
     #[error("invalid inclusion proof: {0}")]
     InvalidInclusionProof(String),
+    /// The constraints of an opened commitment are violated.
+    #[error("constraints of commitment {0:?} are violated: {1}")]
+    ConstraintViolation(CommitmentId, ConstraintError),
+    /// A narrowed proof would not open any commitment.
+    #[error("a narrowed proof must open at least one commitment")]
+    NoOpenings,
+    /// A plaintext hash commitment was not proven to the Notary.
+    #[error("plaintext hash commitment {0:?} was not proven to the notary")]
+    UnprovenPlaintextHash(CommitmentId),
+}
+
+/// A salted hash of substrings of a transcript, opened by a [`SubstringsProof`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaintextHash {
+    direction: Direction,
+    ranges: RangeSet<usize>,
+    hash: Hash,
+    salt: [u8; SALT_LEN],
+}
+
+impl PlaintextHash {
+    /// Returns the direction of the transcript.
+    pub fn direction(&self) -> &Direction {
+        &self.direction
+    }
+
+    /// Returns the ranges of the transcript.
+    pub fn ranges(&self) -> &RangeSet<usize> {
+        &self.ranges
+    }
+
+    /// Returns the salted SHA-256 hash of the data in the ranges.
+    pub fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// Returns the salt of the hash.
+    pub fn salt(&self) -> &[u8; SALT_LEN] {
+        &self.salt
+    }
+
+    /// Returns `true` if the provided data matches the hash.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        hash_plaintext(&self.salt, data) == self.hash
+    }
 }
 
 /// A substring proof using commitments
@@ -197,94 +239,166 @@ pub struct SubstringsProof {
 opaque_debug::implement!(SubstringsProof);
 
 impl SubstringsProof {
-    /// Verifies this proof and, if successful, returns the redacted sent and received transcripts.
+    /// Creates a proof from its openings and the proof of their inclusion in the Merkle tree.
+    pub(crate) fn from_parts(
+        openings: HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
+        inclusion_proof: MerkleProof,
+    ) -> Self {
+        Self {
+            openings,
+            inclusion_proof,
+        }
+    }
+
+    /// Returns an iterator over the ids and info of the opened commitments.
+    pub fn opened(&self) -> impl Iterator<Item = (&CommitmentId, &CommitmentInfo)> {
+        self.openings.iter().map(|(id, (info, _))| (id, info))
+    }
+
+    /// Derives a narrower proof which only opens the commitments for which `keep` returns `true`.
+    ///
+    /// The inclusion proof is recomputed from the openings of this proof, so a holder of a proof
+    /// can redact more of the transcript without the original session data or the Notary.
     ///
     /// # Arguments
     ///
     /// * `header` - The session header.
-    pub fn verify(
+    /// * `keep` - Returns whether the opening of a commitment is kept.
+    pub fn narrow<F>(
         self,
         header: &SessionHeader,
-    ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        mut keep: F,
+    ) -> Result<SubstringsProof, SubstringsProofError>
+    where
+        F: FnMut(&CommitmentId, &CommitmentInfo) -> bool,
+    {
         let Self {
             openings,
             inclusion_proof,
         } = self;
 
+        check_openings(header, &openings)?;
+
         let mut indices = Vec::with_capacity(openings.len());
-        let mut expected_hashes = Vec::with_capacity(openings.len());
-        let mut sent = vec![0u8; header.sent_len()];
-        let mut recv = vec![0u8; header.recv_len()];
-        let mut sent_ranges = RangeSet::default();
-        let mut recv_ranges = RangeSet::default();
-        let mut total_opened = 0u128;
+        let mut hashes = Vec::with_capacity(openings.len());
+        let mut retained = Vec::new();
+        let mut narrowed = HashMap::default();
         for (id, (info, opening)) in openings {
-            let CommitmentInfo {
-                ranges, direction, ..
-            } = info;
-
-            let opened_len = ranges.len();
+            indices.push(id.to_inner() as usize);
+            hashes.push(expected_leaf(header, id, &info, &opening)?);
 
-            // Make sure the amount of data being proved is bounded.
-            total_opened += opened_len as u128;
-            if total_opened > MAX_TOTAL_COMMITTED_DATA as u128 {
-                return Err(SubstringsProofError::MaxDataExceeded(total_opened as usize));
+            if keep(&id, &info) {
+                retained.push(id.to_inner() as usize);
+                narrowed.insert(id, (info, opening));
             }
+        }
 
-            // Make sure the opening length matches the ranges length.
-            if opening.data().len() != opened_len {
-                return Err(SubstringsProofError::InvalidOpening(id));
-            }
+        if narrowed.is_empty() {
+            return Err(SubstringsProofError::NoOpenings);
+        }
 
-            // Make sure duplicate data is not opened.
-            match direction {
-                Direction::Sent => {
-                    if !sent_ranges.is_disjoint(&ranges) {
-                        return Err(SubstringsProofError::DuplicateData(direction, ranges));
-                    }
-                    sent_ranges = sent_ranges.union(&ranges);
-                }
-                Direction::Received => {
-                    if !recv_ranges.is_disjoint(&ranges) {
-                        return Err(SubstringsProofError::DuplicateData(direction, ranges));
-                    }
-                    recv_ranges = recv_ranges.union(&ranges);
-                }
-            }
+        let inclusion_proof = inclusion_proof
+            .narrow(&indices, &hashes, &retained)
+            .map_err(|e| SubstringsProofError::InvalidInclusionProof(e.to_string()))?;
 
-            // Make sure the ranges are within the bounds of the transcript
-            let max = ranges
-                .max()
-                .ok_or(SubstringsProofError::InvalidOpening(id))?;
-            let transcript_len = match direction {
-                Direction::Sent => header.sent_len(),
-                Direction::Received => header.recv_len(),
-            };
+        Ok(Self {
+            openings: narrowed,
+            inclusion_proof,
+        })
+    }
 
-            if max > transcript_len {
-                return Err(SubstringsProofError::RangeOutOfBounds(id, max));
-            }
+    /// Verifies this proof and, if successful, returns the redacted sent and received transcripts.
+    ///
+    /// Proofs opening constrained commitments are rejected, use [`SubstringsProof::verify_with_context`]
+    /// to verify them.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    pub fn verify(
+        self,
+        header: &SessionHeader,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        self.verify_with_context(header, &ConstraintContext::default())
+    }
 
-            // Generate the expected encodings for the purported data in the opening.
-            let encodings = get_value_ids(&ranges, direction)
-                .map(|id| {
-                    header
-                        .encoder()
-                        .encode_by_type(EncodingId::new(&id).to_inner(), &ValueType::U8)
-                })
-                .collect::<Vec<_>>();
+    /// Verifies this proof, checking the constraints of the opened commitments against the provided
+    /// context and, if successful, returns the redacted sent and received transcripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `context` - The context in which the proof is verified.
+    pub fn verify_with_context(
+        self,
+        header: &SessionHeader,
+        context: &ConstraintContext,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), SubstringsProofError> {
+        self.verify_with_hashes(header, context)
+            .map(|(sent, recv, _)| (sent, recv))
+    }
+
+    /// Verifies this proof like [`SubstringsProof::verify_with_context`] and, if successful,
+    /// additionally returns the plaintext hashes opened by the proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `context` - The context in which the proof is verified.
+    pub fn verify_with_hashes(
+        self,
+        header: &SessionHeader,
+        context: &ConstraintContext,
+    ) -> Result<(RedactedTranscript, RedactedTranscript, Vec<PlaintextHash>), SubstringsProofError>
+    {
+        let Self {
+            openings,
+            inclusion_proof,
+        } = self;
 
+        for (id, (info, _)) in &openings {
+            info.constraints
+                .check(context)
+                .map_err(|e| SubstringsProofError::ConstraintViolation(*id, e))?;
+        }
+
+        // The openings are checked before any commitment is recovered, which requires the
+        // encodings of the opened data.
+        let (sent_ranges, recv_ranges) = check_openings(header, &openings)?;
+
+        let mut indices = Vec::with_capacity(openings.len());
+        let mut expected_hashes = Vec::with_capacity(openings.len());
+        let mut sent = vec![0u8; header.sent_len()];
+        let mut recv = vec![0u8; header.recv_len()];
+        let mut plaintext_hashes = Vec::new();
+        for (id, (info, opening)) in openings {
             // Compute the expected hash of the commitment to make sure it is
             // present in the merkle tree.
+            let leaf = expected_leaf(header, id, &info, &opening)?;
             indices.push(id.to_inner() as usize);
-            expected_hashes.push(opening.recover(&encodings).hash());
+            expected_hashes.push(leaf);
 
-            // Make sure the length of data from the opening matches the commitment.
-            let mut data = opening.into_data();
-            if data.len() != ranges.len() {
-                return Err(SubstringsProofError::InvalidOpening(id));
+            let CommitmentInfo {
+                ranges, direction, ..
+            } = info;
+
+            // Hash openings do not reveal any data. Their hash is only authentic if the Notary
+            // checked it against the transcript, which it attests to by binding the leaf to the
+            // header.
+            if let CommitmentOpening::PlaintextHash(opening) = opening {
+                if !header.plaintext_hashes().contains(&leaf) {
+                    return Err(SubstringsProofError::UnprovenPlaintextHash(id));
+                }
+                plaintext_hashes.push(PlaintextHash {
+                    direction,
+                    ranges,
+                    hash: *opening.hash(),
+                    salt: *opening.salt(),
+                });
+                continue;
             }
 
+            let mut data = opening.into_data();
             let dest = match direction {
                 Direction::Sent => &mut sent,
                 Direction::Received => &mut recv,
@@ -321,6 +435,176 @@ impl SubstringsProof {
         Ok((
             RedactedTranscript::new(header.sent_len(), sent_slices),
             RedactedTranscript::new(header.recv_len(), recv_slices),
+            plaintext_hashes,
         ))
     }
 }
+
+/// Checks that the openings are within the bounds of the transcript, that they do not open more
+/// than [`MAX_TOTAL_COMMITTED_DATA`] bytes or the same data twice, and that their data matches the
+/// length of their ranges.
+///
+/// Returns the union of the ranges opened in each direction.
+fn check_openings(
+    header: &SessionHeader,
+    openings: &HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
+) -> Result<(RangeSet<usize>, RangeSet<usize>), SubstringsProofError> {
+    let mut sent_ranges = RangeSet::default();
+    let mut recv_ranges = RangeSet::default();
+    let mut total_opened = 0u128;
+    for (&id, (info, opening)) in openings {
+        // Make sure the ranges are within the bounds of the transcript
+        let max = info
+            .ranges
+            .max()
+            .ok_or(SubstringsProofError::InvalidOpening(id))?;
+        let transcript_len = match info.direction {
+            Direction::Sent => header.sent_len(),
+            Direction::Received => header.recv_len(),
+        };
+
+        if max > transcript_len {
+            return Err(SubstringsProofError::RangeOutOfBounds(id, max));
+        }
+
+        if let CommitmentOpening::PlaintextHash(_) = opening {
+            continue;
+        }
+
+        let ranges = &info.ranges;
+
+        // Make sure the amount of data being proved is bounded.
+        total_opened += ranges.len() as u128;
+        if total_opened > MAX_TOTAL_COMMITTED_DATA as u128 {
+            return Err(SubstringsProofError::MaxDataExceeded(total_opened as usize));
+        }
+
+        // Make sure duplicate data is not opened.
+        let opened = match info.direction {
+            Direction::Sent => &mut sent_ranges,
+            Direction::Received => &mut recv_ranges,
+        };
+        if !opened.is_disjoint(ranges) {
+            return Err(SubstringsProofError::DuplicateData(
+                info.direction,
+                ranges.clone(),
+            ));
+        }
+        *opened = opened.union(ranges);
+
+        // Make sure the length of data from the opening matches the commitment.
+        if opening.data().len() != ranges.len() {
+            return Err(SubstringsProofError::InvalidOpening(id));
+        }
+    }
+
+    Ok((sent_ranges, recv_ranges))
+}
+
+/// Returns the expected Merkle leaf of the commitment opened by `opening`.
+fn expected_leaf(
+    header: &SessionHeader,
+    id: CommitmentId,
+    info: &CommitmentInfo,
+    opening: &CommitmentOpening,
+) -> Result<Hash, SubstringsProofError> {
+    let commitment = opening
+        .recover_expected(header, info)
+        .ok_or(SubstringsProofError::InvalidOpening(id))?;
+
+    Ok(info.leaf(commitment.hash()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commitment::TranscriptCommitmentBuilder, dry_run::DryRun, fixtures, msg::PlaintextHashes,
+    };
+
+    #[test]
+    fn test_narrow() {
+        let mut dry_run = DryRun::new(b"GET / HTTP/1.1".to_vec(), b"HTTP/1.1 200 OK".to_vec());
+        let sent_id = dry_run.commitment_builder().commit_sent(&(0..3)).unwrap();
+        let recv_id = dry_run.commitment_builder().commit_recv(&(9..12)).unwrap();
+        let session = dry_run.finalize().unwrap();
+        let header = session.header();
+
+        let prove = || {
+            let mut builder = session.data().build_substrings_proof();
+            builder
+                .reveal_by_id(sent_id)
+                .unwrap()
+                .reveal_by_id(recv_id)
+                .unwrap();
+            builder.build().unwrap()
+        };
+
+        let narrowed = prove().narrow(header, |id, _| *id == sent_id).unwrap();
+        assert_eq!(narrowed.opened().count(), 1);
+
+        let (sent, recv) = narrowed.verify(header).unwrap();
+        assert_eq!(&sent.data()[0..3], b"GET");
+        assert_eq!(recv.authed().iter_ranges().count(), 0);
+
+        assert!(matches!(
+            prove().narrow(header, |_, _| false),
+            Err(SubstringsProofError::NoOpenings)
+        ));
+    }
+
+    /// Tests that a proof can not open a plaintext hash commitment which the Prover did not
+    /// prove to the Notary
+    #[test]
+    fn test_unproven_plaintext_hash() {
+        let data_sent = b"sent data";
+        let data_recv = b"received data";
+        let transcript_tx = Transcript::new(data_sent.to_vec());
+        let transcript_rx = Transcript::new(data_recv.to_vec());
+
+        let mut commitment_builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(data_sent, data_recv),
+            data_sent.len(),
+            data_recv.len(),
+        )
+        .with_plaintext(data_sent.to_vec(), data_recv.to_vec());
+
+        let proven_id = commitment_builder
+            .commit_hash(&(9..13), Direction::Received)
+            .unwrap();
+        // The Prover adds a leaf to the tree which it does not prove to the Notary
+        let forged_id = commitment_builder
+            .commit_hash(&(0..4), Direction::Sent)
+            .unwrap();
+        let commitments = commitment_builder.build().unwrap();
+
+        let mut plaintext_hashes = PlaintextHashes::new(&commitments);
+        plaintext_hashes
+            .commitments
+            .retain(|(id, _, _)| *id == proven_id);
+
+        let header =
+            fixtures::session_header(commitments.merkle_root(), data_sent.len(), data_recv.len())
+                .with_plaintext_hashes(plaintext_hashes.leaves());
+
+        let prove = |id| {
+            let mut builder =
+                SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+            builder.reveal_by_id(id).unwrap();
+            builder
+                .build()
+                .unwrap()
+                .verify_with_hashes(&header, &ConstraintContext::default())
+        };
+
+        let (_, _, hashes) = prove(proven_id).unwrap();
+        // The hashed range is not revealed, but can be matched against a known value
+        assert!(hashes[0].matches(b"data"));
+        assert!(!hashes[0].matches(b"date"));
+
+        assert!(matches!(
+            prove(forged_id),
+            Err(SubstringsProofError::UnprovenPlaintextHash(id)) if id == forged_id
+        ));
+    }
+}
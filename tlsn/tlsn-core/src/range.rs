@@ -0,0 +1,191 @@
+//! Range utilities for selecting the parts of a transcript to commit to or to reveal.
+//!
+//! A [`RangeSet`] is a sorted set of non-overlapping ranges. Overlapping and adjacent ranges are
+//! merged when the set is constructed, so it can be built from ranges in any order.
+//!
+//! # Example
+//!
+//! ```
+//! use tlsn_core::range::{complement, find_all, RangeSet};
+//!
+//! let data = b"GET / HTTP/1.1\r\nAuthorization: secret\r\n\r\n";
+//!
+//! let private = find_all(data, &[b"secret".as_slice()]);
+//! let public = complement(&private, data.len());
+//!
+//! assert_eq!(private, RangeSet::from(31..37));
+//! assert_eq!(public, RangeSet::from([0..31, 37..data.len()]));
+//! ```
+
+pub use utils::range::{RangeDifference, RangeDisjoint, RangeSet, RangeUnion, ToRangeSet};
+
+/// Returns the ranges of `0..len` which are not contained in `ranges`.
+pub fn complement(ranges: &RangeSet<usize>, len: usize) -> RangeSet<usize> {
+    (0..len).difference(ranges)
+}
+
+/// Returns the ranges of all occurrences of the provided patterns in the data.
+///
+/// Overlapping occurrences are merged. Empty patterns are ignored.
+pub fn find_all(data: &[u8], patterns: &[&[u8]]) -> RangeSet<usize> {
+    let mut ranges = Vec::new();
+    for pattern in patterns.iter().filter(|pattern| !pattern.is_empty()) {
+        for (idx, window) in data.windows(pattern.len()).enumerate() {
+            if window == *pattern {
+                ranges.push(idx..idx + pattern.len());
+            }
+        }
+    }
+
+    RangeSet::from(ranges)
+}
+
+/// Splits the data into public and private ranges, where the private ranges are the occurrences
+/// of the provided patterns.
+///
+/// Returns a tuple of `(public, private)` ranges.
+pub fn split(data: &[u8], private_patterns: &[&[u8]]) -> (RangeSet<usize>, RangeSet<usize>) {
+    let private = find_all(data, private_patterns);
+    let public = complement(&private, data.len());
+
+    (public, private)
+}
+
+/// Expands the ranges so that they do not split a UTF-8 encoded character in the data.
+///
+/// Redacting part of a multi-byte character reveals the remaining bytes, which do not decode to
+/// a character. Expanding the private ranges ensures that a character is either fully revealed
+/// or fully redacted.
+pub fn snap_to_char_boundaries(data: &[u8], ranges: &RangeSet<usize>) -> RangeSet<usize> {
+    let is_continuation = |idx: usize| data.get(idx).is_some_and(|byte| byte & 0xC0 == 0x80);
+
+    let ranges = ranges
+        .iter_ranges()
+        .map(|range| {
+            let (mut start, mut end) = (range.start, range.end);
+            while start > 0 && is_continuation(start) {
+                start -= 1;
+            }
+            while is_continuation(end) {
+                end += 1;
+            }
+
+            start..end
+        })
+        .collect::<Vec<_>>();
+
+    RangeSet::from(ranges)
+}
+
+/// Returns the ranges of all occurrences of the provided patterns in the data, where the data
+/// may be percent encoded, e.g. in a URL or a form body.
+///
+/// The data is matched as if it was percent decoded, and the ranges cover the encoded bytes, so
+/// a pattern is found regardless of which of its bytes are encoded. Overlapping occurrences are
+/// merged. Empty patterns are ignored.
+pub fn find_all_percent_encoded(data: &[u8], patterns: &[&[u8]]) -> RangeSet<usize> {
+    let mut decoded = Vec::with_capacity(data.len());
+    // The offset in the data of each decoded byte, followed by the length of the data.
+    let mut offsets = Vec::with_capacity(data.len() + 1);
+
+    let mut idx = 0;
+    while idx < data.len() {
+        offsets.push(idx);
+        match data[idx..] {
+            [b'%', hi, lo, ..] if hex_value(hi).is_some() && hex_value(lo).is_some() => {
+                decoded.push((hex_value(hi).unwrap() << 4) | hex_value(lo).unwrap());
+                idx += 3;
+            }
+            _ => {
+                decoded.push(data[idx]);
+                idx += 1;
+            }
+        }
+    }
+    offsets.push(data.len());
+
+    let ranges = find_all(&decoded, patterns)
+        .iter_ranges()
+        .map(|range| offsets[range.start]..offsets[range.end])
+        .collect::<Vec<_>>();
+
+    RangeSet::from(ranges)
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all_merges_overlapping() {
+        let data = b"aaab-aaab";
+
+        assert_eq!(
+            find_all(data, &[b"aa".as_slice(), b"ab".as_slice()]),
+            RangeSet::from([0..4, 5..9])
+        );
+        assert_eq!(
+            find_all(data, &[b"".as_slice(), b"c".as_slice()]),
+            RangeSet::default()
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let data = b"user=alice&pass=hunter2";
+
+        let (public, private) = split(data, &[b"hunter2".as_slice(), b"alice".as_slice()]);
+
+        assert_eq!(private, RangeSet::from([5..10, 16..23]));
+        assert_eq!(public, RangeSet::from([0..5, 10..16]));
+        assert_eq!(public.union(&private), RangeSet::from(0..data.len()));
+    }
+
+    #[test]
+    fn test_complement() {
+        assert_eq!(complement(&RangeSet::default(), 4), RangeSet::from(0..4));
+        assert_eq!(complement(&RangeSet::from(0..4), 4), RangeSet::default());
+    }
+
+    #[test]
+    fn test_snap_to_char_boundaries() {
+        let data = "a€b".as_bytes();
+
+        // Only the second byte of the 3 byte euro sign.
+        assert_eq!(
+            snap_to_char_boundaries(data, &RangeSet::from(2..3)),
+            RangeSet::from(1..4)
+        );
+        assert_eq!(
+            snap_to_char_boundaries(data, &RangeSet::from(0..2)),
+            RangeSet::from(0..4)
+        );
+        assert_eq!(
+            snap_to_char_boundaries(data, &RangeSet::from(4..5)),
+            RangeSet::from(4..5)
+        );
+    }
+
+    #[test]
+    fn test_find_all_percent_encoded() {
+        let data = b"GET /?q=a%20b%2Fc&r=a b/c&s=%zz";
+
+        assert_eq!(
+            find_all_percent_encoded(data, &[b"a b/c".as_slice()]),
+            RangeSet::from([8..17, 20..25])
+        );
+        assert_eq!(
+            find_all_percent_encoded(data, &[b"%zz".as_slice()]),
+            RangeSet::from(28..31)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Revocation of attestations.
+//!
+//! A Notary can revoke the attestations it signed, e.g. for sessions later found to be produced
+//! under a compromised key, by publishing a [`SignedRevocationList`]. Sessions are revoked
+//! individually by the hash of their header (see [`SessionHeader::hash`]), or all at once by the
+//! fingerprint of the key which signed them (see [`NotaryPublicKey::fingerprint`]). Keys are not
+//! revoked by the identifier embedded in the header, as it is chosen by the signer, so a
+//! compromised key could sign headers with another identifier.
+//!
+//! Verifiers should obtain the most recent list from the Notary and check proofs against it
+//! before accepting them, see [`SignedRevocationList::check`].
+
+use std::collections::BTreeSet;
+
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize};
+use p256::ecdsa::signature::Signer;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    signature::domain_separated, NotaryPublicKey, SessionHeader, Signature, SignatureVerifyError,
+};
+
+/// The domain of the signatures of revocation lists.
+const REVOCATION_LIST_DOMAIN: &[u8] = b"tlsn/revocation-list/v1";
+
+/// An error for [`SignedRevocationList::check`].
+#[derive(Debug, thiserror::Error)]
+pub enum RevocationError {
+    /// The signature of the revocation list is invalid.
+    #[error(transparent)]
+    InvalidSignature(#[from] SignatureVerifyError),
+    /// The session was revoked.
+    #[error("the session was revoked")]
+    SessionRevoked,
+    /// The key which signed the session was revoked.
+    #[error("the key which signed the session was revoked")]
+    KeyRevoked,
+}
+
+/// A list of revoked attestations.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// The time the list was issued, in seconds since the UNIX epoch.
+    issued_at: u64,
+    /// The hashes of the revoked session headers.
+    sessions: BTreeSet<[u8; 32]>,
+    /// The fingerprints of the revoked keys.
+    keys: BTreeSet<[u8; 32]>,
+}
+
+impl RevocationList {
+    /// Creates a new empty revocation list.
+    ///
+    /// # Arguments
+    ///
+    /// * `issued_at` - The time the list is issued, in seconds since the UNIX epoch.
+    pub fn new(issued_at: u64) -> Self {
+        Self {
+            issued_at,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the time the list was issued, in seconds since the UNIX epoch.
+    pub fn issued_at(&self) -> u64 {
+        self.issued_at
+    }
+
+    /// Revokes the session with the provided header hash.
+    pub fn revoke_session(&mut self, header_hash: Hash) {
+        self.sessions.insert(*header_hash.as_bytes());
+    }
+
+    /// Revokes all sessions signed by the provided key.
+    pub fn revoke_key(&mut self, key: impl Into<NotaryPublicKey>) {
+        self.keys.insert(*key.into().fingerprint().as_bytes());
+    }
+
+    /// Returns the hashes of the revoked session headers.
+    pub fn sessions(&self) -> impl Iterator<Item = Hash> + '_ {
+        self.sessions.iter().copied().map(Hash::from)
+    }
+
+    /// Returns the fingerprints of the revoked keys.
+    pub fn keys(&self) -> impl Iterator<Item = Hash> + '_ {
+        self.keys.iter().copied().map(Hash::from)
+    }
+
+    /// Checks that the session with the provided header is not revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The header of the session.
+    /// * `notary_public_key` - The public key which the signature of the header was verified
+    ///   with.
+    pub fn check(
+        &self,
+        header: &SessionHeader,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), RevocationError> {
+        if self
+            .keys
+            .contains(notary_public_key.into().fingerprint().as_bytes())
+        {
+            return Err(RevocationError::KeyRevoked);
+        }
+
+        if self.sessions.contains(header.hash().as_bytes()) {
+            return Err(RevocationError::SessionRevoked);
+        }
+
+        Ok(())
+    }
+
+    /// Signs the revocation list.
+    pub fn sign<T>(self, signer: &impl Signer<T>) -> SignedRevocationList
+    where
+        T: Into<Signature>,
+    {
+        let signature = signer.sign(&self.signed_bytes()).into();
+
+        SignedRevocationList {
+            list: self,
+            signature,
+        }
+    }
+
+    /// Returns the bytes of the list which are signed.
+    fn signed_bytes(&self) -> Vec<u8> {
+        domain_separated(REVOCATION_LIST_DOMAIN, &self.to_bytes())
+    }
+}
+
+/// A revocation list signed by a Notary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRevocationList {
+    /// The revocation list.
+    pub list: RevocationList,
+    /// The signature of the Notary over the list.
+    pub signature: Signature,
+}
+
+impl SignedRevocationList {
+    /// Verifies the signature of the list, returning the list if it is valid.
+    pub fn verify(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<&RevocationList, SignatureVerifyError> {
+        self.signature
+            .verify(&self.list.signed_bytes(), notary_public_key)
+            .map(|_| &self.list)
+    }
+
+    /// Verifies the signature of the list and checks that the session with the provided header
+    /// is not revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The header of the session, which should be verified beforehand.
+    /// * `session_public_key` - The public key which the signature of the header was verified
+    ///   with.
+    /// * `notary_public_key` - The public key of the Notary which signed the list, which is
+    ///   usually its current key.
+    pub fn check(
+        &self,
+        header: &SessionHeader,
+        session_public_key: impl Into<NotaryPublicKey>,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), RevocationError> {
+        self.verify(notary_public_key)?
+            .check(header, session_public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{notary_signing_key, session_header};
+    use p256::ecdsa::SigningKey;
+
+    fn header(seed: u8) -> SessionHeader {
+        session_header([seed; 32].into(), 100, 100)
+    }
+
+    #[test]
+    fn test_revocation_list() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+        let compromised_key =
+            p256::PublicKey::from(SigningKey::from_slice(&[2; 32]).unwrap().verifying_key());
+
+        let mut list = RevocationList::new(0);
+        list.revoke_session(header(1).hash());
+        list.revoke_key(compromised_key);
+        let signed = list.sign::<p256::ecdsa::Signature>(&signing_key);
+
+        signed.check(&header(2), public_key, public_key).unwrap();
+        assert!(matches!(
+            signed.check(&header(1), public_key, public_key),
+            Err(RevocationError::SessionRevoked)
+        ));
+        assert!(matches!(
+            signed.check(&header(2), compromised_key, public_key),
+            Err(RevocationError::KeyRevoked)
+        ));
+
+        // The compromised key can not escape the revocation by choosing another key id.
+        assert!(matches!(
+            signed.check(&header(2).with_key_id("fresh"), compromised_key, public_key),
+            Err(RevocationError::KeyRevoked)
+        ));
+    }
+
+    #[test]
+    fn test_revocation_list_tampered() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        let mut list = RevocationList::new(0);
+        list.revoke_session(header(1).hash());
+        let mut signed = list.sign::<p256::ecdsa::Signature>(&signing_key);
+
+        // Removing an entry invalidates the signature.
+        signed.list.sessions.clear();
+
+        assert!(matches!(
+            signed.check(&header(1), public_key, public_key),
+            Err(RevocationError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_revocation_list_domain_separated() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        // A signature of the serialized list without the domain is not accepted.
+        let list = RevocationList::new(0);
+        let signature: p256::ecdsa::Signature =
+            p256::ecdsa::signature::Signer::sign(&signing_key, &list.to_bytes());
+        let signed = SignedRevocationList {
+            list,
+            signature: signature.into(),
+        };
+
+        assert!(signed.verify(public_key).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// The maximum length of the audience of a [`SessionBinding`], in bytes.
+pub const MAX_AUDIENCE_LEN: usize = 256;
+/// The maximum length of the nonce of a [`SessionBinding`], in bytes.
+pub const MAX_NONCE_LEN: usize = 64;
+
+/// An error for an invalid [`SessionBinding`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SessionBindingError {
+    /// The audience is longer than [`MAX_AUDIENCE_LEN`].
+    #[error("audience is {0} bytes long, exceeding the maximum of {MAX_AUDIENCE_LEN}")]
+    AudienceTooLong(usize),
+    /// The nonce is longer than [`MAX_NONCE_LEN`].
+    #[error("nonce is {0} bytes long, exceeding the maximum of {MAX_NONCE_LEN}")]
+    NonceTooLong(usize),
+}
+
+/// Binds a session to the relying party which requested it.
+///
+/// The relying party supplies an audience identifying itself and a fresh nonce to the Prover,
+/// which passes them to the Notary to be signed in the [`SessionHeader`](crate::SessionHeader).
+/// A proof can then only be presented to the relying party it was generated for, and only once,
+/// as the relying party checks the binding against its own audience and the nonce it issued.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionBinding {
+    audience: String,
+    nonce: Vec<u8>,
+}
+
+impl SessionBinding {
+    /// Creates a new session binding.
+    ///
+    /// # Arguments
+    ///
+    /// * `audience` - Identifies the relying party, e.g. its origin.
+    /// * `nonce` - A fresh value issued by the relying party for this session.
+    pub fn new(
+        audience: impl Into<String>,
+        nonce: impl Into<Vec<u8>>,
+    ) -> Result<Self, SessionBindingError> {
+        let binding = Self {
+            audience: audience.into(),
+            nonce: nonce.into(),
+        };
+        binding.validate()?;
+
+        Ok(binding)
+    }
+
+    /// Returns the audience of the binding.
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    /// Returns the nonce of the binding.
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+
+    /// Checks the lengths of the audience and nonce, which must be done for bindings received
+    /// from an untrusted party.
+    pub fn validate(&self) -> Result<(), SessionBindingError> {
+        if self.audience.len() > MAX_AUDIENCE_LEN {
+            return Err(SessionBindingError::AudienceTooLong(self.audience.len()));
+        }
+
+        if self.nonce.len() > MAX_NONCE_LEN {
+            return Err(SessionBindingError::NonceTooLong(self.nonce.len()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_binding_limits() {
+        assert!(SessionBinding::new("https://example.com", [0u8; MAX_NONCE_LEN]).is_ok());
+        assert!(matches!(
+            SessionBinding::new("https://example.com", [0u8; MAX_NONCE_LEN + 1]),
+            Err(SessionBindingError::NonceTooLong(_))
+        ));
+        assert!(matches!(
+            SessionBinding::new("a".repeat(MAX_AUDIENCE_LEN + 1), []),
+            Err(SessionBindingError::AudienceTooLong(_))
+        ));
+    }
+}
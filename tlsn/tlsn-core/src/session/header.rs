@@ -1,10 +1,17 @@
-use mpz_core::commit::Decommitment;
+use mpz_core::{commit::Decommitment, hash::Hash, utils::blake3};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use mpz_garble_core::ChaChaEncoder;
+use mpz_garble_core::{encoding_state::Full, ChaChaEncoder, EncodedValue};
 use tls_core::{handshake::HandshakeData, key::PublicKey};
+use utils::range::RangeSet;
 
-use crate::{merkle::MerkleRoot, HandshakeSummary};
+use crate::{
+    commitment::encoding::EncodingSchemeKind,
+    merkle::MerkleRoot,
+    session::{SessionBinding, Timestamp, TimestampError},
+    Direction, HandshakeSummary,
+};
 
 /// An error that can occur while verifying a session header
 #[derive(Debug, thiserror::Error)]
@@ -15,9 +22,34 @@ pub enum SessionHeaderVerifyError {
     InconsistentHeader,
 }
 
+/// The version of the layout of a [`SessionHeader`].
+///
+/// The fields added to the header by a version are appended to the layout of the previous
+/// version, so that the bytes the Notary signed for a header of a previous version do not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum HeaderVersion {
+    /// The layout of releases up to `0.1.0-alpha.6`, which is not versioned and only contains the
+    /// encoder seed, the Merkle root, the transcript lengths and the handshake summary.
+    V0,
+    /// The layout of [`HeaderVersion::V0`] followed by the encoding scheme, timestamp, key id,
+    /// DID, binding, record timings, record order, dry run flag, server name and proven plaintext
+    /// hashes.
+    V1,
+}
+
+impl HeaderVersion {
+    /// The version of the headers signed by this release.
+    pub const CURRENT: Self = Self::V1;
+}
+
 /// An authentic session header from the Notary
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "HeaderRepr", into = "HeaderRepr")]
 pub struct SessionHeader {
+    /// The version of the layout of the header
+    version: HeaderVersion,
+
     /// A PRG seeds used to generate encodings for the plaintext
     encoder_seed: [u8; 32],
 
@@ -35,6 +67,36 @@ pub struct SessionHeader {
     recv_len: usize,
 
     handshake_summary: HandshakeSummary,
+
+    /// The scheme of the encodings generated from the `encoder_seed`.
+    encoding_scheme: EncodingSchemeKind,
+
+    /// The time at which the Notary notarized the session, if it was obtained from a time source.
+    timestamp: Option<Timestamp>,
+
+    /// The identifier of the Notary key which signed the header, if the Notary identifies its keys.
+    key_id: Option<String>,
+
+    /// The DID of the Notary, if the Notary identifies itself by a DID.
+    notary_did: Option<String>,
+
+    /// The binding of the session to the relying party it was requested by, if any.
+    binding: Option<SessionBinding>,
+
+    /// The digest of the times of the records of the session, if the Prover committed to them.
+    record_timings: Option<Hash>,
+
+    /// The digest of the order of the records of the session, if the Prover committed to it.
+    record_order: Option<Hash>,
+
+    /// Whether the session was simulated by a dry run instead of being notarized.
+    dry_run: bool,
+
+    /// The DNS name of the server the Prover declared to the Notary, if any.
+    server_name: Option<String>,
+
+    /// The Merkle leaves of the plaintext hash commitments which the Prover proved to the Notary.
+    plaintext_hashes: Vec<Hash>,
 }
 
 impl SessionHeader {
@@ -47,14 +109,101 @@ impl SessionHeader {
         handshake_summary: HandshakeSummary,
     ) -> Self {
         Self {
+            version: HeaderVersion::CURRENT,
             encoder_seed,
             merkle_root,
             sent_len,
             recv_len,
             handshake_summary,
+            encoding_scheme: EncodingSchemeKind::default(),
+            timestamp: None,
+            key_id: None,
+            notary_did: None,
+            binding: None,
+            record_timings: None,
+            record_order: None,
+            dry_run: false,
+            server_name: None,
+            plaintext_hashes: Vec::new(),
         }
     }
 
+    /// Sets the scheme of the encodings of the session, which defaults to
+    /// [`EncodingSchemeKind::ChaCha`].
+    pub fn with_encoding_scheme(mut self, scheme: EncodingSchemeKind) -> Self {
+        self.encoding_scheme = scheme;
+        self
+    }
+
+    /// Sets the identifier of the Notary key which signs the header.
+    ///
+    /// The identifier lets verifiers select the key to verify the signature with, so the Notary
+    /// can rotate its key without invalidating proofs signed with past keys.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sets the DID of the Notary which signs the header.
+    ///
+    /// The DID, or DID URL selecting one of its keys, lets verifiers resolve the key to verify the
+    /// signature with, see [`did`](crate::did).
+    pub fn with_notary_did(mut self, did: impl Into<String>) -> Self {
+        self.notary_did = Some(did.into());
+        self
+    }
+
+    /// Sets the binding of the session to the relying party it was requested by.
+    pub fn with_binding(mut self, binding: SessionBinding) -> Self {
+        self.binding = Some(binding);
+        self
+    }
+
+    /// Sets the DNS name of the server the Prover declared to the Notary.
+    ///
+    /// A proof of the session is only valid for this server, see [`SessionProof::verify`](crate::proof::SessionProof::verify).
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Sets the Merkle leaves of the plaintext hash commitments which the Prover proved to the
+    /// Notary.
+    ///
+    /// A proof can only open the plaintext hash commitments whose leaves are bound to the header,
+    /// as the Notary did not check the hashes of the others against the transcript.
+    pub fn with_plaintext_hashes(mut self, leaves: Vec<Hash>) -> Self {
+        self.plaintext_hashes = leaves;
+        self
+    }
+
+    /// Sets the digest of the [`RecordTimings`](crate::session::RecordTimings) of the session.
+    pub fn with_record_timings(mut self, digest: Hash) -> Self {
+        self.record_timings = Some(digest);
+        self
+    }
+
+    /// Sets the digest of the [`RecordOrder`](crate::session::RecordOrder) of the session.
+    pub fn with_record_order(mut self, digest: Hash) -> Self {
+        self.record_order = Some(digest);
+        self
+    }
+
+    /// Marks the session as simulated by a [`DryRun`](crate::dry_run::DryRun).
+    #[cfg(any(test, feature = "dry-run"))]
+    pub(crate) fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Sets the timestamp of the session.
+    ///
+    /// The timestamp should be obtained for [`SessionHeader::timestamp_message`].
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
     /// Verify the data in the header is consistent with the Prover's view
     pub fn verify(
         &self,
@@ -80,8 +229,33 @@ impl SessionHeader {
     }
 
     /// Create a new [ChaChaEncoder] from encoder_seed
+    ///
+    /// The encoder only generates the encodings of sessions notarized with
+    /// [`EncodingSchemeKind::ChaCha`], use [`SessionHeader::encodings`] to generate them with the
+    /// scheme of the session.
+    #[deprecated(
+        note = "use `SessionHeader::encodings`, which uses the encoding scheme of the session"
+    )]
     pub fn encoder(&self) -> ChaChaEncoder {
-        ChaChaEncoder::new(self.encoder_seed)
+        match self.encoding_scheme {
+            EncodingSchemeKind::ChaCha => ChaChaEncoder::new(self.encoder_seed),
+        }
+    }
+
+    /// Returns the scheme of the encodings of the session.
+    pub fn encoding_scheme(&self) -> EncodingSchemeKind {
+        self.encoding_scheme
+    }
+
+    /// Returns the full encodings of the bytes of the transcript in the provided ranges, using the
+    /// encoding scheme of the session.
+    pub fn encodings(
+        &self,
+        direction: Direction,
+        ranges: &RangeSet<usize>,
+    ) -> Vec<EncodedValue<Full>> {
+        self.encoding_scheme
+            .encode(self.encoder_seed, direction, ranges)
     }
 
     /// Returns the seed used to generate plaintext encodings
@@ -108,6 +282,128 @@ impl SessionHeader {
         self.handshake_summary.time()
     }
 
+    /// Returns the time at which the Notary notarized the session, if it was obtained from a time
+    /// source.
+    pub fn timestamp(&self) -> Option<&Timestamp> {
+        self.timestamp.as_ref()
+    }
+
+    /// Verifies that the timestamp of the header was signed by an RFC 3161 time-stamp authority.
+    ///
+    /// # Arguments
+    ///
+    /// * `tsa_cert` - The DER encoded certificate of the time-stamp authority, which the caller
+    ///   must trust.
+    pub fn verify_timestamp(&self, tsa_cert: &[u8]) -> Result<(), TimestampError> {
+        self.timestamp
+            .as_ref()
+            .ok_or(TimestampError::MissingToken)?
+            .verify(&self.timestamp_message(), tsa_cert)
+    }
+
+    /// Returns the identifier of the Notary key which signed the header, if any.
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the DID of the Notary which signed the header, if any.
+    pub fn notary_did(&self) -> Option<&str> {
+        self.notary_did.as_deref()
+    }
+
+    /// Returns the binding of the session to the relying party it was requested by, if any.
+    pub fn binding(&self) -> Option<&SessionBinding> {
+        self.binding.as_ref()
+    }
+
+    /// Returns the DNS name of the server the Prover declared to the Notary, if any.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// Returns the Merkle leaves of the plaintext hash commitments which the Prover proved to the
+    /// Notary.
+    pub fn plaintext_hashes(&self) -> &[Hash] {
+        &self.plaintext_hashes
+    }
+
+    /// Returns the digest of the [`RecordTimings`](crate::session::RecordTimings) of the session,
+    /// if the Prover committed to them.
+    pub fn record_timings(&self) -> Option<&Hash> {
+        self.record_timings.as_ref()
+    }
+
+    /// Returns the digest of the [`RecordOrder`](crate::session::RecordOrder) of the session, if
+    /// the Prover committed to it.
+    pub fn record_order(&self) -> Option<&Hash> {
+        self.record_order.as_ref()
+    }
+
+    /// Returns `true` if the session was simulated by a dry run instead of being notarized.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns the version of the layout of the header.
+    pub fn version(&self) -> HeaderVersion {
+        self.version
+    }
+
+    /// Returns the bytes of the header signed by the Notary.
+    ///
+    /// Headers of [`HeaderVersion::V0`] were signed without a version, so their bytes are in the
+    /// layout of that release. The headers of later versions are signed in their serialized
+    /// layout, which starts with the version.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self.version {
+            HeaderVersion::V0 => bincode::serialize(&BaseFields::from(self)),
+            _ => bincode::serialize(self),
+        }
+        .expect("session header is serializable")
+    }
+
+    /// Decodes a header serialized in the unversioned layout of [`HeaderVersion::V0`], e.g. by
+    /// a release which did not version its headers.
+    pub fn from_v0_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize::<BaseFields>(bytes).map(Self::from)
+    }
+
+    /// Creates a header of [`HeaderVersion::V0`] from the fields of the layout of that version.
+    pub(crate) fn from_v0(
+        encoder_seed: [u8; 32],
+        merkle_root: MerkleRoot,
+        sent_len: usize,
+        recv_len: usize,
+        handshake_summary: HandshakeSummary,
+    ) -> Self {
+        Self {
+            version: HeaderVersion::V0,
+            ..Self::new(
+                encoder_seed,
+                merkle_root,
+                sent_len,
+                recv_len,
+                handshake_summary,
+            )
+        }
+    }
+
+    /// Returns the BLAKE3 hash of the header, which identifies the session.
+    pub fn hash(&self) -> Hash {
+        Hash::from(blake3(&self.to_bytes()))
+    }
+
+    /// Returns the SHA-256 hash of the header without its timestamp, which is the message that is
+    /// timestamped.
+    pub fn timestamp_message(&self) -> Hash {
+        let header = Self {
+            timestamp: None,
+            ..self.clone()
+        };
+
+        Hash::from(<[u8; 32]>::from(Sha256::digest(header.to_bytes())))
+    }
+
     /// Returns the number of bytes sent to the server
     pub fn sent_len(&self) -> usize {
         self.sent_len
@@ -118,3 +414,182 @@ impl SessionHeader {
         self.recv_len
     }
 }
+
+/// The fields of the layout of [`HeaderVersion::V0`], which start the layout of every version.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BaseFields {
+    encoder_seed: [u8; 32],
+    merkle_root: MerkleRoot,
+    sent_len: usize,
+    recv_len: usize,
+    handshake_summary: HandshakeSummary,
+}
+
+impl From<&SessionHeader> for BaseFields {
+    fn from(header: &SessionHeader) -> Self {
+        Self {
+            encoder_seed: header.encoder_seed,
+            merkle_root: header.merkle_root,
+            sent_len: header.sent_len,
+            recv_len: header.recv_len,
+            handshake_summary: header.handshake_summary.clone(),
+        }
+    }
+}
+
+impl From<BaseFields> for SessionHeader {
+    fn from(fields: BaseFields) -> Self {
+        Self::from_v0(
+            fields.encoder_seed,
+            fields.merkle_root,
+            fields.sent_len,
+            fields.recv_len,
+            fields.handshake_summary,
+        )
+    }
+}
+
+/// The fields appended to the layout by [`HeaderVersion::V1`].
+#[derive(Serialize, Deserialize)]
+struct FieldsV1 {
+    encoding_scheme: EncodingSchemeKind,
+    timestamp: Option<Timestamp>,
+    key_id: Option<String>,
+    notary_did: Option<String>,
+    binding: Option<SessionBinding>,
+    record_timings: Option<Hash>,
+    record_order: Option<Hash>,
+    dry_run: bool,
+    server_name: Option<String>,
+    plaintext_hashes: Vec<Hash>,
+}
+
+/// The serialized layout of a [`SessionHeader`], tagged with its version.
+///
+/// A new version adds a variant with the fields of the previous version followed by its own.
+#[derive(Serialize, Deserialize)]
+enum HeaderRepr {
+    V0(BaseFields),
+    V1(BaseFields, FieldsV1),
+}
+
+impl From<SessionHeader> for HeaderRepr {
+    fn from(header: SessionHeader) -> Self {
+        let base = BaseFields::from(&header);
+        match header.version {
+            HeaderVersion::V0 => Self::V0(base),
+            HeaderVersion::V1 => Self::V1(
+                base,
+                FieldsV1 {
+                    encoding_scheme: header.encoding_scheme,
+                    timestamp: header.timestamp,
+                    key_id: header.key_id,
+                    notary_did: header.notary_did,
+                    binding: header.binding,
+                    record_timings: header.record_timings,
+                    record_order: header.record_order,
+                    dry_run: header.dry_run,
+                    server_name: header.server_name,
+                    plaintext_hashes: header.plaintext_hashes,
+                },
+            ),
+        }
+    }
+}
+
+impl From<HeaderRepr> for SessionHeader {
+    fn from(repr: HeaderRepr) -> Self {
+        match repr {
+            HeaderRepr::V0(base) => base.into(),
+            HeaderRepr::V1(base, fields) => Self {
+                version: HeaderVersion::V1,
+                encoding_scheme: fields.encoding_scheme,
+                timestamp: fields.timestamp,
+                key_id: fields.key_id,
+                notary_did: fields.notary_did,
+                binding: fields.binding,
+                record_timings: fields.record_timings,
+                record_order: fields.record_order,
+                dry_run: fields.dry_run,
+                server_name: fields.server_name,
+                plaintext_hashes: fields.plaintext_hashes,
+                ..base.into()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{encoder_seed, handshake_summary},
+        session::TimestampSource,
+    };
+
+    #[test]
+    fn test_header_v0_signed_bytes() {
+        let header =
+            SessionHeader::from_v0(encoder_seed(), [1; 32].into(), 16, 32, handshake_summary());
+
+        // The bytes of a V0 header are in the unversioned layout of the release which signed it.
+        assert_eq!(
+            header.to_bytes(),
+            bincode::serialize(&(
+                encoder_seed(),
+                MerkleRoot::from([1; 32]),
+                16usize,
+                32usize,
+                handshake_summary()
+            ))
+            .unwrap()
+        );
+
+        let decoded: SessionHeader =
+            bincode::deserialize(&bincode::serialize(&header).unwrap()).unwrap();
+        assert_eq!(decoded.version(), HeaderVersion::V0);
+        assert_eq!(decoded.to_bytes(), header.to_bytes());
+    }
+
+    #[test]
+    fn test_header_appends_fields() {
+        let header =
+            SessionHeader::new(encoder_seed(), [1; 32].into(), 16, 32, handshake_summary())
+                .with_key_id("key")
+                .with_server_name("tlsnotary.org");
+        let bytes = header.to_bytes();
+
+        // The layout starts with the version, followed by the fields of the V0 layout.
+        let base =
+            SessionHeader::from_v0(encoder_seed(), [1; 32].into(), 16, 32, handshake_summary())
+                .to_bytes();
+        assert_eq!(bytes[..4], 1u32.to_le_bytes());
+        assert_eq!(bytes[4..4 + base.len()], base[..]);
+
+        let decoded: SessionHeader = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.version(), HeaderVersion::V1);
+        assert_eq!(decoded.key_id(), Some("key"));
+        assert_eq!(decoded.server_name(), Some("tlsnotary.org"));
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_timestamp_message() {
+        let header =
+            SessionHeader::new(encoder_seed(), [1; 32].into(), 16, 32, handshake_summary());
+        let message = header.timestamp_message();
+
+        // Timestamping the header does not change the timestamped message, but the signed bytes.
+        let timestamped = header.clone().with_timestamp(Timestamp::new(
+            TimestampSource::SystemClock,
+            1_700_000_000,
+            None,
+        ));
+        assert_eq!(timestamped.timestamp_message(), message);
+        assert_ne!(timestamped.to_bytes(), header.to_bytes());
+        assert_eq!(
+            timestamped.timestamp().map(Timestamp::time),
+            Some(1_700_000_000)
+        );
+    }
+}
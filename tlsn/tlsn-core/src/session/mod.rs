@@ -1,16 +1,27 @@
 //! TLS session types.
 
+mod binding;
 mod data;
 mod handshake;
 mod header;
+mod record_order;
+mod record_timings;
+mod timestamp;
 
 use serde::{Deserialize, Serialize};
 
+pub use binding::{SessionBinding, SessionBindingError, MAX_AUDIENCE_LEN, MAX_NONCE_LEN};
 pub use data::SessionData;
 pub use handshake::{HandshakeSummary, HandshakeVerifyError};
-pub use header::{SessionHeader, SessionHeaderVerifyError};
+pub(crate) use header::BaseFields;
+pub use header::{HeaderVersion, SessionHeader, SessionHeaderVerifyError};
+pub use record_order::{RecordOrder, RecordOrderError};
+pub use record_timings::{RecordTimings, RecordTimingsError};
+pub use timestamp::{Timestamp, TimestampError, TimestampSource, TimestampToken};
 
 use crate::{
+    commitment::{CommitmentId, CommitmentIndex, CommitmentIndexError},
+    json::{from_canonical_json, to_canonical_json, CanonicalJsonError},
     proof::{SessionInfo, SessionProof},
     signature::Signature,
 };
@@ -21,6 +32,12 @@ pub struct NotarizedSession {
     header: SessionHeader,
     signature: Option<Signature>,
     data: SessionData,
+    /// An index of the commitments by label, which is not signed.
+    ///
+    /// The index is not part of the serialized session, so that it can only be loaded validated
+    /// by [`NotarizedSession::from_json`], see [`NotarizedSession::to_json`].
+    #[serde(skip)]
+    index: CommitmentIndex,
 }
 
 opaque_debug::implement!(NotarizedSession);
@@ -32,6 +49,7 @@ impl NotarizedSession {
             header,
             signature,
             data,
+            index: CommitmentIndex::default(),
         }
     }
 
@@ -63,4 +81,142 @@ impl NotarizedSession {
     pub fn data(&self) -> &SessionData {
         &self.data
     }
+
+    /// Returns the index of the commitments by label.
+    pub fn index(&self) -> &CommitmentIndex {
+        &self.index
+    }
+
+    /// Labels a commitment, so it can be addressed by name once the session is stored, see
+    /// [`CommitmentIndex`].
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label of the commitment, e.g. `"response/0/body"`.
+    /// * `id` - The id of the commitment.
+    pub fn label_commitment(
+        &mut self,
+        label: impl Into<String>,
+        id: CommitmentId,
+    ) -> Result<(), CommitmentIndexError> {
+        self.index.insert(label, id, self.data.commitments())
+    }
+
+    /// Returns the id of the commitment with the provided label.
+    pub fn commitment_by_label(&self, label: &str) -> Option<CommitmentId> {
+        self.index.get(label).map(|entry| entry.id)
+    }
+
+    /// Validates the index of the commitments against the Merkle root of the signed header.
+    pub fn validate_index(&self) -> Result<(), CommitmentIndexError> {
+        self.index
+            .validate(self.data.commitments(), self.header.merkle_root())
+    }
+
+    /// Encodes the session as JSON, together with its index of the commitments.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&IndexedSessionRef {
+            session: self,
+            index: &self.index,
+        })
+    }
+
+    /// Decodes a session from JSON written by [`to_json`](Self::to_json), and validates its index
+    /// of the commitments, see [`validate_index`](Self::validate_index).
+    ///
+    /// A session serialized without its index, e.g. with `serde_json`, is loaded with an empty
+    /// index.
+    pub fn from_json(json: &str) -> Result<Self, SessionLoadError> {
+        let IndexedSession { mut session, index } = serde_json::from_str(json)?;
+        session.index = index;
+        session.validate_index()?;
+
+        Ok(session)
+    }
+
+    /// Encodes the session as canonical JSON, see [`json`](crate::json).
+    pub fn to_canonical_json(&self) -> Result<String, CanonicalJsonError> {
+        to_canonical_json(self)
+    }
+
+    /// Decodes a session from canonical JSON, rejecting any input which is not in canonical form.
+    pub fn from_canonical_json(json: &str) -> Result<Self, CanonicalJsonError> {
+        from_canonical_json(json)
+    }
+}
+
+/// A session and its index of the commitments, as encoded by [`NotarizedSession::to_json`].
+#[derive(Serialize)]
+struct IndexedSessionRef<'a> {
+    #[serde(flatten)]
+    session: &'a NotarizedSession,
+    index: &'a CommitmentIndex,
+}
+
+/// A session and its index of the commitments, as decoded by [`NotarizedSession::from_json`].
+#[derive(Deserialize)]
+struct IndexedSession {
+    #[serde(flatten)]
+    session: NotarizedSession,
+    #[serde(default)]
+    index: CommitmentIndex,
+}
+
+/// An error for [`NotarizedSession::from_json`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum SessionLoadError {
+    /// The session could not be decoded.
+    #[error("failed to decode session: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The index of the commitments is invalid.
+    #[error("invalid commitment index: {0}")]
+    Index(#[from] CommitmentIndexError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dry_run::DryRun;
+
+    #[test]
+    fn test_index_round_trip() {
+        let mut dry_run = DryRun::new(
+            b"GET / HTTP/1.1\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\n\r\nhello".to_vec(),
+        );
+        let id = dry_run.commitment_builder().commit_recv(&(19..24)).unwrap();
+
+        let mut session = dry_run.finalize().unwrap();
+        session.label_commitment("response/0/body", id).unwrap();
+
+        let json = session.to_json().unwrap();
+        let loaded = NotarizedSession::from_json(&json).unwrap();
+        assert_eq!(loaded.commitment_by_label("response/0/body"), Some(id));
+        assert_eq!(loaded.index(), session.index());
+
+        // The index is not part of the serialized session, so it can't be loaded unvalidated.
+        let serialized = serde_json::to_string(&session).unwrap();
+        let deserialized: NotarizedSession = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized.index().is_empty());
+        assert!(NotarizedSession::from_json(&serialized)
+            .unwrap()
+            .index()
+            .is_empty());
+
+        // The index does not change the binary encoding of the session.
+        let bytes = bincode::serialize(&session).unwrap();
+        let decoded: NotarizedSession = bincode::deserialize(&bytes).unwrap();
+        assert!(decoded.index().is_empty());
+        assert_eq!(bincode::serialize(&decoded).unwrap(), bytes);
+
+        // An index which does not match the signed commitments is rejected.
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["index"]["entries"]["response/0/body"]["ranges"] =
+            serde_json::to_value(utils::range::RangeSet::from(0..5)).unwrap();
+        assert!(matches!(
+            NotarizedSession::from_json(&value.to_string()),
+            Err(SessionLoadError::Index(CommitmentIndexError::Mismatch(_)))
+        ));
+    }
 }
@@ -0,0 +1,276 @@
+use std::ops::Range;
+
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize, utils::blake3};
+use serde::{Deserialize, Serialize};
+use utils::range::RangeSet;
+
+use crate::{Direction, SessionHeader, Transcript};
+
+/// An error for [`RecordOrder`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum RecordOrderError {
+    /// The order differs from the order observed by the Notary.
+    #[error("record order differs from the order observed by the notary: {0}")]
+    Observation(String),
+    /// The order is inconsistent with the session header.
+    #[error("record order is inconsistent with the session: {0}")]
+    Inconsistent(String),
+    /// The session header does not commit to the order.
+    #[error("session header does not commit to this record order")]
+    Mismatch,
+    /// The ranges are out of the bounds of the transcript.
+    #[error("{direction} ranges are out of bounds")]
+    OutOfBounds {
+        /// The direction of the ranges.
+        direction: &'static str,
+    },
+}
+
+/// The lengths of the application data records of a session and the order in which they were sent
+/// and received.
+///
+/// For each received record, the order holds the number of records which were sent before it was
+/// received. The Notary checks the order against its own observation of the records and signs its
+/// digest in the [`SessionHeader`], so the Prover can prove that some received data was received
+/// after some sent data was sent, e.g. that a response answers a challenge which was sent in a
+/// request.
+///
+/// The Notary only observes the records when the Prover forwards them into the MPC, not when they
+/// are exchanged with the server. The Prover controls this forwarding: it can delay sending a
+/// record until it has received a later response, or withhold a received record until it has sent
+/// more data. The order therefore only shows the order in which the Prover forwarded the records,
+/// and does not prove that the server sent a response after it received a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordOrder {
+    sent_lens: Vec<usize>,
+    recv_lens: Vec<usize>,
+    recv_sent_before: Vec<usize>,
+}
+
+impl RecordOrder {
+    /// Creates a new record order.
+    ///
+    /// # Arguments
+    ///
+    /// * `sent_lens` - The plaintext lengths of the records sent, in order.
+    /// * `recv_lens` - The plaintext lengths of the records received, in order.
+    /// * `recv_sent_before` - The number of records sent before each record was received.
+    pub fn new(sent_lens: Vec<usize>, recv_lens: Vec<usize>, recv_sent_before: Vec<usize>) -> Self {
+        Self {
+            sent_lens,
+            recv_lens,
+            recv_sent_before,
+        }
+    }
+
+    /// Returns the order of the records of the transcripts, if the records and their order are
+    /// known.
+    pub fn from_transcripts(sent: &Transcript, recv: &Transcript) -> Option<Self> {
+        let known = |transcript: &Transcript| {
+            transcript.data().is_empty() || !transcript.records().is_empty()
+        };
+        if !known(sent) || !known(recv) {
+            return None;
+        }
+
+        Some(Self {
+            sent_lens: sent
+                .records()
+                .iter()
+                .map(|record| record.range().len())
+                .collect(),
+            recv_lens: recv
+                .records()
+                .iter()
+                .map(|record| record.range().len())
+                .collect(),
+            recv_sent_before: recv
+                .records()
+                .iter()
+                .map(|record| record.sent_before())
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// Returns the plaintext lengths of the records sent.
+    pub fn sent_lens(&self) -> &[usize] {
+        &self.sent_lens
+    }
+
+    /// Returns the plaintext lengths of the records received.
+    pub fn recv_lens(&self) -> &[usize] {
+        &self.recv_lens
+    }
+
+    /// Returns the number of records sent before each record was received.
+    pub fn recv_sent_before(&self) -> &[usize] {
+        &self.recv_sent_before
+    }
+
+    /// Returns the digest of the order, which is signed in the session header.
+    pub fn digest(&self) -> Hash {
+        Hash::from(blake3(&self.to_bytes()))
+    }
+
+    /// Checks that the order is the order observed by the Notary.
+    pub fn check(&self, observed: &RecordOrder) -> Result<(), RecordOrderError> {
+        if self.sent_lens != observed.sent_lens {
+            return Err(RecordOrderError::Observation(
+                "sent record lengths differ".to_string(),
+            ));
+        }
+
+        if self.recv_lens != observed.recv_lens {
+            return Err(RecordOrderError::Observation(
+                "received record lengths differ".to_string(),
+            ));
+        }
+
+        if self.recv_sent_before != observed.recv_sent_before {
+            return Err(RecordOrderError::Observation(
+                "received records are ordered differently".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the session header commits to this order.
+    pub fn verify(&self, header: &SessionHeader) -> Result<(), RecordOrderError> {
+        if header.record_order() != Some(&self.digest()) {
+            return Err(RecordOrderError::Mismatch);
+        }
+
+        if self.sent_lens.iter().sum::<usize>() != header.sent_len()
+            || self.recv_lens.iter().sum::<usize>() != header.recv_len()
+        {
+            return Err(RecordOrderError::Inconsistent(
+                "record lengths do not add up to the transcript lengths".to_string(),
+            ));
+        }
+
+        if self.recv_sent_before.len() != self.recv_lens.len()
+            || self
+                .recv_sent_before
+                .windows(2)
+                .any(|pair| pair[0] > pair[1])
+            || self
+                .recv_sent_before
+                .last()
+                .is_some_and(|sent_before| *sent_before > self.sent_lens.len())
+        {
+            return Err(RecordOrderError::Inconsistent(
+                "received records are not ordered".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if all the received ranges were received after all the sent ranges were
+    /// sent.
+    ///
+    /// The order is only known at the granularity of records, so data received in a record
+    /// which was received before the last record of the sent ranges was sent is never considered
+    /// to be received after them. This is always `true` if either of the ranges is empty.
+    ///
+    /// The order must be [verified](Self::verify) against the session header of the proof the
+    /// ranges were revealed by.
+    pub fn is_received_after(
+        &self,
+        sent: &RangeSet<usize>,
+        recv: &RangeSet<usize>,
+    ) -> Result<bool, RecordOrderError> {
+        let (Some(sent_end), Some(recv_start)) = (
+            sent.iter_ranges().last().map(|range| range.end),
+            recv.iter_ranges().next().map(|range| range.start),
+        ) else {
+            return Ok(true);
+        };
+
+        // The index of the last record containing sent data, and of the first record containing
+        // received data.
+        let last_sent = record_index(&self.sent_lens, sent_end - 1)
+            .ok_or(RecordOrderError::OutOfBounds { direction: "sent" })?;
+        let first_recv =
+            record_index(&self.recv_lens, recv_start).ok_or(RecordOrderError::OutOfBounds {
+                direction: "received",
+            })?;
+        if recv.iter_ranges().last().map(|range| range.end) > Some(self.recv_lens.iter().sum()) {
+            return Err(RecordOrderError::OutOfBounds {
+                direction: "received",
+            });
+        }
+
+        let sent_before = self.recv_sent_before.get(first_recv).ok_or_else(|| {
+            RecordOrderError::Inconsistent("received records are not ordered".to_string())
+        })?;
+
+        Ok(*sent_before > last_sent)
+    }
+
+    /// Returns the range of the transcript of each record of the provided direction.
+    pub fn record_ranges(&self, direction: Direction) -> Vec<Range<usize>> {
+        let lens = match direction {
+            Direction::Sent => &self.sent_lens,
+            Direction::Received => &self.recv_lens,
+        };
+
+        let mut start = 0;
+        lens.iter()
+            .map(|len| {
+                let range = start..start + len;
+                start += len;
+                range
+            })
+            .collect()
+    }
+}
+
+/// Returns the index of the record containing the byte at the offset.
+fn record_index(lens: &[usize], offset: usize) -> Option<usize> {
+    let mut end = 0;
+    lens.iter().position(|len| {
+        end += len;
+        offset < end
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_order() {
+        // A request sent in two records, a response to the first one, and a response to the
+        // second one.
+        let order = RecordOrder::new(vec![10, 10], vec![5, 5], vec![1, 2]);
+
+        assert!(order
+            .is_received_after(&(0..10).into(), &(0..10).into())
+            .unwrap());
+        assert!(order
+            .is_received_after(&(10..20).into(), &(5..10).into())
+            .unwrap());
+        assert!(!order
+            .is_received_after(&(10..20).into(), &(0..10).into())
+            .unwrap());
+        assert!(!order
+            .is_received_after(&(0..11).into(), &(4..6).into())
+            .unwrap());
+        assert!(matches!(
+            order.is_received_after(&(0..21).into(), &(0..1).into()),
+            Err(RecordOrderError::OutOfBounds { .. })
+        ));
+
+        assert_eq!(order.record_ranges(Direction::Sent), vec![0..10, 10..20]);
+
+        let observed = RecordOrder::new(vec![10, 10], vec![5, 5], vec![0, 2]);
+        assert!(matches!(
+            order.check(&observed),
+            Err(RecordOrderError::Observation(_))
+        ));
+        assert!(order.check(&order.clone()).is_ok());
+    }
+}
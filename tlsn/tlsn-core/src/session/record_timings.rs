@@ -0,0 +1,185 @@
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize, utils::blake3};
+use serde::{Deserialize, Serialize};
+
+use crate::{SessionHeader, Transcript};
+
+/// An error for [`RecordTimings`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum RecordTimingsError {
+    /// The number of records does not match the number of records observed by the Notary.
+    #[error("expected {expected} {direction} records, got {actual}")]
+    RecordCount {
+        /// The direction of the records.
+        direction: &'static str,
+        /// The number of records observed by the Notary.
+        expected: usize,
+        /// The number of records in the timings.
+        actual: usize,
+    },
+    /// The length of a record differs from the length observed by the Notary.
+    #[error("{direction} record {index} has a different length than observed by the notary")]
+    Length {
+        /// The direction of the record.
+        direction: &'static str,
+        /// The index of the record.
+        index: usize,
+    },
+    /// A record time differs from the time observed by the Notary by more than the allowed skew.
+    #[error("{direction} record {index} is {skew} ms away from the time observed by the notary")]
+    Skew {
+        /// The direction of the record.
+        direction: &'static str,
+        /// The index of the record.
+        index: usize,
+        /// The difference between the times, in milliseconds.
+        skew: u64,
+    },
+    /// The session header does not commit to the timings.
+    #[error("session header does not commit to these record timings")]
+    Mismatch,
+}
+
+/// The plaintext lengths of the application data records of a session and the times at which
+/// they were sent and received, in milliseconds since the UNIX epoch.
+///
+/// The timings signed in the [`SessionHeader`] are the Notary's own observation of the records,
+/// i.e. the times at which it took part in encrypting and decrypting them, so the Prover can
+/// prove statements about the timing of the session, e.g. that the server responded within 2
+/// seconds of the request. The Notary only signs them if the Prover's view of the records does
+/// not differ from them by more than the allowed skew.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordTimings {
+    sent: Vec<(usize, u64)>,
+    recv: Vec<(usize, u64)>,
+}
+
+impl RecordTimings {
+    /// Creates new record timings.
+    ///
+    /// # Arguments
+    ///
+    /// * `sent` - The plaintext length and time of each record sent, in order.
+    /// * `recv` - The plaintext length and time of each record received, in order.
+    pub fn new(sent: Vec<(usize, u64)>, recv: Vec<(usize, u64)>) -> Self {
+        Self { sent, recv }
+    }
+
+    /// Returns the timings of the records of the transcripts, if the records of both transcripts
+    /// are known.
+    pub fn from_transcripts(sent: &Transcript, recv: &Transcript) -> Option<Self> {
+        let known = |transcript: &Transcript| {
+            transcript.data().is_empty() || !transcript.records().is_empty()
+        };
+        if !known(sent) || !known(recv) {
+            return None;
+        }
+
+        let timings = |transcript: &Transcript| {
+            transcript
+                .records()
+                .iter()
+                .map(|record| (record.range().len(), record.time()))
+                .collect()
+        };
+
+        Some(Self {
+            sent: timings(sent),
+            recv: timings(recv),
+        })
+    }
+
+    /// Returns the plaintext length and time of each record sent.
+    pub fn sent(&self) -> &[(usize, u64)] {
+        &self.sent
+    }
+
+    /// Returns the plaintext length and time of each record received.
+    pub fn recv(&self) -> &[(usize, u64)] {
+        &self.recv
+    }
+
+    /// Returns the digest of the timings, which is signed in the session header.
+    pub fn digest(&self) -> Hash {
+        Hash::from(blake3(&self.to_bytes()))
+    }
+
+    /// Checks that the timings describe the same records as `observed`, with times within
+    /// `max_skew_ms` of each other.
+    pub fn check(
+        &self,
+        observed: &RecordTimings,
+        max_skew_ms: u64,
+    ) -> Result<(), RecordTimingsError> {
+        for (direction, records, expected) in [
+            ("sent", &self.sent, &observed.sent),
+            ("received", &self.recv, &observed.recv),
+        ] {
+            if records.len() != expected.len() {
+                return Err(RecordTimingsError::RecordCount {
+                    direction,
+                    expected: expected.len(),
+                    actual: records.len(),
+                });
+            }
+
+            for (index, ((len, time), (expected_len, expected_time))) in
+                records.iter().zip(expected).enumerate()
+            {
+                if len != expected_len {
+                    return Err(RecordTimingsError::Length { direction, index });
+                }
+
+                let skew = time.abs_diff(*expected_time);
+                if skew > max_skew_ms {
+                    return Err(RecordTimingsError::Skew {
+                        direction,
+                        index,
+                        skew,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the session header commits to these timings.
+    pub fn verify(&self, header: &SessionHeader) -> Result<(), RecordTimingsError> {
+        if header.record_timings() != Some(&self.digest()) {
+            return Err(RecordTimingsError::Mismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_timings_check() {
+        let observed = RecordTimings::new(vec![(10, 1_000), (5, 3_000)], vec![(20, 2_000)]);
+
+        assert!(
+            RecordTimings::new(vec![(10, 1_100), (5, 2_900)], vec![(20, 2_050)])
+                .check(&observed, 200)
+                .is_ok()
+        );
+        assert!(matches!(
+            RecordTimings::new(vec![(10, 1_000), (5, 3_000)], vec![(20, 2_500)])
+                .check(&observed, 200),
+            Err(RecordTimingsError::Skew { index: 0, .. })
+        ));
+        assert!(matches!(
+            RecordTimings::new(vec![(10, 1_000)], vec![(20, 2_000)]).check(&observed, 200),
+            Err(RecordTimingsError::RecordCount { .. })
+        ));
+        assert!(matches!(
+            RecordTimings::new(vec![(10, 1_000), (6, 3_000)], vec![(20, 2_000)])
+                .check(&observed, 200),
+            Err(RecordTimingsError::Length { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,533 @@
+use cms::{
+    content_info::ContentInfo,
+    signed_data::{SignedData, SignerIdentifier, SignerInfo},
+};
+use der::{
+    asn1::{Any, Int, ObjectIdentifier, OctetString},
+    DateTime, Decode, Encode, Sequence, Tag, Tagged,
+};
+use mpz_core::hash::Hash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x509_cert::{
+    ext::pkix::{name::GeneralName, ExtendedKeyUsage, SubjectKeyIdentifier},
+    ext::Extensions,
+    spki::AlgorithmIdentifierOwned,
+    Certificate,
+};
+
+/// id-signedData, RFC 5652
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+/// id-ct-TSTInfo, RFC 3161
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+/// id-contentType, RFC 5652
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+/// id-messageDigest, RFC 5652
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+/// id-sha256, RFC 5754
+const ID_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+/// rsaEncryption, RFC 8017
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+/// sha256WithRSAEncryption, RFC 8017
+const SHA256_WITH_RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+/// ecdsa-with-SHA256, RFC 5758
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+/// id-kp-timeStamping, RFC 5280
+const ID_KP_TIME_STAMPING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.8");
+
+/// The source of a [`Timestamp`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum TimestampSource {
+    /// The system clock of the Notary.
+    SystemClock,
+    /// The system clock of the Notary, checked against an NTP server.
+    Ntp {
+        /// The address of the NTP server.
+        server: String,
+    },
+    /// An RFC 3161 time-stamp authority.
+    Rfc3161 {
+        /// The URL of the time-stamp authority.
+        url: String,
+    },
+}
+
+/// An error that can occur while verifying a [`Timestamp`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TimestampError {
+    /// There is no timestamp, or the timestamp does not contain a time-stamp token.
+    #[error("timestamp does not contain a time-stamp token")]
+    MissingToken,
+    /// The time-stamp token or the certificate is not valid DER.
+    #[error("invalid encoding: {0}")]
+    Encoding(String),
+    /// The time-stamp token is malformed.
+    #[error("invalid time-stamp token: {0}")]
+    InvalidToken(&'static str),
+    /// The time-stamp token does not timestamp the message.
+    #[error("time-stamp token does not timestamp the message")]
+    MessageMismatch,
+    /// The time of the time-stamp token differs from the time of the timestamp.
+    #[error("time of the time-stamp token differs from the time of the timestamp")]
+    TimeMismatch,
+    /// The time-stamp token was not signed with the certificate.
+    #[error("time-stamp token was not signed with the certificate")]
+    UnknownSigner,
+    /// The certificate is not valid for time-stamping at the time of the token.
+    #[error("certificate is not valid for time-stamping: {0}")]
+    InvalidCertificate(&'static str),
+    /// The algorithm used by the time-stamp authority is not supported.
+    #[error("unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(ObjectIdentifier),
+    /// The signature of the time-stamp token is invalid.
+    #[error("invalid signature of the time-stamp token")]
+    InvalidSignature,
+}
+
+impl From<der::Error> for TimestampError {
+    fn from(err: der::Error) -> Self {
+        Self::Encoding(err.to_string())
+    }
+}
+
+/// The time at which the Notary notarized a session, as obtained from a [`TimestampSource`].
+///
+/// The timestamp is part of the signed [`SessionHeader`](crate::SessionHeader), so the source of the
+/// time of a session can be audited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timestamp {
+    source: TimestampSource,
+    time: u64,
+    token: Option<Vec<u8>>,
+}
+
+impl Timestamp {
+    /// Creates a new timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source of the timestamp.
+    /// * `time` - The time, in seconds since the UNIX epoch.
+    /// * `token` - The DER encoded RFC 3161 `TimeStampToken`, if the source is a time-stamp
+    ///   authority.
+    pub fn new(source: TimestampSource, time: u64, token: Option<Vec<u8>>) -> Self {
+        Self {
+            source,
+            time,
+            token,
+        }
+    }
+
+    /// Returns the source of the timestamp.
+    pub fn source(&self) -> &TimestampSource {
+        &self.source
+    }
+
+    /// Returns the time, in seconds since the UNIX epoch.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Returns the DER encoded RFC 3161 `TimeStampToken`, if any.
+    ///
+    /// The message imprint of the token is the SHA-256 hash returned by
+    /// [`SessionHeader::timestamp_message`](crate::SessionHeader::timestamp_message).
+    pub fn token(&self) -> Option<&[u8]> {
+        self.token.as_deref()
+    }
+
+    /// Verifies that the time-stamp token was signed by a time-stamp authority over the message,
+    /// at the time of the timestamp.
+    ///
+    /// Only the signature of the token is checked against the certificate, whether the
+    /// certificate is trusted is up to the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message which was timestamped.
+    /// * `tsa_cert` - The DER encoded certificate of the time-stamp authority.
+    pub fn verify(&self, message: &Hash, tsa_cert: &[u8]) -> Result<(), TimestampError> {
+        let token = self.token.as_deref().ok_or(TimestampError::MissingToken)?;
+        let cert = Certificate::from_der(tsa_cert)?;
+
+        let info = TimestampToken::parse(token)?;
+        if info.message != *message {
+            return Err(TimestampError::MessageMismatch);
+        }
+        if info.time != self.time {
+            return Err(TimestampError::TimeMismatch);
+        }
+
+        info.verify_signature(&cert)
+    }
+}
+
+/// A parsed RFC 3161 `TimeStampToken`, whose signature has not been verified.
+#[derive(Debug, Clone)]
+pub struct TimestampToken {
+    /// The SHA-256 hash which was timestamped.
+    pub message: Hash,
+    /// The time of the token, in seconds since the UNIX epoch.
+    pub time: u64,
+    /// The nonce of the request, as the big endian bytes of a positive integer.
+    pub nonce: Option<Vec<u8>>,
+    tst_info: Vec<u8>,
+    signer: SignerInfo,
+}
+
+impl TimestampToken {
+    /// Parses a DER encoded `TimeStampToken`, without verifying its signature.
+    pub fn parse(token: &[u8]) -> Result<Self, TimestampError> {
+        let content_info = ContentInfo::from_der(token)?;
+        if content_info.content_type != ID_SIGNED_DATA {
+            return Err(TimestampError::InvalidToken("content is not signed data"));
+        }
+        let signed_data: SignedData = content_info.content.decode_as()?;
+
+        let encap = &signed_data.encap_content_info;
+        if encap.econtent_type != ID_CT_TST_INFO {
+            return Err(TimestampError::InvalidToken("content is not a TSTInfo"));
+        }
+        let tst_info = encap
+            .econtent
+            .as_ref()
+            .ok_or(TimestampError::InvalidToken("missing TSTInfo"))?
+            .decode_as::<OctetString>()?
+            .into_bytes();
+        let parsed = TstInfo::from_der(&tst_info)?;
+
+        let imprint = &parsed.message_imprint;
+        if imprint.hash_algorithm.oid != ID_SHA256 {
+            return Err(TimestampError::UnsupportedAlgorithm(
+                imprint.hash_algorithm.oid,
+            ));
+        }
+        let message: [u8; 32] = imprint
+            .hashed_message
+            .as_bytes()
+            .try_into()
+            .map_err(|_| TimestampError::InvalidToken("invalid message imprint"))?;
+
+        let mut signers = signed_data.signer_infos.0.into_vec().into_iter();
+        let (Some(signer), None) = (signers.next(), signers.next()) else {
+            return Err(TimestampError::InvalidToken("expected exactly one signer"));
+        };
+
+        Ok(Self {
+            message: Hash::from(message),
+            time: parse_generalized_time(&parsed.gen_time)?,
+            nonce: parsed.nonce.map(|nonce| nonce.as_bytes().to_vec()),
+            tst_info,
+            signer,
+        })
+    }
+
+    /// Verifies that the token was signed with the certificate, which must be valid for
+    /// time-stamping at the time of the token.
+    pub fn verify_signature(&self, cert: &Certificate) -> Result<(), TimestampError> {
+        let tbs = &cert.tbs_certificate;
+
+        let signed_by_cert = match &self.signer.sid {
+            SignerIdentifier::IssuerAndSerialNumber(id) => {
+                id.issuer == tbs.issuer && id.serial_number == tbs.serial_number
+            }
+            SignerIdentifier::SubjectKeyIdentifier(id) => tbs
+                .get::<SubjectKeyIdentifier>()?
+                .is_some_and(|(_, cert_id)| cert_id == *id),
+        };
+        if !signed_by_cert {
+            return Err(TimestampError::UnknownSigner);
+        }
+
+        let validity = &tbs.validity;
+        if self.time < validity.not_before.to_unix_duration().as_secs()
+            || self.time > validity.not_after.to_unix_duration().as_secs()
+        {
+            return Err(TimestampError::InvalidCertificate(
+                "the token was issued outside the validity of the certificate",
+            ));
+        }
+        // RFC 3161 requires the certificate to be exclusively for time-stamping.
+        match tbs.get::<ExtendedKeyUsage>()? {
+            Some((true, usage)) if usage.0 == [ID_KP_TIME_STAMPING] => {}
+            _ => {
+                return Err(TimestampError::InvalidCertificate(
+                    "the certificate is not exclusively for time-stamping",
+                ))
+            }
+        }
+
+        if self.signer.digest_alg.oid != ID_SHA256 {
+            return Err(TimestampError::UnsupportedAlgorithm(
+                self.signer.digest_alg.oid,
+            ));
+        }
+
+        // The signature is over the signed attributes, which bind the content by its digest.
+        let signed_attrs = self
+            .signer
+            .signed_attrs
+            .as_ref()
+            .ok_or(TimestampError::InvalidToken("missing signed attributes"))?;
+        let attr = |oid: ObjectIdentifier| -> Result<&Any, TimestampError> {
+            let mut attrs = signed_attrs.iter().filter(|attr| attr.oid == oid);
+            match (attrs.next(), attrs.next()) {
+                (Some(attr), None) if attr.values.len() == 1 => Ok(attr.values.get(0).unwrap()),
+                _ => Err(TimestampError::InvalidToken("invalid signed attributes")),
+            }
+        };
+        if attr(ID_CONTENT_TYPE)?.decode_as::<ObjectIdentifier>()? != ID_CT_TST_INFO {
+            return Err(TimestampError::InvalidToken(
+                "invalid content type attribute",
+            ));
+        }
+        if attr(ID_MESSAGE_DIGEST)?
+            .decode_as::<OctetString>()?
+            .as_bytes()
+            != &Sha256::digest(&self.tst_info)[..]
+        {
+            return Err(TimestampError::InvalidToken(
+                "invalid message digest attribute",
+            ));
+        }
+
+        verify_signature(
+            cert,
+            &self.signer.signature_algorithm,
+            &signed_attrs.to_der()?,
+            self.signer.signature.as_bytes(),
+        )
+    }
+}
+
+/// Verifies a SHA-256 signature of the message with the public key of the certificate.
+fn verify_signature(
+    cert: &Certificate,
+    algorithm: &AlgorithmIdentifierOwned,
+    msg: &[u8],
+    signature: &[u8],
+) -> Result<(), TimestampError> {
+    use p256::{ecdsa::signature::Verifier, pkcs8::DecodePublicKey};
+
+    let spki = cert.tbs_certificate.subject_public_key_info.to_der()?;
+    let verified = match algorithm.oid {
+        RSA_ENCRYPTION | SHA256_WITH_RSA_ENCRYPTION => {
+            let key = rsa::RsaPublicKey::from_public_key_der(&spki)
+                .map_err(|_| TimestampError::InvalidCertificate("invalid RSA public key"))?;
+            let signature = rsa::pkcs1v15::Signature::try_from(signature)
+                .map_err(|_| TimestampError::InvalidSignature)?;
+            rsa::pkcs1v15::VerifyingKey::<Sha256>::new(key).verify(msg, &signature)
+        }
+        ECDSA_WITH_SHA256 => {
+            let key = p256::ecdsa::VerifyingKey::from_public_key_der(&spki)
+                .map_err(|_| TimestampError::InvalidCertificate("invalid P-256 public key"))?;
+            let signature = p256::ecdsa::Signature::from_der(signature)
+                .map_err(|_| TimestampError::InvalidSignature)?;
+            key.verify(msg, &signature)
+        }
+        oid => return Err(TimestampError::UnsupportedAlgorithm(oid)),
+    };
+
+    verified.map_err(|_| TimestampError::InvalidSignature)
+}
+
+/// Parses a GeneralizedTime of the form `YYYYMMDDHHMMSS[.f*]Z` into seconds since the UNIX
+/// epoch.
+///
+/// Time-stamp authorities may include fractions of seconds, which the DER `GeneralizedTime` type
+/// does not support, so the time is parsed by hand.
+fn parse_generalized_time(time: &Any) -> Result<u64, TimestampError> {
+    let invalid = || TimestampError::InvalidToken("invalid time");
+
+    if time.tag() != Tag::GeneralizedTime {
+        return Err(invalid());
+    }
+    let Some((b'Z', time)) = time.value().split_last() else {
+        return Err(invalid());
+    };
+    if time.len() < 14 {
+        return Err(invalid());
+    }
+    let (digits, fraction) = time.split_at(14);
+    match fraction {
+        [] => {}
+        [b'.', fraction @ ..]
+            if !fraction.is_empty()
+                && fraction.iter().all(u8::is_ascii_digit)
+                && fraction.last() != Some(&b'0') => {}
+        _ => return Err(invalid()),
+    }
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return Err(invalid());
+    }
+
+    let number = |range: std::ops::Range<usize>| {
+        digits[range]
+            .iter()
+            .fold(0u16, |acc, digit| acc * 10 + (digit - b'0') as u16)
+    };
+    let time = DateTime::new(
+        number(0..4),
+        number(4..6) as u8,
+        number(6..8) as u8,
+        number(8..10) as u8,
+        number(10..12) as u8,
+        number(12..14) as u8,
+    )
+    .map_err(|_| invalid())?;
+
+    Ok(time.unix_duration().as_secs())
+}
+
+/// `MessageImprint`, RFC 3161
+#[derive(Clone, Debug, Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+/// `Accuracy`, RFC 3161
+#[derive(Clone, Debug, Sequence)]
+struct Accuracy {
+    #[asn1(optional = "true")]
+    seconds: Option<Int>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<Int>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<Int>,
+}
+
+/// `TSTInfo`, RFC 3161
+#[derive(Clone, Debug, Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint,
+    serial_number: Int,
+    gen_time: Any,
+    #[asn1(optional = "true")]
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    #[asn1(optional = "true")]
+    nonce: Option<Int>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P256_TOKEN: &[u8] = include_bytes!("../fixtures/testdata/timestamp/p256/token.der");
+    const P256_CERT: &[u8] = include_bytes!("../fixtures/testdata/timestamp/p256/tsa.der");
+    const P256_TIME: u64 = 1792131842;
+    const RSA_TOKEN: &[u8] = include_bytes!("../fixtures/testdata/timestamp/rsa/token.der");
+    const RSA_CERT: &[u8] = include_bytes!("../fixtures/testdata/timestamp/rsa/tsa.der");
+    const RSA_TIME: u64 = 1792131846;
+
+    /// The message timestamped by the fixtures.
+    const MESSAGE: [u8; 32] = [7u8; 32];
+
+    fn timestamp(token: &[u8], time: u64) -> Timestamp {
+        Timestamp::new(
+            TimestampSource::Rfc3161 {
+                url: "http://localhost".to_string(),
+            },
+            time,
+            Some(token.to_vec()),
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        timestamp(P256_TOKEN, P256_TIME)
+            .verify(&Hash::from(MESSAGE), P256_CERT)
+            .unwrap();
+        timestamp(RSA_TOKEN, RSA_TIME)
+            .verify(&Hash::from(MESSAGE), RSA_CERT)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let token = TimestampToken::parse(P256_TOKEN).unwrap();
+
+        assert_eq!(token.message, Hash::from(MESSAGE));
+        assert_eq!(token.time, P256_TIME);
+        assert!(token.nonce.is_some());
+    }
+
+    #[test]
+    fn test_verify_wrong_message() {
+        let err = timestamp(P256_TOKEN, P256_TIME)
+            .verify(&Hash::from([8u8; 32]), P256_CERT)
+            .unwrap_err();
+
+        assert!(matches!(err, TimestampError::MessageMismatch));
+    }
+
+    #[test]
+    fn test_verify_wrong_time() {
+        let err = timestamp(P256_TOKEN, P256_TIME + 1)
+            .verify(&Hash::from(MESSAGE), P256_CERT)
+            .unwrap_err();
+
+        assert!(matches!(err, TimestampError::TimeMismatch));
+    }
+
+    #[test]
+    fn test_verify_wrong_cert() {
+        let err = timestamp(P256_TOKEN, P256_TIME)
+            .verify(&Hash::from(MESSAGE), RSA_CERT)
+            .unwrap_err();
+
+        assert!(matches!(err, TimestampError::UnknownSigner));
+    }
+
+    #[test]
+    fn test_verify_missing_token() {
+        let timestamp = Timestamp::new(TimestampSource::SystemClock, P256_TIME, None);
+
+        assert!(matches!(
+            timestamp.verify(&Hash::from(MESSAGE), P256_CERT),
+            Err(TimestampError::MissingToken)
+        ));
+    }
+
+    #[test]
+    fn test_verify_tampered_signature() {
+        let mut token = P256_TOKEN.to_vec();
+        // The signature is at the end of the token.
+        let last = token.len() - 1;
+        token[last] ^= 1;
+
+        assert!(timestamp(&token, P256_TIME)
+            .verify(&Hash::from(MESSAGE), P256_CERT)
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_generalized_time() {
+        let time =
+            |value: &[u8]| parse_generalized_time(&Any::new(Tag::GeneralizedTime, value).unwrap());
+
+        assert_eq!(time(b"20240102030405Z").unwrap(), 1704164645);
+        assert_eq!(time(b"20240102030405.5Z").unwrap(), 1704164645);
+        assert_eq!(time(b"20240102030405.123Z").unwrap(), 1704164645);
+        assert!(time(b"20240102030405.50Z").is_err());
+        assert!(time(b"20240102030405.Z").is_err());
+        assert!(time(b"20240102030405").is_err());
+        assert!(time(b"2024010203040Z").is_err());
+        assert!(time(b"20241302030405Z").is_err());
+        assert!(parse_generalized_time(
+            &Any::new(Tag::UtcTime, b"240102030405Z".as_slice()).unwrap()
+        )
+        .is_err());
+    }
+}
@@ -1,6 +1,13 @@
+use mpz_core::{hash::Hash, utils::blake3};
 use serde::{Deserialize, Serialize};
 
-use p256::ecdsa::{signature::Verifier, VerifyingKey};
+use p256::{
+    ecdsa::{signature::Verifier, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+};
+
+#[cfg(feature = "bls")]
+use crate::bls::{BlsPublicKey, BlsSignature};
 
 /// A Notary public key.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -8,6 +15,9 @@ use p256::ecdsa::{signature::Verifier, VerifyingKey};
 pub enum NotaryPublicKey {
     /// A NIST P-256 public key.
     P256(p256::PublicKey),
+    /// A BLS12-381 public key.
+    #[cfg(feature = "bls")]
+    Bls(BlsPublicKey),
 }
 
 impl From<p256::PublicKey> for NotaryPublicKey {
@@ -16,10 +26,53 @@ impl From<p256::PublicKey> for NotaryPublicKey {
     }
 }
 
+impl NotaryPublicKey {
+    /// Returns the fingerprint of the key, which is the BLAKE3 hash of its scheme and its
+    /// compressed encoding.
+    ///
+    /// Unlike the identifier of a key embedded in a session header, which is chosen by the
+    /// signer, the fingerprint is bound to the key itself.
+    pub fn fingerprint(&self) -> Hash {
+        let mut bytes = Vec::new();
+        match self {
+            Self::P256(key) => {
+                bytes.extend_from_slice(b"p256");
+                bytes.extend_from_slice(key.to_encoded_point(true).as_bytes());
+            }
+            #[cfg(feature = "bls")]
+            Self::Bls(key) => {
+                bytes.extend_from_slice(b"bls12-381");
+                bytes.extend_from_slice(&key.to_bytes());
+            }
+        }
+
+        Hash::from(blake3(&bytes))
+    }
+}
+
+#[cfg(feature = "bls")]
+impl From<BlsPublicKey> for NotaryPublicKey {
+    fn from(key: BlsPublicKey) -> Self {
+        Self::Bls(key)
+    }
+}
+
+/// Returns the message a Notary signs for an object of the provided domain.
+///
+/// The message is prefixed with the length and the name of the domain, so that the signature of
+/// one kind of object, e.g. a revocation list, can not be passed off as the signature of another.
+pub(crate) fn domain_separated(domain: &[u8], msg: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(1 + domain.len() + msg.len());
+    bytes.push(domain.len() as u8);
+    bytes.extend_from_slice(domain);
+    bytes.extend_from_slice(msg);
+    bytes
+}
+
 /// An error occurred while verifying a signature.
 #[derive(Debug, thiserror::Error)]
 #[error("signature verification failed: {0}")]
-pub struct SignatureVerifyError(String);
+pub struct SignatureVerifyError(pub(crate) String);
 
 /// A Notary signature.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -27,6 +80,9 @@ pub struct SignatureVerifyError(String);
 pub enum Signature {
     /// A secp256r1 signature.
     P256(p256::ecdsa::Signature),
+    /// A BLS12-381 signature.
+    #[cfg(feature = "bls")]
+    Bls(BlsSignature),
 }
 
 impl From<p256::ecdsa::Signature> for Signature {
@@ -35,11 +91,20 @@ impl From<p256::ecdsa::Signature> for Signature {
     }
 }
 
+#[cfg(feature = "bls")]
+impl From<BlsSignature> for Signature {
+    fn from(sig: BlsSignature) -> Self {
+        Self::Bls(sig)
+    }
+}
+
 impl Signature {
     /// Returns the bytes of this signature.
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             Self::P256(sig) => sig.to_vec(),
+            #[cfg(feature = "bls")]
+            Self::Bls(sig) => sig.to_bytes().to_vec(),
         }
     }
 
@@ -58,6 +123,12 @@ impl Signature {
             (Self::P256(sig), NotaryPublicKey::P256(key)) => VerifyingKey::from(key)
                 .verify(msg, sig)
                 .map_err(|e| SignatureVerifyError(e.to_string())),
+            #[cfg(feature = "bls")]
+            (Self::Bls(sig), NotaryPublicKey::Bls(key)) => sig.verify(msg, &key),
+            #[cfg(feature = "bls")]
+            _ => Err(SignatureVerifyError(
+                "the signature scheme does not match the public key".to_string(),
+            )),
         }
     }
 }
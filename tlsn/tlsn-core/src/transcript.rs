@@ -9,16 +9,141 @@ use utils::range::{RangeDifference, RangeSet, RangeUnion};
 pub(crate) static TX_TRANSCRIPT_ID: &str = "tx";
 pub(crate) static RX_TRANSCRIPT_ID: &str = "rx";
 
+/// An error for [`Transcript`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TranscriptError {
+    /// The lengths of the records do not add up to the length of the transcript.
+    #[error(
+        "record lengths add up to {actual} bytes, but the transcript is {expected} bytes long"
+    )]
+    RecordLength {
+        /// The length of the transcript.
+        expected: usize,
+        /// The sum of the lengths of the records.
+        actual: usize,
+    },
+    /// The number of values does not match the number of records.
+    #[error("{actual} values were provided for {expected} records")]
+    RecordCount {
+        /// The number of records.
+        expected: usize,
+        /// The number of values provided.
+        actual: usize,
+    },
+}
+
 /// A transcript contains a subset of bytes from a TLS session
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Transcript {
     data: Bytes,
+    /// The TLS records the data was transmitted in, if known.
+    #[serde(default)]
+    records: Vec<TranscriptRecord>,
 }
 
 impl Transcript {
     /// Creates a new transcript with the given ID and data
     pub fn new(data: impl Into<Bytes>) -> Self {
-        Self { data: data.into() }
+        Self {
+            data: data.into(),
+            records: Vec::new(),
+        }
+    }
+
+    /// Sets the TLS records the data was transmitted in.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - The direction of the records.
+    /// * `records` - The sequence number, plaintext length and time of each record, in order. The
+    ///   time is in milliseconds since the UNIX epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lengths of the records do not add up to the length of the data.
+    pub fn with_records(
+        mut self,
+        direction: Direction,
+        records: impl IntoIterator<Item = (u64, usize, u64)>,
+    ) -> Result<Self, TranscriptError> {
+        let mut start = 0;
+        self.records = records
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (seq, len, time))| {
+                let record = TranscriptRecord {
+                    direction,
+                    seq,
+                    range: start..start + len,
+                    time,
+                    // The records sent before a sent record are the ones preceding it.
+                    sent_before: (direction == Direction::Sent).then_some(idx),
+                };
+                start += len;
+                record
+            })
+            .collect();
+
+        if start != self.data.len() {
+            return Err(TranscriptError::RecordLength {
+                expected: self.data.len(),
+                actual: start,
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the number of records sent before each of the records of the transcript was received.
+    ///
+    /// This only needs to be set for received records, as it is known for sent records.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of values does not match the number of records.
+    pub fn with_record_order(
+        mut self,
+        sent_before: impl IntoIterator<Item = usize>,
+    ) -> Result<Self, TranscriptError> {
+        let sent_before = sent_before.into_iter().collect::<Vec<_>>();
+        if sent_before.len() != self.records.len() {
+            return Err(TranscriptError::RecordCount {
+                expected: self.records.len(),
+                actual: sent_before.len(),
+            });
+        }
+
+        for (record, sent_before) in self.records.iter_mut().zip(sent_before) {
+            record.sent_before = Some(sent_before);
+        }
+
+        Ok(self)
+    }
+
+    /// Replaces the times of the records of the transcript, e.g. with the times observed by the
+    /// Notary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of times does not match the number of records.
+    pub fn with_record_times(
+        mut self,
+        times: impl IntoIterator<Item = u64>,
+    ) -> Result<Self, TranscriptError> {
+        let times = times.into_iter().collect::<Vec<_>>();
+        if times.len() != self.records.len() {
+            return Err(TranscriptError::RecordCount {
+                expected: self.records.len(),
+                actual: times.len(),
+            });
+        }
+
+        for (record, time) in self.records.iter_mut().zip(times) {
+            record.time = time;
+        }
+
+        Ok(self)
     }
 
     /// Returns the actual traffic data of this transcript
@@ -26,6 +151,23 @@ impl Transcript {
         &self.data
     }
 
+    /// Returns the TLS records the data was transmitted in, in order.
+    ///
+    /// Empty if the records are not known.
+    pub fn records(&self) -> &[TranscriptRecord] {
+        &self.records
+    }
+
+    /// Returns the TLS record which contains the byte at the given offset, if known.
+    pub fn record_at(&self, offset: usize) -> Option<&TranscriptRecord> {
+        let idx = self
+            .records
+            .partition_point(|record| record.range.end <= offset);
+        self.records
+            .get(idx)
+            .filter(|record| record.range.contains(&offset))
+    }
+
     /// Returns a concatenated bytestring located in the given ranges of the transcript.
     ///
     /// # Panics
@@ -43,6 +185,51 @@ impl Transcript {
     }
 }
 
+/// A TLS record of application data within a [`Transcript`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptRecord {
+    direction: Direction,
+    seq: u64,
+    range: Range<usize>,
+    time: u64,
+    #[serde(default)]
+    sent_before: Option<usize>,
+}
+
+impl TranscriptRecord {
+    /// Returns the direction of the record.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the TLS sequence number of the record.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the range of the plaintext of the record within the transcript.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the time at which the record was sent or received, in milliseconds since the UNIX
+    /// epoch.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Returns the number of records which were sent before this record was sent or received, if
+    /// known.
+    pub fn sent_before(&self) -> Option<usize> {
+        self.sent_before
+    }
+
+    /// Returns the data of the record in the transcript.
+    pub fn data<'a>(&self, transcript: &'a Transcript) -> &'a [u8] {
+        &transcript.data[self.range()]
+    }
+}
+
 /// A transcript which may have some data redacted.
 #[derive(Debug)]
 pub struct RedactedTranscript {
@@ -225,6 +412,52 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_records(transcripts: (Transcript, Transcript)) {
+        let (_, recv) = transcripts;
+        let recv = recv
+            .with_records(Direction::Received, [(1, 14, 1_000), (2, 9, 1_200)])
+            .unwrap();
+
+        assert_eq!(recv.records().len(), 2);
+        assert_eq!(recv.records()[1].range(), 14..23);
+        assert_eq!(recv.records()[1].data(&recv), b"987654321");
+
+        assert_eq!(recv.record_at(13).unwrap().seq(), 1);
+        assert_eq!(recv.record_at(14).unwrap().seq(), 2);
+        assert!(recv.record_at(23).is_none());
+        assert_eq!(recv.records()[1].time(), 1_200);
+        assert_eq!(recv.records()[1].sent_before(), None);
+
+        let err = recv.clone().with_record_order([0]).unwrap_err();
+        assert!(matches!(
+            err,
+            TranscriptError::RecordCount {
+                expected: 2,
+                actual: 1
+            }
+        ));
+
+        let recv = recv.with_record_order([0, 1]).unwrap();
+        assert_eq!(recv.records()[1].sent_before(), Some(1));
+
+        assert!(recv.clone().with_record_times([1_100]).is_err());
+        let recv = recv.with_record_times([1_100, 1_300]).unwrap();
+        assert_eq!(recv.records()[1].time(), 1_300);
+    }
+
+    #[rstest]
+    fn test_records_length_mismatch(transcripts: (Transcript, Transcript)) {
+        let (sent, _) = transcripts;
+        let err = sent
+            .with_records(Direction::Sent, [(1, 5, 1_000)])
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TranscriptError::RecordLength { actual: 5, .. }
+        ));
+    }
+
     #[rstest]
     #[should_panic]
     fn test_get_bytes_in_ranges_empty(transcripts: (Transcript, Transcript)) {
@@ -0,0 +1,790 @@
+//! Transparency logs of attestations.
+//!
+//! A Notary can record the hash of the header of every session it signs (see
+//! [`SessionHeader::hash`]) in an append-only log, before it signs the header. The log is a Merkle
+//! tree as specified in [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1), with
+//! SHA-256 as hash function. The Notary signs the head of the log, i.e. its size and root, so that
+//! relying parties can audit how many and which attestations it issued, and check that an
+//! attestation is recorded in the log with an [`InclusionProof`].
+//!
+//! A [`ConsistencyProof`] proves that a head extends an earlier head, i.e. that the entries of the
+//! earlier head were neither removed nor changed. Two signed heads which are not consistent prove
+//! that the Notary forked its log.
+
+use mpz_core::{hash::Hash, serialize::CanonicalSerialize};
+use p256::ecdsa::signature::Signer;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    signature::domain_separated, NotaryPublicKey, SessionHeader, Signature, SignatureVerifyError,
+};
+
+/// Domain of the signatures of log heads.
+const LOG_HEAD_DOMAIN: &[u8] = b"tlsn/transparency-log-head/v1";
+
+/// An error for [`InclusionProof::verify`] and [`ConsistencyProof::verify`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TransparencyLogError {
+    /// The signature of the head of the log is invalid.
+    #[error(transparent)]
+    InvalidSignature(#[from] SignatureVerifyError),
+    /// The entry does not record the session.
+    #[error("the log entry does not record the session")]
+    SessionMismatch,
+    /// The proof does not lead to the head of the log.
+    #[error("the inclusion proof does not lead to the head of the log")]
+    HeadMismatch,
+    /// The later head does not extend the earlier head.
+    #[error("the heads of the log are inconsistent")]
+    Inconsistent,
+}
+
+/// An entry of a transparency log, recording a session signed by the Notary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    index: u64,
+    session: [u8; 32],
+    logged_at: u64,
+}
+
+impl LogEntry {
+    /// Creates a new log entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the entry in the log.
+    /// * `header_hash` - The hash of the header of the session.
+    /// * `logged_at` - The time the entry is appended, in seconds since the UNIX epoch.
+    pub fn new(index: u64, header_hash: Hash, logged_at: u64) -> Self {
+        Self {
+            index,
+            session: *header_hash.as_bytes(),
+            logged_at,
+        }
+    }
+
+    /// Returns the index of the entry in the log.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns the hash of the header of the session.
+    pub fn session(&self) -> Hash {
+        Hash::from(self.session)
+    }
+
+    /// Returns the time the entry was appended, in seconds since the UNIX epoch.
+    pub fn logged_at(&self) -> u64 {
+        self.logged_at
+    }
+
+    /// Returns the hash of the entry, which is its leaf in the Merkle tree of the log.
+    pub fn hash(&self) -> Hash {
+        Hash::from(leaf_hash(&self.to_bytes()))
+    }
+}
+
+/// The head of a transparency log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogHead {
+    size: u64,
+    root: [u8; 32],
+    issued_at: u64,
+}
+
+impl LogHead {
+    /// Creates a new log head.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of entries of the log.
+    /// * `root` - The root of the Merkle tree of the entries.
+    /// * `issued_at` - The time the head is issued, in seconds since the UNIX epoch.
+    pub fn new(size: u64, root: Hash, issued_at: u64) -> Self {
+        Self {
+            size,
+            root: *root.as_bytes(),
+            issued_at,
+        }
+    }
+
+    /// Returns the number of entries of the log.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the root of the Merkle tree of the entries.
+    pub fn root(&self) -> Hash {
+        Hash::from(self.root)
+    }
+
+    /// Returns the time the head was issued, in seconds since the UNIX epoch.
+    pub fn issued_at(&self) -> u64 {
+        self.issued_at
+    }
+
+    /// Signs the head.
+    pub fn sign<T>(self, signer: &impl Signer<T>) -> SignedLogHead
+    where
+        T: Into<Signature>,
+    {
+        let signature = signer.sign(&self.signed_bytes()).into();
+
+        SignedLogHead {
+            head: self,
+            signature,
+        }
+    }
+
+    /// Returns the bytes of the head which are signed.
+    fn signed_bytes(&self) -> Vec<u8> {
+        domain_separated(LOG_HEAD_DOMAIN, &self.to_bytes())
+    }
+}
+
+/// A log head signed by a Notary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedLogHead {
+    /// The head of the log.
+    pub head: LogHead,
+    /// The signature of the Notary over the head.
+    pub signature: Signature,
+}
+
+impl SignedLogHead {
+    /// Verifies the signature of the head, returning the head if it is valid.
+    pub fn verify(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<&LogHead, SignatureVerifyError> {
+        self.signature
+            .verify(&self.head.signed_bytes(), notary_public_key)
+            .map(|_| &self.head)
+    }
+}
+
+/// A proof that an entry is included in a transparency log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    entry: LogEntry,
+    path: Vec<[u8; 32]>,
+}
+
+impl InclusionProof {
+    /// Creates a new inclusion proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry.
+    /// * `path` - The inclusion path from the entry to the root of the tree of the head the proof
+    ///   leads to, see [`LogTree::inclusion_path`].
+    pub fn new(entry: LogEntry, path: Vec<Hash>) -> Self {
+        Self {
+            entry,
+            path: path.iter().map(|hash| *hash.as_bytes()).collect(),
+        }
+    }
+
+    /// Returns the entry.
+    pub fn entry(&self) -> &LogEntry {
+        &self.entry
+    }
+
+    /// Verifies that the entry records the session with the provided header, and that it is
+    /// included in the log with the provided head.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The header of the session, which should be verified beforehand.
+    /// * `head` - The head of the log, which should be verified beforehand.
+    pub fn verify(
+        &self,
+        header: &SessionHeader,
+        head: &LogHead,
+    ) -> Result<(), TransparencyLogError> {
+        if self.entry.session != *header.hash().as_bytes() {
+            return Err(TransparencyLogError::SessionMismatch);
+        }
+
+        if !verify_inclusion(
+            self.entry.index,
+            head.size,
+            *self.entry.hash().as_bytes(),
+            &self.path,
+            &head.root,
+        ) {
+            return Err(TransparencyLogError::HeadMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the signature of the head, and that the entry records the session with the
+    /// provided header and is included in the log with the head.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The header of the session, which should be verified beforehand.
+    /// * `head` - The signed head of the log.
+    /// * `notary_public_key` - The public key of the Notary which signed the head.
+    pub fn verify_signed(
+        &self,
+        header: &SessionHeader,
+        head: &SignedLogHead,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), TransparencyLogError> {
+        self.verify(header, head.verify(notary_public_key)?)
+    }
+}
+
+/// A Merkle tree of the entries of a transparency log, as specified in RFC 6962.
+///
+/// The tree keeps the root of every complete subtree, so that the roots, inclusion proofs and
+/// consistency proofs of the log at any size are computed in logarithmic time.
+#[derive(Debug, Clone, Default)]
+pub struct LogTree {
+    /// The roots of the complete subtrees of each height, from the leaves up.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl LogTree {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of leaves of the tree.
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Returns `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends a leaf to the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The hash of the leaf, see [`LogEntry::hash`].
+    pub fn push(&mut self, leaf: Hash) {
+        let mut node = *leaf.as_bytes();
+        for height in 0.. {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+
+            let level = &mut self.levels[height];
+            level.push(node);
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = node_hash(&level[level.len() - 2], &level[level.len() - 1]);
+        }
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> Hash {
+        self.root_at(self.len())
+            .expect("the size of the tree is in bounds")
+    }
+
+    /// Returns the root of the tree when it had the provided number of leaves, or `None` if the
+    /// tree has fewer leaves.
+    pub fn root_at(&self, size: usize) -> Option<Hash> {
+        if size > self.len() {
+            return None;
+        }
+
+        Some(Hash::from(self.range_root(0, size)))
+    }
+
+    /// Returns the path from the leaf at the provided index to the root of the tree when it had
+    /// the provided number of leaves, or `None` if the leaf is not in the tree of that size.
+    pub fn inclusion_path(&self, index: usize, size: usize) -> Option<Vec<Hash>> {
+        if index >= size || size > self.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        self.inclusion_subpath(index, 0, size, &mut path);
+
+        Some(path.into_iter().map(Hash::from).collect())
+    }
+
+    /// Returns the path proving that the tree with `second` leaves extends the tree with `first`
+    /// leaves, or `None` if `first` is larger than `second` or the tree has fewer leaves than
+    /// `second`.
+    pub fn consistency_path(&self, first: usize, second: usize) -> Option<Vec<Hash>> {
+        if first > second || second > self.len() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        if first > 0 && first < second {
+            self.consistency_subpath(first, 0, second, true, &mut path);
+        }
+
+        Some(path.into_iter().map(Hash::from).collect())
+    }
+
+    /// Returns the root of the leaves in `start..end`, where `start` is a multiple of the largest
+    /// power of two not greater than `end - start`, as is the case for every subtree of RFC 6962.
+    fn range_root(&self, start: usize, end: usize) -> [u8; 32] {
+        // The leaves split into complete subtrees of decreasing sizes, whose roots are hashed
+        // together from the right.
+        let mut roots = Vec::new();
+        let mut offset = start;
+        while offset < end {
+            let height = (end - offset).ilog2() as usize;
+            roots.push(self.levels[height][offset >> height]);
+            offset += 1 << height;
+        }
+
+        roots
+            .into_iter()
+            .rev()
+            .reduce(|right, left| node_hash(&left, &right))
+            .unwrap_or_else(|| Sha256::digest([]).into())
+    }
+
+    fn inclusion_subpath(&self, index: usize, start: usize, end: usize, path: &mut Vec<[u8; 32]>) {
+        if end - start == 1 {
+            return;
+        }
+
+        let split = start + split_point(end - start);
+        if index < split {
+            self.inclusion_subpath(index, start, split, path);
+            path.push(self.range_root(split, end));
+        } else {
+            self.inclusion_subpath(index, split, end, path);
+            path.push(self.range_root(start, split));
+        }
+    }
+
+    fn consistency_subpath(
+        &self,
+        first: usize,
+        start: usize,
+        end: usize,
+        complete: bool,
+        path: &mut Vec<[u8; 32]>,
+    ) {
+        if start + first == end {
+            if !complete {
+                path.push(self.range_root(start, end));
+            }
+            return;
+        }
+
+        let split = split_point(end - start);
+        if first <= split {
+            self.consistency_subpath(first, start, start + split, complete, path);
+            path.push(self.range_root(start + split, end));
+        } else {
+            self.consistency_subpath(first - split, start + split, end, false, path);
+            path.push(self.range_root(start, start + split));
+        }
+    }
+}
+
+/// A proof that a log head extends an earlier log head.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    path: Vec<[u8; 32]>,
+}
+
+impl ConsistencyProof {
+    /// Creates a new consistency proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The consistency path between the trees of the two heads, see
+    ///   [`LogTree::consistency_path`].
+    pub fn new(path: Vec<Hash>) -> Self {
+        Self {
+            path: path.iter().map(|hash| *hash.as_bytes()).collect(),
+        }
+    }
+
+    /// Verifies that the `second` head extends the `first` head.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The earlier head of the log, which should be verified beforehand.
+    /// * `second` - The later head of the log, which should be verified beforehand.
+    pub fn verify(&self, first: &LogHead, second: &LogHead) -> Result<(), TransparencyLogError> {
+        if verify_consistency(
+            first.size,
+            second.size,
+            &first.root,
+            &second.root,
+            &self.path,
+        ) {
+            Ok(())
+        } else {
+            Err(TransparencyLogError::Inconsistent)
+        }
+    }
+
+    /// Verifies the signatures of the heads, and that the `second` head extends the `first` head.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The earlier signed head of the log.
+    /// * `second` - The later signed head of the log.
+    /// * `notary_public_key` - The public key of the Notary which signed the heads.
+    pub fn verify_signed(
+        &self,
+        first: &SignedLogHead,
+        second: &SignedLogHead,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(), TransparencyLogError> {
+        let notary_public_key = notary_public_key.into();
+        self.verify(
+            first.verify(notary_public_key.clone())?,
+            second.verify(notary_public_key)?,
+        )
+    }
+}
+
+/// Returns the size of the left subtree of a tree with `size > 1` leaves, which is the largest
+/// power of two smaller than `size`.
+fn split_point(size: usize) -> usize {
+    1 << (size - 1).ilog2()
+}
+
+/// Returns the hash of a leaf with the provided data.
+fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Returns the hash of an interior node with the provided children.
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Verifies an inclusion path, as specified in section 2.1.3.2 of RFC 9162.
+fn verify_inclusion(
+    index: u64,
+    size: u64,
+    leaf: [u8; 32],
+    path: &[[u8; 32]],
+    root: &[u8; 32],
+) -> bool {
+    if index >= size {
+        return false;
+    }
+
+    let (mut fn_, mut sn) = (index, size - 1);
+    let mut node = leaf;
+    for sibling in path {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            node = node_hash(sibling, &node);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            node = node_hash(&node, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && node == *root
+}
+
+/// Verifies a consistency path, as specified in section 2.1.4.2 of RFC 9162.
+fn verify_consistency(
+    first: u64,
+    second: u64,
+    first_root: &[u8; 32],
+    second_root: &[u8; 32],
+    path: &[[u8; 32]],
+) -> bool {
+    if first > second {
+        return false;
+    } else if first == second {
+        return path.is_empty() && first_root == second_root;
+    } else if first == 0 {
+        // The empty log is extended by every log.
+        return path.is_empty();
+    }
+
+    // The root of the first tree is a node of the second tree if the first tree is complete.
+    let path = if first.is_power_of_two() {
+        [&[*first_root], path].concat()
+    } else {
+        path.to_vec()
+    };
+    let Some((start, path)) = path.split_first() else {
+        return false;
+    };
+
+    let (mut fn_, mut sn) = (first - 1, second - 1);
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    let (mut first_node, mut second_node) = (*start, *start);
+    for node in path {
+        if sn == 0 {
+            return false;
+        }
+
+        if fn_ & 1 == 1 || fn_ == sn {
+            first_node = node_hash(node, &first_node);
+            second_node = node_hash(node, &second_node);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            second_node = node_hash(&second_node, node);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    first_node == *first_root && second_node == *second_root && sn == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{notary_signing_key, session_header};
+
+    fn header(seed: u8) -> SessionHeader {
+        session_header([seed; 32].into(), 100, 100)
+    }
+
+    fn tree(size: usize) -> LogTree {
+        let mut tree = LogTree::new();
+        for index in 0..size {
+            tree.push(Hash::from(leaf_hash(&index.to_be_bytes())));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_log_tree_root() {
+        // The test vectors of the reference implementation of RFC 6962.
+        let leaves: [&[u8]; 8] = [
+            b"",
+            b"\x00",
+            b"\x10",
+            b"\x20\x21",
+            b"\x30\x31",
+            b"\x40\x41\x42\x43",
+            b"\x50\x51\x52\x53\x54\x55\x56\x57",
+            b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+        ];
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+
+        let mut tree = LogTree::new();
+        assert_eq!(
+            hex::encode(tree.root().as_bytes()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        for (leaf, root) in leaves.iter().zip(roots) {
+            tree.push(Hash::from(leaf_hash(leaf)));
+            assert_eq!(hex::encode(tree.root().as_bytes()), root);
+        }
+        for (size, root) in roots.iter().enumerate() {
+            assert_eq!(
+                hex::encode(tree.root_at(size + 1).unwrap().as_bytes()),
+                *root
+            );
+        }
+        assert!(tree.root_at(9).is_none());
+    }
+
+    #[test]
+    fn test_log_tree_inclusion_paths() {
+        let tree = tree(33);
+        for size in 1..=tree.len() {
+            let root = *tree.root_at(size).unwrap().as_bytes();
+            for index in 0..size {
+                let leaf = leaf_hash(&index.to_be_bytes());
+                let path = tree
+                    .inclusion_path(index, size)
+                    .unwrap()
+                    .iter()
+                    .map(|hash| *hash.as_bytes())
+                    .collect::<Vec<_>>();
+
+                assert!(verify_inclusion(
+                    index as u64,
+                    size as u64,
+                    leaf,
+                    &path,
+                    &root
+                ));
+                assert!(!verify_inclusion(
+                    index as u64,
+                    size as u64,
+                    leaf_hash(b"other"),
+                    &path,
+                    &root
+                ));
+                if let Some((sibling, rest)) = path.split_first() {
+                    let tampered = [&[leaf_hash(sibling)], rest].concat();
+                    assert!(!verify_inclusion(
+                        index as u64,
+                        size as u64,
+                        leaf,
+                        &tampered,
+                        &root
+                    ));
+                }
+            }
+            assert!(tree.inclusion_path(size, size).is_none());
+        }
+    }
+
+    #[test]
+    fn test_log_tree_consistency_paths() {
+        let tree = tree(33);
+        for second in 0..=tree.len() {
+            let second_root = *tree.root_at(second).unwrap().as_bytes();
+            for first in 0..=second {
+                let first_root = *tree.root_at(first).unwrap().as_bytes();
+                let path = tree
+                    .consistency_path(first, second)
+                    .unwrap()
+                    .iter()
+                    .map(|hash| *hash.as_bytes())
+                    .collect::<Vec<_>>();
+
+                assert!(verify_consistency(
+                    first as u64,
+                    second as u64,
+                    &first_root,
+                    &second_root,
+                    &path
+                ));
+                if first > 0 {
+                    // A fork of the first tree.
+                    assert!(!verify_consistency(
+                        first as u64,
+                        second as u64,
+                        &leaf_hash(b"fork"),
+                        &second_root,
+                        &path
+                    ));
+                }
+                if first < second {
+                    assert!(!verify_consistency(
+                        second as u64,
+                        first as u64,
+                        &second_root,
+                        &first_root,
+                        &path
+                    ));
+                }
+            }
+        }
+        assert!(tree.consistency_path(2, 34).is_none());
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        let mut tree = LogTree::new();
+        let entries = (0..5)
+            .map(|seed| LogEntry::new(seed as u64, header(seed).hash(), 0))
+            .collect::<Vec<_>>();
+        for entry in &entries {
+            tree.push(entry.hash());
+        }
+
+        let head = LogHead::new(5, tree.root(), 0).sign::<p256::ecdsa::Signature>(&signing_key);
+        let proof = InclusionProof::new(entries[1], tree.inclusion_path(1, 5).unwrap());
+
+        proof.verify_signed(&header(1), &head, public_key).unwrap();
+        assert!(matches!(
+            proof.verify_signed(&header(2), &head, public_key),
+            Err(TransparencyLogError::SessionMismatch)
+        ));
+
+        // A head which the proof does not lead to.
+        let head = LogHead::new(4, tree.root_at(4).unwrap(), 0);
+        assert!(matches!(
+            proof.verify(&header(1), &head),
+            Err(TransparencyLogError::HeadMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_consistency_proof() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        let tree = tree(7);
+        let first = LogHead::new(3, tree.root_at(3).unwrap(), 0)
+            .sign::<p256::ecdsa::Signature>(&signing_key);
+        let second = LogHead::new(7, tree.root(), 1).sign::<p256::ecdsa::Signature>(&signing_key);
+
+        let proof = ConsistencyProof::new(tree.consistency_path(3, 7).unwrap());
+        proof.verify_signed(&first, &second, public_key).unwrap();
+
+        // A fork of the log with a different entry.
+        let mut fork = LogTree::new();
+        for index in 0..7usize {
+            let leaf = if index == 1 { 9usize } else { index };
+            fork.push(Hash::from(leaf_hash(&leaf.to_be_bytes())));
+        }
+        let forked = LogHead::new(7, fork.root(), 1);
+        assert!(matches!(
+            proof.verify(&first.head, &forked),
+            Err(TransparencyLogError::Inconsistent)
+        ));
+    }
+
+    #[test]
+    fn test_log_head_signature_is_domain_separated() {
+        let signing_key = notary_signing_key();
+        let public_key = p256::PublicKey::from(signing_key.verifying_key());
+
+        let head = LogHead::new(1, tree(1).root(), 0);
+        let signature: p256::ecdsa::Signature = signing_key.sign(&head.to_bytes());
+        let signed = SignedLogHead {
+            head,
+            signature: signature.into(),
+        };
+
+        assert!(signed.verify(public_key).is_err());
+        assert!(head
+            .sign::<p256::ecdsa::Signature>(&signing_key)
+            .verify(public_key)
+            .is_ok());
+    }
+}
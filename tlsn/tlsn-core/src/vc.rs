@@ -0,0 +1,521 @@
+//! Export of proofs as W3C Verifiable Credentials.
+//!
+//! A [`TlsProof`] can be wrapped in a [`VerifiableCredential`] issued by the Notary, so that
+//! TLSNotary proofs can be stored in VC wallets and handled by verification middleware. The
+//! subject of the credential describes the server and the disclosed parts of the transcript, and
+//! its proof embeds the [`TlsProof`] itself, encoded as canonical JSON (see [`json`](crate::json)).
+//! The Notary does not sign the credential: its signature is the signature of the session header
+//! contained in the embedded proof, which [`VerifiableCredential::verify`] checks along with the
+//! subject of the credential.
+//!
+//! Credentials are presented in a [`VerifiablePresentation`], either in its JSON-LD form or as a
+//! JWT signed by the holder with ES256 (see [`VerifiablePresentation::to_jwt`]).
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use p256::ecdsa::signature::{Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use tls_core::verify::ServerCertVerifier;
+
+use crate::{
+    json::{from_canonical_json, to_canonical_json, CanonicalJsonError},
+    proof::{SessionProofError, SubstringsProofError, TlsProof, VerifiedProof},
+    NotaryPublicKey, RedactedTranscript,
+};
+
+/// The base context of Verifiable Credentials.
+pub const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+/// The vocabulary of the terms specific to TLSNotary credentials.
+pub const TLSN_VOCAB: &str = "https://tlsnotary.org/vocab#";
+/// The type of the credentials issued for TLS sessions.
+pub const CREDENTIAL_TYPE: &str = "TlsSessionCredential";
+/// The type of the proof of a credential, which embeds a [`TlsProof`].
+pub const PROOF_TYPE: &str = "TlsNotaryProof";
+
+/// An error for [`VerifiableCredential`] and [`VerifiablePresentation`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CredentialError {
+    /// The session proof is invalid.
+    #[error(transparent)]
+    SessionProof(#[from] SessionProofError),
+    /// The substrings proof is invalid.
+    #[error(transparent)]
+    SubstringsProof(#[from] SubstringsProofError),
+    /// The embedded proof could not be encoded or decoded.
+    #[error(transparent)]
+    Json(#[from] CanonicalJsonError),
+    /// The proof of the credential is not a valid TLSNotary proof.
+    #[error("invalid credential proof: {0}")]
+    InvalidProof(String),
+    /// The claims of the credential do not match its proof.
+    #[error("the credential does not match its proof")]
+    ClaimsMismatch,
+    /// The JWT is malformed or its signature is invalid.
+    #[error("invalid JWT: {0}")]
+    InvalidJwt(String),
+}
+
+/// A Verifiable Credential attesting to the contents of a TLS session, issued by the Notary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiableCredential {
+    /// The JSON-LD contexts of the credential.
+    #[serde(rename = "@context")]
+    pub context: Vec<serde_json::Value>,
+    /// The types of the credential.
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    /// The identifier of the Notary.
+    pub issuer: String,
+    /// The time the session took place, as an RFC 3339 UTC date-time.
+    pub issuance_date: String,
+    /// The claims about the session.
+    pub credential_subject: TlsSessionSubject,
+    /// The proof of the claims.
+    pub proof: CredentialProof,
+}
+
+/// The subject of a [`VerifiableCredential`], i.e. what was disclosed of a TLS session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsSessionSubject {
+    /// The type of the subject.
+    #[serde(rename = "type")]
+    pub typ: String,
+    /// The name of the server.
+    pub server_name: String,
+    /// The data sent to the server.
+    pub sent: DisclosedTranscript,
+    /// The data received from the server.
+    pub received: DisclosedTranscript,
+}
+
+/// The disclosed parts of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisclosedTranscript {
+    /// The length of the transcript, including the redacted parts.
+    pub length: usize,
+    /// The disclosed ranges of the transcript, in order.
+    pub disclosed: Vec<DisclosedRange>,
+}
+
+/// A disclosed range of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisclosedRange {
+    /// The start of the range.
+    pub start: usize,
+    /// The end of the range, exclusive.
+    pub end: usize,
+    /// The data of the range, if it is valid UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The data of the range as unpadded base64url, if it is not valid UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_base64: Option<String>,
+}
+
+/// The proof of a [`VerifiableCredential`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialProof {
+    /// The type of the proof, [`PROOF_TYPE`].
+    #[serde(rename = "type")]
+    pub typ: String,
+    /// The time the session took place, as an RFC 3339 UTC date-time.
+    pub created: String,
+    /// The identifier of the Notary key which signed the session.
+    pub verification_method: String,
+    /// The purpose of the proof.
+    pub proof_purpose: String,
+    /// The canonical JSON encoding of the [`TlsProof`], as unpadded base64url.
+    pub proof_value: String,
+}
+
+/// A Verifiable Presentation of credentials by their holder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiablePresentation {
+    /// The JSON-LD contexts of the presentation.
+    #[serde(rename = "@context")]
+    pub context: Vec<serde_json::Value>,
+    /// The types of the presentation.
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    /// The identifier of the holder, i.e. the Prover.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+    /// The presented credentials.
+    pub verifiable_credential: Vec<VerifiableCredential>,
+}
+
+impl VerifiableCredential {
+    /// Creates a credential from a proof, which is verified first so that the claims of the
+    /// credential are authentic.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof.
+    /// * `issuer` - The identifier of the Notary, e.g. a DID or URL.
+    /// * `notary_public_key` - The public key of the Notary.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    pub fn new(
+        proof: TlsProof,
+        issuer: impl Into<String>,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<Self, CredentialError> {
+        let proof_value = BASE64URL.encode(to_canonical_json(&proof)?);
+        let verified = verify_proof(proof, notary_public_key, cert_verifier)?;
+        let issuer = issuer.into();
+        let issuance_date = format_time(verified.time);
+
+        Ok(Self {
+            context: context(),
+            types: vec![
+                "VerifiableCredential".to_string(),
+                CREDENTIAL_TYPE.to_string(),
+            ],
+            issuer: issuer.clone(),
+            issuance_date: issuance_date.clone(),
+            credential_subject: TlsSessionSubject::new(&verified),
+            proof: CredentialProof {
+                typ: PROOF_TYPE.to_string(),
+                created: issuance_date,
+                verification_method: issuer,
+                proof_purpose: "assertionMethod".to_string(),
+                proof_value,
+            },
+        })
+    }
+
+    /// Returns the proof embedded in the credential.
+    pub fn tls_proof(&self) -> Result<TlsProof, CredentialError> {
+        if self.proof.typ != PROOF_TYPE {
+            return Err(CredentialError::InvalidProof(format!(
+                "unsupported proof type {}",
+                self.proof.typ
+            )));
+        }
+
+        let json = BASE64URL
+            .decode(&self.proof.proof_value)
+            .map_err(|err| CredentialError::InvalidProof(err.to_string()))?;
+        let json = String::from_utf8(json)
+            .map_err(|err| CredentialError::InvalidProof(err.to_string()))?;
+
+        Ok(from_canonical_json(&json)?)
+    }
+
+    /// Verifies the embedded proof, and that the claims of the credential are those proven by it,
+    /// returning the redacted sent and received transcripts.
+    ///
+    /// The issuer is not checked: it is up to the caller to resolve the public key of the Notary
+    /// from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the Notary.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    pub fn verify(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(RedactedTranscript, RedactedTranscript), CredentialError> {
+        let verified = verify_proof(self.tls_proof()?, notary_public_key, cert_verifier)?;
+        let issuance_date = format_time(verified.time);
+
+        if self.issuance_date != issuance_date
+            || self.proof.created != issuance_date
+            || self.credential_subject != TlsSessionSubject::new(&verified)
+        {
+            return Err(CredentialError::ClaimsMismatch);
+        }
+
+        Ok((verified.sent, verified.recv))
+    }
+
+    /// Wraps the credential in a presentation by the provided holder.
+    pub fn into_presentation(self, holder: Option<String>) -> VerifiablePresentation {
+        VerifiablePresentation::new(vec![self], holder)
+    }
+}
+
+impl TlsSessionSubject {
+    fn new(proof: &VerifiedProof) -> Self {
+        Self {
+            typ: "TlsSession".to_string(),
+            server_name: proof.server_name.clone(),
+            sent: DisclosedTranscript::new(&proof.sent),
+            received: DisclosedTranscript::new(&proof.recv),
+        }
+    }
+}
+
+impl DisclosedTranscript {
+    fn new(transcript: &RedactedTranscript) -> Self {
+        let data = transcript.data();
+        let disclosed = transcript
+            .authed()
+            .iter_ranges()
+            .map(|range| {
+                let bytes = &data[range.clone()];
+                let (value, value_base64) = match std::str::from_utf8(bytes) {
+                    Ok(value) => (Some(value.to_string()), None),
+                    Err(_) => (None, Some(BASE64URL.encode(bytes))),
+                };
+
+                DisclosedRange {
+                    start: range.start,
+                    end: range.end,
+                    value,
+                    value_base64,
+                }
+            })
+            .collect();
+
+        Self {
+            length: data.len(),
+            disclosed,
+        }
+    }
+}
+
+impl VerifiablePresentation {
+    /// Creates a presentation of the provided credentials.
+    pub fn new(credentials: Vec<VerifiableCredential>, holder: Option<String>) -> Self {
+        Self {
+            context: vec![serde_json::Value::from(CREDENTIALS_CONTEXT)],
+            types: vec!["VerifiablePresentation".to_string()],
+            holder,
+            verifiable_credential: credentials,
+        }
+    }
+
+    /// Encodes the presentation as a JWT signed by the holder with ES256.
+    ///
+    /// The presentation is held in the `vp` claim, and the holder, if any, in the `iss` claim.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The P-256 signing key of the holder.
+    /// * `issued_at` - The time the JWT is issued, in seconds since the UNIX epoch.
+    pub fn to_jwt(
+        &self,
+        signer: &impl Signer<p256::ecdsa::Signature>,
+        issued_at: u64,
+    ) -> Result<String, CredentialError> {
+        let header = serde_json::json!({ "alg": "ES256", "typ": "JWT" });
+        let mut claims = serde_json::json!({ "iat": issued_at, "vp": self });
+        if let Some(holder) = &self.holder {
+            claims["iss"] = serde_json::Value::from(holder.as_str());
+        }
+
+        let signing_input = format!(
+            "{}.{}",
+            BASE64URL.encode(header.to_string()),
+            BASE64URL.encode(claims.to_string())
+        );
+        let signature = signer.sign(signing_input.as_bytes());
+
+        Ok(format!(
+            "{signing_input}.{}",
+            BASE64URL.encode(signature.to_bytes())
+        ))
+    }
+
+    /// Decodes a presentation from a JWT, verifying its ES256 signature with the public key of
+    /// the holder.
+    ///
+    /// The credentials of the presentation still need to be verified.
+    pub fn from_jwt(
+        jwt: &str,
+        holder_key: &p256::ecdsa::VerifyingKey,
+    ) -> Result<Self, CredentialError> {
+        let invalid = |msg: &str| CredentialError::InvalidJwt(msg.to_string());
+
+        let (signing_input, signature) = jwt
+            .rsplit_once('.')
+            .ok_or_else(|| invalid("missing signature"))?;
+        let (header, claims) = signing_input
+            .split_once('.')
+            .ok_or_else(|| invalid("missing claims"))?;
+
+        let header: serde_json::Value = BASE64URL
+            .decode(header)
+            .ok()
+            .and_then(|header| serde_json::from_slice(&header).ok())
+            .ok_or_else(|| invalid("malformed header"))?;
+        if header["alg"] != "ES256" {
+            return Err(invalid("unsupported algorithm"));
+        }
+
+        let signature = BASE64URL
+            .decode(signature)
+            .ok()
+            .and_then(|signature| p256::ecdsa::Signature::from_slice(&signature).ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+        holder_key
+            .verify(signing_input.as_bytes(), &signature)
+            .map_err(|_| invalid("invalid signature"))?;
+
+        let mut claims: serde_json::Value = BASE64URL
+            .decode(claims)
+            .ok()
+            .and_then(|claims| serde_json::from_slice(&claims).ok())
+            .ok_or_else(|| invalid("malformed claims"))?;
+        let presentation: Self = serde_json::from_value(claims["vp"].take())
+            .map_err(|err| CredentialError::InvalidJwt(err.to_string()))?;
+
+        if claims.get("iss").and_then(|iss| iss.as_str()) != presentation.holder.as_deref() {
+            return Err(invalid("the issuer is not the holder of the presentation"));
+        }
+
+        Ok(presentation)
+    }
+}
+
+/// Verifies a proof, returning its contents.
+fn verify_proof(
+    proof: TlsProof,
+    notary_public_key: impl Into<NotaryPublicKey>,
+    cert_verifier: &impl ServerCertVerifier,
+) -> Result<VerifiedProof, CredentialError> {
+    let TlsProof {
+        session,
+        substrings,
+    } = proof;
+
+    session.verify(notary_public_key, cert_verifier)?;
+    let (sent, recv) = substrings.verify(&session.header)?;
+
+    Ok(VerifiedProof {
+        notary: None,
+        server_name: session.session_info.server_name.as_ref().to_string(),
+        time: session.header.time(),
+        sent,
+        recv,
+    })
+}
+
+fn context() -> Vec<serde_json::Value> {
+    vec![
+        serde_json::Value::from(CREDENTIALS_CONTEXT),
+        serde_json::json!({ "@vocab": TLSN_VOCAB }),
+    ]
+}
+
+/// Formats a time in seconds since the UNIX epoch as an RFC 3339 UTC date-time.
+fn format_time(time: u64) -> String {
+    let days = (time / 86400) as i64;
+    let secs = time % 86400;
+
+    // Converts the number of days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dry_run::{dry_run_notary_public_key, DryRun},
+        fixtures::notary_signing_key,
+        proof::default_cert_verifier,
+    };
+
+    fn credential() -> VerifiableCredential {
+        let mut dry_run = DryRun::new(b"GET / HTTP/1.1".to_vec(), b"HTTP/1.1 200 OK".to_vec());
+        let id = dry_run.commitment_builder().commit_sent(&(0..3)).unwrap();
+        let session = dry_run.finalize().unwrap();
+
+        let mut builder = session.data().build_substrings_proof();
+        builder.reveal_by_id(id).unwrap();
+        let proof = TlsProof {
+            session: session.session_proof(),
+            substrings: builder.build().unwrap(),
+        };
+
+        VerifiableCredential::new(
+            proof,
+            "did:example:notary",
+            dry_run_notary_public_key(),
+            &default_cert_verifier(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_time(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_time(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_credential() {
+        let credential = credential();
+        assert_eq!(credential.credential_subject.server_name, "tlsnotary.org");
+        assert_eq!(
+            credential.credential_subject.sent.disclosed[0]
+                .value
+                .as_deref(),
+            Some("GET")
+        );
+
+        let presentation = credential.into_presentation(None);
+        let json = serde_json::to_string(&presentation).unwrap();
+        let presentation: VerifiablePresentation = serde_json::from_str(&json).unwrap();
+
+        let (sent, _) = presentation.verifiable_credential[0]
+            .verify(dry_run_notary_public_key(), &default_cert_verifier())
+            .unwrap();
+        assert_eq!(&sent.data()[0..3], b"GET");
+    }
+
+    #[test]
+    fn test_credential_claims_mismatch() {
+        let mut credential = credential();
+        credential.credential_subject.sent.disclosed[0].value = Some("PUT".to_string());
+
+        assert!(matches!(
+            credential.verify(dry_run_notary_public_key(), &default_cert_verifier()),
+            Err(CredentialError::ClaimsMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_presentation_jwt() {
+        let signing_key = notary_signing_key();
+        let presentation = VerifiablePresentation::new(vec![], Some("did:example:holder".into()));
+
+        let jwt = presentation.to_jwt(&signing_key, 0).unwrap();
+        let decoded = VerifiablePresentation::from_jwt(&jwt, signing_key.verifying_key()).unwrap();
+        assert_eq!(decoded, presentation);
+
+        // Tampering with the claims invalidates the signature.
+        let (header, rest) = jwt.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let claims = BASE64URL.encode(r#"{"iat":0,"vp":{}}"#);
+        assert!(VerifiablePresentation::from_jwt(
+            &format!("{header}.{claims}.{signature}"),
+            signing_key.verifying_key()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Encoding of notarized data as public inputs of zk circuits.
+//!
+//! Hashes are packed into field elements of [`BYTES_PER_ELEMENT`] bytes each, which are below the
+//! modulus of the scalar fields of both BN254 and BLS12-381, the curves used by circom and most
+//! arkworks circuits. A value is split into elements in order, and the bytes of each element are
+//! read as a little-endian integer, so a circuit can recompose the value from its elements.
+//!
+//! With arkworks, an element is converted with `Fr::from_le_bytes_mod_order(&e.to_le_bytes())`.
+//! With circom, [`PublicInputs::to_circom_input`] returns the JSON input of a circuit whose
+//! signals are named after the public inputs.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{commitment::hash::PlaintextHashCommitment, SessionHeader};
+
+/// An error for [`PublicInputs`].
+#[derive(Debug, thiserror::Error)]
+pub enum PublicInputsError {
+    /// An input with the same name was already added.
+    #[error("duplicate public input name: {0}")]
+    DuplicateName(String),
+}
+
+/// The number of bytes packed into a field element.
+pub const BYTES_PER_ELEMENT: usize = 31;
+
+/// A field element, as a little-endian integer of less than 248 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldElement([u8; 32]);
+
+impl FieldElement {
+    /// Returns the little-endian bytes of the element.
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Returns the element as a decimal string, as circom expects its inputs.
+    pub fn to_decimal_string(&self) -> String {
+        let mut n = self.0;
+        let mut digits = Vec::new();
+        while n.iter().any(|b| *b != 0) {
+            let mut rem = 0u16;
+            for b in n.iter_mut().rev() {
+                let cur = (rem << 8) | *b as u16;
+                *b = (cur / 10) as u8;
+                rem = cur % 10;
+            }
+            digits.push(b'0' + rem as u8);
+        }
+
+        if digits.is_empty() {
+            return "0".to_string();
+        }
+
+        digits.reverse();
+        String::from_utf8(digits).expect("digits are ascii")
+    }
+}
+
+/// Packs bytes into field elements of [`BYTES_PER_ELEMENT`] bytes each.
+///
+/// The last element holds the remaining bytes if the length is not a multiple of
+/// [`BYTES_PER_ELEMENT`].
+pub fn pack_bytes(bytes: &[u8]) -> Vec<FieldElement> {
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| {
+            let mut element = [0u8; 32];
+            element[..chunk.len()].copy_from_slice(chunk);
+            FieldElement(element)
+        })
+        .collect()
+}
+
+/// The position of a named public input within the elements of [`PublicInputs`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputLayout {
+    /// The name of the input.
+    pub name: String,
+    /// The index of the first element of the input.
+    pub offset: usize,
+    /// The number of elements of the input.
+    pub len: usize,
+    /// The number of bytes packed into the elements.
+    pub byte_len: usize,
+}
+
+/// Named public inputs of a zk circuit, built from notarized data.
+///
+/// Names are unique, as they identify the inputs in the layout and in the circom input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublicInputs {
+    inputs: Vec<(String, usize, Vec<FieldElement>)>,
+}
+
+impl PublicInputs {
+    /// Creates empty public inputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an input packed from arbitrary bytes.
+    ///
+    /// Returns an error if an input with the same name was already added.
+    pub fn with_bytes(
+        mut self,
+        name: impl Into<String>,
+        bytes: &[u8],
+    ) -> Result<Self, PublicInputsError> {
+        let name = name.into();
+        if self.inputs.iter().any(|(existing, _, _)| *existing == name) {
+            return Err(PublicInputsError::DuplicateName(name));
+        }
+
+        self.inputs.push((name, bytes.len(), pack_bytes(bytes)));
+
+        Ok(self)
+    }
+
+    /// Adds the hash and the Merkle root of a session header, as `session_header_hash` and
+    /// `merkle_root`.
+    pub fn with_session_header(self, header: &SessionHeader) -> Result<Self, PublicInputsError> {
+        self.with_bytes("session_header_hash", header.hash().as_bytes())?
+            .with_bytes("merkle_root", &header.merkle_root().to_inner())
+    }
+
+    /// Adds the hash of a plaintext hash commitment, whose preimage can be proven in the circuit.
+    pub fn with_plaintext_hash(
+        self,
+        name: impl Into<String>,
+        commitment: &PlaintextHashCommitment,
+    ) -> Result<Self, PublicInputsError> {
+        self.with_bytes(name, commitment.hash().as_bytes())
+    }
+
+    /// Returns the elements of all inputs, in the order the inputs were added.
+    pub fn elements(&self) -> Vec<FieldElement> {
+        self.inputs
+            .iter()
+            .flat_map(|(_, _, elements)| elements.iter().copied())
+            .collect()
+    }
+
+    /// Returns the position of each input within [`elements`](Self::elements).
+    pub fn layout(&self) -> Vec<PublicInputLayout> {
+        let mut offset = 0;
+        self.inputs
+            .iter()
+            .map(|(name, byte_len, elements)| {
+                let layout = PublicInputLayout {
+                    name: name.clone(),
+                    offset,
+                    len: elements.len(),
+                    byte_len: *byte_len,
+                };
+                offset += elements.len();
+                layout
+            })
+            .collect()
+    }
+
+    /// Returns the inputs as the JSON input of a circom circuit, mapping each name to the array of
+    /// its elements as decimal strings.
+    pub fn to_circom_input(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.inputs
+                .iter()
+                .map(|(name, _, elements)| {
+                    (
+                        name.clone(),
+                        elements
+                            .iter()
+                            .map(|e| serde_json::Value::String(e.to_decimal_string()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_bytes() {
+        let elements = pack_bytes(&[1u8; 32]);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].to_le_bytes()[31], 0);
+        assert_eq!(elements[1].to_le_bytes()[..2], [1, 0]);
+
+        assert_eq!(pack_bytes(&[1, 1])[0].to_decimal_string(), "257");
+        assert_eq!(pack_bytes(&[0])[0].to_decimal_string(), "0");
+        assert_eq!(
+            pack_bytes(&[0xff; 8])[0].to_decimal_string(),
+            u64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn test_public_inputs_layout() {
+        let inputs = PublicInputs::new()
+            .with_bytes("hash", &[7u8; 32])
+            .unwrap()
+            .with_bytes("nonce", &[1, 2, 3])
+            .unwrap();
+
+        assert_eq!(inputs.elements().len(), 3);
+        assert_eq!(
+            inputs.layout()[1],
+            PublicInputLayout {
+                name: "nonce".to_string(),
+                offset: 2,
+                len: 1,
+                byte_len: 3,
+            }
+        );
+        assert_eq!(inputs.to_circom_input()["nonce"][0], "197121");
+    }
+
+    #[test]
+    fn test_public_inputs_duplicate_name() {
+        let inputs = PublicInputs::new().with_bytes("hash", &[7u8; 32]).unwrap();
+
+        assert!(matches!(
+            inputs.with_bytes("hash", &[1u8; 32]),
+            Err(PublicInputsError::DuplicateName(name)) if name == "hash"
+        ));
+    }
+}
@@ -17,7 +17,7 @@ use tls_core::{
     msgs::{enums::SignatureScheme, handshake::DigitallySignedStruct},
 };
 
-use mpz_core::{commit::HashCommit, serialize::CanonicalSerialize};
+use mpz_core::commit::HashCommit;
 
 use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
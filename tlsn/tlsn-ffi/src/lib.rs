@@ -0,0 +1,471 @@
+//! C ABI and Kotlin/Swift bindings of the prover, generated with [uniffi](https://mozilla.github.io/uniffi-rs/),
+//! so mobile apps can embed notarization.
+//!
+//! The bindings expose the lifecycle of a prover session as a [`ProverSession`] object:
+//!
+//! 1. [`ProverSession::new`] requests a notarization from the notary, sets up the MPC backend and
+//!    connects to the server.
+//! 2. [`ProverSession::write`] and [`ProverSession::read`] send request bytes to and read response
+//!    bytes from the server over the MPC-TLS connection.
+//! 3. [`ProverSession::close`] closes the connection, after which the transcripts can be inspected.
+//! 4. [`ProverSession::commit`] commits to ranges of the transcripts.
+//! 5. [`ProverSession::finalize`] finalizes the notarization, returning a proof revealing all the
+//!    committed ranges.
+//!
+//! All methods block until the operation is complete, so they should be called from a background
+//! thread. A session can not be used anymore once a method fails after the connection is closed.
+
+#![deny(unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use futures::{AsyncReadExt, AsyncWriteExt};
+use notary_client::{Accepted, NotarizationRequest, NotaryClient};
+use tls_client_async::TlsConnection;
+use tlsn_core::{commitment::CommitmentId, proof::TlsProof};
+use tlsn_prover::tls::{
+    state::{Closed, Notarize},
+    Prover, ProverConfig, ProverError,
+};
+use tokio::{runtime::Runtime, task::JoinHandle};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use utils::range::RangeSet;
+
+uniffi::setup_scaffolding!();
+
+/// An error raised by a [`ProverSession`].
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+#[non_exhaustive]
+pub enum TlsnError {
+    /// The method can not be called in the current state of the session.
+    #[error("invalid state: {0}")]
+    InvalidState(&'static str),
+    /// The configuration of the session is invalid.
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    /// The notary rejected the notarization.
+    #[error("notary error: {0}")]
+    Notary(String),
+    /// The connection to the server failed.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The prover failed.
+    #[error("prover error: {0}")]
+    Prover(#[from] ProverError),
+    /// The prover task panicked.
+    #[error("prover task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    /// The commitment or the proof is invalid.
+    #[error("commitment error: {0}")]
+    Commitment(String),
+    /// A range is out of bounds of the transcript.
+    #[error("invalid range {start}..{end} of a transcript of length {len}")]
+    InvalidRange {
+        /// Start of the range.
+        start: u64,
+        /// End of the range.
+        end: u64,
+        /// Length of the transcript.
+        len: u64,
+    },
+}
+
+/// Configuration of a [`ProverSession`].
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct SessionConfig {
+    /// Host of the notary server.
+    pub notary_host: String,
+    /// Port of the notary server.
+    pub notary_port: u16,
+    /// Whether to connect to the notary server with TLS.
+    pub notary_tls: bool,
+    /// API key of the notary server, if whitelisting is enabled.
+    pub notary_api_key: Option<String>,
+    /// DNS name of the server.
+    pub server_dns: String,
+    /// Port of the server.
+    pub server_port: u16,
+    /// Maximum number of bytes that can be sent, defaults to the notary's default.
+    pub max_sent_data: Option<u64>,
+    /// Maximum number of bytes that can be received, defaults to the notary's default.
+    pub max_recv_data: Option<u64>,
+}
+
+/// A range of a transcript.
+#[derive(Debug, Clone, Copy, uniffi::Record)]
+pub struct ByteRange {
+    /// Start of the range, inclusive.
+    pub start: u64,
+    /// End of the range, exclusive.
+    pub end: u64,
+}
+
+/// Direction of a transcript.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Direction {
+    /// Data sent to the server.
+    Sent,
+    /// Data received from the server.
+    Received,
+}
+
+enum State {
+    Connected {
+        conn: TlsConnection,
+        prover_task: JoinHandle<Result<Prover<Closed>, ProverError>>,
+    },
+    Notarize {
+        prover: Prover<Notarize>,
+        commitments: Vec<CommitmentId>,
+    },
+    Finalized,
+}
+
+/// A prover session, from connecting to the server until the notarization is finalized.
+#[derive(uniffi::Object)]
+pub struct ProverSession {
+    runtime: Runtime,
+    state: Mutex<State>,
+}
+
+impl ProverSession {
+    /// Locks the state of the session.
+    ///
+    /// A panic while the state was locked leaves it unknown, so the session can not be used
+    /// anymore rather than panicking again across the FFI boundary.
+    fn state(&self) -> Result<MutexGuard<'_, State>, TlsnError> {
+        self.state
+            .lock()
+            .map_err(|_| TlsnError::InvalidState("the session failed in a previous call"))
+    }
+}
+
+#[uniffi::export]
+impl ProverSession {
+    /// Requests a notarization from the notary, sets up the MPC backend and connects to the
+    /// server.
+    #[uniffi::constructor]
+    pub fn new(config: SessionConfig) -> Result<Arc<Self>, TlsnError> {
+        let runtime = Runtime::new()?;
+        let state = runtime.block_on(connect(config))?;
+
+        Ok(Arc::new(Self {
+            runtime,
+            state: Mutex::new(state),
+        }))
+    }
+
+    /// Writes request bytes to the server.
+    pub fn write(&self, data: Vec<u8>) -> Result<(), TlsnError> {
+        let mut state = self.state()?;
+        let State::Connected { conn, .. } = &mut *state else {
+            return Err(TlsnError::InvalidState("the connection is closed"));
+        };
+
+        self.runtime.block_on(async {
+            conn.write_all(&data).await?;
+            conn.flush().await
+        })?;
+
+        Ok(())
+    }
+
+    /// Reads at most `max_len` response bytes from the server, returning an empty buffer once the
+    /// server has closed the connection.
+    pub fn read(&self, max_len: u32) -> Result<Vec<u8>, TlsnError> {
+        let mut state = self.state()?;
+        let State::Connected { conn, .. } = &mut *state else {
+            return Err(TlsnError::InvalidState("the connection is closed"));
+        };
+
+        let mut buf = vec![0u8; max_len as usize];
+        let n = self.runtime.block_on(conn.read(&mut buf))?;
+        buf.truncate(n);
+
+        Ok(buf)
+    }
+
+    /// Closes the connection to the server, after which the transcripts can be committed to.
+    pub fn close(&self) -> Result<(), TlsnError> {
+        let mut state = self.state()?;
+        let (mut conn, prover_task) = match std::mem::replace(&mut *state, State::Finalized) {
+            State::Connected { conn, prover_task } => (conn, prover_task),
+            other => {
+                *state = other;
+                return Err(TlsnError::InvalidState("the connection is already closed"));
+            }
+        };
+
+        let prover = self.runtime.block_on(async move {
+            conn.close().await?;
+            Ok::<_, TlsnError>(prover_task.await??)
+        })?;
+
+        *state = State::Notarize {
+            prover: prover.start_notarize(),
+            commitments: Vec::new(),
+        };
+
+        Ok(())
+    }
+
+    /// Returns the data sent to the server.
+    pub fn sent_transcript(&self) -> Result<Vec<u8>, TlsnError> {
+        match &*self.state()? {
+            State::Notarize { prover, .. } => Ok(prover.sent_transcript().data().to_vec()),
+            _ => Err(TlsnError::InvalidState("the connection is not closed")),
+        }
+    }
+
+    /// Returns the data received from the server.
+    pub fn recv_transcript(&self) -> Result<Vec<u8>, TlsnError> {
+        match &*self.state()? {
+            State::Notarize { prover, .. } => Ok(prover.recv_transcript().data().to_vec()),
+            _ => Err(TlsnError::InvalidState("the connection is not closed")),
+        }
+    }
+
+    /// Commits to ranges of a transcript, which are revealed in the proof.
+    ///
+    /// Returns the index of the commitment.
+    pub fn commit(&self, direction: Direction, ranges: Vec<ByteRange>) -> Result<u32, TlsnError> {
+        let mut state = self.state()?;
+        let State::Notarize {
+            prover,
+            commitments,
+        } = &mut *state
+        else {
+            return Err(TlsnError::InvalidState("the connection is not closed"));
+        };
+
+        let len = match direction {
+            Direction::Sent => prover.sent_transcript().data().len(),
+            Direction::Received => prover.recv_transcript().data().len(),
+        };
+        let ranges = to_range_set(&ranges, len)?;
+
+        let builder = prover.commitment_builder();
+        let id = match direction {
+            Direction::Sent => builder.commit_sent(&ranges),
+            Direction::Received => builder.commit_recv(&ranges),
+        }
+        .map_err(|err| TlsnError::Commitment(err.to_string()))?;
+
+        let index = u32::try_from(commitments.len())
+            .map_err(|_| TlsnError::Commitment("too many commitments".to_string()))?;
+        commitments.push(id);
+
+        Ok(index)
+    }
+
+    /// Finalizes the notarization, returning a proof revealing all the committed ranges,
+    /// serialized as JSON.
+    pub fn finalize(&self) -> Result<Vec<u8>, TlsnError> {
+        let mut state = self.state()?;
+        let (prover, commitments) = match std::mem::replace(&mut *state, State::Finalized) {
+            State::Notarize {
+                prover,
+                commitments,
+            } => (prover, commitments),
+            State::Finalized => {
+                return Err(TlsnError::InvalidState("the session is already finalized"))
+            }
+            other => {
+                *state = other;
+                return Err(TlsnError::InvalidState("the connection is not closed"));
+            }
+        };
+
+        let notarized_session = self.runtime.block_on(prover.finalize())?;
+
+        let mut proof_builder = notarized_session.data().build_substrings_proof();
+        for id in commitments {
+            proof_builder
+                .reveal_by_id(id)
+                .map_err(|err| TlsnError::Commitment(err.to_string()))?;
+        }
+        let substrings = proof_builder
+            .build()
+            .map_err(|err| TlsnError::Commitment(err.to_string()))?;
+
+        let proof = TlsProof {
+            session: notarized_session.session_proof(),
+            substrings,
+        };
+
+        serde_json::to_vec(&proof).map_err(|err| TlsnError::Commitment(err.to_string()))
+    }
+}
+
+/// Converts ranges of a transcript of length `len` into a range set, checking that they are not
+/// empty and in bounds.
+fn to_range_set(ranges: &[ByteRange], len: usize) -> Result<RangeSet<usize>, TlsnError> {
+    let ranges = ranges
+        .iter()
+        .map(|&ByteRange { start, end }| {
+            if start < end && end <= len as u64 {
+                Ok(start as usize..end as usize)
+            } else {
+                Err(TlsnError::InvalidRange {
+                    start,
+                    end,
+                    len: len as u64,
+                })
+            }
+        })
+        .collect::<Result<Vec<Range<usize>>, _>>()?;
+
+    Ok(RangeSet::from(ranges))
+}
+
+/// Converts a data limit to `usize`.
+fn data_limit(limit: u64) -> Result<usize, TlsnError> {
+    usize::try_from(limit)
+        .map_err(|_| TlsnError::Config(format!("data limit {limit} is too large")))
+}
+
+/// Requests a notarization, sets up the prover and connects to the server.
+async fn connect(config: SessionConfig) -> Result<State, TlsnError> {
+    let mut notary_client = NotaryClient::builder();
+    notary_client
+        .host(config.notary_host)
+        .port(config.notary_port)
+        .enable_tls(config.notary_tls);
+    if let Some(api_key) = config.notary_api_key {
+        notary_client.api_key(api_key);
+    }
+    let notary_client = notary_client
+        .build()
+        .map_err(|err| TlsnError::Config(err.to_string()))?;
+
+    let mut notarization_request = NotarizationRequest::builder();
+    notarization_request.server_dns(config.server_dns.clone());
+    let mut prover_config = ProverConfig::builder();
+    prover_config.server_dns(config.server_dns.clone());
+    if let Some(max_sent_data) = config.max_sent_data {
+        let max_sent_data = data_limit(max_sent_data)?;
+        notarization_request.max_sent_data(max_sent_data);
+        prover_config.max_sent_data(max_sent_data);
+    }
+    if let Some(max_recv_data) = config.max_recv_data {
+        let max_recv_data = data_limit(max_recv_data)?;
+        notarization_request.max_recv_data(max_recv_data);
+        prover_config.max_recv_data(max_recv_data);
+    }
+    let notarization_request = notarization_request
+        .build()
+        .map_err(|err| TlsnError::Config(err.to_string()))?;
+
+    let Accepted {
+        io: notary_connection,
+        id: session_id,
+        ..
+    } = notary_client
+        .request_notarization(notarization_request)
+        .await
+        .map_err(|err| TlsnError::Notary(err.to_string()))?;
+
+    let prover_config = prover_config
+        .id(session_id)
+        .build()
+        .map_err(|err| TlsnError::Config(err.to_string()))?;
+
+    let prover = Prover::new(prover_config)
+        .setup(notary_connection.compat())
+        .await?;
+
+    let client_socket =
+        tokio::net::TcpStream::connect((config.server_dns.as_str(), config.server_port)).await?;
+
+    let (conn, prover_fut) = prover.connect(client_socket.compat()).await?;
+    let prover_task = tokio::spawn(prover_fut);
+
+    Ok(State::Connected { conn, prover_task })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(state: State) -> ProverSession {
+        ProverSession {
+            runtime: Runtime::new().unwrap(),
+            state: Mutex::new(state),
+        }
+    }
+
+    fn range(start: u64, end: u64) -> ByteRange {
+        ByteRange { start, end }
+    }
+
+    #[test]
+    fn test_to_range_set() {
+        assert_eq!(
+            to_range_set(&[range(0, 2), range(4, 10)], 10).unwrap(),
+            RangeSet::from([0..2, 4..10])
+        );
+
+        assert!(matches!(
+            to_range_set(&[range(0, 11)], 10),
+            Err(TlsnError::InvalidRange {
+                start: 0,
+                end: 11,
+                len: 10
+            })
+        ));
+        assert!(to_range_set(&[range(5, 2)], 10).is_err());
+        assert!(to_range_set(&[range(3, 3)], 10).is_err());
+        assert!(to_range_set(&[range(u64::MAX - 1, u64::MAX)], 10).is_err());
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        let session = session(State::Finalized);
+
+        assert!(matches!(
+            session.write(b"GET /".to_vec()),
+            Err(TlsnError::InvalidState(_))
+        ));
+        assert!(matches!(session.read(16), Err(TlsnError::InvalidState(_))));
+        assert!(matches!(session.close(), Err(TlsnError::InvalidState(_))));
+        assert!(matches!(
+            session.sent_transcript(),
+            Err(TlsnError::InvalidState(_))
+        ));
+        assert!(matches!(
+            session.commit(Direction::Sent, vec![range(0, 1)]),
+            Err(TlsnError::InvalidState(_))
+        ));
+        assert!(matches!(
+            session.finalize(),
+            Err(TlsnError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_poisoned_state() {
+        let session = session(State::Finalized);
+
+        // Poison the lock, as a panic in a previous call would.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _state = session.state.lock().unwrap();
+                    panic!("panic while the state is locked");
+                })
+                .join()
+                .unwrap_err();
+        });
+
+        assert!(matches!(
+            session.recv_transcript(),
+            Err(TlsnError::InvalidState(_))
+        ));
+    }
+}
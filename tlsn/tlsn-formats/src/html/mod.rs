@@ -0,0 +1,5 @@
+//! Tooling for working with HTML data.
+
+mod select;
+
+pub use select::{select, select_body, HtmlElement, HtmlError};
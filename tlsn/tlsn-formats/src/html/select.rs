@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use spansy::Spanned;
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::http::Body;
+
+/// Elements which never have contents or a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// An error for [`select`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HtmlError {
+    /// The document is not valid UTF-8.
+    #[error("html document is not valid utf-8")]
+    Utf8(#[from] std::str::Utf8Error),
+    /// The document could not be parsed.
+    #[error("failed to parse html document: {0}")]
+    Parse(String),
+    /// The selector is invalid.
+    #[error("invalid css selector: {0}")]
+    Selector(String),
+}
+
+/// An HTML element selected from a document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlElement {
+    name: String,
+    outer: Range<usize>,
+    inner: Range<usize>,
+}
+
+impl HtmlElement {
+    /// Returns the lowercase name of the element.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the range of the element, including its start and end tags.
+    pub fn outer(&self) -> Range<usize> {
+        self.outer.clone()
+    }
+
+    /// Returns the range of the contents of the element, excluding its start and end tags.
+    ///
+    /// The range is empty for void elements, such as `<img>`.
+    pub fn inner(&self) -> Range<usize> {
+        self.inner.clone()
+    }
+}
+
+impl ToRangeSet<usize> for HtmlElement {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        RangeSet::from(self.outer.clone())
+    }
+}
+
+/// Returns the elements of an HTML document matching a CSS selector, in document order.
+///
+/// Supported selectors are those of [`tl`](https://docs.rs/tl), which include tag names, `#id`,
+/// `.class`, attribute selectors and the descendant combinator.
+///
+/// # Arguments
+///
+/// * `html` - The HTML document.
+/// * `offset` - The offset of the document, which is added to the ranges of the elements. This is
+///   the position of the document in the transcript.
+/// * `selector` - The CSS selector, e.g. `#account-balance`.
+pub fn select(html: &str, offset: usize, selector: &str) -> Result<Vec<HtmlElement>, HtmlError> {
+    let dom = tl::parse(html, tl::ParserOptions::default())
+        .map_err(|e| HtmlError::Parse(format!("{e:?}")))?;
+    let parser = dom.parser();
+
+    let handles = dom
+        .query_selector(selector)
+        .ok_or_else(|| HtmlError::Selector(selector.to_string()))?;
+
+    let mut elements = handles
+        .filter_map(|handle| handle.get(parser)?.as_tag())
+        .map(|tag| {
+            let raw = tag.raw().as_bytes();
+            // The raw bytes of a tag borrow from the document.
+            let start = raw.as_ptr() as usize - html.as_ptr() as usize;
+            let name = tag.name().as_utf8_str().to_ascii_lowercase();
+            let inner = inner_range(raw, &name);
+
+            HtmlElement {
+                outer: offset + start..offset + start + raw.len(),
+                inner: offset + start + inner.start..offset + start + inner.end,
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    elements.sort_by_key(|element| element.outer.start);
+    elements.dedup();
+
+    Ok(elements)
+}
+
+/// Returns the elements of an HTML body matching a CSS selector, in document order.
+///
+/// The ranges of the elements are ranges of the transcript containing the body, so they can be
+/// committed to and revealed directly.
+///
+/// # Arguments
+///
+/// * `body` - The body of an HTTP message.
+/// * `selector` - The CSS selector, e.g. `#account-balance`.
+pub fn select_body(body: &Body, selector: &str) -> Result<Vec<HtmlElement>, HtmlError> {
+    let offset = body
+        .to_range_set()
+        .iter_ranges()
+        .next()
+        .map(|range| range.start)
+        .unwrap_or_default();
+    let html = std::str::from_utf8(body.span().as_bytes())?;
+
+    select(html, offset, selector)
+}
+
+/// Returns the range of the contents of an element, relative to its raw bytes.
+fn inner_range(raw: &[u8], name: &str) -> Range<usize> {
+    // Find the end of the start tag, skipping quoted attribute values.
+    let mut quote = None;
+    let mut start_tag_end = raw.len();
+    for (i, &b) in raw.iter().enumerate() {
+        match (quote, b) {
+            (Some(q), b) if b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'>') => {
+                start_tag_end = i + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    if VOID_ELEMENTS.contains(&name) || raw[..start_tag_end].ends_with(b"/>") {
+        return start_tag_end..start_tag_end;
+    }
+
+    // The end tag is omitted if the element is not closed.
+    let end_tag_start = raw
+        .iter()
+        .rposition(|&b| b == b'<')
+        .filter(|&i| i >= start_tag_end)
+        .filter(|&i| {
+            let end_tag = raw[i..].strip_prefix(b"</").unwrap_or_default();
+            end_tag.len() > name.len()
+                && end_tag[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+                && end_tag[name.len()..]
+                    .iter()
+                    .all(|b| b.is_ascii_whitespace() || *b == b'>')
+        })
+        .unwrap_or(raw.len());
+
+    start_tag_end..end_tag_start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::parse_response;
+
+    static HTML: &str = r#"<html>
+<body>
+  <div class="row" data-note="a > b"><span id="account-balance">$1,024.00</span></div>
+  <div class="row"><img src="logo.png"><p>Hello <b>world</b></p></div>
+</body>
+</html>"#;
+
+    fn texts(elements: &[HtmlElement], range: fn(&HtmlElement) -> Range<usize>) -> Vec<&str> {
+        elements.iter().map(|e| &HTML[range(e)]).collect()
+    }
+
+    #[test]
+    fn test_select_id() {
+        let elements = select(HTML, 0, "#account-balance").unwrap();
+
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].name(), "span");
+        assert_eq!(texts(&elements, HtmlElement::inner), vec!["$1,024.00"]);
+        assert_eq!(
+            texts(&elements, HtmlElement::outer),
+            vec![r#"<span id="account-balance">$1,024.00</span>"#]
+        );
+    }
+
+    #[test]
+    fn test_select_class() {
+        let elements = select(HTML, 0, ".row").unwrap();
+
+        assert_eq!(
+            texts(&elements, HtmlElement::inner),
+            vec![
+                r#"<span id="account-balance">$1,024.00</span>"#,
+                r#"<img src="logo.png"><p>Hello <b>world</b></p>"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_void_and_nested() {
+        let img = select(HTML, 0, "img").unwrap();
+        assert_eq!(
+            texts(&img, HtmlElement::outer),
+            vec![r#"<img src="logo.png">"#]
+        );
+        assert!(img[0].inner().is_empty());
+
+        let b = select(HTML, 0, "p b").unwrap();
+        assert_eq!(texts(&b, HtmlElement::inner), vec!["world"]);
+    }
+
+    #[test]
+    fn test_select_body() {
+        let rx = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{HTML}",
+            HTML.len()
+        );
+        let response = parse_response(rx.as_bytes()).unwrap();
+
+        let elements = select_body(response.body.as_ref().unwrap(), "#account-balance").unwrap();
+
+        assert_eq!(&rx[elements[0].inner()], "$1,024.00");
+    }
+
+    #[test]
+    fn test_select_no_match() {
+        assert!(select(HTML, 0, "#missing").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,366 @@
+use std::ops::Range;
+
+/// Default value of the `User-Agent` header.
+pub const DEFAULT_USER_AGENT: &str = "tlsn";
+/// Default length of the `User-Agent` slot.
+pub const DEFAULT_USER_AGENT_SLOT_LEN: usize = 64;
+
+/// Headers which are set by the builder and can not be provided by the caller.
+const RESERVED_HEADERS: &[&str] = &["host", "user-agent", "content-length"];
+
+/// An error for [`CanonicalRequestBuilder`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CanonicalRequestError {
+    /// The method is not a valid token.
+    #[error("invalid method: {0:?}")]
+    InvalidMethod(String),
+    /// The request target is empty or contains whitespace or control characters.
+    #[error("invalid request target: {0:?}")]
+    InvalidTarget(String),
+    /// The header name is not a valid token.
+    #[error("invalid header name: {0:?}")]
+    InvalidHeaderName(String),
+    /// The header value contains control characters.
+    #[error("invalid value for header {0:?}")]
+    InvalidHeaderValue(String),
+    /// The header is set by the builder.
+    #[error("header {0:?} is set by the builder and can not be provided")]
+    ReservedHeader(String),
+    /// The user agent does not fit in its slot.
+    #[error("user agent is {len} bytes which exceeds the slot length {slot_len}")]
+    UserAgentTooLong {
+        /// Length of the user agent.
+        len: usize,
+        /// Length of the slot.
+        slot_len: usize,
+    },
+}
+
+/// An HTTP/1.1 request with a canonical byte representation.
+///
+/// The same logical request always serializes to the same bytes, so ranges of the sent transcript
+/// (e.g. for commitment or disclosure templates) can be reused across sessions and users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalRequest {
+    bytes: Vec<u8>,
+    headers: Vec<(String, Range<usize>)>,
+    body: Option<Range<usize>>,
+}
+
+impl CanonicalRequest {
+    /// Creates a new builder for a request with the provided method, host and target.
+    pub fn builder(
+        method: impl Into<String>,
+        host: impl Into<String>,
+        target: impl Into<String>,
+    ) -> CanonicalRequestBuilder {
+        CanonicalRequestBuilder::new(method, host, target)
+    }
+
+    /// Returns the serialized request.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the serialized request.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the range of the value of the first header with the provided name,
+    /// including the padding of the `User-Agent` slot.
+    pub fn header_value_range(&self, name: &str) -> Option<Range<usize>> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, range)| range.clone())
+    }
+
+    /// Returns the range of the value of the `User-Agent` header, including its padding.
+    pub fn user_agent_range(&self) -> Range<usize> {
+        self.header_value_range("user-agent")
+            .expect("user agent is always present")
+    }
+
+    /// Returns the range of the body, if any.
+    pub fn body_range(&self) -> Option<Range<usize>> {
+        self.body.clone()
+    }
+}
+
+/// Builder for [`CanonicalRequest`].
+///
+/// The request is serialized as follows:
+///
+/// - The method is upper-cased.
+/// - Header names are lower-cased, and header values are trimmed with inner whitespace collapsed
+///   to a single space.
+/// - The `host` header comes first, followed by the `user-agent` header, whose value is padded
+///   with trailing spaces to a fixed slot length so the following offsets do not depend on it.
+/// - Other headers are sorted by name, preserving the order of headers with the same name.
+/// - The `content-length` header is set if a body is provided.
+/// - Lines are terminated with CRLF.
+#[derive(Debug, Clone)]
+pub struct CanonicalRequestBuilder {
+    method: String,
+    host: String,
+    target: String,
+    user_agent: String,
+    user_agent_slot_len: usize,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+}
+
+impl CanonicalRequestBuilder {
+    /// Creates a new builder for a request with the provided method, host and target.
+    pub fn new(
+        method: impl Into<String>,
+        host: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            host: host.into(),
+            target: target.into(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            user_agent_slot_len: DEFAULT_USER_AGENT_SLOT_LEN,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Sets the value of the `User-Agent` header.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets the length of the `User-Agent` slot.
+    pub fn user_agent_slot_len(mut self, len: usize) -> Self {
+        self.user_agent_slot_len = len;
+        self
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Builds the request.
+    pub fn build(self) -> Result<CanonicalRequest, CanonicalRequestError> {
+        let Self {
+            method,
+            host,
+            target,
+            user_agent,
+            user_agent_slot_len,
+            headers,
+            body,
+        } = self;
+
+        if method.is_empty() || !method.bytes().all(is_token_char) {
+            return Err(CanonicalRequestError::InvalidMethod(method));
+        }
+
+        if target.is_empty()
+            || target
+                .bytes()
+                .any(|b| b.is_ascii_whitespace() || b.is_ascii_control())
+        {
+            return Err(CanonicalRequestError::InvalidTarget(target));
+        }
+
+        let user_agent = normalize_value("user-agent", &user_agent)?;
+        if user_agent.len() > user_agent_slot_len {
+            return Err(CanonicalRequestError::UserAgentTooLong {
+                len: user_agent.len(),
+                slot_len: user_agent_slot_len,
+            });
+        }
+
+        let mut canonical_headers = vec![
+            ("host".to_string(), normalize_value("host", &host)?),
+            // The slot is padded to its length in bytes, as the width of `format!` counts chars
+            (
+                "user-agent".to_string(),
+                format!(
+                    "{user_agent}{}",
+                    " ".repeat(user_agent_slot_len - user_agent.len())
+                ),
+            ),
+        ];
+
+        let mut other_headers = headers
+            .into_iter()
+            .map(|(name, value)| {
+                if name.is_empty() || !name.bytes().all(is_token_char) {
+                    return Err(CanonicalRequestError::InvalidHeaderName(name));
+                }
+                let name = name.to_ascii_lowercase();
+                if RESERVED_HEADERS.contains(&name.as_str()) {
+                    return Err(CanonicalRequestError::ReservedHeader(name));
+                }
+                let value = normalize_value(&name, &value)?;
+                Ok((name, value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(body) = &body {
+            other_headers.push(("content-length".to_string(), body.len().to_string()));
+        }
+
+        // Stable sort preserves the order of headers with the same name
+        other_headers.sort_by(|(a, _), (b, _)| a.cmp(b));
+        canonical_headers.extend(other_headers);
+
+        let mut bytes =
+            format!("{} {} HTTP/1.1\r\n", method.to_ascii_uppercase(), target).into_bytes();
+
+        let mut header_ranges = Vec::with_capacity(canonical_headers.len());
+        for (name, value) in canonical_headers {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(b": ");
+            let start = bytes.len();
+            bytes.extend_from_slice(value.as_bytes());
+            header_ranges.push((name, start..bytes.len()));
+            bytes.extend_from_slice(b"\r\n");
+        }
+        bytes.extend_from_slice(b"\r\n");
+
+        let body = body.map(|body| {
+            let start = bytes.len();
+            bytes.extend_from_slice(&body);
+            start..bytes.len()
+        });
+
+        Ok(CanonicalRequest {
+            bytes,
+            headers: header_ranges,
+            body,
+        })
+    }
+}
+
+/// Trims the value and collapses inner whitespace.
+fn normalize_value(name: &str, value: &str) -> Result<String, CanonicalRequestError> {
+    if value
+        .bytes()
+        .any(|b| b.is_ascii_control() && b != b'\t' && b != b' ')
+    {
+        return Err(CanonicalRequestError::InvalidHeaderValue(name.to_string()));
+    }
+
+    Ok(value
+        .split(|c: char| c == ' ' || c == '\t')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Returns whether the byte is allowed in a token, as defined in RFC 9110.
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_slot_counts_bytes() {
+        let request = CanonicalRequest::builder("GET", "example.com", "/")
+            .user_agent("agent-é")
+            .user_agent_slot_len(10)
+            .build()
+            .unwrap();
+
+        let user_agent = request.user_agent_range();
+        assert_eq!(user_agent.len(), 10);
+        assert_eq!(&request.as_bytes()[user_agent], "agent-é  ".as_bytes());
+    }
+
+    #[test]
+    fn test_canonical_request() {
+        let request = CanonicalRequest::builder("get", " example.com ", "/api?id=1")
+            .user_agent("test")
+            .user_agent_slot_len(8)
+            .header("X-Custom", "  a   b\tc ")
+            .header("Accept", "*/*")
+            .body("{}")
+            .build()
+            .unwrap();
+
+        let expected: &[u8] = b"GET /api?id=1 HTTP/1.1\r\n\
+            host: example.com\r\n\
+            user-agent: test    \r\n\
+            accept: */*\r\n\
+            content-length: 2\r\n\
+            x-custom: a b c\r\n\
+            \r\n\
+            {}";
+        assert_eq!(request.as_bytes(), expected);
+
+        let user_agent = request.user_agent_range();
+        assert_eq!(&request.as_bytes()[user_agent], b"test    ");
+        let custom = request.header_value_range("X-Custom").unwrap();
+        assert_eq!(&request.as_bytes()[custom], b"a b c");
+        assert_eq!(&request.as_bytes()[request.body_range().unwrap()], b"{}");
+    }
+
+    #[test]
+    fn test_canonical_request_order_independent() {
+        let a = CanonicalRequest::builder("GET", "example.com", "/")
+            .user_agent("agent-a")
+            .header("b", "2")
+            .header("A", "1")
+            .build()
+            .unwrap();
+        let b = CanonicalRequest::builder("GET", "example.com", "/")
+            .user_agent("longer-agent-b")
+            .header("a", "1")
+            .header("B", " 2")
+            .build()
+            .unwrap();
+
+        // Only the user agent slot differs
+        let slot = a.user_agent_range();
+        assert_eq!(slot, b.user_agent_range());
+        assert_eq!(a.as_bytes()[..slot.start], b.as_bytes()[..slot.start]);
+        assert_eq!(a.as_bytes()[slot.end..], b.as_bytes()[slot.end..]);
+    }
+
+    #[test]
+    fn test_canonical_request_errors() {
+        assert!(matches!(
+            CanonicalRequest::builder("GET", "example.com", "/")
+                .header("Content-Length", "1")
+                .build(),
+            Err(CanonicalRequestError::ReservedHeader(_))
+        ));
+        assert!(matches!(
+            CanonicalRequest::builder("GET", "example.com", "/")
+                .header("X-Injected", "a\r\nb: c")
+                .build(),
+            Err(CanonicalRequestError::InvalidHeaderValue(_))
+        ));
+        assert!(matches!(
+            CanonicalRequest::builder("GET", "example.com", "/ HTTP/1.0").build(),
+            Err(CanonicalRequestError::InvalidTarget(_))
+        ));
+        assert!(matches!(
+            CanonicalRequest::builder("GET", "example.com", "/")
+                .user_agent_slot_len(2)
+                .build(),
+            Err(CanonicalRequestError::UserAgentTooLong { .. })
+        ));
+    }
+}
@@ -0,0 +1,386 @@
+use serde::{Deserialize, Serialize};
+use spansy::Spanned;
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder},
+    proof::TlsProof,
+    Direction, NotaryPublicKey,
+};
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::http::{
+    HttpLocation, HttpLocationError, HttpPart, HttpTranscript, MessageKind, RedactedHttpTranscript,
+};
+
+/// An error for [`HttpClaim`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HttpClaimError {
+    /// The claimed part does not exist in the transcript.
+    #[error(transparent)]
+    Location(#[from] HttpLocationError),
+    /// The transcript does not support the claim.
+    #[error("claim is not supported by the transcript: {0:?}")]
+    Mismatch(HttpClaim),
+    /// The data the claim is derived from is not fully revealed.
+    #[error("data of the claim is redacted: {0:?}")]
+    Redacted(HttpClaim),
+    /// The data the claim is derived from could not be committed to.
+    #[error("failed to commit to the data of the claim: {0}")]
+    Commit(String),
+    /// The proof carrying the claims is invalid.
+    #[error("invalid proof: {0}")]
+    Proof(String),
+    /// The revealed transcript could not be parsed.
+    #[error(transparent)]
+    Parse(#[from] spansy::ParseError),
+}
+
+/// A typed claim about an exchange of an HTTP transcript, e.g. that the status of a response is
+/// 200, so relying parties can consume structured data instead of raw bytes.
+///
+/// A claim is derived from committed data of the transcript: the Prover reveals that data in the
+/// proof, and the verifier parses the revealed transcript to check that the claim holds, see
+/// [`RedactedHttpTranscript::verify_claim`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpClaim {
+    /// The status code of the response of an exchange, derived from its status line.
+    Status {
+        /// The index of the exchange.
+        exchange: usize,
+        /// The status code.
+        code: u16,
+    },
+    /// The value of the first header with a name in a message of an exchange, derived from the
+    /// header.
+    Header {
+        /// The index of the exchange.
+        exchange: usize,
+        /// The kind of the message.
+        kind: MessageKind,
+        /// The case-insensitive name of the header.
+        name: String,
+        /// The value of the header.
+        value: String,
+    },
+}
+
+impl HttpClaim {
+    /// Returns a claim of the status code of the response of an exchange.
+    pub fn status(exchange: usize, code: u16) -> Self {
+        Self::Status { exchange, code }
+    }
+
+    /// Returns a claim of the value of the first header with the provided name of a message.
+    pub fn header(
+        exchange: usize,
+        kind: MessageKind,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        Self::Header {
+            exchange,
+            kind,
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Returns the direction of the message the claim is about.
+    pub fn direction(&self) -> Direction {
+        match self {
+            Self::Status { .. } => Direction::Received,
+            Self::Header { kind, .. } => match kind {
+                MessageKind::Request => Direction::Sent,
+                MessageKind::Response => Direction::Received,
+            },
+        }
+    }
+
+    /// Returns the ranges of the transcript the claim is derived from, which the proof must
+    /// reveal, and whether the claim holds for the data in these ranges.
+    ///
+    /// The ranges span the message from its start through the end of the claimed status line or
+    /// header. A claim about the first header with a name is otherwise spoofable: redacting the
+    /// name of an earlier header with that name hides it from the parser, as the filler of the
+    /// redacted bytes is a valid character of a header name.
+    fn locate(
+        &self,
+        transcript: &HttpTranscript,
+    ) -> Result<(RangeSet<usize>, bool), HttpClaimError> {
+        match self {
+            Self::Status { exchange, code } => {
+                let response = transcript
+                    .exchange(*exchange)
+                    .ok_or(HttpLocationError::ExchangeNotFound(*exchange))?
+                    .response()
+                    .ok_or(HttpLocationError::MissingResponse(*exchange))?;
+
+                let line = response.status.span().as_str();
+                let holds =
+                    line.split(' ').nth(1).and_then(|code| code.parse().ok()) == Some(*code);
+
+                Ok((through(response, &response.status), holds))
+            }
+            Self::Header {
+                exchange,
+                kind,
+                name,
+                value,
+            } => {
+                let exchange = transcript
+                    .exchange(*exchange)
+                    .ok_or(HttpLocationError::ExchangeNotFound(*exchange))?;
+                let (location, message, header) = match kind {
+                    MessageKind::Request => (
+                        HttpLocation::request(exchange.index(), HttpPart::Header(name.clone())),
+                        exchange.request().to_range_set(),
+                        exchange.request().headers_with_name(name).next(),
+                    ),
+                    MessageKind::Response => {
+                        let response = exchange
+                            .response()
+                            .ok_or(HttpLocationError::MissingResponse(exchange.index()))?;
+                        (
+                            HttpLocation::response(
+                                exchange.index(),
+                                HttpPart::Header(name.clone()),
+                            ),
+                            response.to_range_set(),
+                            response.headers_with_name(name).next(),
+                        )
+                    }
+                };
+                let header =
+                    header.ok_or_else(|| HttpLocationError::PartNotFound(location.to_string()))?;
+
+                Ok((
+                    through(&message, header),
+                    header.value.as_bytes() == value.as_bytes(),
+                ))
+            }
+        }
+    }
+
+    /// Commits to the data the claim is derived from, after checking that the claim holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `transcript` - The HTTP transcript, parsed from the transcripts of the builder.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        transcript: &HttpTranscript,
+    ) -> Result<CommitmentId, HttpClaimError> {
+        let (ranges, holds) = self.locate(transcript)?;
+        if !holds {
+            return Err(HttpClaimError::Mismatch(self.clone()));
+        }
+
+        builder
+            .commit(&ranges, self.direction())
+            .map_err(|e| HttpClaimError::Commit(e.to_string()))
+    }
+}
+
+/// Returns the range from the start of a message through the end of one of its parts.
+fn through(message: &dyn ToRangeSet<usize>, part: &dyn ToRangeSet<usize>) -> RangeSet<usize> {
+    let start = message
+        .to_range_set()
+        .iter_ranges()
+        .next()
+        .map(|range| range.start);
+    let end = part
+        .to_range_set()
+        .iter_ranges()
+        .last()
+        .map(|range| range.end);
+
+    match (start, end) {
+        (Some(start), Some(end)) => (start..end).into(),
+        _ => RangeSet::default(),
+    }
+}
+
+impl RedactedHttpTranscript {
+    /// Verifies that a claim holds for the revealed data of the transcript.
+    ///
+    /// The data the claim is derived from must be fully revealed, from the start of its message
+    /// through the end of the claimed part, so the claim never relies on redacted data.
+    pub fn verify_claim(&self, claim: &HttpClaim) -> Result<(), HttpClaimError> {
+        let (ranges, holds) = claim.locate(self.transcript())?;
+        if !self.is_revealed(claim.direction(), &ranges) {
+            return Err(HttpClaimError::Redacted(claim.clone()));
+        }
+
+        if !holds {
+            return Err(HttpClaimError::Mismatch(claim.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// A proof disclosing typed claims about the HTTP transcript of a session.
+///
+/// The proof reveals the data the claims are derived from, which the verifier checks the claims
+/// against.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpDisclosure {
+    /// The proof of the session and of the revealed data.
+    pub proof: TlsProof,
+    /// The claims about the HTTP transcript.
+    pub claims: Vec<HttpClaim>,
+}
+
+impl HttpDisclosure {
+    /// Verifies the proof and the claims, using trust anchors from the `webpki-roots` crate.
+    ///
+    /// Returns the server name and the verified claims.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    pub fn verify(
+        self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+    ) -> Result<(String, Vec<HttpClaim>), HttpClaimError> {
+        let Self { proof, claims } = self;
+        let TlsProof {
+            session,
+            substrings,
+        } = proof;
+
+        session
+            .verify_with_default_cert_verifier(notary_public_key)
+            .map_err(|e| HttpClaimError::Proof(e.to_string()))?;
+        let (sent, recv) = substrings
+            .verify(&session.header)
+            .map_err(|e| HttpClaimError::Proof(e.to_string()))?;
+
+        let transcript = RedactedHttpTranscript::parse(&sent, &recv)?;
+        for claim in &claims {
+            transcript.verify_claim(claim)?;
+        }
+
+        Ok((
+            session.session_info.server_name.as_ref().to_string(),
+            claims,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::dry_run::{dry_run_notary_public_key, DryRun};
+
+    static TX: &[u8] = b"GET /user HTTP/1.1\r\nHost: tlsnotary.org\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nX-User-Id: 42\r\nSet-Cookie: secret\r\n\r\n";
+
+    fn disclosure(claims: Vec<HttpClaim>, recv: RangeSet<usize>) -> HttpDisclosure {
+        disclosure_of(RX, claims, recv)
+    }
+
+    fn disclosure_of(rx: &[u8], claims: Vec<HttpClaim>, recv: RangeSet<usize>) -> HttpDisclosure {
+        let mut dry_run = DryRun::new(TX, rx);
+        let sent_id = dry_run
+            .commitment_builder()
+            .commit_sent(&(0..TX.len()))
+            .unwrap();
+        let recv_id = dry_run.commitment_builder().commit_recv(&recv).unwrap();
+
+        let session = dry_run.finalize().unwrap();
+        let mut builder = session.data().build_substrings_proof();
+        builder
+            .reveal_by_id(sent_id)
+            .unwrap()
+            .reveal_by_id(recv_id)
+            .unwrap();
+
+        HttpDisclosure {
+            proof: TlsProof {
+                session: session.session_proof(),
+                substrings: builder.build().unwrap(),
+            },
+            claims,
+        }
+    }
+
+    #[test]
+    fn test_verify_claims() {
+        let claims = vec![
+            HttpClaim::status(0, 200),
+            HttpClaim::header(0, MessageKind::Response, "x-user-id", "42"),
+            HttpClaim::header(0, MessageKind::Request, "Host", "tlsnotary.org"),
+        ];
+
+        let (server_name, verified) = disclosure(claims.clone(), (0..RX.len()).into())
+            .verify(dry_run_notary_public_key())
+            .unwrap();
+
+        assert_eq!(server_name, "tlsnotary.org");
+        assert_eq!(verified, claims);
+    }
+
+    #[test]
+    fn test_claim_mismatch() {
+        let err = disclosure(vec![HttpClaim::status(0, 404)], (0..RX.len()).into())
+            .verify(dry_run_notary_public_key())
+            .unwrap_err();
+        assert!(matches!(err, HttpClaimError::Mismatch(_)));
+    }
+
+    #[test]
+    fn test_claim_redacted() {
+        // The value of the "X-User-Id" header is redacted.
+        let recv = RangeSet::from([0..28, 30..RX.len()]);
+        let claims = vec![HttpClaim::header(
+            0,
+            MessageKind::Response,
+            "x-user-id",
+            "42",
+        )];
+
+        let err = disclosure(claims, recv)
+            .verify(dry_run_notary_public_key())
+            .unwrap_err();
+        assert!(matches!(err, HttpClaimError::Redacted(_)));
+    }
+
+    #[test]
+    fn test_claim_spoofed_by_redacted_header_name() {
+        // The name of the first "X-User-Id" header is redacted, so the filler hides it and the
+        // second header would be parsed as the first one.
+        static RX: &[u8] = b"HTTP/1.1 200 OK\r\nX-User-Id: 1\r\nX-User-Id: 42\r\n\r\n";
+        let recv = RangeSet::from([0..19, 23..RX.len()]);
+        let claims = vec![HttpClaim::header(
+            0,
+            MessageKind::Response,
+            "x-user-id",
+            "42",
+        )];
+
+        let err = disclosure_of(RX, claims, recv)
+            .verify(dry_run_notary_public_key())
+            .unwrap_err();
+        assert!(matches!(err, HttpClaimError::Redacted(_)));
+    }
+
+    #[test]
+    fn test_commit_claim() {
+        let mut dry_run = DryRun::new(TX, RX);
+        let transcript =
+            HttpTranscript::parse(dry_run.sent_transcript(), dry_run.recv_transcript()).unwrap();
+
+        HttpClaim::status(0, 200)
+            .commit(dry_run.commitment_builder(), &transcript)
+            .unwrap();
+
+        let err = HttpClaim::header(0, MessageKind::Response, "x-user-id", "43")
+            .commit(dry_run.commitment_builder(), &transcript)
+            .unwrap_err();
+        assert!(matches!(err, HttpClaimError::Mismatch(_)));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{io::Read, ops::Range};
+
+use bytes::Bytes;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use spansy::Spanned;
+use tlsn_core::RedactedTranscript;
+use utils::range::{RangeDisjoint, RangeSet, ToRangeSet};
+
+use crate::{
+    http::{Body, Header, Request, Response},
+    json::JsonValue,
+};
+
+/// The maximum length of a decoded body, to prevent decompression bombs.
+pub const MAX_DECODED_LEN: usize = 1 << 24;
+
+/// An error for [`DecodedBody`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ContentEncodingError {
+    /// The content coding is not supported.
+    #[error("unsupported content coding: {0}")]
+    Unsupported(String),
+    /// The body could not be decoded.
+    #[error("failed to decode body: {0}")]
+    Decode(#[from] std::io::Error),
+    /// The decoded body is longer than [`MAX_DECODED_LEN`].
+    #[error("decoded body exceeds the maximum length of {MAX_DECODED_LEN} bytes")]
+    TooLarge,
+    /// The encoded body is not entirely revealed.
+    #[error("the encoded body is partially redacted")]
+    Redacted,
+}
+
+/// A content coding of an HTTP message body, see [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-8.4.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+    /// No encoding.
+    Identity,
+    /// The gzip format.
+    Gzip,
+    /// The zlib format, named "deflate" in HTTP.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// Parses a single content coding, case-insensitively.
+    pub fn parse(coding: &str) -> Result<Self, ContentEncodingError> {
+        match coding.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(Self::Identity),
+            "gzip" | "x-gzip" => Ok(Self::Gzip),
+            "deflate" => Ok(Self::Deflate),
+            coding => Err(ContentEncodingError::Unsupported(coding.to_string())),
+        }
+    }
+
+    /// Parses the value of a `Content-Encoding` header, returning the codings in the order they
+    /// were applied.
+    pub fn parse_header(value: &[u8]) -> Result<Vec<Self>, ContentEncodingError> {
+        let value = std::str::from_utf8(value)
+            .map_err(|_| ContentEncodingError::Unsupported(format!("{value:?}")))?;
+
+        value
+            .split(',')
+            .map(Self::parse)
+            .filter(|coding| !matches!(coding, Ok(Self::Identity)))
+            .collect()
+    }
+}
+
+/// Decodes data with the provided content codings, in the order they were applied.
+pub fn decode(data: &[u8], encodings: &[ContentEncoding]) -> Result<Vec<u8>, ContentEncodingError> {
+    let mut data = data.to_vec();
+    for encoding in encodings.iter().rev() {
+        let reader: Box<dyn Read + '_> = match encoding {
+            ContentEncoding::Identity => continue,
+            ContentEncoding::Gzip => Box::new(GzDecoder::new(data.as_slice())),
+            ContentEncoding::Deflate => Box::new(ZlibDecoder::new(data.as_slice())),
+        };
+
+        let mut decoded = Vec::new();
+        reader
+            .take(MAX_DECODED_LEN as u64 + 1)
+            .read_to_end(&mut decoded)?;
+
+        if decoded.len() > MAX_DECODED_LEN {
+            return Err(ContentEncodingError::TooLarge);
+        }
+
+        data = decoded;
+    }
+
+    Ok(data)
+}
+
+/// The body of an HTTP message decoded from its content encoding.
+///
+/// The transcript records the encoded body, so statements about the decoded body are linked to the
+/// transcript by decoding the encoded body again. As a single byte of a compressed body can affect
+/// the entire decoded body, the encoded body must be committed to and revealed as a whole, see
+/// [`DecodedBody::encoded`]. A verifier then recovers the decoded body with
+/// [`DecodedBody::verify`].
+#[derive(Debug, Clone)]
+pub struct DecodedBody {
+    encoded: RangeSet<usize>,
+    encodings: Vec<ContentEncoding>,
+    data: Bytes,
+}
+
+impl DecodedBody {
+    /// Decodes the body of a request, if it has one.
+    pub fn from_request(request: &Request) -> Result<Option<Self>, ContentEncodingError> {
+        request
+            .body
+            .as_ref()
+            .map(|body| Self::new(request.headers_with_name("content-encoding"), body))
+            .transpose()
+    }
+
+    /// Decodes the body of a response, if it has one.
+    pub fn from_response(response: &Response) -> Result<Option<Self>, ContentEncodingError> {
+        response
+            .body
+            .as_ref()
+            .map(|body| Self::new(response.headers_with_name("content-encoding"), body))
+            .transpose()
+    }
+
+    fn new<'a>(
+        content_encodings: impl Iterator<Item = &'a Header>,
+        body: &Body,
+    ) -> Result<Self, ContentEncodingError> {
+        let mut encodings = Vec::new();
+        for header in content_encodings {
+            encodings.extend(ContentEncoding::parse_header(header.value.as_bytes())?);
+        }
+
+        let data = decode(body.span().as_bytes(), &encodings)?;
+
+        Ok(Self {
+            encoded: body.to_range_set(),
+            encodings,
+            data: data.into(),
+        })
+    }
+
+    /// Recovers the decoded body from a verified transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The verified transcript.
+    /// * `encoded` - The range of the encoded body in the transcript.
+    /// * `content_encoding` - The value of the `Content-Encoding` header, which should be read from
+    ///   the verified transcript.
+    pub fn verify(
+        transcript: &RedactedTranscript,
+        encoded: Range<usize>,
+        content_encoding: &[u8],
+    ) -> Result<Self, ContentEncodingError> {
+        let encoded = RangeSet::from(encoded);
+        if encoded.max().unwrap_or_default() > transcript.data().len()
+            || !transcript.redacted().is_disjoint(&encoded)
+        {
+            return Err(ContentEncodingError::Redacted);
+        }
+
+        let encodings = ContentEncoding::parse_header(content_encoding)?;
+        let data = encoded
+            .iter_ranges()
+            .flat_map(|range| transcript.data()[range].iter().copied())
+            .collect::<Vec<_>>();
+        let data = decode(&data, &encodings)?;
+
+        Ok(Self {
+            encoded,
+            encodings,
+            data: data.into(),
+        })
+    }
+
+    /// Returns the ranges of the encoded body in the transcript, which must be revealed for the
+    /// decoded body to be verified.
+    pub fn encoded(&self) -> &RangeSet<usize> {
+        &self.encoded
+    }
+
+    /// Returns the content codings of the body, in the order they were applied.
+    pub fn encodings(&self) -> &[ContentEncoding] {
+        &self.encodings
+    }
+
+    /// Returns the decoded body.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Parses the decoded body as JSON.
+    ///
+    /// The spans of the JSON values refer to the decoded body, not the transcript.
+    pub fn json(&self) -> Result<JsonValue, spansy::ParseError> {
+        spansy::json::parse(self.data.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use tlsn_core::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures,
+        proof::SubstringsProofBuilder,
+        Transcript, TranscriptSlice,
+    };
+
+    use super::*;
+    use crate::http::{parse_response, BodyContent};
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn response(body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            ContentEncoding::parse_header(b"deflate, GZIP").unwrap(),
+            vec![ContentEncoding::Deflate, ContentEncoding::Gzip]
+        );
+        assert_eq!(ContentEncoding::parse_header(b"identity").unwrap(), vec![]);
+        assert!(matches!(
+            ContentEncoding::parse_header(b"br"),
+            Err(ContentEncodingError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_decoded_body() {
+        let rx = response(&gzip(br#"{"foo": "bar"}"#));
+        let response = parse_response(&rx).unwrap();
+
+        let body = DecodedBody::from_response(&response).unwrap().unwrap();
+
+        assert_eq!(body.encodings(), &[ContentEncoding::Gzip]);
+        assert_eq!(body.data().as_ref(), br#"{"foo": "bar"}"#);
+        assert!(matches!(
+            response.body.as_ref().unwrap().content,
+            BodyContent::Unknown(_)
+        ));
+
+        let JsonValue::Object(object) = body.json().unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.get("foo").unwrap().span().as_str(), "bar");
+    }
+
+    #[test]
+    fn test_decoded_body_verify() {
+        let rx = response(&gzip(br#"{"foo": "bar"}"#));
+        let response = parse_response(&rx).unwrap();
+        let body = DecodedBody::from_response(&response).unwrap().unwrap();
+
+        let transcript_tx = Transcript::new(Vec::new());
+        let transcript_rx = Transcript::new(rx.clone());
+
+        let mut builder =
+            TranscriptCommitmentBuilder::new(fixtures::encoding_provider(&[], &rx), 0, rx.len());
+        builder.commit_recv(&response.without_data()).unwrap();
+        builder.commit_recv(body.encoded()).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        builder
+            .reveal_recv(&response.without_data(), CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_recv(body.encoded(), CommitmentKind::Blake3)
+            .unwrap();
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), 0, rx.len());
+        let (_, recv) = proof.verify(&header).unwrap();
+
+        let encoded = body.encoded().iter_ranges().next().unwrap();
+        let verified = DecodedBody::verify(&recv, encoded.clone(), b"gzip").unwrap();
+        assert_eq!(verified.data(), body.data());
+
+        // The decoded body can not be verified if the encoded body is redacted.
+        let mut recv = RedactedTranscript::new(
+            rx.len(),
+            vec![TranscriptSlice::new(
+                0..encoded.end - 1,
+                rx[..encoded.end - 1].to_vec(),
+            )],
+        );
+        recv.set_redacted(0);
+        assert!(matches!(
+            DecodedBody::verify(&recv, encoded, b"gzip"),
+            Err(ContentEncodingError::Redacted)
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder},
+    Direction,
+};
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::http::{HttpCommitError, HttpTranscript, MessageKind, Request, Response};
+
+/// An error for [`HttpLocation`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HttpLocationError {
+    /// The transcript has no such exchange.
+    #[error("no exchange #{0} in the transcript")]
+    ExchangeNotFound(usize),
+    /// The exchange has no response.
+    #[error("exchange #{0} has no response")]
+    MissingResponse(usize),
+    /// The message has no such part.
+    #[error("{0} not found")]
+    PartNotFound(String),
+}
+
+/// A part of an HTTP message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HttpPart {
+    /// The entire message.
+    Message,
+    /// The message excluding the target, headers and body.
+    WithoutData,
+    /// The target of a request.
+    Target,
+    /// The first header with the given name, which is case-insensitive.
+    Header(String),
+    /// The body of the message.
+    Body,
+}
+
+/// A request sent to the server and the response it received.
+///
+/// Requests are paired with responses in order, as HTTP/1.1 requires a server to respond to
+/// requests in the order they were sent, even when they are pipelined.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpExchange<'a> {
+    index: usize,
+    request: &'a Request,
+    response: Option<&'a Response>,
+}
+
+impl<'a> HttpExchange<'a> {
+    /// Returns the index of the exchange in the transcript.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the request.
+    pub fn request(&self) -> &'a Request {
+        self.request
+    }
+
+    /// Returns the response, or `None` if the connection was closed before the server responded.
+    pub fn response(&self) -> Option<&'a Response> {
+        self.response
+    }
+}
+
+impl HttpTranscript {
+    /// Returns the exchanges of the transcript, pairing each request with its response.
+    pub fn exchanges(&self) -> impl Iterator<Item = HttpExchange<'_>> {
+        self.requests
+            .iter()
+            .enumerate()
+            .map(|(index, request)| HttpExchange {
+                index,
+                request,
+                response: self.responses.get(index),
+            })
+    }
+
+    /// Returns the exchange at the given index.
+    pub fn exchange(&self, index: usize) -> Option<HttpExchange<'_>> {
+        self.exchanges().nth(index)
+    }
+}
+
+/// The location of a part of an HTTP message within a transcript, e.g. the body of the response
+/// of the second exchange, so that it can be committed to without computing byte offsets across
+/// the whole session.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpLocation {
+    exchange: usize,
+    kind: MessageKind,
+    part: HttpPart,
+}
+
+impl HttpLocation {
+    /// Returns the location of a part of the request of an exchange.
+    pub fn request(exchange: usize, part: HttpPart) -> Self {
+        Self {
+            exchange,
+            kind: MessageKind::Request,
+            part,
+        }
+    }
+
+    /// Returns the location of a part of the response of an exchange.
+    pub fn response(exchange: usize, part: HttpPart) -> Self {
+        Self {
+            exchange,
+            kind: MessageKind::Response,
+            part,
+        }
+    }
+
+    /// Returns the index of the exchange.
+    pub fn exchange(&self) -> usize {
+        self.exchange
+    }
+
+    /// Returns the kind of the message.
+    pub fn kind(&self) -> MessageKind {
+        self.kind
+    }
+
+    /// Returns the part of the message.
+    pub fn part(&self) -> &HttpPart {
+        &self.part
+    }
+
+    /// Returns the direction of the message in the transcript.
+    pub fn direction(&self) -> Direction {
+        match self.kind {
+            MessageKind::Request => Direction::Sent,
+            MessageKind::Response => Direction::Received,
+        }
+    }
+
+    /// Returns the ranges of this location in the transcript of its [`direction`](Self::direction).
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The HTTP transcript.
+    pub fn ranges(
+        &self,
+        transcript: &HttpTranscript,
+    ) -> Result<RangeSet<usize>, HttpLocationError> {
+        let exchange = transcript
+            .exchange(self.exchange)
+            .ok_or(HttpLocationError::ExchangeNotFound(self.exchange))?;
+        let not_found = || HttpLocationError::PartNotFound(self.to_string());
+
+        let ranges = match self.kind {
+            MessageKind::Request => {
+                let request = exchange.request();
+                match &self.part {
+                    HttpPart::Message => request.to_range_set(),
+                    HttpPart::WithoutData => request.without_data().to_range_set(),
+                    HttpPart::Target => request.request.target.to_range_set(),
+                    HttpPart::Header(name) => request
+                        .headers_with_name(name)
+                        .next()
+                        .ok_or_else(not_found)?
+                        .to_range_set(),
+                    HttpPart::Body => request.body.as_ref().ok_or_else(not_found)?.to_range_set(),
+                }
+            }
+            MessageKind::Response => {
+                let response = exchange
+                    .response()
+                    .ok_or(HttpLocationError::MissingResponse(self.exchange))?;
+                match &self.part {
+                    HttpPart::Message => response.to_range_set(),
+                    HttpPart::WithoutData => response.without_data().to_range_set(),
+                    HttpPart::Target => return Err(not_found()),
+                    HttpPart::Header(name) => response
+                        .headers_with_name(name)
+                        .next()
+                        .ok_or_else(not_found)?
+                        .to_range_set(),
+                    HttpPart::Body => response.body.as_ref().ok_or_else(not_found)?.to_range_set(),
+                }
+            }
+        };
+
+        Ok(ranges)
+    }
+
+    /// Commits to this location.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `transcript` - The HTTP transcript, parsed from the transcripts of the builder.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        transcript: &HttpTranscript,
+    ) -> Result<CommitmentId, HttpCommitError> {
+        let with_index = |mut err: HttpCommitError| {
+            err.set_index(self.exchange);
+            err
+        };
+
+        let ranges = self.ranges(transcript).map_err(|e| {
+            with_index(HttpCommitError::new_with_source(
+                self.kind,
+                "failed to resolve http location",
+                e,
+            ))
+        })?;
+
+        builder.commit(&ranges, self.direction()).map_err(|e| {
+            with_index(HttpCommitError::new_with_source(
+                self.kind,
+                "failed to commit http location",
+                e,
+            ))
+        })
+    }
+}
+
+impl fmt::Display for HttpLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            MessageKind::Request => "request",
+            MessageKind::Response => "response",
+        };
+        write!(f, "{kind} #{}", self.exchange)?;
+        match &self.part {
+            HttpPart::Message => Ok(()),
+            HttpPart::WithoutData => write!(f, ", without data"),
+            HttpPart::Target => write!(f, ", target"),
+            HttpPart::Header(name) => write!(f, ", header {name:?}"),
+            HttpPart::Body => write!(f, ", body"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{commitment::CommitmentKind, fixtures, Transcript};
+
+    static TX: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n\
+    GET /second HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nfirst\
+    HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsecond";
+
+    #[test]
+    fn test_http_location() {
+        let transcript = HttpTranscript::parse(&Transcript::new(TX), &Transcript::new(RX)).unwrap();
+
+        let exchanges = transcript.exchanges().collect::<Vec<_>>();
+        assert_eq!(exchanges.len(), 2);
+        assert!(exchanges[1].response().is_some());
+
+        let body = HttpLocation::response(1, HttpPart::Body);
+        assert_eq!(body.to_string(), "response #1, body");
+        assert_eq!(body.ranges(&transcript).unwrap(), RangeSet::from(81..87));
+
+        let target = HttpLocation::request(1, HttpPart::Target);
+        assert_eq!(target.ranges(&transcript).unwrap(), RangeSet::from(39..46));
+
+        assert!(matches!(
+            HttpLocation::response(2, HttpPart::Body).ranges(&transcript),
+            Err(HttpLocationError::ExchangeNotFound(2))
+        ));
+        assert!(matches!(
+            HttpLocation::request(0, HttpPart::Body).ranges(&transcript),
+            Err(HttpLocationError::PartNotFound(_))
+        ));
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        body.commit(&mut builder, &transcript).unwrap();
+        let commitments = builder.build().unwrap();
+        assert!(commitments
+            .get_id_by_info(
+                CommitmentKind::Blake3,
+                &(81..87).into(),
+                Direction::Received
+            )
+            .is_some());
+    }
+}
@@ -0,0 +1,531 @@
+//! Export of HTTP sessions to the HTTP Archive (HAR) format.
+//!
+//! The exported archive follows [HAR 1.2](http://www.softwareishard.com/blog/har-12-spec/), so it
+//! can be opened by browsers' developer tools and other web tooling. Each entry carries a `_tlsn`
+//! extension field listing the commitments to the transcript which lie within its request and
+//! response, so a reader can tell which parts of the exchange can be revealed in a proof.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use spansy::Spanned;
+use tlsn_core::{
+    commitment::{CommitmentId, CommitmentInfo, TranscriptCommitments},
+    Direction,
+};
+use utils::range::{RangeDifference, RangeSet, ToRangeSet};
+
+use crate::http::{
+    Body, DecodedBody, Header, HttpTranscript, NotarizedHttpSession, Request, Response,
+};
+
+/// The version of the HAR format.
+pub const HAR_VERSION: &str = "1.2";
+
+/// An HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct Har {
+    /// The log of the archive.
+    pub log: HarLog,
+}
+
+impl Har {
+    /// Creates an archive of an HTTP transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `server_name` - The name of the server, used to build the URLs of the requests.
+    /// * `time` - The time of the session, in seconds since the UNIX epoch.
+    /// * `transcript` - The HTTP transcript.
+    /// * `commitments` - The commitments to the transcript.
+    pub fn new(
+        server_name: &str,
+        time: u64,
+        transcript: &HttpTranscript,
+        commitments: &TranscriptCommitments,
+    ) -> Self {
+        let started_date_time = format_time(time);
+
+        let entries = transcript
+            .exchanges()
+            .map(|exchange| {
+                let request = exchange.request();
+                let response = exchange.response();
+
+                HarEntry {
+                    started_date_time: started_date_time.clone(),
+                    time: 0,
+                    request: HarRequest::new(server_name, request),
+                    response: response.map(HarResponse::new).unwrap_or_default(),
+                    cache: HarCache {},
+                    timings: HarTimings::default(),
+                    tlsn: HarCommitments {
+                        exchange: exchange.index(),
+                        request: contained_commitments(
+                            commitments,
+                            Direction::Sent,
+                            &request.to_range_set(),
+                        ),
+                        response: response
+                            .map(|response| {
+                                contained_commitments(
+                                    commitments,
+                                    Direction::Received,
+                                    &response.to_range_set(),
+                                )
+                            })
+                            .unwrap_or_default(),
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            log: HarLog {
+                version: HAR_VERSION,
+                creator: HarCreator {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                entries,
+            },
+        }
+    }
+
+    /// Serializes the archive to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("archive is serializable")
+    }
+}
+
+impl NotarizedHttpSession {
+    /// Exports the session to an HTTP Archive.
+    ///
+    /// The entries are dated at the time of the TLS session, as the times of the individual
+    /// messages are not recorded.
+    pub fn to_har(&self) -> Har {
+        let data = self.session().data();
+
+        Har::new(
+            data.session_info().server_name.as_str(),
+            self.session().header().time(),
+            self.transcript(),
+            data.commitments(),
+        )
+    }
+}
+
+/// The log of an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarLog {
+    /// The version of the format.
+    pub version: &'static str,
+    /// The application which created the archive.
+    pub creator: HarCreator,
+    /// The exchanges of the session, in order.
+    pub entries: Vec<HarEntry>,
+}
+
+/// The application which created an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarCreator {
+    /// The name of the application.
+    pub name: &'static str,
+    /// The version of the application.
+    pub version: &'static str,
+}
+
+/// An exchange of an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    /// The time of the exchange, in RFC 3339 format.
+    pub started_date_time: String,
+    /// The duration of the exchange in milliseconds, which is not recorded.
+    pub time: u64,
+    /// The request.
+    pub request: HarRequest,
+    /// The response, which is empty if the server did not respond.
+    pub response: HarResponse,
+    /// The cache state, which is not recorded.
+    pub cache: HarCache,
+    /// The timings of the exchange, which are not recorded.
+    pub timings: HarTimings,
+    /// The commitments to the exchange.
+    #[serde(rename = "_tlsn")]
+    pub tlsn: HarCommitments,
+}
+
+/// A request of an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    /// The method of the request.
+    pub method: String,
+    /// The absolute URL of the request.
+    pub url: String,
+    /// The HTTP version of the request.
+    pub http_version: String,
+    /// The cookies of the request, which are not parsed.
+    pub cookies: Vec<HarNameValue>,
+    /// The headers of the request.
+    pub headers: Vec<HarNameValue>,
+    /// The parameters of the query string of the request.
+    pub query_string: Vec<HarNameValue>,
+    /// The body of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    /// The size of the request line and the headers, in bytes.
+    pub headers_size: usize,
+    /// The size of the body, in bytes.
+    pub body_size: usize,
+}
+
+impl HarRequest {
+    fn new(server_name: &str, request: &Request) -> Self {
+        let line = request.request.span().as_str();
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default();
+        let http_version = parts.next().unwrap_or_default().to_string();
+
+        let query_string = target
+            .split_once('?')
+            .map(|(_, query)| {
+                query
+                    .split('&')
+                    .filter(|param| !param.is_empty())
+                    .map(|param| {
+                        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                        HarNameValue::new(name, value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let body_size = request.body.as_ref().map_or(0, |body| body.span().len());
+        let post_data = request.body.as_ref().map(|_| {
+            let content = content(DecodedBody::from_request(request), request.body.as_ref());
+            HarPostData {
+                mime_type: mime_type(&request.headers),
+                text: content.0,
+                encoding: content.1,
+            }
+        });
+
+        Self {
+            method,
+            url: format!("https://{server_name}{target}"),
+            http_version,
+            cookies: Vec::new(),
+            headers: headers(&request.headers),
+            query_string,
+            post_data,
+            headers_size: request.span().len() - body_size,
+            body_size,
+        }
+    }
+}
+
+/// A response of an HTTP Archive.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    /// The status code of the response.
+    pub status: u16,
+    /// The reason phrase of the response.
+    pub status_text: String,
+    /// The HTTP version of the response.
+    pub http_version: String,
+    /// The cookies of the response, which are not parsed.
+    pub cookies: Vec<HarNameValue>,
+    /// The headers of the response.
+    pub headers: Vec<HarNameValue>,
+    /// The content of the response.
+    pub content: HarContent,
+    /// The target of a redirection.
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    /// The size of the status line and the headers, in bytes.
+    pub headers_size: usize,
+    /// The size of the body, in bytes.
+    pub body_size: usize,
+}
+
+impl HarResponse {
+    fn new(response: &Response) -> Self {
+        let line = response.status.span().as_str().trim_end();
+        let mut parts = line.splitn(3, ' ');
+        let http_version = parts.next().unwrap_or_default().to_string();
+        let status = parts
+            .next()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or_default();
+        let status_text = parts.next().unwrap_or_default().to_string();
+
+        let body_size = response.body.as_ref().map_or(0, |body| body.span().len());
+        let decoded = DecodedBody::from_response(response);
+        let size = match &decoded {
+            Ok(Some(decoded)) => decoded.data().len(),
+            _ => body_size,
+        };
+        let (text, encoding) = content(decoded, response.body.as_ref());
+
+        let redirect_url = response
+            .headers_with_name("location")
+            .next()
+            .map(|header| String::from_utf8_lossy(header.value.as_bytes()).into_owned())
+            .unwrap_or_default();
+
+        Self {
+            status,
+            status_text,
+            http_version,
+            cookies: Vec::new(),
+            headers: headers(&response.headers),
+            content: HarContent {
+                size,
+                mime_type: mime_type(&response.headers),
+                text,
+                encoding,
+            },
+            redirect_url,
+            headers_size: response.span().len() - body_size,
+            body_size,
+        }
+    }
+}
+
+/// A name and value pair of an HTTP Archive, e.g. a header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HarNameValue {
+    /// The name.
+    pub name: String,
+    /// The value.
+    pub value: String,
+}
+
+impl HarNameValue {
+    fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// The body of a request of an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    /// The media type of the body.
+    pub mime_type: String,
+    /// The decoded body.
+    pub text: String,
+    /// The encoding of `text`, which is `base64` if the body is not valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<&'static str>,
+}
+
+/// The content of a response of an HTTP Archive.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    /// The size of the decoded body, in bytes.
+    pub size: usize,
+    /// The media type of the body.
+    pub mime_type: String,
+    /// The decoded body.
+    pub text: String,
+    /// The encoding of `text`, which is `base64` if the body is not valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<&'static str>,
+}
+
+/// The cache state of an exchange of an HTTP Archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarCache {}
+
+/// The timings of an exchange of an HTTP Archive, in milliseconds.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HarTimings {
+    /// The time to send the request.
+    pub send: u64,
+    /// The time waiting for the response.
+    pub wait: u64,
+    /// The time to receive the response.
+    pub receive: u64,
+}
+
+/// The commitments to an exchange, in the `_tlsn` extension field of an entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarCommitments {
+    /// The index of the exchange in the transcript.
+    pub exchange: usize,
+    /// The commitments within the request, sorted by id.
+    pub request: Vec<HarCommitment>,
+    /// The commitments within the response, sorted by id.
+    pub response: Vec<HarCommitment>,
+}
+
+/// A commitment to a part of an exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HarCommitment {
+    /// The id of the commitment.
+    pub id: u32,
+    /// The kind of the commitment, see [`CommitmentKind::id`](tlsn_core::commitment::CommitmentKind::id).
+    pub kind: &'static str,
+    /// The ranges of the transcript which are committed to.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl HarCommitment {
+    fn new(id: &CommitmentId, info: &CommitmentInfo) -> Self {
+        Self {
+            id: id.to_inner(),
+            kind: info.kind().id(),
+            ranges: info
+                .ranges()
+                .iter_ranges()
+                .map(|range| (range.start, range.end))
+                .collect(),
+        }
+    }
+}
+
+/// Returns the commitments in the given direction which lie within the ranges of a message.
+fn contained_commitments(
+    commitments: &TranscriptCommitments,
+    direction: Direction,
+    message: &RangeSet<usize>,
+) -> Vec<HarCommitment> {
+    let mut contained = commitments
+        .iter_info()
+        .filter(|(_, info)| {
+            *info.direction() == direction && info.ranges().difference(message).is_empty()
+        })
+        .map(|(id, info)| HarCommitment::new(id, info))
+        .collect::<Vec<_>>();
+    contained.sort_by_key(|commitment| commitment.id);
+
+    contained
+}
+
+fn headers(headers: &[Header]) -> Vec<HarNameValue> {
+    headers
+        .iter()
+        .map(|header| HarNameValue {
+            name: header.name.as_str().to_string(),
+            value: String::from_utf8_lossy(header.value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn mime_type(headers: &[Header]) -> String {
+    headers
+        .iter()
+        .find(|header| header.name.as_str().eq_ignore_ascii_case("content-type"))
+        .map(|header| String::from_utf8_lossy(header.value.as_bytes()).into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns the text of a body and its encoding, falling back to the encoded body if it can not be
+/// decoded.
+fn content<E>(
+    decoded: Result<Option<DecodedBody>, E>,
+    body: Option<&Body>,
+) -> (String, Option<&'static str>) {
+    let data = match (&decoded, body) {
+        (Ok(Some(decoded)), _) => decoded.data().as_ref(),
+        (_, Some(body)) => body.span().as_bytes(),
+        (_, None) => return (String::new(), None),
+    };
+
+    match std::str::from_utf8(data) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (STANDARD.encode(data), Some("base64")),
+    }
+}
+
+/// Formats a time in seconds since the UNIX epoch in RFC 3339 format.
+fn format_time(secs: u64) -> String {
+    let days = secs / 86_400;
+    let secs_of_day = secs % 86_400;
+
+    // Converts the days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{commitment::TranscriptCommitmentBuilder, fixtures, Transcript};
+
+    static TX: &[u8] = b"GET /items?page=2&sort HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    static RX: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nitems";
+
+    #[test]
+    fn test_har() {
+        let transcript = HttpTranscript::parse(&Transcript::new(TX), &Transcript::new(RX)).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        let target = builder.commit_sent(&(4..22)).unwrap();
+        let body = builder.commit_recv(&(64..69)).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let har = Har::new("example.com", 1_700_000_000, &transcript, &commitments);
+        let entry = &har.log.entries[0];
+
+        assert_eq!(entry.started_date_time, "2023-11-14T22:13:20Z");
+        assert_eq!(entry.request.method, "GET");
+        assert_eq!(entry.request.url, "https://example.com/items?page=2&sort");
+        assert_eq!(
+            entry.request.query_string,
+            vec![
+                HarNameValue::new("page", "2"),
+                HarNameValue::new("sort", "")
+            ]
+        );
+        assert_eq!(entry.response.status, 200);
+        assert_eq!(entry.response.status_text, "OK");
+        assert_eq!(entry.response.content.text, "items");
+        assert_eq!(entry.response.content.mime_type, "text/plain");
+
+        assert_eq!(entry.tlsn.request.len(), 1);
+        assert_eq!(entry.tlsn.request[0].id, target.to_inner());
+        assert_eq!(entry.tlsn.request[0].ranges, vec![(4, 22)]);
+        assert_eq!(entry.tlsn.response[0].id, body.to_inner());
+
+        let json: serde_json::Value = serde_json::from_str(&har.to_json()).unwrap();
+        assert_eq!(json["log"]["version"], HAR_VERSION);
+        assert_eq!(
+            json["log"]["entries"][0]["_tlsn"]["response"][0]["kind"],
+            "blake3-encodings"
+        );
+        assert_eq!(
+            json["log"]["entries"][0]["response"]["redirectURL"],
+            serde_json::Value::from("")
+        );
+    }
+}
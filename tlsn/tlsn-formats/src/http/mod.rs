@@ -1,9 +1,30 @@
 //! Tooling for working with HTTP data.
 
+mod canonical;
+mod claim;
 mod commit;
+mod encoding;
+mod exchange;
+mod har;
+mod redact;
+mod redacted;
 mod session;
 
+pub use canonical::{
+    CanonicalRequest, CanonicalRequestBuilder, CanonicalRequestError, DEFAULT_USER_AGENT,
+    DEFAULT_USER_AGENT_SLOT_LEN,
+};
+pub use claim::{HttpClaim, HttpClaimError, HttpDisclosure};
 pub use commit::{DefaultHttpCommitter, HttpCommit, HttpCommitError};
+pub use encoding::{decode, ContentEncoding, ContentEncodingError, DecodedBody, MAX_DECODED_LEN};
+pub use exchange::{HttpExchange, HttpLocation, HttpLocationError, HttpPart};
+pub use har::{
+    Har, HarCache, HarCommitment, HarCommitments, HarContent, HarCreator, HarEntry, HarLog,
+    HarNameValue, HarPostData, HarRequest, HarResponse, HarTimings, HAR_VERSION,
+};
+pub use redact::{Redactions, STANDARD_REDACTED_HEADERS, STANDARD_REDACTED_HEADER_PATTERNS};
+pub(crate) use redacted::fill;
+pub use redacted::{RedactedHttpError, RedactedHttpTranscript, REDACTED_FILLER};
 pub use session::NotarizedHttpSession;
 
 #[doc(hidden)]
@@ -16,7 +37,8 @@ pub use http::{
 use tlsn_core::Transcript;
 
 /// The kind of HTTP message.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MessageKind {
     /// An HTTP request.
     Request,
@@ -0,0 +1,295 @@
+use tlsn_core::{commitment::TranscriptCommitmentBuilder, Direction};
+use utils::range::{RangeDifference, RangeSet, RangeUnion, ToRangeSet};
+
+use crate::http::{
+    DefaultHttpCommitter, Header, HttpCommit, HttpCommitError, HttpTranscript, MessageKind,
+    Request, Response,
+};
+
+/// The names of the headers redacted by [`Redactions::standard_http`].
+pub const STANDARD_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Substrings of the names of the headers redacted by [`Redactions::standard_http`], which catch
+/// most headers carrying API keys and tokens, e.g. `X-Api-Key` or `X-Auth-Token`.
+pub const STANDARD_REDACTED_HEADER_PATTERNS: &[&str] = &[
+    "api-key", "apikey", "api_key", "token", "secret", "password", "session",
+];
+
+/// A set of headers whose values are excluded from commitments, so that they can never be
+/// revealed in a proof.
+///
+/// When used as an [`HttpCommit`], a message is committed to with the values of the redacted
+/// headers left out, and these headers are only committed to excluding their values. The rest of
+/// the message is committed to as by the [`DefaultHttpCommitter`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redactions {
+    names: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl Redactions {
+    /// Creates an empty set of redactions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the standard redactions for HTTP, which exclude credentials: the `Authorization`,
+    /// `Proxy-Authorization`, `Cookie` and `Set-Cookie` headers, and the headers whose name
+    /// looks like they carry an API key or a token.
+    pub fn standard_http() -> Self {
+        Self {
+            names: STANDARD_REDACTED_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            patterns: STANDARD_REDACTED_HEADER_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        }
+    }
+
+    /// Redacts the header with the given name, which is case-insensitive.
+    pub fn with_header(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Redacts the headers whose name contains the given substring, which is case-insensitive.
+    pub fn with_header_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Returns whether the value of the header with the given name is redacted.
+    pub fn is_redacted(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.names.iter().any(|redacted| *redacted == name)
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| name.contains(pattern.as_str()))
+    }
+
+    /// Returns the ranges of the values of the redacted headers in the sent and received
+    /// transcripts.
+    pub fn private_ranges(
+        &self,
+        transcript: &HttpTranscript,
+    ) -> (RangeSet<usize>, RangeSet<usize>) {
+        let sent = transcript
+            .requests
+            .iter()
+            .fold(RangeSet::default(), |ranges, request| {
+                ranges.union(&self.private_header_ranges(&request.headers))
+            });
+        let recv = transcript
+            .responses
+            .iter()
+            .fold(RangeSet::default(), |ranges, response| {
+                ranges.union(&self.private_header_ranges(&response.headers))
+            });
+
+        (sent, recv)
+    }
+
+    fn private_header_ranges(&self, headers: &[Header]) -> RangeSet<usize> {
+        headers
+            .iter()
+            .filter(|header| self.is_redacted(header.name.as_str()))
+            .fold(RangeSet::default(), |ranges, header| {
+                ranges.union(&value_ranges(header))
+            })
+    }
+}
+
+/// Returns the ranges of the value of a header.
+fn value_ranges(header: &Header) -> RangeSet<usize> {
+    header
+        .to_range_set()
+        .difference(&header.without_value().to_range_set())
+}
+
+impl HttpCommit for Redactions {
+    fn commit_request(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        request: &Request,
+    ) -> Result<(), HttpCommitError> {
+        let private = self.private_header_ranges(&request.headers);
+        let public = request.to_range_set().difference(&private);
+        builder.commit(&public, direction).map_err(|e| {
+            HttpCommitError::new_with_source(
+                MessageKind::Request,
+                "failed to commit to request excluding redacted headers",
+                e,
+            )
+        })?;
+
+        let without_data = request.without_data().to_range_set();
+        if without_data != public {
+            builder.commit(&without_data, direction).map_err(|e| {
+                HttpCommitError::new_with_source(
+                    MessageKind::Request,
+                    "failed to commit to request with excluded data",
+                    e,
+                )
+            })?;
+        }
+
+        self.commit_target(builder, direction, request, &request.request.target)?;
+
+        for header in &request.headers {
+            self.commit_request_header(builder, direction, request, header)?;
+        }
+
+        if let Some(body) = &request.body {
+            self.commit_request_body(builder, direction, request, body)?;
+        }
+
+        Ok(())
+    }
+
+    fn commit_request_header(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        parent: &Request,
+        header: &Header,
+    ) -> Result<(), HttpCommitError> {
+        if !self.is_redacted(header.name.as_str()) {
+            return DefaultHttpCommitter::default()
+                .commit_request_header(builder, direction, parent, header);
+        }
+
+        commit_redacted_header(builder, direction, MessageKind::Request, header)
+    }
+
+    fn commit_response(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        response: &Response,
+    ) -> Result<(), HttpCommitError> {
+        let private = self.private_header_ranges(&response.headers);
+        let public = response.to_range_set().difference(&private);
+        builder.commit(&public, direction).map_err(|e| {
+            HttpCommitError::new_with_source(
+                MessageKind::Response,
+                "failed to commit to response excluding redacted headers",
+                e,
+            )
+        })?;
+
+        let without_data = response.without_data().to_range_set();
+        if without_data != public {
+            builder.commit(&without_data, direction).map_err(|e| {
+                HttpCommitError::new_with_source(
+                    MessageKind::Response,
+                    "failed to commit to response excluding data",
+                    e,
+                )
+            })?;
+        }
+
+        for header in &response.headers {
+            self.commit_response_header(builder, direction, response, header)?;
+        }
+
+        if let Some(body) = &response.body {
+            self.commit_response_body(builder, direction, response, body)?;
+        }
+
+        Ok(())
+    }
+
+    fn commit_response_header(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        parent: &Response,
+        header: &Header,
+    ) -> Result<(), HttpCommitError> {
+        if !self.is_redacted(header.name.as_str()) {
+            return DefaultHttpCommitter::default()
+                .commit_response_header(builder, direction, parent, header);
+        }
+
+        commit_redacted_header(builder, direction, MessageKind::Response, header)
+    }
+}
+
+/// Commits to a redacted header excluding its value.
+fn commit_redacted_header(
+    builder: &mut TranscriptCommitmentBuilder,
+    direction: Direction,
+    kind: MessageKind,
+    header: &Header,
+) -> Result<(), HttpCommitError> {
+    builder
+        .commit(&header.without_value(), direction)
+        .map_err(|e| {
+            HttpCommitError::new_with_source(
+                kind,
+                format!(
+                    "failed to commit to redacted \"{}\" header excluding value",
+                    header.name.as_str()
+                ),
+                e,
+            )
+        })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{commitment::CommitmentKind, fixtures, Transcript};
+
+    static TX: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc\r\n\
+    X-Api-Key: xyz\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nSet-Cookie: id=1\r\nContent-Length: 2\r\n\r\nok";
+
+    #[test]
+    fn test_standard_http_redactions() {
+        let transcript = HttpTranscript::parse(&Transcript::new(TX), &Transcript::new(RX)).unwrap();
+        let mut redactions = Redactions::standard_http();
+
+        assert!(redactions.is_redacted("X-Auth-Token"));
+        assert!(!redactions.is_redacted("Host"));
+
+        let (sent, recv) = redactions.private_ranges(&transcript);
+        assert_eq!(&TX[sent.iter_ranges().next().unwrap()], b"Bearer abc");
+        assert_eq!(sent.iter_ranges().count(), 2);
+        assert_eq!(&RX[recv.iter_ranges().next().unwrap()], b"id=1");
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        redactions
+            .commit_transcript(&mut builder, &transcript)
+            .unwrap();
+        let commitments = builder.build().unwrap();
+
+        let request = &transcript.requests[0];
+        let authorization = request.headers_with_name("authorization").next().unwrap();
+        let blake3 = |ranges: &RangeSet<usize>| {
+            commitments.get_id_by_info(CommitmentKind::Blake3, ranges, Direction::Sent)
+        };
+
+        assert!(blake3(&request.to_range_set()).is_none());
+        assert!(blake3(&authorization.to_range_set()).is_none());
+        assert!(blake3(&request.to_range_set().difference(&sent)).is_some());
+        assert!(blake3(&authorization.without_value().to_range_set()).is_some());
+    }
+}
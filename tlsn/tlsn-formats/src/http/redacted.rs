@@ -0,0 +1,309 @@
+use bytes::Bytes;
+use tlsn_core::{Direction, RedactedTranscript};
+use utils::range::{RangeDifference, RangeSet, ToRangeSet};
+
+use crate::{
+    http::{
+        BodyContent, Header, HttpLocation, HttpLocationError, HttpPart, HttpTranscript,
+        MessageKind, Requests, Responses,
+    },
+    json::{JsonPath, JsonPathError, JsonValue},
+};
+
+/// The byte which replaces the redacted bytes of a transcript before it is parsed.
+///
+/// It is not a separator of HTTP nor JSON, so the redacted parts of a header value or of a JSON
+/// string do not change the structure of the transcript.
+pub const REDACTED_FILLER: u8 = b'X';
+
+/// An error for [`RedactedHttpTranscript`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum RedactedHttpError {
+    /// The location does not exist in the transcript.
+    #[error(transparent)]
+    Location(#[from] HttpLocationError),
+    /// The location is not fully revealed.
+    #[error("{0} is redacted")]
+    Redacted(HttpLocation),
+    /// The body is not JSON.
+    #[error("{0} is not a JSON body")]
+    NotJson(HttpLocation),
+    /// The JSON path does not exist in the body.
+    #[error(transparent)]
+    JsonPath(#[from] JsonPathError),
+}
+
+/// An HTTP transcript parsed from the transcripts revealed by a proof.
+///
+/// The structure of the transcript is recovered by parsing it with the redacted bytes replaced by
+/// [`REDACTED_FILLER`], at their true offsets. This is exact when the redactions do not remove
+/// separators, e.g. when header values, bodies or the content of JSON strings are redacted, as
+/// done by the committers of this crate.
+///
+/// The data returned by the accessors of this type is always fully revealed: an accessor returns
+/// [`RedactedHttpError::Redacted`] instead of returning data which contains a redacted byte, so a
+/// verifier never mistakes the filler for authenticated data.
+#[derive(Debug)]
+pub struct RedactedHttpTranscript {
+    transcript: HttpTranscript,
+    sent: Bytes,
+    recv: Bytes,
+    sent_authed: RangeSet<usize>,
+    recv_authed: RangeSet<usize>,
+}
+
+impl RedactedHttpTranscript {
+    /// Parses the HTTP transcript from the verified transcripts.
+    ///
+    /// # Arguments
+    ///
+    /// * `sent` - The verified transcript of the sent data.
+    /// * `recv` - The verified transcript of the received data.
+    pub fn parse(
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Result<Self, spansy::ParseError> {
+        let (sent_data, recv_data) = (fill(sent), fill(recv));
+        let requests = Requests::new(sent_data.clone()).collect::<Result<Vec<_>, _>>()?;
+        let responses = Responses::new(recv_data.clone()).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            transcript: HttpTranscript {
+                requests,
+                responses,
+            },
+            sent: sent_data,
+            recv: recv_data,
+            sent_authed: sent.authed().clone(),
+            recv_authed: recv.authed().clone(),
+        })
+    }
+
+    /// Returns the structure of the transcript.
+    ///
+    /// # Warning
+    ///
+    /// The redacted bytes of the messages are replaced by [`REDACTED_FILLER`], so the data of a
+    /// message must be checked to be revealed with [`is_revealed`](Self::is_revealed) before it
+    /// is relied upon.
+    pub fn transcript(&self) -> &HttpTranscript {
+        &self.transcript
+    }
+
+    /// Returns `true` if the provided ranges of the transcript are fully revealed.
+    pub fn is_revealed(&self, direction: Direction, ranges: &dyn ToRangeSet<usize>) -> bool {
+        let authed = match direction {
+            Direction::Sent => &self.sent_authed,
+            Direction::Received => &self.recv_authed,
+        };
+
+        ranges.to_range_set().difference(authed).is_empty()
+    }
+
+    /// Returns the data at the provided location, if it is fully revealed.
+    pub fn get(&self, location: &HttpLocation) -> Result<Vec<u8>, RedactedHttpError> {
+        let ranges = location.ranges(&self.transcript)?;
+        self.check_revealed(location, &ranges)?;
+
+        let data = match location.direction() {
+            Direction::Sent => &self.sent,
+            Direction::Received => &self.recv,
+        };
+
+        Ok(ranges
+            .iter_ranges()
+            .flat_map(|range| data[range].iter().copied())
+            .collect())
+    }
+
+    /// Returns the value of the first header with the provided name of a message, if the header
+    /// is fully revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `exchange` - The index of the exchange.
+    /// * `kind` - The kind of the message.
+    /// * `name` - The case-insensitive name of the header.
+    pub fn header_value(
+        &self,
+        exchange: usize,
+        kind: MessageKind,
+        name: &str,
+    ) -> Result<&[u8], RedactedHttpError> {
+        let location = match kind {
+            MessageKind::Request => HttpLocation::request(exchange, HttpPart::Header(name.into())),
+            MessageKind::Response => {
+                HttpLocation::response(exchange, HttpPart::Header(name.into()))
+            }
+        };
+
+        let header = self.header(&location, name)?;
+        self.check_revealed(&location, &header.to_range_set())?;
+
+        Ok(header.value.as_bytes())
+    }
+
+    /// Returns the JSON value at the provided path of the body of a message, if the value is
+    /// fully revealed.
+    ///
+    /// The body is parsed with its redacted bytes replaced, so a value can be extracted even
+    /// though other values of the body are redacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `exchange` - The index of the exchange.
+    /// * `kind` - The kind of the message.
+    /// * `path` - The path of the value in the body.
+    pub fn json(
+        &self,
+        exchange: usize,
+        kind: MessageKind,
+        path: &JsonPath,
+    ) -> Result<&JsonValue, RedactedHttpError> {
+        let location = match kind {
+            MessageKind::Request => HttpLocation::request(exchange, HttpPart::Body),
+            MessageKind::Response => HttpLocation::response(exchange, HttpPart::Body),
+        };
+
+        let exchange = self
+            .transcript
+            .exchange(exchange)
+            .ok_or(HttpLocationError::ExchangeNotFound(exchange))?;
+        let body = match kind {
+            MessageKind::Request => exchange.request().body.as_ref(),
+            MessageKind::Response => exchange
+                .response()
+                .ok_or(HttpLocationError::MissingResponse(exchange.index()))?
+                .body
+                .as_ref(),
+        }
+        .ok_or_else(|| HttpLocationError::PartNotFound(location.to_string()))?;
+
+        let BodyContent::Json(json) = &body.content else {
+            return Err(RedactedHttpError::NotJson(location));
+        };
+
+        let value = path.resolve(json)?;
+        self.check_revealed(&location, &value.to_range_set())?;
+
+        Ok(value)
+    }
+
+    fn header(&self, location: &HttpLocation, name: &str) -> Result<&Header, RedactedHttpError> {
+        let exchange = self
+            .transcript
+            .exchange(location.exchange())
+            .ok_or(HttpLocationError::ExchangeNotFound(location.exchange()))?;
+        let headers = match location.kind() {
+            MessageKind::Request => &exchange.request().headers,
+            MessageKind::Response => {
+                &exchange
+                    .response()
+                    .ok_or(HttpLocationError::MissingResponse(location.exchange()))?
+                    .headers
+            }
+        };
+
+        // The filler is a valid character of a header name, so a header whose name is partly
+        // redacted can match another name, or hide an earlier header with this name. The name is
+        // checked to be revealed with the header, the earlier headers are not.
+        headers
+            .iter()
+            .find(|header| header.name.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| HttpLocationError::PartNotFound(location.to_string()).into())
+    }
+
+    fn check_revealed(
+        &self,
+        location: &HttpLocation,
+        ranges: &RangeSet<usize>,
+    ) -> Result<(), RedactedHttpError> {
+        if self.is_revealed(location.direction(), ranges) {
+            Ok(())
+        } else {
+            Err(RedactedHttpError::Redacted(location.clone()))
+        }
+    }
+}
+
+/// Returns the data of the transcript with the redacted bytes replaced by [`REDACTED_FILLER`].
+pub(crate) fn fill(transcript: &RedactedTranscript) -> Bytes {
+    let mut data = transcript.data().to_vec();
+    for range in transcript.redacted().iter_ranges() {
+        data[range].fill(REDACTED_FILLER);
+    }
+
+    data.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tlsn_core::TranscriptSlice;
+
+    static TX: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    static RX: &[u8] = b"HTTP/1.1 200 OK\r\nSet-Cookie: secret\r\n\
+    Content-Type: application/json\r\nContent-Length: 30\r\n\r\n\
+    {\"name\": \"alice\", \"id\": \"123\"}";
+
+    fn redacted(data: &[u8], secrets: &[&[u8]]) -> RedactedTranscript {
+        let (public, _) = tlsn_core::range::split(data, secrets);
+        let slices = public
+            .iter_ranges()
+            .map(|range| TranscriptSlice::new(range.clone(), data[range].to_vec()))
+            .collect();
+
+        RedactedTranscript::new(data.len(), slices)
+    }
+
+    #[test]
+    fn test_redacted_http_transcript() {
+        let transcript = RedactedHttpTranscript::parse(
+            &redacted(TX, &[]),
+            &redacted(RX, &[b"secret", b"alice"]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            transcript
+                .header_value(0, MessageKind::Request, "host")
+                .unwrap(),
+            b"localhost"
+        );
+        assert_eq!(
+            transcript
+                .header_value(0, MessageKind::Response, "content-type")
+                .unwrap(),
+            b"application/json"
+        );
+        assert!(matches!(
+            transcript.header_value(0, MessageKind::Response, "set-cookie"),
+            Err(RedactedHttpError::Redacted(_))
+        ));
+        assert!(matches!(
+            transcript.header_value(0, MessageKind::Response, "authorization"),
+            Err(RedactedHttpError::Location(_))
+        ));
+
+        let id = transcript
+            .json(0, MessageKind::Response, &JsonPath::parse("id").unwrap())
+            .unwrap();
+        assert_eq!(id.span().as_str(), "123");
+        assert!(matches!(
+            transcript.json(0, MessageKind::Response, &JsonPath::parse("name").unwrap()),
+            Err(RedactedHttpError::Redacted(_))
+        ));
+
+        assert_eq!(
+            transcript
+                .get(&HttpLocation::request(0, HttpPart::Target))
+                .unwrap(),
+            b"/"
+        );
+        assert!(matches!(
+            transcript.get(&HttpLocation::response(0, HttpPart::Body)),
+            Err(RedactedHttpError::Redacted(_))
+        ));
+    }
+}
@@ -1,6 +1,11 @@
-use tlsn_core::{proof::SessionProof, NotarizedSession};
+use tlsn_core::{
+    commitment::{CommitmentIndexError, CommitmentKind},
+    proof::SessionProof,
+    Direction, NotarizedSession,
+};
+use utils::range::{RangeSet, ToRangeSet};
 
-use crate::http::HttpTranscript;
+use crate::http::{Header, HttpTranscript};
 
 /// A notarized HTTP session.
 #[derive(Debug)]
@@ -29,8 +34,112 @@ impl NotarizedHttpSession {
         &self.transcript
     }
 
+    /// Labels the BLAKE3 commitments to the parts of the HTTP messages, so they can be addressed
+    /// by name once the session is stored, see [`NotarizedSession::label_commitment`].
+    ///
+    /// The labels are `request/<n>` and `response/<n>` for the messages, followed by `/target`,
+    /// `/headers/<name>` or `/body` for their parts, where `<name>` is lowercase. Parts which are
+    /// not committed to, e.g. by a custom [`HttpCommit`](crate::http::HttpCommit), and headers
+    /// repeating the name of a previous header are not labeled.
+    pub fn index_commitments(&mut self) -> Result<(), CommitmentIndexError> {
+        let mut parts = Vec::new();
+        for (n, request) in self.transcript.requests.iter().enumerate() {
+            let prefix = format!("request/{n}");
+            parts.push((prefix.clone(), Direction::Sent, request.to_range_set()));
+            parts.push((
+                format!("{prefix}/target"),
+                Direction::Sent,
+                request.request.target.to_range_set(),
+            ));
+            header_parts(&mut parts, &prefix, Direction::Sent, &request.headers);
+            if let Some(body) = &request.body {
+                parts.push((
+                    format!("{prefix}/body"),
+                    Direction::Sent,
+                    body.to_range_set(),
+                ));
+            }
+        }
+        for (n, response) in self.transcript.responses.iter().enumerate() {
+            let prefix = format!("response/{n}");
+            parts.push((prefix.clone(), Direction::Received, response.to_range_set()));
+            header_parts(&mut parts, &prefix, Direction::Received, &response.headers);
+            if let Some(body) = &response.body {
+                parts.push((
+                    format!("{prefix}/body"),
+                    Direction::Received,
+                    body.to_range_set(),
+                ));
+            }
+        }
+
+        for (label, direction, ranges) in parts {
+            if self.session.index().contains(&label) {
+                continue;
+            }
+
+            if let Some(id) = self.session.data().commitments().get_id_by_info(
+                CommitmentKind::Blake3,
+                &ranges,
+                direction,
+            ) {
+                self.session.label_commitment(label, id)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns a proof for the TLS session.
     pub fn session_proof(&self) -> SessionProof {
         self.session.session_proof()
     }
 }
+
+fn header_parts(
+    parts: &mut Vec<(String, Direction, RangeSet<usize>)>,
+    prefix: &str,
+    direction: Direction,
+    headers: &[Header],
+) {
+    for header in headers {
+        parts.push((
+            format!(
+                "{prefix}/headers/{}",
+                header.name.as_str().to_ascii_lowercase()
+            ),
+            direction,
+            header.to_range_set(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{DefaultHttpCommitter, HttpCommit};
+    use tlsn_core::dry_run::DryRun;
+
+    #[test]
+    fn test_index_commitments() {
+        let sent = b"GET /items HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+        let recv = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}".to_vec();
+        let mut dry_run = DryRun::new(sent, recv);
+
+        let transcript =
+            HttpTranscript::parse(dry_run.sent_transcript(), dry_run.recv_transcript()).unwrap();
+        DefaultHttpCommitter::default()
+            .commit_transcript(dry_run.commitment_builder(), &transcript)
+            .unwrap();
+
+        let mut session = NotarizedHttpSession::new(dry_run.finalize().unwrap(), transcript);
+        session.index_commitments().unwrap();
+
+        let index = session.session().index();
+        assert!(index.contains("request/0"));
+        assert!(index.contains("request/0/target"));
+        assert!(index.contains("request/0/headers/host"));
+        assert!(index.contains("response/0/headers/content-length"));
+        session.session().validate_index().unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use std::ops::Range;
+
+use utils::range::{RangeDifference, RangeSet};
+
+use crate::http2::Http2Error;
+
+/// The length of the header of a frame.
+pub const FRAME_HEADER_LEN: usize = 9;
+
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+/// The type of an HTTP/2 frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameType {
+    /// A DATA frame, carrying the body of a message.
+    Data,
+    /// A HEADERS frame, carrying the start of a header block.
+    Headers,
+    /// A PRIORITY frame.
+    Priority,
+    /// A RST_STREAM frame.
+    RstStream,
+    /// A SETTINGS frame.
+    Settings,
+    /// A PUSH_PROMISE frame.
+    PushPromise,
+    /// A PING frame.
+    Ping,
+    /// A GOAWAY frame.
+    Goaway,
+    /// A WINDOW_UPDATE frame.
+    WindowUpdate,
+    /// A CONTINUATION frame, carrying the rest of a header block.
+    Continuation,
+    /// A frame of an unknown type, which must be ignored.
+    Unknown(u8),
+}
+
+impl FrameType {
+    fn from_u8(kind: u8) -> Self {
+        match kind {
+            0x0 => Self::Data,
+            0x1 => Self::Headers,
+            0x2 => Self::Priority,
+            0x3 => Self::RstStream,
+            0x4 => Self::Settings,
+            0x5 => Self::PushPromise,
+            0x6 => Self::Ping,
+            0x7 => Self::Goaway,
+            0x8 => Self::WindowUpdate,
+            0x9 => Self::Continuation,
+            kind => Self::Unknown(kind),
+        }
+    }
+
+    /// Returns `true` if frames of this type carry a fragment of a header block.
+    pub fn is_header_block(&self) -> bool {
+        matches!(self, Self::Headers | Self::PushPromise | Self::Continuation)
+    }
+}
+
+/// An HTTP/2 frame, located in a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    kind: FrameType,
+    flags: u8,
+    stream_id: u32,
+    range: Range<usize>,
+    data: Range<usize>,
+}
+
+impl Frame {
+    /// Parses the frame starting at the provided offset of the data.
+    pub fn parse(data: &[u8], offset: usize) -> Result<Self, Http2Error> {
+        let header = data
+            .get(offset..offset + FRAME_HEADER_LEN)
+            .ok_or(Http2Error::Incomplete { offset })?;
+
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let kind = FrameType::from_u8(header[3]);
+        let flags = header[4];
+        let stream_id =
+            u32::from_be_bytes(header[5..9].try_into().expect("slice is 4 bytes")) & 0x7fff_ffff;
+
+        let start = offset + FRAME_HEADER_LEN;
+        let end = start + len;
+        if end > data.len() {
+            return Err(Http2Error::Incomplete { offset });
+        }
+
+        // The fields which precede the data of the payload, and the length of the padding which
+        // follows it.
+        let mut prefix = 0;
+        let mut padding = 0;
+        let padded = matches!(
+            kind,
+            FrameType::Data | FrameType::Headers | FrameType::PushPromise
+        ) && flags & FLAG_PADDED != 0;
+        if padded {
+            padding = *data.get(start).ok_or(Http2Error::Malformed { offset })? as usize;
+            prefix += 1;
+        }
+        match kind {
+            FrameType::Headers if flags & FLAG_PRIORITY != 0 => prefix += 5,
+            FrameType::PushPromise => prefix += 4,
+            _ => {}
+        }
+
+        if prefix + padding > len {
+            return Err(Http2Error::Malformed { offset });
+        }
+
+        Ok(Self {
+            kind,
+            flags,
+            stream_id,
+            range: offset..end,
+            data: start + prefix..end - padding,
+        })
+    }
+
+    /// Returns the type of the frame.
+    pub fn kind(&self) -> FrameType {
+        self.kind
+    }
+
+    /// Returns the flags of the frame.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the identifier of the stream of the frame, which is 0 for connection frames.
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// Returns `true` if this is the last frame the sender sends on the stream.
+    pub fn is_end_stream(&self) -> bool {
+        matches!(self.kind, FrameType::Data | FrameType::Headers)
+            && self.flags & FLAG_END_STREAM != 0
+    }
+
+    /// Returns `true` if this frame ends a header block.
+    pub fn is_end_headers(&self) -> bool {
+        self.kind.is_header_block() && self.flags & FLAG_END_HEADERS != 0
+    }
+
+    /// Returns the range of the header of the frame.
+    pub fn header(&self) -> Range<usize> {
+        self.range.start..self.range.start + FRAME_HEADER_LEN
+    }
+
+    /// Returns the range of the whole frame.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the range of the data carried by the frame, i.e. its payload without padding nor
+    /// priority fields.
+    ///
+    /// This is the body data of a DATA frame, or the header block fragment of a HEADERS,
+    /// PUSH_PROMISE or CONTINUATION frame.
+    pub fn data(&self) -> Range<usize> {
+        self.data.clone()
+    }
+
+    /// Returns the ranges of the frame which are not its [data](Self::data), i.e. its header,
+    /// padding and priority fields.
+    pub fn framing(&self) -> RangeSet<usize> {
+        self.range().difference(&self.data())
+    }
+}
+
+/// Parses the frames of the data, from the provided offset to the end of the data.
+///
+/// # Arguments
+///
+/// * `data` - The data of the transcript.
+/// * `start` - The offset of the first frame, i.e. the end of the connection preface.
+/// * `authed` - The ranges of the data which are authenticated, if the data is redacted. The
+///   framing of each frame must be authenticated, as the frames are located from their headers.
+pub fn parse_frames(
+    data: &[u8],
+    start: usize,
+    authed: Option<&RangeSet<usize>>,
+) -> Result<Vec<Frame>, Http2Error> {
+    let mut frames = Vec::new();
+    let mut offset = start;
+    while offset < data.len() {
+        if let Some(authed) = authed {
+            // The header is checked before it is parsed, as the length of the padding is only
+            // known afterwards.
+            let header = offset..(offset + FRAME_HEADER_LEN).min(data.len());
+            if !header.difference(authed).is_empty() {
+                return Err(Http2Error::RedactedFraming { offset });
+            }
+        }
+
+        let frame = Frame::parse(data, offset)?;
+
+        if let Some(authed) = authed {
+            if !frame.framing().difference(authed).is_empty() {
+                return Err(Http2Error::RedactedFraming { offset });
+            }
+        }
+
+        offset = frame.range.end;
+        frames.push(frame);
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame() {
+        // A padded DATA frame on stream 3 ending the stream.
+        let mut data = vec![0, 0, 7, 0x0, FLAG_PADDED | FLAG_END_STREAM, 0, 0, 0, 3, 2];
+        data.extend_from_slice(b"abcd");
+        data.extend_from_slice(&[0, 0]);
+
+        let frame = Frame::parse(&data, 0).unwrap();
+        assert_eq!(frame.kind(), FrameType::Data);
+        assert_eq!(frame.stream_id(), 3);
+        assert!(frame.is_end_stream());
+        assert_eq!(frame.header(), 0..9);
+        assert_eq!(frame.data(), 10..14);
+        assert_eq!(&data[frame.data()], b"abcd");
+        assert_eq!(frame.framing(), RangeSet::from([0..10, 14..16]));
+
+        assert!(matches!(
+            Frame::parse(&data[..12], 0),
+            Err(Http2Error::Incomplete { offset: 0 })
+        ));
+
+        // The padding is longer than the payload.
+        data[9] = 9;
+        assert!(matches!(
+            Frame::parse(&data, 0),
+            Err(Http2Error::Malformed { offset: 0 })
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, RedactedTranscript,
+};
+use utils::range::{RangeDifference, RangeSet, RangeUnion, ToRangeSet};
+
+use crate::http2::{Http2Error, Http2Stream};
+
+/// The length of the prefix of a gRPC message.
+pub const GRPC_PREFIX_LEN: usize = 5;
+
+/// A gRPC message, located in a transcript.
+///
+/// The body of a gRPC stream is a sequence of length-prefixed messages, which are split across
+/// the DATA frames of the stream without regard to their boundaries, so a message can span
+/// several frames and its ranges are not contiguous.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcMessage {
+    compressed: bool,
+    prefix: RangeSet<usize>,
+    message: RangeSet<usize>,
+}
+
+impl GrpcMessage {
+    /// Returns `true` if the message is compressed with the encoding of the stream.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Returns the ranges of the prefix of the message, which holds its compression flag and its
+    /// length.
+    pub fn prefix(&self) -> &RangeSet<usize> {
+        &self.prefix
+    }
+
+    /// Returns the ranges of the message, e.g. an encoded protobuf message.
+    pub fn message(&self) -> &RangeSet<usize> {
+        &self.message
+    }
+
+    /// Returns the data of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the transcript the message was parsed from.
+    pub fn data(&self, data: &[u8]) -> Vec<u8> {
+        self.message
+            .iter_ranges()
+            .flat_map(|range| data[range].iter().copied())
+            .collect()
+    }
+
+    /// Returns the data of the message, if the message is fully revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The verified transcript the message was parsed from.
+    pub fn verify(&self, transcript: &RedactedTranscript) -> Result<Vec<u8>, Http2Error> {
+        if !self
+            .to_range_set()
+            .difference(transcript.authed())
+            .is_empty()
+        {
+            return Err(Http2Error::Redacted);
+        }
+
+        Ok(self.data(transcript.data()))
+    }
+
+    /// Commits to the message, returning the commitments to its prefix and to its data.
+    ///
+    /// Revealing the commitment to the prefix only discloses the length of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `direction` - The direction of the message.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<GrpcCommitments, TranscriptCommitmentBuilderError> {
+        let prefix = builder.commit(&self.prefix, direction)?;
+        let message = if self.message.is_empty() {
+            None
+        } else {
+            Some(builder.commit(&self.message, direction)?)
+        };
+
+        Ok(GrpcCommitments { prefix, message })
+    }
+}
+
+impl ToRangeSet<usize> for GrpcMessage {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.prefix.union(&self.message)
+    }
+}
+
+/// The commitments to a [`GrpcMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrpcCommitments {
+    /// The commitment to the prefix of the message.
+    pub prefix: CommitmentId,
+    /// The commitment to the data of the message, unless the message is empty.
+    pub message: Option<CommitmentId>,
+}
+
+/// Parses the gRPC messages carried by the DATA frames of a stream.
+///
+/// # Arguments
+///
+/// * `stream` - The stream.
+/// * `data` - The data of the transcript the stream was parsed from.
+/// * `authed` - The ranges of the data which are authenticated, if the data is redacted. The
+///   prefix of each message must be authenticated, as the messages are located from their
+///   prefixes.
+pub fn parse_messages(
+    stream: &Http2Stream,
+    data: &[u8],
+    authed: Option<&RangeSet<usize>>,
+) -> Result<Vec<GrpcMessage>, Http2Error> {
+    let segments = stream
+        .data_frames()
+        .iter()
+        .map(|frame| frame.data())
+        .filter(|range| !range.is_empty())
+        .collect::<Vec<_>>();
+    let len = segments.iter().map(|range| range.len()).sum::<usize>();
+
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while offset < len {
+        let incomplete = Http2Error::IncompleteMessage {
+            stream_id: stream.id(),
+        };
+
+        if offset + GRPC_PREFIX_LEN > len {
+            return Err(incomplete);
+        }
+        let prefix = locate(&segments, offset..offset + GRPC_PREFIX_LEN);
+
+        if let Some(authed) = authed {
+            if !prefix.difference(authed).is_empty() {
+                return Err(Http2Error::RedactedFraming {
+                    offset: prefix
+                        .iter_ranges()
+                        .next()
+                        .map(|range| range.start)
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        let bytes = prefix
+            .iter_ranges()
+            .flat_map(|range| data[range].iter().copied())
+            .collect::<Vec<_>>();
+        let compressed = bytes[0] != 0;
+        let message_len =
+            u32::from_be_bytes(bytes[1..5].try_into().expect("slice is 4 bytes")) as usize;
+
+        offset += GRPC_PREFIX_LEN;
+        if offset + message_len > len {
+            return Err(incomplete);
+        }
+
+        messages.push(GrpcMessage {
+            compressed,
+            prefix,
+            message: locate(&segments, offset..offset + message_len),
+        });
+        offset += message_len;
+    }
+
+    Ok(messages)
+}
+
+/// Returns the ranges of the transcript holding the provided range of the concatenated segments.
+fn locate(segments: &[Range<usize>], range: Range<usize>) -> RangeSet<usize> {
+    let mut ranges = RangeSet::default();
+    let mut start = 0;
+    for segment in segments {
+        let end = start + segment.len();
+        let (lo, hi) = (range.start.max(start), range.end.min(end));
+        if lo < hi {
+            ranges = ranges.union(&(segment.start + lo - start..segment.start + hi - start));
+        }
+        start = end;
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let segments = [10..14, 20..22, 30..40];
+        assert_eq!(locate(&segments, 0..4), RangeSet::from(10..14));
+        assert_eq!(
+            locate(&segments, 2..8),
+            RangeSet::from([12..14, 20..22, 30..32])
+        );
+        assert_eq!(locate(&segments, 6..16), RangeSet::from(30..40));
+    }
+}
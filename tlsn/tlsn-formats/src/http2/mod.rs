@@ -0,0 +1,420 @@
+//! Tooling for working with HTTP/2 and gRPC data.
+//!
+//! An HTTP/2 connection multiplexes streams over a sequence of frames, so the messages of a
+//! stream are interleaved with those of other streams in the transcripts of a session.
+//! [`Http2Transcript`] locates the frames and groups them by stream, and the gRPC messages carried
+//! by a stream can be located with [`Http2Stream::grpc_messages`], so that each message can be
+//! committed to and disclosed individually.
+//!
+//! Header blocks are compressed with HPACK, whose state spans the whole connection, so they are
+//! located but not decoded: a header block can only be disclosed as a whole.
+
+mod frame;
+mod grpc;
+
+pub use frame::{parse_frames, Frame, FrameType, FRAME_HEADER_LEN};
+pub use grpc::{parse_messages, GrpcCommitments, GrpcMessage, GRPC_PREFIX_LEN};
+
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, RedactedTranscript, Transcript,
+};
+use utils::range::{RangeSet, RangeUnion};
+
+use crate::http::fill;
+
+/// The preface which starts the data sent by an HTTP/2 client.
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// An error for HTTP/2 data.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Http2Error {
+    /// The sent data does not start with the connection preface.
+    #[error("the sent data does not start with the http/2 connection preface")]
+    MissingPreface,
+    /// The frame at the offset is truncated, e.g. because the connection was closed.
+    #[error("incomplete frame at offset {offset}")]
+    Incomplete {
+        /// The offset of the frame.
+        offset: usize,
+    },
+    /// The frame at the offset is malformed.
+    #[error("malformed frame at offset {offset}")]
+    Malformed {
+        /// The offset of the frame.
+        offset: usize,
+    },
+    /// The framing at the offset is redacted, so the data can not be located.
+    #[error("the framing at offset {offset} is redacted")]
+    RedactedFraming {
+        /// The offset of the framing.
+        offset: usize,
+    },
+    /// The last gRPC message of the stream is truncated.
+    #[error("incomplete grpc message on stream {stream_id}")]
+    IncompleteMessage {
+        /// The identifier of the stream.
+        stream_id: u32,
+    },
+    /// The data is not fully revealed.
+    #[error("the data is redacted")]
+    Redacted,
+}
+
+/// The frames of an HTTP/2 stream sent in one direction, i.e. a request or a response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Http2Stream {
+    id: u32,
+    frames: Vec<Frame>,
+}
+
+impl Http2Stream {
+    /// Returns the identifier of the stream.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns all the frames of the stream.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns the frames of the stream carrying header blocks, including trailers.
+    pub fn header_frames(&self) -> Vec<&Frame> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.kind().is_header_block())
+            .collect()
+    }
+
+    /// Returns the DATA frames of the stream.
+    pub fn data_frames(&self) -> Vec<&Frame> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.kind() == FrameType::Data)
+            .collect()
+    }
+
+    /// Returns `false` if the connection was closed before the end of the stream.
+    pub fn is_complete(&self) -> bool {
+        self.frames.iter().any(Frame::is_end_stream)
+    }
+
+    /// Returns the ranges of the framing of the frames, see [`Frame::framing`].
+    pub fn framing(&self) -> RangeSet<usize> {
+        self.frames
+            .iter()
+            .fold(RangeSet::default(), |ranges, frame| {
+                ranges.union(&frame.framing())
+            })
+    }
+
+    /// Returns the ranges of the compressed header blocks of the stream.
+    pub fn header_blocks(&self) -> RangeSet<usize> {
+        union_data(self.header_frames())
+    }
+
+    /// Returns the ranges of the body of the stream.
+    pub fn body(&self) -> RangeSet<usize> {
+        union_data(self.data_frames())
+    }
+
+    /// Parses the gRPC messages of the body of the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The transcript the stream was parsed from.
+    pub fn grpc_messages(&self, transcript: &Transcript) -> Result<Vec<GrpcMessage>, Http2Error> {
+        parse_messages(self, transcript.data(), None)
+    }
+
+    /// Parses the gRPC messages of the body of the stream, from a verified transcript.
+    ///
+    /// The prefixes of all the messages must be revealed. The data of a message must be read with
+    /// [`GrpcMessage::verify`], which checks that it is revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The verified transcript the stream was parsed from.
+    pub fn grpc_messages_redacted(
+        &self,
+        transcript: &RedactedTranscript,
+    ) -> Result<Vec<GrpcMessage>, Http2Error> {
+        parse_messages(self, transcript.data(), Some(transcript.authed()))
+    }
+
+    /// Commits to the stream, returning the commitments to its framing, header blocks and body.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `direction` - The direction of the stream.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<Http2StreamCommitments, TranscriptCommitmentBuilderError> {
+        let framing = builder.commit(&self.framing(), direction)?;
+        let header_blocks = self.header_blocks();
+        let headers = if header_blocks.is_empty() {
+            None
+        } else {
+            Some(builder.commit(&header_blocks, direction)?)
+        };
+        let body = self.body();
+        let body = if body.is_empty() {
+            None
+        } else {
+            Some(builder.commit(&body, direction)?)
+        };
+
+        Ok(Http2StreamCommitments {
+            framing,
+            headers,
+            body,
+        })
+    }
+}
+
+/// The commitments to an [`Http2Stream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Http2StreamCommitments {
+    /// The commitment to the framing of the stream.
+    pub framing: CommitmentId,
+    /// The commitment to the header blocks of the stream, unless it has none.
+    pub headers: Option<CommitmentId>,
+    /// The commitment to the body of the stream, unless it is empty.
+    pub body: Option<CommitmentId>,
+}
+
+/// An HTTP/2 connection.
+#[derive(Debug)]
+pub struct Http2Transcript {
+    /// The frames sent to the server, after the connection preface.
+    pub sent_frames: Vec<Frame>,
+    /// The frames received from the server.
+    pub received_frames: Vec<Frame>,
+    /// The streams sent to the server, in the order they were opened.
+    pub sent: Vec<Http2Stream>,
+    /// The streams received from the server, in the order they were opened.
+    pub received: Vec<Http2Stream>,
+}
+
+impl Http2Transcript {
+    /// Parses the HTTP/2 connection from the provided transcripts.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, Http2Error> {
+        Self::parse_inner(tx.data(), rx.data(), None)
+    }
+
+    /// Parses the HTTP/2 connection from the verified transcripts.
+    ///
+    /// The connection preface and the framing of all the frames must be revealed.
+    pub fn parse_redacted(
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Result<Self, Http2Error> {
+        Self::parse_inner(
+            &fill(sent),
+            &fill(recv),
+            Some((sent.authed(), recv.authed())),
+        )
+    }
+
+    fn parse_inner(
+        tx: &[u8],
+        rx: &[u8],
+        authed: Option<(&RangeSet<usize>, &RangeSet<usize>)>,
+    ) -> Result<Self, Http2Error> {
+        // The redacted bytes are filled, so a redacted preface does not match.
+        if !tx.starts_with(PREFACE) {
+            return Err(Http2Error::MissingPreface);
+        }
+
+        let sent_frames = parse_frames(tx, PREFACE.len(), authed.map(|(sent, _)| sent))?;
+        let received_frames = parse_frames(rx, 0, authed.map(|(_, recv)| recv))?;
+
+        Ok(Self {
+            sent: streams(&sent_frames),
+            received: streams(&received_frames),
+            sent_frames,
+            received_frames,
+        })
+    }
+
+    /// Returns the stream with the provided identifier, sent in the provided direction.
+    pub fn stream(&self, direction: Direction, id: u32) -> Option<&Http2Stream> {
+        match direction {
+            Direction::Sent => &self.sent,
+            Direction::Received => &self.received,
+        }
+        .iter()
+        .find(|stream| stream.id == id)
+    }
+}
+
+/// Groups the frames by stream, ignoring the frames of the connection.
+fn streams(frames: &[Frame]) -> Vec<Http2Stream> {
+    let mut streams: Vec<Http2Stream> = Vec::new();
+    for frame in frames.iter().filter(|frame| frame.stream_id() != 0) {
+        match streams
+            .iter_mut()
+            .find(|stream| stream.id == frame.stream_id())
+        {
+            Some(stream) => stream.frames.push(frame.clone()),
+            None => streams.push(Http2Stream {
+                id: frame.stream_id(),
+                frames: vec![frame.clone()],
+            }),
+        }
+    }
+
+    streams
+}
+
+fn union_data(frames: Vec<&Frame>) -> RangeSet<usize> {
+    frames
+        .into_iter()
+        .filter(|frame| !frame.data().is_empty())
+        .fold(RangeSet::default(), |ranges, frame| {
+            ranges.union(&frame.data())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{commitment::CommitmentKind, fixtures, proof::SubstringsProofBuilder};
+
+    fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+        frame.extend([kind, flags]);
+        frame.extend(stream_id.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    fn grpc(message: &[u8]) -> Vec<u8> {
+        let mut data = vec![0];
+        data.extend((message.len() as u32).to_be_bytes());
+        data.extend_from_slice(message);
+        data
+    }
+
+    fn transcripts() -> (Vec<u8>, Vec<u8>) {
+        let request = grpc(b"get-balance");
+
+        let mut tx = PREFACE.to_vec();
+        tx.extend(frame(0x4, 0, 0, b""));
+        tx.extend(frame(0x1, 0x4, 1, b"request-headers"));
+        // The request message is split across two DATA frames.
+        tx.extend(frame(0x0, 0, 1, &request[..7]));
+        tx.extend(frame(0x0, 0x1, 1, &request[7..]));
+
+        let mut body = grpc(b"balance: 42");
+        body.extend(grpc(b"currency: usd"));
+
+        let mut rx = frame(0x4, 0, 0, b"");
+        rx.extend(frame(0x1, 0x4, 1, b"response-headers"));
+        rx.extend(frame(0x8, 0, 0, &[0, 0, 1, 0]));
+        rx.extend(frame(0x0, 0, 1, &body));
+        rx.extend(frame(0x1, 0x5, 1, b"trailers"));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_http2_transcript() {
+        let (tx, rx) = transcripts();
+        let (tx, rx) = (Transcript::new(tx), Transcript::new(rx));
+        let transcript = Http2Transcript::parse(&tx, &rx).unwrap();
+
+        assert_eq!(transcript.sent_frames.len(), 4);
+        assert_eq!(transcript.received_frames.len(), 5);
+
+        let request = transcript.stream(Direction::Sent, 1).unwrap();
+        assert!(request.is_complete());
+        assert_eq!(request.data_frames().len(), 2);
+        let messages = request.grpc_messages(&tx).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].data(tx.data()), b"get-balance");
+        assert_eq!(messages[0].message().iter_ranges().count(), 2);
+
+        let response = transcript.stream(Direction::Received, 1).unwrap();
+        assert_eq!(response.header_frames().len(), 2);
+        let messages = response.grpc_messages(&rx).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].data(rx.data()), b"currency: usd");
+
+        assert!(matches!(
+            Http2Transcript::parse(&rx, &rx),
+            Err(Http2Error::MissingPreface)
+        ));
+    }
+
+    #[test]
+    fn test_http2_prove() {
+        let (tx, rx) = transcripts();
+        let (transcript_tx, transcript_rx) =
+            (Transcript::new(tx.clone()), Transcript::new(rx.clone()));
+        let transcript = Http2Transcript::parse(&transcript_tx, &transcript_rx).unwrap();
+        let response = transcript.stream(Direction::Received, 1).unwrap();
+        let messages = response.grpc_messages(&transcript_rx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        builder.commit_sent(&(0..tx.len())).unwrap();
+        let framing = builder
+            .commit_recv(
+                &transcript
+                    .received_frames
+                    .iter()
+                    .fold(RangeSet::default(), |ranges, frame| {
+                        ranges.union(&frame.framing())
+                    }),
+            )
+            .unwrap();
+        let balance = messages[0]
+            .commit(&mut builder, Direction::Received)
+            .unwrap();
+        let currency = messages[1]
+            .commit(&mut builder, Direction::Received)
+            .unwrap();
+        let commitments = builder.build().unwrap();
+
+        // Reveal the request, the framing of the response and only the balance.
+        let mut proof_builder =
+            SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        proof_builder
+            .reveal_sent(&(0..tx.len()), CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_by_id(framing)
+            .unwrap()
+            .reveal_by_id(balance.prefix)
+            .unwrap()
+            .reveal_by_id(balance.message.unwrap())
+            .unwrap()
+            .reveal_by_id(currency.prefix)
+            .unwrap();
+        let proof = proof_builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (sent_redacted, recv_redacted) = proof.verify(&header).unwrap();
+
+        let verified = Http2Transcript::parse_redacted(&sent_redacted, &recv_redacted).unwrap();
+        let messages = verified
+            .stream(Direction::Received, 1)
+            .unwrap()
+            .grpc_messages_redacted(&recv_redacted)
+            .unwrap();
+        assert_eq!(messages[0].verify(&recv_redacted).unwrap(), b"balance: 42");
+        assert!(matches!(
+            messages[1].verify(&recv_redacted),
+            Err(Http2Error::Redacted)
+        ));
+    }
+}
@@ -0,0 +1,560 @@
+//! Tooling for working with IMAP data.
+//!
+//! IMAP is line-based, except for literals: a line ending with `{n}` is followed by `n` bytes of
+//! raw data, e.g. a message returned by a FETCH command, after which the line continues.
+//! [`ImapTranscript`] splits the transcripts into commands and responses accounting for literals.
+//!
+//! A typical proof of ownership of a mailbox reveals the user name of the LOGIN command, whose
+//! password is located with [`ImapCommand::login_password`] so it can be kept private, the
+//! tagged OK response to it, and the fields of a fetched message located with
+//! [`message_header`].
+
+use std::ops::Range;
+
+use tlsn_core::{RedactedTranscript, Transcript};
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::http::fill;
+
+/// An error for IMAP data.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ImapError {
+    /// The literal announced at the offset is truncated.
+    #[error("incomplete literal at offset {offset}")]
+    IncompleteLiteral {
+        /// The offset of the literal.
+        offset: usize,
+    },
+    /// The line at the offset is not a valid command or response.
+    #[error("malformed line at offset {offset}")]
+    Malformed {
+        /// The offset of the line.
+        offset: usize,
+    },
+}
+
+/// The kind of an IMAP response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    /// An untagged response, starting with `*`.
+    Untagged,
+    /// A continuation request, starting with `+`.
+    Continuation,
+    /// A tagged response, completing the command with the same tag.
+    Tagged,
+}
+
+/// An IMAP command, located in the sent transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapCommand {
+    tag: String,
+    name: String,
+    range: Range<usize>,
+    args: Range<usize>,
+    literals: Vec<Range<usize>>,
+}
+
+impl ImapCommand {
+    /// Returns the tag of the command.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the name of the command, in upper case.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the range of the whole command, including its literals and line ending.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the range of the arguments of the command, without its line ending.
+    pub fn args(&self) -> Range<usize> {
+        self.args.clone()
+    }
+
+    /// Returns the ranges of the literals of the command.
+    pub fn literals(&self) -> &[Range<usize>] {
+        &self.literals
+    }
+
+    /// Returns the range of the user name of a LOGIN command.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the transcript the command was parsed from.
+    pub fn login_user(&self, data: &[u8]) -> Option<Range<usize>> {
+        self.login_args(data).map(|(user, _)| user)
+    }
+
+    /// Returns the range of the password of a LOGIN command, without its quotes.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the transcript the command was parsed from.
+    pub fn login_password(&self, data: &[u8]) -> Option<Range<usize>> {
+        self.login_args(data).map(|(_, password)| password)
+    }
+
+    fn login_args(&self, data: &[u8]) -> Option<(Range<usize>, Range<usize>)> {
+        if self.name != "LOGIN" {
+            return None;
+        }
+
+        let mut literals = self.literals.iter().cloned();
+        let user = astring(data, self.args.start, self.args.end, &mut literals)?;
+        let password = astring(data, user.1, self.args.end, &mut literals)?;
+
+        Some((user.0, password.0))
+    }
+}
+
+impl ToRangeSet<usize> for ImapCommand {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.range().into()
+    }
+}
+
+/// An IMAP response, located in the received transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImapResponse {
+    kind: ResponseKind,
+    tag: String,
+    name: String,
+    range: Range<usize>,
+    literals: Vec<Range<usize>>,
+}
+
+impl ImapResponse {
+    /// Returns the kind of the response.
+    pub fn kind(&self) -> ResponseKind {
+        self.kind
+    }
+
+    /// Returns the tag of the response, which is `*` or `+` unless it is tagged.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the name of the response in upper case, e.g. `OK` or `FETCH`.
+    ///
+    /// For untagged responses starting with a number, e.g. `* 1 FETCH`, this is the name
+    /// following the number.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the range of the whole response, including its literals and line ending.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the ranges of the literals of the response, e.g. the fetched messages.
+    pub fn literals(&self) -> &[Range<usize>] {
+        &self.literals
+    }
+}
+
+impl ToRangeSet<usize> for ImapResponse {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.range().into()
+    }
+}
+
+/// An IMAP session.
+#[derive(Debug)]
+pub struct ImapTranscript {
+    /// The commands sent to the server.
+    pub commands: Vec<ImapCommand>,
+    /// The responses received from the server, starting with its greeting.
+    pub responses: Vec<ImapResponse>,
+}
+
+impl ImapTranscript {
+    /// Parses the IMAP session from the provided transcripts.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, ImapError> {
+        Self::parse_inner(tx.data(), rx.data())
+    }
+
+    /// Parses the IMAP session from the verified transcripts.
+    ///
+    /// The transcripts are parsed with their redacted bytes replaced, like a
+    /// [`RedactedHttpTranscript`](crate::http::RedactedHttpTranscript), so the tags, names and
+    /// literal lengths must be revealed. The data located in the session must be checked to be
+    /// revealed before it is relied upon.
+    pub fn parse_redacted(
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Result<Self, ImapError> {
+        Self::parse_inner(&fill(sent), &fill(recv))
+    }
+
+    fn parse_inner(tx: &[u8], rx: &[u8]) -> Result<Self, ImapError> {
+        let commands = logical_lines(tx)?
+            .into_iter()
+            .map(|line| {
+                let (tag, rest) = token(tx, line.range.start, line.first_line_end).ok_or(
+                    ImapError::Malformed {
+                        offset: line.range.start,
+                    },
+                )?;
+                let (name, args_start) =
+                    token(tx, rest, line.first_line_end).ok_or(ImapError::Malformed {
+                        offset: line.range.start,
+                    })?;
+
+                Ok(ImapCommand {
+                    tag: text(tx, tag),
+                    name: text(tx, name).to_ascii_uppercase(),
+                    args: args_start.min(line.content_end)..line.content_end,
+                    range: line.range,
+                    literals: line.literals,
+                })
+            })
+            .collect::<Result<Vec<_>, ImapError>>()?;
+
+        let responses = logical_lines(rx)?
+            .into_iter()
+            .map(|line| {
+                let malformed = ImapError::Malformed {
+                    offset: line.range.start,
+                };
+                let (tag, rest) =
+                    token(rx, line.range.start, line.first_line_end).ok_or(malformed)?;
+                let tag = text(rx, tag);
+                let kind = match tag.as_str() {
+                    "*" => ResponseKind::Untagged,
+                    "+" => ResponseKind::Continuation,
+                    _ => ResponseKind::Tagged,
+                };
+
+                let mut name = token(rx, rest, line.first_line_end);
+                if let Some((range, rest)) = name.clone() {
+                    if rx[range].iter().all(u8::is_ascii_digit) {
+                        name = token(rx, rest, line.first_line_end);
+                    }
+                }
+                let name = name
+                    .map(|(range, _)| text(rx, range).to_ascii_uppercase())
+                    .unwrap_or_default();
+
+                Ok(ImapResponse {
+                    kind,
+                    tag,
+                    name,
+                    range: line.range,
+                    literals: line.literals,
+                })
+            })
+            .collect::<Result<Vec<_>, ImapError>>()?;
+
+        Ok(Self {
+            commands,
+            responses,
+        })
+    }
+
+    /// Returns the tagged response completing the provided command.
+    pub fn completion(&self, command: &ImapCommand) -> Option<&ImapResponse> {
+        self.responses
+            .iter()
+            .find(|response| response.kind == ResponseKind::Tagged && response.tag == command.tag)
+    }
+}
+
+/// Returns the range of the value of the first field with the provided name in the header of a
+/// message, e.g. a literal of a FETCH response.
+///
+/// The value excludes the leading whitespace and the line ending, and includes the folded lines
+/// of the field.
+///
+/// # Arguments
+///
+/// * `data` - The data of the transcript.
+/// * `message` - The range of the message.
+/// * `name` - The case-insensitive name of the field.
+pub fn message_header(data: &[u8], message: Range<usize>, name: &str) -> Option<Range<usize>> {
+    let mut offset = message.start;
+    while offset < message.end {
+        let line_end = data[offset..message.end]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|pos| offset + pos + 1)
+            .unwrap_or(message.end);
+        let line = &data[offset..line_end];
+
+        // The header ends at the first empty line.
+        if line == b"\r\n" || line == b"\n" {
+            return None;
+        }
+
+        if let Some(colon) = line.iter().position(|byte| *byte == b':') {
+            if line[..colon].eq_ignore_ascii_case(name.as_bytes()) {
+                let mut start = offset + colon + 1;
+                while start < line_end && matches!(data[start], b' ' | b'\t') {
+                    start += 1;
+                }
+
+                // Folded lines start with whitespace.
+                let mut end = line_end;
+                while end < message.end && matches!(data[end], b' ' | b'\t') {
+                    end = data[end..message.end]
+                        .iter()
+                        .position(|byte| *byte == b'\n')
+                        .map(|pos| end + pos + 1)
+                        .unwrap_or(message.end);
+                }
+                while end > start && matches!(data[end - 1], b'\r' | b'\n') {
+                    end -= 1;
+                }
+
+                return Some(start..end);
+            }
+        }
+
+        offset = line_end;
+    }
+
+    None
+}
+
+/// A line of a command or response, including its literals.
+struct LogicalLine {
+    range: Range<usize>,
+    /// The end of the content of the first line, before its first literal.
+    first_line_end: usize,
+    /// The end of the content of the last line, before its line ending.
+    content_end: usize,
+    literals: Vec<Range<usize>>,
+}
+
+fn logical_lines(data: &[u8]) -> Result<Vec<LogicalLine>, ImapError> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let mut literals = Vec::new();
+        let mut first_line_end = None;
+        let mut offset = start;
+
+        let (content_end, end) = loop {
+            let (content_end, end) = match data[offset..].iter().position(|byte| *byte == b'\n') {
+                Some(pos) => {
+                    let end = offset + pos + 1;
+                    let content_end = if pos > 0 && data[end - 2] == b'\r' {
+                        end - 2
+                    } else {
+                        end - 1
+                    };
+                    (content_end, end)
+                }
+                None => (data.len(), data.len()),
+            };
+            first_line_end.get_or_insert(content_end);
+
+            match literal_len(&data[offset..content_end]) {
+                Some(len) => {
+                    if end + len > data.len() {
+                        return Err(ImapError::IncompleteLiteral { offset: end });
+                    }
+                    literals.push(end..end + len);
+                    offset = end + len;
+                }
+                _ => break (content_end, end),
+            }
+        };
+
+        lines.push(LogicalLine {
+            range: start..end,
+            first_line_end: first_line_end.expect("a line was read"),
+            content_end,
+            literals,
+        });
+        start = end;
+    }
+
+    Ok(lines)
+}
+
+/// Returns the length of the literal announced at the end of the line, e.g. `{42}` or `{42+}`.
+fn literal_len(line: &[u8]) -> Option<usize> {
+    let inner = line.strip_suffix(b"}")?;
+    let open = inner.iter().rposition(|byte| *byte == b'{')?;
+    let digits = &inner[open + 1..];
+    let digits = digits.strip_suffix(b"+").unwrap_or(digits);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+/// Returns the range of the space-delimited token starting at the offset, and the offset of the
+/// next token.
+fn token(data: &[u8], start: usize, end: usize) -> Option<(Range<usize>, usize)> {
+    if start >= end {
+        return None;
+    }
+
+    let len = data[start..end]
+        .iter()
+        .position(|byte| *byte == b' ')
+        .unwrap_or(end - start);
+    if len == 0 {
+        return None;
+    }
+
+    Some((start..start + len, (start + len + 1).min(end)))
+}
+
+/// Returns the range of the astring starting at the offset, i.e. an atom, a quoted string or a
+/// literal, and the offset of the next token.
+fn astring(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    literals: &mut impl Iterator<Item = Range<usize>>,
+) -> Option<(Range<usize>, usize)> {
+    if start >= end {
+        return None;
+    }
+
+    match data[start] {
+        b'"' => {
+            let mut pos = start + 1;
+            while pos < end && data[pos] != b'"' {
+                pos += if data[pos] == b'\\' { 2 } else { 1 };
+            }
+            (pos < end).then(|| (start + 1..pos, (pos + 2).min(end)))
+        }
+        b'{' => {
+            let literal = literals.next()?;
+            Some((literal.clone(), (literal.end + 1).min(end)))
+        }
+        _ => token(data, start, end),
+    }
+}
+
+fn text(data: &[u8], range: Range<usize>) -> String {
+    String::from_utf8_lossy(&data[range]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures,
+        proof::SubstringsProofBuilder,
+        Direction,
+    };
+    use utils::range::RangeDifference;
+
+    use crate::text::{commit_lines, lines};
+
+    static MESSAGE: &[u8] = b"From: bank@example.com\r\nTo: alice@example.com\r\n\
+    Subject: Monthly\r\n statement\r\n\r\n";
+
+    fn transcripts() -> (Vec<u8>, Vec<u8>) {
+        let tx = b"a1 LOGIN alice@example.com \"hunter2\"\r\n\
+        a2 SELECT INBOX\r\n\
+        a3 FETCH 1 (BODY[HEADER.FIELDS (FROM TO SUBJECT)])\r\n"
+            .to_vec();
+
+        let mut rx = b"* OK IMAP ready\r\n\
+        a1 OK LOGIN completed\r\n\
+        * 1 EXISTS\r\n\
+        a2 OK [READ-WRITE] SELECT completed\r\n"
+            .to_vec();
+        rx.extend(
+            format!(
+                "* 1 FETCH (BODY[HEADER.FIELDS (FROM TO SUBJECT)] {{{}}}\r\n",
+                MESSAGE.len()
+            )
+            .as_bytes(),
+        );
+        rx.extend_from_slice(MESSAGE);
+        rx.extend_from_slice(b")\r\na3 OK FETCH completed\r\n");
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_imap_transcript() {
+        let (tx, rx) = transcripts();
+        let transcript =
+            ImapTranscript::parse(&Transcript::new(tx.clone()), &Transcript::new(rx.clone()))
+                .unwrap();
+
+        assert_eq!(transcript.commands.len(), 3);
+        let login = &transcript.commands[0];
+        assert_eq!(login.name(), "LOGIN");
+        assert_eq!(&tx[login.login_user(&tx).unwrap()], b"alice@example.com");
+        assert_eq!(&tx[login.login_password(&tx).unwrap()], b"hunter2");
+        assert_eq!(transcript.commands[1].login_user(&tx), None);
+
+        assert_eq!(transcript.responses.len(), 6);
+        assert_eq!(
+            transcript.completion(login).unwrap().kind(),
+            ResponseKind::Tagged
+        );
+
+        let fetch = &transcript.responses[4];
+        assert_eq!(fetch.name(), "FETCH");
+        assert_eq!(&rx[fetch.literals()[0].clone()], MESSAGE);
+
+        let message = fetch.literals()[0].clone();
+        assert_eq!(
+            &rx[message_header(&rx, message.clone(), "to").unwrap()],
+            b"alice@example.com"
+        );
+        assert_eq!(
+            &rx[message_header(&rx, message.clone(), "subject").unwrap()],
+            b"Monthly\r\n statement"
+        );
+        assert_eq!(message_header(&rx, message, "cc"), None);
+    }
+
+    #[test]
+    fn test_imap_prove() {
+        let (tx, rx) = transcripts();
+        let (transcript_tx, transcript_rx) =
+            (Transcript::new(tx.clone()), Transcript::new(rx.clone()));
+        let transcript = ImapTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+        let password = transcript.commands[0].login_password(&tx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        let sent =
+            commit_lines(&mut builder, &lines(&tx), &password.into(), Direction::Sent).unwrap();
+        builder.commit_recv(&(0..rx.len())).unwrap();
+        let commitments = builder.build().unwrap();
+
+        // Reveal everything but the password.
+        let mut proof_builder =
+            SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        for line in &sent {
+            proof_builder.reveal_by_id(line.public.unwrap()).unwrap();
+        }
+        proof_builder
+            .reveal_recv(&(0..rx.len()), CommitmentKind::Blake3)
+            .unwrap();
+        let proof = proof_builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (sent_redacted, recv_redacted) = proof.verify(&header).unwrap();
+
+        let verified = ImapTranscript::parse_redacted(&sent_redacted, &recv_redacted).unwrap();
+        let login = &verified.commands[0];
+        let user = login.login_user(sent_redacted.data()).unwrap();
+        assert!(user.difference(sent_redacted.authed()).is_empty());
+        assert_eq!(&sent_redacted.data()[user], b"alice@example.com");
+        assert_eq!(verified.completion(login).unwrap().name(), "OK");
+    }
+}
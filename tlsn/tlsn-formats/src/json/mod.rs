@@ -1,6 +1,7 @@
 //! Tooling for working with JSON data.
 
 mod commit;
+mod path;
 
 use spansy::json;
 
@@ -8,3 +9,4 @@ pub use commit::{DefaultJsonCommitter, JsonCommit, JsonCommitError};
 pub use json::{
     Array, Bool, JsonKey, JsonValue, JsonVisit, KeyValue, Null, Number, Object, String,
 };
+pub use path::{JsonPath, JsonPathError, PathSegment};
@@ -0,0 +1,415 @@
+use std::{fmt, str::FromStr};
+
+use spansy::Spanned;
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder},
+    Direction,
+};
+use utils::range::{RangeSet, ToRangeSet};
+
+use crate::json::{JsonCommitError, JsonValue};
+
+/// An error for [`JsonPath`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum JsonPathError {
+    /// The path is malformed.
+    #[error("invalid json path at position {pos}: {msg}")]
+    Parse {
+        /// The byte position in the path where the error occurred.
+        pos: usize,
+        /// The error message.
+        msg: &'static str,
+    },
+    /// No value exists at the path.
+    #[error("no value at {0}")]
+    NotFound(String),
+    /// An object along the path contains the key more than once.
+    #[error("duplicate key at {0}")]
+    DuplicateKey(String),
+    /// A value along the path is neither an object nor an array.
+    #[error("expected an object or an array at {0}")]
+    NotContainer(String),
+}
+
+/// A segment of a [`JsonPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A key of an object.
+    Key(String),
+    /// An index of an array.
+    Index(usize),
+}
+
+/// A path to a value in a JSON document, using the JSONPath notation of a single value.
+///
+/// A path starts with the root `$`, followed by any number of segments:
+///
+/// - `.key` selects the value of a key of an object, where the key does not contain `.`, `[` or
+///   `]`.
+/// - `['key']` or `["key"]` selects the value of any key, where `\` escapes the next character.
+/// - `[index]` selects the element of an array.
+///
+/// For example `$.data.entries[0].message_create.text`.
+///
+/// Keys are compared to the unescaped keys of the document, so `$['é']` matches the key
+/// `"\u00e9"`. The selected ranges never include the whitespace surrounding a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// Parses a JSON path.
+    pub fn parse(path: &str) -> Result<Self, JsonPathError> {
+        let err = |pos, msg| JsonPathError::Parse { pos, msg };
+
+        let mut chars = path.char_indices().peekable();
+        if !matches!(chars.next(), Some((_, '$'))) {
+            return Err(err(0, "expected the root `$`"));
+        }
+
+        let mut segments = Vec::new();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some((_, c)) = chars.next_if(|(_, c)| !matches!(c, '.' | '[' | ']')) {
+                        key.push(c);
+                    }
+
+                    if key.is_empty() {
+                        return Err(err(pos + 1, "expected a key"));
+                    }
+
+                    segments.push(PathSegment::Key(key));
+                }
+                '[' => match chars.next() {
+                    Some((_, quote @ ('\'' | '"'))) => {
+                        let mut key = String::new();
+                        loop {
+                            match chars.next() {
+                                Some((_, c)) if c == quote => break,
+                                Some((_, '\\')) => match chars.next() {
+                                    Some((_, c)) => key.push(c),
+                                    None => return Err(err(path.len(), "unterminated key")),
+                                },
+                                Some((_, c)) => key.push(c),
+                                None => return Err(err(path.len(), "unterminated key")),
+                            }
+                        }
+
+                        match chars.next() {
+                            Some((_, ']')) => segments.push(PathSegment::Key(key)),
+                            Some((pos, _)) => return Err(err(pos, "expected `]`")),
+                            None => return Err(err(path.len(), "expected `]`")),
+                        }
+                    }
+                    Some((start, c)) if c.is_ascii_digit() => {
+                        let mut end = start + 1;
+                        while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {
+                            end += 1;
+                        }
+
+                        let index = path[start..end]
+                            .parse()
+                            .map_err(|_| err(start, "index is too large"))?;
+
+                        match chars.next() {
+                            Some((_, ']')) => segments.push(PathSegment::Index(index)),
+                            Some((pos, _)) => return Err(err(pos, "expected `]`")),
+                            None => return Err(err(path.len(), "expected `]`")),
+                        }
+                    }
+                    Some((pos, _)) => return Err(err(pos, "expected a key or an index")),
+                    None => return Err(err(path.len(), "expected a key or an index")),
+                },
+                _ => return Err(err(pos, "expected `.` or `[`")),
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Returns the segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns the value at this path.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The root value of the document.
+    pub fn resolve<'a>(&self, value: &'a JsonValue) -> Result<&'a JsonValue, JsonPathError> {
+        let mut value = value;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let prefix = || {
+                Self {
+                    segments: self.segments[..=i].to_vec(),
+                }
+                .to_string()
+            };
+
+            value = match (value, segment) {
+                (JsonValue::Object(object), PathSegment::Key(key)) => {
+                    let mut values = object
+                        .elems
+                        .iter()
+                        .filter(|kv| unescape_key(kv.key.span().as_str()).as_deref() == Some(key))
+                        .map(|kv| &kv.value);
+
+                    let value = values
+                        .next()
+                        .ok_or_else(|| JsonPathError::NotFound(prefix()))?;
+
+                    if values.next().is_some() {
+                        return Err(JsonPathError::DuplicateKey(prefix()));
+                    }
+
+                    value
+                }
+                (JsonValue::Array(array), PathSegment::Index(index)) => array
+                    .elems
+                    .get(*index)
+                    .ok_or_else(|| JsonPathError::NotFound(prefix()))?,
+                (JsonValue::Object(_) | JsonValue::Array(_), _) => {
+                    return Err(JsonPathError::NotFound(prefix()))
+                }
+                _ => return Err(JsonPathError::NotContainer(prefix())),
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the ranges of the value at this path.
+    ///
+    /// The ranges of a string exclude its quotes and cover its escaped contents, as they appear in
+    /// the document. If the document was parsed from a transcript, such as the body of an HTTP
+    /// message, the ranges are ranges of the transcript.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The root value of the document.
+    pub fn ranges(&self, value: &JsonValue) -> Result<RangeSet<usize>, JsonPathError> {
+        self.resolve(value).map(|value| value.to_range_set())
+    }
+
+    /// Commits to the value at this path.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `value` - The root value of the document, parsed from the transcript.
+    /// * `direction` - The direction of the data (sent or received).
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        value: &JsonValue,
+        direction: Direction,
+    ) -> Result<CommitmentId, JsonCommitError> {
+        let ranges = self
+            .ranges(value)
+            .map_err(|e| JsonCommitError::new_with_source("failed to resolve json path", e))?;
+
+        builder
+            .commit(&ranges, direction)
+            .map_err(|e| JsonCommitError::new_with_source("failed to commit json path", e))
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if !key.is_empty() && !key.contains(['.', '[', ']']) => {
+                    write!(f, ".{key}")?
+                }
+                PathSegment::Key(key) => {
+                    write!(f, "['")?;
+                    for c in key.chars() {
+                        if c == '\'' || c == '\\' {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{c}")?;
+                    }
+                    write!(f, "']")?
+                }
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Unescapes a key of a JSON object, returning `None` if it contains an invalid escape.
+fn unescape_key(raw: &str) -> Option<String> {
+    let raw = raw
+        .strip_prefix('"')
+        .and_then(|raw| raw.strip_suffix('"'))
+        .unwrap_or(raw);
+
+    let mut key = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            key.push(c);
+            continue;
+        }
+
+        key.push(match chars.next()? {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = parse_hex4(&mut chars)?;
+                if (0xD800..0xDC00).contains(&high) {
+                    if chars.next()? != '\\' || chars.next()? != 'u' {
+                        return None;
+                    }
+                    let low = parse_hex4(&mut chars)?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return None;
+                    }
+                    char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))?
+                } else {
+                    char::from_u32(high)?
+                }
+            }
+            _ => return None,
+        });
+    }
+
+    Some(key)
+}
+
+fn parse_hex4(chars: &mut std::str::Chars<'_>) -> Option<u32> {
+    let mut value = 0;
+    for _ in 0..4 {
+        value = value * 16 + chars.next()?.to_digit(16)?;
+    }
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rstest::rstest;
+
+    use super::*;
+
+    static DOC: &str = r#"{
+        "data" : {
+            "entries": [
+                { "message_create" : { "text" : "say \"hi\"" } },
+                { "message_create": { "text": "bye" } }
+            ]
+        },
+        "caf\u00e9": 1,
+        "a.b": [ true , null ],
+        "emoji\ud83d\ude00": 2.5
+    }"#;
+
+    fn doc() -> JsonValue {
+        spansy::json::parse(Bytes::from_static(DOC.as_bytes())).unwrap()
+    }
+
+    fn select(path: &str) -> String {
+        let ranges = JsonPath::parse(path).unwrap().ranges(&doc()).unwrap();
+        ranges.iter_ranges().map(|range| &DOC[range]).collect()
+    }
+
+    #[rstest]
+    #[case::escaped_string("$.data.entries[0].message_create.text", r#"say \"hi\""#)]
+    #[case::second_entry("$.data.entries[1].message_create.text", "bye")]
+    #[case::object("$.data.entries[1]", r#"{ "message_create": { "text": "bye" } }"#)]
+    #[case::escaped_key("$['café']", "1")]
+    #[case::surrogate_pair("$[\"emoji😀\"]", "2.5")]
+    #[case::bracket_key("$['a.b'][1]", "null")]
+    #[case::bool("$['a.b'][0]", "true")]
+    fn test_json_path_ranges(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(select(path), expected);
+    }
+
+    #[test]
+    fn test_json_path_root() {
+        let path = JsonPath::parse("$").unwrap();
+        assert!(path.segments().is_empty());
+        assert_eq!(
+            path.ranges(&doc()).unwrap().iter_ranges().next().unwrap(),
+            0..DOC.len()
+        );
+    }
+
+    #[rstest]
+    #[case("$.data.entries[0].message_create.text")]
+    #[case("$['a.b'][1]")]
+    #[case("$['it\\'s']")]
+    fn test_json_path_display(#[case] path: &str) {
+        assert_eq!(JsonPath::parse(path).unwrap().to_string(), path);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("data")]
+    #[case("$.")]
+    #[case("$..data")]
+    #[case("$[")]
+    #[case("$[x]")]
+    #[case("$[0")]
+    #[case("$['data'")]
+    #[case("$['data]")]
+    #[case("$.data]")]
+    fn test_json_path_parse_error(#[case] path: &str) {
+        assert!(matches!(
+            JsonPath::parse(path),
+            Err(JsonPathError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_json_path_resolve_error() {
+        let doc = doc();
+        let resolve = |path: &str| JsonPath::parse(path).unwrap().resolve(&doc).map(|_| ());
+
+        assert!(matches!(
+            resolve("$.data.missing"),
+            Err(JsonPathError::NotFound(path)) if path == "$.data.missing"
+        ));
+        assert!(matches!(
+            resolve("$.data.entries[2]"),
+            Err(JsonPathError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve("$.data[0]"),
+            Err(JsonPathError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve("$['café'].foo"),
+            Err(JsonPathError::NotContainer(path)) if path == "$.café.foo"
+        ));
+
+        let doc = spansy::json::parse(Bytes::from_static(br#"{"a": 1, "a": 2}"#)).unwrap();
+        assert!(matches!(
+            JsonPath::parse("$.a").unwrap().resolve(&doc),
+            Err(JsonPathError::DuplicateKey(_))
+        ));
+    }
+}
@@ -11,8 +11,13 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod html;
 pub mod http;
+pub mod http2;
+pub mod imap;
 pub mod json;
+pub mod text;
+pub mod websocket;
 
 #[doc(hidden)]
 pub use spansy;
@@ -0,0 +1,172 @@
+//! Tooling for working with line-based protocols, independently of their syntax.
+//!
+//! Many protocols run over TLS exchange lines of text, e.g. SMTP, IMAP, POP3 or IRC. This module
+//! splits a transcript into lines and commits to each line separately, so that any line can be
+//! disclosed without knowledge of the protocol, while the private ranges of a line are committed
+//! on their own and never revealed along with it.
+//!
+//! See the [`imap`](crate::imap) module for a protocol which builds on these lines.
+
+use std::ops::Range;
+
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction,
+};
+use utils::range::{RangeDifference, RangeSet, ToRangeSet};
+
+/// A line of a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    range: Range<usize>,
+    content: Range<usize>,
+}
+
+impl Line {
+    /// Returns the range of the line, including its line ending.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the range of the content of the line, without its line ending.
+    pub fn content(&self) -> Range<usize> {
+        self.content.clone()
+    }
+
+    /// Returns `true` if the line is terminated by a line ending, i.e. it is not the truncated
+    /// end of the transcript.
+    pub fn is_terminated(&self) -> bool {
+        self.content.end < self.range.end
+    }
+}
+
+impl ToRangeSet<usize> for Line {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.range().into()
+    }
+}
+
+/// Splits the data into lines terminated by `\n` or `\r\n`.
+pub fn lines(data: &[u8]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let line = match data[start..].iter().position(|byte| *byte == b'\n') {
+            Some(pos) => {
+                let end = start + pos + 1;
+                let content_end = if pos > 0 && data[start + pos - 1] == b'\r' {
+                    end - 2
+                } else {
+                    end - 1
+                };
+                Line {
+                    range: start..end,
+                    content: start..content_end,
+                }
+            }
+            None => Line {
+                range: start..data.len(),
+                content: start..data.len(),
+            },
+        };
+
+        start = line.range.end;
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// The commitments to a [`Line`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineCommitments {
+    /// The commitment to the public ranges of the line, unless the line is fully private.
+    pub public: Option<CommitmentId>,
+    /// The commitments to the private ranges of the line, one per range.
+    pub private: Vec<CommitmentId>,
+}
+
+/// Commits to each of the provided lines, returning their commitments in the same order.
+///
+/// The public ranges of a line are committed together, so that revealing the line discloses
+/// all of it except its private ranges.
+///
+/// # Arguments
+///
+/// * `builder` - The commitment builder.
+/// * `lines` - The lines to commit to.
+/// * `private` - The ranges of the transcript which must not be revealed with the lines.
+/// * `direction` - The direction of the lines.
+pub fn commit_lines(
+    builder: &mut TranscriptCommitmentBuilder,
+    lines: &[Line],
+    private: &RangeSet<usize>,
+    direction: Direction,
+) -> Result<Vec<LineCommitments>, TranscriptCommitmentBuilderError> {
+    let mut commitments = Vec::with_capacity(lines.len());
+    for line in lines {
+        let public_ranges = line.range().difference(private);
+        let public = if public_ranges.is_empty() {
+            None
+        } else {
+            Some(builder.commit(&public_ranges, direction)?)
+        };
+
+        let private = line
+            .range()
+            .difference(&public_ranges)
+            .iter_ranges()
+            .map(|range| builder.commit(&range, direction))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        commitments.push(LineCommitments { public, private });
+    }
+
+    Ok(commitments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{fixtures, range::find_all};
+
+    #[test]
+    fn test_lines() {
+        let data = b"* OK ready\r\nA1 NOOP\n\r\ntruncated";
+        let lines = lines(data);
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(&data[lines[0].content()], b"* OK ready");
+        assert_eq!(lines[0].range(), 0..12);
+        assert_eq!(&data[lines[1].content()], b"A1 NOOP");
+        assert!(lines[2].content().is_empty());
+        assert_eq!(&data[lines[3].content()], b"truncated");
+        assert!(!lines[3].is_terminated());
+    }
+
+    #[test]
+    fn test_commit_lines() {
+        let tx = b"A1 LOGIN alice secret\r\nA2 LOGOUT\r\n";
+        let rx = b"A1 OK\r\n";
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(tx, rx),
+            tx.len(),
+            rx.len(),
+        );
+
+        let private = find_all(tx, &[b"secret".as_slice()]);
+        let commitments =
+            commit_lines(&mut builder, &lines(tx), &private, Direction::Sent).unwrap();
+        let commitments_info = builder.build().unwrap();
+
+        assert_eq!(commitments.len(), 2);
+        assert_eq!(commitments[0].private.len(), 1);
+        assert!(commitments[1].private.is_empty());
+
+        let public = commitments_info
+            .get_info(&commitments[0].public.unwrap())
+            .unwrap();
+        assert_eq!(public.ranges(), &RangeSet::from([0..15, 21..23]));
+    }
+}
@@ -0,0 +1,222 @@
+use std::ops::Range;
+
+use utils::range::{RangeDifference, RangeSet};
+
+use crate::websocket::WebSocketError;
+
+/// The opcode of a WebSocket frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    /// A continuation of a fragmented message.
+    Continuation,
+    /// A text message.
+    Text,
+    /// A binary message.
+    Binary,
+    /// A close frame.
+    Close,
+    /// A ping frame.
+    Ping,
+    /// A pong frame.
+    Pong,
+    /// A reserved opcode.
+    Reserved(u8),
+}
+
+impl Opcode {
+    fn from_u8(opcode: u8) -> Self {
+        match opcode {
+            0x0 => Self::Continuation,
+            0x1 => Self::Text,
+            0x2 => Self::Binary,
+            0x8 => Self::Close,
+            0x9 => Self::Ping,
+            0xa => Self::Pong,
+            opcode => Self::Reserved(opcode),
+        }
+    }
+
+    /// Returns `true` if this is the opcode of a control frame, which can not be fragmented.
+    pub fn is_control(&self) -> bool {
+        match self {
+            Self::Close | Self::Ping | Self::Pong => true,
+            Self::Reserved(opcode) => *opcode >= 0x8,
+            _ => false,
+        }
+    }
+}
+
+/// A WebSocket frame, located in a transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    fin: bool,
+    opcode: Opcode,
+    mask: Option<[u8; 4]>,
+    header: Range<usize>,
+    payload: Range<usize>,
+}
+
+impl Frame {
+    /// Parses the frame starting at the provided offset of the data.
+    pub fn parse(data: &[u8], offset: usize) -> Result<Self, WebSocketError> {
+        let incomplete = || WebSocketError::Incomplete { offset };
+        let byte = |pos: usize| data.get(pos).copied().ok_or_else(incomplete);
+
+        let (first, second) = (byte(offset)?, byte(offset + 1)?);
+        let mut pos = offset + 2;
+
+        let payload_len = match second & 0x7f {
+            126 => {
+                let len = u16::from_be_bytes([byte(pos)?, byte(pos + 1)?]) as u64;
+                pos += 2;
+                len
+            }
+            127 => {
+                let bytes = data.get(pos..pos + 8).ok_or_else(incomplete)?;
+                pos += 8;
+                u64::from_be_bytes(bytes.try_into().expect("slice is 8 bytes"))
+            }
+            len => len as u64,
+        };
+
+        let mask = if second & 0x80 != 0 {
+            let key = data.get(pos..pos + 4).ok_or_else(incomplete)?;
+            pos += 4;
+            Some(key.try_into().expect("slice is 4 bytes"))
+        } else {
+            None
+        };
+
+        let end = usize::try_from(payload_len)
+            .ok()
+            .and_then(|len| pos.checked_add(len))
+            .filter(|end| *end <= data.len())
+            .ok_or_else(incomplete)?;
+
+        Ok(Self {
+            fin: first & 0x80 != 0,
+            opcode: Opcode::from_u8(first & 0x0f),
+            mask,
+            header: offset..pos,
+            payload: pos..end,
+        })
+    }
+
+    /// Returns `true` if this is the final frame of a message.
+    pub fn fin(&self) -> bool {
+        self.fin
+    }
+
+    /// Returns the opcode of the frame.
+    pub fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    /// Returns the masking key of the frame, which is set for frames sent by the client.
+    pub fn mask(&self) -> Option<[u8; 4]> {
+        self.mask
+    }
+
+    /// Returns the range of the header of the frame, including the masking key.
+    pub fn header(&self) -> Range<usize> {
+        self.header.clone()
+    }
+
+    /// Returns the range of the payload of the frame.
+    pub fn payload(&self) -> Range<usize> {
+        self.payload.clone()
+    }
+
+    /// Returns the range of the whole frame.
+    pub fn range(&self) -> Range<usize> {
+        self.header.start..self.payload.end
+    }
+
+    /// Returns the unmasked payload of the frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the transcript the frame was parsed from.
+    pub fn unmasked_payload(&self, data: &[u8]) -> Vec<u8> {
+        let payload = &data[self.payload()];
+        match self.mask {
+            Some(mask) => unmask(payload, mask),
+            None => payload.to_vec(),
+        }
+    }
+}
+
+/// Unmasks the payload of a frame sent by the client.
+pub fn unmask(payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    payload
+        .iter()
+        .zip(mask.iter().cycle())
+        .map(|(byte, key)| byte ^ key)
+        .collect()
+}
+
+/// Parses the frames of the data, from the provided offset to the end of the data.
+///
+/// # Arguments
+///
+/// * `data` - The data of the transcript.
+/// * `start` - The offset of the first frame, i.e. the end of the upgrade handshake.
+/// * `authed` - The ranges of the data which are authenticated, if the data is redacted. The
+///   header of each frame must be authenticated, as the frames are located from their headers.
+pub fn parse_frames(
+    data: &[u8],
+    start: usize,
+    authed: Option<&RangeSet<usize>>,
+) -> Result<Vec<Frame>, WebSocketError> {
+    let mut frames = Vec::new();
+    let mut offset = start;
+    while offset < data.len() {
+        let frame = Frame::parse(data, offset)?;
+
+        if let Some(authed) = authed {
+            if !frame.header().difference(authed).is_empty() {
+                return Err(WebSocketError::RedactedHeader { offset });
+            }
+        }
+
+        offset = frame.payload.end;
+        frames.push(frame);
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frame() {
+        // A masked text frame "Hi" from the client.
+        let mask = [1, 2, 3, 4];
+        let mut data = vec![0x81, 0x82];
+        data.extend_from_slice(&mask);
+        data.extend(unmask(b"Hi", mask));
+
+        let frame = Frame::parse(&data, 0).unwrap();
+        assert!(frame.fin());
+        assert_eq!(frame.opcode(), Opcode::Text);
+        assert_eq!(frame.header(), 0..6);
+        assert_eq!(frame.payload(), 6..8);
+        assert_eq!(frame.unmasked_payload(&data), b"Hi");
+
+        // A binary frame with an extended length from the server.
+        let mut data = vec![0x02, 126, 0x01, 0x00];
+        data.extend(vec![7; 256]);
+
+        let frame = Frame::parse(&data, 0).unwrap();
+        assert!(!frame.fin());
+        assert_eq!(frame.opcode(), Opcode::Binary);
+        assert_eq!(frame.payload(), 4..260);
+
+        assert!(matches!(
+            Frame::parse(&data[..100], 0),
+            Err(WebSocketError::Incomplete { offset: 0 })
+        ));
+    }
+}
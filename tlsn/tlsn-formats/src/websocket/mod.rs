@@ -0,0 +1,431 @@
+//! Tooling for working with WebSocket data.
+//!
+//! A WebSocket connection starts with an HTTP/1.1 upgrade handshake, after which both parties
+//! exchange frames over the same TLS connection, so the transcripts of a session hold the
+//! handshake followed by the frames. [`WebSocketTranscript`] locates the frames and assembles them
+//! into messages, which can be committed to and disclosed individually.
+//!
+//! Frames sent by the client are masked with a key carried in their header, so the plaintext of a
+//! sent message is only disclosed if both its [framing](WebSocketMessage::framing) and its
+//! [payload](WebSocketMessage::payload) are revealed.
+
+mod frame;
+
+pub use frame::{parse_frames, unmask, Frame, Opcode};
+
+use spansy::Spanned;
+use tlsn_core::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    Direction, RedactedTranscript, Transcript,
+};
+use utils::range::{RangeDifference, RangeSet, RangeUnion, ToRangeSet};
+
+use crate::http::{fill, Body, Request, Requests, Response, Responses};
+
+/// An error for WebSocket data.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum WebSocketError {
+    /// The upgrade handshake could not be parsed.
+    #[error("failed to parse the upgrade handshake: {0}")]
+    Parse(#[from] spansy::ParseError),
+    /// The transcript does not start with a WebSocket upgrade handshake.
+    #[error("the transcript does not start with a websocket upgrade handshake")]
+    NotUpgraded,
+    /// The frame at the offset is truncated, e.g. because the connection was closed.
+    #[error("incomplete frame at offset {offset}")]
+    Incomplete {
+        /// The offset of the frame.
+        offset: usize,
+    },
+    /// The header of the frame at the offset is redacted, so the frame can not be located.
+    #[error("the header of the frame at offset {offset} is redacted")]
+    RedactedHeader {
+        /// The offset of the frame.
+        offset: usize,
+    },
+    /// A continuation frame does not continue a message.
+    #[error("unexpected continuation frame at offset {offset}")]
+    UnexpectedContinuation {
+        /// The offset of the frame.
+        offset: usize,
+    },
+    /// The message is not fully revealed.
+    #[error("the message is redacted")]
+    Redacted,
+}
+
+/// A WebSocket message, made of one or more frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketMessage {
+    opcode: Opcode,
+    frames: Vec<Frame>,
+}
+
+impl WebSocketMessage {
+    /// Returns the opcode of the message.
+    pub fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    /// Returns the frames of the message.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Returns `false` if the connection was closed before the final frame of the message.
+    pub fn is_complete(&self) -> bool {
+        self.frames.last().is_some_and(Frame::fin)
+    }
+
+    /// Returns the ranges of the headers of the frames, including their masking keys.
+    pub fn framing(&self) -> RangeSet<usize> {
+        self.frames
+            .iter()
+            .fold(RangeSet::default(), |ranges, frame| {
+                ranges.union(&frame.header())
+            })
+    }
+
+    /// Returns the ranges of the payloads of the frames.
+    pub fn payload(&self) -> RangeSet<usize> {
+        self.frames
+            .iter()
+            .filter(|frame| !frame.payload().is_empty())
+            .fold(RangeSet::default(), |ranges, frame| {
+                ranges.union(&frame.payload())
+            })
+    }
+
+    /// Returns the unmasked data of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data of the transcript the message was parsed from.
+    pub fn data(&self, data: &[u8]) -> Vec<u8> {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.unmasked_payload(data))
+            .collect()
+    }
+
+    /// Returns the unmasked data of the message, if the message is fully revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript` - The verified transcript the message was parsed from.
+    pub fn verify(&self, transcript: &RedactedTranscript) -> Result<Vec<u8>, WebSocketError> {
+        if !self
+            .to_range_set()
+            .difference(transcript.authed())
+            .is_empty()
+        {
+            return Err(WebSocketError::Redacted);
+        }
+
+        Ok(self.data(transcript.data()))
+    }
+
+    /// Commits to the message, returning the commitments to its framing and to its payload.
+    ///
+    /// Revealing the commitment to the framing only discloses the type and length of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The commitment builder.
+    /// * `direction` - The direction of the message.
+    pub fn commit(
+        &self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+    ) -> Result<WebSocketCommitments, TranscriptCommitmentBuilderError> {
+        let framing = builder.commit(&self.framing(), direction)?;
+        let payload = if self.payload().is_empty() {
+            None
+        } else {
+            Some(builder.commit(&self.payload(), direction)?)
+        };
+
+        Ok(WebSocketCommitments { framing, payload })
+    }
+}
+
+impl ToRangeSet<usize> for WebSocketMessage {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.framing().union(&self.payload())
+    }
+}
+
+/// The commitments to a [`WebSocketMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebSocketCommitments {
+    /// The commitment to the headers of the frames.
+    pub framing: CommitmentId,
+    /// The commitment to the payloads of the frames, unless the message is empty.
+    pub payload: Option<CommitmentId>,
+}
+
+/// A WebSocket session, upgraded from HTTP/1.1.
+#[derive(Debug)]
+pub struct WebSocketTranscript {
+    /// The upgrade request.
+    pub request: Request,
+    /// The upgrade response.
+    pub response: Response,
+    /// The messages sent to the server.
+    pub sent: Vec<WebSocketMessage>,
+    /// The messages received from the server.
+    pub received: Vec<WebSocketMessage>,
+}
+
+impl WebSocketTranscript {
+    /// Parses the WebSocket session from the provided transcripts.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Result<Self, WebSocketError> {
+        Self::parse_inner(tx.data().clone(), rx.data().clone(), None)
+    }
+
+    /// Parses the WebSocket session from the verified transcripts.
+    ///
+    /// The upgrade handshake is parsed with its redacted bytes replaced, like a
+    /// [`RedactedHttpTranscript`](crate::http::RedactedHttpTranscript), and the headers of all the
+    /// frames must be revealed. The data of a message must be read with
+    /// [`WebSocketMessage::verify`], which checks that it is revealed.
+    pub fn parse_redacted(
+        sent: &RedactedTranscript,
+        recv: &RedactedTranscript,
+    ) -> Result<Self, WebSocketError> {
+        Self::parse_inner(fill(sent), fill(recv), Some((sent.authed(), recv.authed())))
+    }
+
+    fn parse_inner(
+        tx: bytes::Bytes,
+        rx: bytes::Bytes,
+        authed: Option<(&RangeSet<usize>, &RangeSet<usize>)>,
+    ) -> Result<Self, WebSocketError> {
+        let request = Requests::new(tx.clone())
+            .next()
+            .ok_or(WebSocketError::NotUpgraded)??;
+        let response = Responses::new(rx.clone())
+            .next()
+            .ok_or(WebSocketError::NotUpgraded)??;
+
+        let status = response.status.span().as_str().split(' ').nth(1);
+        let upgrade = response
+            .headers_with_name("upgrade")
+            .next()
+            .is_some_and(|header| header.value.as_bytes().eq_ignore_ascii_case(b"websocket"));
+        if status != Some("101") || !upgrade {
+            return Err(WebSocketError::NotUpgraded);
+        }
+
+        let sent = messages(parse_frames(
+            &tx,
+            handshake_end(&request.to_range_set(), request.body.as_ref()),
+            authed.map(|(sent, _)| sent),
+        )?)?;
+        let received = messages(parse_frames(
+            &rx,
+            handshake_end(&response.to_range_set(), response.body.as_ref()),
+            authed.map(|(_, recv)| recv),
+        )?)?;
+
+        Ok(Self {
+            request,
+            response,
+            sent,
+            received,
+        })
+    }
+}
+
+/// Returns the offset of the end of a message of the upgrade handshake.
+///
+/// The handshake messages have no body, so the frames start at the end of the headers, even if the
+/// parser took them for a body read until the connection is closed.
+fn handshake_end(message: &RangeSet<usize>, body: Option<&Body>) -> usize {
+    match body {
+        Some(body) => body
+            .to_range_set()
+            .iter_ranges()
+            .next()
+            .map(|range| range.start)
+            .unwrap_or_default(),
+        None => message
+            .iter_ranges()
+            .last()
+            .map(|range| range.end)
+            .unwrap_or_default(),
+    }
+}
+
+/// Assembles frames into messages.
+///
+/// Control frames can be interleaved with the frames of a fragmented message, and form messages
+/// of their own.
+fn messages(frames: Vec<Frame>) -> Result<Vec<WebSocketMessage>, WebSocketError> {
+    let mut messages = Vec::new();
+    let mut fragmented: Option<WebSocketMessage> = None;
+
+    for frame in frames {
+        if frame.opcode().is_control() {
+            messages.push(WebSocketMessage {
+                opcode: frame.opcode(),
+                frames: vec![frame],
+            });
+            continue;
+        }
+
+        let message = match (fragmented.take(), frame.opcode()) {
+            (Some(mut message), Opcode::Continuation) => {
+                message.frames.push(frame);
+                message
+            }
+            (None, Opcode::Continuation) => {
+                return Err(WebSocketError::UnexpectedContinuation {
+                    offset: frame.header().start,
+                })
+            }
+            (previous, opcode) => {
+                // A new message interrupts an unfinished one, which is kept as it is.
+                messages.extend(previous);
+                WebSocketMessage {
+                    opcode,
+                    frames: vec![frame],
+                }
+            }
+        };
+
+        if message.is_complete() {
+            messages.push(message);
+        } else {
+            fragmented = Some(message);
+        }
+    }
+
+    messages.extend(fragmented);
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{commitment::CommitmentKind, fixtures, proof::SubstringsProofBuilder};
+
+    static UPGRADE_REQUEST: &[u8] = b"GET /chat HTTP/1.1\r\nHost: localhost\r\n\
+    Upgrade: websocket\r\nConnection: Upgrade\r\n\r\n";
+    static UPGRADE_RESPONSE: &[u8] =
+        b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n";
+
+    fn frame(fin: bool, opcode: u8, mask: Option<[u8; 4]>, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![(fin as u8) << 7 | opcode, payload.len() as u8];
+        match mask {
+            Some(mask) => {
+                frame[1] |= 0x80;
+                frame.extend_from_slice(&mask);
+                frame.extend(unmask(payload, mask));
+            }
+            None => frame.extend_from_slice(payload),
+        }
+        frame
+    }
+
+    fn transcripts() -> (Vec<u8>, Vec<u8>) {
+        let mut tx = UPGRADE_REQUEST.to_vec();
+        tx.extend(frame(true, 0x1, Some([1, 2, 3, 4]), b"subscribe"));
+
+        let mut rx = UPGRADE_RESPONSE.to_vec();
+        rx.extend(frame(false, 0x1, None, b"price: "));
+        rx.extend(frame(true, 0x9, None, b""));
+        rx.extend(frame(true, 0x0, None, b"42"));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_websocket_transcript() {
+        let (tx, rx) = transcripts();
+        let transcript =
+            WebSocketTranscript::parse(&Transcript::new(tx.clone()), &Transcript::new(rx.clone()))
+                .unwrap();
+
+        assert_eq!(transcript.sent.len(), 1);
+        assert_eq!(transcript.sent[0].data(&tx), b"subscribe");
+
+        // The ping is interleaved with the fragments of the text message.
+        assert_eq!(transcript.received.len(), 2);
+        assert_eq!(transcript.received[0].opcode(), Opcode::Ping);
+        assert_eq!(transcript.received[1].opcode(), Opcode::Text);
+        assert_eq!(transcript.received[1].frames().len(), 2);
+        assert!(transcript.received[1].is_complete());
+        assert_eq!(transcript.received[1].data(&rx), b"price: 42");
+
+        assert!(matches!(
+            WebSocketTranscript::parse(
+                &Transcript::new(tx.clone()),
+                &Transcript::new(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".as_slice())
+            ),
+            Err(WebSocketError::NotUpgraded)
+        ));
+    }
+
+    #[test]
+    fn test_websocket_prove() {
+        let (tx, rx) = transcripts();
+        let (transcript_tx, transcript_rx) =
+            (Transcript::new(tx.clone()), Transcript::new(rx.clone()));
+        let transcript = WebSocketTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(&tx, &rx),
+            tx.len(),
+            rx.len(),
+        );
+        builder
+            .commit_sent(&transcript.request.to_range_set())
+            .unwrap();
+        builder
+            .commit_recv(&transcript.response.to_range_set())
+            .unwrap();
+        let sent = transcript.sent[0]
+            .commit(&mut builder, Direction::Sent)
+            .unwrap();
+        let mut received = Vec::new();
+        for message in &transcript.received {
+            received.push(message.commit(&mut builder, Direction::Received).unwrap());
+        }
+        let commitments = builder.build().unwrap();
+
+        // Reveal the handshake, the framing of all messages and only the payload of the price.
+        let mut proof_builder =
+            SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        proof_builder
+            .reveal_sent(&transcript.request, CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_recv(&transcript.response, CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_by_id(sent.framing)
+            .unwrap();
+        for commitments in &received {
+            proof_builder.reveal_by_id(commitments.framing).unwrap();
+        }
+        proof_builder
+            .reveal_by_id(received[1].payload.unwrap())
+            .unwrap();
+        let proof = proof_builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), tx.len(), rx.len());
+        let (sent_redacted, recv_redacted) = proof.verify(&header).unwrap();
+
+        let verified = WebSocketTranscript::parse_redacted(&sent_redacted, &recv_redacted).unwrap();
+        assert_eq!(
+            verified.received[1].verify(&recv_redacted).unwrap(),
+            b"price: 42"
+        );
+        assert!(matches!(
+            verified.sent[0].verify(&sent_redacted),
+            Err(WebSocketError::Redacted)
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Notarization of a single HTTPS request with a notary server.
+//!
+//! This library backs the `tlsn` command-line tool and the Python bindings: it sends a single
+//! request to the server, notarizes the session with the notary, and builds a proof revealing the
+//! request and the response, except for the parts matched by the redaction regexes.
+//!
+//! [`notarize`] runs the whole notarization for the common case of a single request, and
+//! [`notarize_session`] returns the notarized session to build proofs from later.
+
+pub mod redact;
+pub mod request;
+
+use std::net::{IpAddr, SocketAddr};
+
+use eyre::{eyre, Result, WrapErr};
+use http_body_util::BodyExt as _;
+use notary_client::{Accepted, NotarizationRequest, NotaryClient};
+use tlsn_core::{commitment::CommitmentKind, proof::TlsProof, Direction, NotarizedSession};
+use tlsn_prover::tls::{http1, state::Notarize, Prover, ProverConfig};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::debug;
+
+use crate::{
+    redact::Redactor,
+    request::{build_request, Target},
+};
+
+/// Connection settings of the notary server.
+#[derive(Clone, Debug)]
+pub struct NotaryOptions {
+    /// Host of the notary server.
+    pub host: String,
+    /// Port of the notary server.
+    pub port: u16,
+    /// Whether to connect to the notary server with TLS.
+    pub tls: bool,
+    /// API key of the notary server, if whitelisting is enabled.
+    pub api_key: Option<String>,
+}
+
+impl Default for NotaryOptions {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 7047,
+            tls: true,
+            api_key: None,
+        }
+    }
+}
+
+/// Options of a notarization.
+#[derive(Clone, Debug)]
+pub struct NotarizeOptions {
+    /// URL of the request.
+    pub url: String,
+    /// HTTP method of the request.
+    pub method: String,
+    /// Headers of the request.
+    pub headers: Vec<(String, String)>,
+    /// Body of the request.
+    pub body: Option<String>,
+    /// Regexes matching data of the request to redact from the proof.
+    pub redact_sent: Vec<String>,
+    /// Regexes matching data of the response to redact from the proof.
+    pub redact_recv: Vec<String>,
+    /// Notary server to notarize the session with.
+    pub notary: NotaryOptions,
+    /// Maximum number of bytes that can be sent.
+    pub max_sent_data: Option<usize>,
+    /// Maximum number of bytes that can be received.
+    pub max_recv_data: Option<usize>,
+    /// IP address to connect to instead of resolving the host of the URL. The certificate of the
+    /// server is still validated against the host.
+    pub server_addr: Option<IpAddr>,
+}
+
+impl NotarizeOptions {
+    /// Creates new options for a `GET` request to the provided URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            body: None,
+            redact_sent: Vec::new(),
+            redact_recv: Vec::new(),
+            notary: NotaryOptions::default(),
+            max_sent_data: None,
+            max_recv_data: None,
+            server_addr: None,
+        }
+    }
+}
+
+/// Notarizes the request, returning a proof of the session with the redactions applied.
+pub async fn notarize(options: NotarizeOptions) -> Result<TlsProof> {
+    let session = notarize_session(options.clone()).await?;
+
+    build_proof(&session, &options)
+}
+
+/// Notarizes the request, returning the notarized session.
+///
+/// This requests a session from the notary, sets up the prover, sends the request to the server,
+/// commits to the parts of the transcripts which are not redacted and finalizes the notarization.
+/// The proof can then be built with [`build_proof`], or by hand from the commitments of the
+/// session.
+pub async fn notarize_session(options: NotarizeOptions) -> Result<NotarizedSession> {
+    // Validate all the inputs before connecting to the notary.
+    let target = Target::parse(&options.url)?;
+    let request = build_request(
+        &options.method,
+        &target,
+        &options.headers,
+        options.body.as_deref(),
+    )?;
+    let sent_redactor = Redactor::new(&options.redact_sent)?;
+    let recv_redactor = Redactor::new(&options.redact_recv)?;
+
+    let mut notary_client = NotaryClient::builder();
+    notary_client
+        .host(options.notary.host.clone())
+        .port(options.notary.port)
+        .enable_tls(options.notary.tls);
+    if let Some(api_key) = &options.notary.api_key {
+        notary_client.api_key(api_key.clone());
+    }
+    let notary_client = notary_client.build()?;
+
+    let mut notarization_request = NotarizationRequest::builder();
+    notarization_request.server_dns(target.host.clone());
+    let mut prover_config = ProverConfig::builder();
+    prover_config.server_dns(target.host.clone());
+    if let Some(server_addr) = options.server_addr {
+        prover_config.server_addr(server_addr);
+    }
+    if let Some(max_sent_data) = options.max_sent_data {
+        notarization_request.max_sent_data(max_sent_data);
+        prover_config.max_sent_data(max_sent_data);
+    }
+    if let Some(max_recv_data) = options.max_recv_data {
+        notarization_request.max_recv_data(max_recv_data);
+        prover_config.max_recv_data(max_recv_data);
+    }
+
+    let Accepted {
+        io: notary_connection,
+        id: session_id,
+        ..
+    } = notary_client
+        .request_notarization(notarization_request.build()?)
+        .await
+        .wrap_err("Failed to request notarization")?;
+
+    debug!(%session_id, "Notarization accepted");
+
+    let prover_config = prover_config.id(session_id).build()?;
+    let server_addrs = prover_config
+        .resolve_server()
+        .await
+        .wrap_err_with(|| format!("Failed to resolve {}", target.host))?;
+    let prover = Prover::new(prover_config)
+        .setup(notary_connection.compat())
+        .await
+        .wrap_err("Failed to set up the prover with the notary")?;
+
+    let client_socket = match server_addrs {
+        Some(addrs) => {
+            let addrs = addrs
+                .into_iter()
+                .map(|addr| SocketAddr::new(addr, target.port))
+                .collect::<Vec<_>>();
+            tokio::net::TcpStream::connect(addrs.as_slice()).await
+        }
+        None => tokio::net::TcpStream::connect((target.host.as_str(), target.port)).await,
+    }
+    .wrap_err_with(|| format!("Failed to connect to {}:{}", target.host, target.port))?;
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await?;
+    let prover_ctrl = prover_fut.control();
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, connection) = http1::handshake(tls_connection).await?;
+    tokio::spawn(connection);
+
+    // The response is only decrypted once the connection is closed, which speeds up the session.
+    prover_ctrl.defer_decryption().await?;
+
+    let response = request_sender
+        .send_request(request)
+        .await
+        .wrap_err("Failed to send the request")?;
+    let status = response.status();
+    response.into_body().collect().await?;
+
+    debug!(%status, "Received response");
+
+    let prover = prover_task.await??.start_notarize();
+
+    finalize(prover, &sent_redactor, &recv_redactor).await
+}
+
+/// Commits to the public parts of the transcript and finalizes the notarization.
+async fn finalize(
+    mut prover: Prover<Notarize>,
+    sent_redactor: &Redactor,
+    recv_redactor: &Redactor,
+) -> Result<NotarizedSession> {
+    let sent_public = sent_redactor.public_ranges(prover.sent_transcript().data());
+    let recv_public = recv_redactor.public_ranges(prover.recv_transcript().data());
+
+    let builder = prover.commitment_builder();
+    if !sent_public.is_empty() {
+        builder.commit_sent(&sent_public)?;
+    }
+    if !recv_public.is_empty() {
+        builder.commit_recv(&recv_public)?;
+    }
+
+    Ok(prover.finalize().await?)
+}
+
+/// Builds a proof of a session notarized with [`notarize_session`], revealing the parts of the
+/// transcripts which are not redacted by the options.
+pub fn build_proof(
+    notarized_session: &NotarizedSession,
+    options: &NotarizeOptions,
+) -> Result<TlsProof> {
+    let data = notarized_session.data();
+    let sent_public =
+        Redactor::new(&options.redact_sent)?.public_ranges(data.sent_transcript().data());
+    let recv_public =
+        Redactor::new(&options.redact_recv)?.public_ranges(data.recv_transcript().data());
+
+    let mut proof_builder = data.build_substrings_proof();
+    for (ranges, direction) in [
+        (sent_public, Direction::Sent),
+        (recv_public, Direction::Received),
+    ] {
+        if ranges.is_empty() {
+            continue;
+        }
+        let commitment = data
+            .commitments()
+            .get_id_by_info(CommitmentKind::Blake3, &ranges, direction)
+            .ok_or_else(|| eyre!("The session has no commitment to the {direction:?} data"))?;
+        proof_builder.reveal_by_id(commitment)?;
+    }
+    let substrings = proof_builder
+        .build()
+        .map_err(|err| eyre!("Failed to build the proof: {err}"))?;
+
+    Ok(TlsProof {
+        session: notarized_session.session_proof(),
+        substrings,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tlsn_core::dry_run::{dry_run_notary_public_key, DryRun};
+
+    const SENT: &[u8] = b"GET / HTTP/1.1\r\nauthorization: Bearer abc\r\n\r\n";
+    const RECV: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n{\"email\":\"alice@example.com\"}";
+
+    #[test]
+    fn test_build_proof_redacts() {
+        let mut options = NotarizeOptions::new("https://tlsnotary.org");
+        options.redact_sent = vec!["authorization: Bearer (\\S+)".to_string()];
+        options.redact_recv = vec!["\"email\":\"([^\"]*)\"".to_string()];
+
+        // Commit like `finalize` does.
+        let mut dry_run = DryRun::new(SENT, RECV);
+        let sent_public = Redactor::new(&options.redact_sent)
+            .unwrap()
+            .public_ranges(SENT);
+        let recv_public = Redactor::new(&options.redact_recv)
+            .unwrap()
+            .public_ranges(RECV);
+        dry_run
+            .commitment_builder()
+            .commit_sent(&sent_public)
+            .unwrap();
+        dry_run
+            .commitment_builder()
+            .commit_recv(&recv_public)
+            .unwrap();
+        let session = dry_run.finalize().unwrap();
+
+        let proof = build_proof(&session, &options).unwrap();
+        proof
+            .session
+            .verify_with_default_cert_verifier(dry_run_notary_public_key())
+            .unwrap();
+        let (mut sent, mut recv) = proof.substrings.verify(&proof.session.header).unwrap();
+        sent.set_redacted(b'X');
+        recv.set_redacted(b'X');
+
+        assert_eq!(
+            sent.data(),
+            b"GET / HTTP/1.1\r\nauthorization: Bearer XXX\r\n\r\n"
+        );
+        assert_eq!(
+            recv.data(),
+            b"HTTP/1.1 200 OK\r\n\r\n{\"email\":\"XXXXXXXXXXXXXXXXX\"}"
+        );
+    }
+
+    #[test]
+    fn test_build_proof_requires_commitments() {
+        let mut options = NotarizeOptions::new("https://tlsnotary.org");
+        options.redact_recv = vec!["email".to_string()];
+
+        // The session commits to the whole response, not to the parts left by the redaction.
+        let mut dry_run = DryRun::new(SENT, RECV);
+        dry_run
+            .commitment_builder()
+            .commit_sent(&(0..SENT.len()))
+            .unwrap();
+        dry_run
+            .commitment_builder()
+            .commit_recv(&(0..RECV.len()))
+            .unwrap();
+        let session = dry_run.finalize().unwrap();
+
+        assert!(build_proof(&session, &options).is_err());
+    }
+}
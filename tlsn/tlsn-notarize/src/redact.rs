@@ -0,0 +1,88 @@
+use eyre::{Result, WrapErr};
+use regex::bytes::Regex;
+use utils::range::{RangeDifference, RangeSet};
+
+/// Redaction of the parts of a transcript matched by regexes.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Creates a new redactor from the provided patterns.
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).wrap_err_with(|| format!("Invalid redaction regex {pattern:?}"))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// Returns the ranges of the data to redact.
+    ///
+    /// If a pattern has capture groups, only the groups are redacted, otherwise the whole match is.
+    pub fn private_ranges(&self, data: &[u8]) -> RangeSet<usize> {
+        let mut ranges = Vec::new();
+        for pattern in &self.patterns {
+            for captures in pattern.captures_iter(data) {
+                if captures.len() == 1 {
+                    let m = captures.get(0).expect("match is always present");
+                    ranges.push(m.range());
+                } else {
+                    ranges.extend(captures.iter().skip(1).flatten().map(|m| m.range()));
+                }
+            }
+        }
+
+        ranges.retain(|range| !range.is_empty());
+
+        RangeSet::from(ranges)
+    }
+
+    /// Returns the ranges of the data to reveal.
+    pub fn public_ranges(&self, data: &[u8]) -> RangeSet<usize> {
+        (0..data.len()).difference(&self.private_ranges(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redact_match() {
+        let redactor = Redactor::new(&["secret".to_string()]).unwrap();
+        let data = b"a secret and another secret";
+
+        assert_eq!(
+            redactor.private_ranges(data),
+            RangeSet::from([2..8, 21..27])
+        );
+        assert_eq!(redactor.public_ranges(data), RangeSet::from([0..2, 8..21]));
+    }
+
+    #[test]
+    fn test_redact_capture_groups() {
+        let redactor = Redactor::new(&[
+            "authorization: Bearer (\\S+)".to_string(),
+            "\"token\":\"([^\"]*)\"".to_string(),
+        ])
+        .unwrap();
+        let data = b"authorization: Bearer abc\r\n{\"token\":\"xyz\"}";
+
+        assert_eq!(
+            redactor.private_ranges(data),
+            RangeSet::from([22..25, 37..40])
+        );
+    }
+
+    #[test]
+    fn test_redact_nothing() {
+        let redactor = Redactor::new(&[]).unwrap();
+        assert_eq!(redactor.public_ranges(b"data"), RangeSet::from(0..4));
+        assert!(Redactor::new(&["(".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use eyre::{eyre, Result, WrapErr};
+use http_body_util::Full;
+use hyper::{body::Bytes, Request, Uri};
+
+/// Headers which are always set by the tool.
+const MANAGED_HEADERS: &[&str] = &["host", "connection", "accept-encoding"];
+
+/// The server targeted by the request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    /// DNS name of the server.
+    pub host: String,
+    /// Port of the server.
+    pub port: u16,
+    /// Path and query of the request.
+    pub path: String,
+}
+
+impl Target {
+    /// Parses the target from an `https` URL.
+    pub fn parse(url: &str) -> Result<Self> {
+        let uri: Uri = url
+            .parse()
+            .wrap_err_with(|| format!("Invalid URL {url:?}"))?;
+
+        if uri.scheme_str() != Some("https") {
+            return Err(eyre!("Only https URLs are supported, got {url:?}"));
+        }
+
+        let host = uri
+            .host()
+            .ok_or_else(|| eyre!("URL {url:?} has no host"))?
+            .to_string();
+        let path = uri
+            .path_and_query()
+            .map(|path| path.as_str())
+            .filter(|path| !path.is_empty())
+            .unwrap_or("/")
+            .to_string();
+
+        Ok(Self {
+            host,
+            port: uri.port_u16().unwrap_or(443),
+            path,
+        })
+    }
+
+    /// Returns the value of the `Host` header, which includes the port unless it is the default
+    /// https port.
+    pub fn authority(&self) -> String {
+        if self.port == 443 {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Parses a header formatted as `Name: value`.
+pub fn parse_header(header: &str) -> Result<(String, String)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| eyre!("Header {header:?} is not formatted as \"Name: value\""))?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(eyre!("Header {header:?} has no name"));
+    }
+
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Loads headers from a file, one `Name: value` per line.
+///
+/// Empty lines and lines starting with `#` are ignored.
+pub fn load_headers_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let file = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Failed to read headers file {}", path.display()))?;
+
+    file.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_header)
+        .collect()
+}
+
+/// Parses a header formatted as `Name=VARIABLE`, whose value is read from the environment.
+pub fn parse_header_env(header_env: &str) -> Result<(String, String)> {
+    let (name, var) = header_env
+        .split_once('=')
+        .ok_or_else(|| eyre!("Header {header_env:?} is not formatted as \"Name=VARIABLE\""))?;
+    let value = std::env::var(var)
+        .wrap_err_with(|| format!("Failed to read environment variable {var}"))?;
+
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// Builds the request to send to the server.
+///
+/// The `Host` header is set from the target, the connection is closed after the response as the
+/// prover only supports a single request, and compression is disabled as compressed responses can
+/// not be redacted.
+pub fn build_request(
+    method: &str,
+    target: &Target,
+    headers: &[(String, String)],
+    body: Option<&str>,
+) -> Result<Request<Full<Bytes>>> {
+    let mut builder = Request::builder()
+        .method(method.to_ascii_uppercase().as_str())
+        .uri(target.path.as_str())
+        .header("Host", target.authority())
+        .header("Connection", "close")
+        .header("Accept-Encoding", "identity");
+
+    for (name, value) in headers {
+        if MANAGED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            return Err(eyre!(
+                "Header {name:?} is set by the tool and can not be provided"
+            ));
+        }
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    let body = body
+        .map(|body| Bytes::copy_from_slice(body.as_bytes()))
+        .unwrap_or_default();
+
+    builder
+        .body(Full::new(body))
+        .wrap_err("Failed to build the request")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            Target::parse("https://example.com:8443/api?id=1").unwrap(),
+            Target {
+                host: "example.com".to_string(),
+                port: 8443,
+                path: "/api?id=1".to_string(),
+            }
+        );
+        assert_eq!(Target::parse("https://example.com").unwrap().path, "/");
+        assert!(Target::parse("http://example.com").is_err());
+        assert!(Target::parse("/api").is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization:  Bearer abc ").unwrap(),
+            ("Authorization".to_string(), "Bearer abc".to_string())
+        );
+        assert_eq!(
+            parse_header("Cookie: a=b; c=d:e").unwrap(),
+            ("Cookie".to_string(), "a=b; c=d:e".to_string())
+        );
+        assert!(parse_header("Authorization").is_err());
+        assert!(parse_header(": value").is_err());
+    }
+
+    #[test]
+    fn test_build_request_managed_headers() {
+        let target = Target::parse("https://example.com").unwrap();
+
+        let request = build_request("get", &target, &[], None).unwrap();
+        assert_eq!(request.method(), "GET");
+        assert_eq!(request.headers()["host"], "example.com");
+        assert_eq!(request.headers()["connection"], "close");
+
+        assert!(build_request(
+            "GET",
+            &target,
+            &[("Connection".to_string(), "keep-alive".to_string())],
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_build_request_host_port() {
+        let target = Target::parse("https://example.com:8443/api").unwrap();
+        let request = build_request("GET", &target, &[], None).unwrap();
+        assert_eq!(request.headers()["host"], "example.com:8443");
+
+        let target = Target::parse("https://example.com:443/api").unwrap();
+        let request = build_request("GET", &target, &[], None).unwrap();
+        assert_eq!(request.headers()["host"], "example.com");
+    }
+}
@@ -8,8 +8,9 @@
 
 pub mod state;
 
+use tlsn_core::commitment::CommitmentId;
 use tlsn_formats::{
-    http::{DefaultHttpCommitter, HttpCommit, HttpCommitError, HttpTranscript},
+    http::{DefaultHttpCommitter, HttpCommit, HttpCommitError, HttpLocation, HttpTranscript},
     ParseError,
 };
 
@@ -74,6 +75,18 @@ impl HttpProver<state::Notarize> {
         )
     }
 
+    /// Commits to a part of a request or response, e.g. the body of the response of the second
+    /// exchange of the session.
+    pub fn commit_location(
+        &mut self,
+        location: &HttpLocation,
+    ) -> Result<CommitmentId, HttpCommitError> {
+        location.commit(
+            self.state.prover.commitment_builder(),
+            &self.state.transcript,
+        )
+    }
+
     /// Finalizes the HTTP session.
     pub async fn finalize(self) -> Result<NotarizedHttpSession, HttpProverError> {
         Ok(NotarizedHttpSession::new(
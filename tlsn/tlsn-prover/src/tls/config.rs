@@ -1,13 +1,35 @@
+use std::{io, net::IpAddr, sync::Arc};
+
 use mpz_ot::{chou_orlandi, kos};
-use tls_client::RootCertStore;
+use tls_client::{
+    RootCertStore, SupportedCipherSuite, SupportedKxGroup, SupportedProtocolVersion, ALL_KX_GROUPS,
+};
+use tls_core::{
+    msgs::fragmenter::{MAX_FRAGMENT_LEN, PACKET_OVERHEAD},
+    suites::{ALL_CIPHER_SUITES, DEFAULT_CIPHER_SUITES},
+    versions::{ALL_VERSIONS, DEFAULT_VERSIONS},
+};
 use tls_mpc::{MpcTlsCommonConfig, MpcTlsLeaderConfig, TranscriptConfig};
+#[cfg(feature = "seeded-rng")]
+use tlsn_common::rng::{derive_seed, MPC_TLS_RNG_STREAM};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
-    Role,
+    concurrency::MAX_MPC_CONCURRENCY,
+    config::{DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT, DEFAULT_MPC_CONCURRENCY},
+    mux::MuxConfig,
+    setup::SessionParams,
 };
+use tlsn_core::{commitment::CommitmentFilter, session::SessionBinding};
+
+use crate::tls::resolve::{ServerResolver, StaticResolver};
+
+/// Minimum size of the plaintext of a TLS record, see [`ProverConfig::max_record_size`].
+pub const MIN_RECORD_SIZE: usize = 32 - PACKET_OVERHEAD;
+/// Maximum size of the plaintext of a TLS record, see [`ProverConfig::max_record_size`].
+pub const MAX_RECORD_SIZE: usize = MAX_FRAGMENT_LEN;
 
 /// Configuration for the prover
 #[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
 pub struct ProverConfig {
     /// Id of the notarization session.
     #[builder(setter(into))]
@@ -15,6 +37,11 @@ pub struct ProverConfig {
     /// The server DNS name.
     #[builder(setter(into))]
     server_dns: String,
+    /// Resolver of the IP addresses of the server, used instead of the system resolver.
+    ///
+    /// The certificate of the server is still validated against `server_dns`.
+    #[builder(setter(custom), default)]
+    resolver: Option<Arc<dyn ServerResolver>>,
     /// TLS root certificate store.
     #[builder(setter(strip_option), default = "default_root_store()")]
     pub(crate) root_cert_store: RootCertStore,
@@ -24,6 +51,188 @@ pub struct ProverConfig {
     /// Maximum number of bytes that can be received.
     #[builder(default = "DEFAULT_MAX_RECV_LIMIT")]
     max_recv_data: usize,
+    /// Whether to capture the OCSP response stapled by the server and its Signed Certificate
+    /// Timestamps in the handshake, so the Verifier can check that the server certificate was not
+    /// revoked and was logged for Certificate Transparency.
+    #[builder(default = "true")]
+    certificate_status: bool,
+    /// Cipher suites offered to the server, in order of preference.
+    ///
+    /// Only the suites in [`ALL_CIPHER_SUITES`] are supported by MPC-TLS.
+    #[builder(setter(into), default = "DEFAULT_CIPHER_SUITES.to_vec()")]
+    cipher_suites: Vec<SupportedCipherSuite>,
+    /// Key exchange groups offered to the server, in order of preference.
+    ///
+    /// Only the groups in [`ALL_KX_GROUPS`] are supported by MPC-TLS, which excludes secp384r1
+    /// and X25519, so servers which only accept these groups cannot be notarized.
+    #[builder(setter(into), default = "ALL_KX_GROUPS.to_vec()")]
+    kx_groups: Vec<&'static SupportedKxGroup>,
+    /// TLS protocol versions offered to the server.
+    ///
+    /// Only the versions in [`ALL_VERSIONS`] are supported by MPC-TLS.
+    #[builder(setter(into), default = "DEFAULT_VERSIONS.to_vec()")]
+    protocol_versions: Vec<&'static SupportedProtocolVersion>,
+    /// Whether to send the Server Name Indication extension.
+    #[builder(default = "true")]
+    enable_sni: bool,
+    /// Protocols offered to the server with the ALPN extension, in order of preference.
+    ///
+    /// No ALPN extension is sent if empty.
+    #[builder(setter(into), default)]
+    alpn_protocols: Vec<Vec<u8>>,
+    /// Maximum size of the plaintext of the TLS records sent, in bytes, between
+    /// [`MIN_RECORD_SIZE`] and [`MAX_RECORD_SIZE`].
+    ///
+    /// Larger writes are split into records of this size. Each record costs the AES blocks of its
+    /// data plus the blocks of its tag in MPC, and the sizes of the records are visible on the
+    /// wire. If `None`, records are only split at [`MAX_RECORD_SIZE`].
+    ///
+    /// TLS 1.2 has no record padding, so records can not be padded to a fixed size: the last
+    /// record of a write still reveals the length of the write modulo the record size.
+    #[builder(setter(strip_option), default)]
+    max_record_size: Option<usize>,
+    /// Whether to compress the connection to the Verifier, if the Verifier supports it.
+    #[builder(default)]
+    compression: bool,
+    /// Configuration of the multiplexer of the connection to the Verifier.
+    #[builder(default)]
+    mux_config: MuxConfig,
+    /// Maximum number of threads the MPC executor forks to run circuits and OT batches
+    /// simultaneously.
+    ///
+    /// Lower values reduce the peak memory of the session at the cost of speed. The lowest of the
+    /// concurrencies of the Prover and the Verifier is used.
+    #[builder(default = "DEFAULT_MPC_CONCURRENCY")]
+    mpc_concurrency: usize,
+    /// Maximum number of TLS records the Prover expects to send and receive in the session.
+    ///
+    /// The pool of oblivious transfers is preprocessed during setup for the lowest of the limits
+    /// of the Prover and the Verifier, and for at least
+    /// [`DEFAULT_RECORD_BUDGET`](tlsn_common::ot_pool::DEFAULT_RECORD_BUDGET) records, so only a
+    /// session which exchanges more records needs to set a limit. If the Verifier does not set a
+    /// limit, it allows at most that many records.
+    #[builder(setter(into, strip_option), default)]
+    max_records: Option<usize>,
+    /// Binding of the session to the relying party which requested it, which the Notary signs
+    /// in the session header so the proof cannot be replayed to another relying party.
+    #[builder(setter(strip_option), default)]
+    binding: Option<SessionBinding>,
+    /// Whether to commit to the times of the TLS records. The Notary signs its own observation of
+    /// the times in the session header, which then replaces the times of the transcript records.
+    #[builder(default)]
+    commit_record_timings: bool,
+    /// Whether to commit to the order in which the TLS records were sent and received, which the
+    /// Notary checks against its own observation and signs in the session header.
+    ///
+    /// The Notary only observes the order in which the records are forwarded into the MPC, see
+    /// [`RecordOrder`](tlsn_core::session::RecordOrder).
+    #[builder(default)]
+    commit_record_order: bool,
+    /// Filter of secrets, e.g. tokens or passwords, which must never be committed to publicly.
+    ///
+    /// Commitments overlapping a secret are rejected, as a safety net against redaction bugs.
+    /// Secrets are matched as literal bytes only, see [`CommitmentFilter`].
+    #[builder(setter(strip_option), default)]
+    commitment_filter: Option<CommitmentFilter>,
+    /// Seed of the randomness of the session, only settable with the `seeded-rng` feature.
+    #[builder(setter(custom), default)]
+    rng_seed: Option<[u8; 32]>,
+}
+
+impl ProverConfigBuilder {
+    /// Sets the resolver of the IP addresses of the server, used instead of the system resolver.
+    pub fn resolver(&mut self, resolver: impl ServerResolver + 'static) -> &mut Self {
+        self.resolver = Some(Some(Arc::new(resolver)));
+        self
+    }
+
+    /// Sets a fixed IP address of the server, which is connected to instead of resolving
+    /// `server_dns`.
+    pub fn server_addr(&mut self, addr: IpAddr) -> &mut Self {
+        self.resolver(StaticResolver::new(vec![addr]))
+    }
+
+    /// Seeds the randomness of the session, so that protocol runs are reproducible.
+    ///
+    /// This is insecure and must only be used in tests.
+    #[cfg(feature = "seeded-rng")]
+    pub fn rng_seed(&mut self, seed: [u8; 32]) -> &mut Self {
+        self.rng_seed = Some(Some(seed));
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(mpc_concurrency) = self.mpc_concurrency {
+            if !(1..=MAX_MPC_CONCURRENCY).contains(&mpc_concurrency) {
+                return Err(format!(
+                    "mpc concurrency must be between 1 and {MAX_MPC_CONCURRENCY}"
+                ));
+            }
+        }
+
+        if let Some(Some(0)) = self.max_records {
+            return Err("max records must be at least 1".to_string());
+        }
+
+        if let Some(Some(max_record_size)) = self.max_record_size {
+            if !(MIN_RECORD_SIZE..=MAX_RECORD_SIZE).contains(&max_record_size) {
+                return Err(format!(
+                    "max record size must be between {MIN_RECORD_SIZE} and {MAX_RECORD_SIZE}"
+                ));
+            }
+        }
+
+        if let Some(cipher_suites) = &self.cipher_suites {
+            if cipher_suites.is_empty() {
+                return Err("at least one cipher suite must be offered".to_string());
+            }
+
+            if let Some(suite) = cipher_suites
+                .iter()
+                .find(|suite| !ALL_CIPHER_SUITES.contains(*suite))
+            {
+                return Err(format!(
+                    "cipher suite {:?} is not supported by MPC-TLS",
+                    suite.suite()
+                ));
+            }
+        }
+
+        if let Some(kx_groups) = &self.kx_groups {
+            if kx_groups.is_empty() {
+                return Err("at least one key exchange group must be offered".to_string());
+            }
+
+            if let Some(group) = kx_groups
+                .iter()
+                .find(|group| !ALL_KX_GROUPS.iter().any(|g| g.name == group.name))
+            {
+                return Err(format!(
+                    "key exchange group {:?} is not supported by MPC-TLS, which only supports {:?}",
+                    group.name,
+                    ALL_KX_GROUPS.map(|group| group.name)
+                ));
+            }
+        }
+
+        if let Some(protocol_versions) = &self.protocol_versions {
+            if protocol_versions.is_empty() {
+                return Err("at least one protocol version must be offered".to_string());
+            }
+
+            if let Some(version) = protocol_versions
+                .iter()
+                .find(|version| !ALL_VERSIONS.contains(*version))
+            {
+                return Err(format!(
+                    "protocol version {:?} is not supported by MPC-TLS",
+                    version.version
+                ));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl ProverConfig {
@@ -32,6 +241,16 @@ impl ProverConfig {
         ProverConfigBuilder::default()
     }
 
+    /// Returns the id of the notarization session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Sets the id of the notarization session, when the setup is retried in a new session.
+    pub(crate) fn set_id(&mut self, id: &str) {
+        self.id = id.to_string();
+    }
+
     /// Returns the maximum number of bytes that can be sent.
     pub fn max_sent_data(&self) -> usize {
         self.max_sent_data
@@ -47,27 +266,127 @@ impl ProverConfig {
         &self.server_dns
     }
 
-    pub(crate) fn build_mpc_tls_config(&self) -> MpcTlsLeaderConfig {
-        MpcTlsLeaderConfig::builder()
-            .common(
-                MpcTlsCommonConfig::builder()
-                    .id(format!("{}/mpc_tls", &self.id))
-                    .tx_config(
-                        TranscriptConfig::default_tx()
-                            .max_size(self.max_sent_data)
-                            .build()
-                            .unwrap(),
-                    )
-                    .rx_config(
-                        TranscriptConfig::default_rx()
-                            .max_size(self.max_recv_data)
-                            .build()
-                            .unwrap(),
-                    )
-                    .handshake_commit(true)
+    /// Returns the resolver of the IP addresses of the server, if any.
+    pub fn resolver(&self) -> Option<&Arc<dyn ServerResolver>> {
+        self.resolver.as_ref()
+    }
+
+    /// Resolves the IP addresses of the server with the configured resolver.
+    ///
+    /// Returns `None` if no resolver is configured, in which case the server should be connected
+    /// to by its DNS name with the system resolver.
+    pub async fn resolve_server(&self) -> io::Result<Option<Vec<IpAddr>>> {
+        match &self.resolver {
+            Some(resolver) => resolver.resolve(&self.server_dns).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns whether the certificate status of the server is captured in the handshake.
+    pub fn certificate_status(&self) -> bool {
+        self.certificate_status
+    }
+
+    /// Returns the cipher suites offered to the server.
+    pub fn cipher_suites(&self) -> &[SupportedCipherSuite] {
+        &self.cipher_suites
+    }
+
+    /// Returns the key exchange groups offered to the server.
+    pub fn kx_groups(&self) -> &[&'static SupportedKxGroup] {
+        &self.kx_groups
+    }
+
+    /// Returns the TLS protocol versions offered to the server.
+    pub fn protocol_versions(&self) -> &[&'static SupportedProtocolVersion] {
+        &self.protocol_versions
+    }
+
+    /// Returns whether the Server Name Indication extension is sent.
+    pub fn enable_sni(&self) -> bool {
+        self.enable_sni
+    }
+
+    /// Returns the protocols offered to the server with the ALPN extension.
+    pub fn alpn_protocols(&self) -> &[Vec<u8>] {
+        &self.alpn_protocols
+    }
+
+    /// Returns the maximum size of the plaintext of the TLS records sent, if any.
+    pub fn max_record_size(&self) -> Option<usize> {
+        self.max_record_size
+    }
+
+    /// Returns whether the connection to the Verifier is compressed if the Verifier supports it.
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
+    /// Returns the configuration of the multiplexer.
+    pub fn mux_config(&self) -> &MuxConfig {
+        &self.mux_config
+    }
+
+    /// Returns the maximum number of threads the MPC executor forks.
+    pub fn mpc_concurrency(&self) -> usize {
+        self.mpc_concurrency
+    }
+
+    /// Returns the maximum number of TLS records the Prover expects, if any.
+    pub fn max_records(&self) -> Option<usize> {
+        self.max_records
+    }
+
+    /// Returns the binding of the session to the relying party which requested it, if any.
+    pub fn binding(&self) -> Option<&SessionBinding> {
+        self.binding.as_ref()
+    }
+
+    /// Returns whether to commit to the times of the TLS records.
+    pub fn commit_record_timings(&self) -> bool {
+        self.commit_record_timings
+    }
+
+    /// Returns whether to commit to the order of the TLS records.
+    pub fn commit_record_order(&self) -> bool {
+        self.commit_record_order
+    }
+
+    /// Returns the filter of secrets which must never be committed to publicly, if any.
+    pub fn commitment_filter(&self) -> Option<&CommitmentFilter> {
+        self.commitment_filter.as_ref()
+    }
+
+    /// Returns the seed of the randomness of the session, if any.
+    pub(crate) fn rng_seed(&self) -> Option<&[u8; 32]> {
+        self.rng_seed.as_ref()
+    }
+
+    /// Builds the MPC-TLS config for the parameters negotiated with the Verifier.
+    pub(crate) fn build_mpc_tls_config(&self, params: &SessionParams) -> MpcTlsLeaderConfig {
+        let mut common = MpcTlsCommonConfig::builder();
+        common
+            .id(format!("{}/mpc_tls", &self.id))
+            .tx_config(
+                TranscriptConfig::default_tx()
+                    .max_size(params.max_sent_data)
+                    .build()
+                    .unwrap(),
+            )
+            .rx_config(
+                TranscriptConfig::default_rx()
+                    .max_size(params.max_recv_data)
                     .build()
                     .unwrap(),
             )
+            .handshake_commit(true);
+        #[cfg(feature = "seeded-rng")]
+        if let Some(seed) = &self.rng_seed {
+            common.rng_seed(derive_seed(Some(seed), MPC_TLS_RNG_STREAM));
+        }
+
+        MpcTlsLeaderConfig::builder()
+            .common(common.build().unwrap())
             .build()
             .unwrap()
     }
@@ -93,14 +412,6 @@ impl ProverConfig {
             .build()
             .unwrap()
     }
-
-    pub(crate) fn ot_sender_setup_count(&self) -> usize {
-        ot_send_estimate(Role::Prover, self.max_sent_data, self.max_recv_data)
-    }
-
-    pub(crate) fn ot_receiver_setup_count(&self) -> usize {
-        ot_recv_estimate(Role::Prover, self.max_sent_data, self.max_recv_data)
-    }
 }
 
 /// Default root store using mozilla certs.
@@ -116,3 +427,31 @@ fn default_root_store() -> RootCertStore {
 
     root_store
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tls_client::kx_group::{SECP256R1, SECP384R1, X25519};
+
+    fn builder() -> ProverConfigBuilder {
+        let mut builder = ProverConfig::builder();
+        builder.id("test").server_dns("example.com");
+        builder
+    }
+
+    #[test]
+    fn test_kx_groups() {
+        let config = builder().kx_groups(vec![&SECP256R1]).build().unwrap();
+        assert_eq!(config.kx_groups().len(), 1);
+
+        assert!(builder()
+            .kx_groups(Vec::<&SupportedKxGroup>::new())
+            .build()
+            .is_err());
+        assert!(builder()
+            .kx_groups(vec![&SECP384R1, &SECP256R1])
+            .build()
+            .is_err());
+        assert!(builder().kx_groups(vec![&X25519]).build().is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Connector to use the TLS connection of the prover with hyper client stacks.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::future::{ready, Ready};
+use hyper::{
+    rt::{Read, ReadBufCursor, Write},
+    Uri,
+};
+use hyper_util::{
+    client::legacy::connect::{Connected, Connection},
+    rt::TokioIo,
+};
+use tls_client_async::TlsConnection;
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt};
+use tower_service::Service;
+
+/// An error for [`ProverConnector`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConnectorError {
+    /// The requested host is not the server the prover is connected to.
+    #[error("prover is connected to {expected}, not {actual:?}")]
+    HostMismatch {
+        /// The server name of the prover.
+        expected: String,
+        /// The requested host.
+        actual: Option<String>,
+    },
+    /// The connection was already handed out.
+    #[error("the prover connection was already used")]
+    ConnectionUsed,
+}
+
+/// A connector which hands out the TLS connection of the prover, implementing
+/// [`tower_service::Service`] so it can be used with hyper client stacks, e.g.
+/// `hyper_util::client::legacy::Client`.
+///
+/// The prover has a single connection to the server, so the connector can only be called once.
+/// Clients should send a single request, with the `Connection: close` header, as the connection
+/// is not reused by the pool.
+#[derive(Debug, Clone)]
+pub struct ProverConnector {
+    server_dns: String,
+    conn: Arc<Mutex<Option<TlsConnection>>>,
+}
+
+impl ProverConnector {
+    /// Creates a new connector.
+    ///
+    /// # Arguments
+    ///
+    /// * `conn` - The TLS connection returned by [`Prover::connect`](crate::tls::Prover::connect).
+    /// * `server_dns` - The server name the prover is connected to.
+    pub fn new(conn: TlsConnection, server_dns: impl Into<String>) -> Self {
+        Self {
+            server_dns: server_dns.into(),
+            conn: Arc::new(Mutex::new(Some(conn))),
+        }
+    }
+
+    fn connect(&self, uri: &Uri) -> Result<ProverStream, ConnectorError> {
+        if uri.host() != Some(self.server_dns.as_str()) {
+            return Err(ConnectorError::HostMismatch {
+                expected: self.server_dns.clone(),
+                actual: uri.host().map(str::to_string),
+            });
+        }
+
+        let conn = self
+            .conn
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or(ConnectorError::ConnectionUsed)?;
+
+        Ok(ProverStream::new(conn))
+    }
+}
+
+impl Service<Uri> for ProverConnector {
+    type Response = ProverStream;
+    type Error = ConnectorError;
+    type Future = Ready<Result<ProverStream, ConnectorError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        ready(self.connect(&uri))
+    }
+}
+
+/// The TLS connection of the prover, implementing the hyper IO traits.
+#[derive(Debug)]
+pub struct ProverStream {
+    inner: TokioIo<Compat<TlsConnection>>,
+}
+
+impl ProverStream {
+    /// Wraps the TLS connection returned by [`Prover::connect`](crate::tls::Prover::connect).
+    pub fn new(conn: TlsConnection) -> Self {
+        Self {
+            inner: TokioIo::new(conn.compat()),
+        }
+    }
+
+    /// Returns the underlying TLS connection.
+    pub fn into_inner(self) -> TlsConnection {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+impl From<TlsConnection> for ProverStream {
+    fn from(conn: TlsConnection) -> Self {
+        Self::new(conn)
+    }
+}
+
+impl Connection for ProverStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl Read for ProverStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl Write for ProverStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -1,6 +1,10 @@
 use std::error::Error;
 use tls_mpc::MpcTlsError;
-use tlsn_core::commitment::TranscriptCommitmentBuilderError;
+use tlsn_common::{
+    abort::Aborted,
+    error::{ClassifiedError, ErrorCategory},
+};
+use tlsn_core::{commitment::TranscriptCommitmentBuilderError, TranscriptError};
 
 /// An error that can occur during proving.
 #[derive(Debug, thiserror::Error)]
@@ -26,6 +30,72 @@ pub enum ProverError {
     CommitmentError(#[from] CommitmentError),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("the connection to the server is already closed")]
+    ConnectionClosed,
+    #[error(
+        "{0} commitments were registered while the connection was open, but the session is not notarized"
+    )]
+    UnusedCommitments(usize),
+    #[error(transparent)]
+    Aborted(#[from] Aborted),
+    #[error("invalid records of the transcript: {0}")]
+    Transcript(#[from] TranscriptError),
+}
+
+impl ClassifiedError for ProverError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::TlsClientError(e)
+            | Self::AsyncClientError(tls_client_async::ConnectionError::TlsError(e)) => {
+                tls_client_category(e)
+            }
+            Self::ServerNoCloseNotify | Self::NotarizationError(_) => {
+                ErrorCategory::ProtocolViolation
+            }
+            Self::AsyncClientError(tls_client_async::ConnectionError::IOError(e))
+            | Self::IOError(e) => ErrorCategory::from_io(e),
+            Self::MpcError(e) => match e.downcast_ref::<MpcTlsError>() {
+                Some(e) => e.category(),
+                None => {
+                    ErrorCategory::from_source_chain(e.as_ref(), ErrorCategory::ProtocolViolation)
+                }
+            },
+            Self::CommitmentBuilder(_)
+            | Self::InvalidServerName(_)
+            | Self::InvalidRange
+            | Self::UnusedCommitments(_) => ErrorCategory::InvalidInput,
+            Self::ConnectionClosed => ErrorCategory::InvalidState,
+            Self::CommitmentError(_) | Self::Transcript(_) => ErrorCategory::Internal,
+            Self::Aborted(e) => e.category(),
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::TlsClientError(_) => "prover.tls",
+            Self::AsyncClientError(_) => "prover.tls_connection",
+            Self::IOError(_) => "prover.io",
+            Self::NotarizationError(_) => "prover.notarization",
+            Self::CommitmentBuilder(_) => "prover.commitment_builder",
+            Self::InvalidServerName(_) => "prover.invalid_server_name",
+            Self::MpcError(_) => "prover.mpc",
+            Self::ServerNoCloseNotify => "prover.server_no_close_notify",
+            Self::CommitmentError(_) => "prover.commitment",
+            Self::InvalidRange => "prover.invalid_range",
+            Self::ConnectionClosed => "prover.connection_closed",
+            Self::UnusedCommitments(_) => "prover.unused_commitments",
+            Self::Aborted(e) => e.code(),
+            Self::Transcript(_) => "prover.transcript",
+        }
+    }
+}
+
+/// Returns the category of an error of the TLS client, whose backend runs the MPC with the notary.
+fn tls_client_category(err: &tls_client::Error) -> ErrorCategory {
+    match err {
+        tls_client::Error::BackendError(e) => e.category(),
+        _ => ErrorCategory::ProtocolViolation,
+    }
 }
 
 impl From<uid_mux::yamux::ConnectionError> for ProverError {
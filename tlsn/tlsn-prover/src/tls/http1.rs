@@ -0,0 +1,69 @@
+//! Adapter to send HTTP/1.1 requests over the TLS connection of the prover with hyper 1.x.
+//!
+//! The TLS connection implements the `futures` IO traits, this module wraps it in a
+//! [`ProverStream`] implementing the hyper [`rt::Read`](hyper::rt::Read) and
+//! [`rt::Write`](hyper::rt::Write) traits, so it can be used without compatibility shims.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let (tls_connection, prover_fut) = prover.connect(client_socket).await?;
+//! let prover_task = tokio::spawn(prover_fut);
+//!
+//! let (mut request_sender, connection) = http1::handshake(tls_connection).await?;
+//! tokio::spawn(connection);
+//!
+//! let response = request_sender.send_request(request).await?;
+//! ```
+//!
+//! # WebSocket
+//!
+//! A connection upgraded to WebSocket keeps using the TLS connection of the prover, so the
+//! frames are part of the transcripts of the session. The connection must be polled with
+//! [`Connection::with_upgrades`] for the upgrade to be completed:
+//!
+//! ```ignore
+//! let (mut request_sender, connection) = http1::handshake(tls_connection).await?;
+//! tokio::spawn(connection.with_upgrades());
+//!
+//! let response = request_sender.send_request(upgrade_request).await?;
+//! let upgraded = hyper::upgrade::on(response).await?;
+//! ```
+//!
+//! The upgraded stream must be closed for the session to be notarized, and all the frames
+//! exchanged until then count towards `max_sent_data` and `max_recv_data`, which must be
+//! configured for the lifetime of the connection. The messages are committed and disclosed with
+//! `tlsn_formats::websocket::WebSocketTranscript`.
+
+use hyper::{
+    body::Body,
+    client::conn::http1::{self, Connection, SendRequest},
+};
+use tls_client_async::TlsConnection;
+
+use crate::tls::ProverStream;
+
+/// Performs an HTTP/1.1 handshake over the TLS connection of the prover, with the default
+/// options.
+///
+/// The returned [`Connection`] must be polled, usually by spawning it, for requests sent with
+/// the [`SendRequest`] to make progress.
+pub async fn handshake<B>(
+    conn: TlsConnection,
+) -> Result<(SendRequest<B>, Connection<ProverStream, B>), hyper::Error>
+where
+    B: Body + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    builder().handshake(ProverStream::new(conn)).await
+}
+
+/// Returns a builder to configure the HTTP/1.1 connection, e.g. to preserve the case of header
+/// names.
+///
+/// Use [`ProverStream::new`] to wrap the TLS connection passed to
+/// [`Builder::handshake`](http1::Builder::handshake).
+pub fn builder() -> http1::Builder {
+    http1::Builder::new()
+}
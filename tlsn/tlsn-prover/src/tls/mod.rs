@@ -5,46 +5,123 @@
 //!
 //! The TLS prover provides a low-level API, see the [`HTTP prover`](crate::http) which provides
 //! abstractions for working with HTTP sessions.
+//!
+//! # States
+//!
+//! The [`Prover`] is a typestate, so its methods can only be called in the phase of the protocol
+//! they belong to:
+//!
+//! 1. [`Initialized`](state::Initialized): [`Prover::setup`] performs the MPC setup with the
+//!    verifier.
+//! 2. [`Setup`](state::Setup): [`Prover::connect`] binds the prover to the connection to the
+//!    server. The returned [`ProverFuture`] runs the handshake and the session, and resolves once
+//!    the connection is closed.
+//! 3. [`Closed`](state::Closed): the transcripts can be inspected, and either
+//!    [`Prover::start_notarize`] or [`Prover::start_prove`] is called.
+//! 4. [`Notarize`](state::Notarize) or [`Prove`](state::Prove): commitments are built or parts of
+//!    the transcript are revealed, before the session is finalized.
 
 mod config;
+#[cfg(feature = "connector")]
+mod connector;
 mod error;
 mod future;
+#[cfg(feature = "connector")]
+pub mod http1;
 mod notarize;
 mod prove;
+mod resolve;
 pub mod state;
+mod stats;
 
-pub use config::{ProverConfig, ProverConfigBuilder, ProverConfigBuilderError};
+pub use config::{
+    ProverConfig, ProverConfigBuilder, ProverConfigBuilderError, MAX_RECORD_SIZE, MIN_RECORD_SIZE,
+};
+#[cfg(feature = "connector")]
+pub use connector::{ConnectorError, ProverConnector, ProverStream};
 pub use error::ProverError;
 pub use future::ProverFuture;
+pub use notarize::{ExclusionCommitments, ProofSizeEstimate};
+pub use resolve::{ServerResolver, StaticResolver};
 use state::{Notarize, Prove};
+use stats::PhaseTimer;
+pub use stats::{Phase, ProverStats, ProverStatsHandle};
+pub use tls_client_async::TranscriptView;
+pub use tlsn_common::{
+    abort::{AbortHandle, AbortOrigin, Aborted},
+    aes::AesBackend,
+    error::{ClassifiedError, ErrorCategory},
+    reconnect::Reconnect,
+    stats::{ProtocolStats, ProtocolStatsHandle, Traffic},
+    timing::{SetupTimings, TimingsHandle},
+};
 
-use futures::{AsyncRead, AsyncWrite, TryFutureExt};
-use mpz_common::Allocate;
-use mpz_garble::config::Role as DEAPRole;
-use mpz_ot::{chou_orlandi, kos};
-use rand::Rng;
-use serio::StreamExt;
-use std::sync::Arc;
+use futures::{
+    channel::mpsc,
+    future::{pending, select, try_join, Either},
+    AsyncRead, AsyncWrite, StreamExt as _, TryFutureExt,
+};
+use mpz_common::{Allocate, Preprocess};
+use mpz_garble::{config::Role as DEAPRole, protocol::deap::PeerEncodings};
+use mpz_ot::{chou_orlandi, kos, OTSetup};
+use serio::{stream::IoStreamExt as _, SinkExt as _, StreamExt};
+use state::RegisteredCommitment;
+use std::{convert::Infallible, io, sync::Arc};
 use tls_client::{ClientConnection, ServerName as TlsServerName};
 use tls_client_async::{bind_client, ClosedConnection, TlsConnection};
-use tls_mpc::{build_components, LeaderCtrl, MpcTlsLeader, TlsRole};
+use tls_core::msgs::fragmenter::PACKET_OVERHEAD;
+use tls_mpc::{build_components, CircuitCounter, LeaderCtrl, MpcTlsLeader, TlsRole};
 use tlsn_common::{
+    abort::{AbortChannel, ABORT_STREAM_ID},
     mux::{attach_mux, MuxControl},
+    ot_pool::OtPoolSize,
+    rng::{derive_seed, VM_RNG_STREAM},
+    schedule::StreamPriorities,
+    setup::{self, SessionParams, SetupParams},
     DEAPThread, Executor, OTReceiver, OTSender, Role,
 };
-use tlsn_core::transcript::Transcript;
+use tlsn_core::{
+    commitment::{blake3::Blake3Commitment, CommitmentFilter, TranscriptCommitmentBuilderError},
+    msg::{AbortReason, SessionPhase, SignedSessionOpenedReceipt},
+    transcript::{get_value_ids, Transcript},
+    Direction,
+};
 use uid_mux::FramedUidMux as _;
+use utils::range::RangeSet;
 
 #[cfg(feature = "formats")]
 use crate::http::{state as http_state, HttpProver, HttpProverError};
 
-use tracing::{debug, debug_span, instrument, Instrument};
+use tracing::{debug, debug_span, instrument, warn, Instrument};
 
 /// A prover instance.
 #[derive(Debug)]
 pub struct Prover<T: state::ProverState> {
     config: ProverConfig,
     state: T,
+    stats: ProverStatsHandle,
+    abort: AbortHandle,
+}
+
+impl<T: state::ProverState> Prover<T> {
+    /// Returns a handle to abort the session.
+    ///
+    /// Aborting notifies the verifier, and the prover returns [`ProverError::Aborted`] from the
+    /// phase it is running, or the next phase it runs.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Returns a snapshot of the statistics of the prover.
+    pub fn stats(&self) -> ProverStats {
+        self.stats.get()
+    }
+
+    /// Returns a handle to the statistics of the prover, which remains valid after the prover
+    /// is consumed.
+    pub fn stats_handle(&self) -> ProverStatsHandle {
+        self.stats.clone()
+    }
 }
 
 impl Prover<state::Initialized> {
@@ -57,6 +134,8 @@ impl Prover<state::Initialized> {
         Self {
             config,
             state: state::Initialized,
+            stats: ProverStatsHandle::default(),
+            abort: AbortHandle::new(),
         }
     }
 
@@ -68,49 +147,212 @@ impl Prover<state::Initialized> {
     /// # Arguments
     ///
     /// * `socket` - The socket to the TLS verifier.
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn setup<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         self,
         socket: S,
     ) -> Result<Prover<state::Setup>, ProverError> {
-        let (mut mux_fut, mux_ctrl) = attach_mux(socket, Role::Prover);
+        let timer = self.stats.start_phase(Phase::Setup);
+        let state = self.setup_attempt(socket).instrument(timer.span()).await?;
+        self.finish_setup(state, timer)
+    }
 
-        // Maximum thread forking concurrency of 8.
-        // TODO: Determine the optimal number of threads.
-        let mut exec = Executor::new(mux_ctrl.clone(), 8);
+    /// Sets up the prover like [`setup`](Self::setup), retrying if the setup fails with a
+    /// transient error, e.g. a dropped connection to the verifier.
+    ///
+    /// Nothing is sent to the server before the setup completes, so a failed setup is discarded
+    /// and run again from the start over a new connection, with fresh randomness. If the new
+    /// connection belongs to a new session, as reported by [`Reconnect::session_id`], the prover
+    /// takes over the id of that session.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The socket to the TLS verifier.
+    /// * `reconnect` - Establishes a new connection to the verifier if the setup fails.
+    /// * `max_retries` - Maximum number of times the setup is retried.
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
+    pub async fn setup_with_retry<R>(
+        mut self,
+        socket: R::Io,
+        mut reconnect: R,
+        max_retries: usize,
+    ) -> Result<Prover<state::Setup>, ProverError>
+    where
+        R: Reconnect,
+        R::Io: 'static,
+    {
+        let timer = self.stats.start_phase(Phase::Setup);
+
+        let mut retries = max_retries;
+        let mut result = self.setup_attempt(socket).instrument(timer.span()).await;
+        while let Err(err) = &result {
+            if retries == 0 || !err.is_retryable() || self.abort.is_aborted() {
+                break;
+            }
+            retries -= 1;
+
+            warn!("setup failed, retrying: {err}");
+
+            result = match reconnect.reconnect().await {
+                Ok(socket) => {
+                    if let Some(id) = reconnect.session_id() {
+                        debug!(session_id = id, "retrying setup in a new session");
+                        self.config.set_id(id);
+                    }
+                    self.setup_attempt(socket).instrument(timer.span()).await
+                }
+                Err(err) => Err(err.into()),
+            };
+        }
 
-        let (mpc_tls, vm, ot_recv) = mux_fut
-            .poll_with(setup_mpc_backend(&self.config, &mux_ctrl, &mut exec))
-            .await?;
+        self.finish_setup(result?, timer)
+    }
 
-        let io = mux_fut
+    /// Runs a single attempt of the setup over the provided socket.
+    async fn setup_attempt<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        &self,
+        socket: S,
+    ) -> Result<state::Setup, ProverError> {
+        if !AesBackend::detect().is_hardware() {
+            warn!(
+                "hardware AES is not available, garbling uses the slower software implementation"
+            );
+        }
+        let socket = self.stats.meter(socket);
+        let (socket, session_params) = setup::negotiate(
+            socket,
+            SetupParams {
+                compression: self.config.compression(),
+                concurrency: self.config.mpc_concurrency(),
+                session_receipts: true,
+                max_sent_data: self.config.max_sent_data(),
+                max_recv_data: self.config.max_recv_data(),
+                max_records: self.config.max_records(),
+            },
+        )
+        .await?;
+        debug!(?session_params, "negotiated session parameters");
+        let ot_pool = OtPoolSize::for_session(Role::Prover, &session_params)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        debug!(?ot_pool, "sized ot pool");
+        let (mut mux_fut, mux_ctrl) = attach_mux(socket, Role::Prover, self.config.mux_config());
+
+        let mut exec = Executor::new(mux_ctrl.clone(), session_params.concurrency);
+
+        let abort_io = mux_fut
             .poll_with(
                 mux_ctrl
-                    .open_framed(b"tlsnotary")
+                    .open_framed(ABORT_STREAM_ID)
                     .map_err(ProverError::from),
             )
             .await?;
+        let priorities = mux_fut.priorities();
+        if let Some(priorities) = &priorities {
+            priorities.register(ABORT_STREAM_ID, &abort_io);
+        }
+        let mut abort =
+            AbortChannel::new(abort_io, Role::Prover, self.config.id(), self.abort.clone());
+
+        let (mpc_tls, vm, ot_recv) = mux_fut
+            .poll_with(abort.guard(
+                SessionPhase::Setup,
+                setup_mpc_backend(
+                    &self.config,
+                    &session_params,
+                    ot_pool,
+                    &mux_ctrl,
+                    priorities.as_ref(),
+                    &mut exec,
+                    &self.stats.timings_handle(),
+                    self.stats.protocol_handle().circuit_counter(),
+                ),
+            ))
+            .await??;
+
+        let mut io = mux_fut
+            .poll_with(
+                abort.guard(
+                    SessionPhase::Setup,
+                    mux_ctrl
+                        .open_framed(b"tlsnotary")
+                        .map_err(ProverError::from),
+                ),
+            )
+            .await??;
+        if let Some(priorities) = &priorities {
+            priorities.register(b"tlsnotary", &io);
+        }
+
+        // The receipt is only exchanged if the verifier issues one, so that the setup of
+        // verifiers which don't is unchanged
+        let opened_receipt = if session_params.session_receipts {
+            let receipt = mux_fut
+                .poll_with(abort.guard(SessionPhase::Setup, async {
+                    io.send(
+                        self.config
+                            .binding()
+                            .map(|binding| binding.audience().to_string()),
+                    )
+                    .await?;
+                    let receipt: SignedSessionOpenedReceipt = io.expect_next().await?;
+
+                    Ok::<_, ProverError>(receipt)
+                }))
+                .await??;
+
+            Some(receipt)
+        } else {
+            None
+        };
 
         let ctx = mux_fut
-            .poll_with(exec.new_thread().map_err(ProverError::from))
-            .await?;
+            .poll_with(abort.guard(
+                SessionPhase::Setup,
+                exec.new_thread().map_err(ProverError::from),
+            ))
+            .await??;
+
+        self.stats.add_ots(ot_pool.send, ot_pool.recv);
+
+        Ok(state::Setup {
+            io,
+            mux_ctrl,
+            mux_fut,
+            abort,
+            mpc_tls,
+            vm,
+            ot_recv,
+            ctx,
+            opened_receipt,
+        })
+    }
+
+    /// Transitions to the setup state once the setup succeeded.
+    fn finish_setup(
+        self,
+        state: state::Setup,
+        timer: PhaseTimer,
+    ) -> Result<Prover<state::Setup>, ProverError> {
+        timer.finish();
 
         Ok(Prover {
             config: self.config,
-            state: state::Setup {
-                io,
-                mux_ctrl,
-                mux_fut,
-                mpc_tls,
-                vm,
-                ot_recv,
-                ctx,
-            },
+            stats: self.stats,
+            abort: self.abort,
+            state,
         })
     }
 }
 
 impl Prover<state::Setup> {
+    /// Returns the receipt issued by the verifier when the session was opened, if it is a notary
+    /// which issues them.
+    ///
+    /// The receipt proves that the notarization was attempted, even if the session fails later.
+    pub fn opened_receipt(&self) -> Option<&SignedSessionOpenedReceipt> {
+        self.state.opened_receipt.as_ref()
+    }
+
     /// Connects to the server using the provided socket.
     ///
     /// Returns a handle to the TLS connection, a future which returns the prover once the connection is
@@ -119,7 +361,7 @@ impl Prover<state::Setup> {
     /// # Arguments
     ///
     /// * `socket` - The socket to the server.
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn connect<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         self,
         socket: S,
@@ -128,23 +370,40 @@ impl Prover<state::Setup> {
             io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mpc_tls,
             vm,
             ot_recv,
             ctx,
+            ..
         } = self.state;
 
+        let timer = self.stats.start_phase(Phase::TlsSession);
+        let abort_handle = self.abort.clone();
+        let session_id = self.config.id().to_string();
         let (mpc_ctrl, mpc_fut) = mpc_tls.run();
 
         let server_name = TlsServerName::try_from(self.config.server_dns())?;
-        let config = tls_client::ClientConfig::builder()
-            .with_safe_defaults()
+        let mut config = tls_client::ClientConfig::builder()
+            .with_cipher_suites(self.config.cipher_suites())
+            .with_kx_groups(self.config.kx_groups())
+            .with_protocol_versions(self.config.protocol_versions())?
             .with_root_certificates(self.config.root_cert_store.clone())
             .with_no_client_auth();
+        config.enable_certificate_status = self.config.certificate_status();
+        config.enable_sni = self.config.enable_sni();
+        config.alpn_protocols = self.config.alpn_protocols().to_vec();
+        config.max_fragment_size = self
+            .config
+            .max_record_size()
+            .map(|size| size + PACKET_OVERHEAD);
         let client =
             ClientConnection::new(Arc::new(config), Box::new(mpc_ctrl.clone()), server_name)?;
 
         let (conn, conn_fut) = bind_client(socket, client);
+        let transcript = conn_fut.transcript();
+        let (commitment_sender, mut commitment_receiver) = mpsc::unbounded();
+        let commitment_filter = self.config.commitment_filter().cloned();
 
         let start_time = web_time::UNIX_EPOCH.elapsed().unwrap().as_secs();
 
@@ -152,26 +411,87 @@ impl Prover<state::Setup> {
             let mpc_ctrl = mpc_ctrl.clone();
             async move {
                 let conn_fut = async {
-                    let ClosedConnection { sent, recv, .. } = mux_fut
-                        .poll_with(conn_fut.map_err(ProverError::from))
-                        .await?;
+                    let ClosedConnection { sent, recv, .. } =
+                        conn_fut.map_err(ProverError::from).await?;
 
                     mpc_ctrl.close_connection().await?;
 
                     Ok::<_, ProverError>((sent, recv))
                 };
 
-                let ((sent, recv), mpc_tls_data) = futures::try_join!(
-                    conn_fut,
-                    mpc_fut.in_current_span().map_err(ProverError::from)
+                let mut registered_commitments = Vec::new();
+                let ((sent, recv), mpc_tls_data) = mux_fut
+                    .poll_with(abort.guard(SessionPhase::TlsSession, async {
+                        let session = try_join(
+                            conn_fut,
+                            mpc_fut.in_current_span().map_err(ProverError::from),
+                        );
+
+                        // Commitments registered while the connection is open are hashed right
+                        // away, so that the work is not left until the connection is closed.
+                        let register_commitments = async {
+                            while let Some((direction, ranges)) = commitment_receiver.next().await {
+                                register_commitment(
+                                    &vm,
+                                    &mut registered_commitments,
+                                    direction,
+                                    ranges,
+                                );
+                            }
+                            pending::<Infallible>().await
+                        };
+
+                        futures::pin_mut!(session, register_commitments);
+                        match select(session, register_commitments).await {
+                            Either::Left((result, _)) => result,
+                            Either::Right((never, _)) => match never {},
+                        }
+                    }))
+                    .instrument(timer.span())
+                    .await??;
+
+                // Commitments registered after the connection is closed are rejected, and those
+                // which were registered before are still hashed.
+                commitment_receiver.close();
+                while let Ok(Some((direction, ranges))) = commitment_receiver.try_next() {
+                    register_commitment(&vm, &mut registered_commitments, direction, ranges);
+                }
+
+                self.stats.set_mpc_tls_timings(mpc_tls_data.timings);
+                timer.finish();
+
+                let transcript_tx = Transcript::new(sent).with_records(
+                    Direction::Sent,
+                    mpc_tls_data
+                        .sent_records
+                        .iter()
+                        .map(|record| (record.seq, record.len, record.time)),
                 )?;
-
+                let transcript_rx = Transcript::new(recv)
+                    .with_records(
+                        Direction::Received,
+                        mpc_tls_data
+                            .recv_records
+                            .iter()
+                            .map(|record| (record.seq, record.len, record.time)),
+                    )?
+                    .with_record_order(
+                        mpc_tls_data
+                            .recv_records
+                            .iter()
+                            .map(|record| record.sent_before),
+                    )?;
+
+                let commitment_filter = self.config.commitment_filter().cloned();
                 Ok(Prover {
                     config: self.config,
+                    stats: self.stats,
+                    abort: self.abort,
                     state: state::Closed {
                         io,
                         mux_ctrl,
                         mux_fut,
+                        abort,
                         vm,
                         ot_recv,
                         ctx,
@@ -180,19 +500,27 @@ impl Prover<state::Setup> {
                             .handshake_decommitment
                             .expect("handshake was committed"),
                         server_public_key: mpc_tls_data.server_public_key,
-                        transcript_tx: Transcript::new(sent),
-                        transcript_rx: Transcript::new(recv),
+                        transcript_tx,
+                        transcript_rx,
+                        registered_commitments,
+                        commitment_filter,
                     },
                 })
             }
-            .instrument(debug_span!("prover"))
+            .instrument(debug_span!("prover", session_id = %session_id))
         });
 
         Ok((
             conn,
             ProverFuture {
                 fut,
-                ctrl: ProverControl { mpc_ctrl },
+                ctrl: ProverControl {
+                    mpc_ctrl,
+                    abort: abort_handle,
+                    transcript,
+                    commitment_sender,
+                    commitment_filter,
+                },
             },
         ))
     }
@@ -223,6 +551,8 @@ impl Prover<state::Closed> {
         Prover {
             config: self.config,
             state: self.state.into(),
+            stats: self.stats,
+            abort: self.abort,
         }
     }
 
@@ -234,28 +564,69 @@ impl Prover<state::Closed> {
         Prover {
             config: self.config,
             state: self.state.into(),
+            stats: self.stats,
+            abort: self.abort,
         }
     }
 }
 
 /// Performs a setup of the various MPC subprotocols.
 #[instrument(level = "debug", skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 async fn setup_mpc_backend(
     config: &ProverConfig,
+    session_params: &SessionParams,
+    ot_pool: OtPoolSize,
     mux: &MuxControl,
+    priorities: Option<&StreamPriorities>,
     exec: &mut Executor,
+    timings: &TimingsHandle,
+    circuit_counter: CircuitCounter,
 ) -> Result<(MpcTlsLeader, DEAPThread, OTReceiver), ProverError> {
     let mut ot_sender = kos::Sender::new(
         config.build_ot_sender_config(),
         chou_orlandi::Receiver::new(config.build_base_ot_receiver_config()),
     );
-    ot_sender.alloc(config.ot_sender_setup_count());
+    ot_sender.alloc(ot_pool.send);
 
     let mut ot_receiver = kos::Receiver::new(
         config.build_ot_receiver_config(),
         chou_orlandi::Sender::new(config.build_base_ot_sender_config()),
     );
-    ot_receiver.alloc(config.ot_receiver_setup_count());
+    ot_receiver.alloc(ot_pool.recv);
+
+    // Run the base OT and the OT extension up front instead of lazily on first use, so that they
+    // are timed separately. Threads are paired with the verifier's in creation order, so the
+    // sender runs on the thread of the verifier's receiver and vice versa
+    let (mut ctx_ot_sender, mut ctx_ot_receiver) =
+        futures::try_join!(exec.new_thread(), exec.new_thread())?;
+
+    let start = web_time::Instant::now();
+    futures::try_join!(
+        ot_sender
+            .setup(&mut ctx_ot_sender)
+            .map_err(ProverError::from),
+        ot_receiver
+            .setup(&mut ctx_ot_receiver)
+            .map_err(ProverError::from),
+    )?;
+    let base_ot = start.elapsed();
+
+    let start = web_time::Instant::now();
+    futures::try_join!(
+        ot_sender
+            .preprocess(&mut ctx_ot_sender)
+            .map_err(ProverError::from),
+        ot_receiver
+            .preprocess(&mut ctx_ot_receiver)
+            .map_err(ProverError::from),
+    )?;
+    let ot_extension = start.elapsed();
+
+    timings.update(|t| {
+        t.base_ot = Some(base_ot);
+        t.ot_extension = Some(ot_extension);
+    });
 
     let ot_sender = OTSender::new(ot_sender);
     let ot_receiver = OTReceiver::new(ot_receiver);
@@ -292,13 +663,13 @@ async fn setup_mpc_backend(
 
     let vm = DEAPThread::new(
         DEAPRole::Leader,
-        rand::rngs::OsRng.gen(),
+        derive_seed(config.rng_seed(), VM_RNG_STREAM),
         ctx_vm,
         ot_sender.clone(),
         ot_receiver.clone(),
     );
 
-    let mpc_tls_config = config.build_mpc_tls_config();
+    let mpc_tls_config = config.build_mpc_tls_config(session_params);
     let (ke, prf, encrypter, decrypter) = build_components(
         TlsRole::Leader,
         mpc_tls_config.common(),
@@ -332,9 +703,13 @@ async fn setup_mpc_backend(
         )?,
         ot_sender.clone(),
         ot_receiver.clone(),
+        circuit_counter,
     );
 
     let channel = mux.open_framed(b"mpc_tls").await?;
+    if let Some(priorities) = priorities {
+        priorities.register(b"mpc_tls", &channel);
+    }
     let mut mpc_tls = MpcTlsLeader::new(
         mpc_tls_config,
         Box::new(StreamExt::compat_stream(channel)),
@@ -355,6 +730,12 @@ async fn setup_mpc_backend(
 #[derive(Clone)]
 pub struct ProverControl {
     mpc_ctrl: LeaderCtrl,
+    abort: AbortHandle,
+    transcript: TranscriptView,
+    /// Sends the commitments registered while the connection is open to be hashed.
+    commitment_sender: mpsc::UnboundedSender<(Direction, RangeSet<usize>)>,
+    /// Filter of secrets which must never be committed to publicly.
+    commitment_filter: Option<CommitmentFilter>,
 }
 
 impl ProverControl {
@@ -373,4 +754,107 @@ impl ProverControl {
             .await
             .map_err(ProverError::from)
     }
+
+    /// Aborts the session, notifying the verifier.
+    ///
+    /// Returns `false` if the session was already aborted.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason for aborting.
+    /// * `details` - Human readable details sent to the verifier.
+    pub fn abort(&self, reason: AbortReason, details: Option<String>) -> bool {
+        self.abort.abort(reason, details)
+    }
+
+    /// Returns a view of the transcript of the TLS connection, which can be inspected while the
+    /// connection is open.
+    pub fn transcript(&self) -> TranscriptView {
+        self.transcript.clone()
+    }
+
+    /// Registers a commitment to ranges of the transcript which have already been transmitted,
+    /// while the connection is still open.
+    ///
+    /// The commitment is hashed while the connection is open if the encodings of the ranges are
+    /// available, which is not the case for received data whose decryption is
+    /// [deferred](Self::defer_decryption). Otherwise it is hashed when the commitments are built.
+    /// It is added to the [`commitment_builder`](crate::tls::Prover::commitment_builder) once
+    /// notarization starts, where its ID can be looked up with
+    /// [`TranscriptCommitmentBuilder::get_id`](tlsn_core::commitment::TranscriptCommitmentBuilder::get_id).
+    /// Commitments can not be used when proving to a verifier directly, so proving fails if any
+    /// was registered.
+    ///
+    /// Returns [`ProverError::ConnectionClosed`] if the connection has already been closed, in
+    /// which case the commitment should be added to the commitment builder directly.
+    /// Commitments overlapping a secret of the
+    /// [`commitment_filter`](crate::tls::ProverConfig::commitment_filter) are rejected.
+    ///
+    /// # Arguments
+    /// * `ranges` - The ranges of the transcript to commit to
+    /// * `direction` - The direction of the transcript to commit to
+    pub fn commit(
+        &self,
+        ranges: impl Into<RangeSet<usize>>,
+        direction: Direction,
+    ) -> Result<(), ProverError> {
+        let range_set = ranges.into();
+
+        let check = |data: &[u8]| {
+            match range_set.max() {
+                Some(max) if max <= data.len() => {}
+                _ => return Err(ProverError::InvalidRange),
+            }
+
+            if let Some(filter) = &self.commitment_filter {
+                let blocked = filter.blocked(data, &range_set);
+                if !blocked.is_empty() {
+                    return Err(TranscriptCommitmentBuilderError::Filtered {
+                        direction,
+                        ranges: blocked,
+                    }
+                    .into());
+                }
+            }
+
+            Ok(())
+        };
+
+        match direction {
+            Direction::Sent => self.transcript.with_sent(check),
+            Direction::Received => self.transcript.with_recv(check),
+        }?;
+
+        self.commitment_sender
+            .unbounded_send((direction, range_set))
+            .map_err(|_| ProverError::ConnectionClosed)
+    }
+}
+
+/// Registers a commitment, hashing it if the encodings of its ranges are available.
+fn register_commitment(
+    vm: &impl PeerEncodings,
+    registered_commitments: &mut Vec<RegisteredCommitment>,
+    direction: Direction,
+    ranges: RangeSet<usize>,
+) {
+    if registered_commitments
+        .iter()
+        .any(|registered| registered.direction == direction && registered.ranges == ranges)
+    {
+        return;
+    }
+
+    let ids = get_value_ids(&ranges, direction).collect::<Vec<_>>();
+    let id_refs = ids.iter().map(String::as_str).collect::<Vec<_>>();
+    let commitment = vm
+        .get_peer_encodings(&id_refs)
+        .ok()
+        .map(|encodings| Blake3Commitment::new(&encodings));
+
+    registered_commitments.push(RegisteredCommitment {
+        direction,
+        ranges,
+        commitment,
+    });
 }
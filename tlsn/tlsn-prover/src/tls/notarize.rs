@@ -2,14 +2,23 @@
 //!
 //! The prover deals with a TLS verifier that is only a notary.
 
-use super::{state::Notarize, Prover, ProverError};
+use super::{state::Notarize, stats::Phase, Prover, ProverError};
+use mpz_core::hash::Hash;
+use mpz_garble::{Memory, Prove};
 use mpz_ot::VerifiableOTReceiver;
 use serio::{stream::IoStreamExt as _, SinkExt as _};
+use tlsn_common::plaintext_hash::{build_plaintext_hash_circuit, plaintext_hash_refs, salt_id};
 use tlsn_core::{
-    commitment::TranscriptCommitmentBuilder, msg::SignedSessionHeader, transcript::Transcript,
-    NotarizedSession, ServerName, SessionData,
+    commitment::{hash::SALT_LEN, CommitmentId, TranscriptCommitmentBuilder},
+    merkle::MerkleRoot,
+    msg::{PlaintextHashes, SessionPhase, SignedSessionHeader},
+    proof::{SessionInfo, SessionProof},
+    range::{self, RangeSet},
+    session::{RecordOrder, RecordTimings},
+    transcript::Transcript,
+    Direction, HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader,
 };
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, Instrument as _};
 
 impl Prover<Notarize> {
     /// Returns the transcript of the sent data.
@@ -27,13 +36,133 @@ impl Prover<Notarize> {
         &mut self.state.builder
     }
 
+    /// Commits to the sent transcript, excluding the provided secrets from the public commitments.
+    ///
+    /// See [`commit_excluding`](Self::commit_excluding).
+    pub fn commit_sent_excluding(
+        &mut self,
+        secrets: &[&[u8]],
+    ) -> Result<ExclusionCommitments, ProverError> {
+        self.commit_excluding(Direction::Sent, secrets)
+    }
+
+    /// Commits to the received transcript, excluding the provided secrets from the public
+    /// commitments.
+    ///
+    /// See [`commit_excluding`](Self::commit_excluding).
+    pub fn commit_recv_excluding(
+        &mut self,
+        secrets: &[&[u8]],
+    ) -> Result<ExclusionCommitments, ProverError> {
+        self.commit_excluding(Direction::Received, secrets)
+    }
+
+    /// Commits to the transcript, excluding the provided secrets from the public commitments.
+    ///
+    /// All occurrences of the secrets in the transcript are located. Each range of the remainder
+    /// of the transcript is committed to as public, and each range of the secrets is committed to
+    /// separately, so the public commitments can be revealed without revealing the secrets.
+    ///
+    /// The secrets must not match the
+    /// [`commitment_filter`](crate::tls::ProverConfig::commitment_filter), as their commitments
+    /// would be rejected. If any of the commitments is rejected, no commitment is added.
+    pub fn commit_excluding(
+        &mut self,
+        direction: Direction,
+        secrets: &[&[u8]],
+    ) -> Result<ExclusionCommitments, ProverError> {
+        let data = match direction {
+            Direction::Sent => self.state.transcript_tx.data(),
+            Direction::Received => self.state.transcript_rx.data(),
+        };
+        let (public_ranges, private_ranges) = range::split(data, secrets);
+
+        // Both sets of ranges are committed to at once, so nothing is committed to if any of the
+        // commitments is rejected.
+        let ranges = public_ranges
+            .iter_ranges()
+            .chain(private_ranges.iter_ranges())
+            .collect::<Vec<_>>();
+        let mut public = if ranges.is_empty() {
+            Vec::new()
+        } else {
+            self.state.builder.commit_ranges(&ranges, direction)?
+        };
+        let private = public.split_off(public_ranges.iter_ranges().count());
+
+        Ok(ExclusionCommitments {
+            public_ranges,
+            public,
+            private_ranges,
+            private,
+        })
+    }
+
+    /// Estimates the size of the [`TlsProof`](tlsn_core::proof::TlsProof) revealing the provided
+    /// commitments, before the session is finalized.
+    ///
+    /// The estimate is computed from the commitments added so far and the lengths of the
+    /// transcripts, and is exact for the bincode encoding of a proof signed with a P-256 key,
+    /// except for the timestamp and the key identifier which the Notary may add to the session
+    /// header. It lets applications with a size budget, e.g. for on-chain submission, adjust
+    /// which commitments they make or reveal before paying for finalization.
+    ///
+    /// # Arguments
+    ///
+    /// * `reveal` - The commitments which will be opened in the proof.
+    pub fn estimate_proof_size(
+        &self,
+        reveal: &[CommitmentId],
+    ) -> Result<ProofSizeEstimate, ProverError> {
+        let substrings = self.state.builder.substrings_proof_size(reveal)?;
+
+        // Only the size of the header matters, so the values to be chosen by the Notary are
+        // replaced by placeholders of the same size.
+        let mut header = SessionHeader::new(
+            [0; 32],
+            MerkleRoot::from([0; 32]),
+            self.state.transcript_tx.data().len(),
+            self.state.transcript_rx.data().len(),
+            HandshakeSummary::new(
+                self.state.start_time,
+                self.state.server_public_key.clone(),
+                Hash::from([0; 32]),
+            ),
+        );
+        if let Some(binding) = self.config.binding() {
+            header = header.with_binding(binding.clone());
+        }
+        if self.config.commit_record_timings() {
+            header = header.with_record_timings(Hash::from([0; 32]));
+        }
+        if self.config.commit_record_order() {
+            header = header.with_record_order(Hash::from([0; 32]));
+        }
+        header = header.with_plaintext_hashes(vec![
+            Hash::from([0; 32]);
+            self.state.builder.plaintext_hash_count()
+        ]);
+
+        let session_info = SessionInfo {
+            server_name: ServerName::Dns(self.config.server_dns().to_string()),
+            handshake_decommitment: self.state.handshake_decommitment.clone(),
+        };
+
+        Ok(ProofSizeEstimate {
+            session: SessionProof::estimate_size(&header, &session_info),
+            substrings,
+        })
+    }
+
     /// Finalizes the notarization returning a [`NotarizedSession`].
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn finalize(self) -> Result<NotarizedSession, ProverError> {
+        let timer = self.stats.start_phase(Phase::Finalize);
         let Notarize {
             mut io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mut vm,
             mut ot_recv,
             mut ctx,
@@ -43,41 +172,97 @@ impl Prover<Notarize> {
             transcript_tx,
             transcript_rx,
             builder,
+            registration_error,
         } = self.state;
 
+        if let Some(err) = registration_error {
+            return Err(err.into());
+        }
+
         let commitments = builder.build()?;
 
-        let session_data = SessionData::new(
-            ServerName::Dns(self.config.server_dns().to_string()),
-            handshake_decommitment,
-            transcript_tx,
-            transcript_rx,
-            commitments,
-        );
+        let merkle_root = commitments.merkle_root();
+        let record_timings = if self.config.commit_record_timings() {
+            Some(
+                RecordTimings::from_transcripts(&transcript_tx, &transcript_rx).ok_or_else(
+                    || {
+                        ProverError::NotarizationError(
+                            "the times of the TLS records are not known".to_string(),
+                        )
+                    },
+                )?,
+            )
+        } else {
+            None
+        };
+
+        let record_order = if self.config.commit_record_order() {
+            Some(
+                RecordOrder::from_transcripts(&transcript_tx, &transcript_rx).ok_or_else(|| {
+                    ProverError::NotarizationError(
+                        "the order of the TLS records is not known".to_string(),
+                    )
+                })?,
+            )
+        } else {
+            None
+        };
+
+        let plaintext_hashes = PlaintextHashes::new(&commitments);
+        let plaintext_hash_leaves = plaintext_hashes.leaves();
+
+        let (notary_encoder_seed, notary_record_timings, SignedSessionHeader { header, signature }) =
+            mux_fut
+                .poll_with(abort.guard(SessionPhase::Finalize, async {
+                    debug!("starting finalization");
 
-        let merkle_root = session_data.commitments().merkle_root();
+                    io.send(merkle_root).await?;
+                    io.send(self.config.binding().cloned()).await?;
+                    io.send(record_timings.clone()).await?;
+                    io.send(record_order.clone()).await?;
 
-        let (notary_encoder_seed, SignedSessionHeader { header, signature }) = mux_fut
-            .poll_with(async {
-                debug!("starting finalization");
+                    // Prove the plaintext hash commitments to the notary, which must happen before
+                    // the MPC is finalized.
+                    io.send(plaintext_hashes).await?;
 
-                io.send(merkle_root).await?;
+                    for (index, (_, info, commitment)) in
+                        commitments.plaintext_hashes().into_iter().enumerate()
+                    {
+                        let salt = vm.new_private_input::<[u8; SALT_LEN]>(&salt_id(index))?;
+                        vm.assign(&salt, *commitment.salt())?;
+                        let (data, hash) = plaintext_hash_refs(&mut vm, index, info)?;
 
-                ot_recv.accept_reveal(&mut ctx).await?;
+                        let circuit = build_plaintext_hash_circuit(info.ranges().len());
+                        self.stats.protocol_handle().add_circuit(&circuit);
 
-                debug!("received OT secret");
+                        vm.execute_prove(circuit, &[salt, data], &[hash.clone()])
+                            .await?;
+                        vm.prove(&[hash]).await?;
+                    }
 
-                let notary_encoder_seed = vm
-                    .finalize()
-                    .await
-                    .map_err(|e| ProverError::MpcError(Box::new(e)))?
-                    .expect("encoder seed returned");
+                    debug!("proved plaintext hashes");
 
-                let signed_header: SignedSessionHeader = io.expect_next().await?;
+                    ot_recv.accept_reveal(&mut ctx).await?;
 
-                Ok::<_, ProverError>((notary_encoder_seed, signed_header))
-            })
-            .await?;
+                    debug!("received OT secret");
+
+                    let notary_encoder_seed = vm
+                        .finalize()
+                        .await
+                        .map_err(|e| ProverError::MpcError(Box::new(e)))?
+                        .expect("encoder seed returned");
+
+                    let notary_record_timings: Option<RecordTimings> = io.expect_next().await?;
+                    let signed_header: SignedSessionHeader = io.expect_next().await?;
+
+                    Ok::<_, ProverError>((
+                        notary_encoder_seed,
+                        notary_record_timings,
+                        signed_header,
+                    ))
+                }))
+                .instrument(timer.span())
+                .await??;
 
         // Wait for the notary to correctly close the connection.
         if !mux_fut.is_complete() {
@@ -90,9 +275,9 @@ impl Prover<Notarize> {
             .verify(
                 start_time,
                 &server_public_key,
-                &session_data.commitments().merkle_root(),
+                &merkle_root,
                 &notary_encoder_seed,
-                &session_data.session_info().handshake_decommitment,
+                &handshake_decommitment,
             )
             .map_err(|_| {
                 ProverError::NotarizationError(
@@ -100,6 +285,105 @@ impl Prover<Notarize> {
                 )
             })?;
 
+        if header.binding() != self.config.binding() {
+            return Err(ProverError::NotarizationError(
+                "notary signed a session header with a different session binding".to_string(),
+            ));
+        }
+
+        // The Notary signs the times at which it observed the records, which replace the
+        // Prover's own times once they are checked to be of the same records.
+        let (transcript_tx, transcript_rx) = match (record_timings, notary_record_timings) {
+            (None, None) => (transcript_tx, transcript_rx),
+            (Some(record_timings), Some(notary_record_timings)) => {
+                if record_timings
+                    .check(&notary_record_timings, u64::MAX)
+                    .is_err()
+                    || header.record_timings() != Some(&notary_record_timings.digest())
+                {
+                    return Err(ProverError::NotarizationError(
+                        "notary signed a session header with different record timings".to_string(),
+                    ));
+                }
+
+                (
+                    transcript_tx.with_record_times(
+                        notary_record_timings.sent().iter().map(|(_, time)| *time),
+                    )?,
+                    transcript_rx.with_record_times(
+                        notary_record_timings.recv().iter().map(|(_, time)| *time),
+                    )?,
+                )
+            }
+            _ => {
+                return Err(ProverError::NotarizationError(
+                    "notary did not sign the record timings as requested".to_string(),
+                ))
+            }
+        };
+
+        if header.record_order() != record_order.map(|order| order.digest()).as_ref() {
+            return Err(ProverError::NotarizationError(
+                "notary signed a session header with a different record order".to_string(),
+            ));
+        }
+
+        if header.plaintext_hashes() != plaintext_hash_leaves {
+            return Err(ProverError::NotarizationError(
+                "notary signed a session header with different plaintext hashes".to_string(),
+            ));
+        }
+
+        if let Some(server_name) = header.server_name() {
+            if !server_name
+                .trim_end_matches('.')
+                .eq_ignore_ascii_case(self.config.server_dns().trim_end_matches('.'))
+            {
+                return Err(ProverError::NotarizationError(
+                    "notary signed a session header with a different server name".to_string(),
+                ));
+            }
+        }
+
+        let session_data = SessionData::new(
+            ServerName::Dns(self.config.server_dns().to_string()),
+            handshake_decommitment,
+            transcript_tx,
+            transcript_rx,
+            commitments,
+        );
+
+        timer.finish();
+
         Ok(NotarizedSession::new(header, Some(signature), session_data))
     }
 }
+
+/// The commitments created by [`Prover::commit_excluding`].
+#[derive(Debug, Clone)]
+pub struct ExclusionCommitments {
+    /// The ranges of the transcript which do not contain a secret.
+    pub public_ranges: RangeSet<usize>,
+    /// The commitments to the public ranges, one per range in ascending order.
+    pub public: Vec<CommitmentId>,
+    /// The ranges of the transcript which contain a secret.
+    pub private_ranges: RangeSet<usize>,
+    /// The commitments to the private ranges, one per range in ascending order.
+    pub private: Vec<CommitmentId>,
+}
+
+/// The estimated size of a proof, returned by [`Prover::estimate_proof_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofSizeEstimate {
+    /// The size of the session proof, in bytes.
+    pub session: usize,
+    /// The size of the substrings proof, in bytes.
+    pub substrings: usize,
+}
+
+impl ProofSizeEstimate {
+    /// Returns the total size of the proof, in bytes.
+    pub fn total(&self) -> usize {
+        self.session + self.substrings
+    }
+}
@@ -3,14 +3,17 @@
 //! Here the prover deals with a verifier directly, so there is no notary involved. Instead
 //! the verifier directly verifies parts of the transcript.
 
-use super::{state::Prove as ProveState, Prover, ProverError};
+use super::{state::Prove as ProveState, stats::Phase, Prover, ProverError};
 use mpz_garble::{Memory, Prove};
 use mpz_ot::VerifiableOTReceiver;
 use serio::SinkExt as _;
-use tlsn_core::{proof::SessionInfo, transcript::get_value_ids, Direction, ServerName, Transcript};
+use tlsn_core::{
+    msg::SessionPhase, proof::SessionInfo, transcript::get_value_ids, Direction, ServerName,
+    Transcript,
+};
 use utils::range::{RangeSet, RangeUnion};
 
-use tracing::{info, instrument};
+use tracing::{info, instrument, Instrument as _};
 
 impl Prover<ProveState> {
     /// Returns the transcript of the sent requests
@@ -60,13 +63,20 @@ impl Prover<ProveState> {
     }
 
     /// Prove transcript values
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn prove(&mut self) -> Result<(), ProverError> {
+        if self.state.registered_commitments > 0 {
+            return Err(ProverError::UnusedCommitments(
+                self.state.registered_commitments,
+            ));
+        }
+
+        let timer = self.stats.start_phase(Phase::Prove);
         let mut proving_info = std::mem::take(&mut self.state.proving_info);
 
         self.state
             .mux_fut
-            .poll_with(async {
+            .poll_with(self.state.abort.guard(SessionPhase::Prove, async {
                 // Now prove the transcript parts which have been marked for reveal
                 let sent_value_ids = proving_info
                     .sent_ids
@@ -120,19 +130,30 @@ impl Prover<ProveState> {
                 info!("Successfully proved cleartext");
 
                 Ok::<_, ProverError>(())
-            })
-            .await?;
+            }))
+            .instrument(timer.span())
+            .await??;
+
+        timer.finish();
 
         Ok(())
     }
 
     /// Finalize the proving
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn finalize(self) -> Result<(), ProverError> {
+        if self.state.registered_commitments > 0 {
+            return Err(ProverError::UnusedCommitments(
+                self.state.registered_commitments,
+            ));
+        }
+
+        let timer = self.stats.start_phase(Phase::Finalize);
         let ProveState {
             mut io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mut vm,
             mut ot_recv,
             mut ctx,
@@ -147,7 +168,7 @@ impl Prover<ProveState> {
         };
 
         mux_fut
-            .poll_with(async move {
+            .poll_with(abort.guard(SessionPhase::Finalize, async move {
                 ot_recv.accept_reveal(&mut ctx).await?;
 
                 _ = vm
@@ -160,8 +181,9 @@ impl Prover<ProveState> {
                 io.send(session_info).await?;
 
                 Ok::<_, ProverError>(())
-            })
-            .await?;
+            }))
+            .instrument(timer.span())
+            .await??;
 
         // Wait for the verifier to correctly close the connection.
         if !mux_fut.is_complete() {
@@ -169,6 +191,8 @@ impl Prover<ProveState> {
             mux_fut.await?;
         }
 
+        timer.finish();
+
         Ok(())
     }
 }
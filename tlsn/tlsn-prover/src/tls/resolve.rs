@@ -0,0 +1,91 @@
+//! Resolution of the address of the server.
+
+use std::{fmt::Debug, io, net::IpAddr};
+
+use futures::future::{ready, BoxFuture, FutureExt};
+
+/// A resolver of the IP addresses of the server, used instead of the system resolver, e.g. for
+/// split-horizon DNS, DNS-over-HTTPS or to connect to a staging server.
+///
+/// The prover does not open the connection to the server itself, so the addresses are only
+/// returned by [`ProverConfig::resolve_server`](crate::tls::ProverConfig::resolve_server) to be
+/// connected to by the caller. The certificate of the server is always validated against the
+/// [`server_dns`](crate::tls::ProverConfig::server_dns) name, regardless of the addresses it
+/// resolves to.
+pub trait ServerResolver: Debug + Send + Sync {
+    /// Resolves the IP addresses of the server, in order of preference.
+    ///
+    /// # Arguments
+    ///
+    /// * `server_dns` - The DNS name of the server.
+    fn resolve<'a>(&'a self, server_dns: &'a str) -> BoxFuture<'a, io::Result<Vec<IpAddr>>>;
+}
+
+/// A resolver which returns fixed IP addresses for any server name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticResolver {
+    addrs: Vec<IpAddr>,
+}
+
+impl StaticResolver {
+    /// Creates a new resolver returning the given addresses.
+    pub fn new(addrs: impl Into<Vec<IpAddr>>) -> Self {
+        Self {
+            addrs: addrs.into(),
+        }
+    }
+
+    /// Returns the addresses of the resolver.
+    pub fn addrs(&self) -> &[IpAddr] {
+        &self.addrs
+    }
+}
+
+impl ServerResolver for StaticResolver {
+    fn resolve<'a>(&'a self, server_dns: &'a str) -> BoxFuture<'a, io::Result<Vec<IpAddr>>> {
+        let result = if self.addrs.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no address configured for {server_dns}"),
+            ))
+        } else {
+            Ok(self.addrs.clone())
+        };
+
+        ready(result).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::ProverConfig;
+
+    #[test]
+    fn test_resolve_server() {
+        let config = ProverConfig::builder()
+            .id("test")
+            .server_dns("example.com")
+            .build()
+            .unwrap();
+        assert!(futures::executor::block_on(config.resolve_server())
+            .unwrap()
+            .is_none());
+
+        let addr: IpAddr = "10.0.0.1".parse().unwrap();
+        let config = ProverConfig::builder()
+            .id("test")
+            .server_dns("example.com")
+            .server_addr(addr)
+            .build()
+            .unwrap();
+        assert_eq!(config.server_dns(), "example.com");
+        assert_eq!(
+            futures::executor::block_on(config.resolve_server()).unwrap(),
+            Some(vec![addr])
+        );
+
+        let empty = StaticResolver::new(Vec::new());
+        assert!(futures::executor::block_on(empty.resolve("example.com")).is_err());
+    }
+}
@@ -7,10 +7,19 @@ use std::collections::HashMap;
 use tls_core::{handshake::HandshakeData, key::PublicKey};
 use tls_mpc::MpcTlsLeader;
 use tlsn_common::{
+    abort::AbortChannel,
     mux::{MuxControl, MuxFuture},
     Context, DEAPThread, Io, OTReceiver,
 };
-use tlsn_core::{commitment::TranscriptCommitmentBuilder, msg::ProvingInfo, Transcript};
+use tlsn_core::{
+    commitment::{
+        blake3::Blake3Commitment, CommitmentFilter, TranscriptCommitmentBuilder,
+        TranscriptCommitmentBuilderError,
+    },
+    msg::{ProvingInfo, SignedSessionOpenedReceipt},
+    Direction, Transcript,
+};
+use utils::range::RangeSet;
 
 /// Entry state
 pub struct Initialized;
@@ -22,11 +31,15 @@ pub struct Setup {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) mpc_tls: MpcTlsLeader,
     pub(crate) vm: DEAPThread,
     pub(crate) ot_recv: OTReceiver,
     pub(crate) ctx: Context,
+
+    /// The receipt issued by the verifier when the session was opened, if it is a notary.
+    pub(crate) opened_receipt: Option<SignedSessionOpenedReceipt>,
 }
 
 opaque_debug::implement!(Setup);
@@ -36,6 +49,7 @@ pub struct Closed {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_recv: OTReceiver,
@@ -47,6 +61,11 @@ pub struct Closed {
 
     pub(crate) transcript_tx: Transcript,
     pub(crate) transcript_rx: Transcript,
+
+    /// Commitments registered while the connection was open.
+    pub(crate) registered_commitments: Vec<RegisteredCommitment>,
+    /// Filter of secrets which must never be committed to publicly.
+    pub(crate) commitment_filter: Option<CommitmentFilter>,
 }
 
 opaque_debug::implement!(Closed);
@@ -56,6 +75,7 @@ pub struct Notarize {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_recv: OTReceiver,
@@ -69,10 +89,22 @@ pub struct Notarize {
     pub(crate) transcript_rx: Transcript,
 
     pub(crate) builder: TranscriptCommitmentBuilder,
+    /// The error which occurred when adding the commitments registered while the connection was
+    /// open to the builder, returned when finalizing.
+    pub(crate) registration_error: Option<TranscriptCommitmentBuilderError>,
 }
 
 opaque_debug::implement!(Notarize);
 
+/// A commitment registered while the connection was open.
+pub(crate) struct RegisteredCommitment {
+    pub(crate) direction: Direction,
+    pub(crate) ranges: RangeSet<usize>,
+    /// The commitment, if the encodings of the ranges were available while the connection was
+    /// open. Otherwise it is hashed when the commitments are built.
+    pub(crate) commitment: Option<Blake3Commitment>,
+}
+
 impl From<Closed> for Notarize {
     fn from(state: Closed) -> Self {
         let encodings = collect_encodings(&state.vm, &state.transcript_tx, &state.transcript_rx);
@@ -81,16 +113,39 @@ impl From<Closed> for Notarize {
             ids.iter().map(|id| encodings.get(*id).cloned()).collect()
         });
 
-        let builder = TranscriptCommitmentBuilder::new(
+        let mut builder = TranscriptCommitmentBuilder::new(
             encoding_provider,
             state.transcript_tx.data().len(),
             state.transcript_rx.data().len(),
+        )
+        .with_plaintext(
+            state.transcript_tx.data().to_vec(),
+            state.transcript_rx.data().to_vec(),
         );
+        if let Some(filter) = state.commitment_filter {
+            builder = builder.with_filter(filter);
+        }
+
+        // The commitments were validated when they were registered, so adding them should not
+        // fail. Any error is returned when finalizing, as the transition is infallible.
+        let mut registration_error = None;
+        for registered in state.registered_commitments {
+            let result = match registered.commitment {
+                Some(commitment) => {
+                    builder.commit_precomputed(&registered.ranges, registered.direction, commitment)
+                }
+                None => builder.commit(&registered.ranges, registered.direction),
+            };
+            if let Err(err) = result {
+                registration_error.get_or_insert(err);
+            }
+        }
 
         Self {
             io: state.io,
             mux_ctrl: state.mux_ctrl,
             mux_fut: state.mux_fut,
+            abort: state.abort,
             vm: state.vm,
             ot_recv: state.ot_recv,
             ctx: state.ctx,
@@ -100,6 +155,7 @@ impl From<Closed> for Notarize {
             transcript_tx: state.transcript_tx,
             transcript_rx: state.transcript_rx,
             builder,
+            registration_error,
         }
     }
 }
@@ -109,6 +165,7 @@ pub struct Prove {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_recv: OTReceiver,
@@ -120,6 +177,10 @@ pub struct Prove {
     pub(crate) transcript_rx: Transcript,
 
     pub(crate) proving_info: ProvingInfo,
+
+    /// The number of commitments registered while the connection was open, which can not be used
+    /// when proving to a verifier directly.
+    pub(crate) registered_commitments: usize,
 }
 
 impl From<Closed> for Prove {
@@ -128,6 +189,7 @@ impl From<Closed> for Prove {
             io: state.io,
             mux_ctrl: state.mux_ctrl,
             mux_fut: state.mux_fut,
+            abort: state.abort,
             vm: state.vm,
             ot_recv: state.ot_recv,
             ctx: state.ctx,
@@ -135,6 +197,7 @@ impl From<Closed> for Prove {
             transcript_tx: state.transcript_tx,
             transcript_rx: state.transcript_rx,
             proving_info: ProvingInfo::default(),
+            registered_commitments: state.registered_commitments.len(),
         }
     }
 }
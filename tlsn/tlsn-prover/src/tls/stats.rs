@@ -0,0 +1,258 @@
+//! Statistics collected by the prover.
+
+use futures::{AsyncRead, AsyncWrite};
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tls_mpc::MpcTlsTimings;
+use tlsn_common::{
+    aes::AesBackend,
+    stats::{ProtocolStats, ProtocolStatsHandle, Traffic},
+    timing::{SetupTimings, TimingsHandle},
+};
+use tlsn_core::msg::SessionPhase;
+
+/// A phase of the prover protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// MPC setup prior to connecting to the server, see [`Prover::setup`](crate::tls::Prover::setup).
+    Setup,
+    /// The TLS session with the server, from connecting until the connection is closed.
+    TlsSession,
+    /// Proving the revealed parts of the transcript to the verifier.
+    Prove,
+    /// Finalization of the notarization or proof.
+    Finalize,
+}
+
+/// Statistics of a prover, useful to diagnose the performance of a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverStats {
+    /// Number of bytes sent to the verifier.
+    pub bytes_sent_to_notary: u64,
+    /// Number of bytes received from the verifier.
+    pub bytes_recv: u64,
+    /// Number of oblivious transfers extended during setup, both as sender and receiver.
+    pub ot_count: usize,
+    /// Number of circuits evaluated by the record layer and to prove the plaintext hash
+    /// commitments.
+    pub circuits_evaluated: u64,
+    /// Durations of the phases which have completed, in the order they completed.
+    pub phase_durations: Vec<(Phase, Duration)>,
+    /// Breakdown of the durations of the phases of the MPC protocol.
+    pub timings: SetupTimings,
+    /// The AES implementation used for garbling, which is much slower without hardware support.
+    pub aes_backend: AesBackend,
+    /// Counters of the cost of the MPC protocol.
+    pub protocol: ProtocolStats,
+}
+
+impl ProverStats {
+    /// Returns the duration of a phase, if it has completed.
+    pub fn phase_duration(&self, phase: Phase) -> Option<Duration> {
+        self.phase_durations
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|(_, duration)| *duration)
+    }
+}
+
+/// A handle to the statistics of a prover.
+///
+/// The handle remains valid after the prover is consumed, e.g. to inspect the statistics once the
+/// notarization has been finalized.
+#[derive(Debug, Clone, Default)]
+pub struct ProverStatsHandle(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    bytes_sent: AtomicU64,
+    bytes_recv: AtomicU64,
+    ot_count: AtomicU64,
+    phase_durations: Mutex<Vec<(Phase, Duration)>>,
+    timings: TimingsHandle,
+    protocol: ProtocolStatsHandle,
+}
+
+impl ProverStatsHandle {
+    /// Returns a snapshot of the statistics.
+    pub fn get(&self) -> ProverStats {
+        let protocol = self.0.protocol.get();
+        ProverStats {
+            bytes_sent_to_notary: self.0.bytes_sent.load(Ordering::Relaxed),
+            bytes_recv: self.0.bytes_recv.load(Ordering::Relaxed),
+            ot_count: self.0.ot_count.load(Ordering::Relaxed) as usize,
+            circuits_evaluated: protocol.circuits,
+            phase_durations: self.0.phase_durations.lock().unwrap().clone(),
+            timings: self.0.timings.get(),
+            aes_backend: AesBackend::detect(),
+            protocol,
+        }
+    }
+
+    /// Returns a handle to the timings of the session.
+    pub fn timings_handle(&self) -> TimingsHandle {
+        self.0.timings.clone()
+    }
+
+    /// Returns a handle to the counters of the cost of the MPC protocol.
+    pub fn protocol_handle(&self) -> ProtocolStatsHandle {
+        self.0.protocol.clone()
+    }
+
+    pub(crate) fn set_mpc_tls_timings(&self, timings: MpcTlsTimings) {
+        self.0.timings.update(|t| {
+            t.handshake_2pc = Some(timings.handshake);
+            t.record_encryption = Some(timings.record_layer);
+        });
+    }
+
+    pub(crate) fn add_ots(&self, sent: usize, received: usize) {
+        self.0
+            .ot_count
+            .fetch_add((sent + received) as u64, Ordering::Relaxed);
+        self.0.protocol.add_ots(sent, received);
+    }
+
+    /// Starts timing a phase, which is recorded when [`PhaseTimer::finish`] is called.
+    ///
+    /// The work of the phase should be instrumented with [`PhaseTimer::span`].
+    pub(crate) fn start_phase(&self, phase: Phase) -> PhaseTimer {
+        PhaseTimer {
+            stats: self.clone(),
+            phase,
+            span: tracing::info_span!("phase", phase = ?phase),
+            start: web_time::Instant::now(),
+            start_traffic: self.traffic(),
+        }
+    }
+
+    /// Returns the bytes exchanged with the verifier so far.
+    fn traffic(&self) -> Traffic {
+        Traffic {
+            sent: self.0.bytes_sent.load(Ordering::Relaxed),
+            recv: self.0.bytes_recv.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Wraps the socket to the verifier to count the bytes exchanged over it.
+    pub(crate) fn meter<S>(&self, socket: S) -> MeteredSocket<S> {
+        MeteredSocket {
+            inner: socket,
+            stats: self.clone(),
+        }
+    }
+}
+
+/// Timer of a phase of the prover protocol.
+pub(crate) struct PhaseTimer {
+    stats: ProverStatsHandle,
+    phase: Phase,
+    span: tracing::Span,
+    start: web_time::Instant,
+    start_traffic: Traffic,
+}
+
+impl PhaseTimer {
+    /// Returns the tracing span of the phase.
+    pub(crate) fn span(&self) -> tracing::Span {
+        self.span.clone()
+    }
+
+    /// Records the duration of the phase.
+    pub(crate) fn finish(self) {
+        let duration = self.start.elapsed();
+
+        tracing::debug!(parent: &self.span, ?duration, "phase complete");
+
+        match self.phase {
+            Phase::Setup => self
+                .stats
+                .0
+                .timings
+                .update(|t| t.mpc_setup = Some(duration)),
+            Phase::Finalize => self
+                .stats
+                .0
+                .timings
+                .update(|t| t.finalization = Some(duration)),
+            Phase::TlsSession | Phase::Prove => {}
+        }
+
+        let traffic = self.stats.traffic();
+        let session_phase = match self.phase {
+            Phase::Setup => SessionPhase::Setup,
+            Phase::TlsSession => SessionPhase::TlsSession,
+            Phase::Prove => SessionPhase::Prove,
+            Phase::Finalize => SessionPhase::Finalize,
+        };
+        self.stats.0.protocol.add_traffic(
+            session_phase,
+            Traffic {
+                sent: traffic.sent - self.start_traffic.sent,
+                recv: traffic.recv - self.start_traffic.recv,
+            },
+        );
+
+        self.stats
+            .0
+            .phase_durations
+            .lock()
+            .unwrap()
+            .push((self.phase, duration));
+    }
+}
+
+/// A socket which counts the bytes sent and received.
+pub(crate) struct MeteredSocket<S> {
+    inner: S,
+    stats: ProverStatsHandle,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for MeteredSocket<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.stats
+                .0
+                .bytes_recv
+                .fetch_add(n as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for MeteredSocket<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.stats
+                .0
+                .bytes_sent
+                .fetch_add(n as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -0,0 +1,21 @@
+"""Notarize HTTPS requests and verify TLSNotary proofs.
+
+Example::
+
+    import tlsn
+
+    proof = tlsn.notarize(
+        "https://example.com/api",
+        headers={"Authorization": "Bearer secret"},
+        redactions={"sent": ["authorization: Bearer (\\S+)"]},
+        notary_host="notary.example.org",
+        notary_port=443,
+    )
+
+    session = tlsn.verify(proof, open("notary.pub").read())
+    print(session.server_name, session.recv)
+"""
+
+from ._tlsn import TlsnError, VerifiedSession, notarize, verify
+
+__all__ = ["TlsnError", "VerifiedSession", "notarize", "verify"]
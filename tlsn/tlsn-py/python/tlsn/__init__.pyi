@@ -0,0 +1,28 @@
+from typing import Dict, List, Optional, Tuple
+
+class TlsnError(Exception): ...
+
+class VerifiedSession:
+    server_name: str
+    time: int
+    sent: bytes
+    recv: bytes
+    sent_authed: List[Tuple[int, int]]
+    recv_authed: List[Tuple[int, int]]
+
+def notarize(
+    url: str,
+    headers: Optional[Dict[str, str]] = None,
+    redactions: Optional[Dict[str, List[str]]] = None,
+    *,
+    method: str = "GET",
+    body: Optional[str] = None,
+    notary_host: str = "127.0.0.1",
+    notary_port: int = 7047,
+    notary_tls: bool = True,
+    notary_api_key: Optional[str] = None,
+    max_sent_data: Optional[int] = None,
+    max_recv_data: Optional[int] = None,
+) -> bytes: ...
+
+def verify(proof: bytes, notary_pubkey: str) -> VerifiedSession: ...
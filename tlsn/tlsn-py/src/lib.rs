@@ -0,0 +1,292 @@
+//! Python bindings to notarize HTTPS requests and verify TLSNotary proofs.
+//!
+//! The bindings expose two functions, `notarize` and `verify`, so proofs can be produced and
+//! checked from Python scripts without the Rust toolchain.
+
+#![deny(unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::ops::Range;
+
+use p256::pkcs8::DecodePublicKey;
+use pyo3::{
+    create_exception,
+    exceptions::PyException,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+use tlsn_core::proof::{SessionProof, TlsProof};
+use tlsn_notarize::{NotarizeOptions, NotaryOptions};
+
+create_exception!(tlsn, TlsnError, PyException, "An error raised by tlsn.");
+
+/// Notarizes an HTTPS request with a notary server, returning the proof serialized as JSON.
+///
+/// `headers` maps header names to values. `redactions` maps `"sent"` and `"recv"` to lists of
+/// regexes matching data of the request and the response, respectively, to redact from the proof.
+/// If a regex has capture groups, only the groups are redacted.
+#[pyfunction]
+#[pyo3(signature = (
+    url,
+    headers = None,
+    redactions = None,
+    *,
+    method = "GET",
+    body = None,
+    notary_host = "127.0.0.1",
+    notary_port = 7047,
+    notary_tls = true,
+    notary_api_key = None,
+    max_sent_data = None,
+    max_recv_data = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn notarize<'py>(
+    py: Python<'py>,
+    url: String,
+    headers: Option<&Bound<'py, PyDict>>,
+    redactions: Option<&Bound<'py, PyDict>>,
+    method: &str,
+    body: Option<String>,
+    notary_host: &str,
+    notary_port: u16,
+    notary_tls: bool,
+    notary_api_key: Option<String>,
+    max_sent_data: Option<usize>,
+    max_recv_data: Option<usize>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut options = NotarizeOptions::new(url);
+    options.method = method.to_string();
+    options.body = body;
+    options.notary = NotaryOptions {
+        host: notary_host.to_string(),
+        port: notary_port,
+        tls: notary_tls,
+        api_key: notary_api_key,
+    };
+    options.max_sent_data = max_sent_data;
+    options.max_recv_data = max_recv_data;
+
+    if let Some(headers) = headers {
+        // Dictionaries preserve insertion order, so headers are sent in the provided order.
+        for (name, value) in headers.iter() {
+            options.headers.push((name.extract()?, value.extract()?));
+        }
+    }
+
+    if let Some(redactions) = redactions {
+        for (direction, patterns) in redactions.iter() {
+            set_redactions(&mut options, direction.extract()?, patterns.extract()?)
+                .map_err(TlsnError::new_err)?;
+        }
+    }
+
+    // The notarization takes a while, so other Python threads are allowed to run meanwhile.
+    let proof = py.allow_threads(|| {
+        tokio::runtime::Runtime::new()
+            .map_err(|err| TlsnError::new_err(err.to_string()))?
+            .block_on(tlsn_notarize::notarize(options))
+            .map_err(|err| TlsnError::new_err(format!("{err:#}")))
+    })?;
+
+    let proof = serde_json::to_vec(&proof).map_err(|err| TlsnError::new_err(err.to_string()))?;
+
+    Ok(PyBytes::new_bound(py, &proof))
+}
+
+/// Sets the redactions of the data sent or received, depending on `direction`.
+fn set_redactions(
+    options: &mut NotarizeOptions,
+    direction: &str,
+    patterns: Vec<String>,
+) -> Result<(), String> {
+    match direction {
+        "sent" => options.redact_sent = patterns,
+        "recv" => options.redact_recv = patterns,
+        direction => {
+            return Err(format!(
+                "invalid redaction direction {direction:?}, expected \"sent\" or \"recv\""
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// A session verified from a proof.
+///
+/// The bytes which the prover chose not to disclose are set to `b"X"` in `sent` and `recv`.
+#[pyclass(frozen, module = "tlsn")]
+struct VerifiedSession {
+    /// Name of the server.
+    #[pyo3(get)]
+    server_name: String,
+    /// Time of the session, in seconds since the UNIX epoch.
+    #[pyo3(get)]
+    time: u64,
+    sent: Vec<u8>,
+    recv: Vec<u8>,
+    /// Ranges of `sent` which were disclosed, as `(start, end)` tuples.
+    #[pyo3(get)]
+    sent_authed: Vec<(usize, usize)>,
+    /// Ranges of `recv` which were disclosed, as `(start, end)` tuples.
+    #[pyo3(get)]
+    recv_authed: Vec<(usize, usize)>,
+}
+
+#[pymethods]
+impl VerifiedSession {
+    /// Data sent to the server.
+    #[getter]
+    fn sent<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.sent)
+    }
+
+    /// Data received from the server.
+    #[getter]
+    fn recv<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.recv)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "VerifiedSession(server_name={:?}, time={}, sent_len={}, recv_len={})",
+            self.server_name,
+            self.time,
+            self.sent.len(),
+            self.recv.len()
+        )
+    }
+}
+
+/// Verifies a proof serialized as JSON against the PEM encoded public key of the notary.
+///
+/// The identity of the server is verified with the root certificates from the `webpki-roots`
+/// crate.
+#[pyfunction]
+fn verify(proof: &[u8], notary_pubkey: &str) -> PyResult<VerifiedSession> {
+    verify_proof(proof, notary_pubkey).map_err(TlsnError::new_err)
+}
+
+/// Verifies a proof, see [`verify`].
+fn verify_proof(proof: &[u8], notary_pubkey: &str) -> Result<VerifiedSession, String> {
+    let proof: TlsProof =
+        serde_json::from_slice(proof).map_err(|err| format!("invalid proof: {err}"))?;
+    let notary_pubkey = p256::PublicKey::from_public_key_pem(notary_pubkey)
+        .map_err(|err| format!("invalid notary public key: {err}"))?;
+
+    let TlsProof {
+        session,
+        substrings,
+    } = proof;
+
+    session
+        .verify_with_default_cert_verifier(notary_pubkey)
+        .map_err(|err| err.to_string())?;
+
+    let SessionProof {
+        header,
+        session_info,
+        ..
+    } = session;
+
+    let (mut sent, mut recv) = substrings.verify(&header).map_err(|err| err.to_string())?;
+
+    sent.set_redacted(b'X');
+    recv.set_redacted(b'X');
+
+    let ranges = |ranges: Vec<Range<usize>>| {
+        ranges
+            .into_iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    };
+
+    Ok(VerifiedSession {
+        server_name: session_info.server_name.as_str().to_string(),
+        time: header.time(),
+        sent_authed: ranges(sent.authed().iter_ranges().collect()),
+        recv_authed: ranges(recv.authed().iter_ranges().collect()),
+        sent: sent.data().to_vec(),
+        recv: recv.data().to_vec(),
+    })
+}
+
+#[pymodule]
+#[pyo3(name = "_tlsn")]
+fn tlsn_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(notarize, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_class::<VerifiedSession>()?;
+    m.add("TlsnError", m.py().get_type_bound::<TlsnError>())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::pkcs8::{EncodePublicKey, LineEnding};
+    use tlsn_core::dry_run::{dry_run_notary_public_key, DryRun};
+
+    fn proof() -> Vec<u8> {
+        let mut dry_run = DryRun::new(
+            b"GET / HTTP/1.1\r\n\r\n".to_vec(),
+            b"HTTP/1.1 200 OK\r\n\r\nsecret".to_vec(),
+        );
+        let sent = dry_run.commitment_builder().commit_sent(&(0..18)).unwrap();
+        let recv = dry_run.commitment_builder().commit_recv(&(0..19)).unwrap();
+        let session = dry_run.finalize().unwrap();
+
+        let mut builder = session.data().build_substrings_proof();
+        builder
+            .reveal_by_id(sent)
+            .unwrap()
+            .reveal_by_id(recv)
+            .unwrap();
+        let proof = TlsProof {
+            session: session.session_proof(),
+            substrings: builder.build().unwrap(),
+        };
+
+        serde_json::to_vec(&proof).unwrap()
+    }
+
+    fn pem(key: p256::PublicKey) -> String {
+        key.to_public_key_pem(LineEnding::LF).unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        let session = verify_proof(&proof(), &pem(dry_run_notary_public_key())).unwrap();
+
+        assert_eq!(session.server_name, "tlsnotary.org");
+        assert_eq!(session.sent, b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(session.recv, b"HTTP/1.1 200 OK\r\n\r\nXXXXXX");
+        assert_eq!(session.sent_authed, vec![(0, 18)]);
+        assert_eq!(session.recv_authed, vec![(0, 19)]);
+    }
+
+    #[test]
+    fn test_verify_rejects_other_notary() {
+        let other_key =
+            p256::PublicKey::from(*tlsn_core::fixtures::notary_signing_key().verifying_key());
+
+        assert!(verify_proof(&proof(), &pem(other_key)).is_err());
+        assert!(verify_proof(&proof(), "not a key").is_err());
+        assert!(verify_proof(b"{}", &pem(dry_run_notary_public_key())).is_err());
+    }
+
+    #[test]
+    fn test_set_redactions() {
+        let mut options = NotarizeOptions::new("https://example.com");
+
+        set_redactions(&mut options, "sent", vec!["a".to_string()]).unwrap();
+        set_redactions(&mut options, "recv", vec!["b".to_string()]).unwrap();
+        assert_eq!(options.redact_sent, ["a"]);
+        assert_eq!(options.redact_recv, ["b"]);
+
+        assert!(set_redactions(&mut options, "received", Vec::new()).is_err());
+    }
+}
@@ -5,7 +5,8 @@ use std::{
 
 use axum::{
     extract::{Query, State},
-    response::{Html, Json},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse, Json},
     routing::get,
     Router,
 };
@@ -33,6 +34,8 @@ pub static SERVER_CERT_DER: &[u8] = include_bytes!("tls/test_server_cert.der");
 pub static SERVER_KEY_DER: &[u8] = include_bytes!("tls/test_server_private_key.der");
 /// The domain name bound to the server certificate.
 pub static SERVER_DOMAIN: &str = "test-server.io";
+/// The bearer token accepted by the `/api/messages` endpoint.
+pub static API_TOKEN: &str = "tlsn-fixture-token";
 
 struct AppState {
     shutdown: Option<oneshot::Sender<()>>,
@@ -44,6 +47,7 @@ fn app(state: AppState) -> Router {
         .route("/bytes", get(bytes))
         .route("/formats/json", get(json))
         .route("/formats/html", get(html))
+        .route("/api/messages", get(messages))
         .with_state(Arc::new(Mutex::new(state)))
 }
 
@@ -132,3 +136,28 @@ async fn html(
 
     Html(include_str!("data/4kb.html"))
 }
+
+async fn messages(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, StatusCode> {
+    if params.get("shutdown").is_some() {
+        _ = state.lock().unwrap().shutdown.take().unwrap().send(());
+    }
+
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token == API_TOKEN);
+
+    if !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/json")],
+        include_str!("data/messages.json"),
+    ))
+}
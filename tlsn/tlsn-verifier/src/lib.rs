@@ -4,4 +4,7 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod tls;
+pub mod trust;
@@ -0,0 +1,96 @@
+//! In-process notary for testing.
+//!
+//! [`MockNotary`] runs the notary role in the same process as the prover, connected over an
+//! in-memory duplex channel, so that application code using the prover can be tested without a
+//! notary server or any networking.
+
+use futures::Future;
+use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use tlsn_core::SessionHeader;
+use tokio::io::DuplexStream;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::tls::{Verifier, VerifierConfig, VerifierError};
+
+/// Secret key of the mock notary's deterministic signing key.
+pub const MOCK_NOTARY_KEY: [u8; 32] = [1u8; 32];
+
+/// Size of the in-memory buffer of the channel between the prover and the mock notary.
+const CHANNEL_BUFFER_SIZE: usize = 1 << 23;
+
+/// The prover's end of the channel to a [`MockNotary`].
+pub type MockNotaryIo = Compat<DuplexStream>;
+
+/// An in-process notary for testing.
+pub struct MockNotary {
+    config: VerifierConfig,
+    signing_key: SigningKey,
+}
+
+impl MockNotary {
+    /// Creates a new mock notary with the default verifier configuration.
+    pub fn new() -> Self {
+        Self::with_config(
+            VerifierConfig::builder()
+                .id("mock-notary")
+                .build()
+                .expect("default config is valid"),
+        )
+    }
+
+    /// Creates a new mock notary with the provided verifier configuration.
+    ///
+    /// The limits of the configuration must match the ones of the prover.
+    pub fn with_config(config: VerifierConfig) -> Self {
+        Self {
+            config,
+            signing_key: SigningKey::from_bytes(&MOCK_NOTARY_KEY.into())
+                .expect("mock notary key is valid"),
+        }
+    }
+
+    /// Returns the signing key of the notary.
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+
+    /// Returns the verifying key of the notary, used to verify notarized sessions.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        *self.signing_key.verifying_key()
+    }
+
+    /// Binds the notary to a new in-memory channel.
+    ///
+    /// Returns the prover's end of the channel, to be passed to the prover's `setup`, and a future
+    /// which runs the notarization. The future must be polled concurrently with the prover, and
+    /// returns the signed session header once the prover has finalized the notarization.
+    pub fn bind(
+        self,
+    ) -> (
+        MockNotaryIo,
+        impl Future<Output = Result<SessionHeader, VerifierError>> + Send + 'static,
+    ) {
+        let (prover_io, notary_io) = tokio::io::duplex(CHANNEL_BUFFER_SIZE);
+
+        let Self {
+            config,
+            signing_key,
+        } = self;
+
+        let fut = async move {
+            Verifier::new(config)
+                .notarize::<_, Signature>(notary_io.compat(), &signing_key)
+                .await
+        };
+
+        (prover_io.compat(), fut)
+    }
+}
+
+impl Default for MockNotary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+opaque_debug::implement!(MockNotary);
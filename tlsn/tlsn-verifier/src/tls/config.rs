@@ -1,12 +1,25 @@
 use mpz_ot::{chou_orlandi, kos};
-use std::fmt::{Debug, Formatter, Result};
+use std::{
+    fmt::{Debug, Formatter, Result},
+    sync::Arc,
+    time::Duration,
+};
 use tls_core::verify::{ServerCertVerifier, WebPkiVerifier};
 use tls_mpc::{MpcTlsCommonConfig, MpcTlsFollowerConfig, TranscriptConfig};
+#[cfg(feature = "seeded-rng")]
+use tlsn_common::rng::{derive_seed, MPC_TLS_RNG_STREAM};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
-    Role,
+    config::{DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT, DEFAULT_MPC_CONCURRENCY},
+    mux::MuxConfig,
+    setup::SessionParams,
 };
-use tlsn_core::proof::default_cert_verifier;
+use tlsn_core::{msg::SessionLimits, proof::default_cert_verifier};
+
+use crate::tls::{log::SessionLog, time::TimeSource};
+
+/// The default maximum difference between the times of the TLS records committed to by the Prover
+/// and the times observed by the Verifier.
+pub const DEFAULT_MAX_RECORD_TIME_SKEW: Duration = Duration::from_secs(5);
 
 /// Configuration for the [`Verifier`](crate::tls::Verifier).
 #[allow(missing_docs)]
@@ -27,6 +40,74 @@ pub struct VerifierConfig {
         default = "Some(default_cert_verifier())"
     )]
     cert_verifier: Option<WebPkiVerifier>,
+    /// Source of the timestamp embedded in the session header. If not set, the session header
+    /// is not timestamped.
+    #[builder(setter(strip_option), default)]
+    time_source: Option<Arc<dyn TimeSource>>,
+    /// Log in which the header of every session is recorded before it is signed. If not set, the
+    /// sessions are not logged.
+    #[builder(setter(strip_option), default)]
+    session_log: Option<Arc<dyn SessionLog>>,
+    /// Identifier of the signing key, which is embedded in the session header. If not set, the
+    /// session header does not identify the key.
+    #[builder(setter(into, strip_option), default)]
+    key_id: Option<String>,
+    /// DID of the Notary, which is embedded in the session header. If not set, the session header
+    /// does not reference the Notary by a DID.
+    #[builder(setter(into, strip_option), default)]
+    notary_did: Option<String>,
+    /// DNS name of the server the Prover declared, which is embedded in the session header so
+    /// that proofs of the session are only valid for this server. If not set, the session header
+    /// does not restrict the server.
+    #[builder(setter(into, strip_option), default)]
+    server_name: Option<String>,
+    /// Whether to compress the connection to the Prover, if the Prover supports it.
+    #[builder(default)]
+    compression: bool,
+    /// Configuration of the multiplexer of the connection to the Prover.
+    #[builder(default)]
+    mux_config: MuxConfig,
+    /// Maximum difference between the times of the TLS records committed to by the Prover and the
+    /// times observed by the Verifier.
+    #[builder(default = "DEFAULT_MAX_RECORD_TIME_SKEW")]
+    max_record_time_skew: Duration,
+    /// Maximum number of threads the MPC executor forks to run circuits and OT batches
+    /// simultaneously. The lowest of the concurrencies of the Prover and the Verifier is used.
+    #[builder(default = "DEFAULT_MPC_CONCURRENCY")]
+    mpc_concurrency: usize,
+    /// Maximum number of TLS records the Prover can send and receive in the session. If not set,
+    /// the session is unlimited unless the Prover sets a limit, in which case at most
+    /// [`DEFAULT_RECORD_BUDGET`](tlsn_common::ot_pool::DEFAULT_RECORD_BUDGET) records are
+    /// allowed.
+    ///
+    /// The pool of oblivious transfers is preprocessed during setup for the lowest of the limits
+    /// of the Prover and the Verifier, which is also the limit enforced during the session.
+    #[builder(setter(into, strip_option), default)]
+    max_records: Option<usize>,
+    /// Maximum number of oblivious transfers the Verifier performs in the session. Unlimited if
+    /// not set.
+    ///
+    /// The OTs are preprocessed during setup and the OT extension performs no more than were
+    /// preprocessed, so the setup fails if the pool the session requires exceeds this limit.
+    #[builder(setter(into, strip_option), default)]
+    max_ots: Option<usize>,
+    /// Maximum duration of the session, after which it is aborted. Unlimited if not set.
+    #[builder(setter(into, strip_option), default)]
+    max_session_duration: Option<Duration>,
+    /// Seed of the randomness of the session, only settable with the `seeded-rng` feature.
+    #[builder(setter(custom), default)]
+    rng_seed: Option<[u8; 32]>,
+}
+
+impl VerifierConfigBuilder {
+    /// Seeds the randomness of the session, so that protocol runs are reproducible.
+    ///
+    /// This is insecure and must only be used in tests.
+    #[cfg(feature = "seeded-rng")]
+    pub fn rng_seed(mut self, seed: [u8; 32]) -> Self {
+        self.rng_seed = Some(Some(seed));
+        self
+    }
 }
 
 impl Debug for VerifierConfig {
@@ -36,6 +117,19 @@ impl Debug for VerifierConfig {
             .field("max_sent_data", &self.max_sent_data)
             .field("max_recv_data", &self.max_recv_data)
             .field("cert_verifier", &"_")
+            .field("time_source", &self.time_source)
+            .field("session_log", &self.session_log)
+            .field("key_id", &self.key_id)
+            .field("notary_did", &self.notary_did)
+            .field("server_name", &self.server_name)
+            .field("compression", &self.compression)
+            .field("mux_config", &self.mux_config)
+            .field("max_record_time_skew", &self.max_record_time_skew)
+            .field("mpc_concurrency", &self.mpc_concurrency)
+            .field("max_records", &self.max_records)
+            .field("max_ots", &self.max_ots)
+            .field("max_session_duration", &self.max_session_duration)
+            .field("rng_seed", &self.rng_seed.map(|_| "_"))
             .finish()
     }
 }
@@ -68,6 +162,77 @@ impl VerifierConfig {
             .expect("Certificate verifier should be set")
     }
 
+    /// Returns the source of the timestamp embedded in the session header, if any.
+    pub fn time_source(&self) -> Option<&Arc<dyn TimeSource>> {
+        self.time_source.as_ref()
+    }
+
+    /// Returns the log in which the session header is recorded before it is signed, if any.
+    pub fn session_log(&self) -> Option<&Arc<dyn SessionLog>> {
+        self.session_log.as_ref()
+    }
+
+    /// Returns the identifier of the signing key embedded in the session header, if any.
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    /// Returns the DID of the Notary embedded in the session header, if any.
+    pub fn notary_did(&self) -> Option<&str> {
+        self.notary_did.as_deref()
+    }
+
+    /// Returns the DNS name of the server embedded in the session header, if any.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// Returns whether the connection to the Prover is compressed if the Prover supports it.
+    pub fn compression(&self) -> bool {
+        self.compression
+    }
+
+    /// Returns the configuration of the multiplexer.
+    pub fn mux_config(&self) -> &MuxConfig {
+        &self.mux_config
+    }
+
+    /// Returns the maximum difference between the times of the TLS records committed to by the
+    /// Prover and the times observed by the Verifier.
+    pub fn max_record_time_skew(&self) -> Duration {
+        self.max_record_time_skew
+    }
+
+    /// Returns the maximum number of threads the MPC executor forks.
+    pub fn mpc_concurrency(&self) -> usize {
+        self.mpc_concurrency
+    }
+
+    /// Returns the maximum number of TLS records the Prover can send and receive, if any.
+    pub fn max_records(&self) -> Option<usize> {
+        self.max_records
+    }
+
+    /// Returns the maximum number of oblivious transfers the Verifier performs, if any.
+    pub fn max_ots(&self) -> Option<usize> {
+        self.max_ots
+    }
+
+    /// Returns the maximum duration of the session, if any.
+    pub fn max_session_duration(&self) -> Option<Duration> {
+        self.max_session_duration
+    }
+
+    /// Returns the limits of the session, as signed in session opened receipts.
+    pub(crate) fn session_limits(&self) -> SessionLimits {
+        SessionLimits {
+            max_sent_data: self.max_sent_data,
+            max_recv_data: self.max_recv_data,
+            max_records: self.max_records,
+            max_session_duration_secs: self.max_session_duration.map(|duration| duration.as_secs()),
+        }
+    }
+
     pub(crate) fn build_base_ot_sender_config(&self) -> chou_orlandi::SenderConfig {
         chou_orlandi::SenderConfig::default()
     }
@@ -90,36 +255,38 @@ impl VerifierConfig {
         kos::ReceiverConfig::default()
     }
 
-    pub(crate) fn build_mpc_tls_config(&self) -> MpcTlsFollowerConfig {
-        MpcTlsFollowerConfig::builder()
-            .common(
-                MpcTlsCommonConfig::builder()
-                    .id(format!("{}/mpc_tls", &self.id))
-                    .tx_config(
-                        TranscriptConfig::default_tx()
-                            .max_size(self.max_sent_data)
-                            .build()
-                            .unwrap(),
-                    )
-                    .rx_config(
-                        TranscriptConfig::default_rx()
-                            .max_size(self.max_recv_data)
-                            .build()
-                            .unwrap(),
-                    )
-                    .handshake_commit(true)
+    /// Returns the seed of the randomness of the session, if any.
+    pub(crate) fn rng_seed(&self) -> Option<&[u8; 32]> {
+        self.rng_seed.as_ref()
+    }
+
+    /// Builds the MPC-TLS config for the parameters negotiated with the Prover.
+    pub(crate) fn build_mpc_tls_config(&self, params: &SessionParams) -> MpcTlsFollowerConfig {
+        let mut common = MpcTlsCommonConfig::builder();
+        common
+            .id(format!("{}/mpc_tls", &self.id))
+            .tx_config(
+                TranscriptConfig::default_tx()
+                    .max_size(params.max_sent_data)
+                    .build()
+                    .unwrap(),
+            )
+            .rx_config(
+                TranscriptConfig::default_rx()
+                    .max_size(params.max_recv_data)
                     .build()
                     .unwrap(),
             )
+            .handshake_commit(true);
+        #[cfg(feature = "seeded-rng")]
+        if let Some(seed) = &self.rng_seed {
+            common.rng_seed(derive_seed(Some(seed), MPC_TLS_RNG_STREAM));
+        }
+
+        MpcTlsFollowerConfig::builder()
+            .common(common.build().unwrap())
+            .max_records(params.max_records)
             .build()
             .unwrap()
     }
-
-    pub(crate) fn ot_sender_setup_count(&self) -> usize {
-        ot_send_estimate(Role::Verifier, self.max_sent_data, self.max_recv_data)
-    }
-
-    pub(crate) fn ot_receiver_setup_count(&self) -> usize {
-        ot_recv_estimate(Role::Verifier, self.max_sent_data, self.max_recv_data)
-    }
 }
@@ -1,5 +1,9 @@
 use std::error::Error;
 use tls_mpc::MpcTlsError;
+use tlsn_common::{
+    abort::Aborted,
+    error::{ClassifiedError, ErrorCategory},
+};
 
 /// An error that can occur during TLS verification.
 #[derive(Debug, thiserror::Error)]
@@ -13,6 +17,62 @@ pub enum VerifierError {
     MpcError(Box<dyn Error + Send + Sync + 'static>),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("invalid plaintext hashes: {0}")]
+    InvalidPlaintextHashes(String),
+    #[error("invalid session binding: {0}")]
+    InvalidSessionBinding(String),
+    #[error("invalid record timings: {0}")]
+    InvalidRecordTimings(String),
+    #[error("invalid record order: {0}")]
+    InvalidRecordOrder(String),
+    #[error(transparent)]
+    TimeSourceError(#[from] crate::tls::time::TimeSourceError),
+    #[error(transparent)]
+    SessionLogError(#[from] crate::tls::log::SessionLogError),
+    #[error("session quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error(transparent)]
+    Aborted(#[from] Aborted),
+}
+
+impl ClassifiedError for VerifierError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            Self::IOError(e) => ErrorCategory::from_io(e),
+            Self::MuxerError(_) => ErrorCategory::NetworkTransient,
+            Self::MpcError(e) => match e.downcast_ref::<MpcTlsError>() {
+                Some(e) => e.category(),
+                None => {
+                    ErrorCategory::from_source_chain(e.as_ref(), ErrorCategory::ProtocolViolation)
+                }
+            },
+            Self::InvalidRange
+            | Self::InvalidPlaintextHashes(_)
+            | Self::InvalidSessionBinding(_)
+            | Self::InvalidRecordTimings(_)
+            | Self::InvalidRecordOrder(_) => ErrorCategory::ProtocolViolation,
+            Self::TimeSourceError(_) | Self::SessionLogError(_) => ErrorCategory::Internal,
+            Self::QuotaExceeded(_) => ErrorCategory::ResourceLimit,
+            Self::Aborted(e) => e.category(),
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::IOError(_) => "verifier.io",
+            Self::MuxerError(_) => "verifier.muxer",
+            Self::MpcError(_) => "verifier.mpc",
+            Self::InvalidRange => "verifier.invalid_range",
+            Self::InvalidPlaintextHashes(_) => "verifier.invalid_plaintext_hashes",
+            Self::InvalidSessionBinding(_) => "verifier.invalid_session_binding",
+            Self::InvalidRecordTimings(_) => "verifier.invalid_record_timings",
+            Self::InvalidRecordOrder(_) => "verifier.invalid_record_order",
+            Self::TimeSourceError(_) => "verifier.time_source",
+            Self::SessionLogError(_) => "verifier.session_log",
+            Self::QuotaExceeded(_) => "verifier.quota_exceeded",
+            Self::Aborted(e) => e.code(),
+        }
+    }
 }
 
 impl From<uid_mux::yamux::ConnectionError> for VerifierError {
@@ -32,7 +92,11 @@ impl From<mpz_common::ContextError> for VerifierError {
 
 impl From<MpcTlsError> for VerifierError {
     fn from(e: MpcTlsError) -> Self {
-        Self::MpcError(Box::new(e))
+        if e.is_quota_exceeded() {
+            Self::QuotaExceeded(e.msg().to_string())
+        } else {
+            Self::MpcError(Box::new(e))
+        }
     }
 }
 
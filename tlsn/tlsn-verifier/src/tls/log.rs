@@ -0,0 +1,31 @@
+//! Logs of the sessions notarized by the Notary.
+
+use std::{error::Error, fmt::Debug};
+
+use futures::future::BoxFuture;
+use tlsn_core::SessionHeader;
+
+/// An error returned by a [`SessionLog`].
+#[derive(Debug, thiserror::Error)]
+#[error("session log error: {0}")]
+pub struct SessionLogError(Box<dyn Error + Send + Sync + 'static>);
+
+impl SessionLogError {
+    /// Creates a new session log error.
+    pub fn new(err: impl Into<Box<dyn Error + Send + Sync + 'static>>) -> Self {
+        Self(err.into())
+    }
+}
+
+/// A log of the sessions notarized by the Notary, e.g. a transparency log.
+///
+/// The header of a session is recorded before it is signed. If recording fails the header is not
+/// signed, so every signed header is recorded in the log.
+pub trait SessionLog: Debug + Send + Sync {
+    /// Records the header of a session which is about to be signed.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The complete header of the session.
+    fn record(&self, header: &SessionHeader) -> BoxFuture<'_, Result<(), SessionLogError>>;
+}
@@ -2,30 +2,53 @@
 
 pub(crate) mod config;
 mod error;
+pub mod log;
 mod notarize;
 pub mod state;
+pub mod time;
 mod verify;
 
 pub use config::{VerifierConfig, VerifierConfigBuilder, VerifierConfigBuilderError};
 pub use error::VerifierError;
-use mpz_common::Allocate;
-use serio::StreamExt;
+use mpz_common::{Allocate, Preprocess};
+use serio::{stream::IoStreamExt as _, SinkExt as _, StreamExt};
+pub use tlsn_common::{
+    abort::{AbortHandle, AbortOrigin, Aborted},
+    aes::AesBackend,
+    error::{ClassifiedError, ErrorCategory},
+    stats::{ProtocolStats, ProtocolStatsHandle, Traffic},
+    timing::{SetupTimings, TimingsHandle},
+};
 use uid_mux::FramedUidMux;
+use zeroize::Zeroizing;
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 use futures::{AsyncRead, AsyncWrite, TryFutureExt};
 use mpz_garble::config::Role as DEAPRole;
-use mpz_ot::{chou_orlandi, kos};
-use rand::Rng;
+use mpz_ot::{chou_orlandi, kos, OTSetup};
 use signature::Signer;
 use state::{Notarize, Verify};
-use tls_mpc::{build_components, MpcTlsFollower, MpcTlsFollowerData, TlsRole};
+use tls_mpc::{build_components, CircuitCounter, MpcTlsFollower, MpcTlsFollowerData, TlsRole};
 use tlsn_common::{
+    abort::{AbortChannel, ABORT_STREAM_ID},
     mux::{attach_mux, MuxControl},
+    ot_pool::OtPoolSize,
+    rng::{derive_seed, VM_RNG_STREAM},
+    schedule::StreamPriorities,
+    setup::{self, SessionParams, SetupParams},
     DEAPThread, Executor, OTReceiver, OTSender, Role,
 };
-use tlsn_core::{proof::SessionInfo, RedactedTranscript, SessionHeader, Signature};
+use tlsn_core::{
+    msg::{SessionOpenedReceipt, SessionPhase, SignedSessionOpenedReceipt},
+    proof::SessionInfo,
+    session::MAX_AUDIENCE_LEN,
+    session::{RecordOrder, RecordTimings},
+    RedactedTranscript, SessionHeader, Signature,
+};
 
 use tracing::{debug, info, instrument};
 
@@ -33,6 +56,33 @@ use tracing::{debug, info, instrument};
 pub struct Verifier<T: state::VerifierState> {
     config: VerifierConfig,
     state: T,
+    abort: AbortHandle,
+    timings: TimingsHandle,
+    protocol_stats: ProtocolStatsHandle,
+}
+
+impl<T: state::VerifierState> Verifier<T> {
+    /// Returns a handle to abort the session.
+    ///
+    /// Aborting notifies the prover, and the verifier returns [`VerifierError::Aborted`] from the
+    /// phase it is running, or the next phase it runs.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+
+    /// Returns a handle to the timings of the phases of the session.
+    ///
+    /// The handle remains valid after the session is finalized.
+    pub fn timings_handle(&self) -> TimingsHandle {
+        self.timings.clone()
+    }
+
+    /// Returns a handle to the counters of the cost of the MPC protocol.
+    ///
+    /// The handle remains valid after the session is finalized.
+    pub fn protocol_stats_handle(&self) -> ProtocolStatsHandle {
+        self.protocol_stats.clone()
+    }
 }
 
 impl Verifier<state::Initialized> {
@@ -40,10 +90,53 @@ impl Verifier<state::Initialized> {
     pub fn new(config: VerifierConfig) -> Self {
         Self {
             config,
-            state: state::Initialized,
+            state: state::Initialized::default(),
+            abort: AbortHandle::new(),
+            timings: TimingsHandle::default(),
+            protocol_stats: ProtocolStatsHandle::default(),
         }
     }
 
+    /// Issues signed receipts when the session is aborted by either party.
+    ///
+    /// The receipt is sent to the prover, which can use it to prove that the notary acknowledged
+    /// the abort.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The signer used to sign the receipts.
+    pub fn with_abort_receipts<S, T>(mut self, signer: S) -> Self
+    where
+        S: Signer<T> + Send + Sync + 'static,
+        T: Into<Signature> + 'static,
+    {
+        self.state.receipt_signer = Some(Arc::new(move |msg: &[u8]| {
+            Signer::<T>::sign(&signer, msg).into()
+        }));
+        self
+    }
+
+    /// Issues a signed receipt when the session is opened, once the MPC setup has completed.
+    ///
+    /// The receipt is sent to the prover, which can use it to prove that it attempted the
+    /// notarization even if the session fails later, see [`SessionOpenedReceipt`]. Whether the
+    /// receipt is exchanged is negotiated during the setup, the prover only sends its audience
+    /// when a receipt is issued.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The signer used to sign the receipts.
+    pub fn with_session_receipts<S, T>(mut self, signer: S) -> Self
+    where
+        S: Signer<T> + Send + Sync + 'static,
+        T: Into<Signature> + 'static,
+    {
+        self.state.opened_receipt_signer = Some(Arc::new(move |msg: &[u8]| {
+            Signer::<T>::sign(&signer, msg).into()
+        }));
+        self
+    }
+
     /// Sets up the verifier.
     ///
     /// This performs all MPC setup.
@@ -51,49 +144,153 @@ impl Verifier<state::Initialized> {
     /// # Arguments
     ///
     /// * `socket` - The socket to the prover.
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn setup<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         self,
         socket: S,
     ) -> Result<Verifier<state::Setup>, VerifierError> {
-        let (mut mux_fut, mux_ctrl) = attach_mux(socket, Role::Verifier);
+        let start = Instant::now();
+        let (socket, session_params) = setup::negotiate(
+            socket,
+            SetupParams {
+                compression: self.config.compression(),
+                concurrency: self.config.mpc_concurrency(),
+                session_receipts: self.state.opened_receipt_signer.is_some(),
+                max_sent_data: self.config.max_sent_data(),
+                max_recv_data: self.config.max_recv_data(),
+                max_records: self.config.max_records(),
+            },
+        )
+        .await?;
+        debug!(?session_params, "negotiated session parameters");
+        let ot_pool = OtPoolSize::for_session(Role::Verifier, &session_params)
+            .map_err(|err| VerifierError::QuotaExceeded(err.to_string()))?;
+        debug!(?ot_pool, "sized ot pool");
+        let (mut mux_fut, mux_ctrl) = attach_mux(socket, Role::Verifier, self.config.mux_config());
 
-        // Maximum thread forking concurrency of 8.
-        // TODO: Determine the optimal number of threads.
-        let mut exec = Executor::new(mux_ctrl.clone(), 8);
+        let mut exec = Executor::new(mux_ctrl.clone(), session_params.concurrency);
 
-        let encoder_seed: [u8; 32] = rand::rngs::OsRng.gen();
-        let (mpc_tls, vm, ot_send) = mux_fut
-            .poll_with(setup_mpc_backend(
-                &self.config,
-                &mux_ctrl,
-                &mut exec,
-                encoder_seed,
-            ))
-            .await?;
-
-        let io = mux_fut
+        let abort_io = mux_fut
             .poll_with(
                 mux_ctrl
-                    .open_framed(b"tlsnotary")
+                    .open_framed(ABORT_STREAM_ID)
                     .map_err(VerifierError::from),
             )
             .await?;
+        let priorities = mux_fut.priorities();
+        if let Some(priorities) = &priorities {
+            priorities.register(ABORT_STREAM_ID, &abort_io);
+        }
+        let mut abort = AbortChannel::new(
+            abort_io,
+            Role::Verifier,
+            self.config.id(),
+            self.abort.clone(),
+        )
+        .with_receipt_signer(self.state.receipt_signer)
+        .with_deadline(
+            self.config
+                .max_session_duration()
+                .map(|duration| start + duration),
+        );
+
+        let encoder_seed = Zeroizing::new(derive_seed(self.config.rng_seed(), VM_RNG_STREAM));
+        let (mpc_tls, vm, ot_send) = mux_fut
+            .poll_with(abort.guard(
+                SessionPhase::Setup,
+                setup_mpc_backend(
+                    &self.config,
+                    &session_params,
+                    ot_pool,
+                    &mux_ctrl,
+                    priorities.as_ref(),
+                    &mut exec,
+                    &encoder_seed,
+                    &self.timings,
+                    self.protocol_stats.circuit_counter(),
+                ),
+            ))
+            .await??;
+
+        let mut io = mux_fut
+            .poll_with(
+                abort.guard(
+                    SessionPhase::Setup,
+                    mux_ctrl
+                        .open_framed(b"tlsnotary")
+                        .map_err(VerifierError::from),
+                ),
+            )
+            .await??;
+        if let Some(priorities) = &priorities {
+            priorities.register(b"tlsnotary", &io);
+        }
+
+        let opened_receipt = match self.state.opened_receipt_signer {
+            Some(signer) if session_params.session_receipts => {
+                let id = self.config.id().to_string();
+                let limits = self.config.session_limits();
+                let receipt = mux_fut
+                    .poll_with(abort.guard(SessionPhase::Setup, async {
+                        let audience: Option<String> = io.expect_next().await?;
+                        if let Some(audience) = &audience {
+                            if audience.len() > MAX_AUDIENCE_LEN {
+                                return Err(VerifierError::InvalidSessionBinding(format!(
+                                    "audience is {} bytes long, exceeding the maximum of {MAX_AUDIENCE_LEN}",
+                                    audience.len()
+                                )));
+                            }
+                        }
+
+                        let receipt = SessionOpenedReceipt {
+                            id,
+                            audience,
+                            limits,
+                            time: SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .unwrap()
+                                .as_secs(),
+                        };
+                        let signature = signer(&receipt.signed_bytes());
+                        let receipt = SignedSessionOpenedReceipt { receipt, signature };
+                        io.send(receipt.clone()).await?;
+
+                        Ok(receipt)
+                    }))
+                    .await??;
+
+                Some(receipt)
+            }
+            _ => None,
+        };
 
         let ctx = mux_fut
-            .poll_with(exec.new_thread().map_err(VerifierError::from))
-            .await?;
+            .poll_with(abort.guard(
+                SessionPhase::Setup,
+                exec.new_thread().map_err(VerifierError::from),
+            ))
+            .await??;
+
+        let duration = start.elapsed();
+        self.timings.update(|t| t.mpc_setup = Some(duration));
+        self.protocol_stats.add_ots(ot_pool.send, ot_pool.recv);
 
         Ok(Verifier {
             config: self.config,
+            abort: self.abort,
+            timings: self.timings,
+            protocol_stats: self.protocol_stats,
             state: state::Setup {
                 io,
                 mux_ctrl,
                 mux_fut,
+                abort,
                 mpc_tls,
                 vm,
                 ot_send,
                 ctx,
                 encoder_seed,
+                opened_receipt,
             },
         })
     }
@@ -143,17 +340,26 @@ impl Verifier<state::Initialized> {
 }
 
 impl Verifier<state::Setup> {
+    /// Returns the receipt issued when the session was opened, if any, e.g. to keep a record of
+    /// the sessions opened by a notary.
+    pub fn opened_receipt(&self) -> Option<&SignedSessionOpenedReceipt> {
+        self.state.opened_receipt.as_ref()
+    }
+
     /// Runs the verifier until the TLS connection is closed.
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn run(self) -> Result<Verifier<state::Closed>, VerifierError> {
         let state::Setup {
             io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mpc_tls,
             vm,
             ot_send,
             ctx,
             encoder_seed,
+            ..
         } = self.state;
 
         let start_time = SystemTime::now()
@@ -166,21 +372,39 @@ impl Verifier<state::Setup> {
             server_key: server_ephemeral_key,
             bytes_sent: sent_len,
             bytes_recv: recv_len,
+            timings,
+            sent_record_times,
+            recv_record_times,
+            sent_record_lens,
+            recv_record_lens,
+            recv_record_order,
         } = mux_fut
-            .poll_with(mpc_tls.run().1.map_err(VerifierError::from))
-            .await?;
+            .poll_with(abort.guard(
+                SessionPhase::TlsSession,
+                mpc_tls.run().1.map_err(VerifierError::from),
+            ))
+            .await??;
 
         info!("Finished TLS session");
 
+        self.timings.update(|t| {
+            t.handshake_2pc = Some(timings.handshake);
+            t.record_encryption = Some(timings.record_layer);
+        });
+
         // TODO: We should be able to skip this commitment and verify the handshake directly.
         let handshake_commitment = handshake_commitment.expect("handshake commitment is set");
 
         Ok(Verifier {
             config: self.config,
+            abort: self.abort,
+            timings: self.timings,
+            protocol_stats: self.protocol_stats,
             state: state::Closed {
                 io,
                 mux_ctrl,
                 mux_fut,
+                abort,
                 vm,
                 ot_send,
                 ctx,
@@ -190,6 +414,23 @@ impl Verifier<state::Setup> {
                 handshake_commitment,
                 sent_len,
                 recv_len,
+                record_timings: RecordTimings::new(
+                    sent_record_lens
+                        .iter()
+                        .copied()
+                        .zip(sent_record_times)
+                        .collect(),
+                    recv_record_lens
+                        .iter()
+                        .copied()
+                        .zip(recv_record_times)
+                        .collect(),
+                ),
+                record_order: RecordOrder::new(
+                    sent_record_lens,
+                    recv_record_lens,
+                    recv_record_order,
+                ),
             },
         })
     }
@@ -204,6 +445,9 @@ impl Verifier<state::Closed> {
         Verifier {
             config: self.config,
             state: self.state.into(),
+            abort: self.abort,
+            timings: self.timings,
+            protocol_stats: self.protocol_stats,
         }
     }
 
@@ -215,29 +459,78 @@ impl Verifier<state::Closed> {
         Verifier {
             config: self.config,
             state: self.state.into(),
+            abort: self.abort,
+            timings: self.timings,
+            protocol_stats: self.protocol_stats,
         }
     }
 }
 
 /// Performs a setup of the various MPC subprotocols.
 #[instrument(level = "debug", skip_all, err)]
+#[allow(clippy::too_many_arguments)]
 async fn setup_mpc_backend(
     config: &VerifierConfig,
+    session_params: &SessionParams,
+    ot_pool: OtPoolSize,
     mux: &MuxControl,
+    priorities: Option<&StreamPriorities>,
     exec: &mut Executor,
-    encoder_seed: [u8; 32],
+    encoder_seed: &[u8; 32],
+    timings: &TimingsHandle,
+    circuit_counter: CircuitCounter,
 ) -> Result<(MpcTlsFollower, DEAPThread, OTSender), VerifierError> {
+    // The OT extension refuses OTs beyond the preprocessed pool, which is never extended, so the
+    // quota holds for the whole session.
+    if let Some(max_ots) = config.max_ots() {
+        ot_pool
+            .check_quota(max_ots)
+            .map_err(|err| VerifierError::QuotaExceeded(err.to_string()))?;
+    }
+
     let mut ot_sender = kos::Sender::new(
         config.build_ot_sender_config(),
         chou_orlandi::Receiver::new(config.build_base_ot_receiver_config()),
     );
-    ot_sender.alloc(config.ot_sender_setup_count());
+    ot_sender.alloc(ot_pool.send);
 
     let mut ot_receiver = kos::Receiver::new(
         config.build_ot_receiver_config(),
         chou_orlandi::Sender::new(config.build_base_ot_sender_config()),
     );
-    ot_receiver.alloc(config.ot_receiver_setup_count());
+    ot_receiver.alloc(ot_pool.recv);
+
+    // Set up the OTs up front to time them, like the prover. The receiver runs on the first
+    // thread, which the prover uses for its sender
+    let (mut ctx_ot_receiver, mut ctx_ot_sender) =
+        futures::try_join!(exec.new_thread(), exec.new_thread())?;
+
+    let start = Instant::now();
+    futures::try_join!(
+        ot_sender
+            .setup(&mut ctx_ot_sender)
+            .map_err(VerifierError::from),
+        ot_receiver
+            .setup(&mut ctx_ot_receiver)
+            .map_err(VerifierError::from),
+    )?;
+    let base_ot = start.elapsed();
+
+    let start = Instant::now();
+    futures::try_join!(
+        ot_sender
+            .preprocess(&mut ctx_ot_sender)
+            .map_err(VerifierError::from),
+        ot_receiver
+            .preprocess(&mut ctx_ot_receiver)
+            .map_err(VerifierError::from),
+    )?;
+    let ot_extension = start.elapsed();
+
+    timings.update(|t| {
+        t.base_ot = Some(base_ot);
+        t.ot_extension = Some(ot_extension);
+    });
 
     let ot_sender = OTSender::new(ot_sender);
     let ot_receiver = OTReceiver::new(ot_receiver);
@@ -274,13 +567,13 @@ async fn setup_mpc_backend(
 
     let vm = DEAPThread::new(
         DEAPRole::Follower,
-        encoder_seed,
+        *encoder_seed,
         ctx_vm,
         ot_sender.clone(),
         ot_receiver.clone(),
     );
 
-    let mpc_tls_config = config.build_mpc_tls_config();
+    let mpc_tls_config = config.build_mpc_tls_config(session_params);
     let (ke, prf, encrypter, decrypter) = build_components(
         TlsRole::Follower,
         mpc_tls_config.common(),
@@ -314,9 +607,13 @@ async fn setup_mpc_backend(
         )?,
         ot_sender.clone(),
         ot_receiver.clone(),
+        circuit_counter,
     );
 
     let channel = mux.open_framed(b"mpc_tls").await?;
+    if let Some(priorities) = priorities {
+        priorities.register(b"mpc_tls", &channel);
+    }
     let mut mpc_tls = MpcTlsFollower::new(
         mpc_tls_config,
         Box::new(StreamExt::compat_stream(channel)),
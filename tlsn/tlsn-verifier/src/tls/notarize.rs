@@ -3,12 +3,18 @@
 //! The TLS verifier is only a notary.
 
 use super::{state::Notarize, Verifier, VerifierError};
-use mpz_core::serialize::CanonicalSerialize;
+use mpz_garble::{Memory, Verify};
 use mpz_ot::CommittedOTSender;
 use serio::{stream::IoStreamExt, SinkExt as _};
 use signature::Signer;
+use std::time::Instant;
+use tlsn_common::plaintext_hash::{build_plaintext_hash_circuit, plaintext_hash_refs, salt_id};
 use tlsn_core::{
-    merkle::MerkleRoot, msg::SignedSessionHeader, HandshakeSummary, SessionHeader, Signature,
+    commitment::hash::SALT_LEN,
+    merkle::MerkleRoot,
+    msg::{PlaintextHashes, SessionPhase, SignedSessionHeader},
+    session::{RecordOrder, RecordTimings, SessionBinding},
+    Direction, HandshakeSummary, SessionHeader, Signature,
 };
 
 use tracing::{debug, info, instrument};
@@ -19,7 +25,7 @@ impl Verifier<Notarize> {
     /// # Arguments
     ///
     /// * `signer` - The signer used to sign the notarization result.
-    #[instrument(level = "debug", skip_all, err)]
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn finalize<T>(self, signer: &impl Signer<T>) -> Result<SessionHeader, VerifierError>
     where
         T: Into<Signature>,
@@ -28,6 +34,7 @@ impl Verifier<Notarize> {
             mut io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mut vm,
             mut ot_send,
             mut ctx,
@@ -37,11 +44,73 @@ impl Verifier<Notarize> {
             handshake_commitment,
             sent_len,
             recv_len,
+            record_timings: observed_record_timings,
+            record_order: observed_record_order,
         } = self.state;
 
+        let start = Instant::now();
         let session_header = mux_fut
-            .poll_with(async {
+            .poll_with(abort.guard(SessionPhase::Finalize, async {
                 let merkle_root: MerkleRoot = io.expect_next().await?;
+                let binding: Option<SessionBinding> = io.expect_next().await?;
+                if let Some(binding) = &binding {
+                    binding
+                        .validate()
+                        .map_err(|e| VerifierError::InvalidSessionBinding(e.to_string()))?;
+                }
+                // The Notary signs its own observation of the records, provided the Prover's view
+                // of them is close enough to it.
+                let record_timings: Option<RecordTimings> = io.expect_next().await?;
+                let record_timings = record_timings
+                    .map(|record_timings| {
+                        record_timings
+                            .check(
+                                &observed_record_timings,
+                                self.config.max_record_time_skew().as_millis() as u64,
+                            )
+                            .map(|_| observed_record_timings)
+                            .map_err(|e| VerifierError::InvalidRecordTimings(e.to_string()))
+                    })
+                    .transpose()?;
+                let record_order: Option<RecordOrder> = io.expect_next().await?;
+                if let Some(record_order) = &record_order {
+                    record_order
+                        .check(&observed_record_order)
+                        .map_err(|e| VerifierError::InvalidRecordOrder(e.to_string()))?;
+                }
+
+                // Verify the plaintext hash commitments of the prover, which must happen before
+                // the MPC is finalized.
+                let plaintext_hashes: PlaintextHashes = io.expect_next().await?;
+                plaintext_hashes
+                    .verify(&merkle_root)
+                    .map_err(|e| VerifierError::InvalidPlaintextHashes(e.to_string()))?;
+
+                for (index, (_, info, expected_hash)) in
+                    plaintext_hashes.commitments.iter().enumerate()
+                {
+                    let max = info.ranges().max().ok_or(VerifierError::InvalidRange)?;
+                    let len = match info.direction() {
+                        Direction::Sent => sent_len,
+                        Direction::Received => recv_len,
+                    };
+                    if max > len {
+                        return Err(VerifierError::InvalidRange);
+                    }
+
+                    let salt = vm.new_blind_input::<[u8; SALT_LEN]>(&salt_id(index))?;
+                    let (data, hash) = plaintext_hash_refs(&mut vm, index, info)?;
+
+                    let circuit = build_plaintext_hash_circuit(info.ranges().len());
+                    self.protocol_stats.add_circuit(&circuit);
+
+                    vm.execute_verify(circuit, &[salt, data], &[hash.clone()])
+                        .await?;
+                    vm.verify(&[hash], &[expected_hash.as_bytes().to_vec().into()])
+                        .await?;
+                }
+
+                debug!("verified plaintext hashes");
 
                 // Finalize all MPC before signing the session header.
                 ot_send.reveal(&mut ctx).await?;
@@ -57,18 +126,64 @@ impl Verifier<Notarize> {
                 let handshake_summary =
                     HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
 
-                let session_header = SessionHeader::new(
-                    encoder_seed,
+                // The encoder seed is no longer secret once the MPC is finalized, as the Prover
+                // needs it to verify the encodings of the transcript.
+                let mut session_header = SessionHeader::new(
+                    *encoder_seed,
                     merkle_root,
                     sent_len,
                     recv_len,
                     handshake_summary,
                 );
 
+                if let Some(binding) = binding {
+                    session_header = session_header.with_binding(binding);
+                }
+
+                if let Some(record_timings) = &record_timings {
+                    session_header = session_header.with_record_timings(record_timings.digest());
+                }
+
+                if let Some(record_order) = record_order {
+                    session_header = session_header.with_record_order(record_order.digest());
+                }
+
+                if let Some(key_id) = self.config.key_id() {
+                    session_header = session_header.with_key_id(key_id);
+                }
+
+                if let Some(did) = self.config.notary_did() {
+                    session_header = session_header.with_notary_did(did);
+                }
+
+                if let Some(server_name) = self.config.server_name() {
+                    session_header = session_header.with_server_name(server_name);
+                }
+
+                // Only the plaintext hashes proven above may be opened by proofs of the session.
+                session_header = session_header.with_plaintext_hashes(plaintext_hashes.leaves());
+
+                if let Some(time_source) = self.config.time_source() {
+                    let timestamp = time_source
+                        .timestamp(session_header.timestamp_message())
+                        .await?;
+
+                    debug!(source = ?timestamp.source(), "obtained timestamp");
+
+                    session_header = session_header.with_timestamp(timestamp);
+                }
+
+                if let Some(session_log) = self.config.session_log() {
+                    session_log.record(&session_header).await?;
+
+                    debug!("recorded session header in the log");
+                }
+
                 let signature = signer.sign(&session_header.to_bytes());
 
                 info!("Signed session header");
 
+                io.send(record_timings).await?;
                 io.send(SignedSessionHeader {
                     header: session_header.clone(),
                     signature: signature.into(),
@@ -78,8 +193,11 @@ impl Verifier<Notarize> {
                 info!("Sent session header");
 
                 Ok::<_, VerifierError>(session_header)
-            })
-            .await?;
+            }))
+            .await??;
+
+        let duration = start.elapsed();
+        self.timings.update(|t| t.finalization = Some(duration));
 
         if !mux_fut.is_complete() {
             mux_ctrl.mux().close();
@@ -4,15 +4,25 @@ use mpz_core::hash::Hash;
 use tls_core::key::PublicKey;
 use tls_mpc::MpcTlsFollower;
 use tlsn_common::{
+    abort::{AbortChannel, AbortReceiptSigner},
     mux::{MuxControl, MuxFuture},
     Context, DEAPThread, Io, OTSender,
 };
+use tlsn_core::{
+    msg::SignedSessionOpenedReceipt,
+    session::{RecordOrder, RecordTimings},
+};
+use zeroize::Zeroizing;
 
 /// TLS Verifier state.
 pub trait VerifierState: sealed::Sealed {}
 
 /// Initialized state.
-pub struct Initialized;
+#[derive(Default)]
+pub struct Initialized {
+    pub(crate) receipt_signer: Option<AbortReceiptSigner>,
+    pub(crate) opened_receipt_signer: Option<AbortReceiptSigner>,
+}
 
 opaque_debug::implement!(Initialized);
 
@@ -21,13 +31,17 @@ pub struct Setup {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) mpc_tls: MpcTlsFollower,
     pub(crate) vm: DEAPThread,
     pub(crate) ot_send: OTSender,
     pub(crate) ctx: Context,
 
-    pub(crate) encoder_seed: [u8; 32],
+    pub(crate) encoder_seed: Zeroizing<[u8; 32]>,
+
+    /// The receipt issued when the session was opened, if any.
+    pub(crate) opened_receipt: Option<SignedSessionOpenedReceipt>,
 }
 
 /// State after the TLS connection has been closed.
@@ -35,17 +49,20 @@ pub struct Closed {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_send: OTSender,
     pub(crate) ctx: Context,
 
-    pub(crate) encoder_seed: [u8; 32],
+    pub(crate) encoder_seed: Zeroizing<[u8; 32]>,
     pub(crate) start_time: u64,
     pub(crate) server_ephemeral_key: PublicKey,
     pub(crate) handshake_commitment: Hash,
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
+    pub(crate) record_timings: RecordTimings,
+    pub(crate) record_order: RecordOrder,
 }
 
 opaque_debug::implement!(Closed);
@@ -55,17 +72,20 @@ pub struct Notarize {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_send: OTSender,
     pub(crate) ctx: Context,
 
-    pub(crate) encoder_seed: [u8; 32],
+    pub(crate) encoder_seed: Zeroizing<[u8; 32]>,
     pub(crate) start_time: u64,
     pub(crate) server_ephemeral_key: PublicKey,
     pub(crate) handshake_commitment: Hash,
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
+    pub(crate) record_timings: RecordTimings,
+    pub(crate) record_order: RecordOrder,
 }
 
 opaque_debug::implement!(Notarize);
@@ -76,6 +96,7 @@ impl From<Closed> for Notarize {
             io: value.io,
             mux_ctrl: value.mux_ctrl,
             mux_fut: value.mux_fut,
+            abort: value.abort,
             vm: value.vm,
             ot_send: value.ot_send,
             ctx: value.ctx,
@@ -85,6 +106,8 @@ impl From<Closed> for Notarize {
             handshake_commitment: value.handshake_commitment,
             sent_len: value.sent_len,
             recv_len: value.recv_len,
+            record_timings: value.record_timings,
+            record_order: value.record_order,
         }
     }
 }
@@ -94,6 +117,7 @@ pub struct Verify {
     pub(crate) io: Io,
     pub(crate) mux_ctrl: MuxControl,
     pub(crate) mux_fut: MuxFuture,
+    pub(crate) abort: AbortChannel,
 
     pub(crate) vm: DEAPThread,
     pub(crate) ot_send: OTSender,
@@ -114,6 +138,7 @@ impl From<Closed> for Verify {
             io: value.io,
             mux_ctrl: value.mux_ctrl,
             mux_fut: value.mux_fut,
+            abort: value.abort,
             vm: value.vm,
             ot_send: value.ot_send,
             ctx: value.ctx,
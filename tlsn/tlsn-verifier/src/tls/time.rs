@@ -0,0 +1,52 @@
+//! Sources of the time at which the Notary notarizes a session.
+
+use std::{
+    error::Error,
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::{self, BoxFuture};
+
+pub use mpz_core::hash::Hash;
+pub use tlsn_core::session::{Timestamp, TimestampSource};
+
+/// An error returned by a [`TimeSource`].
+#[derive(Debug, thiserror::Error)]
+#[error("time source error: {0}")]
+pub struct TimeSourceError(Box<dyn Error + Send + Sync + 'static>);
+
+impl TimeSourceError {
+    /// Creates a new time source error.
+    pub fn new(err: impl Into<Box<dyn Error + Send + Sync + 'static>>) -> Self {
+        Self(err.into())
+    }
+}
+
+/// A source of the time at which the Notary notarizes a session.
+///
+/// The obtained [`Timestamp`] is embedded in the signed [`SessionHeader`](tlsn_core::SessionHeader).
+pub trait TimeSource: Debug + Send + Sync {
+    /// Obtains a timestamp of a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The SHA-256 hash of the session header without its timestamp, which must be
+    ///   the message imprint of a time-stamp token.
+    fn timestamp(&self, message: Hash) -> BoxFuture<'_, Result<Timestamp, TimeSourceError>>;
+}
+
+/// A [`TimeSource`] using the system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn timestamp(&self, _message: Hash) -> BoxFuture<'_, Result<Timestamp, TimeSourceError>> {
+        Box::pin(future::ready(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| Timestamp::new(TimestampSource::SystemClock, time.as_secs(), None))
+                .map_err(TimeSourceError::new),
+        ))
+    }
+}
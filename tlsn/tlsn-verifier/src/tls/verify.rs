@@ -7,12 +7,15 @@ use mpz_circuits::types::Value;
 use mpz_garble::{Memory, Verify};
 use mpz_ot::CommittedOTSender;
 use serio::stream::IoStreamExt;
+use std::time::Instant;
 use tlsn_core::{
-    msg::ProvingInfo, proof::SessionInfo, transcript::get_value_ids, Direction, HandshakeSummary,
-    RedactedTranscript, TranscriptSlice,
+    msg::{ProvingInfo, SessionPhase},
+    proof::SessionInfo,
+    transcript::get_value_ids,
+    Direction, HandshakeSummary, RedactedTranscript, TranscriptSlice,
 };
 
-use tracing::info;
+use tracing::{info, instrument};
 
 impl Verifier<VerifyState> {
     /// Receives the **purported** transcript from the Prover.
@@ -20,12 +23,13 @@ impl Verifier<VerifyState> {
     /// # Warning
     ///
     /// The content of the received transcripts can not be considered authentic until after finalization.
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn receive(
         &mut self,
     ) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
         self.state
             .mux_fut
-            .poll_with(async {
+            .poll_with(self.state.abort.guard(SessionPhase::Prove, async {
                 // Receive the proving info from the prover
                 let mut proving_info: ProvingInfo = self.state.io.expect_next().await?;
                 let mut cleartext = proving_info.cleartext.clone();
@@ -105,16 +109,18 @@ impl Verifier<VerifyState> {
                 info!("Successfully created redacted transcripts");
 
                 Ok::<_, VerifierError>((sent_redacted, recv_redacted))
-            })
-            .await
+            }))
+            .await?
     }
 
     /// Verifies the TLS session.
+    #[instrument(level = "debug", skip_all, fields(session_id = %self.config.id()), err)]
     pub async fn finalize(self) -> Result<SessionInfo, VerifierError> {
         let VerifyState {
             mut io,
             mux_ctrl,
             mut mux_fut,
+            mut abort,
             mut vm,
             mut ot_send,
             mut ctx,
@@ -124,8 +130,9 @@ impl Verifier<VerifyState> {
             ..
         } = self.state;
 
+        let start = Instant::now();
         let session_info = mux_fut
-            .poll_with(async {
+            .poll_with(abort.guard(SessionPhase::Finalize, async {
                 // Finalize all MPC
                 ot_send.reveal(&mut ctx).await?;
 
@@ -138,8 +145,8 @@ impl Verifier<VerifyState> {
                 info!("Finalized all MPC");
 
                 Ok::<_, VerifierError>(session_info)
-            })
-            .await?;
+            }))
+            .await??;
 
         let handshake_summary =
             HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
@@ -149,6 +156,9 @@ impl Verifier<VerifyState> {
 
         info!("Successfully verified session");
 
+        let duration = start.elapsed();
+        self.timings.update(|t| t.finalization = Some(duration));
+
         if !mux_fut.is_complete() {
             mux_ctrl.mux().close();
             mux_fut.await?;
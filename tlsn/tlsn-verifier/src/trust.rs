@@ -0,0 +1,579 @@
+//! Trust store of notary keys.
+//!
+//! A [`TrustStore`] holds the keys of the notaries a verifier trusts, each with a validity window
+//! and free-form metadata. Proofs are verified against the store, which selects the key which
+//! signed the proof, see [`TrustStore::verify`], so applications do not need to pass a single
+//! notary key around, and can rotate keys by editing the store.
+//!
+//! The store is the single list of trusted notaries of a verifier: its keys are the keys accepted
+//! by a [`VerifierPolicy`] evaluated with [`TrustStore::evaluate`], including for verifying the
+//! signature of its revocation list, and its DIDs are the trusted DIDs to resolve with a
+//! [`DidResolver`](tlsn_core::did::DidResolver), see [`TrustStore::trusted_dids`].
+//!
+//! The store is saved as JSON:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "notaries": [
+//!     {
+//!       "id": "notary-2024",
+//!       "key": { "P256": "3059301306072A8648CE3D020106082A8648CE3D03010703420004..." },
+//!       "key_id": "2024-01",
+//!       "did": "did:web:notary.example.com",
+//!       "not_before": 1704067200,
+//!       "not_after": 1735689599,
+//!       "metadata": { "operator": "example.com" }
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tls_core::verify::ServerCertVerifier;
+use tlsn_core::{
+    proof::{PolicyError, SessionProofError, SubstringsProofError, TlsProof, VerifierPolicy},
+    NotaryPublicKey, SessionHeader, Signature,
+};
+
+pub use tlsn_core::proof::VerifiedProof;
+
+/// Version of the file format of the trust store.
+pub const TRUST_STORE_VERSION: u32 = 1;
+
+/// An error for [`TrustStore`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum TrustStoreError {
+    /// The store could not be read or written.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The store could not be encoded or decoded.
+    #[error("invalid trust store: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The version of the file format is not supported.
+    #[error("unsupported trust store version: {0}")]
+    UnsupportedVersion(u32),
+    /// A notary with the same id is already in the store.
+    #[error("notary {0:?} is already in the trust store")]
+    DuplicateId(String),
+    /// The proof is not signed by a notary of the store.
+    #[error("the proof is not signed by a trusted notary")]
+    UntrustedNotary,
+    /// The proof is signed by a notary of the store, outside of the validity window of its key.
+    #[error("the key of notary {id:?} is not valid at {time}")]
+    OutsideValidity {
+        /// The id of the notary.
+        id: String,
+        /// The time of the session, in seconds since the UNIX epoch.
+        time: u64,
+    },
+    /// The session proof is invalid.
+    #[error(transparent)]
+    SessionProof(#[from] SessionProofError),
+    /// The substrings proof is invalid.
+    #[error(transparent)]
+    SubstringsProof(#[from] SubstringsProofError),
+    /// The proof violates the policy.
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+}
+
+/// A notary trusted by a [`TrustStore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedNotary {
+    /// The id of the notary in the store.
+    pub id: String,
+    /// The public key of the notary.
+    pub key: NotaryPublicKey,
+    /// The id of the key, which the notary embeds in the session headers it signs, see
+    /// [`SessionHeader::key_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// The DID of the notary, which it references in the session headers it signs, see
+    /// [`SessionHeader::notary_did`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub did: Option<String>,
+    /// The start of the validity window of the key, in seconds since the UNIX epoch, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<u64>,
+    /// The end of the validity window of the key, in seconds since the UNIX epoch, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<u64>,
+    /// Free-form metadata, e.g. the operator of the notary.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl TrustedNotary {
+    /// Creates a new trusted notary whose key is always valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the notary.
+    /// * `key` - The public key of the notary.
+    pub fn new(id: impl Into<String>, key: impl Into<NotaryPublicKey>) -> Self {
+        Self {
+            id: id.into(),
+            key: key.into(),
+            key_id: None,
+            did: None,
+            not_before: None,
+            not_after: None,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the id of the key.
+    pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Sets the DID of the notary, without a fragment.
+    pub fn with_did(mut self, did: impl Into<String>) -> Self {
+        self.did = Some(did.into());
+        self
+    }
+
+    /// Sets the validity window of the key, in seconds since the UNIX epoch, inclusive.
+    pub fn with_validity(mut self, not_before: Option<u64>, not_after: Option<u64>) -> Self {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    /// Adds an entry to the metadata.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns `true` if the key id and the DID referenced by the session header, if any, are the
+    /// ones of the notary.
+    ///
+    /// A header which does not reference a key id or a DID, or a notary without one, matches on
+    /// the signature alone.
+    pub fn matches_header(&self, header: &SessionHeader) -> bool {
+        let key_id_matches = match (&self.key_id, header.key_id()) {
+            (Some(key_id), Some(header_key_id)) => key_id == header_key_id,
+            _ => true,
+        };
+        // The header may reference one of the keys of the DID document by a fragment.
+        let did_matches = match (&self.did, header.notary_did()) {
+            (Some(did), Some(did_url)) => did_url.split('#').next() == Some(did.as_str()),
+            _ => true,
+        };
+
+        key_id_matches && did_matches
+    }
+
+    /// Returns `true` if the key is valid at the provided time, in seconds since the UNIX epoch.
+    pub fn is_valid_at(&self, time: u64) -> bool {
+        self.not_before
+            .map_or(true, |not_before| time >= not_before)
+            && self.not_after.map_or(true, |not_after| time <= not_after)
+    }
+}
+
+/// A store of the notaries trusted by a verifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustStore {
+    version: u32,
+    notaries: Vec<TrustedNotary>,
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self {
+            version: TRUST_STORE_VERSION,
+            notaries: Vec::new(),
+        }
+    }
+}
+
+impl TrustStore {
+    /// Creates a new, empty trust store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a notary to the store.
+    pub fn add(&mut self, notary: TrustedNotary) -> Result<(), TrustStoreError> {
+        if self.get(&notary.id).is_some() {
+            return Err(TrustStoreError::DuplicateId(notary.id));
+        }
+
+        self.notaries.push(notary);
+
+        Ok(())
+    }
+
+    /// Removes the notary with the provided id, returning it if it was in the store.
+    pub fn remove(&mut self, id: &str) -> Option<TrustedNotary> {
+        let index = self.notaries.iter().position(|notary| notary.id == id)?;
+
+        Some(self.notaries.remove(index))
+    }
+
+    /// Returns the notary with the provided id.
+    pub fn get(&self, id: &str) -> Option<&TrustedNotary> {
+        self.notaries.iter().find(|notary| notary.id == id)
+    }
+
+    /// Returns an iterator over the notaries of the store, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &TrustedNotary> {
+        self.notaries.iter()
+    }
+
+    /// Returns the number of notaries in the store.
+    pub fn len(&self) -> usize {
+        self.notaries.len()
+    }
+
+    /// Returns `true` if the store has no notaries.
+    pub fn is_empty(&self) -> bool {
+        self.notaries.is_empty()
+    }
+
+    /// Returns the DIDs of the notaries of the store, to resolve the keys of notaries referenced
+    /// by DID with [`DidResolver::resolve_header`](tlsn_core::did::DidResolver::resolve_header).
+    pub fn trusted_dids(&self) -> Vec<&str> {
+        self.notaries
+            .iter()
+            .filter_map(|notary| notary.did.as_deref())
+            .collect()
+    }
+
+    /// Decodes a store from JSON.
+    pub fn from_json(json: &str) -> Result<Self, TrustStoreError> {
+        let store: Self = serde_json::from_str(json)?;
+        if store.version != TRUST_STORE_VERSION {
+            return Err(TrustStoreError::UnsupportedVersion(store.version));
+        }
+
+        let mut ids = store
+            .notaries
+            .iter()
+            .map(|notary| &notary.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        if let Some(id) = ids.windows(2).find(|ids| ids[0] == ids[1]) {
+            return Err(TrustStoreError::DuplicateId(id[0].clone()));
+        }
+
+        Ok(store)
+    }
+
+    /// Encodes the store as JSON.
+    pub fn to_json(&self) -> Result<String, TrustStoreError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Loads a store from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TrustStoreError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Saves the store to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), TrustStoreError> {
+        std::fs::write(path, self.to_json()?)?;
+
+        Ok(())
+    }
+
+    /// Returns the notary which signed the session header.
+    ///
+    /// Only the notaries matching the key id and the DID referenced by the header are considered,
+    /// see [`TrustedNotary::matches_header`]. The key must be valid at the time of the session, so
+    /// an entry of a rotated key is skipped in favour of another entry of the same key which is
+    /// valid at that time.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `signature` - The signature of the header.
+    pub fn find_signer(
+        &self,
+        header: &SessionHeader,
+        signature: &Signature,
+    ) -> Result<&TrustedNotary, TrustStoreError> {
+        let msg = header.to_bytes();
+        let mut expired = None;
+        for notary in self
+            .notaries
+            .iter()
+            .filter(|notary| notary.matches_header(header))
+            .filter(|notary| signature.verify(&msg, notary.key.clone()).is_ok())
+        {
+            if notary.is_valid_at(header.time()) {
+                return Ok(notary);
+            }
+            expired.get_or_insert(notary);
+        }
+
+        Err(match expired {
+            Some(notary) => TrustStoreError::OutsideValidity {
+                id: notary.id.clone(),
+                time: header.time(),
+            },
+            None => TrustStoreError::UntrustedNotary,
+        })
+    }
+
+    /// Verifies a proof against the notaries of the store.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof to verify.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    pub fn verify(
+        &self,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<VerifiedProof, TrustStoreError> {
+        let notary = self.find_signer_of(&proof)?;
+        proof.session.verify(notary.key.clone(), cert_verifier)?;
+
+        let TlsProof {
+            session,
+            substrings,
+        } = proof;
+        let (sent, recv) = substrings.verify(&session.header)?;
+
+        Ok(VerifiedProof {
+            notary: Some(notary.id.clone()),
+            server_name: session.session_info.server_name.as_ref().to_string(),
+            time: session.header.time(),
+            sent,
+            recv,
+        })
+    }
+
+    /// Verifies a proof against the notaries of the store, and evaluates the provided policy
+    /// against it, see [`VerifierPolicy::evaluate`].
+    ///
+    /// The keys of the notaries of the store are added to the notary keys accepted by the policy,
+    /// so its revocation list may be signed by any of them, e.g. after a key rotation. The proof
+    /// itself must still be signed by a notary whose key is valid at the time of the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy.
+    /// * `proof` - The proof to evaluate.
+    /// * `cert_verifier` - The verifier of the server certificate.
+    pub fn evaluate(
+        &self,
+        policy: &VerifierPolicy,
+        proof: TlsProof,
+        cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<VerifiedProof, TrustStoreError> {
+        let notary = self.find_signer_of(&proof)?;
+        let server_name = proof.session.session_info.server_name.as_ref().to_string();
+        let time = proof.session.header.time();
+
+        // The signer comes first, so the policy checks the age of its key even if another notary
+        // of the store has the same key. The start of the validity window stands in for the
+        // creation time of the key.
+        let (sent, recv) = std::iter::once(notary)
+            .chain(self.notaries.iter().filter(|other| other.id != notary.id))
+            .fold(policy.clone(), |policy, notary| {
+                policy.accept_notary_key(notary.key.clone(), notary.not_before)
+            })
+            .evaluate(proof, cert_verifier)?;
+
+        Ok(VerifiedProof {
+            notary: Some(notary.id.clone()),
+            server_name,
+            time,
+            sent,
+            recv,
+        })
+    }
+
+    fn find_signer_of(&self, proof: &TlsProof) -> Result<&TrustedNotary, TrustStoreError> {
+        let signature = proof
+            .session
+            .signature
+            .as_ref()
+            .ok_or(SessionProofError::MissingNotarySignature)?;
+
+        self.find_signer(&proof.session.header, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use tlsn_core::{
+        did::did_key,
+        dry_run::{dry_run_notary_key, dry_run_notary_public_key, DryRun},
+        proof::default_cert_verifier,
+    };
+
+    fn proof() -> TlsProof {
+        let recv = b"HTTP/1.1 200 OK\r\n\r\nhello";
+        let mut dry_run = DryRun::new(b"GET / HTTP/1.1\r\n\r\n".to_vec(), recv.to_vec());
+        let id = dry_run
+            .commitment_builder()
+            .commit_recv(&(0..recv.len()))
+            .unwrap();
+
+        let session = dry_run.finalize().unwrap();
+        let mut builder = session.data().build_substrings_proof();
+        builder.reveal_by_id(id).unwrap();
+
+        TlsProof {
+            session: session.session_proof(),
+            substrings: builder.build().unwrap(),
+        }
+    }
+
+    fn store() -> TrustStore {
+        let other = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+        let mut store = TrustStore::new();
+        store
+            .add(TrustedNotary::new(
+                "other",
+                p256::PublicKey::from(*other.verifying_key()),
+            ))
+            .unwrap();
+        store
+            .add(
+                TrustedNotary::new("dry-run", dry_run_notary_public_key())
+                    .with_metadata("operator", "tlsnotary.org"),
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_verify() {
+        let verified = store().verify(proof(), &default_cert_verifier()).unwrap();
+
+        assert_eq!(verified.notary.as_deref(), Some("dry-run"));
+        assert_eq!(verified.server_name, "tlsnotary.org");
+        assert!(verified.recv.data().ends_with(b"hello"));
+    }
+
+    #[test]
+    fn test_rejects_untrusted_or_expired_notary() {
+        let mut store = store();
+        let proof = proof();
+        let time = proof.session.header.time();
+
+        let notary = store.remove("dry-run").unwrap();
+        assert!(matches!(
+            store.verify(proof, &default_cert_verifier()),
+            Err(TrustStoreError::UntrustedNotary)
+        ));
+
+        store
+            .add(notary.with_validity(None, Some(time - 1)))
+            .unwrap();
+        assert!(matches!(
+            store.verify(self::proof(), &default_cert_verifier()),
+            Err(TrustStoreError::OutsideValidity { .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_signer_skips_expired_entry() {
+        let mut store = store();
+        let proof = proof();
+        let time = proof.session.header.time();
+        let signature = proof.session.signature.as_ref().unwrap();
+
+        let notary = store.remove("dry-run").unwrap();
+        store
+            .add(
+                TrustedNotary::new("dry-run-expired", notary.key.clone())
+                    .with_validity(None, Some(time - 1)),
+            )
+            .unwrap();
+        store.add(notary.with_validity(Some(time), None)).unwrap();
+
+        assert_eq!(
+            store
+                .find_signer(&proof.session.header, signature)
+                .unwrap()
+                .id,
+            "dry-run"
+        );
+    }
+
+    #[test]
+    fn test_find_signer_by_key_id_and_did() {
+        let did = did_key(&dry_run_notary_public_key());
+        let header = proof()
+            .session
+            .header
+            .with_key_id("2024-01")
+            .with_notary_did(format!("{did}#key-1"));
+        let signature: p256::ecdsa::Signature = dry_run_notary_key().sign(&header.to_bytes());
+        let signature = Signature::from(signature);
+
+        // The id of the notary in the store is not its key id.
+        let mut store = store();
+        assert_eq!(
+            store.find_signer(&header, &signature).unwrap().id,
+            "dry-run"
+        );
+
+        let notary = store.remove("dry-run").unwrap();
+        store
+            .add(notary.clone().with_key_id("2023-01").with_did(did.clone()))
+            .unwrap();
+        assert!(matches!(
+            store.find_signer(&header, &signature),
+            Err(TrustStoreError::UntrustedNotary)
+        ));
+
+        store.remove("dry-run").unwrap();
+        store
+            .add(notary.with_key_id("2024-01").with_did(did.clone()))
+            .unwrap();
+        assert_eq!(
+            store.find_signer(&header, &signature).unwrap().id,
+            "dry-run"
+        );
+        assert_eq!(store.trusted_dids(), vec![did.as_str()]);
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let policy = VerifierPolicy::new().accept_server_name("example.com");
+
+        assert!(matches!(
+            store().evaluate(&policy, proof(), &default_cert_verifier()),
+            Err(TrustStoreError::Policy(PolicyError::Violations(_)))
+        ));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let store = store();
+        let path = std::env::temp_dir().join(format!("trust-store-{}.json", std::process::id()));
+
+        store.save(&path).unwrap();
+        let loaded = TrustStore::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.get("dry-run").unwrap().metadata["operator"],
+            "tlsnotary.org"
+        );
+
+        let mut duplicated = store;
+        duplicated.notaries.push(duplicated.notaries[0].clone());
+        assert!(matches!(
+            TrustStore::from_json(&duplicated.to_json().unwrap()),
+            Err(TrustStoreError::DuplicateId(_))
+        ));
+    }
+}
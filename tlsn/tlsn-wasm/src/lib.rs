@@ -0,0 +1,269 @@
+//! WebAssembly bindings to verify TLSNotary proofs in the browser, generated with
+//! [wasm-bindgen](https://rustwasm.github.io/wasm-bindgen/).
+//!
+//! The bindings expose a single function, `verify`, so websites can check proofs client-side
+//! without trusting a server to do it for them. Only the verification of proofs is included, so
+//! the bindings do not depend on an async runtime, sockets or the filesystem.
+
+#![deny(unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use p256::pkcs8::DecodePublicKey;
+use tlsn_core::proof::{SessionProof, TlsProof};
+use wasm_bindgen::prelude::*;
+
+/// The maximum length of a serialized proof, in bytes.
+///
+/// The proof is parsed from JSON, so the allocations made while deserializing it are bounded by
+/// the length of the input.
+pub const MAX_PROOF_LEN: usize = 32 * 1024 * 1024;
+
+/// A session verified from a proof.
+///
+/// The bytes which the prover chose not to disclose are set to `X` in `sent` and `recv`.
+#[wasm_bindgen]
+pub struct VerifiedSession {
+    server_name: String,
+    time: u64,
+    sent: Vec<u8>,
+    recv: Vec<u8>,
+    sent_authed: Vec<u32>,
+    recv_authed: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl VerifiedSession {
+    /// Name of the server.
+    #[wasm_bindgen(getter, js_name = serverName)]
+    pub fn server_name(&self) -> String {
+        self.server_name.clone()
+    }
+
+    /// Time of the session, in seconds since the UNIX epoch.
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Data sent to the server.
+    #[wasm_bindgen(getter)]
+    pub fn sent(&self) -> Vec<u8> {
+        self.sent.clone()
+    }
+
+    /// Data received from the server.
+    #[wasm_bindgen(getter)]
+    pub fn recv(&self) -> Vec<u8> {
+        self.recv.clone()
+    }
+
+    /// Data sent to the server, decoded as UTF-8 with invalid sequences replaced.
+    #[wasm_bindgen(js_name = sentText)]
+    pub fn sent_text(&self) -> String {
+        String::from_utf8_lossy(&self.sent).into_owned()
+    }
+
+    /// Data received from the server, decoded as UTF-8 with invalid sequences replaced.
+    #[wasm_bindgen(js_name = recvText)]
+    pub fn recv_text(&self) -> String {
+        String::from_utf8_lossy(&self.recv).into_owned()
+    }
+
+    /// Ranges of `sent` which were disclosed, as consecutive pairs of start (inclusive) and end
+    /// (exclusive) offsets.
+    #[wasm_bindgen(getter, js_name = sentAuthed)]
+    pub fn sent_authed(&self) -> Vec<u32> {
+        self.sent_authed.clone()
+    }
+
+    /// Ranges of `recv` which were disclosed, as consecutive pairs of start (inclusive) and end
+    /// (exclusive) offsets.
+    #[wasm_bindgen(getter, js_name = recvAuthed)]
+    pub fn recv_authed(&self) -> Vec<u32> {
+        self.recv_authed.clone()
+    }
+}
+
+/// Verifies a proof serialized as JSON against the PEM encoded public key of the notary.
+///
+/// The identity of the server is verified with the root certificates from the `webpki-roots`
+/// crate.
+#[wasm_bindgen]
+pub fn verify(proof: &[u8], notary_pubkey: &str) -> Result<VerifiedSession, JsError> {
+    verify_proof(proof, notary_pubkey).map_err(|err| JsError::new(&err))
+}
+
+fn verify_proof(proof: &[u8], notary_pubkey: &str) -> Result<VerifiedSession, String> {
+    if proof.len() > MAX_PROOF_LEN {
+        return Err(format!(
+            "proof is too large: {} > {MAX_PROOF_LEN} bytes",
+            proof.len()
+        ));
+    }
+
+    let proof: TlsProof =
+        serde_json::from_slice(proof).map_err(|err| format!("invalid proof: {err}"))?;
+    let notary_pubkey = p256::PublicKey::from_public_key_pem(notary_pubkey)
+        .map_err(|err| format!("invalid notary public key: {err}"))?;
+
+    let TlsProof {
+        session,
+        substrings,
+    } = proof;
+
+    session
+        .verify_with_default_cert_verifier(notary_pubkey)
+        .map_err(|err| err.to_string())?;
+
+    let SessionProof {
+        header,
+        session_info,
+        ..
+    } = session;
+
+    let (mut sent, mut recv) = substrings.verify(&header).map_err(|err| err.to_string())?;
+
+    sent.set_redacted(b'X');
+    recv.set_redacted(b'X');
+
+    // Transcripts are far smaller than 4GB, so the offsets fit into JS numbers as `u32`.
+    let ranges = |ranges: &tlsn_core::range::RangeSet<usize>| {
+        ranges
+            .iter_ranges()
+            .flat_map(|range| [range.start as u32, range.end as u32])
+            .collect()
+    };
+
+    Ok(VerifiedSession {
+        server_name: session_info.server_name.as_str().to_string(),
+        time: header.time(),
+        sent_authed: ranges(sent.authed()),
+        recv_authed: ranges(recv.authed()),
+        sent: sent.data().to_vec(),
+        recv: recv.data().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mpz_core::commit::HashCommit;
+    use p256::{
+        ecdsa::{signature::Signer, Signature},
+        pkcs8::{EncodePublicKey, LineEnding},
+    };
+    use tlsn_core::{
+        commitment::TranscriptCommitmentBuilder, fixtures, HandshakeSummary, NotarizedSession,
+        ServerName, SessionData, SessionHeader, Transcript,
+    };
+
+    const SENT: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+    const RECV: &[u8] = b"HTTP/1.1 200 OK\r\n\r\nsecret";
+
+    /// Returns a proof revealing all of the sent data and the received data but the secret,
+    /// serialized as JSON, and the PEM encoded public key of the notary.
+    fn proof() -> (Vec<u8>, String) {
+        let testdata = fixtures::cert::tlsnotary();
+        let (handshake_decommitment, handshake_commitment) =
+            fixtures::handshake_data().hash_commit();
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(SENT, RECV),
+            SENT.len(),
+            RECV.len(),
+        );
+        let sent_id = builder.commit_sent(&(0..SENT.len())).unwrap();
+        let recv_id = builder.commit_recv(&(0..RECV.len() - 6)).unwrap();
+
+        let data = SessionData::new(
+            ServerName::Dns(testdata.dns_name),
+            handshake_decommitment,
+            Transcript::new(SENT.to_vec()),
+            Transcript::new(RECV.to_vec()),
+            builder.build().unwrap(),
+        );
+
+        let header = SessionHeader::new(
+            fixtures::encoder_seed(),
+            data.commitments().merkle_root(),
+            SENT.len(),
+            RECV.len(),
+            HandshakeSummary::new(
+                testdata.time,
+                fixtures::server_ephemeral_key(),
+                handshake_commitment,
+            ),
+        );
+
+        let signing_key = fixtures::notary_signing_key();
+        let signature: Signature = signing_key.sign(&header.to_bytes());
+        let session = NotarizedSession::new(header, Some(signature.into()), data);
+
+        let mut substrings = session.data().build_substrings_proof();
+        substrings
+            .reveal_by_id(sent_id)
+            .unwrap()
+            .reveal_by_id(recv_id)
+            .unwrap();
+
+        let proof = TlsProof {
+            session: session.session_proof(),
+            substrings: substrings.build().unwrap(),
+        };
+
+        let notary_pubkey = signing_key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+
+        (serde_json::to_vec(&proof).unwrap(), notary_pubkey)
+    }
+
+    #[test]
+    fn test_verify() {
+        let (proof, notary_pubkey) = proof();
+
+        let session = verify_proof(&proof, &notary_pubkey).unwrap();
+
+        assert_eq!(session.server_name(), "tlsnotary.org");
+        assert_eq!(session.time(), fixtures::cert::tlsnotary().time);
+        assert_eq!(session.sent(), SENT);
+        assert_eq!(session.recv_text(), "HTTP/1.1 200 OK\r\n\r\nXXXXXX");
+        assert_eq!(session.sent_authed(), [0, SENT.len() as u32]);
+        assert_eq!(session.recv_authed(), [0, RECV.len() as u32 - 6]);
+    }
+
+    #[test]
+    fn test_verify_wrong_notary() {
+        let (proof, _) = proof();
+        let other_pubkey = p256::ecdsa::SigningKey::from_slice(&[2; 32])
+            .unwrap()
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+
+        assert!(verify_proof(&proof, &other_pubkey).is_err());
+        assert!(verify_proof(&proof, "not a key")
+            .err()
+            .unwrap()
+            .starts_with("invalid notary public key"));
+    }
+
+    #[test]
+    fn test_verify_rejects_malformed_proof() {
+        let (proof, notary_pubkey) = proof();
+
+        assert!(verify_proof(&proof[..proof.len() - 1], &notary_pubkey)
+            .err()
+            .unwrap()
+            .starts_with("invalid proof"));
+
+        let oversized = vec![b' '; MAX_PROOF_LEN + 1];
+        assert!(verify_proof(&oversized, &notary_pubkey)
+            .err()
+            .unwrap()
+            .starts_with("proof is too large"));
+    }
+}